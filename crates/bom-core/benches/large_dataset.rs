@@ -0,0 +1,201 @@
+//! Timings of the repository and query layer on a synthetic holdings dataset.
+//!
+//! ```bash
+//! cargo bench -p bom-core                          # 100 000 rows
+//! BOM_BENCH_ROWS=20000 cargo bench -p bom-core     # quicker run
+//! ```
+//!
+//! Each step prints the median and fastest of a few runs. Compare against the numbers of
+//! the previous release before tagging; a step that got noticeably slower is a regression
+//! in `infra::sqlite` or the summary code.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::hint::black_box;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bom_core::domain::entities::dataset::DatasetId;
+use bom_core::domain::entities::edit::{CellKey, StagedEdits};
+use bom_core::infra::import::csv::import_csv_to_sqlite;
+use bom_core::infra::sqlite::repo::SqliteRepo;
+use bom_core::report::dataset_summary_report;
+use bom_core::usecase::services::edit_service::EditService;
+use bom_core::usecase::services::query_service::QueryService;
+use bom_core::{build_page_query, QueryOptions};
+
+const DEFAULT_ROWS: usize = 100_000;
+const RUNS: usize = 5;
+const EDITED_CELLS: usize = 1_000;
+
+const HEADERS: [&str; 9] = [
+    "所有權人",
+    "名稱",
+    "類別",
+    "代號",
+    "數量",
+    "市價",
+    "總成本",
+    "淨值",
+    "估計配息",
+];
+const OWNERS: [&str; 4] = ["甲", "乙", "丙", "丁"];
+const CATEGORIES: [&str; 3] = ["股票", "債券", "基金"];
+
+fn main() {
+    let row_count = std::env::var("BOM_BENCH_ROWS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_ROWS);
+    let dir = std::env::temp_dir().join(format!("bom-bench-{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("create bench directory");
+    let csv_path = dir.join("holdings.csv");
+    write_synthetic_csv(&csv_path, row_count);
+    println!("{row_count} rows, {RUNS} runs per step");
+
+    let mut dataset_id = 0;
+    let mut run = 0;
+    measure("import csv", || {
+        // A fresh database per run, so later runs do not pay for earlier datasets.
+        run += 1;
+        let db_path = dir.join(format!("import-{run}.sqlite"));
+        dataset_id = import_csv_to_sqlite(&db_path, &csv_path)
+            .expect("import csv")
+            .dataset_id;
+    });
+    let db_path = dir.join(format!("import-{run}.sqlite"));
+    let repo = Arc::new(SqliteRepo { db_path });
+    let query = QueryService::new(repo.clone());
+    let edit = EditService::new(repo);
+
+    let page = |options: QueryOptions| {
+        query
+            .query_page(build_page_query(dataset_id, 0, &options))
+            .expect("query page")
+    };
+    measure("query_page", || page(QueryOptions::default()));
+    measure("query_page global search", || {
+        page(QueryOptions {
+            global_search: "0050".to_string(),
+            ..QueryOptions::default()
+        })
+    });
+    measure("query_page column filter", || {
+        page(QueryOptions {
+            column_search_col: Some(2),
+            column_search_text: "債券".to_string(),
+            ..QueryOptions::default()
+        })
+    });
+    measure("query_page value filter", || {
+        page(QueryOptions {
+            value_filters: [(0, BTreeSet::from(["甲".to_string(), "丙".to_string()]))].into(),
+            ..QueryOptions::default()
+        })
+    });
+    measure("query_page numeric sort", || {
+        page(QueryOptions {
+            sort_col: Some(7),
+            sort_desc: true,
+            ..QueryOptions::default()
+        })
+    });
+
+    let mut round = 0;
+    measure("apply_edits", || {
+        round += 1;
+        edit.apply_edits(DatasetId(dataset_id), synthetic_edits(row_count, round))
+            .expect("apply edits")
+    });
+    measure("summary report", || {
+        dataset_summary_report(&query, DatasetId(dataset_id), &[]).expect("summary report")
+    });
+
+    if let Err(err) = fs::remove_dir_all(&dir) {
+        eprintln!("could not remove {}: {err}", dir.display());
+    }
+}
+
+/// Runs `step` [`RUNS`] times and prints its median and fastest time.
+fn measure<T>(name: &str, mut step: impl FnMut() -> T) {
+    let mut samples: Vec<Duration> = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(step());
+            start.elapsed()
+        })
+        .collect();
+    samples.sort();
+    println!(
+        "{name:<28} median {:>9.1} ms   fastest {:>9.1} ms",
+        millis(samples[RUNS / 2]),
+        millis(samples[0]),
+    );
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Holdings-like rows with a handful of owners and categories, so filters and grouping
+/// have realistic selectivity.
+fn write_synthetic_csv(path: &Path, row_count: usize) {
+    let file = fs::File::create(path).expect("create csv");
+    let mut out = std::io::BufWriter::new(file);
+    writeln!(out, "{}", HEADERS.join(",")).expect("write header");
+    for idx in 0..row_count {
+        let quantity = 1_000 + (idx * 37) % 9_000;
+        let price = 10.0 + (idx % 500) as f64 * 0.25;
+        let cost = quantity as f64 * price * 0.9;
+        let value = quantity as f64 * price;
+        writeln!(
+            out,
+            "{},標的{},{},{:04},{},{:.2},{:.0},{:.0},{:.0}",
+            OWNERS[idx % OWNERS.len()],
+            idx % 2_000,
+            CATEGORIES[idx % CATEGORIES.len()],
+            50 + idx % 9_000,
+            quantity,
+            price,
+            cost,
+            value,
+            value * 0.04,
+        )
+        .expect("write row");
+    }
+    out.flush().expect("flush csv");
+}
+
+/// A save as the table would stage it: scattered cell edits plus a few added and
+/// deleted rows. `round` varies the values so every run writes real changes.
+fn synthetic_edits(row_count: usize, round: usize) -> StagedEdits {
+    let staged_cells: HashMap<CellKey, String> = (0..EDITED_CELLS.min(row_count))
+        .map(|idx| {
+            let row_idx = (idx * 97) % row_count;
+            let key = CellKey {
+                row_idx,
+                col_idx: 4,
+                column: HEADERS[4].to_string(),
+            };
+            (key, (round * 10 + idx % 10).to_string())
+        })
+        .collect();
+    let added_rows = (0..10)
+        .map(|idx| {
+            let mut row: Vec<String> = HEADERS.iter().map(|_| "0".to_string()).collect();
+            row[0] = OWNERS[idx % OWNERS.len()].to_string();
+            row[1] = format!("新增{round}-{idx}");
+            row
+        })
+        .collect();
+    StagedEdits {
+        staged_cells,
+        deleted_rows: (0..10)
+            .map(|idx| idx * 11 + round)
+            .filter(|idx| *idx < row_count)
+            .collect(),
+        added_rows,
+    }
+}
@@ -0,0 +1,269 @@
+//! Things across the stored datasets that need a look: data that fails a check, a
+//! 資產總表 that disagrees with its holdings, holdings past a loss threshold and 定存 about
+//! to mature. Alerts are worked out again from the data each time; only which ones were
+//! acknowledged is stored, under the alert's key.
+
+use anyhow::Result;
+use chrono::NaiveDate;
+
+use crate::domain::entities::dataset::{DatasetId, DatasetKind};
+use crate::holdings::{holdings_total_net, investment_balance_updates, is_investment_asset};
+use crate::numeric::{
+    format_f64, format_percent, parse_date_value, parse_numeric_value, row_value,
+};
+use crate::usecase::ports::repo::DatasetMeta;
+use crate::usecase::services::query_service::QueryService;
+use crate::usecase::services::recalc_service::in_group;
+
+/// 損益率 loss, as a ratio, past which a holding is flagged until 設定 says otherwise.
+pub const DEFAULT_LOSS_THRESHOLD: f64 = 0.1;
+
+/// 定存 maturing within this many days are flagged.
+pub const MATURITY_WARNING_DAYS: i64 = 30;
+
+/// What an alert is about, in the order the panel lists them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AlertKind {
+    Validation,
+    Reconciliation,
+    Threshold,
+    Maturity,
+}
+
+impl AlertKind {
+    pub const ALL: [AlertKind; 4] = [
+        AlertKind::Validation,
+        AlertKind::Reconciliation,
+        AlertKind::Threshold,
+        AlertKind::Maturity,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AlertKind::Validation => "資料檢查",
+            AlertKind::Reconciliation => "對帳不符",
+            AlertKind::Threshold => "損益警示",
+            AlertKind::Maturity => "定存到期",
+        }
+    }
+}
+
+/// One alert. `key` names what it is about, including the values that raised it, so an
+/// acknowledged alert comes back once those values change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub key: String,
+    pub kind: AlertKind,
+    pub dataset_id: DatasetId,
+    pub dataset_name: String,
+    pub message: String,
+}
+
+/// Alerts of every stored dataset on `today`, holdings flagged once 損益率 falls to
+/// `-loss_threshold` or below. Sorted by kind, then dataset.
+pub fn collect_alerts(
+    query: &QueryService,
+    today: NaiveDate,
+    loss_threshold: f64,
+) -> Result<Vec<Alert>> {
+    let datasets = query.list_datasets(false)?;
+    let mut alerts = Vec::new();
+    for dataset in &datasets {
+        match dataset.effective_kind() {
+            DatasetKind::Holdings => {
+                let page = query.load_tabular_data(dataset.id)?;
+                alerts.extend(holdings_alerts(
+                    dataset,
+                    &page.columns,
+                    &page.rows,
+                    loss_threshold,
+                ));
+            }
+            DatasetKind::Assets => {
+                let page = query.load_tabular_data(dataset.id)?;
+                alerts.extend(assets_alerts(dataset, &page.columns, &page.rows, today));
+                let Some((group, _)) = dataset.source_path.split_once('#') else {
+                    continue;
+                };
+                let holdings = datasets.iter().find(|meta| {
+                    in_group(meta, group) && meta.effective_kind() == DatasetKind::Holdings
+                });
+                if let Some(holdings) = holdings {
+                    let holdings_page = query.load_tabular_data(holdings.id)?;
+                    let total_net = holdings_total_net(&holdings_page.columns, &holdings_page.rows);
+                    if let Some(total_net) = total_net {
+                        alerts.extend(reconciliation_alert(
+                            dataset,
+                            &page.columns,
+                            &page.rows,
+                            total_net,
+                        ));
+                    }
+                }
+            }
+            DatasetKind::Other => {}
+        }
+    }
+    alerts.sort_by_key(|alert| (alert.kind, alert.dataset_id.0));
+    Ok(alerts)
+}
+
+/// 代號 listed on more than one row, and rows whose 損益率 is `-loss_threshold` or lower.
+pub fn holdings_alerts(
+    dataset: &DatasetMeta,
+    columns: &[String],
+    rows: &[Vec<String>],
+    loss_threshold: f64,
+) -> Vec<Alert> {
+    let position = |name: &str| columns.iter().position(|column| column == name);
+    let mut alerts = Vec::new();
+    let name_of = |row: &[String]| {
+        position("名稱")
+            .map(|idx| row_value(row, idx))
+            .unwrap_or_default()
+    };
+
+    if let Some(code_idx) = position("代號") {
+        let mut seen: Vec<(String, Vec<String>)> = Vec::new();
+        for row in rows {
+            let code = row_value(row, code_idx).trim().to_string();
+            if code.is_empty() {
+                continue;
+            }
+            match seen.iter_mut().find(|(known, _)| *known == code) {
+                Some((_, names)) => names.push(name_of(row)),
+                None => seen.push((code, vec![name_of(row)])),
+            }
+        }
+        for (code, names) in seen.into_iter().filter(|(_, names)| names.len() > 1) {
+            alerts.push(alert(
+                dataset,
+                AlertKind::Validation,
+                format!("duplicate-code:{code}"),
+                format!(
+                    "代號 {code} 出現在 {} 列：{}",
+                    names.len(),
+                    names.join("、")
+                ),
+            ));
+        }
+    }
+
+    if let Some(rate_idx) = position("損益率") {
+        for row in rows {
+            let Some(rate) = parse_numeric_value(&row_value(row, rate_idx)) else {
+                continue;
+            };
+            if rate > -loss_threshold {
+                continue;
+            }
+            let code = position("代號")
+                .map(|idx| row_value(row, idx))
+                .unwrap_or_default();
+            let owner = position("所有權人")
+                .map(|idx| row_value(row, idx))
+                .unwrap_or_default();
+            alerts.push(alert(
+                dataset,
+                AlertKind::Threshold,
+                format!("loss:{owner}:{code}:{}", format_f64(loss_threshold)),
+                format!(
+                    "{}（{code}）損益率 {}，已達 -{}",
+                    name_of(row),
+                    format_percent(rate),
+                    format_percent(loss_threshold)
+                ),
+            ));
+        }
+    }
+    alerts
+}
+
+/// Typed 餘額 that are not numbers, and 定存 whose 到期日 is past or within
+/// [`MATURITY_WARNING_DAYS`] of `today`.
+pub fn assets_alerts(
+    dataset: &DatasetMeta,
+    columns: &[String],
+    rows: &[Vec<String>],
+    today: NaiveDate,
+) -> Vec<Alert> {
+    let position = |name: &str| columns.iter().position(|column| column == name);
+    let value = |row: &[String], name: &str| {
+        position(name)
+            .map(|idx| row_value(row, idx).trim().to_string())
+            .unwrap_or_default()
+    };
+    let mut alerts = Vec::new();
+    for row in rows {
+        let form = value(row, "資產形式");
+        let account = format!("{} {}", value(row, "往來機構"), value(row, "帳號"))
+            .trim()
+            .to_string();
+        let balance = value(row, "餘額");
+        if position("餘額").is_some()
+            && !is_investment_asset(&form)
+            && parse_numeric_value(&balance).is_none()
+        {
+            alerts.push(alert(
+                dataset,
+                AlertKind::Validation,
+                format!("balance:{account}:{balance}"),
+                format!("{account} 的餘額「{balance}」不是數字"),
+            ));
+        }
+
+        let Some(maturity) = parse_date_value(&value(row, "到期日")) else {
+            continue;
+        };
+        if !form.contains("定存") {
+            continue;
+        }
+        let days = (maturity - today).num_days();
+        if days > MATURITY_WARNING_DAYS {
+            continue;
+        }
+        let message = if days < 0 {
+            format!("{account} 的定存已於 {maturity} 到期")
+        } else {
+            format!("{account} 的定存將於 {maturity} 到期（剩 {days} 天）")
+        };
+        alerts.push(alert(
+            dataset,
+            AlertKind::Maturity,
+            format!("maturity:{account}:{maturity}"),
+            message,
+        ));
+    }
+    alerts
+}
+
+/// Raised when the investment 餘額 of a 資產總表 no longer matches `holdings_total_net`, the
+/// 淨值 of the 持股 imported with it.
+pub fn reconciliation_alert(
+    dataset: &DatasetMeta,
+    columns: &[String],
+    rows: &[Vec<String>],
+    holdings_total_net: f64,
+) -> Option<Alert> {
+    let (row_idx, col_idx, expected) =
+        investment_balance_updates(columns, rows, holdings_total_net)
+            .into_iter()
+            .next()?;
+    let current = row_value(&rows[row_idx], col_idx);
+    Some(alert(
+        dataset,
+        AlertKind::Reconciliation,
+        format!("investment-balance:{current}:{expected}"),
+        format!("股票投資餘額 {current} 與持股淨值 {expected} 不符"),
+    ))
+}
+
+fn alert(dataset: &DatasetMeta, kind: AlertKind, key: String, message: String) -> Alert {
+    Alert {
+        key: format!("{}:{key}", dataset.id.0),
+        kind,
+        dataset_id: dataset.id,
+        dataset_name: dataset.name.clone(),
+        message,
+    }
+}
@@ -0,0 +1,209 @@
+//! Accounts of a 資產總表: one row per bank account, brokerage account or 定存, added and
+//! checked as a whole through a form instead of cell by cell.
+
+use anyhow::{bail, Result};
+
+use crate::domain::entities::deposit_rate::DepositRate;
+use crate::holdings::is_investment_asset;
+use crate::numeric::{
+    format_f64, parse_numeric_value, parse_numeric_value_in, row_value, NumberLocale,
+};
+
+/// Fields of an account in form order, named after the 資產總表 columns they fill.
+pub const ACCOUNT_FIELDS: [&str; 6] = ["資產形式", "所有權人", "往來機構", "帳號", "幣別", "餘額"];
+
+/// 資產形式 offered in the account form; other values can still be typed.
+pub const ASSET_FORMS: [&str; 4] = ["活存", "定存", "外幣存款", "股票投資"];
+
+/// Columns holding the tenor of a 定存, e.g. `1年`, tried in this order.
+pub const TENOR_COLUMNS: [&str; 3] = ["存期", "期別", "天期"];
+
+/// One row of a 資產總表, field by field as the form edits it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AssetAccount {
+    pub form: String,
+    pub owner: String,
+    pub institution: String,
+    pub account: String,
+    pub currency: String,
+    pub balance: String,
+}
+
+impl AssetAccount {
+    /// Values in the order of [`ACCOUNT_FIELDS`].
+    pub fn values(&self) -> [&str; 6] {
+        [
+            &self.form,
+            &self.owner,
+            &self.institution,
+            &self.account,
+            &self.currency,
+            &self.balance,
+        ]
+    }
+
+    /// Sets the field named `field`, one of [`ACCOUNT_FIELDS`]; other names are ignored.
+    pub fn set(&mut self, field: &str, value: String) {
+        match field {
+            "資產形式" => self.form = value,
+            "所有權人" => self.owner = value,
+            "往來機構" => self.institution = value,
+            "帳號" => self.account = value,
+            "幣別" => self.currency = value,
+            "餘額" => self.balance = value,
+            _ => {}
+        }
+    }
+
+    /// The account in a row of a dataset with `columns`. Missing columns read as empty.
+    pub fn from_row(columns: &[String], row: &[String]) -> Self {
+        let mut account = Self::default();
+        for field in ACCOUNT_FIELDS {
+            if let Some(idx) = columns.iter().position(|column| column == field) {
+                account.set(field, row_value(row, idx));
+            }
+        }
+        account
+    }
+
+    /// A row for a dataset with `columns`: each field in its column, the rest left empty.
+    pub fn to_row(&self, columns: &[String]) -> Vec<String> {
+        columns
+            .iter()
+            .map(|column| {
+                ACCOUNT_FIELDS
+                    .iter()
+                    .position(|field| field == column)
+                    .map(|idx| self.values()[idx].to_string())
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+}
+
+/// Checks `account` before it joins `rows` of a 資產總表 with `columns`, returning it as it
+/// is stored: every field filled in and trimmed, 幣別 a three-letter code in capitals and
+/// 餘額 a number written in `locale`. An account already listed under the same 往來機構
+/// and 帳號 is refused.
+pub fn validate_account(
+    account: &AssetAccount,
+    columns: &[String],
+    rows: &[Vec<String>],
+    locale: NumberLocale,
+) -> Result<AssetAccount> {
+    for (field, value) in ACCOUNT_FIELDS.iter().zip(account.values()) {
+        if value.trim().is_empty() {
+            bail!("請填寫{field}");
+        }
+    }
+    let currency = account.currency.trim().to_ascii_uppercase();
+    if currency.len() != 3 || !currency.chars().all(|ch| ch.is_ascii_alphabetic()) {
+        bail!("幣別須為三個英文字母的代碼，例如 TWD 或 USD");
+    }
+    let normalized = AssetAccount {
+        form: account.form.trim().to_string(),
+        owner: account.owner.trim().to_string(),
+        institution: account.institution.trim().to_string(),
+        account: account.account.trim().to_string(),
+        currency,
+        balance: parse_balance(&account.balance, locale)?,
+    };
+    let duplicate = rows.iter().any(|row| {
+        let existing = AssetAccount::from_row(columns, row);
+        existing.institution.trim() == normalized.institution
+            && existing.account.trim() == normalized.account
+    });
+    if duplicate {
+        bail!(
+            "{} 的帳號 {} 已在資產總表中",
+            normalized.institution,
+            normalized.account
+        );
+    }
+    Ok(normalized)
+}
+
+/// `value` written in `locale` as a stored 餘額, e.g. `1,234.5` as `1234.5`.
+pub fn parse_balance(value: &str, locale: NumberLocale) -> Result<String> {
+    match parse_numeric_value_in(value, locale) {
+        Some(balance) => Ok(format_f64(balance)),
+        None => bail!("餘額必須是數字"),
+    }
+}
+
+/// Rows whose 餘額 is typed in by hand, in order: all but the investment lines, whose 餘額
+/// follows the holdings. Empty when there is no 餘額 column.
+pub fn typed_balance_rows(columns: &[String], rows: &[Vec<String>]) -> Vec<usize> {
+    if !columns.iter().any(|column| column == "餘額") {
+        return Vec::new();
+    }
+    rows.iter()
+        .enumerate()
+        .filter(|(_, row)| !is_investment_asset(&AssetAccount::from_row(columns, row).form))
+        .map(|(row_idx, _)| row_idx)
+        .collect()
+}
+
+/// The 定存 rate table as typed in 設定: one `往來機構 存期 利率` line per rate, fields
+/// separated by spaces, tabs or commas. 利率 is in percent, with or without `%`; a line
+/// without 存期 sets the bank's rate for every tenor not listed on its own.
+pub fn parse_deposit_rates(text: &str) -> Result<Vec<DepositRate>> {
+    let mut rates: Vec<DepositRate> = Vec::new();
+    for (line_idx, line) in text.lines().enumerate() {
+        let fields: Vec<&str> = line
+            .split(|ch: char| ch == ',' || ch == '，' || ch.is_whitespace())
+            .filter(|field| !field.is_empty())
+            .collect();
+        let (institution, tenor, rate) = match fields.as_slice() {
+            [] => continue,
+            [institution, rate] => (*institution, "", *rate),
+            [institution, tenor, rate] => (*institution, *tenor, *rate),
+            _ => bail!("第 {} 行應為「往來機構 存期 利率」", line_idx + 1),
+        };
+        let Some(percent) = parse_numeric_value(rate.trim_end_matches('%')) else {
+            bail!("第 {} 行的利率必須是數字", line_idx + 1);
+        };
+        if rates
+            .iter()
+            .any(|known| known.institution == institution && known.tenor == tenor)
+        {
+            bail!("第 {} 行重複了 {institution} {tenor} 的利率", line_idx + 1);
+        }
+        rates.push(DepositRate {
+            institution: institution.to_string(),
+            tenor: tenor.to_string(),
+            rate: percent / 100.0,
+        });
+    }
+    Ok(rates)
+}
+
+/// `rates` written the way [`parse_deposit_rates`] reads them, one per line.
+pub fn format_deposit_rates(rates: &[DepositRate]) -> String {
+    rates
+        .iter()
+        .map(|rate| {
+            let percent = format!("{}%", format_f64(rate.rate * 100.0));
+            [rate.institution.as_str(), rate.tenor.as_str(), &percent]
+                .into_iter()
+                .filter(|field| !field.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The rate `institution` pays on a 定存 of `tenor`: the entry for that tenor, else the
+/// bank's entry without one.
+pub fn deposit_rate_for(rates: &[DepositRate], institution: &str, tenor: &str) -> Option<f64> {
+    let institution = institution.trim();
+    let tenor = tenor.trim();
+    let of_bank = |tenor: &str| {
+        rates
+            .iter()
+            .find(|rate| rate.institution == institution && rate.tenor == tenor)
+            .map(|rate| rate.rate)
+    };
+    of_bank(tenor).or_else(|| of_bank(""))
+}
@@ -0,0 +1,280 @@
+//! Made-up 資產 / 持股 / 股息 data for trying the app without a real workbook. The raw
+//! sheets go through the same transforms as an imported BOM workbook, so the result has
+//! exactly the columns, derived values and summary rows a real import would.
+
+use crate::domain::entities::dataset::DatasetId;
+use crate::holdings::{
+    merge_holdings_and_dividends, transform_assets_sheet, transform_dividend_sheet,
+    transform_holdings_sheet, DividendColumns, DuplicateCodePolicy,
+};
+use crate::numeric::format_f64;
+use crate::usecase::ports::repo::{NewDatasetMeta, RepoError, TabularData};
+use crate::usecase::services::edit_service::EditService;
+
+/// Group the demo datasets appear under in the dataset picker.
+pub const DEMO_SOURCE: &str = "示範資料";
+
+struct DemoHolding {
+    owner: &'static str,
+    name: &'static str,
+    category: &'static str,
+    kind: &'static str,
+    market: &'static str,
+    code: &'static str,
+    buy: f64,
+    price: f64,
+    quantity: f64,
+    annual_dividend: f64,
+    frequency: &'static str,
+    /// Per-share payout of each month this year, January first.
+    payouts: [f64; 12],
+    last_year: f64,
+}
+
+const HOLDINGS: [DemoHolding; 6] = [
+    DemoHolding {
+        owner: "王小明",
+        name: "元大高股息",
+        category: "ETF",
+        kind: "股票",
+        market: "國內",
+        code: "0056",
+        buy: 32.5,
+        price: 36.8,
+        quantity: 5000.0,
+        annual_dividend: 2.8,
+        frequency: "季配",
+        payouts: [0.7, 0.0, 0.0, 0.7, 0.0, 0.0, 0.7, 0.0, 0.0, 0.0, 0.0, 0.0],
+        last_year: 2.6,
+    },
+    DemoHolding {
+        owner: "王小明",
+        name: "國泰永續高股息",
+        category: "ETF",
+        kind: "股票",
+        market: "國內",
+        code: "00878",
+        buy: 19.2,
+        price: 21.4,
+        quantity: 8000.0,
+        annual_dividend: 1.6,
+        frequency: "季配",
+        payouts: [0.0, 0.4, 0.0, 0.0, 0.4, 0.0, 0.0, 0.4, 0.0, 0.0, 0.0, 0.0],
+        last_year: 1.5,
+    },
+    DemoHolding {
+        owner: "王小明",
+        name: "台積電",
+        category: "個股",
+        kind: "股票",
+        market: "國內",
+        code: "2330",
+        buy: 520.0,
+        price: 980.0,
+        quantity: 300.0,
+        annual_dividend: 16.0,
+        frequency: "季配",
+        payouts: [4.0, 0.0, 0.0, 4.0, 0.0, 0.0, 4.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+        last_year: 14.0,
+    },
+    DemoHolding {
+        owner: "陳美華",
+        name: "中華電",
+        category: "個股",
+        kind: "股票",
+        market: "國內",
+        code: "2412",
+        buy: 118.0,
+        price: 125.5,
+        quantity: 1000.0,
+        annual_dividend: 4.7,
+        frequency: "年配",
+        payouts: [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 4.7, 0.0, 0.0, 0.0, 0.0, 0.0],
+        last_year: 4.6,
+    },
+    DemoHolding {
+        owner: "陳美華",
+        name: "元大美債20年",
+        category: "ETF",
+        kind: "債券",
+        market: "國外",
+        code: "00679B",
+        buy: 30.1,
+        price: 28.4,
+        quantity: 6000.0,
+        annual_dividend: 1.2,
+        frequency: "季配",
+        payouts: [0.0, 0.3, 0.0, 0.0, 0.3, 0.0, 0.0, 0.3, 0.0, 0.0, 0.0, 0.0],
+        last_year: 1.1,
+    },
+    DemoHolding {
+        owner: "陳美華",
+        name: "群益ESG投等債20+",
+        category: "ETF",
+        kind: "債券",
+        market: "國外",
+        code: "00937B",
+        buy: 15.6,
+        price: 16.1,
+        quantity: 10000.0,
+        annual_dividend: 1.08,
+        frequency: "月配",
+        payouts: [
+            0.09, 0.09, 0.09, 0.09, 0.09, 0.09, 0.09, 0.09, 0.09, 0.0, 0.0, 0.0,
+        ],
+        last_year: 1.02,
+    },
+];
+
+/// 資產形式, 所有權人, 往來機構, 帳號, 幣別, 餘額. The 股票投資 balance is replaced by the
+/// holdings' net value, as it is for an imported workbook.
+const ASSETS: [[&str; 6]; 5] = [
+    [
+        "活存",
+        "王小明",
+        "台新銀行",
+        "2888-10-0123456",
+        "TWD",
+        "286000",
+    ],
+    [
+        "定存",
+        "王小明",
+        "中華郵政",
+        "0001234-5678901",
+        "TWD",
+        "1000000",
+    ],
+    ["股票投資", "王小明", "元大證券", "9A95-0012345", "TWD", "0"],
+    [
+        "活存",
+        "陳美華",
+        "國泰世華",
+        "0150-35-012345",
+        "TWD",
+        "154000",
+    ],
+    [
+        "外幣存款",
+        "陳美華",
+        "國泰世華",
+        "0150-35-067890",
+        "USD",
+        "8200",
+    ],
+];
+
+/// The 資產總表 and 持股股息總表 datasets, as an import of a BOM workbook would create them.
+pub fn demo_datasets() -> Vec<(NewDatasetMeta, TabularData)> {
+    let holdings_rows = HOLDINGS
+        .iter()
+        .map(|holding| {
+            let mut row = vec![String::new(); 23];
+            row[0] = holding.owner.to_string();
+            row[1] = holding.name.to_string();
+            row[2] = holding.category.to_string();
+            row[3] = holding.kind.to_string();
+            row[4] = holding.market.to_string();
+            row[5] = holding.code.to_string();
+            row[6] = format_f64(holding.buy);
+            row[7] = format_f64(holding.price);
+            row[8] = format_f64(holding.quantity);
+            row[18] = format_f64(holding.annual_dividend);
+            row[21] = holding.frequency.to_string();
+            let latest = holding
+                .payouts
+                .iter()
+                .rev()
+                .find(|payout| **payout > 0.0)
+                .copied()
+                .unwrap_or_default();
+            row[22] = format_f64(latest);
+            row
+        })
+        .collect::<Vec<_>>();
+    let columns = DividendColumns::LEGACY;
+    let mut dividend_header = vec![String::new(); 34];
+    for (idx, label) in [
+        (columns.name, "名稱"),
+        (columns.kind, "性質"),
+        (columns.code, "代號"),
+        (columns.owner, "所有權人"),
+        (columns.payout_method, "配息方式"),
+        (columns.periods, "期數"),
+        (columns.last_year, "2023年"),
+        (columns.prev_total, "去年度累積"),
+    ] {
+        dividend_header[idx] = label.to_string();
+    }
+    for (month, idx) in columns.months.iter().enumerate() {
+        dividend_header[*idx] = format!("{}月", month + 1);
+    }
+    let dividend_rows = std::iter::once(dividend_header)
+        .chain(HOLDINGS.iter().map(|holding| {
+            let mut row = vec![String::new(); 34];
+            row[columns.name] = holding.name.to_string();
+            row[columns.kind] = holding.kind.to_string();
+            row[columns.code] = holding.code.to_string();
+            row[columns.owner] = holding.owner.to_string();
+            row[columns.payout_method] = if holding.kind == "債券" {
+                "現金"
+            } else {
+                "現金/股票"
+            }
+            .to_string();
+            row[columns.periods] = holding
+                .payouts
+                .iter()
+                .filter(|p| **p > 0.0)
+                .count()
+                .to_string();
+            row[columns.last_year] = format_f64(holding.last_year * 0.9 * holding.quantity);
+            row[columns.prev_total] =
+                format_f64((holding.last_year * 0.9 + holding.last_year) * holding.quantity);
+            for (idx, payout) in columns.months.iter().zip(holding.payouts) {
+                row[*idx] = format_f64(payout * holding.quantity);
+            }
+            row
+        }))
+        .collect::<Vec<_>>();
+    let asset_rows = ASSETS
+        .iter()
+        .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+        .collect::<Vec<Vec<String>>>();
+
+    let holdings = transform_holdings_sheet(&holdings_rows, DuplicateCodePolicy::KeepBoth);
+    let (assets_headers, assets_data, _) =
+        transform_assets_sheet(&asset_rows, holdings.total_cost, holdings.total_net);
+    let (dividend_headers, dividend_data, _) =
+        transform_dividend_sheet(&dividend_rows, &holdings.by_code);
+    let (merged_headers, merged_data, _) = merge_holdings_and_dividends(
+        holdings.headers,
+        holdings.rows,
+        &dividend_headers,
+        &dividend_data,
+    );
+
+    [
+        ("資產總表", assets_headers, assets_data),
+        ("持股股息總表", merged_headers, merged_data),
+    ]
+    .into_iter()
+    .map(|(name, columns, rows)| {
+        (
+            NewDatasetMeta {
+                name: name.to_string(),
+                source_path: format!("{DEMO_SOURCE}#{name}"),
+            },
+            TabularData { columns, rows },
+        )
+    })
+    .collect()
+}
+
+/// Creates the demo datasets and returns their ids, 資產總表 first.
+pub fn create_demo_datasets(service: &EditService) -> Result<Vec<DatasetId>, RepoError> {
+    demo_datasets()
+        .into_iter()
+        .map(|(meta, data)| service.create_dataset(meta, data))
+        .collect()
+}
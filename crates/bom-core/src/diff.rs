@@ -0,0 +1,174 @@
+//! Row-level differences between two versions of a dataset, such as last month's save and
+//! this month's, with rows matched by a key column.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use anyhow::Result;
+
+/// Column that identifies a row across versions: the security code of a holding.
+pub const DIFF_KEY_COLUMN: &str = "代號";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowChange {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl RowChange {
+    pub fn label(self) -> &'static str {
+        match self {
+            RowChange::Added => "新增",
+            RowChange::Removed => "刪除",
+            RowChange::Changed => "變更",
+        }
+    }
+}
+
+/// One row that differs. `before` is empty for an added row and `after` for a removed
+/// one; both follow [`DatasetDiff::columns`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffRow {
+    pub change: RowChange,
+    pub key: String,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatasetDiff {
+    /// Columns of the newer version, then any the older one had that were dropped since.
+    pub columns: Vec<String>,
+    /// Added rows, then removed, then changed, each in dataset order.
+    pub rows: Vec<DiffRow>,
+}
+
+impl DatasetDiff {
+    pub fn count(&self, change: RowChange) -> usize {
+        self.rows.iter().filter(|row| row.change == change).count()
+    }
+
+    /// The diff as one sheet: a 變動 column, then every column. Changed rows show the new
+    /// values, with each changed cell written as `舊值 → 新值`.
+    pub fn table(&self) -> (Vec<String>, Vec<Vec<String>>) {
+        let mut headers = vec!["變動".to_string()];
+        headers.extend(self.columns.iter().cloned());
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                let mut line = vec![row.change.label().to_string()];
+                line.extend((0..self.columns.len()).map(|col_idx| match row.change {
+                    RowChange::Added => row.after[col_idx].clone(),
+                    RowChange::Removed => row.before[col_idx].clone(),
+                    RowChange::Changed => {
+                        let (before, after) = (&row.before[col_idx], &row.after[col_idx]);
+                        if before == after {
+                            after.clone()
+                        } else {
+                            format!("{before} → {after}")
+                        }
+                    }
+                }));
+                line
+            })
+            .collect();
+        (headers, rows)
+    }
+}
+
+/// Compares `before` with `after`, each given as its headers and rows, matching rows on
+/// `key_column`. Rows without a key cannot be matched and are left out; a key that occurs
+/// several times is paired up in row order. Values are compared as stored, after trimming.
+pub fn diff_datasets(
+    key_column: &str,
+    before: (&[String], &[Vec<String>]),
+    after: (&[String], &[Vec<String>]),
+) -> Result<DatasetDiff> {
+    let (before_headers, before_rows) = before;
+    let (after_headers, after_rows) = after;
+    let key_of = |headers: &[String], which: &str| {
+        headers
+            .iter()
+            .position(|header| header == key_column)
+            .ok_or_else(|| anyhow::anyhow!("{which} dataset has no {key_column} column"))
+    };
+    let before_key = key_of(before_headers, "older")?;
+    let after_key = key_of(after_headers, "newer")?;
+
+    let mut columns = after_headers.to_vec();
+    for header in before_headers {
+        if !columns.contains(header) {
+            columns.push(header.clone());
+        }
+    }
+    let align = |headers: &[String], row: &[String]| -> Vec<String> {
+        columns
+            .iter()
+            .map(|column| {
+                headers
+                    .iter()
+                    .position(|header| header == column)
+                    .and_then(|idx| row.get(idx))
+                    .map(|value| value.trim().to_string())
+                    .unwrap_or_default()
+            })
+            .collect()
+    };
+
+    let mut unmatched: BTreeMap<&str, VecDeque<usize>> = BTreeMap::new();
+    for (row_idx, row) in before_rows.iter().enumerate() {
+        let row_key = key_at(row, before_key);
+        if !row_key.is_empty() {
+            unmatched.entry(row_key).or_default().push_back(row_idx);
+        }
+    }
+
+    let (mut added, mut changed) = (Vec::new(), Vec::new());
+    for row in after_rows {
+        let row_key = key_at(row, after_key);
+        if row_key.is_empty() {
+            continue;
+        }
+        let after_values = align(after_headers, row);
+        match unmatched.get_mut(row_key).and_then(VecDeque::pop_front) {
+            Some(before_idx) => {
+                let before_values = align(before_headers, &before_rows[before_idx]);
+                if before_values != after_values {
+                    changed.push(DiffRow {
+                        change: RowChange::Changed,
+                        key: row_key.to_string(),
+                        before: before_values,
+                        after: after_values,
+                    });
+                }
+            }
+            None => added.push(DiffRow {
+                change: RowChange::Added,
+                key: row_key.to_string(),
+                before: Vec::new(),
+                after: after_values,
+            }),
+        }
+    }
+    let mut removed_idx: Vec<usize> = unmatched.into_values().flatten().collect();
+    removed_idx.sort_unstable();
+    let removed = removed_idx.into_iter().map(|row_idx| {
+        let row = &before_rows[row_idx];
+        DiffRow {
+            change: RowChange::Removed,
+            key: key_at(row, before_key).to_string(),
+            before: align(before_headers, row),
+            after: Vec::new(),
+        }
+    });
+
+    let mut rows = added;
+    rows.extend(removed);
+    rows.extend(changed);
+    Ok(DatasetDiff { columns, rows })
+}
+
+fn key_at(row: &[String], idx: usize) -> &str {
+    row.get(idx).map(|value| value.trim()).unwrap_or_default()
+}
@@ -0,0 +1,94 @@
+//! Marking a month's dividends as received in 持股股息總表. The rows expected to pay in a
+//! month are found from their 期數 and the months already received this year, each with
+//! the amount it is expected to pay: 估計配息金額 over 期數, or else its latest payout.
+
+use crate::numeric::{is_summary_label, parse_numeric_value, row_value};
+
+/// Yearly estimates of a dividend row, in order of preference.
+const ESTIMATE_COLUMNS: [&str; 2] = ["估計配息金額", "估計配息"];
+
+/// A row expected to pay in the month, not yet marked received.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpectedPayout {
+    pub row_idx: usize,
+    pub name: String,
+    pub code: String,
+    pub owner: String,
+    pub expected: Option<f64>,
+}
+
+/// Column of `month` (1–12) in a dividend table with `headers`.
+pub fn month_column(headers: &[String], month: u32) -> Option<usize> {
+    let header = format!("{month}月");
+    headers.iter().position(|column| *column == header)
+}
+
+/// Rows of `rows` expected to pay in `month` whose month cell is still empty or zero, in
+/// table order. A row is expected when it pays monthly, when a month received this year
+/// lies a whole number of payment intervals away, or, with nothing received yet, when it
+/// has an estimate to go by.
+pub fn expected_payouts(
+    headers: &[String],
+    rows: &[Vec<String>],
+    month: u32,
+) -> Vec<ExpectedPayout> {
+    let position = |name: &str| headers.iter().position(|column| column == name);
+    let Some(month_idx) = month_column(headers, month) else {
+        return Vec::new();
+    };
+    let months: Vec<(u32, usize)> = (1..=12)
+        .filter_map(|month| Some((month, month_column(headers, month)?)))
+        .collect();
+    let text = |row: &[String], name: &str| {
+        position(name)
+            .map(|idx| row_value(row, idx).trim().to_string())
+            .unwrap_or_default()
+    };
+    let number = |row: &[String], idx: usize| parse_numeric_value(&row_value(row, idx));
+    let estimate_idx = ESTIMATE_COLUMNS.into_iter().find_map(position);
+    let periods_idx = position("期數");
+
+    rows.iter()
+        .enumerate()
+        .filter_map(|(row_idx, row)| {
+            let name = text(row, "名稱");
+            if name.is_empty() || is_summary_label(&name) {
+                return None;
+            }
+            if number(row, month_idx).is_some_and(|amount| amount != 0.0) {
+                return None;
+            }
+            let received: Vec<(u32, f64)> = months
+                .iter()
+                .filter_map(|&(month, idx)| Some((month, number(row, idx).filter(|a| *a > 0.0)?)))
+                .collect();
+            let periods = periods_idx
+                .and_then(|idx| number(row, idx))
+                .map(|periods| periods.round() as u32)
+                .filter(|periods| (1..=12).contains(periods));
+            let expected = periods
+                .zip(estimate_idx.and_then(|idx| number(row, idx)))
+                .filter(|(_, estimate)| *estimate > 0.0)
+                .map(|(periods, estimate)| estimate / f64::from(periods))
+                .or_else(|| received.last().map(|(_, amount)| *amount));
+            let on_schedule = match periods {
+                Some(periods) if periods >= 12 => true,
+                Some(periods) if 12 % periods == 0 => {
+                    let interval = (12 / periods) as i32;
+                    received
+                        .iter()
+                        .any(|(paid, _)| (month as i32 - *paid as i32).rem_euclid(interval) == 0)
+                }
+                _ => false,
+            };
+            let expected_now = on_schedule || (received.is_empty() && expected.is_some());
+            expected_now.then(|| ExpectedPayout {
+                row_idx,
+                name,
+                code: text(row, "代號"),
+                owner: text(row, "所有權人"),
+                expected,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,140 @@
+//! Dividends swept into cash. A dividend entered in a month column of 持股股息總表 has
+//! been paid into the owner's bank account, so the 活存 餘額 of that 所有權人 in the
+//! 資產總表 grows by the same amount. What the month columns held at the last sweep is
+//! kept per dataset as a total per owner; each save credits the difference, so a
+//! correction or a row moved to another owner is taken back as well.
+
+use std::collections::BTreeMap;
+
+use crate::domain::entities::edit::SavedChanges;
+use crate::numeric::{format_f64, is_summary_label, parse_numeric_value, row_value};
+
+/// Holds `on` once dividends are swept into cash. Sweeping rewrites another dataset, so
+/// it stays off until turned on in the settings.
+pub const DIVIDEND_SWEEP_KEY: &str = "dividend_sweep";
+/// Followed by the dataset id; holds [`format_received`] of the last sweep.
+pub const DIVIDEND_SWEEP_RECEIVED_KEY_PREFIX: &str = "dividend_sweep_received:";
+
+const OWNER_COLUMN: &str = "所有權人";
+const NAME_COLUMN: &str = "名稱";
+const FORM_COLUMN: &str = "資產形式";
+const BALANCE_COLUMN: &str = "餘額";
+/// 資產形式 of the account dividends are paid into.
+const CASH_FORM: &str = "活存";
+
+/// Whether the stored [`DIVIDEND_SWEEP_KEY`] value turns sweeping on.
+pub fn sweep_enabled(setting: Option<&str>) -> bool {
+    setting.is_some_and(|value| value.trim() == "on")
+}
+
+/// Sum of the `1月`–`12月` cells per 所有權人, subtotal rows left out; `None` for a table
+/// without month columns.
+pub fn received_by_owner(
+    headers: &[String],
+    rows: &[Vec<String>],
+) -> Option<BTreeMap<String, f64>> {
+    let position = |name: &str| headers.iter().position(|header| header == name);
+    let months = (1..=12)
+        .filter_map(|month| position(&format!("{month}月")))
+        .collect::<Vec<_>>();
+    if months.is_empty() {
+        return None;
+    }
+    let owner_idx = position(OWNER_COLUMN);
+    let name_idx = position(NAME_COLUMN);
+    let mut received = BTreeMap::new();
+    for row in rows {
+        if name_idx.is_some_and(|idx| is_summary_label(&row_value(row, idx))) {
+            continue;
+        }
+        let owner = owner_idx
+            .map(|idx| row_value(row, idx).trim().to_string())
+            .unwrap_or_default();
+        let total: f64 = months
+            .iter()
+            .filter_map(|idx| parse_numeric_value(&row_value(row, *idx)))
+            .sum();
+        *received.entry(owner).or_insert(0.0) += total;
+    }
+    Some(received)
+}
+
+/// One `所有權人\t金額` line per owner.
+pub fn format_received(received: &BTreeMap<String, f64>) -> String {
+    received
+        .iter()
+        .map(|(owner, amount)| format!("{owner}\t{}", format_f64(*amount)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn parse_received(text: &str) -> BTreeMap<String, f64> {
+    text.lines()
+        .filter_map(|line| {
+            let (owner, amount) = line.split_once('\t')?;
+            Some((owner.to_string(), parse_numeric_value(amount)?))
+        })
+        .collect()
+}
+
+/// What each owner received since `before`, to the cent; owners with no change are left
+/// out.
+pub fn owner_deltas(
+    before: &BTreeMap<String, f64>,
+    after: &BTreeMap<String, f64>,
+) -> BTreeMap<String, f64> {
+    before
+        .keys()
+        .chain(after.keys())
+        .filter_map(|owner| {
+            let amount = |totals: &BTreeMap<String, f64>| totals.get(owner).copied().unwrap_or(0.0);
+            let delta = ((amount(after) - amount(before)) * 100.0).round() / 100.0;
+            (delta != 0.0).then(|| (owner.clone(), delta))
+        })
+        .collect()
+}
+
+/// The rows as they were before the save `changes` records: changed cells get their
+/// previous value back and cells of added rows are emptied.
+pub fn rows_before_save(rows: &[Vec<String>], changes: &SavedChanges) -> Vec<Vec<String>> {
+    let mut before = rows.to_vec();
+    for (&(row_idx, col_idx), previous) in changes {
+        if let Some(cell) = before.get_mut(row_idx).and_then(|row| row.get_mut(col_idx)) {
+            *cell = previous.clone().unwrap_or_default();
+        }
+    }
+    before
+}
+
+/// The 餘額 cells of a 資產總表 to rewrite for `deltas`, as `(row, column, new value)`:
+/// each owner's first 活存 account takes the whole amount. Owners without one are
+/// returned separately.
+pub fn cash_balance_updates(
+    columns: &[String],
+    rows: &[Vec<String>],
+    deltas: &BTreeMap<String, f64>,
+) -> (Vec<(usize, usize, String)>, Vec<String>) {
+    let position = |name: &str| columns.iter().position(|column| column == name);
+    let (Some(form_idx), Some(owner_idx), Some(balance_idx)) = (
+        position(FORM_COLUMN),
+        position(OWNER_COLUMN),
+        position(BALANCE_COLUMN),
+    ) else {
+        return (Vec::new(), deltas.keys().cloned().collect());
+    };
+    let mut updates = Vec::new();
+    let mut unmatched = Vec::new();
+    for (owner, delta) in deltas {
+        let account = rows.iter().position(|row| {
+            row_value(row, form_idx).contains(CASH_FORM)
+                && row_value(row, owner_idx).trim() == owner
+        });
+        let Some(row_idx) = account else {
+            unmatched.push(owner.clone());
+            continue;
+        };
+        let balance = parse_numeric_value(&row_value(&rows[row_idx], balance_idx)).unwrap_or(0.0);
+        updates.push((row_idx, balance_idx, format_f64(balance + delta)));
+    }
+    (updates, unmatched)
+}
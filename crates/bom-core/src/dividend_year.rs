@@ -0,0 +1,159 @@
+//! Closing a year of dividends in 持股股息總表. The twelve month columns only ever hold the
+//! current year; at rollover their sum is archived into a new `YYYY年` column and added to
+//! 去年度累積, and the months start again from zero. The closed year stays available as a
+//! renamed copy of the dataset.
+
+use anyhow::{bail, Context, Result};
+
+use crate::domain::entities::dataset::DatasetId;
+use crate::holdings::{parse_year_header, year_header};
+use crate::numeric::{format_f64, parse_numeric_value};
+use crate::usecase::ports::repo::{DatasetMeta, NewDatasetMeta, TabularData};
+use crate::usecase::services::edit_service::EditService;
+use crate::usecase::services::query_service::QueryService;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DividendRollover {
+    /// The year whose months were archived.
+    pub closed_year: i32,
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// The year the month columns currently belong to: the one after the newest `YYYY年`.
+pub fn open_dividend_year(headers: &[String]) -> Option<i32> {
+    headers
+        .iter()
+        .filter_map(|header| parse_year_header(header))
+        .max()
+        .map(|year| year + 1)
+}
+
+/// Archives the month columns into a `YYYY年` column appended at the end, so existing
+/// column positions (and the visibility, aliases and formats keyed by them) stay valid.
+pub fn roll_over_dividend_year(
+    headers: &[String],
+    rows: &[Vec<String>],
+) -> Result<DividendRollover> {
+    let closed_year = open_dividend_year(headers).context("no YYYY年 dividend columns")?;
+    let column = |name: &str| headers.iter().position(|header| header == name);
+    let months = (1..=12)
+        .map(|month| column(&format!("{month}月")))
+        .collect::<Option<Vec<_>>>()
+        .context("missing month columns")?;
+    let (Some(prev_total), Some(current_total)) = (column("去年度累積"), column("今年度累積"))
+    else {
+        bail!("missing 去年度累積 / 今年度累積 columns");
+    };
+    let total = column("總累積");
+    let expected = column("預估累積");
+    let variance = column("預算實際差異");
+
+    let mut rolled_headers = headers.to_vec();
+    rolled_headers.push(year_header(closed_year));
+    let rolled_rows = rows
+        .iter()
+        .map(|row| {
+            let mut rolled = row.clone();
+            rolled.resize(headers.len(), String::new());
+            let value = |idx: usize| parse_numeric_value(&rolled[idx]);
+            let has_dividends = months
+                .iter()
+                .chain([&prev_total, &current_total])
+                .any(|idx| !rolled[*idx].trim().is_empty());
+            if !has_dividends {
+                rolled.push(String::new());
+                return rolled;
+            }
+            let year_total: f64 = months.iter().filter_map(|idx| value(*idx)).sum();
+            let cumulative = value(prev_total).unwrap_or(0.0) + year_total;
+            let expected = expected.and_then(value).unwrap_or(0.0);
+            for idx in &months {
+                rolled[*idx] = format_f64(0.0);
+            }
+            rolled[prev_total] = format_f64(cumulative);
+            rolled[current_total] = format_f64(0.0);
+            if let Some(idx) = total {
+                rolled[idx] = format_f64(cumulative);
+            }
+            if let Some(idx) = variance {
+                rolled[idx] = format_f64(-expected);
+            }
+            rolled.push(format_f64(year_total));
+            rolled
+        })
+        .collect();
+
+    Ok(DividendRollover {
+        closed_year,
+        headers: rolled_headers,
+        rows: rolled_rows,
+    })
+}
+
+/// Rolls `dataset_id` into a new dataset with the same name, group and column settings,
+/// and renames the original to `名稱 YYYY年` as the archive of the closed year. Returns the
+/// new dataset and the closed year.
+pub fn roll_over_dividend_dataset(
+    query: &QueryService,
+    edit: &EditService,
+    dataset_id: DatasetId,
+) -> Result<(DatasetId, i32)> {
+    let meta = query
+        .list_datasets(false)?
+        .into_iter()
+        .find(|meta| meta.id == dataset_id)
+        .context("dataset not found")?;
+    let computed = query.load_computed_columns(dataset_id)?;
+    // Stored columns only: computed columns are re-evaluated on the new dataset.
+    let page = query.load_tabular_data(dataset_id)?;
+    let rollover = roll_over_dividend_year(&page.columns, &page.rows)?;
+
+    let new_id = edit.create_dataset(
+        NewDatasetMeta {
+            name: meta.name.clone(),
+            source_path: meta.source_path.clone(),
+        },
+        TabularData {
+            columns: rollover.headers,
+            rows: rollover.rows,
+        },
+    )?;
+    query.upsert_column_visibility(new_id, query.load_column_visibility(dataset_id)?)?;
+    query.upsert_column_aliases(new_id, query.load_column_aliases(dataset_id)?)?;
+    query.upsert_column_formats(new_id, query.load_column_formats(dataset_id)?)?;
+    query.save_computed_columns(new_id, computed)?;
+    if let Some(is_holdings) = query.load_holdings_flags()?.get(&dataset_id.0) {
+        query.upsert_holdings_flag(new_id, *is_holdings)?;
+    }
+    query.rename_dataset(
+        dataset_id,
+        format!("{} {}", meta.name, year_header(rollover.closed_year)),
+    )?;
+    Ok((new_id, rollover.closed_year))
+}
+
+/// The datasets of `datasets` that hold current figures. 另存 copies and closed-year
+/// archives repeat the rows of the dataset they came from, so totals over every dataset
+/// would count those rows twice.
+pub fn live_datasets(datasets: &[DatasetMeta]) -> Vec<DatasetMeta> {
+    datasets
+        .iter()
+        .filter(|dataset| {
+            dataset.saved_as_copy_of().is_none() && !is_year_archive(dataset, datasets)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Whether `meta` is the archive a rollover left behind: named `名稱 YYYY年`, from the same
+/// source as a dataset of `datasets` still named `名稱`.
+pub fn is_year_archive(meta: &DatasetMeta, datasets: &[DatasetMeta]) -> bool {
+    let Some((name, year)) = meta.name.rsplit_once(' ') else {
+        return false;
+    };
+    parse_year_header(year).is_some()
+        && datasets.iter().any(|other| {
+            other.id != meta.id && other.source_path == meta.source_path && other.name == name
+        })
+}
@@ -0,0 +1,30 @@
+use std::collections::BTreeMap;
+
+/// The source cell an imported cell was read from, with its value as the file had it:
+/// before the import normalised it or put a computed figure in its place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellOrigin {
+    pub sheet: String,
+    /// Position in the sheet, counted from its first row and column.
+    pub row_idx: usize,
+    pub col_idx: usize,
+    pub value: String,
+}
+
+impl CellOrigin {
+    /// Spreadsheet-style reference such as `持股明細!I5`.
+    pub fn reference(&self) -> String {
+        let mut letters = String::new();
+        let mut col = self.col_idx + 1;
+        while col > 0 {
+            let rem = (col - 1) % 26;
+            letters.insert(0, char::from(b'A' + rem as u8));
+            col = (col - 1) / 26;
+        }
+        format!("{}!{letters}{}", self.sheet, self.row_idx + 1)
+    }
+}
+
+/// Origins of a dataset's imported cells, keyed by `(row_idx, col_idx)`. Cells the import
+/// computed and rows added afterwards have none.
+pub type CellOrigins = BTreeMap<(usize, usize), CellOrigin>;
@@ -0,0 +1,42 @@
+use std::collections::BTreeMap;
+
+/// The columns a dataset type is built around. `required` columns must be filled in on
+/// every row, and a table holding all of the 持股 ones is treated as holdings; `derived`
+/// columns are computed at import and never edited by hand. Saved per dataset type so
+/// workbooks whose headers differ slightly can be adapted in 設定.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnSet {
+    pub required: Vec<String>,
+    pub derived: Vec<String>,
+}
+
+impl ColumnSet {
+    /// Whether `headers` contain every required column. A set without required columns
+    /// matches nothing.
+    pub fn matches(&self, headers: &[String]) -> bool {
+        !self.required.is_empty()
+            && self
+                .required
+                .iter()
+                .all(|column| headers.iter().any(|header| header == column))
+    }
+
+    /// The column visibility a table of this set starts with: only the required columns
+    /// are shown.
+    pub fn default_visibility(&self, headers: &[String]) -> BTreeMap<i64, bool> {
+        headers
+            .iter()
+            .enumerate()
+            .map(|(idx, header)| (idx as i64, self.required.contains(header)))
+            .collect()
+    }
+
+    /// Columns of `headers` that can be edited: all but the derived ones.
+    pub fn editable_columns(&self, headers: &[String]) -> Vec<String> {
+        headers
+            .iter()
+            .filter(|header| !self.derived.contains(header))
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,7 @@
+/// A column whose values are computed from other columns of the same row each time the
+/// dataset is queried. `expression` uses the syntax of [`crate::expression`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComputedColumn {
+    pub name: String,
+    pub expression: String,
+}
@@ -0,0 +1,284 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DatasetId(pub i64);
+
+impl From<i64> for DatasetId {
+    fn from(value: i64) -> Self {
+        DatasetId(value)
+    }
+}
+
+impl From<DatasetId> for i64 {
+    fn from(value: DatasetId) -> Self {
+        value.0
+    }
+}
+
+/// What a dataset is used as. 資產總表 and 持股 tables get their own tab buttons and
+/// editable columns; the type is stored per dataset so renaming one keeps its behaviour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DatasetKind {
+    Assets,
+    Holdings,
+    #[default]
+    Other,
+}
+
+impl DatasetKind {
+    pub const ALL: [DatasetKind; 3] = [
+        DatasetKind::Assets,
+        DatasetKind::Holdings,
+        DatasetKind::Other,
+    ];
+
+    /// The type a dataset called `name` gets when it is created.
+    pub fn from_name(name: &str) -> Self {
+        let trimmed = name.trim();
+        if trimmed.contains("資產總表") {
+            DatasetKind::Assets
+        } else if trimmed.contains("持股") {
+            DatasetKind::Holdings
+        } else {
+            DatasetKind::Other
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DatasetKind::Assets => "assets",
+            DatasetKind::Holdings => "holdings",
+            DatasetKind::Other => "other",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == value.trim())
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DatasetKind::Assets => "資產總表",
+            DatasetKind::Holdings => "持股",
+            DatasetKind::Other => "一般",
+        }
+    }
+}
+
+/// What the values of a column are. Decides alignment, sorting, which edits a cell accepts
+/// and whether it is shown as a number. Inferred at import and stored per column; the
+/// user can override it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColumnType {
+    Text,
+    Number,
+    /// Numbers stored as ratios and shown as percentages.
+    Percent,
+    Date,
+}
+
+impl ColumnType {
+    pub const ALL: [ColumnType; 4] = [
+        ColumnType::Text,
+        ColumnType::Number,
+        ColumnType::Percent,
+        ColumnType::Date,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ColumnType::Text => "text",
+            ColumnType::Number => "number",
+            ColumnType::Percent => "percent",
+            ColumnType::Date => "date",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|column_type| column_type.as_str() == value.trim())
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ColumnType::Text => "文字",
+            ColumnType::Number => "數字",
+            ColumnType::Percent => "百分比",
+            ColumnType::Date => "日期",
+        }
+    }
+
+    pub fn is_numeric(self) -> bool {
+        matches!(self, ColumnType::Number | ColumnType::Percent)
+    }
+}
+
+/// Unit amount columns are shown in. Values stay stored in 元; only display and export
+/// divide them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DisplayUnit {
+    #[default]
+    Yuan,
+    Thousand,
+    TenThousand,
+    Million,
+}
+
+impl DisplayUnit {
+    pub const ALL: [DisplayUnit; 4] = [
+        DisplayUnit::Yuan,
+        DisplayUnit::Thousand,
+        DisplayUnit::TenThousand,
+        DisplayUnit::Million,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DisplayUnit::Yuan => "yuan",
+            DisplayUnit::Thousand => "thousand",
+            DisplayUnit::TenThousand => "ten_thousand",
+            DisplayUnit::Million => "million",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|unit| unit.as_str() == value.trim())
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            DisplayUnit::Yuan => "元",
+            DisplayUnit::Thousand => "千元",
+            DisplayUnit::TenThousand => "萬元",
+            DisplayUnit::Million => "百萬元",
+        }
+    }
+
+    /// How many 元 one displayed unit is.
+    pub fn divisor(self) -> f64 {
+        match self {
+            DisplayUnit::Yuan => 1.0,
+            DisplayUnit::Thousand => 1_000.0,
+            DisplayUnit::TenThousand => 10_000.0,
+            DisplayUnit::Million => 1_000_000.0,
+        }
+    }
+}
+
+/// Currency and unit a dataset's amounts are shown in, stored per dataset so a USD sheet
+/// is not read as TWD.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatasetDisplay {
+    pub currency: String,
+    pub unit: DisplayUnit,
+}
+
+impl Default for DatasetDisplay {
+    fn default() -> Self {
+        DatasetDisplay {
+            currency: DEFAULT_CURRENCY.to_string(),
+            unit: DisplayUnit::Yuan,
+        }
+    }
+}
+
+/// Currency of a dataset without a display setting.
+pub const DEFAULT_CURRENCY: &str = "TWD";
+
+impl DatasetDisplay {
+    pub fn is_default(&self) -> bool {
+        *self == DatasetDisplay::default()
+    }
+
+    /// What amounts are in, such as `USD 千元`.
+    pub fn caption(&self) -> String {
+        format!("{} {}", self.currency, self.unit.label())
+    }
+}
+
+/// Display format for a numeric column, stored per dataset column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnFormat {
+    pub decimals: u32,
+    pub percent: bool,
+    pub thousands_separator: bool,
+    pub currency_symbol: String,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SortSpec {
+    pub column_idx: i64,
+    pub direction: SortDirection,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ColumnFilter {
+    pub column_idx: i64,
+    pub term: String,
+}
+
+/// Keeps only rows whose cell in `column_idx` is exactly one of `values`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ValueFilter {
+    pub column_idx: i64,
+    pub values: Vec<String>,
+}
+
+/// Keeps only rows whose cell in `column_idx` passes `expression`, a
+/// [`crate::filter_expr`] expression such as `> 1000`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ColumnCondition {
+    pub column_idx: i64,
+    pub expression: String,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PageQuery {
+    pub dataset_id: DatasetId,
+    pub page: i64,
+    pub page_size: i64,
+    pub global_search: String,
+    pub column_filter: Option<ColumnFilter>,
+    pub value_filters: Vec<ValueFilter>,
+    pub column_conditions: Vec<ColumnCondition>,
+    pub sort: Option<SortSpec>,
+}
+
+/// One column over the rows that pass a query's filters.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ColumnStats {
+    /// Cells that are not blank.
+    pub nonempty: i64,
+    /// Cells read as numbers, the ones `min`, `max` and `sum` cover.
+    pub numeric: i64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub sum: f64,
+}
+
+impl ColumnStats {
+    pub fn average(&self) -> Option<f64> {
+        (self.numeric > 0).then(|| self.sum / self.numeric as f64)
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    pub total_rows: i64,
+}
@@ -0,0 +1,8 @@
+/// Annual interest a bank pays on 定存 of one tenor, as a ratio (`0.016` for 1.6%). An
+/// empty `tenor` applies to every tenor of the bank without a rate of its own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepositRate {
+    pub institution: String,
+    pub tenor: String,
+    pub rate: f64,
+}
@@ -1,4 +1,4 @@
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct CellKey {
@@ -13,3 +13,7 @@ pub struct StagedEdits {
     pub deleted_rows: BTreeSet<usize>,
     pub added_rows: Vec<Vec<String>>,
 }
+
+/// Cells the last save of a dataset changed, keyed by `(row_idx, col_idx)` after that
+/// save, with the value each held before it. Cells of rows the save added have `None`.
+pub type SavedChanges = BTreeMap<(usize, usize), Option<String>>;
@@ -0,0 +1,30 @@
+/// Saved way of reading a CSV whose columns don't match the holdings sheet: which source
+/// column feeds each target column, where the header row is and how the file is encoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportProfile {
+    pub name: String,
+    /// 0-based row holding the column names; rows above it are skipped.
+    pub header_row: usize,
+    /// WHATWG encoding label, e.g. `utf-8` or `big5`.
+    pub encoding: String,
+    /// Target columns in output order.
+    pub columns: Vec<ColumnMapping>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMapping {
+    pub source: String,
+    pub target: String,
+}
+
+impl ImportProfile {
+    /// Whether a file with `headers` can be read with this profile: every mapped source
+    /// column is present.
+    pub fn matches(&self, headers: &[String]) -> bool {
+        !self.columns.is_empty()
+            && self
+                .columns
+                .iter()
+                .all(|mapping| headers.iter().any(|header| header.trim() == mapping.source))
+    }
+}
@@ -0,0 +1,60 @@
+/// Whether an import history entry read a file or re-ran the transform on the sheets
+/// stored with a dataset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportEventKind {
+    Import,
+    Retransform,
+}
+
+impl ImportEventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ImportEventKind::Import => "import",
+            ImportEventKind::Retransform => "retransform",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [ImportEventKind::Import, ImportEventKind::Retransform]
+            .into_iter()
+            .find(|kind| kind.as_str() == value)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ImportEventKind::Import => "匯入",
+            ImportEventKind::Retransform => "重新轉換",
+        }
+    }
+}
+
+/// A dataset an import wrote, with the rows it received.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedSheet {
+    pub dataset_id: i64,
+    pub name: String,
+    pub row_count: i64,
+}
+
+/// One import or re-transform, kept so the dataset manager can tell when a dataset last
+/// came from its workbook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportRecord {
+    pub kind: ImportEventKind,
+    pub source_path: String,
+    /// SHA-256 of the file, in hex. Empty for re-transforms, which read no file.
+    pub file_hash: String,
+    pub sheets: Vec<ImportedSheet>,
+    /// Local time the import finished, as `YYYY-MM-DD HH:MM:SS`.
+    pub imported_at: String,
+    pub duration_ms: i64,
+    pub warnings: Vec<String>,
+}
+
+impl ImportRecord {
+    pub fn includes(&self, dataset_id: i64) -> bool {
+        self.sheets
+            .iter()
+            .any(|sheet| sheet.dataset_id == dataset_id)
+    }
+}
@@ -0,0 +1,11 @@
+pub mod cell_origin;
+pub mod column_set;
+pub mod computed_column;
+pub mod dataset;
+pub mod deposit_rate;
+pub mod edit;
+pub mod import_profile;
+pub mod import_record;
+pub mod owner_share;
+pub mod price_point;
+pub mod saved_report;
@@ -0,0 +1,12 @@
+use std::collections::BTreeMap;
+
+/// One co-owner's part of a dataset row, in percent of the row's amounts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwnerShare {
+    pub owner: String,
+    pub percent: f64,
+}
+
+/// Shares of the co-owned rows of a dataset, by row index. Rows not listed belong wholly
+/// to the owner in their 所有權人 cell.
+pub type OwnerSplits = BTreeMap<usize, Vec<OwnerShare>>;
@@ -0,0 +1,13 @@
+use std::collections::BTreeMap;
+
+/// A market price of one 代號 as a price provider fetched it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PricePoint {
+    pub code: String,
+    pub price: f64,
+    /// Local time of the fetch, as `YYYY-MM-DD HH:MM:SS`.
+    pub fetched_at: String,
+}
+
+/// Stored prices by 代號, each list oldest first.
+pub type PriceHistory = BTreeMap<String, Vec<PricePoint>>;
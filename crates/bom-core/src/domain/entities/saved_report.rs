@@ -0,0 +1,7 @@
+/// A report built in the report builder, kept under its name. `script` is the report in
+/// the report-script language, so saved reports also run with 執行自訂報表.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavedReport {
+    pub name: String,
+    pub script: String,
+}
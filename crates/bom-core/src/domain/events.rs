@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::domain::entities::dataset::DatasetId;
+
+/// Something that changed the stored data. Services publish these after a successful
+/// write so the UI, history and exporters can react without the caller wiring each one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DomainEvent {
+    DatasetImported {
+        dataset_ids: Vec<DatasetId>,
+        source_path: PathBuf,
+    },
+    EditsApplied {
+        dataset_id: DatasetId,
+    },
+    DatasetDeleted {
+        dataset_id: DatasetId,
+    },
+    /// A dataset was taken out of the trash.
+    DatasetRestored {
+        dataset_id: DatasetId,
+    },
+    /// Market prices of a dataset were refreshed.
+    PricesUpdated {
+        dataset_id: DatasetId,
+    },
+    /// Settings and column preferences were replaced from a preferences file.
+    PreferencesImported,
+}
+
+/// Handle returned by [`EventBus::subscribe`], used to unsubscribe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+type Handler = Arc<dyn Fn(&DomainEvent) + Send + Sync>;
+
+#[derive(Default)]
+struct Subscribers {
+    next_id: u64,
+    handlers: Vec<(SubscriptionId, Handler)>,
+}
+
+/// Synchronous publish/subscribe hub shared by the services. Cloning shares the subscribers.
+#[derive(Clone, Default)]
+pub struct EventBus {
+    inner: Arc<Mutex<Subscribers>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe(
+        &self,
+        handler: impl Fn(&DomainEvent) + Send + Sync + 'static,
+    ) -> SubscriptionId {
+        let mut inner = self.lock();
+        let id = SubscriptionId(inner.next_id);
+        inner.next_id += 1;
+        inner.handlers.push((id, Arc::new(handler)));
+        id
+    }
+
+    pub fn unsubscribe(&self, id: SubscriptionId) {
+        self.lock()
+            .handlers
+            .retain(|(handler_id, _)| *handler_id != id);
+    }
+
+    /// Calls every subscriber in subscription order. Handlers run outside the lock, so
+    /// they may subscribe or publish themselves.
+    pub fn publish(&self, event: DomainEvent) {
+        let handlers = self
+            .lock()
+            .handlers
+            .iter()
+            .map(|(_, handler)| handler.clone())
+            .collect::<Vec<_>>();
+        tracing::debug!(?event, subscribers = handlers.len(), "publish event");
+        for handler in handlers {
+            handler(&event);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Subscribers> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
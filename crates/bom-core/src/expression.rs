@@ -0,0 +1,267 @@
+//! Arithmetic over the columns of a row, for computed columns such as
+//! `淨值 / 總成本 - 1` or `估計配息 / 12`.
+//!
+//! Operands are numbers or column names; `+ - * /`, unary minus and parentheses work as
+//! usual. A column name that contains spaces, operators or parentheses is quoted, e.g.
+//! `"國內 /國外" * 2`. A blank or non-numeric cell, or a division by zero, leaves the
+//! result blank rather than failing the whole row.
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::domain::entities::computed_column::ComputedColumn;
+use crate::numeric::{format_f64, parse_numeric_value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Number(f64),
+    Column(String),
+    Negate(Box<Expression>),
+    Binary(Box<Expression>, Operator, Box<Expression>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Operator(Operator),
+    Open,
+    Close,
+}
+
+impl Expression {
+    pub fn parse(text: &str) -> Result<Self> {
+        let tokens = tokenize(text)?;
+        if tokens.is_empty() {
+            bail!("運算式是空的");
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let expression = parser.sum()?;
+        if parser.pos < parser.tokens.len() {
+            bail!("運算式第 {} 個項目無法解析", parser.pos + 1);
+        }
+        Ok(expression)
+    }
+
+    /// Column names the expression reads, in order of appearance.
+    pub fn columns(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.collect_columns(&mut names);
+        names
+    }
+
+    fn collect_columns<'a>(&'a self, names: &mut Vec<&'a str>) {
+        match self {
+            Expression::Number(_) => {}
+            Expression::Column(name) => {
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+            Expression::Negate(inner) => inner.collect_columns(names),
+            Expression::Binary(left, _, right) => {
+                left.collect_columns(names);
+                right.collect_columns(names);
+            }
+        }
+    }
+
+    /// Value for one row; `None` when an operand is blank or not a number.
+    pub fn evaluate(&self, headers: &[String], row: &[String]) -> Option<f64> {
+        match self {
+            Expression::Number(value) => Some(*value),
+            Expression::Column(name) => {
+                let idx = headers.iter().position(|header| header == name)?;
+                parse_numeric_value(row.get(idx)?)
+            }
+            Expression::Negate(inner) => inner.evaluate(headers, row).map(|value| -value),
+            Expression::Binary(left, operator, right) => {
+                let left = left.evaluate(headers, row)?;
+                let right = right.evaluate(headers, row)?;
+                let value = match operator {
+                    Operator::Add => left + right,
+                    Operator::Subtract => left - right,
+                    Operator::Multiply => left * right,
+                    Operator::Divide => left / right,
+                };
+                value.is_finite().then_some(value)
+            }
+        }
+    }
+}
+
+/// Checks that every computed column has a unique name and an expression over columns
+/// defined before it: the stored `headers` or earlier computed columns.
+pub fn validate_computed_columns(headers: &[String], computed: &[ComputedColumn]) -> Result<()> {
+    let mut known = headers.to_vec();
+    for column in computed {
+        let name = column.name.trim();
+        if name.is_empty() {
+            bail!("計算欄位名稱不可空白");
+        }
+        if known.iter().any(|header| header == name) {
+            bail!("欄位名稱重複：{name}");
+        }
+        let expression = Expression::parse(&column.expression)
+            .map_err(|err| anyhow!("計算欄位「{name}」：{err}"))?;
+        if let Some(missing) = expression
+            .columns()
+            .into_iter()
+            .find(|referenced| !known.iter().any(|header| header == referenced))
+        {
+            bail!("計算欄位「{name}」：找不到欄位「{missing}」");
+        }
+        known.push(name.to_string());
+    }
+    Ok(())
+}
+
+/// Appends the computed columns to `headers` and every row. Each computed column can use
+/// the ones before it. Definitions that no longer parse yield blank cells.
+pub fn append_computed_columns(
+    headers: &mut Vec<String>,
+    rows: &mut [Vec<String>],
+    computed: &[ComputedColumn],
+) {
+    let stored_len = headers.len();
+    for row in rows.iter_mut() {
+        row.resize(stored_len, String::new());
+    }
+    for column in computed {
+        let expression = Expression::parse(&column.expression).ok();
+        for row in rows.iter_mut() {
+            let value = expression
+                .as_ref()
+                .and_then(|expression| expression.evaluate(headers, row))
+                .map(format_f64)
+                .unwrap_or_default();
+            row.push(value);
+        }
+        headers.push(column.name.clone());
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&ch) = chars.peek() {
+        match ch {
+            _ if ch.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            '+' | '-' | '*' | '/' => {
+                chars.next();
+                tokens.push(Token::Operator(match ch {
+                    '+' => Operator::Add,
+                    '-' => Operator::Subtract,
+                    '*' => Operator::Multiply,
+                    _ => Operator::Divide,
+                }));
+            }
+            '"' => {
+                chars.next();
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(ch) => name.push(ch),
+                        None => bail!("欄位名稱缺少結尾的引號"),
+                    }
+                }
+                tokens.push(Token::Name(name));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if ch.is_whitespace() || "()+-*/\"".contains(ch) {
+                        break;
+                    }
+                    word.push(ch);
+                    chars.next();
+                }
+                tokens.push(match word.parse::<f64>() {
+                    Ok(value) => Token::Number(value),
+                    Err(_) => Token::Name(word),
+                });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next_operator(&mut self, accepted: &[Operator]) -> Option<Operator> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Operator(operator)) if accepted.contains(operator) => {
+                self.pos += 1;
+                Some(*operator)
+            }
+            _ => None,
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expression> {
+        let mut expression = self.product()?;
+        while let Some(operator) = self.next_operator(&[Operator::Add, Operator::Subtract]) {
+            let right = self.product()?;
+            expression = Expression::Binary(Box::new(expression), operator, Box::new(right));
+        }
+        Ok(expression)
+    }
+
+    fn product(&mut self) -> Result<Expression> {
+        let mut expression = self.unary()?;
+        while let Some(operator) = self.next_operator(&[Operator::Multiply, Operator::Divide]) {
+            let right = self.unary()?;
+            expression = Expression::Binary(Box::new(expression), operator, Box::new(right));
+        }
+        Ok(expression)
+    }
+
+    fn unary(&mut self) -> Result<Expression> {
+        if self.next_operator(&[Operator::Subtract]).is_some() {
+            return Ok(Expression::Negate(Box::new(self.unary()?)));
+        }
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| anyhow!("運算式不完整"))?;
+        self.pos += 1;
+        match token {
+            Token::Number(value) => Ok(Expression::Number(value)),
+            Token::Name(name) => Ok(Expression::Column(name)),
+            Token::Open => {
+                let inner = self.sum()?;
+                if self.tokens.get(self.pos) != Some(&Token::Close) {
+                    bail!("缺少右括號");
+                }
+                self.pos += 1;
+                Ok(inner)
+            }
+            Token::Close | Token::Operator(_) => {
+                bail!("運算式第 {} 個項目無法解析", self.pos)
+            }
+        }
+    }
+}
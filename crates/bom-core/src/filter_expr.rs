@@ -0,0 +1,140 @@
+//! Filter expressions typed under a column header, such as `< 0` for the losing holdings
+//! or `> 1000` for large positions.
+//!
+//! `=`, `!=`, `>`, `>=`, `<` and `<=` compare with a number, `1~5` keeps the values
+//! between both ends inclusive, and `空` / `非空` keep blank or filled cells. A bare value
+//! means `= value`; `=` or `!=` with nothing after it mean `空` or `非空`. Cells are read as
+//! [`parse_numeric_value`] reads them, so `1,200` and `12%` compare as numbers. `=` and
+//! `!=` with a value that is not a number compare the trimmed text instead.
+
+use crate::numeric::parse_numeric_value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl CompareOp {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            CompareOp::Eq => "=",
+            CompareOp::Ne => "!=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+        }
+    }
+
+    fn holds(self, left: f64, right: f64) -> bool {
+        match self {
+            CompareOp::Eq => left == right,
+            CompareOp::Ne => left != right,
+            CompareOp::Gt => left > right,
+            CompareOp::Ge => left >= right,
+            CompareOp::Lt => left < right,
+            CompareOp::Le => left <= right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpr {
+    Compare(CompareOp, f64),
+    /// Both ends included, lower end first.
+    Between(f64, f64),
+    /// `=` or, when `negated`, `!=` a value that is not a number.
+    Text {
+        value: String,
+        negated: bool,
+    },
+    Empty,
+    NotEmpty,
+}
+
+/// Operators in the order they are tried, longer ones before their prefixes.
+const OPERATORS: [(&str, CompareOp); 7] = [
+    (">=", CompareOp::Ge),
+    ("<=", CompareOp::Le),
+    ("!=", CompareOp::Ne),
+    ("<>", CompareOp::Ne),
+    (">", CompareOp::Gt),
+    ("<", CompareOp::Lt),
+    ("=", CompareOp::Eq),
+];
+
+/// Reads `text`; `None` when it is blank or not an expression, e.g. `> abc`. Full-width
+/// operators as typed with a Chinese input method work too.
+pub fn parse_filter_expr(text: &str) -> Option<FilterExpr> {
+    let text: String = text
+        .trim()
+        .chars()
+        .map(|ch| match ch {
+            '＞' => '>',
+            '＜' => '<',
+            '＝' => '=',
+            '！' => '!',
+            '～' => '~',
+            other => other,
+        })
+        .collect();
+    match text.as_str() {
+        "" => return None,
+        "空" => return Some(FilterExpr::Empty),
+        "非空" => return Some(FilterExpr::NotEmpty),
+        _ => {}
+    }
+    for (symbol, op) in OPERATORS {
+        let Some(operand) = text.strip_prefix(symbol) else {
+            continue;
+        };
+        let operand = operand.trim();
+        return match (op, parse_numeric_value(operand)) {
+            (CompareOp::Eq, _) if operand.is_empty() => Some(FilterExpr::Empty),
+            (CompareOp::Ne, _) if operand.is_empty() => Some(FilterExpr::NotEmpty),
+            (op, Some(number)) => Some(FilterExpr::Compare(op, number)),
+            (CompareOp::Eq | CompareOp::Ne, None) => Some(FilterExpr::Text {
+                value: operand.to_string(),
+                negated: op == CompareOp::Ne,
+            }),
+            _ => None,
+        };
+    }
+    if let Some((low, high)) = text.split_once('~') {
+        let low = parse_numeric_value(low)?;
+        let high = parse_numeric_value(high)?;
+        return Some(FilterExpr::Between(low.min(high), low.max(high)));
+    }
+    Some(match parse_numeric_value(&text) {
+        Some(number) => FilterExpr::Compare(CompareOp::Eq, number),
+        None => FilterExpr::Text {
+            value: text,
+            negated: false,
+        },
+    })
+}
+
+impl FilterExpr {
+    /// Whether a row whose cell holds `cell` is kept. `!=` keeps blank and text cells, as
+    /// they are not equal to the number either.
+    pub fn matches(&self, cell: &str) -> bool {
+        let number = || parse_numeric_value(cell);
+        match self {
+            FilterExpr::Compare(CompareOp::Ne, value) => number() != Some(*value),
+            FilterExpr::Compare(op, value) => {
+                number().is_some_and(|number| op.holds(number, *value))
+            }
+            FilterExpr::Between(low, high) => {
+                number().is_some_and(|number| (*low..=*high).contains(&number))
+            }
+            FilterExpr::Text { value, negated } => (cell.trim() == value) != *negated,
+            FilterExpr::Empty => cell.trim().is_empty(),
+            FilterExpr::NotEmpty => !cell.trim().is_empty(),
+        }
+    }
+}
@@ -0,0 +1,244 @@
+//! Everything known about one 代號, gathered from the datasets that mention it: its rows
+//! in the 持股 tables, the dividends those rows record, its stored prices, the note kept
+//! for it and the rows of any other table listing it, such as a sheet of trades.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+
+use crate::dividend_year::live_datasets;
+use crate::domain::entities::dataset::{DatasetId, DatasetKind};
+use crate::domain::entities::price_point::PricePoint;
+use crate::holdings::parse_year_header;
+use crate::numeric::{parse_numeric_value, row_value};
+use crate::prices::CODE_COLUMN;
+use crate::trade_costs::{ledger_trades, position_cost, FeeSchedule, Oversold, PositionCost};
+use crate::usecase::services::query_service::QueryService;
+
+/// Settings key prefix of the note kept for a 代號; the 代號 follows it.
+pub const HOLDING_NOTE_KEY_PREFIX: &str = "holding_note:";
+
+/// 持股 columns shown in a position summary, in this order, when the table has them.
+pub const POSITION_FIELDS: [&str; 12] = [
+    "所有權人",
+    "類別",
+    "性質",
+    "數量",
+    "買進",
+    "市價",
+    "總成本",
+    "淨值",
+    "資本利得",
+    "損益率",
+    "估計配息",
+    "估計殖利率",
+];
+
+const NAME_COLUMN: &str = "名稱";
+const NOTE_COLUMN: &str = "備註";
+
+/// A row of a 持股 table holding the 代號.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HoldingPosition {
+    pub dataset_id: DatasetId,
+    pub dataset_name: String,
+    pub row_idx: usize,
+    /// The [`POSITION_FIELDS`] the table has, with the row's values.
+    pub fields: Vec<(String, String)>,
+}
+
+/// Dividends of one archived year or one month of the open year, summed over positions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DividendPeriod {
+    pub label: String,
+    pub amount: f64,
+}
+
+/// The rows of a table other than 持股 that list the 代號, by row index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelatedRows {
+    pub dataset_id: DatasetId,
+    pub dataset_name: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<(usize, Vec<String>)>,
+    /// What the rows add up to when the table is a trade ledger, at the standard broker
+    /// fees; see [`ledger_trades`].
+    pub trade_cost: Option<Result<PositionCost, Oversold>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HoldingDetail {
+    pub code: String,
+    /// 名稱 of the first position; empty when no 持股 table lists the 代號.
+    pub name: String,
+    pub positions: Vec<HoldingPosition>,
+    pub dividends: Vec<DividendPeriod>,
+    /// Oldest first.
+    pub prices: Vec<PricePoint>,
+    /// Non-empty 備註 cells of the positions.
+    pub remarks: Vec<String>,
+    /// The note kept for the 代號 in BOM itself.
+    pub note: String,
+    pub related: Vec<RelatedRows>,
+}
+
+/// Every 代號 of the stored 持股 tables with the 名稱 it is first listed under, sorted by
+/// 代號.
+pub fn holding_codes(query: &QueryService) -> Result<Vec<(String, String)>> {
+    let mut codes = BTreeMap::new();
+    for dataset in live_datasets(&query.list_datasets(false)?) {
+        if dataset.effective_kind() != DatasetKind::Holdings {
+            continue;
+        }
+        let page = query.load_tabular_data(dataset.id)?;
+        let position = |name: &str| page.columns.iter().position(|column| column == name);
+        let Some(code_idx) = position(CODE_COLUMN) else {
+            continue;
+        };
+        for row in &page.rows {
+            let code = row_value(row, code_idx).trim().to_string();
+            if code.is_empty() {
+                continue;
+            }
+            let name = position(NAME_COLUMN)
+                .map(|idx| row_value(row, idx))
+                .unwrap_or_default();
+            codes.entry(code).or_insert(name);
+        }
+    }
+    Ok(codes.into_iter().collect())
+}
+
+/// Reads what every stored dataset knows about `code`.
+pub fn collect_holding_detail(query: &QueryService, code: &str) -> Result<HoldingDetail> {
+    let code = code.trim();
+    let mut detail = HoldingDetail {
+        code: code.to_string(),
+        ..HoldingDetail::default()
+    };
+    let mut dividend_rows = Vec::new();
+    for dataset in live_datasets(&query.list_datasets(false)?) {
+        let page = query.load_tabular_data(dataset.id)?;
+        let position = |name: &str| page.columns.iter().position(|column| column == name);
+        let Some(code_idx) = position(CODE_COLUMN) else {
+            continue;
+        };
+        let matching = page
+            .rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| row_value(row, code_idx).trim() == code)
+            .collect::<Vec<_>>();
+        if matching.is_empty() {
+            continue;
+        }
+        if dataset.effective_kind() != DatasetKind::Holdings {
+            let rows = matching
+                .into_iter()
+                .map(|(row_idx, row)| (row_idx, row.clone()))
+                .collect::<Vec<_>>();
+            let cells = rows.iter().map(|(_, row)| row.clone()).collect::<Vec<_>>();
+            detail.related.push(RelatedRows {
+                dataset_id: dataset.id,
+                dataset_name: dataset.name.clone(),
+                columns: page.columns.clone(),
+                rows,
+                trade_cost: ledger_trades(&page.columns, &cells)
+                    .map(|trades| position_cost(&trades, &FeeSchedule::default())),
+            });
+            continue;
+        }
+        for (row_idx, row) in matching {
+            if detail.name.is_empty() {
+                if let Some(idx) = position(NAME_COLUMN) {
+                    detail.name = row_value(row, idx).trim().to_string();
+                }
+            }
+            if let Some(remark) = position(NOTE_COLUMN)
+                .map(|idx| row_value(row, idx).trim().to_string())
+                .filter(|remark| !remark.is_empty())
+            {
+                detail.remarks.push(remark);
+            }
+            detail.positions.push(HoldingPosition {
+                dataset_id: dataset.id,
+                dataset_name: dataset.name.clone(),
+                row_idx,
+                fields: POSITION_FIELDS
+                    .iter()
+                    .filter_map(|field| {
+                        let idx = position(field)?;
+                        Some((field.to_string(), row_value(row, idx)))
+                    })
+                    .collect(),
+            });
+            dividend_rows.push((page.columns.clone(), row.clone()));
+        }
+    }
+    detail.dividends = dividend_history(&dividend_rows);
+    detail.prices = query
+        .load_price_history(vec![code.to_string()])?
+        .remove(code)
+        .unwrap_or_default();
+    detail.note = query
+        .load_setting(&holding_note_key(code))?
+        .unwrap_or_default();
+    Ok(detail)
+}
+
+/// Keeps `note` for `code`, replacing the one kept before.
+pub fn save_holding_note(query: &QueryService, code: &str, note: &str) -> Result<()> {
+    query.save_setting(&holding_note_key(code.trim()), note.trim())?;
+    Ok(())
+}
+
+/// Dividends recorded by 持股股息總表 rows, each given with its table's headers: the
+/// archived `YYYY年` columns oldest first, then the months of the open year that hold a
+/// value. Amounts of the same period are added up across rows.
+pub fn dividend_history(rows: &[(Vec<String>, Vec<String>)]) -> Vec<DividendPeriod> {
+    let mut years = BTreeMap::<i32, f64>::new();
+    let mut months = BTreeMap::<(i32, u32), f64>::new();
+    for (headers, row) in rows {
+        let open_year = headers
+            .iter()
+            .filter_map(|header| parse_year_header(header))
+            .max()
+            .map(|year| year + 1);
+        for (idx, header) in headers.iter().enumerate() {
+            let Some(amount) = parse_numeric_value(&row_value(row, idx)) else {
+                continue;
+            };
+            if let Some(year) = parse_year_header(header) {
+                *years.entry(year).or_default() += amount;
+            } else if let Some(month) = header
+                .trim()
+                .strip_suffix('月')
+                .and_then(|month| month.parse::<u32>().ok())
+                .filter(|month| (1..=12).contains(month))
+            {
+                *months
+                    .entry((open_year.unwrap_or_default(), month))
+                    .or_default() += amount;
+            }
+        }
+    }
+    let years = years.into_iter().map(|(year, amount)| DividendPeriod {
+        label: format!("{year}年"),
+        amount,
+    });
+    let months = months
+        .into_iter()
+        .map(|((year, month), amount)| DividendPeriod {
+            label: if year == 0 {
+                format!("{month}月")
+            } else {
+                format!("{year}年{month}月")
+            },
+            amount,
+        });
+    years.chain(months).collect()
+}
+
+fn holding_note_key(code: &str) -> String {
+    format!("{HOLDING_NOTE_KEY_PREFIX}{code}")
+}
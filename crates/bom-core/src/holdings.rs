@@ -0,0 +1,882 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+
+use crate::domain::entities::column_set::ColumnSet;
+use crate::domain::entities::dataset::DatasetKind;
+use crate::numeric::{
+    format_f64, format_ratio_or_na, is_summary_label, parse_f64, parse_frequency,
+    parse_numeric_value, row_value, safe_div,
+};
+
+#[derive(Clone, Debug, Default)]
+pub struct HoldingDerived {
+    pub buy_price: f64,
+    pub market_price: f64,
+    pub quantity: f64,
+    pub estimated_dividend: f64,
+}
+
+pub struct HoldingsTransform {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    pub by_code: HashMap<String, HoldingDerived>,
+    pub total_cost: f64,
+    pub total_net: f64,
+    /// 代號 the sheet lists more than once, whichever way they were handled.
+    pub duplicates: Vec<DuplicateCode>,
+    /// The 持股明細 cell each output cell was copied from.
+    pub sources: SourceCells,
+}
+
+/// For each cell of a transform's output, the `(row, column)` of the input cell it was
+/// copied from. Cells the transform computed have `None`.
+pub type SourceCells = Vec<Vec<Option<(usize, usize)>>>;
+
+/// What the import does with 持股明細 rows that share a 代號, which is usually a
+/// data-entry error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateCodePolicy {
+    /// Import every row as it is.
+    #[default]
+    KeepBoth,
+    /// One row per 代號: the first row, with the quantities added up and 買進 averaged
+    /// over them.
+    Merge,
+}
+
+/// A 代號 on more than one row of 持股明細, with the 名稱 of each of those rows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateCode {
+    pub code: String,
+    pub names: Vec<String>,
+}
+
+const HOLDINGS_NAME_COL: usize = 1;
+const HOLDINGS_CODE_COL: usize = 5;
+const HOLDINGS_BUY_COL: usize = 6;
+const HOLDINGS_QTY_COL: usize = 8;
+
+/// 持股明細 columns copied into the leading columns of the holdings output, 名稱 through
+/// 最新配息.
+const HOLDINGS_COPIED_COLUMNS: [usize; 11] = [
+    HOLDINGS_NAME_COL,
+    2,
+    3,
+    4,
+    HOLDINGS_CODE_COL,
+    HOLDINGS_BUY_COL,
+    7,
+    HOLDINGS_QTY_COL,
+    18,
+    21,
+    22,
+];
+
+/// Whether a 持股明細 row is a holding rather than a blank or summary line.
+fn is_holding_row(row: &[String]) -> bool {
+    let name = row_value(row, HOLDINGS_NAME_COL);
+    !name.trim().is_empty() && !is_summary_label(&name)
+}
+
+/// 代號 listed on more than one holding row of 持股明細, in sheet order.
+pub fn find_duplicate_codes(rows: &[Vec<String>]) -> Vec<DuplicateCode> {
+    let mut found: Vec<DuplicateCode> = Vec::new();
+    for row in rows.iter().filter(|row| is_holding_row(row)) {
+        let code = row_value(row, HOLDINGS_CODE_COL).trim().to_string();
+        if code.is_empty() {
+            continue;
+        }
+        let name = row_value(row, HOLDINGS_NAME_COL);
+        match found.iter_mut().find(|duplicate| duplicate.code == code) {
+            Some(duplicate) => duplicate.names.push(name),
+            None => found.push(DuplicateCode {
+                code,
+                names: vec![name],
+            }),
+        }
+    }
+    found.retain(|duplicate| duplicate.names.len() > 1);
+    found
+}
+
+/// `rows` with every later row of a 代號 folded into its first one, see
+/// [`DuplicateCodePolicy::Merge`]. Also returns, for each remaining row, its index in
+/// `rows` and whether later rows were folded into it.
+fn merge_duplicate_codes(rows: &[Vec<String>]) -> (Vec<Vec<String>>, Vec<(usize, bool)>) {
+    let mut merged: Vec<Vec<String>> = Vec::with_capacity(rows.len());
+    let mut kept: Vec<(usize, bool)> = Vec::with_capacity(rows.len());
+    let mut first_by_code: HashMap<String, usize> = HashMap::new();
+    for (row_idx, row) in rows.iter().enumerate() {
+        let code = row_value(row, HOLDINGS_CODE_COL).trim().to_string();
+        let first = if is_holding_row(row) && !code.is_empty() {
+            match first_by_code.get(&code) {
+                Some(first) => Some(*first),
+                None => {
+                    first_by_code.insert(code, merged.len());
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let Some(first) = first else {
+            merged.push(row.clone());
+            kept.push((row_idx, false));
+            continue;
+        };
+        kept[first].1 = true;
+        let target = &mut merged[first];
+        let kept_qty = parse_f64(&row_value(target, HOLDINGS_QTY_COL));
+        let added_qty = parse_f64(&row_value(row, HOLDINGS_QTY_COL));
+        let total_qty = kept_qty + added_qty;
+        let total_cost = parse_f64(&row_value(target, HOLDINGS_BUY_COL)) * kept_qty
+            + parse_f64(&row_value(row, HOLDINGS_BUY_COL)) * added_qty;
+        if target.len() <= HOLDINGS_QTY_COL {
+            target.resize(HOLDINGS_QTY_COL + 1, String::new());
+        }
+        target[HOLDINGS_QTY_COL] = format_f64(total_qty);
+        target[HOLDINGS_BUY_COL] = format_f64(safe_div(total_cost, total_qty));
+    }
+    (merged, kept)
+}
+
+/// Computes 持股股息總表 from the 持股明細 sheet. Duplicate 代號 are reported in
+/// [`HoldingsTransform::duplicates`] and handled as `duplicates` says.
+pub fn transform_holdings_sheet(
+    rows: &[Vec<String>],
+    duplicates: DuplicateCodePolicy,
+) -> HoldingsTransform {
+    let found_duplicates = find_duplicate_codes(rows);
+    let merged;
+    let (rows, source_rows) = match duplicates {
+        DuplicateCodePolicy::Merge if !found_duplicates.is_empty() => {
+            let source_rows;
+            (merged, source_rows) = merge_duplicate_codes(rows);
+            (merged.as_slice(), source_rows)
+        }
+        _ => (
+            rows,
+            (0..rows.len()).map(|row_idx| (row_idx, false)).collect(),
+        ),
+    };
+    let headers = vec![
+        "名稱".to_string(),
+        "類別".to_string(),
+        "性質".to_string(),
+        "國內 /國外".to_string(),
+        "代號".to_string(),
+        "買進".to_string(),
+        "市價".to_string(),
+        "數量".to_string(),
+        "年配息".to_string(),
+        "配息頻率".to_string(),
+        "最新配息".to_string(),
+        "總成本".to_string(),
+        "資本利得".to_string(),
+        "損益率".to_string(),
+        "淨值".to_string(),
+        "已收配息".to_string(),
+        "總損益".to_string(),
+        "報酬率".to_string(),
+        "估計配息".to_string(),
+        "估計殖利率".to_string(),
+        "最新殖利率".to_string(),
+        "最新領息".to_string(),
+        "差異".to_string(),
+        "股票成本".to_string(),
+        "股票淨值".to_string(),
+        "債券成本".to_string(),
+        "債券淨值".to_string(),
+        "最新股息".to_string(),
+        "最新債息".to_string(),
+    ];
+
+    let mut output = Vec::new();
+    let mut sources = Vec::new();
+    let mut by_code = HashMap::new();
+    let mut total_cost_sum = 0.0;
+    let mut total_net_sum = 0.0;
+
+    for (row, (source_row, folded)) in rows.iter().zip(source_rows) {
+        if !is_holding_row(row) {
+            continue;
+        }
+        // 買進 and 數量 of a merged row are worked out from several rows.
+        let mut row_sources: Vec<_> = HOLDINGS_COPIED_COLUMNS
+            .iter()
+            .map(|&col| {
+                let recomputed = folded && (col == HOLDINGS_BUY_COL || col == HOLDINGS_QTY_COL);
+                (!recomputed).then_some((source_row, col))
+            })
+            .collect();
+        row_sources.resize(headers.len(), None);
+        sources.push(row_sources);
+        let name = row_value(row, HOLDINGS_NAME_COL);
+        let category = row_value(row, 2);
+        let asset_kind = row_value(row, 3);
+        let market = row_value(row, 4);
+        let code = row_value(row, HOLDINGS_CODE_COL);
+        let buy = parse_f64(&row_value(row, HOLDINGS_BUY_COL));
+        let price = parse_f64(&row_value(row, 7));
+        let qty = parse_f64(&row_value(row, HOLDINGS_QTY_COL));
+        let annual_dividend = parse_f64(&row_value(row, 18));
+        let freq = parse_frequency(&row_value(row, 21));
+        let latest_dividend = parse_f64(&row_value(row, 22));
+
+        let total_cost = buy * qty;
+        let capital_gain = (price - buy) * qty;
+        let net_value = total_cost + capital_gain;
+        let received_dividend = 0.0;
+        let total_gain = capital_gain + received_dividend;
+        let estimated_dividend = annual_dividend * qty;
+        let estimated_yield = safe_div(estimated_dividend, total_cost);
+        let latest_yield = safe_div(latest_dividend * freq, price);
+        let latest_income = latest_dividend * freq * qty;
+        let diff = latest_yield - estimated_yield;
+
+        let is_stock = asset_kind.contains('股');
+        let is_bond = asset_kind.contains('債');
+
+        total_cost_sum += total_cost;
+        total_net_sum += net_value;
+
+        by_code.insert(
+            code.clone(),
+            HoldingDerived {
+                buy_price: buy,
+                market_price: price,
+                quantity: qty,
+                estimated_dividend,
+            },
+        );
+
+        output.push(vec![
+            name,
+            category,
+            asset_kind,
+            market,
+            code,
+            format_f64(buy),
+            format_f64(price),
+            format_f64(qty),
+            format_f64(annual_dividend),
+            format_f64(freq),
+            format_f64(latest_dividend),
+            format_f64(total_cost),
+            format_f64(capital_gain),
+            format_ratio_or_na(capital_gain, total_cost),
+            format_f64(net_value),
+            format_f64(received_dividend),
+            format_f64(total_gain),
+            format_ratio_or_na(total_gain, total_cost),
+            format_f64(estimated_dividend),
+            format_ratio_or_na(estimated_dividend, total_cost),
+            format_ratio_or_na(latest_dividend * freq, price),
+            format_f64(latest_income),
+            format_f64(diff),
+            format_f64(if is_stock { total_cost } else { 0.0 }),
+            format_f64(if is_stock { net_value } else { 0.0 }),
+            format_f64(if is_bond { total_cost } else { 0.0 }),
+            format_f64(if is_bond { net_value } else { 0.0 }),
+            format_f64(if is_stock { latest_income } else { 0.0 }),
+            format_f64(if is_bond { latest_income } else { 0.0 }),
+        ]);
+    }
+
+    HoldingsTransform {
+        headers,
+        rows: output,
+        by_code,
+        total_cost: total_cost_sum,
+        total_net: total_net_sum,
+        duplicates: found_duplicates,
+        sources,
+    }
+}
+
+pub fn transform_assets_sheet(
+    rows: &[Vec<String>],
+    holdings_total_cost: f64,
+    holdings_total_net: f64,
+) -> (Vec<String>, Vec<Vec<String>>, SourceCells) {
+    let headers = vec![
+        "資產形式".to_string(),
+        "所有權人".to_string(),
+        "往來機構".to_string(),
+        "帳號".to_string(),
+        "幣別".to_string(),
+        "餘額".to_string(),
+        "交割款".to_string(),
+    ];
+
+    let mut output = Vec::new();
+    let mut sources = Vec::new();
+    for (row_idx, row) in rows.iter().enumerate() {
+        let asset_form = row_value(row, 0);
+        if asset_form.trim().is_empty()
+            || is_summary_label(&asset_form)
+            || asset_form.trim() == "交割款"
+        {
+            continue;
+        }
+        let owner = row_value(row, 1);
+        let institution = row_value(row, 2);
+        let account = row_value(row, 3);
+        let currency = row_value(row, 4);
+        if owner.trim().is_empty()
+            || institution.trim().is_empty()
+            || account.trim().is_empty()
+            || currency.trim().is_empty()
+        {
+            continue;
+        }
+        let balance_raw = row_value(row, 5);
+        let Some(balance_value) = parse_numeric_value(&balance_raw) else {
+            continue;
+        };
+        let mut cost = balance_value;
+        let is_investment = is_investment_asset(&asset_form);
+        if is_investment {
+            cost = holdings_total_cost;
+        }
+        let balance = if is_investment {
+            holdings_total_net
+        } else {
+            cost
+        };
+        let settlement = String::new();
+
+        output.push(vec![
+            asset_form,
+            owner,
+            institution,
+            account,
+            currency,
+            format_f64(balance),
+            settlement,
+        ]);
+        // 餘額 keeps pointing at the typed amount, also where the holdings replaced it.
+        let mut row_sources: Vec<_> = (0..=5).map(|col| Some((row_idx, col))).collect();
+        row_sources.push(None);
+        sources.push(row_sources);
+    }
+
+    (headers, output, sources)
+}
+
+/// 資產形式 values whose 餘額 is the 淨值 of the holdings rather than a typed amount.
+pub(crate) fn is_investment_asset(asset_form: &str) -> bool {
+    asset_form.contains("投資") || asset_form.contains("股票")
+}
+
+/// Sum of 淨值 over stored 持股股息總表 rows; `None` when there is no 淨值 column.
+pub fn holdings_total_net(columns: &[String], rows: &[Vec<String>]) -> Option<f64> {
+    let net_idx = columns.iter().position(|column| column == "淨值")?;
+    Some(
+        rows.iter()
+            .filter_map(|row| parse_numeric_value(&row_value(row, net_idx)))
+            .sum(),
+    )
+}
+
+/// The 餘額 cells of a stored 資產總表 that no longer match `holdings_total_net`, as
+/// `(row, column, new value)`. Like the import, only investment lines follow the holdings.
+pub fn investment_balance_updates(
+    columns: &[String],
+    rows: &[Vec<String>],
+    holdings_total_net: f64,
+) -> Vec<(usize, usize, String)> {
+    let position = |name: &str| columns.iter().position(|column| column == name);
+    let (Some(form_idx), Some(balance_idx)) = (position("資產形式"), position("餘額")) else {
+        return Vec::new();
+    };
+    let balance = format_f64(holdings_total_net);
+    rows.iter()
+        .enumerate()
+        .filter(|(_, row)| is_investment_asset(&row_value(row, form_idx)))
+        .filter(|(_, row)| row_value(row, balance_idx) != balance)
+        .map(|(row_idx, _)| (row_idx, balance_idx, balance.clone()))
+        .collect()
+}
+
+/// Where 股息收入明細表 keeps each field. Detected from the header row so inserted or
+/// moved columns keep importing; a sheet without a recognisable header falls back to the
+/// original workbook layout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DividendColumns {
+    pub name: usize,
+    pub kind: usize,
+    pub code: usize,
+    pub owner: usize,
+    pub payout_method: usize,
+    pub periods: usize,
+    pub last_year: usize,
+    pub prev_total: usize,
+    /// 1月 through 12月.
+    pub months: [usize; 12],
+}
+
+impl DividendColumns {
+    /// Column positions of the original BOM workbook.
+    pub const LEGACY: DividendColumns = DividendColumns {
+        name: 0,
+        kind: 1,
+        code: 2,
+        owner: 9,
+        payout_method: 10,
+        periods: 11,
+        last_year: 14,
+        prev_total: 16,
+        months: [22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33],
+    };
+
+    /// How many leading rows are searched for the header.
+    const HEADER_SEARCH_ROWS: usize = 5;
+
+    /// Finds the header row (the first one naming all twelve months) and returns the
+    /// layout with the index of that row. Fields whose header is missing keep their
+    /// legacy position.
+    pub fn detect(rows: &[Vec<String>]) -> Option<(DividendColumns, usize)> {
+        rows.iter()
+            .take(Self::HEADER_SEARCH_ROWS)
+            .enumerate()
+            .find_map(|(row_idx, row)| Self::from_headers(row).map(|layout| (layout, row_idx)))
+    }
+
+    pub fn from_headers(headers: &[String]) -> Option<DividendColumns> {
+        let mut months = [None; 12];
+        for (idx, header) in headers.iter().enumerate() {
+            if let Some(month) = month_header(header) {
+                months[month - 1].get_or_insert(idx);
+            }
+        }
+        if months.iter().any(Option::is_none) {
+            return None;
+        }
+        let find = |names: &[&str], fallback: usize| {
+            headers
+                .iter()
+                .position(|header| names.contains(&header.trim()))
+                .unwrap_or(fallback)
+        };
+        let legacy = Self::LEGACY;
+        Some(DividendColumns {
+            name: find(&["名稱", "基金名稱", "股票名稱"], legacy.name),
+            kind: find(&["性質"], legacy.kind),
+            code: find(&["代號", "代碼"], legacy.code),
+            owner: find(&["所有權人", "持有人"], legacy.owner),
+            payout_method: find(&["配息方式"], legacy.payout_method),
+            periods: find(&["期數", "配息期數"], legacy.periods),
+            last_year: headers
+                .iter()
+                .position(|header| parse_year_header(header).is_some())
+                .unwrap_or(legacy.last_year),
+            prev_total: find(&["去年度累積"], legacy.prev_total),
+            months: months.map(|idx| idx.unwrap_or_default()),
+        })
+    }
+}
+
+/// `1月` … `12月` (spaces and a leading zero allowed).
+fn month_header(header: &str) -> Option<usize> {
+    let month = header
+        .trim()
+        .strip_suffix('月')?
+        .trim()
+        .parse::<usize>()
+        .ok()?;
+    (1..=12).contains(&month).then_some(month)
+}
+
+/// The year of a calendar-year column such as `2023年`.
+pub fn parse_year_header(header: &str) -> Option<i32> {
+    let year = header.trim().strip_suffix('年')?;
+    if year.len() != 4 || !year.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    year.parse().ok()
+}
+
+pub fn year_header(year: i32) -> String {
+    format!("{year}年")
+}
+
+/// Year of the oldest archived column when a sheet names none.
+const DEFAULT_ARCHIVED_YEAR: i32 = 2023;
+
+pub fn transform_dividend_sheet(
+    rows: &[Vec<String>],
+    by_code: &HashMap<String, HoldingDerived>,
+) -> (Vec<String>, Vec<Vec<String>>, SourceCells) {
+    // Without a recognisable header the first row is still the workbook's header.
+    let detected = DividendColumns::detect(rows);
+    let archived_year = detected
+        .as_ref()
+        .and_then(|(columns, header_row)| {
+            parse_year_header(&row_value(&rows[*header_row], columns.last_year))
+        })
+        .unwrap_or(DEFAULT_ARCHIVED_YEAR);
+    let (columns, header_row) = detected.unwrap_or((DividendColumns::LEGACY, 0));
+
+    let mut headers = vec![
+        "名稱".to_string(),
+        "性質".to_string(),
+        "代號".to_string(),
+        "所有權人".to_string(),
+        "配息方式".to_string(),
+        "期數".to_string(),
+        year_header(archived_year),
+        "去年度累積".to_string(),
+    ];
+    headers.extend((1..=12).map(|month| format!("{month}月")));
+    headers.extend(
+        [
+            "買入價",
+            "市價",
+            "股數",
+            "原始投入金額",
+            "債",
+            "股",
+            "估計配息金額",
+            "殖利率",
+        ]
+        .map(String::from),
+    );
+    headers.push(year_header(archived_year + 1));
+    headers.extend(
+        [
+            "今年度累積",
+            "總累積",
+            "預估累積",
+            "預算實際差異",
+            "累計殖利率",
+        ]
+        .map(String::from),
+    );
+
+    let copied_columns = [
+        columns.name,
+        columns.kind,
+        columns.code,
+        columns.owner,
+        columns.payout_method,
+        columns.periods,
+        columns.last_year,
+        columns.prev_total,
+    ]
+    .into_iter()
+    .chain(columns.months)
+    .collect::<Vec<_>>();
+
+    let mut output = Vec::new();
+    let mut sources = Vec::new();
+    for (row_idx, row) in rows.iter().enumerate().skip(header_row + 1) {
+        let name = row_value(row, columns.name);
+        if name.trim().is_empty() || is_summary_label(&name) {
+            continue;
+        }
+        let mut row_sources: Vec<_> = copied_columns
+            .iter()
+            .map(|&col| Some((row_idx, col)))
+            .collect();
+        row_sources.resize(headers.len(), None);
+        sources.push(row_sources);
+        let asset_kind = row_value(row, columns.kind);
+        let code = row_value(row, columns.code);
+        let owner = row_value(row, columns.owner);
+        let payout_method = row_value(row, columns.payout_method);
+        let periods = parse_f64(&row_value(row, columns.periods));
+        let archived = parse_f64(&row_value(row, columns.last_year));
+        let prev_total = parse_f64(&row_value(row, columns.prev_total));
+
+        let months = columns
+            .months
+            .iter()
+            .map(|idx| parse_f64(&row_value(row, *idx)))
+            .collect::<Vec<_>>();
+        let current_total: f64 = months.iter().sum();
+
+        let hold = by_code.get(&code).cloned().unwrap_or_default();
+        let principal = hold.buy_price * hold.quantity;
+        let debt = if asset_kind.contains('債') {
+            principal
+        } else {
+            0.0
+        };
+        let stock = if asset_kind.contains('股') {
+            principal
+        } else {
+            0.0
+        };
+        let estimated = hold.estimated_dividend;
+        let last_year_total = prev_total - archived;
+        let total = prev_total + current_total;
+        let expected = estimated;
+        let variance = current_total - expected;
+
+        let mut result = vec![
+            name,
+            asset_kind,
+            code,
+            owner,
+            payout_method,
+            format_f64(periods),
+            format_f64(archived),
+            format_f64(prev_total),
+        ];
+        for month in months {
+            result.push(format_f64(month));
+        }
+        result.extend_from_slice(&[
+            format_f64(hold.buy_price),
+            format_f64(hold.market_price),
+            format_f64(hold.quantity),
+            format_f64(principal),
+            format_f64(debt),
+            format_f64(stock),
+            format_f64(estimated),
+            format_ratio_or_na(estimated, principal),
+            format_f64(last_year_total),
+            format_f64(current_total),
+            format_f64(total),
+            format_f64(expected),
+            format_f64(variance),
+            format_ratio_or_na(total, principal),
+        ]);
+
+        output.push(result);
+    }
+
+    (headers, output, sources)
+}
+
+/// Columns of [`transform_dividend_sheet`] carried into 持股股息總表: 所有權人 through
+/// 12月, then the year-to-date figures from the closed year onwards.
+const MERGED_DIVIDEND_COLUMNS: [usize; 23] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 28, 29, 30, 31, 32, 33,
+];
+
+/// A column of 持股股息總表 and the transformed sheet it was taken from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergedColumn {
+    /// A column of the holdings rows.
+    Holdings(usize),
+    /// A column of the rows of [`transform_dividend_sheet`].
+    Dividends(usize),
+}
+
+/// Where the rows and columns of 持股股息總表 were taken from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergedLayout {
+    /// For each merged row, its holdings row and the dividend row joined to it, if any.
+    pub rows: Vec<(usize, Option<usize>)>,
+    pub columns: Vec<MergedColumn>,
+}
+
+pub fn merge_holdings_and_dividends(
+    holdings_headers: Vec<String>,
+    holdings_rows: Vec<Vec<String>>,
+    dividend_headers: &[String],
+    dividend_rows: &[Vec<String>],
+) -> (Vec<String>, Vec<Vec<String>>, MergedLayout) {
+    let holdings_width = holdings_headers.len();
+    let mut merged_headers = holdings_headers;
+    merged_headers.extend(
+        MERGED_DIVIDEND_COLUMNS
+            .iter()
+            .map(|idx| row_value(dividend_headers, *idx)),
+    );
+
+    let mut dividend_by_code: HashMap<String, Vec<usize>> = HashMap::new();
+    for (row_idx, row) in dividend_rows.iter().enumerate() {
+        let code = row_value(row, 2);
+        if code.trim().is_empty() {
+            continue;
+        }
+        dividend_by_code.entry(code).or_default().push(row_idx);
+    }
+
+    let mut merged_rows = Vec::new();
+    let mut row_layout = Vec::new();
+    for (holdings_idx, row) in holdings_rows.into_iter().enumerate() {
+        let code = row_value(&row, 4);
+        if let Some(divs) = dividend_by_code.get(&code) {
+            for &div_idx in divs {
+                let mut merged = row.clone();
+                merged.extend(
+                    MERGED_DIVIDEND_COLUMNS
+                        .iter()
+                        .map(|idx| row_value(&dividend_rows[div_idx], *idx)),
+                );
+                merged_rows.push(merged);
+                row_layout.push((holdings_idx, Some(div_idx)));
+            }
+        } else {
+            let mut merged = row;
+            merged.extend(std::iter::repeat_n(
+                String::new(),
+                MERGED_DIVIDEND_COLUMNS.len(),
+            ));
+            merged_rows.push(merged);
+            row_layout.push((holdings_idx, None));
+        }
+    }
+
+    let preferred_order = [
+        "所有權人",
+        "名稱",
+        "類別",
+        "性質",
+        "國內 /國外",
+        "代號",
+        "買進",
+        "市價",
+        "數量",
+        "配息方式",
+        "期數",
+    ];
+    let columns = reorder_indices(&merged_headers, &preferred_order)
+        .into_iter()
+        .map(|idx| match idx.checked_sub(holdings_width) {
+            Some(dividend_idx) => MergedColumn::Dividends(MERGED_DIVIDEND_COLUMNS[dividend_idx]),
+            None => MergedColumn::Holdings(idx),
+        })
+        .collect();
+    let (headers, rows) = reorder_headers_and_rows(&merged_headers, &merged_rows, &preferred_order);
+    (
+        headers,
+        rows,
+        MergedLayout {
+            rows: row_layout,
+            columns,
+        },
+    )
+}
+
+pub fn reorder_headers_and_rows(
+    headers: &[String],
+    rows: &[Vec<String>],
+    preferred_order: &[&str],
+) -> (Vec<String>, Vec<Vec<String>>) {
+    let indices = reorder_indices(headers, preferred_order);
+    let new_headers = indices.iter().map(|&idx| headers[idx].clone()).collect();
+    let mut new_rows = Vec::with_capacity(rows.len());
+    for row in rows {
+        let mut reordered = Vec::with_capacity(indices.len());
+        for &idx in &indices {
+            reordered.push(row.get(idx).cloned().unwrap_or_default());
+        }
+        new_rows.push(reordered);
+    }
+
+    (new_headers, new_rows)
+}
+
+/// The order [`reorder_headers_and_rows`] puts `headers` in, as indices into them.
+fn reorder_indices(headers: &[String], preferred_order: &[&str]) -> Vec<usize> {
+    let mut indices = Vec::new();
+    let mut used = vec![false; headers.len()];
+
+    for &name in preferred_order {
+        if let Some((idx, _)) = headers
+            .iter()
+            .enumerate()
+            .find(|(_, header)| header.as_str() == name)
+        {
+            indices.push(idx);
+            used[idx] = true;
+        }
+    }
+
+    for (idx, _) in headers.iter().enumerate() {
+        if !used[idx] {
+            indices.push(idx);
+        }
+    }
+    indices
+}
+
+/// Built-in required columns of 持股; the set saved in 設定 takes their place.
+pub fn required_columns_for_holdings() -> Vec<String> {
+    vec![
+        "所有權人".to_string(),
+        "名稱".to_string(),
+        "類別".to_string(),
+        "性質".to_string(),
+        "國內 /國外".to_string(),
+        "代號".to_string(),
+        "買進".to_string(),
+        "市價".to_string(),
+        "數量".to_string(),
+        "配息方式".to_string(),
+        "期數".to_string(),
+    ]
+}
+
+/// Built-in derived columns of 持股: what [`transform_holdings_sheet`] computes from the
+/// other columns rather than reads from the workbook.
+pub fn derived_columns_for_holdings() -> Vec<String> {
+    [
+        "總成本",
+        "資本利得",
+        "損益率",
+        "淨值",
+        "總損益",
+        "報酬率",
+        "估計配息",
+        "估計殖利率",
+        "最新殖利率",
+        "最新領息",
+        "差異",
+        "股票成本",
+        "股票淨值",
+        "債券成本",
+        "債券淨值",
+        "最新股息",
+        "最新債息",
+    ]
+    .into_iter()
+    .map(str::to_string)
+    .collect()
+}
+
+/// The column set of `kind` until one is saved: 持股 has its required and derived
+/// columns, the other types edit every column and require none.
+pub fn builtin_column_set(kind: DatasetKind) -> ColumnSet {
+    match kind {
+        DatasetKind::Holdings => ColumnSet {
+            required: required_columns_for_holdings(),
+            derived: derived_columns_for_holdings(),
+        },
+        DatasetKind::Assets | DatasetKind::Other => ColumnSet::default(),
+    }
+}
+
+/// Checks a column set before it is saved for `kind`: 持股 needs required columns to be
+/// recognised at all, and a column can't be both required and derived.
+pub fn validate_column_set(kind: DatasetKind, set: &ColumnSet) -> Result<()> {
+    if kind == DatasetKind::Holdings && set.required.is_empty() {
+        bail!("持股至少需要一個必填欄位");
+    }
+    if let Some(column) = set
+        .required
+        .iter()
+        .find(|column| set.derived.contains(column))
+    {
+        bail!("欄位不可同時為必填與衍生：{column}");
+    }
+    Ok(())
+}
+
+/// Column names written one per line, as in 設定; blank lines and repeats are dropped.
+pub fn parse_column_names(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let name = line.trim();
+        if !name.is_empty() && !names.iter().any(|known| known == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
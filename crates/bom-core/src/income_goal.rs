@@ -0,0 +1,167 @@
+//! The monthly passive income goal. The 持股 tables' 估計配息 is spread over the months
+//! and followed into the future in today's dollars: the target is set in what money buys
+//! now, so income paid years from now is discounted by the inflation rate before it is
+//! held against it.
+
+use anyhow::Result;
+
+use crate::dividend_year::live_datasets;
+use crate::domain::entities::dataset::DatasetKind;
+use crate::numeric::{
+    format_f64, format_percent, is_summary_label, parse_numeric_value, row_value,
+};
+use crate::usecase::services::query_service::QueryService;
+
+/// Holds the monthly target, in today's dollars.
+pub const MONTHLY_INCOME_TARGET_KEY: &str = "monthly_income_target";
+/// Holds the yearly inflation rate, as a ratio.
+pub const INFLATION_RATE_KEY: &str = "inflation_rate";
+pub const DEFAULT_INFLATION_RATE: f64 = 0.02;
+/// Years from now the projection lists.
+pub const PROJECTION_YEARS: [u32; 6] = [0, 1, 5, 10, 20, 30];
+
+/// Yearly income estimates of a 持股 table, in order of preference.
+const ESTIMATE_COLUMNS: [&str; 2] = ["估計配息", "估計配息金額"];
+const NAME_COLUMN: &str = "名稱";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IncomeGoal {
+    /// Passive income wanted a month, in today's dollars; `0` when none is set.
+    pub monthly_target: f64,
+    pub inflation_rate: f64,
+}
+
+impl Default for IncomeGoal {
+    fn default() -> Self {
+        Self {
+            monthly_target: 0.0,
+            inflation_rate: DEFAULT_INFLATION_RATE,
+        }
+    }
+}
+
+/// One year of the projection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IncomeProjectionRow {
+    pub years: u32,
+    /// Income a month as it will be paid.
+    pub nominal_monthly: f64,
+    /// The same income in today's dollars.
+    pub real_monthly: f64,
+}
+
+impl IncomeProjectionRow {
+    /// Share of the target the income covers in today's dollars; not given without one.
+    pub fn progress(&self, goal: &IncomeGoal) -> Option<f64> {
+        (goal.monthly_target > 0.0).then(|| self.real_monthly / goal.monthly_target)
+    }
+}
+
+pub fn load_income_goal(query: &QueryService) -> Result<IncomeGoal> {
+    let setting = |key: &str| -> Result<Option<f64>> {
+        Ok(query
+            .load_setting(key)?
+            .as_deref()
+            .and_then(parse_numeric_value))
+    };
+    let defaults = IncomeGoal::default();
+    Ok(IncomeGoal {
+        monthly_target: setting(MONTHLY_INCOME_TARGET_KEY)?
+            .filter(|target| *target >= 0.0)
+            .unwrap_or(defaults.monthly_target),
+        inflation_rate: setting(INFLATION_RATE_KEY)?
+            .filter(|rate| *rate > -1.0)
+            .unwrap_or(defaults.inflation_rate),
+    })
+}
+
+pub fn save_income_goal(query: &QueryService, goal: &IncomeGoal) -> Result<()> {
+    query.save_setting(MONTHLY_INCOME_TARGET_KEY, &format_f64(goal.monthly_target))?;
+    query.save_setting(INFLATION_RATE_KEY, &format_f64(goal.inflation_rate))?;
+    Ok(())
+}
+
+/// What `amount` paid `years` from now is worth today at `inflation_rate` a year.
+pub fn discount_to_today(amount: f64, inflation_rate: f64, years: f64) -> f64 {
+    amount / (1.0 + inflation_rate).powf(years)
+}
+
+/// What a value of `amount` today takes `years` from now at `inflation_rate` a year.
+pub fn inflate_from_today(amount: f64, inflation_rate: f64, years: f64) -> f64 {
+    amount * (1.0 + inflation_rate).powf(years)
+}
+
+/// The yearly 估計配息 of every stored 持股 table, subtotal rows left out, over twelve.
+/// 另存 copies and year archives hold the same holdings again and are not counted.
+pub fn estimated_monthly_income(query: &QueryService) -> Result<f64> {
+    let mut yearly = 0.0;
+    for dataset in live_datasets(&query.list_datasets(false)?) {
+        if dataset.effective_kind() != DatasetKind::Holdings {
+            continue;
+        }
+        let page = query.load_tabular_data(dataset.id)?;
+        let position = |name: &str| page.columns.iter().position(|column| column == name);
+        let Some(estimate_idx) = ESTIMATE_COLUMNS.into_iter().find_map(position) else {
+            continue;
+        };
+        let name_idx = position(NAME_COLUMN);
+        yearly += page
+            .rows
+            .iter()
+            .filter(|row| !name_idx.is_some_and(|idx| is_summary_label(&row_value(row, idx))))
+            .filter_map(|row| parse_numeric_value(&row_value(row, estimate_idx)))
+            .sum::<f64>();
+    }
+    Ok(yearly / 12.0)
+}
+
+/// `monthly_income` kept as it is paid now, for each of `years`, with its worth today.
+pub fn project_income(
+    monthly_income: f64,
+    goal: &IncomeGoal,
+    years: &[u32],
+) -> Vec<IncomeProjectionRow> {
+    years
+        .iter()
+        .map(|&years| IncomeProjectionRow {
+            years,
+            nominal_monthly: monthly_income,
+            real_monthly: discount_to_today(monthly_income, goal.inflation_rate, years.into()),
+        })
+        .collect()
+}
+
+/// The projection as a table: 年後, 每月收入, 今日幣值, and with a target the income it
+/// takes as paid then and the share of it reached.
+pub fn income_projection_table(
+    rows: &[IncomeProjectionRow],
+    goal: &IncomeGoal,
+) -> (Vec<String>, Vec<Vec<String>>) {
+    let has_target = goal.monthly_target > 0.0;
+    let mut headers = vec![
+        "年後".to_string(),
+        "每月收入".to_string(),
+        "今日幣值".to_string(),
+    ];
+    if has_target {
+        headers.extend(["目標（當年幣值）", "達成率"].map(String::from));
+    }
+    let table = rows
+        .iter()
+        .map(|row| {
+            let mut cells = vec![
+                row.years.to_string(),
+                format_f64(row.nominal_monthly.round()),
+                format_f64(row.real_monthly.round()),
+            ];
+            if has_target {
+                let target =
+                    inflate_from_today(goal.monthly_target, goal.inflation_rate, row.years.into());
+                cells.push(format_f64(target.round()));
+                cells.push(row.progress(goal).map(format_percent).unwrap_or_default());
+            }
+            cells
+        })
+        .collect();
+    (headers, table)
+}
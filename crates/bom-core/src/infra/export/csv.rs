@@ -0,0 +1,147 @@
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+
+/// Field separator of an exported CSV file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CsvDelimiter {
+    #[default]
+    Comma,
+    Semicolon,
+    Tab,
+}
+
+impl CsvDelimiter {
+    pub const ALL: [CsvDelimiter; 3] = [
+        CsvDelimiter::Comma,
+        CsvDelimiter::Semicolon,
+        CsvDelimiter::Tab,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CsvDelimiter::Comma => "comma",
+            CsvDelimiter::Semicolon => "semicolon",
+            CsvDelimiter::Tab => "tab",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|delimiter| delimiter.as_str() == value)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CsvDelimiter::Comma => "逗號 ,",
+            CsvDelimiter::Semicolon => "分號 ;",
+            CsvDelimiter::Tab => "Tab",
+        }
+    }
+
+    fn byte(self) -> u8 {
+        match self {
+            CsvDelimiter::Comma => b',',
+            CsvDelimiter::Semicolon => b';',
+            CsvDelimiter::Tab => b'\t',
+        }
+    }
+}
+
+/// Text encoding of an exported CSV file. Excel opens UTF-8 correctly only with the byte
+/// order mark; bank and broker tools often read nothing but Big5.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CsvEncoding {
+    #[default]
+    Utf8Bom,
+    Big5,
+}
+
+impl CsvEncoding {
+    pub const ALL: [CsvEncoding; 2] = [CsvEncoding::Utf8Bom, CsvEncoding::Big5];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CsvEncoding::Utf8Bom => "utf-8-bom",
+            CsvEncoding::Big5 => "big5",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|encoding| encoding.as_str() == value)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CsvEncoding::Utf8Bom => "UTF-8（含 BOM）",
+            CsvEncoding::Big5 => "Big5（繁體中文 Windows）",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CsvOptions {
+    pub delimiter: CsvDelimiter,
+    pub encoding: CsvEncoding,
+}
+
+/// Writes `headers` followed by `rows` as a UTF-8 CSV file.
+pub fn export_csv(path: &Path, headers: &[String], rows: &[Vec<String>]) -> Result<()> {
+    let mut writer = csv::Writer::from_path(path)
+        .with_context(|| format!("failed to create csv: {}", path.display()))?;
+    writer
+        .write_record(headers)
+        .with_context(|| format!("failed to write csv header: {}", path.display()))?;
+    for row in rows {
+        writer
+            .write_record(row)
+            .with_context(|| format!("failed to write csv row: {}", path.display()))?;
+    }
+    writer
+        .flush()
+        .with_context(|| format!("failed to write csv: {}", path.display()))?;
+    Ok(())
+}
+
+/// Like [`export_csv`], separated and encoded as `options` say. Nothing is written when a
+/// character has no Big5 form, rather than leaving a placeholder in its place.
+pub fn export_csv_with(
+    path: &Path,
+    headers: &[String],
+    rows: &[Vec<String>],
+    options: &CsvOptions,
+) -> Result<()> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(options.delimiter.byte())
+        .from_writer(Vec::new());
+    writer
+        .write_record(headers)
+        .context("failed to write csv header")?;
+    for row in rows {
+        writer
+            .write_record(row)
+            .context("failed to write csv row")?;
+    }
+    let text = String::from_utf8(writer.into_inner().context("failed to write csv")?)
+        .context("csv is not valid UTF-8")?;
+    let bytes = match options.encoding {
+        CsvEncoding::Utf8Bom => [&[0xEF, 0xBB, 0xBF][..], text.as_bytes()].concat(),
+        CsvEncoding::Big5 => {
+            let (encoded, _, had_errors) = encoding_rs::BIG5.encode(&text);
+            if had_errors {
+                let missing = text
+                    .chars()
+                    .find(|c| encoding_rs::BIG5.encode(&c.to_string()).2)
+                    .unwrap_or_default();
+                bail!("「{missing}」無法以 Big5 表示，請改用 UTF-8");
+            }
+            encoded.into_owned()
+        }
+    };
+    std::fs::write(path, bytes)
+        .with_context(|| format!("failed to write csv: {}", path.display()))?;
+    Ok(())
+}
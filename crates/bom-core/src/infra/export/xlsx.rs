@@ -0,0 +1,138 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/></Types>"#;
+
+const ROOT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#;
+
+const WORKBOOK_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/></Relationships>"#;
+
+/// Writes `headers` followed by `rows` as a one-sheet XLSX workbook named `sheet_name`.
+/// Plain numbers become number cells; everything else, codes such as `0050` included,
+/// stays text.
+pub fn export_xlsx(
+    path: &Path,
+    sheet_name: &str,
+    headers: &[String],
+    rows: &[Vec<String>],
+) -> Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("failed to create xlsx: {}", path.display()))?;
+    let mut zip = ZipWriter::new(file);
+    let workbook = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="{}" sheetId="1" r:id="rId1"/></sheets></workbook>"#,
+        escape_xml(&sheet_title(sheet_name))
+    );
+    let parts = [
+        ("[Content_Types].xml", CONTENT_TYPES.to_string()),
+        ("_rels/.rels", ROOT_RELS.to_string()),
+        ("xl/workbook.xml", workbook),
+        ("xl/_rels/workbook.xml.rels", WORKBOOK_RELS.to_string()),
+        ("xl/worksheets/sheet1.xml", sheet_xml(headers, rows)),
+    ];
+    for (name, content) in parts {
+        zip.start_file(name, FileOptions::default())
+            .with_context(|| format!("failed to write xlsx part {name}: {}", path.display()))?;
+        zip.write_all(content.as_bytes())
+            .with_context(|| format!("failed to write xlsx part {name}: {}", path.display()))?;
+    }
+    zip.finish()
+        .with_context(|| format!("failed to write xlsx: {}", path.display()))?;
+    Ok(())
+}
+
+fn sheet_xml(headers: &[String], rows: &[Vec<String>]) -> String {
+    let mut xml = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>"#,
+    );
+    for (row_idx, row) in std::iter::once(headers)
+        .chain(rows.iter().map(Vec::as_slice))
+        .enumerate()
+    {
+        let row_num = row_idx + 1;
+        xml.push_str(&format!(r#"<row r="{row_num}">"#));
+        for (col_idx, value) in row.iter().enumerate() {
+            if value.is_empty() {
+                continue;
+            }
+            let cell = format!("{}{row_num}", column_letters(col_idx));
+            if row_idx > 0 && is_plain_number(value) {
+                xml.push_str(&format!(r#"<c r="{cell}"><v>{value}</v></c>"#));
+            } else {
+                xml.push_str(&format!(
+                    r#"<c r="{cell}" t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#,
+                    escape_xml(value)
+                ));
+            }
+        }
+        xml.push_str("</row>");
+    }
+    xml.push_str("</sheetData></worksheet>");
+    xml
+}
+
+/// `A`, `B`, … `Z`, `AA`, … for a zero-based column index.
+fn column_letters(mut col_idx: usize) -> String {
+    let mut letters = Vec::new();
+    loop {
+        letters.push(b'A' + (col_idx % 26) as u8);
+        if col_idx < 26 {
+            break;
+        }
+        col_idx = col_idx / 26 - 1;
+    }
+    letters.reverse();
+    String::from_utf8(letters).unwrap_or_default()
+}
+
+/// A decimal number written the way Excel would write it back, so a number cell loses
+/// nothing: no leading zeros, separators or signs other than `-`.
+fn is_plain_number(value: &str) -> bool {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    let (int, frac) = digits.split_once('.').unwrap_or((digits, "0"));
+    !int.is_empty()
+        && !frac.is_empty()
+        && int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit())
+        && (int == "0" || !int.starts_with('0'))
+}
+
+/// Excel limits sheet names to 31 characters without `[]:*?/\`.
+fn sheet_title(name: &str) -> String {
+    let title: String = name
+        .chars()
+        .filter(|c| !matches!(c, '[' | ']' | ':' | '*' | '?' | '/' | '\\'))
+        .take(31)
+        .collect();
+    if title.trim().is_empty() {
+        "Sheet1".to_string()
+    } else {
+        title
+    }
+}
+
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            // Other control characters are not allowed in XML at all.
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if (c as u32) < 0x20 => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,70 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use csv::StringRecord;
+
+use crate::infra::import::import_with;
+use crate::usecase::ports::importer::{Importer, ParsedDataset};
+use crate::ImportResult;
+
+/// Plain CSV with a header row; each file becomes one dataset named after the file.
+pub struct CsvImporter;
+
+impl Importer for CsvImporter {
+    fn name(&self) -> &str {
+        "CSV"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["csv"]
+    }
+
+    fn parse(&self, csv_path: &Path) -> Result<Vec<ParsedDataset>> {
+        let mut reader = csv::Reader::from_path(csv_path)
+            .with_context(|| format!("failed to open csv: {}", csv_path.display()))?;
+        let headers = reader
+            .headers()
+            .with_context(|| format!("failed to read headers from csv: {}", csv_path.display()))?
+            .iter()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+
+        if headers.is_empty() {
+            anyhow::bail!("csv header is required")
+        }
+
+        let mut rows = Vec::new();
+        for record in reader.records() {
+            let record = record.context("failed to parse csv record")?;
+            rows.push(
+                (0..headers.len())
+                    .map(|col_idx| record.get(col_idx).unwrap_or("").to_string())
+                    .collect(),
+            );
+        }
+
+        let name = csv_path
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .filter(|name| !name.is_empty())
+            .unwrap_or("dataset")
+            .to_string();
+        Ok(vec![ParsedDataset {
+            name,
+            source_path: csv_path.to_string_lossy().into_owned(),
+            headers,
+            rows,
+            origins: Vec::new(),
+        }])
+    }
+}
+
+#[allow(dead_code)]
+pub fn import_csv_to_sqlite(db_path: &Path, csv_path: &Path) -> Result<ImportResult> {
+    import_with(&CsvImporter, db_path, csv_path)?
+        .pop()
+        .context("csv import produced no dataset")
+}
+
+#[allow(dead_code)]
+pub fn csv_headers_placeholder(_headers: &StringRecord) {}
@@ -0,0 +1,218 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use encoding_rs::Encoding;
+
+use crate::domain::entities::import_profile::{ColumnMapping, ImportProfile};
+use crate::usecase::ports::importer::{Importer, ParsedDataset};
+
+/// Encodings offered when setting up an import profile, as (WHATWG label, display name).
+pub const PROFILE_ENCODINGS: [(&str, &str); 3] = [
+    ("utf-8", "UTF-8"),
+    ("big5", "Big5（繁體中文 Windows）"),
+    ("gbk", "GBK（簡體中文 Windows）"),
+];
+
+/// Every record of `csv_path` decoded from `encoding`, header rows included. A byte order
+/// mark overrides `encoding`.
+pub fn read_csv_records(csv_path: &Path, encoding: &str) -> Result<Vec<Vec<String>>> {
+    let encoding = Encoding::for_label(encoding.trim().as_bytes())
+        .with_context(|| format!("unknown encoding: {encoding}"))?;
+    let bytes = std::fs::read(csv_path)
+        .with_context(|| format!("failed to open csv: {}", csv_path.display()))?;
+    let (text, _, _) = encoding.decode(&bytes);
+
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    reader
+        .records()
+        .map(|record| {
+            let record = record.context("failed to parse csv record")?;
+            Ok(record.iter().map(str::to_string).collect())
+        })
+        .collect()
+}
+
+/// Column names on row `header_row` (0-based) of `csv_path`.
+pub fn csv_headers(csv_path: &Path, encoding: &str, header_row: usize) -> Result<Vec<String>> {
+    let records = read_csv_records(csv_path, encoding)?;
+    let headers = records
+        .into_iter()
+        .nth(header_row)
+        .with_context(|| format!("csv has no row {}", header_row + 1))?;
+    Ok(headers
+        .into_iter()
+        .map(|header| header.trim().to_string())
+        .collect())
+}
+
+/// The saved profile that fits `csv_path`, preferring the one that maps the most columns.
+pub fn find_matching_profile<'a>(
+    csv_path: &Path,
+    profiles: &'a [ImportProfile],
+) -> Option<&'a ImportProfile> {
+    profiles
+        .iter()
+        .filter(|profile| {
+            csv_headers(csv_path, &profile.encoding, profile.header_row)
+                .is_ok_and(|headers| profile.matches(&headers))
+        })
+        .max_by_key(|profile| profile.columns.len())
+}
+
+/// Suggestions weaker than this are left for the user to pick.
+pub const MIN_HEADER_SIMILARITY: f64 = 0.5;
+
+/// How alike two column names are, from 0 to 1. Case, spaces, brackets and full-width
+/// forms are ignored, so `代號（股票）` and `代號` score high; otherwise the score falls with
+/// the edit distance between the names.
+pub fn header_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (normalize_header(a), normalize_header(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    if a == b {
+        return 1.0;
+    }
+    let (shorter, longer) = if a.len() <= b.len() {
+        (&a, &b)
+    } else {
+        (&b, &a)
+    };
+    let contained = longer
+        .windows(shorter.len())
+        .any(|window| window == shorter.as_slice());
+    let containment = if contained {
+        0.5 + 0.5 * shorter.len() as f64 / longer.len() as f64
+    } else {
+        0.0
+    };
+    let distance = 1.0 - edit_distance(&a, &b) as f64 / longer.len() as f64;
+    containment.max(distance)
+}
+
+/// A source column for each of `targets` where one of `headers` is alike enough, each
+/// header used at most once and the closest pairs matched first. Targets keep their order.
+pub fn suggest_column_mappings(headers: &[String], targets: &[String]) -> Vec<ColumnMapping> {
+    let mut pairs = Vec::new();
+    for (target_idx, target) in targets.iter().enumerate() {
+        for (header_idx, header) in headers.iter().enumerate() {
+            let score = header_similarity(header, target);
+            if score >= MIN_HEADER_SIMILARITY {
+                pairs.push((score, target_idx, header_idx));
+            }
+        }
+    }
+    pairs.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+    let mut sources = vec![None; targets.len()];
+    let mut used = vec![false; headers.len()];
+    for (_, target_idx, header_idx) in pairs {
+        if sources[target_idx].is_none() && !used[header_idx] {
+            sources[target_idx] = Some(header_idx);
+            used[header_idx] = true;
+        }
+    }
+    targets
+        .iter()
+        .zip(sources)
+        .filter_map(|(target, source)| {
+            source.map(|idx| ColumnMapping {
+                source: headers[idx].trim().to_string(),
+                target: target.clone(),
+            })
+        })
+        .collect()
+}
+
+fn normalize_header(header: &str) -> Vec<char> {
+    header
+        .chars()
+        .map(|ch| match ch {
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(ch as u32 - 0xFEE0).unwrap_or(ch),
+            '\u{3000}' => ' ',
+            _ => ch,
+        })
+        .filter(|ch| !ch.is_whitespace() && !"()[]【】「」_-/:.".contains(*ch))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// CSV read through an [`ImportProfile`]: the dataset gets the profile's target columns,
+/// filled from the mapped source columns. Blank rows are dropped.
+pub struct MappedCsvImporter {
+    pub profile: ImportProfile,
+}
+
+impl Importer for MappedCsvImporter {
+    fn name(&self) -> &str {
+        "CSV"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["csv"]
+    }
+
+    fn parse(&self, csv_path: &Path) -> Result<Vec<ParsedDataset>> {
+        let profile = &self.profile;
+        let mut records = read_csv_records(csv_path, &profile.encoding)?
+            .into_iter()
+            .skip(profile.header_row);
+        let source_headers = records
+            .next()
+            .with_context(|| format!("csv has no row {}", profile.header_row + 1))?;
+        let source_indices = profile
+            .columns
+            .iter()
+            .map(|mapping| {
+                source_headers
+                    .iter()
+                    .position(|header| header.trim() == mapping.source)
+                    .with_context(|| format!("csv is missing column: {}", mapping.source))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let rows = records
+            .filter(|record| record.iter().any(|cell| !cell.trim().is_empty()))
+            .map(|record| {
+                source_indices
+                    .iter()
+                    .map(|idx| record.get(*idx).cloned().unwrap_or_default())
+                    .collect()
+            })
+            .collect();
+
+        let name = csv_path
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .filter(|name| !name.is_empty())
+            .unwrap_or("dataset")
+            .to_string();
+        Ok(vec![ParsedDataset {
+            name,
+            source_path: csv_path.to_string_lossy().into_owned(),
+            headers: profile
+                .columns
+                .iter()
+                .map(|mapping| mapping.target.clone())
+                .collect(),
+            rows,
+            origins: Vec::new(),
+        }])
+    }
+}
@@ -0,0 +1,241 @@
+pub mod csv;
+pub mod mapped_csv;
+pub mod registry;
+pub mod xlsx;
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use ring::digest::{digest, SHA256};
+use rusqlite::params;
+
+use crate::domain::entities::dataset::{ColumnType, DatasetKind};
+use crate::domain::entities::import_record::{ImportEventKind, ImportRecord, ImportedSheet};
+use crate::holdings::builtin_column_set;
+use crate::infra::sqlite::queries::{
+    insert_cell_origins, insert_column_types, insert_column_visibility, insert_header_names,
+    insert_source_sheets, load_column_set, record_import,
+};
+use crate::infra::sqlite::schema::{init_db, open_connection};
+use crate::numeric::{
+    infer_column_types, normalize_numeric_text, percent_to_ratio_text, NumberLocale,
+};
+use crate::usecase::ports::importer::{Importer, ParsedDataset};
+use crate::ImportResult;
+
+/// Parses and transforms `path` with `importer`, then stores every resulting dataset in one
+/// transaction so a failing file leaves nothing behind. Column types are inferred from the
+/// values, and percent columns are stored as ratios whichever way the file wrote them.
+/// Where the transform tracked them, the origins of the cells are stored alongside, and
+/// importers that keep their source sheets store them with each dataset as well. A table
+/// holding every required 持股 column is stored showing only those columns. The import is
+/// added to the import history.
+pub fn import_with(
+    importer: &dyn Importer,
+    db_path: &Path,
+    path: &Path,
+) -> Result<Vec<ImportResult>> {
+    import_with_locale(importer, db_path, path, NumberLocale::default())
+}
+
+/// [`import_with`] for a file whose numbers are written in `locale`; they are rewritten to
+/// the stored form before the importer's transform reads them.
+pub fn import_with_locale(
+    importer: &dyn Importer,
+    db_path: &Path,
+    path: &Path,
+    locale: NumberLocale,
+) -> Result<Vec<ImportResult>> {
+    let started = Instant::now();
+    let mut parsed = importer
+        .parse(path)
+        .with_context(|| format!("{} import failed: {}", importer.name(), path.display()))?;
+    if locale != NumberLocale::default() {
+        for cell in parsed
+            .iter_mut()
+            .flat_map(|dataset| dataset.rows.iter_mut())
+            .flatten()
+        {
+            *cell = normalize_numeric_text(cell, locale);
+        }
+    }
+    let sources = importer.keeps_source_sheets().then(|| parsed.clone());
+    let mut datasets = importer.transform(parsed)?;
+    let column_types = prepare_for_storage(&mut datasets);
+    let imported = store_datasets(db_path, &datasets, &column_types, sources.as_deref())?;
+    let file_hash = file_hash(path)
+        .inspect_err(|err| tracing::warn!(error = %err, "failed to hash imported file"))
+        .unwrap_or_default();
+    let record = import_record(
+        ImportEventKind::Import,
+        &path.to_string_lossy(),
+        file_hash,
+        &datasets,
+        &imported,
+        started,
+    );
+    if let Err(err) = record_import(db_path, &record) {
+        tracing::warn!(error = %err, "failed to record import history");
+    }
+    Ok(imported)
+}
+
+/// The history entry of an import or re-transform that stored `datasets` as `imported`
+/// and began at `started`.
+pub fn import_record(
+    kind: ImportEventKind,
+    source_path: &str,
+    file_hash: String,
+    datasets: &[ParsedDataset],
+    imported: &[ImportResult],
+    started: Instant,
+) -> ImportRecord {
+    ImportRecord {
+        kind,
+        source_path: source_path.to_string(),
+        file_hash,
+        sheets: datasets
+            .iter()
+            .zip(imported)
+            .map(|(dataset, result)| ImportedSheet {
+                dataset_id: result.dataset_id,
+                name: dataset.name.clone(),
+                row_count: result.row_count,
+            })
+            .collect(),
+        imported_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        duration_ms: i64::try_from(started.elapsed().as_millis()).unwrap_or(i64::MAX),
+        warnings: import_warnings(datasets),
+    }
+}
+
+/// What is worth noting about stored `datasets`: those that came out empty, and 代號
+/// shared by more than one row.
+pub fn import_warnings(datasets: &[ParsedDataset]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for dataset in datasets {
+        if dataset.rows.is_empty() {
+            warnings.push(format!("{}：沒有資料列", dataset.name));
+            continue;
+        }
+        let Some(code_col) = dataset.headers.iter().position(|header| header == "代號") else {
+            continue;
+        };
+        let mut counts = BTreeMap::<&str, usize>::new();
+        for code in dataset
+            .rows
+            .iter()
+            .filter_map(|row| row.get(code_col))
+            .map(|code| code.trim())
+            .filter(|code| !code.is_empty())
+        {
+            *counts.entry(code).or_default() += 1;
+        }
+        for (code, count) in counts.into_iter().filter(|(_, count)| *count > 1) {
+            warnings.push(format!("{}：代號 {code} 出現 {count} 次", dataset.name));
+        }
+    }
+    warnings
+}
+
+/// SHA-256 of the file at `path`, in hex.
+pub fn file_hash(path: &Path) -> Result<String> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(digest(&SHA256, &bytes)
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+/// Infers the column types of transformed `datasets` and rewrites their percent columns
+/// to ratios, as every import stores them. Returns the types, one list per dataset.
+pub fn prepare_for_storage(datasets: &mut [ParsedDataset]) -> Vec<Vec<Option<ColumnType>>> {
+    let mut column_types = Vec::with_capacity(datasets.len());
+    for dataset in datasets {
+        let types = infer_column_types(&dataset.headers, &dataset.rows);
+        for row in &mut dataset.rows {
+            for (cell, _) in row
+                .iter_mut()
+                .zip(&types)
+                .filter(|(_, column_type)| **column_type == Some(ColumnType::Percent))
+            {
+                *cell = percent_to_ratio_text(cell);
+            }
+        }
+        column_types.push(types);
+    }
+    column_types
+}
+
+fn store_datasets(
+    db_path: &Path,
+    datasets: &[ParsedDataset],
+    column_types: &[Vec<Option<ColumnType>>],
+    sources: Option<&[ParsedDataset]>,
+) -> Result<Vec<ImportResult>> {
+    init_db(db_path)?;
+    let holdings = load_column_set(db_path, DatasetKind::Holdings)?
+        .unwrap_or_else(|| builtin_column_set(DatasetKind::Holdings));
+    let mut conn = open_connection(db_path)?;
+    let tx = conn
+        .transaction()
+        .context("failed to start import transaction")?;
+
+    let mut imported = Vec::new();
+    for (dataset, types) in datasets.iter().zip(column_types) {
+        tx.execute(
+            "INSERT INTO dataset(name, source_path, row_count, kind) VALUES (?1, ?2, 0, ?3)",
+            params![
+                dataset.name,
+                dataset.source_path,
+                DatasetKind::from_name(&dataset.name).as_str()
+            ],
+        )
+        .with_context(|| format!("failed to insert dataset: {}", dataset.name))?;
+        let dataset_id = tx.last_insert_rowid();
+
+        insert_header_names(&tx, dataset_id, &dataset.headers)?;
+        insert_column_types(&tx, dataset_id, types)?;
+        if holdings.matches(&dataset.headers) {
+            let visibility = holdings.default_visibility(&dataset.headers);
+            insert_column_visibility(&tx, dataset_id, &visibility)?;
+        }
+        insert_cell_origins(&tx, dataset_id, &dataset.origins)?;
+        if let Some(sources) = sources {
+            insert_source_sheets(&tx, dataset_id, &dataset.name, sources)?;
+        }
+
+        let mut insert_cell = tx
+            .prepare(
+                "INSERT INTO cell(dataset_id, row_idx, col_idx, value) VALUES (?1, ?2, ?3, ?4)",
+            )
+            .context("failed to prepare cell insert")?;
+        for (row_idx, row) in dataset.rows.iter().enumerate() {
+            for (col_idx, value) in row.iter().enumerate() {
+                insert_cell
+                    .execute(params![dataset_id, row_idx as i64, col_idx as i64, value])
+                    .context("failed to insert cell")?;
+            }
+        }
+        drop(insert_cell);
+
+        let row_count = dataset.rows.len() as i64;
+        tx.execute(
+            "UPDATE dataset SET row_count = ?1 WHERE id = ?2",
+            params![row_count, dataset_id],
+        )
+        .context("failed to update dataset row_count")?;
+
+        imported.push(ImportResult {
+            dataset_id,
+            row_count,
+        });
+    }
+
+    tx.commit().context("failed to commit import transaction")?;
+    Ok(imported)
+}
@@ -0,0 +1,35 @@
+use std::path::Path;
+
+use crate::infra::import::csv::CsvImporter;
+use crate::infra::import::xlsx::XlsxImporter;
+use crate::usecase::ports::importer::{ImportError, Importer};
+
+/// Importers known to the app, tried in registration order.
+#[derive(Default)]
+pub struct ImporterRegistry {
+    importers: Vec<Box<dyn Importer>>,
+}
+
+impl ImporterRegistry {
+    /// The built-in CSV and XLSX importers.
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::default();
+        registry.register(Box::new(XlsxImporter::default()));
+        registry.register(Box::new(CsvImporter));
+        registry
+    }
+
+    pub fn register(&mut self, importer: Box<dyn Importer>) {
+        self.importers.push(importer);
+    }
+
+    pub fn importers(&self) -> impl Iterator<Item = &dyn Importer> {
+        self.importers.iter().map(|importer| importer.as_ref())
+    }
+
+    pub fn find(&self, path: &Path) -> Result<&dyn Importer, ImportError> {
+        self.importers()
+            .find(|importer| importer.matches(path))
+            .ok_or_else(|| ImportError::Unsupported(path.to_path_buf()))
+    }
+}
@@ -0,0 +1,5 @@
+pub mod export;
+pub mod import;
+pub mod remote_backup;
+pub mod security;
+pub mod sqlite;
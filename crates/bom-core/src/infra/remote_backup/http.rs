@@ -0,0 +1,158 @@
+use std::io::Read;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+const IO_TIMEOUT: Duration = Duration::from_secs(120);
+/// How much of an error response body is kept for the status message.
+const ERROR_BODY_LIMIT: usize = 300;
+
+/// An `http://` or `https://` URL split into the parts a request needs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpUrl {
+    pub secure: bool,
+    pub host: String,
+    pub port: u16,
+    /// Always starts with `/`; kept exactly as configured (already percent-encoded).
+    pub path: String,
+}
+
+impl HttpUrl {
+    pub fn parse(url: &str) -> Result<Self> {
+        let url = url.trim();
+        let (secure, rest) = if let Some(rest) = url.strip_prefix("https://") {
+            (true, rest)
+        } else if let Some(rest) = url.strip_prefix("http://") {
+            (false, rest)
+        } else {
+            bail!("網址需以 http:// 或 https:// 開頭：{url}");
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(idx) => (&rest[..idx], &rest[idx..]),
+            None => (rest, "/"),
+        };
+        let default_port = if secure { 443 } else { 80 };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse::<u16>()
+                    .with_context(|| format!("網址的連接埠無效：{url}"))?,
+            ),
+            None => (authority, default_port),
+        };
+        if host.is_empty() {
+            bail!("網址缺少主機名稱：{url}");
+        }
+        Ok(Self {
+            secure,
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+
+    /// The `Host` header value; the port is only included when it is not the default.
+    pub fn host_header(&self) -> String {
+        let default_port = if self.secure { 443 } else { 80 };
+        if self.port == default_port {
+            self.host.clone()
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    /// Appends `name` as the last path segment.
+    pub fn join(&self, name: &str) -> Self {
+        let mut path = self.path.trim_end_matches('/').to_string();
+        path.push('/');
+        path.push_str(&percent_encode(name));
+        Self {
+            path,
+            ..self.clone()
+        }
+    }
+
+    /// Whether the host is this machine, where a plain `http://` request never crosses
+    /// the network.
+    pub fn is_loopback(&self) -> bool {
+        let host = self.host.trim_start_matches('[').trim_end_matches(']');
+        host.eq_ignore_ascii_case("localhost")
+            || host.parse::<IpAddr>().is_ok_and(|addr| addr.is_loopback())
+    }
+
+    pub fn to_url(&self) -> String {
+        let scheme = if self.secure { "https" } else { "http" };
+        format!("{scheme}://{}{}", self.host_header(), self.path)
+    }
+}
+
+/// Status code and (truncated) body of a response.
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+impl HttpResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// Sends one request whose body is the `content_length` bytes read from `body`, streamed
+/// rather than held in memory, and reads the response. `https://` goes over rustls with
+/// the bundled web PKI roots. Redirects are not followed, so credentials in `headers`
+/// only ever reach `url`.
+pub fn send(
+    method: &str,
+    url: &HttpUrl,
+    headers: &[(String, String)],
+    body: impl Read,
+    content_length: u64,
+) -> Result<HttpResponse> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout_connect(CONNECT_TIMEOUT)
+        .timeout_read(IO_TIMEOUT)
+        .timeout_write(IO_TIMEOUT)
+        .redirects(0)
+        .build();
+    let mut request = agent
+        .request(method, &url.to_url())
+        .set("Content-Length", &content_length.to_string());
+    for (name, value) in headers {
+        request = request.set(name, value);
+    }
+    let response = match request.send(body) {
+        Ok(response) => response,
+        Err(ureq::Error::Status(_, response)) => response,
+        Err(ureq::Error::Transport(err)) => {
+            bail!("無法連線至 {}：{err}", url.host_header())
+        }
+    };
+    let status = response.status();
+    // Only error bodies are shown to the user, so a partial or failed read is fine.
+    let mut body = Vec::new();
+    let _ = response
+        .into_reader()
+        .take(ERROR_BODY_LIMIT as u64)
+        .read_to_end(&mut body);
+    Ok(HttpResponse {
+        status,
+        body: String::from_utf8_lossy(&body).trim().to_string(),
+    })
+}
+
+/// Percent-encodes everything except RFC 3986 unreserved characters.
+pub fn percent_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
@@ -0,0 +1,124 @@
+//! Off-site copies of database backups: a plain HTTP `PUT` to a WebDAV collection or an
+//! S3-compatible bucket. The credentials are kept with the other settings in the local
+//! database, in plain text, so anyone who can read the database file can read them too.
+
+pub mod http;
+pub mod s3;
+
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use self::http::HttpUrl;
+
+/// Protocol spoken by the remote backup endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RemoteBackupKind {
+    #[default]
+    WebDav,
+    S3,
+}
+
+impl RemoteBackupKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RemoteBackupKind::WebDav => "webdav",
+            RemoteBackupKind::S3 => "s3",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "webdav" => Some(RemoteBackupKind::WebDav),
+            "s3" => Some(RemoteBackupKind::S3),
+            _ => None,
+        }
+    }
+}
+
+/// Where backups are uploaded. `url` is a WebDAV collection, or for S3 the path-style
+/// bucket URL (`https://endpoint/bucket[/prefix]`); the backup file name is appended.
+/// `username` / `secret` are the Basic auth credentials or the S3 access key pair.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RemoteBackupConfig {
+    pub kind: RemoteBackupKind,
+    pub url: String,
+    pub username: String,
+    pub secret: String,
+    /// S3 signing region; empty means [`s3::DEFAULT_REGION`].
+    pub region: String,
+}
+
+impl RemoteBackupConfig {
+    pub fn is_configured(&self) -> bool {
+        !self.url.trim().is_empty()
+    }
+}
+
+/// Uploads `file_path` under its own file name and returns the URL it was stored at. The
+/// file is streamed, not read into memory. Credentials are only sent over `https://`, or
+/// plain `http://` to this machine.
+pub fn upload_backup(config: &RemoteBackupConfig, file_path: &Path) -> Result<String> {
+    if !config.is_configured() {
+        bail!("尚未設定遠端備份位置");
+    }
+    let file_name = file_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .with_context(|| format!("invalid backup file name: {}", file_path.display()))?;
+    let url = HttpUrl::parse(&config.url)?.join(file_name);
+    let has_credentials = config.kind == RemoteBackupKind::S3
+        || !config.username.is_empty()
+        || !config.secret.is_empty();
+    if has_credentials && !url.secure && !url.is_loopback() {
+        bail!("帳號與金鑰只能透過 https:// 傳送，請改用 https:// 網址");
+    }
+    let open = || {
+        File::open(file_path)
+            .with_context(|| format!("failed to read backup: {}", file_path.display()))
+    };
+    let content_length = open()?
+        .metadata()
+        .with_context(|| format!("failed to read backup: {}", file_path.display()))?
+        .len();
+
+    let mut headers = vec![(
+        "Content-Type".to_string(),
+        "application/octet-stream".to_string(),
+    )];
+    match config.kind {
+        RemoteBackupKind::WebDav => {
+            if !config.username.is_empty() {
+                let credentials = STANDARD.encode(format!("{}:{}", config.username, config.secret));
+                headers.push(("Authorization".to_string(), format!("Basic {credentials}")));
+            }
+        }
+        RemoteBackupKind::S3 => {
+            if config.username.is_empty() || config.secret.is_empty() {
+                bail!("S3 需要存取金鑰與私密金鑰");
+            }
+            let payload_hash = s3::sha256_hex(open()?)
+                .with_context(|| format!("failed to read backup: {}", file_path.display()))?;
+            headers.extend(s3::signed_put_headers(
+                &url,
+                &config.username,
+                &config.secret,
+                &config.region,
+                &payload_hash,
+                chrono::Utc::now(),
+            ));
+        }
+    }
+
+    let response = http::send("PUT", &url, &headers, open()?, content_length)?;
+    if !response.is_success() {
+        if response.body.is_empty() {
+            bail!("遠端伺服器回應 {}", response.status);
+        }
+        bail!("遠端伺服器回應 {}：{}", response.status, response.body);
+    }
+    Ok(url.to_url())
+}
@@ -0,0 +1,33 @@
+use anyhow::{anyhow, Result};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use ring::rand::{SecureRandom, SystemRandom};
+
+const SALT_LEN: usize = 16;
+
+/// Hashes `passphrase` with Argon2id and a fresh random salt into a PHC string
+/// (`$argon2id$v=19$m=…,t=…,p=…$<salt>$<hash>`), suitable for settings. The string
+/// carries its own parameters, so they can be raised later without locking anyone out.
+pub fn hash_passphrase(passphrase: &str) -> Result<String> {
+    let mut salt = [0u8; SALT_LEN];
+    SystemRandom::new()
+        .fill(&mut salt)
+        .map_err(|_| anyhow!("failed to generate passphrase salt"))?;
+    let salt = SaltString::encode_b64(&salt).map_err(|err| anyhow!("invalid salt: {err}"))?;
+    let hash = Argon2::default()
+        .hash_password(passphrase.as_bytes(), &salt)
+        .map_err(|err| anyhow!("failed to hash passphrase: {err}"))?;
+    Ok(hash.to_string())
+}
+
+/// Checks `passphrase` against a value produced by [`hash_passphrase`]. Malformed values
+/// and other algorithms never verify.
+pub fn verify_passphrase(passphrase: &str, stored: &str) -> bool {
+    let Ok(hash) = PasswordHash::new(stored) else {
+        return false;
+    };
+    hash.algorithm == argon2::ARGON2ID_IDENT
+        && Argon2::default()
+            .verify_password(passphrase.as_bytes(), &hash)
+            .is_ok()
+}
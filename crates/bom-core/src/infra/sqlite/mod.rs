@@ -1,3 +1,4 @@
 pub mod queries;
 pub mod repo;
 pub mod schema;
+pub mod sync_folder;
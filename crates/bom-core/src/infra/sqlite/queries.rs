@@ -0,0 +1,2901 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use csv::StringRecord;
+use rusqlite::{params, types::Value, Connection, OptionalExtension};
+
+use crate::domain::entities::cell_origin::{CellOrigin, CellOrigins};
+use crate::domain::entities::column_set::ColumnSet;
+use crate::domain::entities::computed_column::ComputedColumn;
+use crate::domain::entities::dataset::{
+    ColumnFormat, ColumnStats, ColumnType, DatasetDisplay, DatasetKind, DisplayUnit,
+};
+use crate::domain::entities::deposit_rate::DepositRate;
+use crate::domain::entities::edit::{CellKey, SavedChanges};
+use crate::domain::entities::import_profile::{ColumnMapping, ImportProfile};
+use crate::domain::entities::import_record::{ImportEventKind, ImportRecord, ImportedSheet};
+use crate::domain::entities::owner_share::{OwnerShare, OwnerSplits};
+use crate::domain::entities::price_point::{PriceHistory, PricePoint};
+use crate::domain::entities::saved_report::SavedReport;
+use crate::expression::append_computed_columns;
+use crate::filter_expr::{parse_filter_expr, CompareOp, FilterExpr};
+use crate::infra::sqlite::schema::{init_db, open_connection};
+use crate::numeric::{infer_column_types, parse_numeric_value};
+use crate::report::{OwnerSummary, StoredSummaryReport, SummaryEntry, SummaryReport};
+use crate::usecase::ports::importer::ParsedDataset;
+use crate::usecase::ports::repo::{DatasetContext, DatasetMeta};
+use crate::QueryOptions;
+
+type ReloadPageResult = (Vec<String>, Vec<Vec<String>>, i64, i64);
+
+#[allow(dead_code)]
+pub fn insert_headers(
+    tx: &rusqlite::Transaction<'_>,
+    dataset_id: i64,
+    headers: &StringRecord,
+) -> Result<()> {
+    let mut insert_header = tx
+        .prepare("INSERT INTO column_name(dataset_id, col_idx, name) VALUES (?1, ?2, ?3)")
+        .context("failed to prepare header insert")?;
+
+    for (col_idx, name) in headers.iter().enumerate() {
+        insert_header
+            .execute(params![dataset_id, col_idx as i64, name])
+            .context("failed to insert header")?;
+    }
+
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn insert_header_names(
+    tx: &rusqlite::Transaction<'_>,
+    dataset_id: i64,
+    headers: &[String],
+) -> Result<()> {
+    let mut insert_header = tx
+        .prepare("INSERT INTO column_name(dataset_id, col_idx, name) VALUES (?1, ?2, ?3)")
+        .context("failed to prepare header insert")?;
+
+    for (col_idx, name) in headers.iter().enumerate() {
+        insert_header
+            .execute(params![dataset_id, col_idx as i64, name])
+            .context("failed to insert header")?;
+    }
+
+    Ok(())
+}
+
+/// Stores the types of a new dataset's columns; columns whose type is undecided are left
+/// out and typed from their values when shown.
+pub fn insert_column_types(
+    tx: &rusqlite::Transaction<'_>,
+    dataset_id: i64,
+    types: &[Option<ColumnType>],
+) -> Result<()> {
+    let mut insert_type = tx
+        .prepare("INSERT INTO column_type(dataset_id, col_idx, type) VALUES (?1, ?2, ?3)")
+        .context("failed to prepare column type insert")?;
+
+    for (col_idx, column_type) in types.iter().enumerate() {
+        if let Some(column_type) = column_type {
+            insert_type
+                .execute(params![dataset_id, col_idx as i64, column_type.as_str()])
+                .context("failed to insert column type")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Stores the column visibility of a new dataset, which has no aliases yet.
+pub fn insert_column_visibility(
+    tx: &rusqlite::Transaction<'_>,
+    dataset_id: i64,
+    visibility: &BTreeMap<i64, bool>,
+) -> Result<()> {
+    let mut insert_visibility = tx
+        .prepare(
+            "INSERT INTO column_visibility(dataset_id, col_idx, visible, alias)
+             VALUES (?1, ?2, ?3, '')",
+        )
+        .context("failed to prepare column visibility insert")?;
+
+    for (col_idx, visible) in visibility {
+        insert_visibility
+            .execute(params![dataset_id, *col_idx, i64::from(*visible)])
+            .context("failed to insert column visibility")?;
+    }
+
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn upsert_column_visibility(
+    db_path: &Path,
+    dataset_id: i64,
+    visibility: &BTreeMap<i64, bool>,
+) -> Result<()> {
+    let mut conn = open_connection(db_path)?;
+    let tx = conn
+        .transaction()
+        .context("failed to start column visibility transaction")?;
+
+    // Aliases live in the same rows; carry them over for the columns being rewritten.
+    let aliases = {
+        let mut stmt = tx
+            .prepare("SELECT col_idx, alias FROM column_visibility WHERE dataset_id = ?1")
+            .context("failed to prepare column alias query")?;
+        let alias_iter = stmt
+            .query_map([dataset_id], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })
+            .context("failed to query column aliases")?;
+        let mut aliases = BTreeMap::new();
+        for item in alias_iter {
+            let (col_idx, alias) = item.context("failed to read column alias row")?;
+            aliases.insert(col_idx, alias);
+        }
+        aliases
+    };
+
+    tx.execute(
+        "DELETE FROM column_visibility WHERE dataset_id = ?1",
+        [dataset_id],
+    )
+    .context("failed to clear existing column visibility")?;
+
+    let mut insert_stmt = tx
+        .prepare(
+            "INSERT INTO column_visibility(dataset_id, col_idx, visible, alias)
+             VALUES (?1, ?2, ?3, ?4)",
+        )
+        .context("failed to prepare column visibility insert")?;
+
+    for (col_idx, visible) in visibility {
+        let value = if *visible { 1 } else { 0 };
+        let alias = aliases.get(col_idx).map(String::as_str).unwrap_or("");
+        insert_stmt
+            .execute(params![dataset_id, *col_idx, value, alias])
+            .context("failed to insert column visibility")?;
+    }
+
+    drop(insert_stmt);
+    tx.commit()
+        .context("failed to commit column visibility updates")?;
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn load_column_visibility(db_path: &Path, dataset_id: i64) -> Result<BTreeMap<i64, bool>> {
+    let conn = open_connection(db_path)?;
+    column_visibility_with(&conn, dataset_id)
+}
+
+fn column_visibility_with(conn: &Connection, dataset_id: i64) -> Result<BTreeMap<i64, bool>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT col_idx, visible
+             FROM column_visibility
+             WHERE dataset_id = ?1
+             ORDER BY col_idx ASC",
+        )
+        .context("failed to prepare column visibility query")?;
+
+    let visibility_iter = stmt
+        .query_map([dataset_id], |row| {
+            let col_idx: i64 = row.get(0)?;
+            let visible: i64 = row.get(1)?;
+            Ok((col_idx, visible != 0))
+        })
+        .context("failed to query column visibility")?;
+
+    let mut visibility = BTreeMap::new();
+    for item in visibility_iter {
+        let (col_idx, visible) = item.context("failed to read column visibility row")?;
+        visibility.insert(col_idx, visible);
+    }
+
+    Ok(visibility)
+}
+
+#[allow(dead_code)]
+pub fn save_visibility_preset(
+    db_path: &Path,
+    dataset_id: i64,
+    name: &str,
+    visibility: &BTreeMap<i64, bool>,
+) -> Result<()> {
+    let mut conn = open_connection(db_path)?;
+    let tx = conn
+        .transaction()
+        .context("failed to start visibility preset transaction")?;
+
+    tx.execute(
+        "DELETE FROM column_visibility_preset WHERE dataset_id = ?1 AND name = ?2",
+        params![dataset_id, name],
+    )
+    .context("failed to clear existing visibility preset")?;
+
+    let mut insert_stmt = tx
+        .prepare(
+            "INSERT INTO column_visibility_preset(dataset_id, name, col_idx, visible)
+             VALUES (?1, ?2, ?3, ?4)",
+        )
+        .context("failed to prepare visibility preset insert")?;
+
+    for (col_idx, visible) in visibility {
+        let value = if *visible { 1 } else { 0 };
+        insert_stmt
+            .execute(params![dataset_id, name, *col_idx, value])
+            .context("failed to insert visibility preset")?;
+    }
+
+    drop(insert_stmt);
+    tx.commit().context("failed to commit visibility preset")?;
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn delete_visibility_preset(db_path: &Path, dataset_id: i64, name: &str) -> Result<()> {
+    let conn = open_connection(db_path)?;
+    conn.execute(
+        "DELETE FROM column_visibility_preset WHERE dataset_id = ?1 AND name = ?2",
+        params![dataset_id, name],
+    )
+    .with_context(|| format!("failed to delete visibility preset {name}"))?;
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn load_visibility_presets(
+    db_path: &Path,
+    dataset_id: i64,
+) -> Result<BTreeMap<String, BTreeMap<i64, bool>>> {
+    let conn = open_connection(db_path)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT name, col_idx, visible
+             FROM column_visibility_preset
+             WHERE dataset_id = ?1
+             ORDER BY name ASC, col_idx ASC",
+        )
+        .context("failed to prepare visibility preset query")?;
+
+    let preset_iter = stmt
+        .query_map([dataset_id], |row| {
+            let name: String = row.get(0)?;
+            let col_idx: i64 = row.get(1)?;
+            let visible: i64 = row.get(2)?;
+            Ok((name, col_idx, visible != 0))
+        })
+        .context("failed to query visibility presets")?;
+
+    let mut presets: BTreeMap<String, BTreeMap<i64, bool>> = BTreeMap::new();
+    for item in preset_iter {
+        let (name, col_idx, visible) = item.context("failed to read visibility preset row")?;
+        presets.entry(name).or_default().insert(col_idx, visible);
+    }
+
+    Ok(presets)
+}
+
+/// Stores display aliases next to the visibility flags. Columns without a visibility row
+/// yet are inserted as visible.
+#[allow(dead_code)]
+pub fn upsert_column_aliases(
+    db_path: &Path,
+    dataset_id: i64,
+    aliases: &BTreeMap<i64, String>,
+) -> Result<()> {
+    let mut conn = open_connection(db_path)?;
+    let tx = conn
+        .transaction()
+        .context("failed to start column alias transaction")?;
+
+    tx.execute(
+        "UPDATE column_visibility SET alias = '' WHERE dataset_id = ?1",
+        [dataset_id],
+    )
+    .context("failed to clear existing column aliases")?;
+
+    let mut upsert_stmt = tx
+        .prepare(
+            "INSERT INTO column_visibility(dataset_id, col_idx, visible, alias)
+             VALUES (?1, ?2, 1, ?3)
+             ON CONFLICT(dataset_id, col_idx) DO UPDATE SET alias = excluded.alias",
+        )
+        .context("failed to prepare column alias upsert")?;
+
+    for (col_idx, alias) in aliases {
+        let alias = alias.trim();
+        if alias.is_empty() {
+            continue;
+        }
+        upsert_stmt
+            .execute(params![dataset_id, *col_idx, alias])
+            .context("failed to upsert column alias")?;
+    }
+
+    drop(upsert_stmt);
+    tx.commit()
+        .context("failed to commit column alias updates")?;
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn load_column_aliases(db_path: &Path, dataset_id: i64) -> Result<BTreeMap<i64, String>> {
+    let conn = open_connection(db_path)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT col_idx, alias
+             FROM column_visibility
+             WHERE dataset_id = ?1 AND alias <> ''
+             ORDER BY col_idx ASC",
+        )
+        .context("failed to prepare column alias query")?;
+
+    let alias_iter = stmt
+        .query_map([dataset_id], |row| {
+            let col_idx: i64 = row.get(0)?;
+            let alias: String = row.get(1)?;
+            Ok((col_idx, alias))
+        })
+        .context("failed to query column aliases")?;
+
+    let mut aliases = BTreeMap::new();
+    for item in alias_iter {
+        let (col_idx, alias) = item.context("failed to read column alias row")?;
+        aliases.insert(col_idx, alias);
+    }
+
+    Ok(aliases)
+}
+
+#[allow(dead_code)]
+pub fn upsert_column_formats(
+    db_path: &Path,
+    dataset_id: i64,
+    formats: &BTreeMap<i64, ColumnFormat>,
+) -> Result<()> {
+    let mut conn = open_connection(db_path)?;
+    let tx = conn
+        .transaction()
+        .context("failed to start column format transaction")?;
+
+    tx.execute(
+        "DELETE FROM column_visibility_preset WHERE dataset_id = ?1",
+        params![dataset_id],
+    )
+    .with_context(|| format!("failed to delete visibility presets for dataset #{dataset_id}"))?;
+    tx.execute(
+        "DELETE FROM column_format WHERE dataset_id = ?1",
+        [dataset_id],
+    )
+    .context("failed to clear existing column formats")?;
+
+    let mut insert_stmt = tx
+        .prepare(
+            "INSERT INTO column_format(
+                dataset_id, col_idx, decimals, percent, thousands_separator, currency_symbol
+             )
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )
+        .context("failed to prepare column format insert")?;
+
+    for (col_idx, format) in formats {
+        insert_stmt
+            .execute(params![
+                dataset_id,
+                *col_idx,
+                format.decimals as i64,
+                format.percent as i64,
+                format.thousands_separator as i64,
+                format.currency_symbol
+            ])
+            .context("failed to insert column format")?;
+    }
+
+    drop(insert_stmt);
+    tx.commit()
+        .context("failed to commit column format updates")?;
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn load_column_formats(db_path: &Path, dataset_id: i64) -> Result<BTreeMap<i64, ColumnFormat>> {
+    let conn = open_connection(db_path)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT col_idx, decimals, percent, thousands_separator, currency_symbol
+             FROM column_format
+             WHERE dataset_id = ?1
+             ORDER BY col_idx ASC",
+        )
+        .context("failed to prepare column format query")?;
+
+    let format_iter = stmt
+        .query_map([dataset_id], |row| {
+            let col_idx: i64 = row.get(0)?;
+            let decimals: i64 = row.get(1)?;
+            let percent: i64 = row.get(2)?;
+            let thousands_separator: i64 = row.get(3)?;
+            let currency_symbol: String = row.get(4)?;
+            Ok((
+                col_idx,
+                ColumnFormat {
+                    decimals: decimals.max(0) as u32,
+                    percent: percent != 0,
+                    thousands_separator: thousands_separator != 0,
+                    currency_symbol,
+                },
+            ))
+        })
+        .context("failed to query column formats")?;
+
+    let mut formats = BTreeMap::new();
+    for item in format_iter {
+        let (col_idx, format) = item.context("failed to read column format row")?;
+        formats.insert(col_idx, format);
+    }
+
+    Ok(formats)
+}
+
+pub fn load_column_types(db_path: &Path, dataset_id: i64) -> Result<BTreeMap<i64, ColumnType>> {
+    let conn = open_connection(db_path)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT col_idx, type
+             FROM column_type
+             WHERE dataset_id = ?1
+             ORDER BY col_idx ASC",
+        )
+        .context("failed to prepare column type query")?;
+
+    let rows = stmt
+        .query_map([dataset_id], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })
+        .context("failed to query column types")?;
+
+    let mut types = BTreeMap::new();
+    for item in rows {
+        let (col_idx, value) = item.context("failed to read column type row")?;
+        if let Some(column_type) = ColumnType::parse(&value) {
+            types.insert(col_idx, column_type);
+        }
+    }
+
+    Ok(types)
+}
+
+/// Sets the type of each column in `types`; other columns keep theirs.
+pub fn upsert_column_types(
+    db_path: &Path,
+    dataset_id: i64,
+    types: &BTreeMap<i64, ColumnType>,
+) -> Result<()> {
+    let mut conn = open_connection(db_path)?;
+    let tx = conn
+        .transaction()
+        .context("failed to start column type transaction")?;
+
+    let mut upsert_stmt = tx
+        .prepare(
+            "INSERT INTO column_type(dataset_id, col_idx, type)
+             VALUES (?1, ?2, ?3)
+             ON CONFLICT(dataset_id, col_idx) DO UPDATE SET type = excluded.type",
+        )
+        .context("failed to prepare column type upsert")?;
+    for (col_idx, column_type) in types {
+        upsert_stmt
+            .execute(params![dataset_id, *col_idx, column_type.as_str()])
+            .context("failed to upsert column type")?;
+    }
+
+    drop(upsert_stmt);
+    tx.commit()
+        .context("failed to commit column type updates")?;
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn upsert_holdings_flag(db_path: &Path, dataset_id: i64, is_holdings: bool) -> Result<()> {
+    let conn = open_connection(db_path)?;
+    let value = if is_holdings { 1 } else { 0 };
+    conn.execute(
+        "INSERT INTO dataset_flag(dataset_id, is_holdings)
+         VALUES (?1, ?2)
+         ON CONFLICT(dataset_id) DO UPDATE SET is_holdings = excluded.is_holdings",
+        params![dataset_id, value],
+    )
+    .context("failed to upsert holdings flag")?;
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn load_holdings_flags(db_path: &Path) -> Result<BTreeMap<i64, bool>> {
+    let conn = open_connection(db_path)?;
+    holdings_flags_with(&conn)
+}
+
+fn holdings_flags_with(conn: &Connection) -> Result<BTreeMap<i64, bool>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT dataset_id, is_holdings
+             FROM dataset_flag",
+        )
+        .context("failed to prepare holdings flag query")?;
+
+    let mut flags = BTreeMap::new();
+    let rows = stmt
+        .query_map([], |row| {
+            let dataset_id: i64 = row.get(0)?;
+            let is_holdings: i64 = row.get(1)?;
+            Ok((dataset_id, is_holdings != 0))
+        })
+        .context("failed to query holdings flags")?;
+
+    for row in rows {
+        let (dataset_id, is_holdings) = row.context("failed to read holdings flag row")?;
+        flags.insert(dataset_id, is_holdings);
+    }
+
+    Ok(flags)
+}
+
+/// The dataset's display currency and unit; the default when none is stored.
+pub fn load_dataset_display(db_path: &Path, dataset_id: i64) -> Result<DatasetDisplay> {
+    let conn = open_connection(db_path)?;
+    let stored = conn
+        .query_row(
+            "SELECT currency, unit FROM dataset_display WHERE dataset_id = ?1",
+            params![dataset_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )
+        .optional()
+        .context("failed to load dataset display")?;
+    Ok(match stored {
+        Some((currency, unit)) => DatasetDisplay {
+            currency,
+            unit: DisplayUnit::parse(&unit).unwrap_or_default(),
+        },
+        None => DatasetDisplay::default(),
+    })
+}
+
+pub fn save_dataset_display(
+    db_path: &Path,
+    dataset_id: i64,
+    display: &DatasetDisplay,
+) -> Result<()> {
+    let conn = open_connection(db_path)?;
+    conn.execute(
+        "INSERT INTO dataset_display(dataset_id, currency, unit)
+         VALUES (?1, ?2, ?3)
+         ON CONFLICT(dataset_id) DO UPDATE SET
+             currency = excluded.currency,
+             unit = excluded.unit",
+        params![dataset_id, display.currency, display.unit.as_str()],
+    )
+    .context("failed to save dataset display")?;
+    Ok(())
+}
+
+/// Rows per page chosen for the dataset, if one was chosen.
+pub fn load_page_size(db_path: &Path, dataset_id: i64) -> Result<Option<i64>> {
+    let conn = open_connection(db_path)?;
+    conn.query_row(
+        "SELECT page_size FROM dataset_page_size WHERE dataset_id = ?1",
+        params![dataset_id],
+        |row| row.get(0),
+    )
+    .optional()
+    .context("failed to load page size")
+}
+
+/// Stores the dataset's page size; `None` returns it to the default.
+pub fn save_page_size(db_path: &Path, dataset_id: i64, page_size: Option<i64>) -> Result<()> {
+    let conn = open_connection(db_path)?;
+    match page_size {
+        Some(page_size) => conn.execute(
+            "INSERT INTO dataset_page_size(dataset_id, page_size) VALUES (?1, ?2)
+             ON CONFLICT(dataset_id) DO UPDATE SET page_size = excluded.page_size",
+            params![dataset_id, page_size.max(1)],
+        ),
+        None => conn.execute(
+            "DELETE FROM dataset_page_size WHERE dataset_id = ?1",
+            params![dataset_id],
+        ),
+    }
+    .context("failed to save page size")?;
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn rename_dataset(db_path: &Path, dataset_id: i64, name: &str) -> Result<()> {
+    let conn = open_connection(db_path)?;
+    conn.execute(
+        "UPDATE dataset SET name = ?1 WHERE id = ?2",
+        params![name, dataset_id],
+    )
+    .context("failed to rename dataset")?;
+    Ok(())
+}
+
+pub fn set_dataset_kind(db_path: &Path, dataset_id: i64, kind: DatasetKind) -> Result<()> {
+    let conn = open_connection(db_path)?;
+    conn.execute(
+        "UPDATE dataset SET kind = ?1 WHERE id = ?2",
+        params![kind.as_str(), dataset_id],
+    )
+    .context("failed to set dataset kind")?;
+    Ok(())
+}
+
+pub fn load_setting(db_path: &Path, key: &str) -> Result<Option<String>> {
+    let conn = open_connection(db_path)?;
+    let mut stmt = conn
+        .prepare("SELECT value FROM app_setting WHERE key = ?1")
+        .context("failed to prepare setting query")?;
+    let mut rows = stmt
+        .query(params![key])
+        .context("failed to query setting")?;
+    match rows.next().context("failed to read setting")? {
+        Some(row) => Ok(Some(row.get(0).context("failed to read setting value")?)),
+        None => Ok(None),
+    }
+}
+
+pub fn save_setting(db_path: &Path, key: &str, value: &str) -> Result<()> {
+    let conn = open_connection(db_path)?;
+    conn.execute(
+        "INSERT INTO app_setting (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![key, value],
+    )
+    .context("failed to save setting")?;
+    Ok(())
+}
+
+/// Every saved import profile, by name, with its columns in output order.
+pub fn load_import_profiles(db_path: &Path) -> Result<Vec<ImportProfile>> {
+    let conn = open_connection(db_path)?;
+    let mut stmt = conn
+        .prepare("SELECT name, header_row, encoding FROM import_profile ORDER BY name ASC")
+        .context("failed to prepare import profile query")?;
+    let mut profiles = stmt
+        .query_map([], |row| {
+            Ok(ImportProfile {
+                name: row.get(0)?,
+                header_row: row.get::<_, i64>(1)?.max(0) as usize,
+                encoding: row.get(2)?,
+                columns: Vec::new(),
+            })
+        })
+        .context("failed to query import profiles")?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to read import profile row")?;
+
+    let mut column_stmt = conn
+        .prepare(
+            "SELECT source, target FROM import_profile_column
+             WHERE profile_name = ?1
+             ORDER BY position ASC",
+        )
+        .context("failed to prepare import profile column query")?;
+    for profile in &mut profiles {
+        profile.columns = column_stmt
+            .query_map([&profile.name], |row| {
+                Ok(ColumnMapping {
+                    source: row.get(0)?,
+                    target: row.get(1)?,
+                })
+            })
+            .context("failed to query import profile columns")?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("failed to read import profile column row")?;
+    }
+    Ok(profiles)
+}
+
+/// Stores `profile`, replacing a saved profile of the same name.
+pub fn save_import_profile(db_path: &Path, profile: &ImportProfile) -> Result<()> {
+    let mut conn = open_connection(db_path)?;
+    let tx = conn
+        .transaction()
+        .context("failed to start import profile transaction")?;
+
+    tx.execute(
+        "DELETE FROM import_profile_column WHERE profile_name = ?1",
+        params![profile.name],
+    )
+    .context("failed to clear existing import profile columns")?;
+    tx.execute(
+        "INSERT INTO import_profile(name, header_row, encoding) VALUES (?1, ?2, ?3)
+         ON CONFLICT(name) DO UPDATE SET
+             header_row = excluded.header_row,
+             encoding = excluded.encoding",
+        params![profile.name, profile.header_row as i64, profile.encoding],
+    )
+    .context("failed to save import profile")?;
+
+    let mut insert_stmt = tx
+        .prepare(
+            "INSERT INTO import_profile_column(profile_name, position, source, target)
+             VALUES (?1, ?2, ?3, ?4)",
+        )
+        .context("failed to prepare import profile column insert")?;
+    for (position, mapping) in profile.columns.iter().enumerate() {
+        insert_stmt
+            .execute(params![
+                profile.name,
+                position as i64,
+                mapping.source,
+                mapping.target
+            ])
+            .context("failed to insert import profile column")?;
+    }
+
+    drop(insert_stmt);
+    tx.commit().context("failed to commit import profile")?;
+    Ok(())
+}
+
+pub fn delete_import_profile(db_path: &Path, name: &str) -> Result<()> {
+    let conn = open_connection(db_path)?;
+    conn.execute(
+        "DELETE FROM import_profile_column WHERE profile_name = ?1",
+        params![name],
+    )
+    .with_context(|| format!("failed to delete import profile columns {name}"))?;
+    conn.execute("DELETE FROM import_profile WHERE name = ?1", params![name])
+        .with_context(|| format!("failed to delete import profile {name}"))?;
+    Ok(())
+}
+
+/// The column set saved for `kind` in 設定, or `None` while it uses the built-in one.
+pub fn load_column_set(db_path: &Path, kind: DatasetKind) -> Result<Option<ColumnSet>> {
+    let conn = open_connection(db_path)?;
+    let saved = conn
+        .query_row(
+            "SELECT 1 FROM column_set WHERE kind = ?1",
+            params![kind.as_str()],
+            |_| Ok(()),
+        )
+        .optional()
+        .context("failed to load column set")?;
+    if saved.is_none() {
+        return Ok(None);
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT role, name FROM column_set_column
+             WHERE kind = ?1
+             ORDER BY role ASC, position ASC",
+        )
+        .context("failed to prepare column set query")?;
+    let columns = stmt
+        .query_map(params![kind.as_str()], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .context("failed to query column set")?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to read column set row")?;
+    let mut set = ColumnSet::default();
+    for (role, name) in columns {
+        match role.as_str() {
+            "required" => set.required.push(name),
+            "derived" => set.derived.push(name),
+            _ => {}
+        }
+    }
+    Ok(Some(set))
+}
+
+pub fn save_column_set(db_path: &Path, kind: DatasetKind, set: &ColumnSet) -> Result<()> {
+    let mut conn = open_connection(db_path)?;
+    let tx = conn
+        .transaction()
+        .context("failed to start column set transaction")?;
+    tx.execute(
+        "DELETE FROM column_set_column WHERE kind = ?1",
+        params![kind.as_str()],
+    )
+    .context("failed to clear existing column set")?;
+    tx.execute(
+        "INSERT INTO column_set(kind) VALUES (?1) ON CONFLICT(kind) DO NOTHING",
+        params![kind.as_str()],
+    )
+    .context("failed to save column set")?;
+
+    let mut insert_stmt = tx
+        .prepare(
+            "INSERT INTO column_set_column(kind, role, position, name)
+             VALUES (?1, ?2, ?3, ?4)",
+        )
+        .context("failed to prepare column set insert")?;
+    for (role, names) in [("required", &set.required), ("derived", &set.derived)] {
+        for (position, name) in names.iter().enumerate() {
+            insert_stmt
+                .execute(params![kind.as_str(), role, position as i64, name])
+                .context("failed to insert column set column")?;
+        }
+    }
+
+    drop(insert_stmt);
+    tx.commit().context("failed to commit column set")?;
+    Ok(())
+}
+
+/// Forgets the column set saved for `kind`, so the built-in one applies again.
+pub fn delete_column_set(db_path: &Path, kind: DatasetKind) -> Result<()> {
+    let conn = open_connection(db_path)?;
+    conn.execute(
+        "DELETE FROM column_set_column WHERE kind = ?1",
+        params![kind.as_str()],
+    )
+    .context("failed to delete column set columns")?;
+    conn.execute(
+        "DELETE FROM column_set WHERE kind = ?1",
+        params![kind.as_str()],
+    )
+    .context("failed to delete column set")?;
+    Ok(())
+}
+
+/// Distinct non-blank values of one column with how many rows hold each, most common
+/// first. Used for the quick-filter chips.
+pub fn column_value_counts(
+    db_path: &Path,
+    dataset_id: i64,
+    col_idx: i64,
+) -> Result<Vec<(String, i64)>> {
+    let conn = open_connection(db_path)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT value, COUNT(*) FROM cell
+             WHERE dataset_id = ?1 AND col_idx = ?2 AND TRIM(value) <> ''
+             GROUP BY value
+             ORDER BY COUNT(*) DESC, value ASC",
+        )
+        .context("failed to prepare column value count query")?;
+    let counts = stmt
+        .query_map(params![dataset_id, col_idx], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .context("failed to query column value counts")?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to read column value count row")?;
+    Ok(counts)
+}
+
+/// Columns left blank in every row that passes the filters of `options`. Empty when no
+/// row passes them, so a filter that matches nothing does not hide the whole table.
+pub fn empty_columns(
+    db_path: &Path,
+    dataset_id: i64,
+    options: &QueryOptions,
+) -> Result<BTreeSet<i64>> {
+    if !load_computed_columns(db_path, dataset_id)?.is_empty() {
+        // Computed values exist only once a row is read, so scan the filtered rows.
+        let (columns, rows, _) = query_page(db_path, dataset_id, 0, i64::MAX, options)?;
+        if rows.is_empty() {
+            return Ok(BTreeSet::new());
+        }
+        return Ok((0..columns.len())
+            .filter(|col_idx| rows.iter().all(|row| row[*col_idx].trim().is_empty()))
+            .map(|col_idx| col_idx as i64)
+            .collect());
+    }
+
+    let conn = open_connection(db_path)?;
+    let (where_sql, filter_params) = row_filter_sql(dataset_id, options);
+    let any_row: bool = conn
+        .query_row(
+            &format!("SELECT EXISTS (SELECT 1 FROM cell base WHERE {where_sql})"),
+            rusqlite::params_from_iter(filter_params.iter().cloned()),
+            |row| row.get(0),
+        )
+        .context("failed to query filtered rows")?;
+    if !any_row {
+        return Ok(BTreeSet::new());
+    }
+    let mut params = vec![Value::Integer(dataset_id)];
+    params.extend(filter_params);
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT col.col_idx FROM column_name col
+             WHERE col.dataset_id = ?
+               AND NOT EXISTS (
+                   SELECT 1 FROM cell base
+                   WHERE {where_sql}
+                     AND base.col_idx = col.col_idx
+                     AND TRIM(base.value) <> ''
+               )
+             ORDER BY col.col_idx ASC"
+        ))
+        .context("failed to prepare empty column query")?;
+    let columns = stmt
+        .query_map(rusqlite::params_from_iter(params), |row| row.get(0))
+        .context("failed to query empty columns")?
+        .collect::<rusqlite::Result<BTreeSet<i64>>>()
+        .context("failed to read empty column row")?;
+    Ok(columns)
+}
+
+/// Statistics of each column over the rows that pass the filters of `options`. Columns
+/// with no cell in those rows are left out.
+pub fn column_stats(
+    db_path: &Path,
+    dataset_id: i64,
+    options: &QueryOptions,
+) -> Result<BTreeMap<i64, ColumnStats>> {
+    if !load_computed_columns(db_path, dataset_id)?.is_empty() {
+        // Computed values exist only once a row is read, so scan the filtered rows.
+        let (columns, rows, _) = query_page(db_path, dataset_id, 0, i64::MAX, options)?;
+        if rows.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+        return Ok((0..columns.len())
+            .map(|col_idx| {
+                let mut stats = ColumnStats::default();
+                for cell in rows.iter().map(|row| row[col_idx].as_str()) {
+                    if cell.trim().is_empty() {
+                        continue;
+                    }
+                    stats.nonempty += 1;
+                    if let Some(number) = parse_numeric_value(cell) {
+                        stats.numeric += 1;
+                        stats.min = Some(stats.min.map_or(number, |min| min.min(number)));
+                        stats.max = Some(stats.max.map_or(number, |max| max.max(number)));
+                        stats.sum += number;
+                    }
+                }
+                (col_idx as i64, stats)
+            })
+            .collect());
+    }
+
+    let conn = open_connection(db_path)?;
+    let (where_sql, filter_params) = row_filter_sql(dataset_id, options);
+    let number = numeric_cell_sql("base.value");
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT stats.col_idx,
+                    COUNT(CASE WHEN TRIM(stats.value) <> '' THEN 1 END),
+                    COUNT(stats.number),
+                    MIN(stats.number),
+                    MAX(stats.number),
+                    TOTAL(stats.number)
+             FROM (
+                 SELECT base.col_idx, base.value, {number} AS number
+                 FROM cell base
+                 WHERE {where_sql}
+             ) stats
+             GROUP BY stats.col_idx
+             ORDER BY stats.col_idx ASC"
+        ))
+        .context("failed to prepare column stats query")?;
+    let stats = stmt
+        .query_map(rusqlite::params_from_iter(filter_params), |row| {
+            Ok((
+                row.get(0)?,
+                ColumnStats {
+                    nonempty: row.get(1)?,
+                    numeric: row.get(2)?,
+                    min: row.get(3)?,
+                    max: row.get(4)?,
+                    sum: row.get(5)?,
+                },
+            ))
+        })
+        .context("failed to query column stats")?
+        .collect::<rusqlite::Result<BTreeMap<i64, ColumnStats>>>()
+        .context("failed to read column stats row")?;
+    Ok(stats)
+}
+
+/// Computed columns of a dataset, in the order they are appended.
+pub fn load_computed_columns(db_path: &Path, dataset_id: i64) -> Result<Vec<ComputedColumn>> {
+    let conn = open_connection(db_path)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT name, expression FROM computed_column
+             WHERE dataset_id = ?1
+             ORDER BY position ASC",
+        )
+        .context("failed to prepare computed column query")?;
+    let columns = stmt
+        .query_map([dataset_id], |row| {
+            Ok(ComputedColumn {
+                name: row.get(0)?,
+                expression: row.get(1)?,
+            })
+        })
+        .context("failed to query computed columns")?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to read computed column row")?;
+    Ok(columns)
+}
+
+/// Replaces the computed columns of a dataset with `columns`. Bumps the dataset revision,
+/// since its pages change even though no cell did.
+pub fn save_computed_columns(
+    db_path: &Path,
+    dataset_id: i64,
+    columns: &[ComputedColumn],
+) -> Result<()> {
+    let mut conn = open_connection(db_path)?;
+    let tx = conn
+        .transaction()
+        .context("failed to start computed column transaction")?;
+    tx.execute(
+        "DELETE FROM computed_column WHERE dataset_id = ?1",
+        params![dataset_id],
+    )
+    .context("failed to clear computed columns")?;
+    let mut insert_stmt = tx
+        .prepare(
+            "INSERT INTO computed_column(dataset_id, position, name, expression)
+             VALUES (?1, ?2, ?3, ?4)",
+        )
+        .context("failed to prepare computed column insert")?;
+    for (position, column) in columns.iter().enumerate() {
+        insert_stmt
+            .execute(params![
+                dataset_id,
+                position as i64,
+                column.name,
+                column.expression
+            ])
+            .with_context(|| format!("failed to insert computed column {}", column.name))?;
+    }
+    drop(insert_stmt);
+    tx.execute(
+        "UPDATE dataset SET revision = revision + 1 WHERE id = ?1",
+        params![dataset_id],
+    )
+    .context("failed to bump dataset revision")?;
+    tx.commit().context("failed to commit computed columns")?;
+    Ok(())
+}
+
+/// Owner shares of the co-owned rows of a dataset.
+pub fn load_owner_splits(db_path: &Path, dataset_id: i64) -> Result<OwnerSplits> {
+    let conn = open_connection(db_path)?;
+    owner_splits_with(&conn, dataset_id)
+}
+
+fn owner_splits_with(conn: &Connection, dataset_id: i64) -> Result<OwnerSplits> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT row_idx, owner, percent FROM owner_share
+             WHERE dataset_id = ?1
+             ORDER BY row_idx ASC, position ASC",
+        )
+        .context("failed to prepare owner share query")?;
+    let shares = stmt
+        .query_map([dataset_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                OwnerShare {
+                    owner: row.get(1)?,
+                    percent: row.get(2)?,
+                },
+            ))
+        })
+        .context("failed to query owner shares")?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to read owner share row")?;
+    let mut splits = OwnerSplits::new();
+    for (row_idx, share) in shares {
+        splits.entry(row_idx as usize).or_default().push(share);
+    }
+    Ok(splits)
+}
+
+fn insert_owner_shares(
+    tx: &rusqlite::Transaction,
+    dataset_id: i64,
+    row_idx: usize,
+    shares: &[OwnerShare],
+) -> Result<()> {
+    let mut insert_stmt = tx
+        .prepare(
+            "INSERT INTO owner_share(dataset_id, row_idx, position, owner, percent)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )
+        .context("failed to prepare owner share insert")?;
+    for (position, share) in shares.iter().enumerate() {
+        insert_stmt
+            .execute(params![
+                dataset_id,
+                row_idx as i64,
+                position as i64,
+                share.owner,
+                share.percent
+            ])
+            .with_context(|| format!("failed to insert owner share of {}", share.owner))?;
+    }
+    Ok(())
+}
+
+/// Replaces the owner shares of one row; no shares gives the row back to its 所有權人.
+/// Bumps the dataset revision, since per-owner summaries change even though no cell did.
+pub fn save_owner_shares(
+    db_path: &Path,
+    dataset_id: i64,
+    row_idx: usize,
+    shares: &[OwnerShare],
+) -> Result<()> {
+    let mut conn = open_connection(db_path)?;
+    let tx = conn
+        .transaction()
+        .context("failed to start owner share transaction")?;
+    tx.execute(
+        "DELETE FROM owner_share WHERE dataset_id = ?1 AND row_idx = ?2",
+        params![dataset_id, row_idx as i64],
+    )
+    .context("failed to clear owner shares")?;
+    insert_owner_shares(&tx, dataset_id, row_idx, shares)?;
+    tx.execute(
+        "UPDATE dataset SET revision = revision + 1 WHERE id = ?1",
+        params![dataset_id],
+    )
+    .context("failed to bump dataset revision")?;
+    tx.commit().context("failed to commit owner shares")?;
+    Ok(())
+}
+
+/// Every saved report, by name.
+pub fn load_saved_reports(db_path: &Path) -> Result<Vec<SavedReport>> {
+    let conn = open_connection(db_path)?;
+    let mut stmt = conn
+        .prepare("SELECT name, script FROM saved_report ORDER BY name ASC")
+        .context("failed to prepare saved report query")?;
+    let reports = stmt
+        .query_map([], |row| {
+            Ok(SavedReport {
+                name: row.get(0)?,
+                script: row.get(1)?,
+            })
+        })
+        .context("failed to query saved reports")?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to read saved report row")?;
+    Ok(reports)
+}
+
+/// Stores `report`, replacing a saved report of the same name.
+pub fn save_saved_report(db_path: &Path, report: &SavedReport) -> Result<()> {
+    let conn = open_connection(db_path)?;
+    conn.execute(
+        "INSERT INTO saved_report(name, script) VALUES (?1, ?2)
+         ON CONFLICT(name) DO UPDATE SET script = excluded.script",
+        params![report.name, report.script],
+    )
+    .context("failed to save report")?;
+    Ok(())
+}
+
+pub fn delete_saved_report(db_path: &Path, name: &str) -> Result<()> {
+    let conn = open_connection(db_path)?;
+    conn.execute("DELETE FROM saved_report WHERE name = ?1", params![name])
+        .with_context(|| format!("failed to delete report {name}"))?;
+    Ok(())
+}
+
+/// The 定存 rate table, in the order it was saved.
+pub fn load_deposit_rates(db_path: &Path) -> Result<Vec<DepositRate>> {
+    let conn = open_connection(db_path)?;
+    let mut stmt = conn
+        .prepare("SELECT institution, tenor, rate FROM deposit_rate ORDER BY position ASC")
+        .context("failed to prepare deposit rate query")?;
+    let rates = stmt
+        .query_map([], |row| {
+            Ok(DepositRate {
+                institution: row.get(0)?,
+                tenor: row.get(1)?,
+                rate: row.get(2)?,
+            })
+        })
+        .context("failed to query deposit rates")?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to read deposit rate row")?;
+    Ok(rates)
+}
+
+/// Replaces the 定存 rate table with `rates`. Bumps every dataset revision, since 資產總表
+/// summaries derive their 定存 interest from the table.
+pub fn save_deposit_rates(db_path: &Path, rates: &[DepositRate]) -> Result<()> {
+    let mut conn = open_connection(db_path)?;
+    let tx = conn
+        .transaction()
+        .context("failed to start deposit rate transaction")?;
+    tx.execute("DELETE FROM deposit_rate", [])
+        .context("failed to clear deposit rates")?;
+    let mut insert_stmt = tx
+        .prepare(
+            "INSERT INTO deposit_rate(position, institution, tenor, rate) VALUES (?1, ?2, ?3, ?4)",
+        )
+        .context("failed to prepare deposit rate insert")?;
+    for (position, rate) in rates.iter().enumerate() {
+        insert_stmt
+            .execute(params![
+                position as i64,
+                rate.institution,
+                rate.tenor,
+                rate.rate
+            ])
+            .with_context(|| {
+                format!(
+                    "failed to insert deposit rate {} {}",
+                    rate.institution, rate.tenor
+                )
+            })?;
+    }
+    drop(insert_stmt);
+    tx.execute("UPDATE dataset SET revision = revision + 1", [])
+        .context("failed to bump dataset revisions")?;
+    tx.commit().context("failed to commit deposit rates")?;
+    Ok(())
+}
+
+/// Adds fetched `prices` to the price history. A 代號 fetched twice at the same time
+/// keeps the later price.
+pub fn record_prices(db_path: &Path, prices: &[PricePoint]) -> Result<()> {
+    let mut conn = open_connection(db_path)?;
+    let tx = conn
+        .transaction()
+        .context("failed to start price history transaction")?;
+    let mut insert_stmt = tx
+        .prepare(
+            "INSERT OR REPLACE INTO price_history(code, fetched_at, price) VALUES (?1, ?2, ?3)",
+        )
+        .context("failed to prepare price history insert")?;
+    for point in prices {
+        insert_stmt
+            .execute(params![point.code, point.fetched_at, point.price])
+            .with_context(|| format!("failed to insert price of {}", point.code))?;
+    }
+    drop(insert_stmt);
+    tx.commit().context("failed to commit price history")?;
+    Ok(())
+}
+
+/// The stored prices of `codes`, oldest first. Codes without prices are left out.
+pub fn load_price_history(db_path: &Path, codes: &[String]) -> Result<PriceHistory> {
+    let conn = open_connection(db_path)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT fetched_at, price FROM price_history
+             WHERE code = ?1
+             ORDER BY fetched_at ASC",
+        )
+        .context("failed to prepare price history query")?;
+    let mut history = PriceHistory::new();
+    for code in codes {
+        let points = stmt
+            .query_map(params![code], |row| {
+                Ok(PricePoint {
+                    code: code.clone(),
+                    fetched_at: row.get(0)?,
+                    price: row.get(1)?,
+                })
+            })
+            .context("failed to query price history")?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("failed to read price history row")?;
+        if !points.is_empty() {
+            history.insert(code.clone(), points);
+        }
+    }
+    Ok(history)
+}
+
+/// Appends `record` to the import history. Datasets keep their history entries after
+/// they are deleted, since ids are never reused.
+pub fn record_import(db_path: &Path, record: &ImportRecord) -> Result<()> {
+    let mut conn = open_connection(db_path)?;
+    let tx = conn
+        .transaction()
+        .context("failed to start import history transaction")?;
+    tx.execute(
+        "INSERT INTO import_history(kind, source_path, file_hash, imported_at, duration_ms, warnings)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            record.kind.as_str(),
+            record.source_path,
+            record.file_hash,
+            record.imported_at,
+            record.duration_ms,
+            record.warnings.join("\n")
+        ],
+    )
+    .context("failed to insert import history")?;
+    let history_id = tx.last_insert_rowid();
+    let mut insert_sheet = tx
+        .prepare(
+            "INSERT INTO import_history_sheet(history_id, position, dataset_id, name, row_count)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )
+        .context("failed to prepare import history sheet insert")?;
+    for (position, sheet) in record.sheets.iter().enumerate() {
+        insert_sheet
+            .execute(params![
+                history_id,
+                position as i64,
+                sheet.dataset_id,
+                sheet.name,
+                sheet.row_count
+            ])
+            .context("failed to insert import history sheet")?;
+    }
+    drop(insert_sheet);
+    tx.commit().context("failed to commit import history")?;
+    Ok(())
+}
+
+/// Every recorded import and re-transform, newest first.
+pub fn load_import_history(db_path: &Path) -> Result<Vec<ImportRecord>> {
+    let conn = open_connection(db_path)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, kind, source_path, file_hash, imported_at, duration_ms, warnings
+             FROM import_history
+             ORDER BY id DESC",
+        )
+        .context("failed to prepare import history query")?;
+    let entries = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, i64>(5)?,
+                row.get::<_, String>(6)?,
+            ))
+        })
+        .context("failed to query import history")?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to read import history row")?;
+
+    let mut sheet_stmt = conn
+        .prepare(
+            "SELECT dataset_id, name, row_count FROM import_history_sheet
+             WHERE history_id = ?1
+             ORDER BY position ASC",
+        )
+        .context("failed to prepare import history sheet query")?;
+    let mut history = Vec::with_capacity(entries.len());
+    for (id, kind, source_path, file_hash, imported_at, duration_ms, warnings) in entries {
+        let sheets = sheet_stmt
+            .query_map(params![id], |row| {
+                Ok(ImportedSheet {
+                    dataset_id: row.get(0)?,
+                    name: row.get(1)?,
+                    row_count: row.get(2)?,
+                })
+            })
+            .context("failed to query import history sheets")?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("failed to read import history sheet row")?;
+        history.push(ImportRecord {
+            kind: ImportEventKind::parse(&kind).unwrap_or(ImportEventKind::Import),
+            source_path,
+            file_hash,
+            sheets,
+            imported_at,
+            duration_ms,
+            warnings: warnings
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect(),
+        });
+    }
+    Ok(history)
+}
+
+/// Keys of the alerts acknowledged in the alerts panel.
+pub fn load_acknowledged_alerts(db_path: &Path) -> Result<BTreeSet<String>> {
+    let conn = open_connection(db_path)?;
+    let mut stmt = conn
+        .prepare("SELECT key FROM alert_ack")
+        .context("failed to prepare alert acknowledgment query")?;
+    let keys = stmt
+        .query_map([], |row| row.get(0))
+        .context("failed to query alert acknowledgments")?
+        .collect::<rusqlite::Result<BTreeSet<String>>>()
+        .context("failed to read alert acknowledgment row")?;
+    Ok(keys)
+}
+
+pub fn acknowledge_alerts(db_path: &Path, keys: &[String]) -> Result<()> {
+    let mut conn = open_connection(db_path)?;
+    let tx = conn
+        .transaction()
+        .context("failed to start alert acknowledgment transaction")?;
+    let acknowledged_at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    for key in keys {
+        tx.execute(
+            "INSERT INTO alert_ack(key, acknowledged_at) VALUES (?1, ?2)
+             ON CONFLICT(key) DO NOTHING",
+            params![key, acknowledged_at],
+        )
+        .with_context(|| format!("failed to acknowledge alert {key}"))?;
+    }
+    tx.commit()
+        .context("failed to commit alert acknowledgments")?;
+    Ok(())
+}
+
+/// Bumped every time edits are written to the dataset's cells.
+pub fn dataset_revision(db_path: &Path, dataset_id: i64) -> Result<i64> {
+    let conn = open_connection(db_path)?;
+    conn.query_row(
+        "SELECT revision FROM dataset WHERE id = ?1",
+        params![dataset_id],
+        |row| row.get(0),
+    )
+    .with_context(|| format!("failed to read revision of dataset #{dataset_id}"))
+}
+
+/// How many computed summaries are kept per dataset; older ones are dropped on save. Enough
+/// for a year of daily scheduled snapshots.
+const SUMMARY_HISTORY_LIMIT: i64 = 400;
+
+/// Stores `report` as computed from the dataset's current revision.
+pub fn save_summary_report(
+    db_path: &Path,
+    dataset_id: i64,
+    report: &SummaryReport,
+) -> Result<StoredSummaryReport> {
+    let mut conn = open_connection(db_path)?;
+    let tx = conn
+        .transaction()
+        .context("failed to start summary report transaction")?;
+    let revision: i64 = tx
+        .query_row(
+            "SELECT revision FROM dataset WHERE id = ?1",
+            params![dataset_id],
+            |row| row.get(0),
+        )
+        .with_context(|| format!("failed to read revision of dataset #{dataset_id}"))?;
+    let created_at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    tx.execute(
+        "INSERT INTO summary_report(dataset_id, revision, title, group_heading, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            dataset_id,
+            revision,
+            report.title,
+            report.group_heading,
+            created_at
+        ],
+    )
+    .context("failed to save summary report")?;
+    let id = tx.last_insert_rowid();
+
+    let mut insert_stmt = tx
+        .prepare(
+            "INSERT INTO summary_report_entry(report_id, position, kind, owner, label, value)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )
+        .context("failed to prepare summary entry insert")?;
+    let totals = report
+        .totals
+        .iter()
+        .map(|entry| ("total", "", entry.label.as_str(), entry.value.as_str()));
+    let owners = report.owner_totals.iter().flat_map(|owner| {
+        owner.entries.iter().map(|entry| {
+            (
+                "owner",
+                owner.owner.as_str(),
+                entry.label.as_str(),
+                entry.value.as_str(),
+            )
+        })
+    });
+    let notes = report
+        .notes
+        .iter()
+        .map(|note| ("note", "", "", note.as_str()));
+    for (position, (kind, owner, label, value)) in totals.chain(owners).chain(notes).enumerate() {
+        insert_stmt
+            .execute(params![id, position as i64, kind, owner, label, value])
+            .context("failed to insert summary entry")?;
+    }
+    drop(insert_stmt);
+
+    tx.execute(
+        "DELETE FROM summary_report_entry WHERE report_id IN (
+             SELECT id FROM summary_report WHERE dataset_id = ?1
+             ORDER BY id DESC LIMIT -1 OFFSET ?2)",
+        params![dataset_id, SUMMARY_HISTORY_LIMIT],
+    )
+    .context("failed to trim summary entries")?;
+    tx.execute(
+        "DELETE FROM summary_report WHERE id IN (
+             SELECT id FROM summary_report WHERE dataset_id = ?1
+             ORDER BY id DESC LIMIT -1 OFFSET ?2)",
+        params![dataset_id, SUMMARY_HISTORY_LIMIT],
+    )
+    .context("failed to trim summary history")?;
+    tx.commit().context("failed to commit summary report")?;
+
+    Ok(StoredSummaryReport {
+        id,
+        dataset_id,
+        revision,
+        created_at,
+        report: report.clone(),
+    })
+}
+
+/// Saved summaries of `dataset_id`, newest first.
+pub fn load_summary_reports(db_path: &Path, dataset_id: i64) -> Result<Vec<StoredSummaryReport>> {
+    let conn = open_connection(db_path)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, revision, title, group_heading, created_at FROM summary_report
+             WHERE dataset_id = ?1
+             ORDER BY id DESC",
+        )
+        .context("failed to prepare summary report query")?;
+    let mut reports = stmt
+        .query_map(params![dataset_id], |row| {
+            Ok(StoredSummaryReport {
+                id: row.get(0)?,
+                dataset_id,
+                revision: row.get(1)?,
+                created_at: row.get(4)?,
+                report: SummaryReport {
+                    title: row.get(2)?,
+                    group_heading: row.get(3)?,
+                    ..SummaryReport::default()
+                },
+            })
+        })
+        .context("failed to query summary reports")?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to read summary report row")?;
+
+    let mut entry_stmt = conn
+        .prepare(
+            "SELECT kind, owner, label, value FROM summary_report_entry
+             WHERE report_id = ?1
+             ORDER BY position ASC",
+        )
+        .context("failed to prepare summary entry query")?;
+    for stored in &mut reports {
+        let entries = entry_stmt
+            .query_map(params![stored.id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })
+            .context("failed to query summary entries")?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("failed to read summary entry row")?;
+        let report = &mut stored.report;
+        for (kind, owner, label, value) in entries {
+            match kind.as_str() {
+                "total" => report.totals.push(SummaryEntry { label, value }),
+                "owner" => {
+                    let entry = SummaryEntry { label, value };
+                    match report.owner_totals.last_mut() {
+                        Some(last) if last.owner == owner => last.entries.push(entry),
+                        _ => report.owner_totals.push(OwnerSummary {
+                            owner,
+                            entries: vec![entry],
+                        }),
+                    }
+                }
+                _ => report.notes.push(value),
+            }
+        }
+    }
+    Ok(reports)
+}
+
+/// Writes a consistent copy of the whole database to `dest_path`, overwriting any file there.
+pub fn backup_database(db_path: &Path, dest_path: &Path) -> Result<()> {
+    if dest_path.exists() {
+        std::fs::remove_file(dest_path)
+            .with_context(|| format!("failed to replace backup: {}", dest_path.display()))?;
+    }
+    let conn = open_connection(db_path)?;
+    conn.execute(
+        "VACUUM INTO ?1",
+        params![dest_path.to_string_lossy().into_owned()],
+    )
+    .with_context(|| format!("failed to back up database to {}", dest_path.display()))?;
+    Ok(())
+}
+
+#[allow(dead_code)]
+/// One page of a dataset: its stored columns followed by its computed columns. Sorting or
+/// searching on a computed column evaluates every matching row before paging; the global
+/// search only looks at stored cells.
+pub fn query_page(
+    db_path: &Path,
+    dataset_id: i64,
+    target_page: i64,
+    page_size: i64,
+    options: &QueryOptions,
+) -> Result<(Vec<String>, Vec<Vec<String>>, i64)> {
+    let computed = load_computed_columns(db_path, dataset_id)?;
+    if computed.is_empty() {
+        return query_stored_page(db_path, dataset_id, target_page, page_size, options);
+    }
+    let stored_len = stored_column_count(db_path, dataset_id)?;
+    let is_computed = |col_idx: Option<i64>| col_idx.filter(|idx| *idx >= stored_len);
+    let search_col = is_computed(options.column_search_col)
+        .filter(|_| !options.column_search_text.trim().is_empty());
+    let sort_col = is_computed(options.sort_col);
+    let (computed_values, stored_values): (BTreeMap<_, _>, BTreeMap<_, _>) = options
+        .value_filters
+        .clone()
+        .into_iter()
+        .partition(|(col_idx, _)| *col_idx >= stored_len);
+    let (computed_conditions, stored_conditions): (BTreeMap<_, _>, BTreeMap<_, _>) = options
+        .column_conditions
+        .clone()
+        .into_iter()
+        .partition(|(col_idx, _)| *col_idx >= stored_len);
+    if search_col.is_none()
+        && sort_col.is_none()
+        && computed_values.is_empty()
+        && computed_conditions.is_empty()
+    {
+        let (mut columns, mut rows, total_rows) =
+            query_stored_page(db_path, dataset_id, target_page, page_size, options)?;
+        append_computed_columns(&mut columns, &mut rows, &computed);
+        return Ok((columns, rows, total_rows));
+    }
+    if page_size <= 0 {
+        anyhow::bail!("page_size must be greater than zero")
+    }
+
+    let stored_options = QueryOptions {
+        global_search: options.global_search.clone(),
+        column_search_col: options.column_search_col.filter(|_| search_col.is_none()),
+        column_search_text: if search_col.is_none() {
+            options.column_search_text.clone()
+        } else {
+            String::new()
+        },
+        value_filters: stored_values,
+        column_conditions: stored_conditions,
+        sort_col: options.sort_col.filter(|_| sort_col.is_none()),
+        sort_desc: options.sort_desc,
+    };
+    let (mut columns, mut rows, _) =
+        query_stored_page(db_path, dataset_id, 0, i64::MAX, &stored_options)?;
+    append_computed_columns(&mut columns, &mut rows, &computed);
+    let check_range = |col_idx: i64, what: &str| {
+        if col_idx as usize >= columns.len() {
+            anyhow::bail!(
+                "{what} out of range: {col_idx} (columns: {})",
+                columns.len()
+            );
+        }
+        Ok(())
+    };
+    if let Some(col_idx) = search_col {
+        check_range(col_idx, "column_search_col")?;
+        let term = options.column_search_text.trim().to_lowercase();
+        rows.retain(|row| row[col_idx as usize].to_lowercase().contains(&term));
+    }
+    for (col_idx, values) in computed_values
+        .iter()
+        .filter(|(_, values)| !values.is_empty())
+    {
+        check_range(*col_idx, "value filter column")?;
+        rows.retain(|row| values.contains(&row[*col_idx as usize]));
+    }
+    for (col_idx, expression) in &computed_conditions {
+        let Some(expr) = parse_filter_expr(expression) else {
+            continue;
+        };
+        check_range(*col_idx, "column condition")?;
+        rows.retain(|row| expr.matches(&row[*col_idx as usize]));
+    }
+    if let Some(col_idx) = sort_col {
+        check_range(col_idx, "sort_col")?;
+        // Stable, so equal values keep the stored row order as the SQL sort does.
+        rows.sort_by(|left, right| {
+            let ordering =
+                compare_computed_cells(&left[col_idx as usize], &right[col_idx as usize]);
+            if options.sort_desc {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+    }
+    let total_rows = rows.len() as i64;
+    let offset = target_page.max(0).saturating_mul(page_size);
+    let rows = rows
+        .into_iter()
+        .skip(usize::try_from(offset).unwrap_or(usize::MAX))
+        .take(usize::try_from(page_size).unwrap_or(usize::MAX))
+        .collect();
+    Ok((columns, rows, total_rows))
+}
+
+fn stored_column_type(
+    conn: &Connection,
+    dataset_id: i64,
+    col_idx: i64,
+) -> Result<Option<ColumnType>> {
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT type FROM column_type WHERE dataset_id = ?1 AND col_idx = ?2",
+            params![dataset_id, col_idx],
+            |row| row.get(0),
+        )
+        .optional()
+        .context("failed to query sort column type")?;
+    Ok(value.as_deref().and_then(ColumnType::parse))
+}
+
+fn stored_column_count(db_path: &Path, dataset_id: i64) -> Result<i64> {
+    let conn = open_connection(db_path)?;
+    conn.query_row(
+        "SELECT COUNT(*) FROM column_name WHERE dataset_id = ?1",
+        params![dataset_id],
+        |row| row.get(0),
+    )
+    .with_context(|| format!("failed to count columns of dataset #{dataset_id}"))
+}
+
+/// Blank cells first, then numbers by value, then any other text.
+fn compare_computed_cells(left: &str, right: &str) -> std::cmp::Ordering {
+    let key = |value: &str| match parse_numeric_value(value) {
+        _ if value.trim().is_empty() => (0, 0.0),
+        Some(number) => (1, number),
+        None => (2, 0.0),
+    };
+    let (left_rank, left_value) = key(left);
+    let (right_rank, right_value) = key(right);
+    left_rank
+        .cmp(&right_rank)
+        .then(left_value.total_cmp(&right_value))
+        .then_with(|| left.cmp(right))
+}
+
+/// `WHERE` condition over `cell base` selecting the cells of the rows that pass the
+/// search and value filters of `options`, with its parameters.
+fn row_filter_sql(dataset_id: i64, options: &QueryOptions) -> (String, Vec<Value>) {
+    let mut filter_clauses = vec!["base.dataset_id = ?".to_string()];
+    let mut filter_params = vec![Value::Integer(dataset_id)];
+
+    let global_search = options.global_search.trim();
+    if !global_search.is_empty() {
+        filter_clauses.push(
+            "EXISTS (
+                SELECT 1 FROM cell gs
+                WHERE gs.dataset_id = ?
+                  AND gs.row_idx = base.row_idx
+                  AND gs.value LIKE ?
+            )"
+            .to_string(),
+        );
+        filter_params.push(Value::Integer(dataset_id));
+        filter_params.push(Value::Text(format!("%{global_search}%")));
+    }
+
+    let column_search_text = options.column_search_text.trim();
+    if !column_search_text.is_empty() {
+        if let Some(column_search_col) = options.column_search_col {
+            filter_clauses.push(
+                "EXISTS (
+                    SELECT 1 FROM cell cs
+                    WHERE cs.dataset_id = ?
+                      AND cs.row_idx = base.row_idx
+                      AND cs.col_idx = ?
+                      AND cs.value LIKE ?
+                )"
+                .to_string(),
+            );
+            filter_params.push(Value::Integer(dataset_id));
+            filter_params.push(Value::Integer(column_search_col));
+            filter_params.push(Value::Text(format!("%{column_search_text}%")));
+        }
+    }
+
+    for (col_idx, values) in &options.value_filters {
+        if values.is_empty() {
+            continue;
+        }
+        let placeholders = vec!["?"; values.len()].join(", ");
+        filter_clauses.push(format!(
+            "EXISTS (
+                SELECT 1 FROM cell vf
+                WHERE vf.dataset_id = ?
+                  AND vf.row_idx = base.row_idx
+                  AND vf.col_idx = ?
+                  AND vf.value IN ({placeholders})
+            )"
+        ));
+        filter_params.push(Value::Integer(dataset_id));
+        filter_params.push(Value::Integer(*col_idx));
+        filter_params.extend(values.iter().cloned().map(Value::Text));
+    }
+
+    for (col_idx, expression) in &options.column_conditions {
+        let Some(expr) = parse_filter_expr(expression) else {
+            continue;
+        };
+        let (clause, params) = condition_sql(&expr);
+        filter_clauses.push(clause);
+        filter_params.push(Value::Integer(dataset_id));
+        filter_params.push(Value::Integer(*col_idx));
+        filter_params.extend(params);
+    }
+
+    (filter_clauses.join(" AND "), filter_params)
+}
+
+/// The number `cell` holds as [`parse_numeric_value`] reads it, or NULL: thousands
+/// separators dropped and a trailing `%` dividing by 100.
+fn numeric_cell_sql(cell: &str) -> String {
+    let cleaned = format!("REPLACE(TRIM({cell}), ',', '')");
+    let digits = format!("RTRIM({cleaned}, '%')");
+    format!(
+        "CASE WHEN {digits} GLOB '*[0-9]*' AND {digits} NOT GLOB '*[^0-9.eE+-]*' \
+         THEN CAST({digits} AS REAL) / (CASE WHEN {digits} = {cleaned} THEN 1.0 ELSE 100.0 END) \
+         END"
+    )
+}
+
+/// `WHERE` condition keeping the rows whose cell passes `expr`, taking the dataset id
+/// and column index as its first two parameters, followed by the returned ones. Matches
+/// [`FilterExpr::matches`].
+fn condition_sql(expr: &FilterExpr) -> (String, Vec<Value>) {
+    let number = numeric_cell_sql("cf.value");
+    let (negated, test, params) = match expr {
+        FilterExpr::Compare(CompareOp::Ne, value) => {
+            (true, format!("{number} = ?"), vec![Value::Real(*value)])
+        }
+        FilterExpr::Compare(op, value) => (
+            false,
+            format!("{number} {} ?", op.symbol()),
+            vec![Value::Real(*value)],
+        ),
+        FilterExpr::Between(low, high) => (
+            false,
+            format!("{number} BETWEEN ? AND ?"),
+            vec![Value::Real(*low), Value::Real(*high)],
+        ),
+        FilterExpr::Text { value, negated } => (
+            *negated,
+            "TRIM(cf.value) = ?".to_string(),
+            vec![Value::Text(value.clone())],
+        ),
+        FilterExpr::Empty => (true, "TRIM(cf.value) <> ''".to_string(), Vec::new()),
+        FilterExpr::NotEmpty => (false, "TRIM(cf.value) <> ''".to_string(), Vec::new()),
+    };
+    let exists = if negated { "NOT EXISTS" } else { "EXISTS" };
+    (
+        format!(
+            "{exists} (
+                SELECT 1 FROM cell cf
+                WHERE cf.dataset_id = ?
+                  AND cf.row_idx = base.row_idx
+                  AND cf.col_idx = ?
+                  AND {test}
+            )"
+        ),
+        params,
+    )
+}
+
+/// One page of the stored cells only, without computed columns. Edits are written back
+/// against this view of the dataset.
+pub fn query_stored_page(
+    db_path: &Path,
+    dataset_id: i64,
+    target_page: i64,
+    page_size: i64,
+    options: &QueryOptions,
+) -> Result<(Vec<String>, Vec<Vec<String>>, i64)> {
+    if page_size <= 0 {
+        anyhow::bail!("page_size must be greater than zero")
+    }
+
+    let conn = open_connection(db_path)?;
+
+    let mut columns_stmt = conn
+        .prepare(
+            "SELECT name
+             FROM column_name
+             WHERE dataset_id = ?1
+             ORDER BY col_idx ASC",
+        )
+        .context("failed to prepare columns query")?;
+    let columns = columns_stmt
+        .query_map([dataset_id], |row| row.get::<_, String>(0))
+        .context("failed to query columns")?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to collect columns")?;
+    drop(columns_stmt);
+
+    if columns.is_empty() {
+        return Ok((columns, Vec::new(), 0));
+    }
+
+    if let Some(column_search_col) = options.column_search_col {
+        if column_search_col < 0 || column_search_col as usize >= columns.len() {
+            anyhow::bail!(
+                "column_search_col out of range: {column_search_col} (columns: {})",
+                columns.len()
+            );
+        }
+    }
+
+    if let Some(sort_col) = options.sort_col {
+        if sort_col < 0 || sort_col as usize >= columns.len() {
+            anyhow::bail!(
+                "sort_col out of range: {sort_col} (columns: {})",
+                columns.len()
+            );
+        }
+    }
+
+    for col_idx in options.value_filters.keys() {
+        if *col_idx < 0 || *col_idx as usize >= columns.len() {
+            anyhow::bail!(
+                "value filter column out of range: {col_idx} (columns: {})",
+                columns.len()
+            );
+        }
+    }
+
+    for col_idx in options.column_conditions.keys() {
+        if *col_idx < 0 || *col_idx as usize >= columns.len() {
+            anyhow::bail!(
+                "column condition out of range: {col_idx} (columns: {})",
+                columns.len()
+            );
+        }
+    }
+
+    let (where_sql, filter_params) = row_filter_sql(dataset_id, options);
+
+    let count_sql = format!(
+        "SELECT COUNT(*)
+         FROM (
+             SELECT base.row_idx
+             FROM cell base
+             WHERE {where_sql}
+             GROUP BY base.row_idx
+         ) filtered"
+    );
+    let total_rows: i64 = conn
+        .query_row(
+            &count_sql,
+            rusqlite::params_from_iter(filter_params.iter().cloned()),
+            |row| row.get(0),
+        )
+        .context("failed to query filtered row count")?;
+
+    let offset = target_page
+        .max(0)
+        .checked_mul(page_size)
+        .context("page is out of range")?;
+    let sort_direction = if options.sort_desc { "DESC" } else { "ASC" };
+
+    let mut row_params = Vec::<Value>::new();
+    let mut row_sql = String::from("SELECT base.row_idx FROM cell base ");
+    if let Some(sort_col) = options.sort_col {
+        row_sql.push_str(
+            "LEFT JOIN cell sort_cell
+             ON sort_cell.dataset_id = base.dataset_id
+            AND sort_cell.row_idx = base.row_idx
+            AND sort_cell.col_idx = ? ",
+        );
+        row_params.push(Value::Integer(sort_col));
+    }
+
+    row_sql.push_str(&format!(
+        "WHERE {where_sql} GROUP BY base.row_idx ORDER BY "
+    ));
+    if let Some(sort_col) = options.sort_col {
+        if stored_column_type(&conn, dataset_id, sort_col)?.is_some_and(ColumnType::is_numeric) {
+            // By value rather than as text, with blank cells last.
+            row_sql.push_str(&format!(
+                "COALESCE(sort_cell.value, '') = '', \
+                 CAST(REPLACE(sort_cell.value, ',', '') AS REAL) {sort_direction}, "
+            ));
+        }
+        row_sql.push_str(&format!("COALESCE(sort_cell.value, '') {sort_direction}, "));
+    }
+    row_sql.push_str("base.row_idx ASC LIMIT ? OFFSET ?");
+
+    row_params.extend(filter_params.iter().cloned());
+    row_params.push(Value::Integer(page_size));
+    row_params.push(Value::Integer(offset));
+
+    let mut row_stmt = conn
+        .prepare(&row_sql)
+        .context("failed to prepare page row_idx query")?;
+    let row_indices = row_stmt
+        .query_map(rusqlite::params_from_iter(row_params), |row| {
+            row.get::<_, i64>(0)
+        })
+        .context("failed to query page row_idx")?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to collect page row_idx")?;
+    drop(row_stmt);
+
+    if row_indices.is_empty() {
+        return Ok((columns, Vec::new(), total_rows));
+    }
+
+    let placeholders = std::iter::repeat_n("?", row_indices.len())
+        .collect::<Vec<_>>()
+        .join(",");
+    let hydrate_sql = format!(
+        "SELECT row_idx, col_idx, value
+         FROM cell
+         WHERE dataset_id = ? AND row_idx IN ({placeholders})
+         ORDER BY row_idx ASC, col_idx ASC"
+    );
+    let mut hydrate_params = vec![Value::Integer(dataset_id)];
+    hydrate_params.extend(row_indices.iter().copied().map(Value::Integer));
+
+    let mut rows = vec![vec![String::new(); columns.len()]; row_indices.len()];
+    let row_pos: HashMap<i64, usize> = row_indices
+        .iter()
+        .copied()
+        .enumerate()
+        .map(|(idx, row_idx)| (row_idx, idx))
+        .collect();
+
+    let mut hydrate_stmt = conn
+        .prepare(&hydrate_sql)
+        .context("failed to prepare row hydration query")?;
+
+    let mut hydrate_rows = hydrate_stmt
+        .query(rusqlite::params_from_iter(hydrate_params))
+        .context("failed to run row hydration query")?;
+
+    while let Some(row) = hydrate_rows.next().context("failed to read hydrated row")? {
+        let row_idx: i64 = row.get(0).context("failed to read row_idx")?;
+        let col_idx: i64 = row.get(1).context("failed to read col_idx")?;
+        let value: String = row.get(2).context("failed to read value")?;
+
+        if let Some(&dest_row_idx) = row_pos.get(&row_idx) {
+            if let Some(dest_cell) = rows
+                .get_mut(dest_row_idx)
+                .and_then(|dest_row| dest_row.get_mut(col_idx as usize))
+            {
+                *dest_cell = value;
+            }
+        }
+    }
+
+    Ok((columns, rows, total_rows))
+}
+
+#[allow(dead_code)]
+pub fn reload_page_data(
+    db_path: &Path,
+    dataset_id: Option<i64>,
+    target_page: i64,
+    options: &QueryOptions,
+) -> Result<ReloadPageResult> {
+    let page = target_page.max(0);
+    if let Some(dataset_id) = dataset_id {
+        let (columns, rows, total_rows) =
+            query_page(db_path, dataset_id, page, crate::PAGE_SIZE, options)?;
+        Ok((columns, rows, total_rows, page))
+    } else {
+        Ok((Vec::new(), Vec::new(), 0, 0))
+    }
+}
+
+#[allow(dead_code)]
+pub fn list_datasets(db_path: &Path, include_deleted: bool) -> Result<Vec<DatasetMeta>> {
+    init_db(db_path)?;
+    let conn = open_connection(db_path)?;
+    list_datasets_with(&conn, include_deleted)
+}
+
+/// Everything a [`DatasetContext`] holds besides the page, read over one connection: the
+/// dataset list, the holdings flags and, once `select` has picked a dataset from the list,
+/// its column visibility.
+pub fn load_dataset_overview(
+    db_path: &Path,
+    include_deleted: bool,
+    select: impl FnOnce(&[DatasetMeta]) -> Option<i64>,
+) -> Result<DatasetContext> {
+    init_db(db_path)?;
+    let conn = open_connection(db_path)?;
+    let datasets = list_datasets_with(&conn, include_deleted)?;
+    let holdings_flags = holdings_flags_with(&conn)?;
+    let column_visibility = match select(&datasets) {
+        Some(dataset_id) => column_visibility_with(&conn, dataset_id)?,
+        None => BTreeMap::new(),
+    };
+    Ok(DatasetContext {
+        datasets,
+        page: None,
+        column_visibility,
+        holdings_flags,
+    })
+}
+
+fn list_datasets_with(conn: &Connection, include_deleted: bool) -> Result<Vec<DatasetMeta>> {
+    let filter = if include_deleted {
+        ""
+    } else {
+        "WHERE deleted_at IS NULL"
+    };
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT id, name, row_count, source_path, deleted_at, kind, keep_forever
+             FROM dataset
+             {filter}
+             ORDER BY id DESC"
+        ))
+        .context("failed to prepare datasets query")?;
+
+    let datasets = stmt
+        .query_map([], |row| {
+            Ok(DatasetMeta {
+                id: row.get::<_, i64>(0)?.into(),
+                name: row.get(1)?,
+                row_count: row.get(2)?,
+                source_path: row.get(3)?,
+                deleted_at: row.get(4)?,
+                kind: row
+                    .get::<_, Option<String>>(5)?
+                    .as_deref()
+                    .and_then(DatasetKind::parse),
+                keep_forever: row.get(6)?,
+            })
+        })
+        .context("failed to query datasets")?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to collect datasets")?;
+
+    Ok(datasets)
+}
+
+pub fn soft_delete_dataset(db_path: &Path, dataset_id: i64) -> Result<()> {
+    init_db(db_path)?;
+    let conn = open_connection(db_path)?;
+    conn.execute(
+        "UPDATE dataset SET deleted_at = datetime('now') WHERE id = ?1",
+        params![dataset_id],
+    )
+    .with_context(|| format!("failed to soft-delete dataset #{dataset_id}"))?;
+    Ok(())
+}
+
+/// Takes dataset `dataset_id` out of the trash.
+pub fn restore_dataset(db_path: &Path, dataset_id: i64) -> Result<()> {
+    init_db(db_path)?;
+    let conn = open_connection(db_path)?;
+    conn.execute(
+        "UPDATE dataset SET deleted_at = NULL WHERE id = ?1",
+        params![dataset_id],
+    )
+    .with_context(|| format!("failed to restore dataset #{dataset_id}"))?;
+    Ok(())
+}
+
+/// Marks whether dataset `dataset_id` stays in the trash however long it has been there.
+pub fn set_keep_forever(db_path: &Path, dataset_id: i64, keep_forever: bool) -> Result<()> {
+    init_db(db_path)?;
+    let conn = open_connection(db_path)?;
+    conn.execute(
+        "UPDATE dataset SET keep_forever = ?2 WHERE id = ?1",
+        params![dataset_id, keep_forever],
+    )
+    .with_context(|| format!("failed to update keep-forever of dataset #{dataset_id}"))?;
+    Ok(())
+}
+
+/// Datasets that have been in the trash for at least `retention_days` days and are not
+/// kept forever, oldest id first.
+pub fn expired_datasets(db_path: &Path, retention_days: u32) -> Result<Vec<i64>> {
+    init_db(db_path)?;
+    let conn = open_connection(db_path)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id FROM dataset
+             WHERE deleted_at IS NOT NULL
+               AND keep_forever = 0
+               AND deleted_at <= datetime('now', ?1)
+             ORDER BY id",
+        )
+        .context("failed to prepare expired datasets query")?;
+    let ids = stmt
+        .query_map(params![format!("-{retention_days} days")], |row| row.get(0))
+        .context("failed to query expired datasets")?
+        .collect::<rusqlite::Result<Vec<i64>>>()
+        .context("failed to collect expired datasets")?;
+    Ok(ids)
+}
+
+#[allow(dead_code)]
+pub fn purge_dataset(db_path: &Path, dataset_id: i64) -> Result<()> {
+    init_db(db_path)?;
+    let mut conn = open_connection(db_path)?;
+    let tx = conn
+        .transaction()
+        .context("failed to start purge transaction")?;
+    tx.execute(
+        "DELETE FROM column_visibility WHERE dataset_id = ?1",
+        params![dataset_id],
+    )
+    .with_context(|| format!("failed to delete column visibility for dataset #{dataset_id}"))?;
+    tx.execute(
+        "DELETE FROM column_format WHERE dataset_id = ?1",
+        params![dataset_id],
+    )
+    .with_context(|| format!("failed to delete column formats for dataset #{dataset_id}"))?;
+    tx.execute(
+        "DELETE FROM column_type WHERE dataset_id = ?1",
+        params![dataset_id],
+    )
+    .with_context(|| format!("failed to delete column types for dataset #{dataset_id}"))?;
+    tx.execute(
+        "DELETE FROM dataset_flag WHERE dataset_id = ?1",
+        params![dataset_id],
+    )
+    .with_context(|| format!("failed to delete dataset flags for dataset #{dataset_id}"))?;
+    tx.execute(
+        "DELETE FROM dataset_display WHERE dataset_id = ?1",
+        params![dataset_id],
+    )
+    .with_context(|| format!("failed to delete display settings for dataset #{dataset_id}"))?;
+    tx.execute(
+        "DELETE FROM dataset_page_size WHERE dataset_id = ?1",
+        params![dataset_id],
+    )
+    .with_context(|| format!("failed to delete page size for dataset #{dataset_id}"))?;
+    tx.execute(
+        "DELETE FROM summary_report_entry WHERE report_id IN
+             (SELECT id FROM summary_report WHERE dataset_id = ?1)",
+        params![dataset_id],
+    )
+    .with_context(|| format!("failed to delete summary entries for dataset #{dataset_id}"))?;
+    tx.execute(
+        "DELETE FROM summary_report WHERE dataset_id = ?1",
+        params![dataset_id],
+    )
+    .with_context(|| format!("failed to delete summaries for dataset #{dataset_id}"))?;
+    tx.execute(
+        "DELETE FROM computed_column WHERE dataset_id = ?1",
+        params![dataset_id],
+    )
+    .with_context(|| format!("failed to delete computed columns for dataset #{dataset_id}"))?;
+    tx.execute(
+        "DELETE FROM owner_share WHERE dataset_id = ?1",
+        params![dataset_id],
+    )
+    .with_context(|| format!("failed to delete owner shares for dataset #{dataset_id}"))?;
+    tx.execute(
+        "DELETE FROM saved_change WHERE dataset_id = ?1",
+        params![dataset_id],
+    )
+    .with_context(|| format!("failed to delete saved changes for dataset #{dataset_id}"))?;
+    tx.execute(
+        "DELETE FROM cell_origin WHERE dataset_id = ?1",
+        params![dataset_id],
+    )
+    .with_context(|| format!("failed to delete cell origins for dataset #{dataset_id}"))?;
+    tx.execute(
+        "DELETE FROM source_sheet_cell WHERE dataset_id = ?1",
+        params![dataset_id],
+    )
+    .with_context(|| format!("failed to delete source sheet cells for dataset #{dataset_id}"))?;
+    tx.execute(
+        "DELETE FROM source_sheet WHERE dataset_id = ?1",
+        params![dataset_id],
+    )
+    .with_context(|| format!("failed to delete source sheets for dataset #{dataset_id}"))?;
+    tx.execute(
+        "DELETE FROM cell WHERE dataset_id = ?1",
+        params![dataset_id],
+    )
+    .with_context(|| format!("failed to delete cells for dataset #{dataset_id}"))?;
+    tx.execute(
+        "DELETE FROM column_name WHERE dataset_id = ?1",
+        params![dataset_id],
+    )
+    .with_context(|| format!("failed to delete columns for dataset #{dataset_id}"))?;
+    tx.execute("DELETE FROM dataset WHERE id = ?1", params![dataset_id])
+        .with_context(|| format!("failed to delete dataset #{dataset_id}"))?;
+    tx.commit().context("failed to commit purge transaction")?;
+    Ok(())
+}
+
+pub fn build_updated_rows(
+    columns: &[String],
+    rows: &[Vec<String>],
+    staged_cells: &HashMap<CellKey, String>,
+    deleted_rows: &BTreeSet<usize>,
+    added_rows: &[Vec<String>],
+) -> Vec<Vec<String>> {
+    let mut updated = Vec::new();
+    for (row_idx, row) in rows.iter().enumerate() {
+        if deleted_rows.contains(&row_idx) {
+            continue;
+        }
+        let mut next_row = row.clone();
+        for (col_idx, header) in columns.iter().enumerate() {
+            if let Some(value) = staged_cells.get(&CellKey {
+                row_idx,
+                col_idx,
+                column: header.clone(),
+            }) {
+                if col_idx < next_row.len() {
+                    next_row[col_idx] = value.clone();
+                }
+            }
+        }
+        updated.push(next_row);
+    }
+    for row in added_rows {
+        updated.push(row.clone());
+    }
+    updated
+}
+
+/// Cells of `updated` that differ from the `rows` they replace. Rows kept by the save are
+/// compared with themselves at their new position, past the `deleted_rows`; the rows
+/// after them were added and count as changed throughout.
+fn saved_changes(
+    rows: &[Vec<String>],
+    deleted_rows: &BTreeSet<usize>,
+    updated: &[Vec<String>],
+) -> SavedChanges {
+    let kept: Vec<&Vec<String>> = rows
+        .iter()
+        .enumerate()
+        .filter(|(row_idx, _)| !deleted_rows.contains(row_idx))
+        .map(|(_, row)| row)
+        .collect();
+    let mut changes = SavedChanges::new();
+    for (row_idx, row) in updated.iter().enumerate() {
+        let before = kept.get(row_idx);
+        for (col_idx, value) in row.iter().enumerate() {
+            match before {
+                Some(before) => {
+                    let previous = before.get(col_idx).map(String::as_str).unwrap_or_default();
+                    if previous != value {
+                        changes.insert((row_idx, col_idx), Some(previous.to_string()));
+                    }
+                }
+                None => {
+                    changes.insert((row_idx, col_idx), None);
+                }
+            }
+        }
+    }
+    changes
+}
+
+/// What the last save of the dataset changed; empty before its first save.
+pub fn load_saved_changes(db_path: &Path, dataset_id: i64) -> Result<SavedChanges> {
+    let conn = open_connection(db_path)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT row_idx, col_idx, previous
+             FROM saved_change
+             WHERE dataset_id = ?1",
+        )
+        .context("failed to prepare saved change query")?;
+    let rows = stmt
+        .query_map(params![dataset_id], |row| {
+            let row_idx: i64 = row.get(0)?;
+            let col_idx: i64 = row.get(1)?;
+            let previous: Option<String> = row.get(2)?;
+            Ok(((row_idx as usize, col_idx as usize), previous))
+        })
+        .context("failed to query saved changes")?;
+    let mut changes = SavedChanges::new();
+    for row in rows {
+        let (key, previous) = row.context("failed to read saved change row")?;
+        changes.insert(key, previous);
+    }
+    Ok(changes)
+}
+
+#[allow(dead_code)]
+pub fn apply_changes_to_dataset(
+    db_path: &Path,
+    dataset_id: i64,
+    columns: &[String],
+    rows: &[Vec<String>],
+    staged_cells: &HashMap<CellKey, String>,
+    deleted_rows: &BTreeSet<usize>,
+    added_rows: &[Vec<String>],
+) -> Result<()> {
+    let updated_rows = build_updated_rows(columns, rows, staged_cells, deleted_rows, added_rows);
+    let mut conn = open_connection(db_path)?;
+    let tx = conn
+        .transaction()
+        .context("failed to start update transaction")?;
+
+    tx.execute(
+        "DELETE FROM cell WHERE dataset_id = ?1",
+        params![dataset_id],
+    )
+    .context("failed to clear existing cells")?;
+
+    let mut insert_cell = tx
+        .prepare("INSERT INTO cell(dataset_id, row_idx, col_idx, value) VALUES (?1, ?2, ?3, ?4)")
+        .context("failed to prepare cell insert")?;
+    for (row_idx, row) in updated_rows.iter().enumerate() {
+        for (col_idx, value) in row.iter().enumerate() {
+            insert_cell
+                .execute(params![dataset_id, row_idx as i64, col_idx as i64, value])
+                .context("failed to insert updated cell")?;
+        }
+    }
+    drop(insert_cell);
+
+    // Owner shares follow their rows, which move up past the deleted ones.
+    let splits = owner_splits_with(&tx, dataset_id)?;
+    tx.execute(
+        "DELETE FROM owner_share WHERE dataset_id = ?1",
+        params![dataset_id],
+    )
+    .context("failed to clear owner shares")?;
+    for (row_idx, shares) in splits {
+        if deleted_rows.contains(&row_idx) {
+            continue;
+        }
+        let moved = row_idx - deleted_rows.range(..row_idx).count();
+        insert_owner_shares(&tx, dataset_id, moved, &shares)?;
+    }
+    // So do the origins of imported cells.
+    let origins = cell_origins_with(&tx, dataset_id)?;
+    tx.execute(
+        "DELETE FROM cell_origin WHERE dataset_id = ?1",
+        params![dataset_id],
+    )
+    .context("failed to clear cell origins")?;
+    insert_cell_origin_entries(
+        &tx,
+        dataset_id,
+        origins
+            .iter()
+            .filter(|((row_idx, _), _)| !deleted_rows.contains(row_idx))
+            .map(|(&(row_idx, col_idx), origin)| {
+                let moved = row_idx - deleted_rows.range(..row_idx).count();
+                ((moved, col_idx), origin)
+            }),
+    )?;
+
+    // Only the latest save is kept: the cells it changed against the version before it.
+    tx.execute(
+        "DELETE FROM saved_change WHERE dataset_id = ?1",
+        params![dataset_id],
+    )
+    .context("failed to clear saved changes")?;
+    let mut insert_change = tx
+        .prepare(
+            "INSERT INTO saved_change(dataset_id, row_idx, col_idx, previous)
+             VALUES (?1, ?2, ?3, ?4)",
+        )
+        .context("failed to prepare saved change insert")?;
+    for ((row_idx, col_idx), previous) in saved_changes(rows, deleted_rows, &updated_rows) {
+        insert_change
+            .execute(params![
+                dataset_id,
+                row_idx as i64,
+                col_idx as i64,
+                previous
+            ])
+            .context("failed to insert saved change")?;
+    }
+    drop(insert_change);
+
+    // A new revision makes summaries cached for the old contents stale.
+    tx.execute(
+        "UPDATE dataset SET row_count = ?1, revision = revision + 1 WHERE id = ?2",
+        params![updated_rows.len() as i64, dataset_id],
+    )
+    .context("failed to update dataset row_count")?;
+
+    tx.commit().context("failed to commit dataset update")?;
+    Ok(())
+}
+
+#[allow(dead_code)]
+pub fn create_dataset_from_rows(
+    db_path: &Path,
+    name: &str,
+    source_path: &str,
+    columns: &[String],
+    rows: &[Vec<String>],
+) -> Result<i64> {
+    init_db(db_path)?;
+    let mut conn = open_connection(db_path)?;
+    let tx = conn
+        .transaction()
+        .context("failed to start dataset create transaction")?;
+
+    tx.execute(
+        "INSERT INTO dataset(name, source_path, row_count, kind) VALUES (?1, ?2, 0, ?3)",
+        params![name, source_path, DatasetKind::from_name(name).as_str()],
+    )
+    .context("failed to insert dataset")?;
+    let dataset_id = tx.last_insert_rowid();
+
+    insert_header_names(&tx, dataset_id, columns)?;
+    insert_column_types(&tx, dataset_id, &infer_column_types(columns, rows))?;
+
+    let mut insert_cell = tx
+        .prepare("INSERT INTO cell(dataset_id, row_idx, col_idx, value) VALUES (?1, ?2, ?3, ?4)")
+        .context("failed to prepare cell insert")?;
+    for (row_idx, row) in rows.iter().enumerate() {
+        for (col_idx, value) in row.iter().enumerate() {
+            insert_cell
+                .execute(params![dataset_id, row_idx as i64, col_idx as i64, value])
+                .context("failed to insert dataset cell")?;
+        }
+    }
+    drop(insert_cell);
+
+    tx.execute(
+        "UPDATE dataset SET row_count = ?1 WHERE id = ?2",
+        params![rows.len() as i64, dataset_id],
+    )
+    .context("failed to update dataset row_count")?;
+
+    tx.commit().context("failed to commit dataset create")?;
+    Ok(dataset_id)
+}
+
+/// Stores where the imported cells of a dataset came from; `origins` runs parallel to
+/// its rows.
+pub fn insert_cell_origins(
+    tx: &rusqlite::Transaction<'_>,
+    dataset_id: i64,
+    origins: &[Vec<Option<CellOrigin>>],
+) -> Result<()> {
+    insert_cell_origin_entries(
+        tx,
+        dataset_id,
+        origins.iter().enumerate().flat_map(|(row_idx, row)| {
+            row.iter()
+                .enumerate()
+                .filter_map(move |(col_idx, origin)| Some(((row_idx, col_idx), origin.as_ref()?)))
+        }),
+    )
+}
+
+fn insert_cell_origin_entries<'a>(
+    tx: &rusqlite::Transaction<'_>,
+    dataset_id: i64,
+    entries: impl IntoIterator<Item = ((usize, usize), &'a CellOrigin)>,
+) -> Result<()> {
+    let mut insert_origin = tx
+        .prepare(
+            "INSERT INTO cell_origin(dataset_id, row_idx, col_idx, sheet, source_row, source_col, value)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )
+        .context("failed to prepare cell origin insert")?;
+    for ((row_idx, col_idx), origin) in entries {
+        insert_origin
+            .execute(params![
+                dataset_id,
+                row_idx as i64,
+                col_idx as i64,
+                origin.sheet,
+                origin.row_idx as i64,
+                origin.col_idx as i64,
+                origin.value
+            ])
+            .context("failed to insert cell origin")?;
+    }
+    Ok(())
+}
+
+/// Where the imported cells of a dataset came from; empty for datasets whose import did
+/// not keep track.
+pub fn load_cell_origins(db_path: &Path, dataset_id: i64) -> Result<CellOrigins> {
+    let conn = open_connection(db_path)?;
+    cell_origins_with(&conn, dataset_id)
+}
+
+fn cell_origins_with(conn: &Connection, dataset_id: i64) -> Result<CellOrigins> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT row_idx, col_idx, sheet, source_row, source_col, value
+             FROM cell_origin
+             WHERE dataset_id = ?1",
+        )
+        .context("failed to prepare cell origin query")?;
+    let rows = stmt
+        .query_map(params![dataset_id], |row| {
+            let row_idx: i64 = row.get(0)?;
+            let col_idx: i64 = row.get(1)?;
+            let source_row: i64 = row.get(3)?;
+            let source_col: i64 = row.get(4)?;
+            Ok((
+                (row_idx as usize, col_idx as usize),
+                CellOrigin {
+                    sheet: row.get(2)?,
+                    row_idx: source_row as usize,
+                    col_idx: source_col as usize,
+                    value: row.get(5)?,
+                },
+            ))
+        })
+        .context("failed to query cell origins")?;
+    let mut origins = CellOrigins::new();
+    for row in rows {
+        let (key, origin) = row.context("failed to read cell origin row")?;
+        origins.insert(key, origin);
+    }
+    Ok(origins)
+}
+
+/// Stores the parsed `sheets` a dataset was transformed from, with the name of the
+/// transform output it is, so the transform can be run again later.
+pub fn insert_source_sheets(
+    tx: &rusqlite::Transaction<'_>,
+    dataset_id: i64,
+    output: &str,
+    sheets: &[ParsedDataset],
+) -> Result<()> {
+    let mut insert_sheet = tx
+        .prepare(
+            "INSERT INTO source_sheet(dataset_id, position, sheet, output) VALUES (?1, ?2, ?3, ?4)",
+        )
+        .context("failed to prepare source sheet insert")?;
+    let mut insert_cell = tx
+        .prepare(
+            "INSERT INTO source_sheet_cell(dataset_id, position, row_idx, col_idx, value)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )
+        .context("failed to prepare source sheet cell insert")?;
+    for (position, sheet) in sheets.iter().enumerate() {
+        insert_sheet
+            .execute(params![dataset_id, position as i64, sheet.name, output])
+            .with_context(|| format!("failed to insert source sheet {}", sheet.name))?;
+        for (row_idx, row) in sheet.rows.iter().enumerate() {
+            for (col_idx, value) in row.iter().enumerate() {
+                insert_cell
+                    .execute(params![
+                        dataset_id,
+                        position as i64,
+                        row_idx as i64,
+                        col_idx as i64,
+                        value
+                    ])
+                    .context("failed to insert source sheet cell")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The sheets stored by [`insert_source_sheets`] and the transform output the dataset
+/// is, or `None` when the dataset was not imported from sheets that were kept.
+pub fn load_source_sheets(
+    db_path: &Path,
+    dataset_id: i64,
+) -> Result<Option<(String, Vec<ParsedDataset>)>> {
+    let conn = open_connection(db_path)?;
+    let source_path: String = conn
+        .query_row(
+            "SELECT source_path FROM dataset WHERE id = ?1",
+            params![dataset_id],
+            |row| row.get(0),
+        )
+        .with_context(|| format!("failed to read source of dataset #{dataset_id}"))?;
+    // Outputs are stored as `{file}#{output}`; the sheets came from the file itself.
+    let source_path = source_path
+        .split_once('#')
+        .map(|(file, _)| file.to_string())
+        .unwrap_or(source_path);
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT sheet, output FROM source_sheet
+             WHERE dataset_id = ?1
+             ORDER BY position ASC",
+        )
+        .context("failed to prepare source sheet query")?;
+    let sheets = stmt
+        .query_map([dataset_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .context("failed to query source sheets")?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to read source sheet row")?;
+    let Some(output) = sheets.first().map(|(_, output)| output.clone()) else {
+        return Ok(None);
+    };
+
+    let mut datasets: Vec<ParsedDataset> = sheets
+        .into_iter()
+        .map(|(name, _)| ParsedDataset {
+            name,
+            source_path: source_path.clone(),
+            headers: Vec::new(),
+            rows: Vec::new(),
+            origins: Vec::new(),
+        })
+        .collect();
+    let mut stmt = conn
+        .prepare(
+            "SELECT position, row_idx, col_idx, value FROM source_sheet_cell
+             WHERE dataset_id = ?1
+             ORDER BY position ASC, row_idx ASC, col_idx ASC",
+        )
+        .context("failed to prepare source sheet cell query")?;
+    let cells = stmt
+        .query_map([dataset_id], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })
+        .context("failed to query source sheet cells")?;
+    for cell in cells {
+        let (position, row_idx, col_idx, value) =
+            cell.context("failed to read source sheet cell")?;
+        let Some(sheet) = datasets.get_mut(position as usize) else {
+            continue;
+        };
+        let (row_idx, col_idx) = (row_idx as usize, col_idx as usize);
+        if sheet.rows.len() <= row_idx {
+            sheet.rows.resize_with(row_idx + 1, Vec::new);
+        }
+        let row = &mut sheet.rows[row_idx];
+        if row.len() <= col_idx {
+            row.resize(col_idx + 1, String::new());
+        }
+        row[col_idx] = value;
+    }
+    Ok(Some((output, datasets)))
+}
+
+/// Replaces a dataset's columns, cells and their origins with those of `dataset`, as a
+/// fresh import of it would store them. Column settings stay with their positions and
+/// owner shares with their rows; what the last save changed no longer applies and is
+/// dropped.
+pub fn replace_dataset_contents(
+    db_path: &Path,
+    dataset_id: i64,
+    dataset: &ParsedDataset,
+    types: &[Option<ColumnType>],
+) -> Result<()> {
+    let (headers, rows) = (&dataset.headers, &dataset.rows);
+    let mut conn = open_connection(db_path)?;
+    let tx = conn
+        .transaction()
+        .context("failed to start dataset replace transaction")?;
+    for table in [
+        "column_name",
+        "column_type",
+        "cell",
+        "cell_origin",
+        "saved_change",
+    ] {
+        tx.execute(
+            &format!("DELETE FROM {table} WHERE dataset_id = ?1"),
+            params![dataset_id],
+        )
+        .with_context(|| format!("failed to clear {table} of dataset #{dataset_id}"))?;
+    }
+    tx.execute(
+        "DELETE FROM owner_share WHERE dataset_id = ?1 AND row_idx >= ?2",
+        params![dataset_id, rows.len() as i64],
+    )
+    .context("failed to clear owner shares of removed rows")?;
+
+    insert_header_names(&tx, dataset_id, headers)?;
+    insert_column_types(&tx, dataset_id, types)?;
+    insert_cell_origins(&tx, dataset_id, &dataset.origins)?;
+    let mut insert_cell = tx
+        .prepare("INSERT INTO cell(dataset_id, row_idx, col_idx, value) VALUES (?1, ?2, ?3, ?4)")
+        .context("failed to prepare cell insert")?;
+    for (row_idx, row) in rows.iter().enumerate() {
+        for (col_idx, value) in row.iter().enumerate() {
+            insert_cell
+                .execute(params![dataset_id, row_idx as i64, col_idx as i64, value])
+                .context("failed to insert replaced cell")?;
+        }
+    }
+    drop(insert_cell);
+
+    tx.execute(
+        "UPDATE dataset SET row_count = ?1, revision = revision + 1 WHERE id = ?2",
+        params![rows.len() as i64, dataset_id],
+    )
+    .context("failed to update dataset row_count")?;
+    tx.commit().context("failed to commit dataset replace")?;
+    Ok(())
+}
+
+/// Tables holding a dataset's cells and per-dataset settings, with the columns besides
+/// `dataset_id`. [`clone_dataset`] copies every one of them, so a new per-dataset setting
+/// table belongs here too.
+const DATASET_TABLES: [(&str, &str); 15] = [
+    ("column_name", "col_idx, name"),
+    ("cell", "row_idx, col_idx, value"),
+    ("column_visibility", "col_idx, visible, alias"),
+    ("column_visibility_preset", "name, col_idx, visible"),
+    (
+        "column_format",
+        "col_idx, decimals, percent, thousands_separator, currency_symbol",
+    ),
+    ("column_type", "col_idx, type"),
+    ("dataset_flag", "is_holdings"),
+    ("dataset_display", "currency, unit"),
+    ("dataset_page_size", "page_size"),
+    ("computed_column", "position, name, expression"),
+    ("owner_share", "row_idx, position, owner, percent"),
+    ("saved_change", "row_idx, col_idx, previous"),
+    (
+        "cell_origin",
+        "row_idx, col_idx, sheet, source_row, source_col, value",
+    ),
+    ("source_sheet", "position, sheet, output"),
+    ("source_sheet_cell", "position, row_idx, col_idx, value"),
+];
+
+/// Copies dataset `source_id` as it is stored, under `name`: its cells together with the
+/// column visibility and aliases, visibility presets, number formats, holdings flag,
+/// display currency and unit, page size, computed columns, owner shares, what its last save
+/// changed, where its imported cells came from and the sheets it was transformed from.
+/// Staged edits are not part of it; summary history
+/// stays with the source.
+pub fn clone_dataset(db_path: &Path, source_id: i64, name: &str, source_path: &str) -> Result<i64> {
+    init_db(db_path)?;
+    let mut conn = open_connection(db_path)?;
+    let tx = conn
+        .transaction()
+        .context("failed to start dataset clone transaction")?;
+
+    let inserted = tx
+        .execute(
+            "INSERT INTO dataset(name, source_path, row_count, kind)
+             SELECT ?1, ?2, row_count, kind FROM dataset WHERE id = ?3",
+            params![name, source_path, source_id],
+        )
+        .context("failed to insert cloned dataset")?;
+    if inserted == 0 {
+        anyhow::bail!("dataset {source_id} not found");
+    }
+    let dataset_id = tx.last_insert_rowid();
+
+    for (table, columns) in DATASET_TABLES {
+        tx.execute(
+            &format!(
+                "INSERT INTO {table}(dataset_id, {columns})
+                 SELECT ?1, {columns} FROM {table} WHERE dataset_id = ?2"
+            ),
+            params![dataset_id, source_id],
+        )
+        .with_context(|| format!("failed to copy {table} of dataset {source_id}"))?;
+    }
+
+    tx.commit().context("failed to commit dataset clone")?;
+    Ok(dataset_id)
+}
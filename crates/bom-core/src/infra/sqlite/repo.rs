@@ -0,0 +1,488 @@
+use std::path::{Path, PathBuf};
+
+use rusqlite::ErrorCode;
+
+use crate::domain::entities::cell_origin::CellOrigins;
+use crate::domain::entities::column_set::ColumnSet;
+use crate::domain::entities::computed_column::ComputedColumn;
+use crate::domain::entities::dataset::{
+    ColumnFormat, ColumnStats, ColumnType, DatasetDisplay, DatasetId, DatasetKind, PageQuery,
+    PageResult, SortDirection,
+};
+use crate::domain::entities::deposit_rate::DepositRate;
+use crate::domain::entities::edit::{SavedChanges, StagedEdits};
+use crate::domain::entities::import_profile::ImportProfile;
+use crate::domain::entities::import_record::ImportRecord;
+use crate::domain::entities::owner_share::{OwnerShare, OwnerSplits};
+use crate::domain::entities::price_point::{PriceHistory, PricePoint};
+use crate::domain::entities::saved_report::SavedReport;
+use crate::infra::sqlite::queries::{
+    acknowledge_alerts, apply_changes_to_dataset, backup_database, clone_dataset, column_stats,
+    column_value_counts, create_dataset_from_rows, dataset_revision, delete_column_set,
+    delete_import_profile, delete_saved_report, delete_visibility_preset, empty_columns,
+    expired_datasets, list_datasets, load_acknowledged_alerts, load_cell_origins,
+    load_column_aliases, load_column_formats, load_column_set, load_column_types,
+    load_column_visibility, load_computed_columns, load_dataset_display, load_dataset_overview,
+    load_deposit_rates, load_holdings_flags, load_import_history, load_import_profiles,
+    load_owner_splits, load_page_size, load_price_history, load_saved_changes, load_saved_reports,
+    load_setting, load_summary_reports, load_visibility_presets, purge_dataset, query_page,
+    query_stored_page, record_prices, rename_dataset, restore_dataset, save_column_set,
+    save_computed_columns, save_dataset_display, save_deposit_rates, save_import_profile,
+    save_owner_shares, save_page_size, save_saved_report, save_setting, save_summary_report,
+    save_visibility_preset, set_dataset_kind, set_keep_forever, soft_delete_dataset,
+    upsert_column_aliases, upsert_column_formats, upsert_column_types, upsert_column_visibility,
+    upsert_holdings_flag,
+};
+use crate::infra::sqlite::schema::init_db;
+use crate::report::{StoredSummaryReport, SummaryReport};
+use crate::usecase::ports::repo::{
+    DatasetContext, DatasetMeta, DatasetRepository, NewDatasetMeta, RepoError, TabularData,
+};
+use crate::QueryOptions;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Keeps the whole anyhow context chain in the message so the UI can show it.
+fn repo_error(err: anyhow::Error) -> RepoError {
+    tracing::warn!(target: "bom::repo", "{err:#}");
+    let message = format!("{err:#}");
+    for cause in err.chain() {
+        if let Some(sqlite_err) = cause.downcast_ref::<rusqlite::Error>() {
+            match sqlite_err {
+                rusqlite::Error::QueryReturnedNoRows => return RepoError::NotFound(message),
+                rusqlite::Error::SqliteFailure(failure, detail) => {
+                    let detail = detail.as_deref().unwrap_or_default();
+                    match failure.code {
+                        ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked => {
+                            return RepoError::Busy(message)
+                        }
+                        ErrorCode::NotADatabase => return RepoError::SchemaMismatch(message),
+                        _ if detail.starts_with("no such table")
+                            || detail.starts_with("no such column") =>
+                        {
+                            return RepoError::SchemaMismatch(message)
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+        if let Some(io_err) = cause.downcast_ref::<std::io::Error>() {
+            if io_err.kind() == std::io::ErrorKind::NotFound {
+                return RepoError::FileNotFound(message);
+            }
+        }
+    }
+    RepoError::Message(message)
+}
+
+#[allow(dead_code)]
+pub struct SqliteRepo {
+    pub db_path: PathBuf,
+}
+
+impl DatasetRepository for SqliteRepo {
+    fn init(&self) -> Result<(), RepoError> {
+        init_db(&self.db_path).map_err(repo_error)
+    }
+
+    fn list_datasets(&self, include_deleted: bool) -> Result<Vec<DatasetMeta>, RepoError> {
+        list_datasets(&self.db_path, include_deleted).map_err(repo_error)
+    }
+
+    fn query_page(&self, query: PageQuery) -> Result<PageResult, RepoError> {
+        let (dataset_id, page, page_size) = (query.dataset_id.0, query.page, query.page_size);
+        let (columns, rows, total_rows) = query_page(
+            &self.db_path,
+            dataset_id,
+            page,
+            page_size,
+            &query_options(query),
+        )
+        .map_err(repo_error)?;
+
+        Ok(PageResult {
+            columns,
+            rows,
+            total_rows,
+        })
+    }
+
+    fn load_tabular_data(&self, id: DatasetId) -> Result<TabularData, RepoError> {
+        let (columns, rows, _total) =
+            query_stored_page(&self.db_path, id.0, 0, i64::MAX, &QueryOptions::default())
+                .map_err(repo_error)?;
+        Ok(TabularData { columns, rows })
+    }
+
+    fn empty_columns(&self, query: PageQuery) -> Result<BTreeSet<i64>, RepoError> {
+        let dataset_id = query.dataset_id.0;
+        empty_columns(&self.db_path, dataset_id, &query_options(query)).map_err(repo_error)
+    }
+
+    fn column_stats(&self, query: PageQuery) -> Result<BTreeMap<i64, ColumnStats>, RepoError> {
+        let dataset_id = query.dataset_id.0;
+        column_stats(&self.db_path, dataset_id, &query_options(query)).map_err(repo_error)
+    }
+    fn create_dataset(
+        &self,
+        meta: NewDatasetMeta,
+        data: TabularData,
+    ) -> Result<DatasetId, RepoError> {
+        let dataset_id = create_dataset_from_rows(
+            &self.db_path,
+            &meta.name,
+            &meta.source_path,
+            &data.columns,
+            &data.rows,
+        )
+        .map_err(repo_error)?;
+
+        Ok(DatasetId(dataset_id))
+    }
+
+    fn apply_edits(&self, id: DatasetId, edits: StagedEdits) -> Result<(), RepoError> {
+        let (columns, rows, _total) =
+            query_stored_page(&self.db_path, id.0, 0, i64::MAX, &QueryOptions::default())
+                .map_err(repo_error)?;
+        // Added rows come from the page view, which ends with the computed columns.
+        let added_rows: Vec<Vec<String>> = edits
+            .added_rows
+            .into_iter()
+            .map(|mut row| {
+                row.truncate(columns.len());
+                row
+            })
+            .collect();
+
+        apply_changes_to_dataset(
+            &self.db_path,
+            id.0,
+            &columns,
+            &rows,
+            &edits.staged_cells,
+            &edits.deleted_rows,
+            &added_rows,
+        )
+        .map_err(repo_error)
+    }
+
+    fn soft_delete_dataset(&self, id: DatasetId) -> Result<(), RepoError> {
+        soft_delete_dataset(&self.db_path, id.0).map_err(repo_error)
+    }
+
+    fn purge_dataset(&self, id: DatasetId) -> Result<(), RepoError> {
+        purge_dataset(&self.db_path, id.0).map_err(repo_error)
+    }
+
+    fn restore_dataset(&self, id: DatasetId) -> Result<(), RepoError> {
+        restore_dataset(&self.db_path, id.0).map_err(repo_error)
+    }
+
+    fn set_keep_forever(&self, id: DatasetId, keep_forever: bool) -> Result<(), RepoError> {
+        set_keep_forever(&self.db_path, id.0, keep_forever).map_err(repo_error)
+    }
+
+    fn expired_datasets(&self, retention_days: u32) -> Result<Vec<DatasetId>, RepoError> {
+        let ids = expired_datasets(&self.db_path, retention_days).map_err(repo_error)?;
+        Ok(ids.into_iter().map(DatasetId).collect())
+    }
+
+    fn load_column_visibility(&self, id: DatasetId) -> Result<BTreeMap<i64, bool>, RepoError> {
+        load_column_visibility(&self.db_path, id.0).map_err(repo_error)
+    }
+
+    fn upsert_column_visibility(
+        &self,
+        id: DatasetId,
+        visibility: BTreeMap<i64, bool>,
+    ) -> Result<(), RepoError> {
+        upsert_column_visibility(&self.db_path, id.0, &visibility).map_err(repo_error)
+    }
+
+    fn load_visibility_presets(
+        &self,
+        id: DatasetId,
+    ) -> Result<BTreeMap<String, BTreeMap<i64, bool>>, RepoError> {
+        load_visibility_presets(&self.db_path, id.0).map_err(repo_error)
+    }
+
+    fn save_visibility_preset(
+        &self,
+        id: DatasetId,
+        name: String,
+        visibility: BTreeMap<i64, bool>,
+    ) -> Result<(), RepoError> {
+        save_visibility_preset(&self.db_path, id.0, &name, &visibility).map_err(repo_error)
+    }
+
+    fn delete_visibility_preset(&self, id: DatasetId, name: String) -> Result<(), RepoError> {
+        delete_visibility_preset(&self.db_path, id.0, &name).map_err(repo_error)
+    }
+
+    fn load_column_aliases(&self, id: DatasetId) -> Result<BTreeMap<i64, String>, RepoError> {
+        load_column_aliases(&self.db_path, id.0).map_err(repo_error)
+    }
+
+    fn upsert_column_aliases(
+        &self,
+        id: DatasetId,
+        aliases: BTreeMap<i64, String>,
+    ) -> Result<(), RepoError> {
+        upsert_column_aliases(&self.db_path, id.0, &aliases).map_err(repo_error)
+    }
+
+    fn load_column_formats(&self, id: DatasetId) -> Result<BTreeMap<i64, ColumnFormat>, RepoError> {
+        load_column_formats(&self.db_path, id.0).map_err(repo_error)
+    }
+
+    fn upsert_column_formats(
+        &self,
+        id: DatasetId,
+        formats: BTreeMap<i64, ColumnFormat>,
+    ) -> Result<(), RepoError> {
+        upsert_column_formats(&self.db_path, id.0, &formats).map_err(repo_error)
+    }
+
+    fn load_column_types(&self, id: DatasetId) -> Result<BTreeMap<i64, ColumnType>, RepoError> {
+        load_column_types(&self.db_path, id.0).map_err(repo_error)
+    }
+
+    fn upsert_column_types(
+        &self,
+        id: DatasetId,
+        types: BTreeMap<i64, ColumnType>,
+    ) -> Result<(), RepoError> {
+        upsert_column_types(&self.db_path, id.0, &types).map_err(repo_error)
+    }
+
+    fn load_saved_changes(&self, id: DatasetId) -> Result<SavedChanges, RepoError> {
+        load_saved_changes(&self.db_path, id.0).map_err(repo_error)
+    }
+
+    fn load_cell_origins(&self, id: DatasetId) -> Result<CellOrigins, RepoError> {
+        load_cell_origins(&self.db_path, id.0).map_err(repo_error)
+    }
+
+    fn load_dataset_display(&self, id: DatasetId) -> Result<DatasetDisplay, RepoError> {
+        load_dataset_display(&self.db_path, id.0).map_err(repo_error)
+    }
+
+    fn save_dataset_display(
+        &self,
+        id: DatasetId,
+        display: DatasetDisplay,
+    ) -> Result<(), RepoError> {
+        save_dataset_display(&self.db_path, id.0, &display).map_err(repo_error)
+    }
+
+    fn load_page_size(&self, id: DatasetId) -> Result<Option<i64>, RepoError> {
+        load_page_size(&self.db_path, id.0).map_err(repo_error)
+    }
+
+    fn save_page_size(&self, id: DatasetId, page_size: Option<i64>) -> Result<(), RepoError> {
+        save_page_size(&self.db_path, id.0, page_size).map_err(repo_error)
+    }
+
+    fn load_holdings_flags(&self) -> Result<BTreeMap<i64, bool>, RepoError> {
+        load_holdings_flags(&self.db_path).map_err(repo_error)
+    }
+
+    fn upsert_holdings_flag(&self, id: DatasetId, is_holdings: bool) -> Result<(), RepoError> {
+        upsert_holdings_flag(&self.db_path, id.0, is_holdings).map_err(repo_error)
+    }
+
+    fn rename_dataset(&self, id: DatasetId, name: String) -> Result<(), RepoError> {
+        rename_dataset(&self.db_path, id.0, &name).map_err(repo_error)
+    }
+
+    fn set_dataset_kind(&self, id: DatasetId, kind: DatasetKind) -> Result<(), RepoError> {
+        set_dataset_kind(&self.db_path, id.0, kind).map_err(repo_error)
+    }
+
+    fn backup_database(&self, dest_path: &Path) -> Result<(), RepoError> {
+        backup_database(&self.db_path, dest_path).map_err(repo_error)
+    }
+
+    fn load_setting(&self, key: &str) -> Result<Option<String>, RepoError> {
+        load_setting(&self.db_path, key).map_err(repo_error)
+    }
+
+    fn save_setting(&self, key: &str, value: &str) -> Result<(), RepoError> {
+        save_setting(&self.db_path, key, value).map_err(repo_error)
+    }
+
+    fn load_import_profiles(&self) -> Result<Vec<ImportProfile>, RepoError> {
+        load_import_profiles(&self.db_path).map_err(repo_error)
+    }
+
+    fn save_import_profile(&self, profile: ImportProfile) -> Result<(), RepoError> {
+        save_import_profile(&self.db_path, &profile).map_err(repo_error)
+    }
+
+    fn delete_import_profile(&self, name: String) -> Result<(), RepoError> {
+        delete_import_profile(&self.db_path, &name).map_err(repo_error)
+    }
+
+    fn load_column_set(&self, kind: DatasetKind) -> Result<Option<ColumnSet>, RepoError> {
+        load_column_set(&self.db_path, kind).map_err(repo_error)
+    }
+
+    fn save_column_set(&self, kind: DatasetKind, set: ColumnSet) -> Result<(), RepoError> {
+        save_column_set(&self.db_path, kind, &set).map_err(repo_error)
+    }
+
+    fn delete_column_set(&self, kind: DatasetKind) -> Result<(), RepoError> {
+        delete_column_set(&self.db_path, kind).map_err(repo_error)
+    }
+
+    fn load_saved_reports(&self) -> Result<Vec<SavedReport>, RepoError> {
+        load_saved_reports(&self.db_path).map_err(repo_error)
+    }
+
+    fn save_saved_report(&self, report: SavedReport) -> Result<(), RepoError> {
+        save_saved_report(&self.db_path, &report).map_err(repo_error)
+    }
+
+    fn delete_saved_report(&self, name: String) -> Result<(), RepoError> {
+        delete_saved_report(&self.db_path, &name).map_err(repo_error)
+    }
+
+    fn load_deposit_rates(&self) -> Result<Vec<DepositRate>, RepoError> {
+        load_deposit_rates(&self.db_path).map_err(repo_error)
+    }
+
+    fn save_deposit_rates(&self, rates: Vec<DepositRate>) -> Result<(), RepoError> {
+        save_deposit_rates(&self.db_path, &rates).map_err(repo_error)
+    }
+
+    fn load_import_history(&self) -> Result<Vec<ImportRecord>, RepoError> {
+        load_import_history(&self.db_path).map_err(repo_error)
+    }
+
+    fn record_prices(&self, prices: Vec<PricePoint>) -> Result<(), RepoError> {
+        record_prices(&self.db_path, &prices).map_err(repo_error)
+    }
+
+    fn load_price_history(&self, codes: Vec<String>) -> Result<PriceHistory, RepoError> {
+        load_price_history(&self.db_path, &codes).map_err(repo_error)
+    }
+
+    fn load_acknowledged_alerts(&self) -> Result<BTreeSet<String>, RepoError> {
+        load_acknowledged_alerts(&self.db_path).map_err(repo_error)
+    }
+
+    fn acknowledge_alerts(&self, keys: Vec<String>) -> Result<(), RepoError> {
+        acknowledge_alerts(&self.db_path, &keys).map_err(repo_error)
+    }
+
+    fn column_value_counts(
+        &self,
+        id: DatasetId,
+        col_idx: i64,
+    ) -> Result<Vec<(String, i64)>, RepoError> {
+        column_value_counts(&self.db_path, id.0, col_idx).map_err(repo_error)
+    }
+
+    fn load_computed_columns(&self, id: DatasetId) -> Result<Vec<ComputedColumn>, RepoError> {
+        load_computed_columns(&self.db_path, id.0).map_err(repo_error)
+    }
+
+    fn save_computed_columns(
+        &self,
+        id: DatasetId,
+        columns: Vec<ComputedColumn>,
+    ) -> Result<(), RepoError> {
+        save_computed_columns(&self.db_path, id.0, &columns).map_err(repo_error)
+    }
+
+    fn load_owner_splits(&self, id: DatasetId) -> Result<OwnerSplits, RepoError> {
+        load_owner_splits(&self.db_path, id.0).map_err(repo_error)
+    }
+
+    fn save_owner_shares(
+        &self,
+        id: DatasetId,
+        row_idx: usize,
+        shares: Vec<OwnerShare>,
+    ) -> Result<(), RepoError> {
+        save_owner_shares(&self.db_path, id.0, row_idx, &shares).map_err(repo_error)
+    }
+
+    fn dataset_revision(&self, id: DatasetId) -> Result<i64, RepoError> {
+        dataset_revision(&self.db_path, id.0).map_err(repo_error)
+    }
+
+    fn save_summary_report(
+        &self,
+        id: DatasetId,
+        report: &SummaryReport,
+    ) -> Result<StoredSummaryReport, RepoError> {
+        save_summary_report(&self.db_path, id.0, report).map_err(repo_error)
+    }
+
+    fn load_summary_reports(&self, id: DatasetId) -> Result<Vec<StoredSummaryReport>, RepoError> {
+        load_summary_reports(&self.db_path, id.0).map_err(repo_error)
+    }
+
+    fn clone_dataset(
+        &self,
+        source: DatasetId,
+        meta: NewDatasetMeta,
+    ) -> Result<DatasetId, RepoError> {
+        clone_dataset(&self.db_path, source.0, &meta.name, &meta.source_path)
+            .map(DatasetId)
+            .map_err(repo_error)
+    }
+
+    fn load_dataset_context(
+        &self,
+        include_deleted: bool,
+        select: &dyn Fn(&[DatasetMeta]) -> Option<PageQuery>,
+    ) -> Result<DatasetContext, RepoError> {
+        let mut query = None;
+        let mut context = load_dataset_overview(&self.db_path, include_deleted, |datasets| {
+            query = select(datasets);
+            query.as_ref().map(|query| query.dataset_id.0)
+        })
+        .map_err(repo_error)?;
+        if let Some(query) = query {
+            let dataset_id = query.dataset_id;
+            context.page = Some((dataset_id, self.query_page(query)?));
+        }
+        Ok(context)
+    }
+}
+
+fn query_options(query: PageQuery) -> QueryOptions {
+    let (column_search_col, column_search_text) = match query.column_filter {
+        Some(filter) => (Some(filter.column_idx), filter.term),
+        None => (None, String::new()),
+    };
+    let (sort_col, sort_desc) = match query.sort {
+        Some(sort) => (
+            Some(sort.column_idx),
+            matches!(sort.direction, SortDirection::Desc),
+        ),
+        None => (None, false),
+    };
+    let value_filters = query
+        .value_filters
+        .into_iter()
+        .map(|filter| (filter.column_idx, filter.values.into_iter().collect()))
+        .collect();
+    let column_conditions = query
+        .column_conditions
+        .into_iter()
+        .map(|condition| (condition.column_idx, condition.expression))
+        .collect();
+    QueryOptions {
+        global_search: query.global_search,
+        column_search_col,
+        column_search_text,
+        value_filters,
+        column_conditions,
+        sort_col,
+        sort_desc,
+    }
+}
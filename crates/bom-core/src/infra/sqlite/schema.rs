@@ -0,0 +1,362 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+
+use crate::numeric::{is_percent_header, percent_to_ratio_text};
+
+#[allow(dead_code)]
+pub fn open_connection(db_path: &Path) -> Result<Connection> {
+    let conn = Connection::open(db_path)
+        .with_context(|| format!("failed to open db: {}", db_path.display()))?;
+    conn.execute("PRAGMA foreign_keys = ON", [])
+        .context("failed to enable foreign key enforcement")?;
+    Ok(conn)
+}
+
+#[allow(dead_code)]
+pub fn init_db(db_path: &Path) -> Result<()> {
+    if let Some(parent) = db_path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create parent dir: {}", parent.display()))?;
+    }
+
+    let conn = open_connection(db_path)?;
+
+    conn.execute_batch(
+        "
+        CREATE TABLE IF NOT EXISTS dataset (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            name        TEXT NOT NULL,
+            source_path TEXT NOT NULL,
+            row_count   INTEGER NOT NULL,
+            deleted_at  TEXT,
+            imported_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            revision    INTEGER NOT NULL DEFAULT 0,
+            keep_forever INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE TABLE IF NOT EXISTS column_name (
+            dataset_id  INTEGER NOT NULL,
+            col_idx     INTEGER NOT NULL,
+            name        TEXT NOT NULL,
+            PRIMARY KEY (dataset_id, col_idx),
+            FOREIGN KEY (dataset_id) REFERENCES dataset(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS cell (
+            dataset_id  INTEGER NOT NULL,
+            row_idx     INTEGER NOT NULL,
+            col_idx     INTEGER NOT NULL,
+            value       TEXT NOT NULL,
+            PRIMARY KEY (dataset_id, row_idx, col_idx),
+            FOREIGN KEY (dataset_id) REFERENCES dataset(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS column_visibility (
+            dataset_id  INTEGER NOT NULL,
+            col_idx     INTEGER NOT NULL,
+            visible     INTEGER NOT NULL,
+            alias       TEXT NOT NULL DEFAULT '',
+            PRIMARY KEY (dataset_id, col_idx),
+            FOREIGN KEY (dataset_id) REFERENCES dataset(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS column_visibility_preset (
+            dataset_id  INTEGER NOT NULL,
+            name        TEXT NOT NULL,
+            col_idx     INTEGER NOT NULL,
+            visible     INTEGER NOT NULL,
+            PRIMARY KEY (dataset_id, name, col_idx),
+            FOREIGN KEY (dataset_id) REFERENCES dataset(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS column_format (
+            dataset_id          INTEGER NOT NULL,
+            col_idx             INTEGER NOT NULL,
+            decimals            INTEGER NOT NULL,
+            percent             INTEGER NOT NULL,
+            thousands_separator INTEGER NOT NULL,
+            currency_symbol     TEXT NOT NULL DEFAULT '',
+            PRIMARY KEY (dataset_id, col_idx),
+            FOREIGN KEY (dataset_id) REFERENCES dataset(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS column_type (
+            dataset_id  INTEGER NOT NULL,
+            col_idx     INTEGER NOT NULL,
+            type        TEXT NOT NULL,
+            PRIMARY KEY (dataset_id, col_idx),
+            FOREIGN KEY (dataset_id) REFERENCES dataset(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS dataset_flag (
+            dataset_id   INTEGER PRIMARY KEY,
+            is_holdings  INTEGER NOT NULL DEFAULT 0,
+            FOREIGN KEY (dataset_id) REFERENCES dataset(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS saved_change (
+            dataset_id  INTEGER NOT NULL,
+            row_idx     INTEGER NOT NULL,
+            col_idx     INTEGER NOT NULL,
+            previous    TEXT,
+            PRIMARY KEY (dataset_id, row_idx, col_idx),
+            FOREIGN KEY (dataset_id) REFERENCES dataset(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS dataset_display (
+            dataset_id  INTEGER PRIMARY KEY,
+            currency    TEXT NOT NULL,
+            unit        TEXT NOT NULL,
+            FOREIGN KEY (dataset_id) REFERENCES dataset(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS dataset_page_size (
+            dataset_id  INTEGER PRIMARY KEY,
+            page_size   INTEGER NOT NULL,
+            FOREIGN KEY (dataset_id) REFERENCES dataset(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS app_setting (
+            key    TEXT PRIMARY KEY,
+            value  TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS import_profile (
+            name        TEXT PRIMARY KEY,
+            header_row  INTEGER NOT NULL,
+            encoding    TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS import_profile_column (
+            profile_name  TEXT NOT NULL,
+            position      INTEGER NOT NULL,
+            source        TEXT NOT NULL,
+            target        TEXT NOT NULL,
+            PRIMARY KEY (profile_name, position),
+            FOREIGN KEY (profile_name) REFERENCES import_profile(name) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS column_set (
+            kind  TEXT PRIMARY KEY
+        );
+
+        CREATE TABLE IF NOT EXISTS column_set_column (
+            kind      TEXT NOT NULL,
+            role      TEXT NOT NULL,
+            position  INTEGER NOT NULL,
+            name      TEXT NOT NULL,
+            PRIMARY KEY (kind, role, position),
+            FOREIGN KEY (kind) REFERENCES column_set(kind) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS computed_column (
+            dataset_id  INTEGER NOT NULL,
+            position    INTEGER NOT NULL,
+            name        TEXT NOT NULL,
+            expression  TEXT NOT NULL,
+            PRIMARY KEY (dataset_id, position),
+            FOREIGN KEY (dataset_id) REFERENCES dataset(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS owner_share (
+            dataset_id  INTEGER NOT NULL,
+            row_idx     INTEGER NOT NULL,
+            position    INTEGER NOT NULL,
+            owner       TEXT NOT NULL,
+            percent     REAL NOT NULL,
+            PRIMARY KEY (dataset_id, row_idx, position),
+            FOREIGN KEY (dataset_id) REFERENCES dataset(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS cell_origin (
+            dataset_id  INTEGER NOT NULL,
+            row_idx     INTEGER NOT NULL,
+            col_idx     INTEGER NOT NULL,
+            sheet       TEXT NOT NULL,
+            source_row  INTEGER NOT NULL,
+            source_col  INTEGER NOT NULL,
+            value       TEXT NOT NULL,
+            PRIMARY KEY (dataset_id, row_idx, col_idx),
+            FOREIGN KEY (dataset_id) REFERENCES dataset(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS source_sheet (
+            dataset_id  INTEGER NOT NULL,
+            position    INTEGER NOT NULL,
+            sheet       TEXT NOT NULL,
+            output      TEXT NOT NULL,
+            PRIMARY KEY (dataset_id, position),
+            FOREIGN KEY (dataset_id) REFERENCES dataset(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS source_sheet_cell (
+            dataset_id  INTEGER NOT NULL,
+            position    INTEGER NOT NULL,
+            row_idx     INTEGER NOT NULL,
+            col_idx     INTEGER NOT NULL,
+            value       TEXT NOT NULL,
+            PRIMARY KEY (dataset_id, position, row_idx, col_idx),
+            FOREIGN KEY (dataset_id) REFERENCES dataset(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS saved_report (
+            name    TEXT PRIMARY KEY,
+            script  TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS deposit_rate (
+            position     INTEGER NOT NULL,
+            institution  TEXT NOT NULL,
+            tenor        TEXT NOT NULL,
+            rate         REAL NOT NULL,
+            PRIMARY KEY (institution, tenor)
+        );
+
+        CREATE TABLE IF NOT EXISTS alert_ack (
+            key              TEXT PRIMARY KEY,
+            acknowledged_at  TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS import_history (
+            id           INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind         TEXT NOT NULL,
+            source_path  TEXT NOT NULL,
+            file_hash    TEXT NOT NULL DEFAULT '',
+            imported_at  TEXT NOT NULL,
+            duration_ms  INTEGER NOT NULL,
+            warnings     TEXT NOT NULL DEFAULT ''
+        );
+
+        CREATE TABLE IF NOT EXISTS import_history_sheet (
+            history_id  INTEGER NOT NULL,
+            position    INTEGER NOT NULL,
+            dataset_id  INTEGER NOT NULL,
+            name        TEXT NOT NULL,
+            row_count   INTEGER NOT NULL,
+            PRIMARY KEY (history_id, position),
+            FOREIGN KEY (history_id) REFERENCES import_history(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS price_history (
+            code        TEXT NOT NULL,
+            fetched_at  TEXT NOT NULL,
+            price       REAL NOT NULL,
+            PRIMARY KEY (code, fetched_at)
+        );
+
+        CREATE TABLE IF NOT EXISTS summary_report (
+            id            INTEGER PRIMARY KEY AUTOINCREMENT,
+            dataset_id    INTEGER NOT NULL,
+            revision      INTEGER NOT NULL,
+            title         TEXT NOT NULL,
+            group_heading TEXT NOT NULL DEFAULT '',
+            created_at    TEXT NOT NULL,
+            FOREIGN KEY (dataset_id) REFERENCES dataset(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS summary_report_entry (
+            report_id  INTEGER NOT NULL,
+            position   INTEGER NOT NULL,
+            kind       TEXT NOT NULL,
+            owner      TEXT NOT NULL DEFAULT '',
+            label      TEXT NOT NULL,
+            value      TEXT NOT NULL,
+            PRIMARY KEY (report_id, position),
+            FOREIGN KEY (report_id) REFERENCES summary_report(id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_cell_dataset_row
+            ON cell(dataset_id, row_idx);
+
+        CREATE INDEX IF NOT EXISTS idx_cell_dataset_col_value
+            ON cell(dataset_id, col_idx, value);
+        ",
+    )
+    .context("failed to initialize schema")?;
+
+    conn.execute("ALTER TABLE dataset ADD COLUMN deleted_at TEXT", [])
+        .ok();
+    conn.execute(
+        "ALTER TABLE dataset ADD COLUMN revision INTEGER NOT NULL DEFAULT 0",
+        [],
+    )
+    .ok();
+    conn.execute(
+        "ALTER TABLE column_visibility ADD COLUMN alias TEXT NOT NULL DEFAULT ''",
+        [],
+    )
+    .ok();
+    conn.execute("ALTER TABLE dataset ADD COLUMN kind TEXT", [])
+        .ok();
+    conn.execute(
+        "ALTER TABLE dataset ADD COLUMN keep_forever INTEGER NOT NULL DEFAULT 0",
+        [],
+    )
+    .ok();
+
+    let version: i64 = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .context("failed to read schema version")?;
+    if version < 1 {
+        migrate_percent_cells_to_ratios(&conn)?;
+        conn.execute_batch("PRAGMA user_version = 1")
+            .context("failed to update schema version")?;
+    }
+
+    Ok(())
+}
+
+/// Rewrites `5%`-style values in percent columns to the stored ratio form (`0.05`). Older
+/// versions kept whatever the import or the editor produced.
+fn migrate_percent_cells_to_ratios(conn: &Connection) -> Result<()> {
+    let tx = conn
+        .unchecked_transaction()
+        .context("failed to start percent migration")?;
+    let cells = {
+        let mut stmt = tx
+            .prepare(
+                "SELECT c.dataset_id, c.row_idx, c.col_idx, c.value, n.name, f.percent
+                 FROM cell c
+                 JOIN column_name n ON n.dataset_id = c.dataset_id AND n.col_idx = c.col_idx
+                 LEFT JOIN column_format f
+                     ON f.dataset_id = c.dataset_id AND f.col_idx = c.col_idx
+                 WHERE c.value LIKE '%\\%%' ESCAPE '\\'",
+            )
+            .context("failed to prepare percent migration query")?;
+        let cells = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, Option<i64>>(5)?,
+                ))
+            })
+            .context("failed to query percent cells")?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .context("failed to read percent cell")?;
+        cells
+    };
+
+    let mut update = tx
+        .prepare(
+            "UPDATE cell SET value = ?1 WHERE dataset_id = ?2 AND row_idx = ?3 AND col_idx = ?4",
+        )
+        .context("failed to prepare percent migration update")?;
+    for (dataset_id, row_idx, col_idx, value, header, percent) in cells {
+        let is_percent = percent.map_or_else(|| is_percent_header(&header), |flag| flag != 0);
+        let ratio = percent_to_ratio_text(&value);
+        if is_percent && ratio != value {
+            update
+                .execute(params![ratio, dataset_id, row_idx, col_idx])
+                .context("failed to migrate percent cell")?;
+        }
+    }
+    drop(update);
+    tx.commit().context("failed to commit percent migration")?;
+    Ok(())
+}
@@ -0,0 +1,191 @@
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+
+use super::schema::open_connection;
+
+/// How long a writer lock stays valid without a heartbeat. Generous, because the lock file
+/// itself only reaches other machines as fast as the sync client uploads it.
+pub const WRITER_LOCK_STALE_AFTER: Duration = Duration::from_secs(10 * 60);
+/// How often the holder should call [`WriterLock::refresh`].
+pub const WRITER_LOCK_HEARTBEAT: Duration = Duration::from_secs(60);
+
+/// Cloud sync client whose folder the database was found in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncProvider {
+    Dropbox,
+    OneDrive,
+    GoogleDrive,
+    ICloud,
+    Other,
+}
+
+impl SyncProvider {
+    pub fn label(self) -> &'static str {
+        match self {
+            SyncProvider::Dropbox => "Dropbox",
+            SyncProvider::OneDrive => "OneDrive",
+            SyncProvider::GoogleDrive => "Google Drive",
+            SyncProvider::ICloud => "iCloud Drive",
+            SyncProvider::Other => "同步資料夾",
+        }
+    }
+}
+
+/// Returns the sync client whose folder contains `db_path`, judged by the folder names the
+/// clients use (and Dropbox's `.dropbox` marker). Symlinks are resolved first, so a data
+/// directory linked into Dropbox is caught too.
+pub fn detect_sync_folder(db_path: &Path) -> Option<SyncProvider> {
+    let dir = db_path.parent().unwrap_or(db_path);
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    for ancestor in dir.ancestors() {
+        if ancestor.join(".dropbox").exists() {
+            return Some(SyncProvider::Dropbox);
+        }
+        let Some(name) = ancestor.file_name().and_then(OsStr::to_str) else {
+            continue;
+        };
+        let provider = match name {
+            "Dropbox" => SyncProvider::Dropbox,
+            _ if name == "OneDrive" || name.starts_with("OneDrive - ") => SyncProvider::OneDrive,
+            "Google Drive" | "GoogleDrive" | "My Drive" => SyncProvider::GoogleDrive,
+            "iCloud Drive" | "iCloudDrive" | "Mobile Documents" => SyncProvider::ICloud,
+            "Nextcloud" | "ownCloud" | "pCloud Drive" | "Box" | "MEGA" => SyncProvider::Other,
+            _ => continue,
+        };
+        return Some(provider);
+    }
+    None
+}
+
+/// Files next to `db_path` that look like a sync client's conflicted copy of it, e.g.
+/// `datasets (Ann's conflicted copy 2024-05-01).sqlite`, `datasets-LAPTOP.sqlite`
+/// (OneDrive), `datasets (1).sqlite` or `datasets.sync-conflict-….sqlite`.
+pub fn find_conflicted_copies(db_path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(stem), Some(db_name)) = (
+        db_path.parent(),
+        db_path.file_stem().and_then(OsStr::to_str),
+        db_path.file_name(),
+    ) else {
+        return Vec::new();
+    };
+    let extension = db_path.extension();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut copies = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.file_name() != Some(db_name) && path.extension() == extension)
+        .filter(|path| {
+            let Some(rest) = path
+                .file_stem()
+                .and_then(OsStr::to_str)
+                .and_then(|other| other.strip_prefix(stem))
+            else {
+                return false;
+            };
+            let lower = rest.to_lowercase();
+            lower.contains("conflict")
+                || rest.starts_with('-')
+                || (rest.starts_with(" (") && rest.ends_with(')'))
+        })
+        .collect::<Vec<_>>();
+    copies.sort();
+    copies
+}
+
+/// Puts the database in rollback-journal mode with full syncs. WAL keeps recent commits in
+/// `-wal` / `-shm` side files, which sync clients upload separately from the database and
+/// can pair up wrongly on another machine; a rollback journal leaves the database file
+/// complete after every commit.
+pub fn use_sync_safe_journal(db_path: &Path) -> Result<()> {
+    let conn = open_connection(db_path)?;
+    conn.query_row("PRAGMA journal_mode = DELETE", [], |_| Ok(()))
+        .context("failed to switch journal mode")?;
+    conn.execute_batch("PRAGMA synchronous = FULL")
+        .context("failed to set synchronous mode")?;
+    Ok(())
+}
+
+/// Who holds the writer lock of a database in a sync folder.
+pub enum WriterLockOutcome {
+    Acquired(WriterLock),
+    /// Another machine holds a fresh lock; this one should stay read-only.
+    HeldBy(String),
+}
+
+/// Lock file (`<db>.lock`) marking the one machine allowed to write a database that is
+/// shared through a sync folder. The file is removed on drop if it is still ours.
+#[derive(Debug)]
+pub struct WriterLock {
+    path: PathBuf,
+    holder: String,
+}
+
+/// Takes the writer lock for `holder` unless another holder refreshed it within
+/// [`WRITER_LOCK_STALE_AFTER`]. A lock left behind by `holder` itself is taken over.
+pub fn acquire_writer_lock(db_path: &Path, holder: &str) -> Result<WriterLockOutcome> {
+    let path = writer_lock_path(db_path);
+    if let Ok(text) = std::fs::read_to_string(&path) {
+        let mut lines = text.lines();
+        let other = lines.next().unwrap_or_default().trim();
+        let refreshed_at = lines
+            .next()
+            .and_then(|line| line.trim().parse::<u64>().ok())
+            .unwrap_or(0);
+        let fresh = unix_now().saturating_sub(refreshed_at) < WRITER_LOCK_STALE_AFTER.as_secs();
+        if !other.is_empty() && other != holder && fresh {
+            return Ok(WriterLockOutcome::HeldBy(other.to_string()));
+        }
+    }
+    let lock = WriterLock {
+        path,
+        holder: holder.to_string(),
+    };
+    lock.refresh()?;
+    Ok(WriterLockOutcome::Acquired(lock))
+}
+
+impl WriterLock {
+    /// Rewrites the lock with the current time so other machines keep treating it as live.
+    pub fn refresh(&self) -> Result<()> {
+        std::fs::write(&self.path, format!("{}\n{}\n", self.holder, unix_now()))
+            .with_context(|| format!("failed to write lock file: {}", self.path.display()))
+    }
+}
+
+impl Drop for WriterLock {
+    fn drop(&mut self) {
+        let still_ours = std::fs::read_to_string(&self.path)
+            .is_ok_and(|text| text.lines().next() == Some(self.holder.as_str()));
+        if still_ours {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+pub fn writer_lock_path(db_path: &Path) -> PathBuf {
+    let mut name = db_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".lock");
+    db_path.with_file_name(name)
+}
+
+/// Name this machine goes by in a writer lock.
+pub fn machine_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
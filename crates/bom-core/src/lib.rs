@@ -0,0 +1,129 @@
+//! Core of BOM: dataset entities, the repository port and its SQLite implementation,
+//! CSV/XLSX import and export, and the holdings / summary calculations.
+//!
+//! Nothing here depends on a UI toolkit, so the desktop app, command-line tools and
+//! integration tests all drive the same code. The usual entry point is
+//! [`usecase::services::query_service::QueryService`] wrapped around
+//! [`infra::sqlite::repo::SqliteRepo`]:
+//!
+//! ```no_run
+//! use std::path::PathBuf;
+//! use std::sync::Arc;
+//!
+//! use bom_core::infra::sqlite::repo::SqliteRepo;
+//! use bom_core::usecase::services::query_service::QueryService;
+//!
+//! let repo = SqliteRepo {
+//!     db_path: PathBuf::from("datasets.sqlite"),
+//! };
+//! let service = QueryService::new(Arc::new(repo));
+//! let datasets = service.list_datasets(false).unwrap();
+//! ```
+
+pub mod alerts;
+pub mod assets;
+pub mod demo;
+pub mod diff;
+pub mod dividend_receipts;
+pub mod dividend_sweep;
+pub mod dividend_year;
+pub mod domain;
+pub mod expression;
+pub mod filter_expr;
+pub mod holding_detail;
+pub mod holdings;
+pub mod income_goal;
+pub mod infra;
+pub mod numeric;
+pub mod owner_split;
+pub mod prices;
+pub mod report;
+pub mod report_script;
+pub mod retirement;
+pub mod trade_costs;
+pub mod usecase;
+pub mod year_comparison;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::domain::entities::dataset::{
+    ColumnCondition, ColumnFilter, PageQuery, SortDirection, SortSpec, ValueFilter,
+};
+
+/// Rows the table loads and renders at once. Results up to this size arrive whole; larger
+/// ones (a million-row import would hang the webview) are paged instead of rendered in full.
+pub const PAGE_SIZE: i64 = 5_000;
+
+/// Page sizes a dataset can be set to; [`PAGE_SIZE`] is the default.
+pub const PAGE_SIZE_CHOICES: [i64; 4] = [100, 500, 1_000, PAGE_SIZE];
+
+/// Results above this many rows are only rendered unpaged after the 載入全部 prompt is
+/// confirmed.
+pub const LOAD_ALL_CONFIRM_ROWS: i64 = 20_000;
+
+/// Dataset created by an import and how many rows it received.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportResult {
+    pub dataset_id: i64,
+    pub row_count: i64,
+}
+
+/// Search and sort settings applied when paging through a dataset.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryOptions {
+    pub global_search: String,
+    pub column_search_col: Option<i64>,
+    pub column_search_text: String,
+    /// Quick filters: a row is kept when, for every listed column, its value is one of
+    /// the selected values. Columns with no selected values do not filter.
+    pub value_filters: BTreeMap<i64, BTreeSet<String>>,
+    /// Filter expressions typed under the column headers, see [`filter_expr`]. A row is
+    /// kept when it passes all of them; blank or unreadable ones do not filter.
+    pub column_conditions: BTreeMap<i64, String>,
+    pub sort_col: Option<i64>,
+    pub sort_desc: bool,
+}
+
+/// Builds the repository query for one page of `dataset_id` under `options`.
+pub fn build_page_query(dataset_id: i64, page: i64, options: &QueryOptions) -> PageQuery {
+    let column_filter = options.column_search_col.map(|col| ColumnFilter {
+        column_idx: col,
+        term: options.column_search_text.clone(),
+    });
+    let sort = options.sort_col.map(|col| SortSpec {
+        column_idx: col,
+        direction: if options.sort_desc {
+            SortDirection::Desc
+        } else {
+            SortDirection::Asc
+        },
+    });
+    let value_filters = options
+        .value_filters
+        .iter()
+        .filter(|(_, values)| !values.is_empty())
+        .map(|(col, values)| ValueFilter {
+            column_idx: *col,
+            values: values.iter().cloned().collect(),
+        })
+        .collect();
+    let column_conditions = options
+        .column_conditions
+        .iter()
+        .filter(|(_, expression)| !expression.trim().is_empty())
+        .map(|(col, expression)| ColumnCondition {
+            column_idx: *col,
+            expression: expression.clone(),
+        })
+        .collect();
+    PageQuery {
+        dataset_id: dataset_id.into(),
+        page,
+        page_size: PAGE_SIZE,
+        global_search: options.global_search.clone(),
+        column_filter,
+        value_filters,
+        column_conditions,
+        sort,
+    }
+}
@@ -0,0 +1,539 @@
+use chrono::NaiveDate;
+
+use crate::domain::entities::dataset::{ColumnFormat, ColumnType, DatasetDisplay};
+
+pub fn parse_f64(value: &str) -> f64 {
+    value.trim().replace(',', "").parse::<f64>().unwrap_or(0.0)
+}
+
+pub fn format_f64(value: f64) -> String {
+    if !value.is_finite() {
+        return String::new();
+    }
+    if (value.fract()).abs() < f64::EPSILON {
+        format!("{}", value as i64)
+    } else {
+        let mut text = format!("{value:.6}");
+        while text.ends_with('0') {
+            text.pop();
+        }
+        if text.ends_with('.') {
+            text.pop();
+        }
+        text
+    }
+}
+
+pub fn format_number_with_commas(value: f64, decimals: usize) -> String {
+    if !value.is_finite() {
+        return String::new();
+    }
+
+    let sign = if value < 0.0 { "-" } else { "" };
+    let abs = value.abs();
+    let raw = format!("{:.*}", decimals, abs);
+    let (int_part, frac_part) = raw.split_once('.').unwrap_or((&raw, ""));
+    let mut int_with_commas = String::new();
+    for (idx, ch) in int_part.chars().rev().enumerate() {
+        if idx > 0 && idx % 3 == 0 {
+            int_with_commas.push(',');
+        }
+        int_with_commas.push(ch);
+    }
+    let int_with_commas: String = int_with_commas.chars().rev().collect();
+    if decimals == 0 {
+        format!("{sign}{int_with_commas}")
+    } else {
+        format!("{sign}{int_with_commas}.{frac_part}")
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum NumericFormat {
+    Integer,
+    TwoDecimals,
+    Percent,
+}
+
+pub fn is_text_header(header: &str) -> bool {
+    matches!(
+        header,
+        "名稱"
+            | "類別"
+            | "性質"
+            | "國內 /國外"
+            | "代號"
+            | "資產形式"
+            | "所有權人"
+            | "往來機構"
+            | "帳號"
+            | "幣別"
+            | "配息方式"
+            | "備註"
+    )
+}
+
+pub fn numeric_format_for_header(header: &str) -> NumericFormat {
+    if matches!(header, "買進" | "市價" | "買入價") {
+        NumericFormat::TwoDecimals
+    } else if matches!(
+        header,
+        "損益率" | "報酬率" | "估計殖利率" | "最新殖利率" | "差異" | "殖利率" | "累計殖利率"
+    ) {
+        NumericFormat::Percent
+    } else {
+        NumericFormat::Integer
+    }
+}
+
+/// Whether a header's values are percentages. Percent columns store ratios (`0.05` for
+/// 5%); the `%` only appears on display.
+pub fn is_percent_header(header: &str) -> bool {
+    matches!(numeric_format_for_header(header), NumericFormat::Percent)
+}
+
+/// Type of a column the BOM workbook defines by its header; `None` for any other header,
+/// whose type comes from its values.
+pub fn header_column_type(header: &str) -> Option<ColumnType> {
+    if is_text_header(header) {
+        Some(ColumnType::Text)
+    } else if is_percent_header(header) {
+        Some(ColumnType::Percent)
+    } else if matches!(
+        numeric_format_for_header(header),
+        NumericFormat::TwoDecimals
+    ) {
+        Some(ColumnType::Number)
+    } else {
+        None
+    }
+}
+
+/// Type of a column from its header and values. A column is numeric only when every
+/// non-blank value is a number, and a percent column when every one also ends in `%`.
+/// Codes with a leading zero such as `0050` make it text. `None` when nothing decides it:
+/// an unknown header over blank cells.
+pub fn infer_column_type<'a>(
+    header: &str,
+    values: impl IntoIterator<Item = &'a str>,
+) -> Option<ColumnType> {
+    if let Some(column_type) = header_column_type(header) {
+        return Some(column_type);
+    }
+    let (mut seen, mut numeric, mut percent, mut date) = (false, true, true, true);
+    for value in values.into_iter().map(str::trim).filter(|v| !v.is_empty()) {
+        seen = true;
+        let is_number = parse_numeric_value(value).is_some() && !is_code_like(value);
+        numeric &= is_number;
+        percent &= is_number && value.ends_with('%');
+        date &= parse_date_value(value).is_some();
+        if !numeric && !date {
+            return Some(ColumnType::Text);
+        }
+    }
+    if !seen {
+        None
+    } else if percent {
+        Some(ColumnType::Percent)
+    } else if numeric {
+        Some(ColumnType::Number)
+    } else {
+        Some(ColumnType::Date)
+    }
+}
+
+/// [`infer_column_type`] for every column of `rows`.
+pub fn infer_column_types(headers: &[String], rows: &[Vec<String>]) -> Vec<Option<ColumnType>> {
+    headers
+        .iter()
+        .enumerate()
+        .map(|(idx, header)| {
+            infer_column_type(
+                header,
+                rows.iter()
+                    .filter_map(|row| row.get(idx))
+                    .map(String::as_str),
+            )
+        })
+        .collect()
+}
+
+fn is_code_like(value: &str) -> bool {
+    value.len() > 1 && value.starts_with('0') && value.chars().all(|ch| ch.is_ascii_digit())
+}
+
+/// A date written as `2024-01-31`, `2024/1/31` or `2024.01.31`.
+pub fn parse_date_value(value: &str) -> Option<NaiveDate> {
+    let trimmed = value.trim();
+    ["%Y-%m-%d", "%Y/%m/%d", "%Y.%m.%d"]
+        .into_iter()
+        .find_map(|format| NaiveDate::parse_from_str(trimmed, format).ok())
+}
+
+/// Ratio text for a value written as a percentage: `5%` becomes `0.05`. Values without a
+/// `%` are taken to be ratios already and, like non-numeric text, come back unchanged.
+pub fn percent_to_ratio_text(raw: &str) -> String {
+    if !raw.trim().ends_with('%') {
+        return raw.to_string();
+    }
+    match parse_numeric_value(raw) {
+        Some(ratio) => format_f64(ratio),
+        None => raw.to_string(),
+    }
+}
+
+/// Stored form of an imported or edited cell. A column is a percent column when its
+/// stored format says so, or by type when it has none.
+pub fn canonical_cell_value(
+    column_type: ColumnType,
+    raw: &str,
+    format: Option<&ColumnFormat>,
+) -> String {
+    let percent = format.map_or(column_type == ColumnType::Percent, |format| format.percent);
+    if percent {
+        percent_to_ratio_text(raw)
+    } else {
+        raw.to_string()
+    }
+}
+
+/// A ratio shown as a percentage, the way percent columns display it: `0.016` → `1.60%`.
+pub fn format_percent(ratio: f64) -> String {
+    format_numeric_value(
+        ratio,
+        &ColumnFormat {
+            decimals: 2,
+            percent: true,
+            thousands_separator: true,
+            currency_symbol: String::new(),
+        },
+    )
+}
+
+pub fn parse_numeric_value(value: &str) -> Option<f64> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    let (number_text, is_percent) = if trimmed.ends_with('%') {
+        (trimmed.trim_end_matches('%'), true)
+    } else {
+        (trimmed, false)
+    };
+    let cleaned = number_text.replace(',', "");
+    let parsed = cleaned.parse::<f64>().ok()?;
+    if is_percent {
+        Some(parsed / 100.0)
+    } else {
+        Some(parsed)
+    }
+}
+
+/// How numbers are written in imported text files and typed into cells. Stored values
+/// always use the [`NumberLocale::DotDecimal`] form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberLocale {
+    /// `1,234.56`
+    #[default]
+    DotDecimal,
+    /// `1.234,56`, as in German, Italian or Spanish exports.
+    CommaDecimal,
+    /// `1 234,56`, as in French or Nordic exports.
+    SpaceCommaDecimal,
+}
+
+impl NumberLocale {
+    pub const ALL: [NumberLocale; 3] = [
+        NumberLocale::DotDecimal,
+        NumberLocale::CommaDecimal,
+        NumberLocale::SpaceCommaDecimal,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NumberLocale::DotDecimal => "dot",
+            NumberLocale::CommaDecimal => "comma",
+            NumberLocale::SpaceCommaDecimal => "space_comma",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|locale| locale.as_str() == value.trim())
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NumberLocale::DotDecimal => "1,234.56",
+            NumberLocale::CommaDecimal => "1.234,56",
+            NumberLocale::SpaceCommaDecimal => "1 234,56",
+        }
+    }
+
+    fn decimal_separator(self) -> char {
+        match self {
+            NumberLocale::DotDecimal => '.',
+            NumberLocale::CommaDecimal | NumberLocale::SpaceCommaDecimal => ',',
+        }
+    }
+
+    fn is_group_separator(self, ch: char) -> bool {
+        match self {
+            NumberLocale::DotDecimal => ch == ',',
+            NumberLocale::CommaDecimal => ch == '.',
+            // Spreadsheets often export a no-break or narrow no-break space here.
+            NumberLocale::SpaceCommaDecimal => matches!(ch, ' ' | '\u{a0}' | '\u{202f}'),
+        }
+    }
+}
+
+/// Rewrites a number written in `locale` (e.g. `-1.234,5%`) into the stored form
+/// (`-1234.5%`). Anything that is not a well-formed number in `locale`, including text
+/// such as stock codes, comes back unchanged; so does every value under the default
+/// locale, whose parsing already accepts comma grouping.
+pub fn normalize_numeric_text(value: &str, locale: NumberLocale) -> String {
+    if locale == NumberLocale::DotDecimal {
+        return value.to_string();
+    }
+    let trimmed = value.trim();
+    let (body, percent) = match trimmed.strip_suffix('%') {
+        Some(body) => (body.trim_end(), "%"),
+        None => (trimmed, ""),
+    };
+    let (sign, unsigned) = match body.strip_prefix(['-', '+']) {
+        Some(rest) => (&body[..1], rest),
+        None => ("", body),
+    };
+    let (int_part, frac_part) = match unsigned.split_once(locale.decimal_separator()) {
+        Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+        None => (unsigned, None),
+    };
+
+    let is_digits = |text: &str| !text.is_empty() && text.chars().all(|ch| ch.is_ascii_digit());
+    let groups = int_part
+        .split(|ch| locale.is_group_separator(ch))
+        .collect::<Vec<_>>();
+    let int_ok = match groups.as_slice() {
+        [single] => is_digits(single) || (single.is_empty() && frac_part.is_some()),
+        [first, rest @ ..] => {
+            is_digits(first)
+                && first.len() <= 3
+                && rest
+                    .iter()
+                    .all(|group| is_digits(group) && group.len() == 3)
+        }
+        [] => false,
+    };
+    if !int_ok || frac_part.is_some_and(|frac| !is_digits(frac)) {
+        return value.to_string();
+    }
+
+    let mut normalized = format!("{sign}{}", groups.concat());
+    if let Some(frac) = frac_part {
+        normalized.push('.');
+        normalized.push_str(frac);
+    }
+    normalized.push_str(percent);
+    normalized
+}
+
+/// [`parse_numeric_value`] for text written in `locale`.
+pub fn parse_numeric_value_in(value: &str, locale: NumberLocale) -> Option<f64> {
+    parse_numeric_value(&normalize_numeric_text(value, locale))
+}
+
+/// Built-in format for a column, used when it has no stored format.
+pub fn default_column_format(header: &str, column_type: ColumnType) -> ColumnFormat {
+    let (decimals, percent) = match (column_type, numeric_format_for_header(header)) {
+        (ColumnType::Percent, _) => (2, true),
+        (_, NumericFormat::TwoDecimals) => (2, false),
+        _ => (0, false),
+    };
+    ColumnFormat {
+        decimals,
+        percent,
+        thousands_separator: true,
+        currency_symbol: String::new(),
+    }
+}
+
+pub fn format_numeric_value(value: f64, format: &ColumnFormat) -> String {
+    let scaled = if format.percent { value * 100.0 } else { value };
+    let decimals = format.decimals as usize;
+    let number = if format.thousands_separator {
+        format_number_with_commas(scaled, decimals)
+    } else if scaled.is_finite() {
+        format!("{:.*}", decimals, scaled)
+    } else {
+        String::new()
+    };
+    if number.is_empty() {
+        return number;
+    }
+    let (sign, digits) = match number.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", number.as_str()),
+    };
+    let suffix = if format.percent { "%" } else { "" };
+    format!("{sign}{}{digits}{suffix}", format.currency_symbol)
+}
+
+#[allow(dead_code)]
+pub fn format_cell_value(header: &str, raw: &str) -> String {
+    format_cell_value_with(header, raw, None)
+}
+
+/// Formats a cell with the column's stored format, typing the column by its header.
+pub fn format_cell_value_with(header: &str, raw: &str, format: Option<&ColumnFormat>) -> String {
+    let column_type = header_column_type(header).unwrap_or(ColumnType::Number);
+    format_typed_cell_value(header, column_type, raw, format)
+}
+
+/// Formats a cell of a `column_type` column. Text and dates are shown as stored unless
+/// the column has a number format; numbers use the stored format or the default one.
+pub fn format_typed_cell_value(
+    header: &str,
+    column_type: ColumnType,
+    raw: &str,
+    format: Option<&ColumnFormat>,
+) -> String {
+    if format.is_none() && !column_type.is_numeric() {
+        return raw.to_string();
+    }
+    let Some(value) = parse_numeric_value(raw) else {
+        return raw.to_string();
+    };
+    match format {
+        Some(format) => format_numeric_value(value, format),
+        None => format_numeric_value(value, &default_column_format(header, column_type)),
+    }
+}
+
+/// Whether a column holds money, which a dataset's display unit divides: number columns
+/// shown as whole numbers, other than counts such as 數量 and 期數.
+pub fn is_amount_column(header: &str, column_type: ColumnType) -> bool {
+    column_type == ColumnType::Number
+        && !matches!(header, "數量" | "股數" | "期數" | "配息頻率")
+        && matches!(numeric_format_for_header(header), NumericFormat::Integer)
+}
+
+/// `raw` in the dataset's display unit when the column holds amounts; other cells, and
+/// cells that are not numbers, are returned as they are.
+pub fn display_cell_value(
+    header: &str,
+    column_type: ColumnType,
+    raw: &str,
+    display: &DatasetDisplay,
+) -> String {
+    if display.unit.divisor() == 1.0 || !is_amount_column(header, column_type) {
+        return raw.to_string();
+    }
+    match parse_numeric_value(raw) {
+        Some(value) => format_f64(value / display.unit.divisor()),
+        None => raw.to_string(),
+    }
+}
+
+/// [`format_typed_cell_value`] after converting amounts to the dataset's display unit.
+pub fn format_display_cell_value(
+    header: &str,
+    column_type: ColumnType,
+    raw: &str,
+    format: Option<&ColumnFormat>,
+    display: &DatasetDisplay,
+) -> String {
+    let value = display_cell_value(header, column_type, raw, display);
+    format_typed_cell_value(header, column_type, &value, format)
+}
+
+/// `rows` with amount columns converted to the dataset's display unit, for export.
+pub fn display_rows(
+    headers: &[String],
+    types: &[ColumnType],
+    rows: &[Vec<String>],
+    display: &DatasetDisplay,
+) -> Vec<Vec<String>> {
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .enumerate()
+                .map(|(idx, value)| match (headers.get(idx), types.get(idx)) {
+                    (Some(header), Some(column_type)) => {
+                        display_cell_value(header, *column_type, value, display)
+                    }
+                    _ => value.clone(),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Exported header of column `header`, shown as `label`: amount columns of a dataset
+/// with a non-default display say what they are in, as in `淨值 (USD 千元)`.
+pub fn display_header(
+    header: &str,
+    label: &str,
+    column_type: ColumnType,
+    display: &DatasetDisplay,
+) -> String {
+    if display.is_default() || !is_amount_column(header, column_type) {
+        label.to_string()
+    } else {
+        format!("{label} ({})", display.caption())
+    }
+}
+
+pub fn safe_div(numerator: f64, denominator: f64) -> f64 {
+    if denominator.abs() < f64::EPSILON {
+        0.0
+    } else {
+        numerator / denominator
+    }
+}
+
+pub fn format_ratio_or_na(numerator: f64, denominator: f64) -> String {
+    if denominator.abs() < f64::EPSILON {
+        "N/A".to_string()
+    } else {
+        format_f64(numerator / denominator)
+    }
+}
+
+pub fn parse_frequency(text: &str) -> f64 {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return 0.0;
+    }
+    if trimmed.contains('年') {
+        return 1.0;
+    }
+    if trimmed.contains("半年") {
+        return 2.0;
+    }
+    if trimmed.contains('季') {
+        return 4.0;
+    }
+    if trimmed.contains('月') {
+        return 12.0;
+    }
+    let count = trimmed
+        .split(['、', ',', '，', '/', ' '])
+        .filter(|item| !item.trim().is_empty())
+        .count();
+    if count > 0 {
+        count as f64
+    } else {
+        parse_f64(trimmed)
+    }
+}
+
+pub fn is_summary_label(value: &str) -> bool {
+    ["小計", "合計", "總計", "加總", "平均"]
+        .iter()
+        .any(|token| value.contains(token))
+}
+
+pub fn row_value(row: &[String], idx: usize) -> String {
+    row.get(idx).cloned().unwrap_or_default()
+}
@@ -0,0 +1,117 @@
+//! Co-owned rows. A row can be split by percentage across several 所有權人; per-owner
+//! figures then give each owner their share of the row's amounts instead of giving the
+//! whole row to the owner named in its 所有權人 cell.
+
+use anyhow::{bail, Result};
+
+use crate::domain::entities::owner_share::{OwnerShare, OwnerSplits};
+use crate::numeric::{
+    format_f64, is_text_header, numeric_format_for_header, parse_numeric_value, NumericFormat,
+};
+
+/// Column naming the owner of a row.
+pub const OWNER_COLUMN: &str = "所有權人";
+
+/// Whether a column holds an amount that is divided between co-owners, such as 數量, 淨值
+/// or a month of dividends. Prices and rates stay as they are.
+pub fn is_split_amount_header(header: &str) -> bool {
+    !is_text_header(header) && matches!(numeric_format_for_header(header), NumericFormat::Integer)
+}
+
+/// `rows` with every co-owned row replaced by one row per share: 所有權人 set to the
+/// co-owner and each amount column scaled to their percentage. Rows without shares, and
+/// every row when there is no 所有權人 column, are kept as they are.
+pub fn split_rows_by_owner(
+    headers: &[String],
+    rows: &[Vec<String>],
+    splits: &OwnerSplits,
+) -> Vec<Vec<String>> {
+    let Some(owner_idx) = headers.iter().position(|header| header == OWNER_COLUMN) else {
+        return rows.to_vec();
+    };
+    let amounts: Vec<usize> = headers
+        .iter()
+        .enumerate()
+        .filter(|(_, header)| is_split_amount_header(header))
+        .map(|(idx, _)| idx)
+        .collect();
+    let mut split = Vec::with_capacity(rows.len());
+    for (row_idx, row) in rows.iter().enumerate() {
+        let Some(shares) = splits.get(&row_idx).filter(|shares| !shares.is_empty()) else {
+            split.push(row.clone());
+            continue;
+        };
+        for share in shares {
+            let mut part = row.clone();
+            if let Some(owner) = part.get_mut(owner_idx) {
+                *owner = share.owner.clone();
+            }
+            for idx in &amounts {
+                if let Some(value) = part.get_mut(*idx) {
+                    if let Some(parsed) = parse_numeric_value(value) {
+                        *value = format_f64(parsed * share.percent / 100.0);
+                    }
+                }
+            }
+            split.push(part);
+        }
+    }
+    split
+}
+
+/// Checks that `shares` name distinct owners with positive percentages adding up to 100.
+/// No shares at all is valid: the row belongs to its 所有權人 again.
+pub fn validate_owner_shares(shares: &[OwnerShare]) -> Result<()> {
+    if shares.is_empty() {
+        return Ok(());
+    }
+    for (idx, share) in shares.iter().enumerate() {
+        if share.owner.trim().is_empty() {
+            bail!("所有權人不可空白");
+        }
+        if shares[..idx].iter().any(|known| known.owner == share.owner) {
+            bail!("所有權人重複：{}", share.owner);
+        }
+        if !(share.percent > 0.0 && share.percent <= 100.0) {
+            bail!("{} 的比例必須介於 0 到 100", share.owner);
+        }
+    }
+    let total: f64 = shares.iter().map(|share| share.percent).sum();
+    if (total - 100.0).abs() > 0.01 {
+        bail!("比例合計為 {}%，應為 100%", format_f64(total));
+    }
+    Ok(())
+}
+
+/// Reads shares written one per line as `所有權人 百分比`, such as `王小明 60%`; the `%` is
+/// optional and blank lines are skipped. The result is validated.
+pub fn parse_owner_shares(text: &str) -> Result<Vec<OwnerShare>> {
+    let mut shares = Vec::new();
+    for (line_idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Some((owner, percent)) = line.rsplit_once(char::is_whitespace) else {
+            bail!("第 {} 行：請以「所有權人 百分比」填寫", line_idx + 1);
+        };
+        let Ok(percent) = percent.trim_end_matches('%').parse::<f64>() else {
+            bail!("第 {} 行：比例不是數字：{percent}", line_idx + 1);
+        };
+        shares.push(OwnerShare {
+            owner: owner.trim().to_string(),
+            percent,
+        });
+    }
+    validate_owner_shares(&shares)?;
+    Ok(shares)
+}
+
+/// The text [`parse_owner_shares`] reads back.
+pub fn format_owner_shares(shares: &[OwnerShare]) -> String {
+    shares
+        .iter()
+        .map(|share| format!("{} {}%", share.owner, format_f64(share.percent)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,84 @@
+//! Stored market prices. Each fetch is kept with its time, so a 代號's price on any
+//! earlier date can be looked up and a portfolio valued as it stood then.
+
+use std::collections::BTreeSet;
+
+use crate::domain::entities::edit::SavedChanges;
+use crate::domain::entities::price_point::{PriceHistory, PricePoint};
+use crate::numeric::{parse_numeric_value, row_value};
+
+/// Column naming the 代號 prices are stored under.
+pub const CODE_COLUMN: &str = "代號";
+
+/// Column of a 持股 table holding the market price of its 代號.
+pub const PRICE_COLUMN: &str = "市價";
+
+/// The 市價 cells a save changed, per `changes`, as prices of their 代號 at `fetched_at`.
+/// `headers` and `rows` are the dataset after the save. Cells that are not a number and
+/// rows without a 代號 are left out.
+pub fn saved_prices(
+    headers: &[String],
+    rows: &[Vec<String>],
+    changes: &SavedChanges,
+    fetched_at: &str,
+) -> Vec<PricePoint> {
+    let position = |name: &str| headers.iter().position(|header| header == name);
+    let (Some(code_idx), Some(price_idx)) = (position(CODE_COLUMN), position(PRICE_COLUMN)) else {
+        return Vec::new();
+    };
+    changes
+        .keys()
+        .filter(|(_, col_idx)| *col_idx == price_idx)
+        .filter_map(|(row_idx, _)| {
+            let row = rows.get(*row_idx)?;
+            let code = row_value(row, code_idx).trim().to_string();
+            let price = parse_numeric_value(&row_value(row, price_idx))?;
+            (!code.is_empty()).then(|| PricePoint {
+                code,
+                price,
+                fetched_at: fetched_at.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The distinct 代號 of `rows`, in order of appearance. Empty without a 代號 column.
+pub fn row_codes(headers: &[String], rows: &[Vec<String>]) -> Vec<String> {
+    let Some(code_idx) = headers.iter().position(|header| header == CODE_COLUMN) else {
+        return Vec::new();
+    };
+    let mut seen = BTreeSet::new();
+    rows.iter()
+        .filter_map(|row| row.get(code_idx))
+        .map(|code| code.trim())
+        .filter(|code| !code.is_empty() && seen.insert(code.to_string()))
+        .map(str::to_string)
+        .collect()
+}
+
+/// The last price in `points` (oldest first) fetched at or before `at`, a time or date in
+/// the `YYYY-MM-DD HH:MM:SS` form the points use. A bare date covers the whole day.
+pub fn price_at(points: &[PricePoint], at: &str) -> Option<f64> {
+    let end_of_day;
+    let at = if at.len() == "YYYY-MM-DD".len() {
+        end_of_day = format!("{at} 23:59:59");
+        end_of_day.as_str()
+    } else {
+        at
+    };
+    let fetched = points.partition_point(|point| point.fetched_at.as_str() <= at);
+    fetched.checked_sub(1).map(|idx| points[idx].price)
+}
+
+/// The market value of `quantities` (代號 and 數量) at `at`, each priced with
+/// [`price_at`]. `None` when any of them had no price yet, since the total would be short.
+pub fn market_value_at(
+    quantities: &[(String, f64)],
+    history: &PriceHistory,
+    at: &str,
+) -> Option<f64> {
+    quantities.iter().try_fold(0.0, |total, (code, quantity)| {
+        let price = price_at(history.get(code)?, at)?;
+        Some(total + price * quantity)
+    })
+}
@@ -0,0 +1,935 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use calamine::{open_workbook_auto, Reader};
+use chrono::NaiveDateTime;
+
+use crate::assets::{deposit_rate_for, TENOR_COLUMNS};
+use crate::domain::entities::dataset::{DatasetId, DatasetKind};
+use crate::domain::entities::deposit_rate::DepositRate;
+use crate::holdings::DividendColumns;
+use crate::numeric::{
+    format_f64, format_percent, is_summary_label, parse_numeric_value, row_value, safe_div,
+};
+use crate::owner_split::split_rows_by_owner;
+use crate::usecase::ports::repo::RepoError;
+use crate::usecase::services::query_service::QueryService;
+
+#[derive(Clone, Default)]
+pub struct XlsxInterestSummary {
+    pub label: String,
+    pub annual: String,
+    pub monthly: String,
+    pub yield_rate: String,
+}
+
+#[derive(Clone, Default)]
+pub struct XlsxOwnerDividendSummary {
+    pub owner: String,
+    pub monthly: String,
+    pub monthly_with_pension: Option<String>,
+    pub note: Option<String>,
+}
+
+#[derive(Clone, Default)]
+pub struct XlsxSummaryReport {
+    pub title: String,
+    pub interest_rows: Vec<XlsxInterestSummary>,
+    pub dividend_total: Option<String>,
+    pub owner_dividends: Vec<XlsxOwnerDividendSummary>,
+    pub notes: Vec<String>,
+}
+
+/// [`read_xlsx_summary_report_with_owners`] for every owner listed in 股息收入明細表.
+pub fn read_xlsx_summary_report(xlsx_path: &Path) -> Result<XlsxSummaryReport> {
+    read_xlsx_summary_report_with_owners(xlsx_path, &[])
+}
+
+/// Reads the summary rows at the bottom of 資產總表 and 股息收入明細表. `owners` picks the
+/// per-owner rows in that order; empty means every owner of the dividend sheet.
+pub fn read_xlsx_summary_report_with_owners(
+    xlsx_path: &Path,
+    owners: &[String],
+) -> Result<XlsxSummaryReport> {
+    let mut workbook = open_workbook_auto(xlsx_path)
+        .with_context(|| format!("failed to open xlsx: {}", xlsx_path.display()))?;
+
+    let assets_range = workbook
+        .worksheet_range("資產總表")
+        .context("failed to read sheet: 資產總表")?;
+    let dividends_range = workbook
+        .worksheet_range("股息收入明細表")
+        .context("failed to read sheet: 股息收入明細表")?;
+
+    let assets_rows: Vec<Vec<String>> = assets_range
+        .rows()
+        .map(|row| {
+            row.iter()
+                .map(crate::infra::import::xlsx::cell_to_string)
+                .collect()
+        })
+        .collect();
+    let dividends_rows: Vec<Vec<String>> = dividends_range
+        .rows()
+        .map(|row| {
+            row.iter()
+                .map(crate::infra::import::xlsx::cell_to_string)
+                .collect()
+        })
+        .collect();
+
+    let mut report = XlsxSummaryReport {
+        title: "總結報表".to_string(),
+        ..XlsxSummaryReport::default()
+    };
+
+    let interest_labels = ["定存資金", "股債息(平均)", "合計(平均)", "合計(最新)"];
+    for label in interest_labels {
+        if let Some(row) = find_row_by_first_cell(&assets_rows, label) {
+            let annual = format_summary_value(row.get(1));
+            let monthly = format_summary_value(row.get(2));
+            let yield_rate = format_summary_percent(row.get(3));
+            report.interest_rows.push(XlsxInterestSummary {
+                label: label.to_string(),
+                annual,
+                monthly,
+                yield_rate,
+            });
+        }
+    }
+
+    let columns = DividendSummaryColumns::detect(&dividends_rows);
+    if let Some(row) = find_row_by_first_cell(&dividends_rows, "總計") {
+        report.dividend_total = Some(format_summary_value(row.get(columns.monthly)));
+    }
+
+    let owners = if owners.is_empty() {
+        dividend_sheet_owners(&dividends_rows)
+    } else {
+        owners.to_vec()
+    };
+    for owner in owners {
+        if let Some(row) = find_row_by_first_cell(&dividends_rows, &owner) {
+            let monthly = format_summary_value(row.get(columns.monthly));
+            let monthly_with_pension = format_optional_value(row.get(columns.with_pension));
+            let note = row.get(columns.note).and_then(|value| {
+                let trimmed = value.trim();
+                if trimmed.is_empty() {
+                    None
+                } else {
+                    Some(trimmed.to_string())
+                }
+            });
+            report.owner_dividends.push(XlsxOwnerDividendSummary {
+                owner,
+                monthly,
+                monthly_with_pension,
+                note,
+            });
+        }
+    }
+
+    if report.interest_rows.is_empty() && report.dividend_total.is_none() {
+        report.notes.push("找不到報表資料".to_string());
+    }
+
+    Ok(report)
+}
+
+/// Columns of the per-owner rows below 股息收入明細表, found by their header and falling
+/// back to the original workbook layout.
+struct DividendSummaryColumns {
+    monthly: usize,
+    with_pension: usize,
+    note: usize,
+}
+
+impl DividendSummaryColumns {
+    fn detect(rows: &[Vec<String>]) -> Self {
+        let headers = DividendColumns::detect(rows)
+            .map(|(_, header_row)| rows[header_row].as_slice())
+            .unwrap_or_default();
+        let find = |matches: &dyn Fn(&str) -> bool, fallback: usize| {
+            headers
+                .iter()
+                .position(|header| matches(header.trim()))
+                .unwrap_or(fallback)
+        };
+        Self {
+            monthly: find(
+                &|header| header.contains("月平均") || header.contains("每月"),
+                47,
+            ),
+            with_pension: find(&|header| header.contains("退休"), 48),
+            note: find(&|header| header == "備註", 49),
+        }
+    }
+}
+
+/// Distinct 所有權人 of the dividend sheet's holdings, in sheet order.
+fn dividend_sheet_owners(rows: &[Vec<String>]) -> Vec<String> {
+    let (columns, header_row) =
+        DividendColumns::detect(rows).unwrap_or((DividendColumns::LEGACY, 0));
+    let mut owners: Vec<String> = Vec::new();
+    for row in rows.iter().skip(header_row + 1) {
+        let owner = row
+            .get(columns.owner)
+            .map(|value| value.trim())
+            .unwrap_or("");
+        if !owner.is_empty() && !owners.iter().any(|known| known == owner) {
+            owners.push(owner.to_string());
+        }
+    }
+    owners
+}
+
+/// The owners list saved in settings: one name per line, blanks and repeats dropped.
+pub fn parse_owner_list(text: &str) -> Vec<String> {
+    let mut owners: Vec<String> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if !line.is_empty() && !owners.iter().any(|known| known == line) {
+            owners.push(line.to_string());
+        }
+    }
+    owners
+}
+
+/// Puts the owners named in `owners` first, in that order; the rest keep their order.
+pub fn order_owner_totals(report: &mut SummaryReport, owners: &[String]) {
+    report.owner_totals.sort_by_key(|summary| {
+        owners
+            .iter()
+            .position(|owner| *owner == summary.owner)
+            .unwrap_or(owners.len())
+    });
+}
+
+pub fn find_row_by_first_cell(rows: &[Vec<String>], label: &str) -> Option<Vec<String>> {
+    rows.iter()
+        .find(|row| row.first().map(|value| value.trim()) == Some(label))
+        .cloned()
+}
+
+pub fn format_summary_value(value: Option<&String>) -> String {
+    let Some(value) = value else {
+        return String::new();
+    };
+    if let Some(parsed) = parse_numeric_value(value) {
+        format_f64(parsed)
+    } else {
+        value.trim().to_string()
+    }
+}
+
+/// [`format_summary_value`] for a rate: stored ratios and `%` strings both display as
+/// a percentage, matching the table.
+pub fn format_summary_percent(value: Option<&String>) -> String {
+    let Some(value) = value else {
+        return String::new();
+    };
+    match parse_numeric_value(value) {
+        Some(ratio) => format_percent(ratio),
+        None => value.trim().to_string(),
+    }
+}
+
+pub fn format_optional_value(value: Option<&String>) -> Option<String> {
+    let value = format_summary_value(value);
+    if value.trim().is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+pub fn resolve_summary_value(
+    row: Option<&Vec<String>>,
+    idx: usize,
+    derived: Option<f64>,
+) -> String {
+    if let Some(value) = derived {
+        return format_f64(value);
+    }
+    if let Some(row) = row {
+        if let Some(value) = row.get(idx) {
+            if !value.trim().is_empty() {
+                return format_summary_value(Some(value));
+            }
+        }
+    }
+    String::new()
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SummaryEntry {
+    pub label: String,
+    pub value: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OwnerSummary {
+    pub owner: String,
+    pub entries: Vec<SummaryEntry>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SummaryReport {
+    pub title: String,
+    pub totals: Vec<SummaryEntry>,
+    pub owner_totals: Vec<OwnerSummary>,
+    /// Heading above `owner_totals`; empty means the built-in grouping by 所有權人.
+    pub group_heading: String,
+    pub notes: Vec<String>,
+}
+
+/// A [`SummaryReport`] saved with the dataset revision it was computed from.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StoredSummaryReport {
+    pub id: i64,
+    pub dataset_id: i64,
+    pub revision: i64,
+    pub created_at: String,
+    pub report: SummaryReport,
+}
+
+/// The chosen `sections` of `report` as a flat table for CSV export: one row per entry,
+/// with the group it belongs to (合計 for the totals) and the notes last.
+pub fn summary_report_table(
+    report: &SummaryReport,
+    sections: SummarySections,
+) -> (Vec<String>, Vec<Vec<String>>) {
+    let headers = ["分組", "項目", "數值"].map(String::from).to_vec();
+    let row = |group: &str, entry: &SummaryEntry| {
+        vec![group.to_string(), entry.label.clone(), entry.value.clone()]
+    };
+    let mut rows = Vec::new();
+    if sections.totals {
+        rows.extend(report.totals.iter().map(|entry| row("合計", entry)));
+    }
+    if sections.owner_totals {
+        for owner in &report.owner_totals {
+            rows.extend(owner.entries.iter().map(|entry| row(&owner.owner, entry)));
+        }
+    }
+    if sections.notes {
+        rows.extend(
+            report
+                .notes
+                .iter()
+                .map(|note| vec!["備註".to_string(), String::new(), note.clone()]),
+        );
+    }
+    (headers, rows)
+}
+
+/// Which parts of a [`SummaryReport`] are shown, copied with [`summary_report_text`] and
+/// exported with [`summary_report_table`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SummarySections {
+    pub totals: bool,
+    pub owner_totals: bool,
+    pub notes: bool,
+}
+
+impl Default for SummarySections {
+    fn default() -> Self {
+        Self {
+            totals: true,
+            owner_totals: true,
+            notes: true,
+        }
+    }
+}
+
+impl SummarySections {
+    const NAMES: [&'static str; 3] = ["totals", "owner_totals", "notes"];
+
+    fn flags(self) -> [bool; 3] {
+        [self.totals, self.owner_totals, self.notes]
+    }
+
+    /// Comma-separated names of the checked sections, as stored in settings.
+    pub fn to_setting(self) -> String {
+        Self::NAMES
+            .into_iter()
+            .zip(self.flags())
+            .filter_map(|(name, checked)| checked.then_some(name))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub fn parse_setting(value: &str) -> Self {
+        let checked = |name: &str| value.split(',').any(|item| item.trim() == name);
+        Self {
+            totals: checked("totals"),
+            owner_totals: checked("owner_totals"),
+            notes: checked("notes"),
+        }
+    }
+}
+
+/// Tab-separated text of the chosen `sections`, for pasting into chat or mail: the title,
+/// the totals as `項目\t數值` lines, the groups as a table with one row per group, and the
+/// notes. Sections are separated by a blank line.
+pub fn summary_report_text(report: &SummaryReport, sections: SummarySections) -> String {
+    let mut blocks = vec![report.title.clone()];
+    if sections.totals && !report.totals.is_empty() {
+        blocks.push(
+            report
+                .totals
+                .iter()
+                .map(|entry| format!("{}\t{}", entry.label, entry.value))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+    }
+    if sections.owner_totals && !report.owner_totals.is_empty() {
+        let mut labels: Vec<&str> = Vec::new();
+        for entry in report.owner_totals.iter().flat_map(|owner| &owner.entries) {
+            if !labels.contains(&entry.label.as_str()) {
+                labels.push(&entry.label);
+            }
+        }
+        let heading = if report.group_heading.is_empty() {
+            "所有權人"
+        } else {
+            report.group_heading.trim_start_matches('依')
+        };
+        let mut lines = vec![std::iter::once(heading)
+            .chain(labels.iter().copied())
+            .collect::<Vec<_>>()
+            .join("\t")];
+        for owner in &report.owner_totals {
+            let values = labels.iter().map(|label| {
+                owner
+                    .entries
+                    .iter()
+                    .find(|entry| entry.label == *label)
+                    .map_or("", |entry| entry.value.as_str())
+            });
+            lines.push(
+                std::iter::once(owner.owner.as_str())
+                    .chain(values)
+                    .collect::<Vec<_>>()
+                    .join("\t"),
+            );
+        }
+        blocks.push(lines.join("\n"));
+    }
+    if sections.notes && !report.notes.is_empty() {
+        blocks.push(report.notes.join("\n"));
+    }
+    blocks.join("\n\n") + "\n"
+}
+
+/// Summary of a dataset's rows. A 資產總表 is summarised without the 定存 rate table; see
+/// [`compute_assets_summary_report`].
+pub fn compute_summary_report(headers: &[String], rows: &[Vec<String>]) -> SummaryReport {
+    if is_assets_headers(headers) {
+        return compute_assets_summary_report(headers, rows, &[]);
+    }
+    let mut header_map = HashMap::new();
+    for (idx, header) in headers.iter().enumerate() {
+        header_map.insert(header.clone(), idx);
+    }
+
+    let total_columns = [
+        "總成本",
+        "資本利得",
+        "淨值",
+        "已收配息",
+        "總損益",
+        "估計配息",
+        "股票成本",
+        "股票淨值",
+        "債券成本",
+        "債券淨值",
+        "今年度累積",
+        "總累積",
+        "預估累積",
+        "預算實際差異",
+    ];
+
+    let owner_columns = ["數量", "總成本", "淨值", "市值", "估計配息"];
+
+    let mut report = SummaryReport {
+        title: "總結報表".to_string(),
+        ..SummaryReport::default()
+    };
+
+    for column in total_columns {
+        if let Some(idx) = header_map.get(column) {
+            let mut sum = 0.0;
+            for row in rows {
+                if let Some(value) = row.get(*idx) {
+                    if let Some(parsed) = parse_numeric_value(value) {
+                        sum += parsed;
+                    }
+                }
+            }
+            report.totals.push(SummaryEntry {
+                label: column.to_string(),
+                value: format_f64(sum),
+            });
+        }
+    }
+
+    if report.totals.is_empty() {
+        report.notes.push("沒有可計算的摘要欄位".to_string());
+    }
+
+    if let Some(owner_idx) = header_map.get("所有權人") {
+        let mut owner_map: BTreeMap<String, Vec<(String, f64)>> = BTreeMap::new();
+        for row in rows {
+            let owner = row.get(*owner_idx).cloned().unwrap_or_default();
+            if owner.trim().is_empty() {
+                continue;
+            }
+            for column in owner_columns {
+                if let Some(idx) = header_map.get(column) {
+                    let value = row
+                        .get(*idx)
+                        .and_then(|raw| parse_numeric_value(raw))
+                        .unwrap_or(0.0);
+                    let entries = owner_map.entry(owner.clone()).or_default();
+                    if let Some(existing) = entries.iter_mut().find(|(label, _)| label == column) {
+                        existing.1 += value;
+                    } else {
+                        entries.push((column.to_string(), value));
+                    }
+                }
+            }
+        }
+
+        for (owner, entries) in owner_map {
+            let mut mapped = Vec::new();
+            for (label, value) in entries {
+                mapped.push(SummaryEntry {
+                    label,
+                    value: format_f64(value),
+                });
+            }
+            if !mapped.is_empty() {
+                report.owner_totals.push(OwnerSummary {
+                    owner,
+                    entries: mapped,
+                });
+            }
+        }
+    }
+
+    if report.owner_totals.is_empty() {
+        report.notes.push("沒有可計算的所有權人欄位".to_string());
+    }
+
+    report
+}
+
+/// The summary of a stored dataset, including edits saved since the import, with co-owned
+/// rows divided between their owners. Only when the stored rows have nothing to summarise
+/// and the workbook it came from is still at `source_path` are the workbook's own summary
+/// rows shown instead.
+pub fn dataset_summary_report(
+    query: &QueryService,
+    dataset_id: DatasetId,
+    owners: &[String],
+) -> Result<SummaryReport> {
+    let page = query.load_tabular_data(dataset_id)?;
+    let splits = query.load_owner_splits(dataset_id)?;
+    let rows = split_rows_by_owner(&page.columns, &page.rows, &splits);
+    let mut report = if is_assets_headers(&page.columns) {
+        let deposit_rates = query.load_deposit_rates()?;
+        compute_assets_summary_report(&page.columns, &rows, &deposit_rates)
+    } else {
+        compute_summary_report(&page.columns, &rows)
+    };
+    order_owner_totals(&mut report, owners);
+    if !report.totals.is_empty() || !report.owner_totals.is_empty() {
+        return Ok(report);
+    }
+
+    let source_path = query
+        .list_datasets(false)?
+        .into_iter()
+        .find(|meta| meta.id == dataset_id)
+        .map(|meta| meta.source_path)
+        .unwrap_or_default();
+    let workbook = Path::new(source_path.split('#').next().unwrap_or_default());
+    let is_xlsx = workbook
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("xlsx"));
+    if !is_xlsx || !workbook.is_file() {
+        return Ok(report);
+    }
+    match read_xlsx_summary_report_with_owners(workbook, owners) {
+        Ok(xlsx) => Ok(summary_from_xlsx(xlsx, workbook)),
+        Err(err) => {
+            tracing::warn!(error = %err, "failed to read workbook summary");
+            Ok(report)
+        }
+    }
+}
+
+/// The dataset's summary, reused from the history while no edits were applied since it
+/// was computed; otherwise computed by [`dataset_summary_report`] and saved.
+pub fn cached_summary_report(
+    query: &QueryService,
+    dataset_id: DatasetId,
+    owners: &[String],
+) -> Result<StoredSummaryReport> {
+    let revision = query.dataset_revision(dataset_id)?;
+    let latest = query.load_summary_reports(dataset_id)?.into_iter().next();
+    if let Some(mut stored) = latest.filter(|stored| stored.revision == revision) {
+        // The owners order is a display setting and may have changed since.
+        order_owner_totals(&mut stored.report, owners);
+        return Ok(stored);
+    }
+    let report = dataset_summary_report(query, dataset_id, owners)?;
+    match query.save_summary_report(dataset_id, &report) {
+        Ok(stored) => Ok(stored),
+        Err(RepoError::ReadOnly) => Ok(StoredSummaryReport {
+            id: 0,
+            dataset_id: dataset_id.0,
+            revision,
+            created_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            report,
+        }),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// How often the summary history gets an entry without an edit, so the portfolio's value
+/// is on record for days nothing was saved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SnapshotSchedule {
+    #[default]
+    Off,
+    Daily,
+    Weekly,
+}
+
+impl SnapshotSchedule {
+    pub const ALL: [SnapshotSchedule; 3] = [
+        SnapshotSchedule::Off,
+        SnapshotSchedule::Daily,
+        SnapshotSchedule::Weekly,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SnapshotSchedule::Off => "off",
+            SnapshotSchedule::Daily => "daily",
+            SnapshotSchedule::Weekly => "weekly",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|schedule| schedule.as_str() == value.trim())
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SnapshotSchedule::Off => "關閉",
+            SnapshotSchedule::Daily => "每日",
+            SnapshotSchedule::Weekly => "每週",
+        }
+    }
+
+    /// Whether a snapshot is due at `now` when the last one was taken at `last`, a
+    /// [`StoredSummaryReport::created_at`]. Daily means once per calendar day, weekly once
+    /// seven days have passed; an unreadable `last` counts as none.
+    pub fn is_due(self, last: Option<&str>, now: NaiveDateTime) -> bool {
+        let last =
+            last.and_then(|value| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").ok());
+        match (self, last) {
+            (SnapshotSchedule::Off, _) => false,
+            (_, None) => true,
+            (SnapshotSchedule::Daily, Some(last)) => now.date() > last.date(),
+            (SnapshotSchedule::Weekly, Some(last)) => (now.date() - last.date()).num_days() >= 7,
+        }
+    }
+}
+
+/// Adds a summary to the history of the newest 資產總表 and 持股 datasets when `schedule`
+/// says one is due, whether or not they changed since the last. Returns the datasets that
+/// got one; none in read-only mode.
+pub fn record_scheduled_snapshots(
+    query: &QueryService,
+    schedule: SnapshotSchedule,
+    owners: &[String],
+    now: NaiveDateTime,
+) -> Result<Vec<DatasetId>> {
+    if schedule == SnapshotSchedule::Off {
+        return Ok(Vec::new());
+    }
+    let datasets = query.list_datasets(false)?;
+    let mut recorded = Vec::new();
+    for kind in [DatasetKind::Assets, DatasetKind::Holdings] {
+        // Listed newest first, so this is the latest save of that kind.
+        let Some(dataset) = datasets.iter().find(|meta| meta.effective_kind() == kind) else {
+            continue;
+        };
+        let last = query.load_summary_reports(dataset.id)?.into_iter().next();
+        if !schedule.is_due(last.as_ref().map(|stored| stored.created_at.as_str()), now) {
+            continue;
+        }
+        let report = dataset_summary_report(query, dataset.id, owners)?;
+        match query.save_summary_report(dataset.id, &report) {
+            Ok(_) => recorded.push(dataset.id),
+            Err(RepoError::ReadOnly) => return Ok(recorded),
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(recorded)
+}
+
+fn summary_from_xlsx(xlsx: XlsxSummaryReport, workbook: &Path) -> SummaryReport {
+    let entry = |label: String, value: String| SummaryEntry { label, value };
+    let mut totals = Vec::new();
+    for row in xlsx.interest_rows {
+        totals.push(entry(format!("{} 年", row.label), row.annual));
+        totals.push(entry(format!("{} 月", row.label), row.monthly));
+        totals.push(entry(format!("{} 殖利率", row.label), row.yield_rate));
+    }
+    if let Some(total) = xlsx.dividend_total {
+        totals.push(entry("股息月平均".to_string(), total));
+    }
+    let owner_totals = xlsx
+        .owner_dividends
+        .into_iter()
+        .map(|owner| {
+            let mut entries = vec![entry("每月".to_string(), owner.monthly)];
+            if let Some(value) = owner.monthly_with_pension {
+                entries.push(entry("含退休金".to_string(), value));
+            }
+            if let Some(note) = owner.note {
+                entries.push(entry("備註".to_string(), note));
+            }
+            OwnerSummary {
+                owner: owner.owner,
+                entries,
+            }
+        })
+        .collect();
+    let mut notes = vec![format!(
+        "資料表沒有可計算的欄位，改用原始檔案：{}",
+        workbook.display()
+    )];
+    notes.extend(xlsx.notes);
+    SummaryReport {
+        title: xlsx.title,
+        totals,
+        owner_totals,
+        group_heading: String::new(),
+        notes,
+    }
+}
+
+pub fn is_assets_headers(headers: &[String]) -> bool {
+    headers.iter().any(|header| header == "資產形式")
+}
+
+/// Summary of a 資產總表. 定存 interest is worked out row by row: each 定存 balance earns
+/// the rate its bank pays for its tenor in `deposit_rates`, or, for banks not in the table,
+/// the rate in the row's own 利率 column.
+pub fn compute_assets_summary_report(
+    headers: &[String],
+    rows: &[Vec<String>],
+    deposit_rates: &[DepositRate],
+) -> SummaryReport {
+    let mut header_map = HashMap::new();
+    for (idx, header) in headers.iter().enumerate() {
+        header_map.insert(header.clone(), idx);
+    }
+
+    let mut report = SummaryReport {
+        title: "總結報表".to_string(),
+        ..SummaryReport::default()
+    };
+
+    let label_idx = header_map.get("資產形式").copied().unwrap_or(0);
+    let cost_idx = header_map
+        .get("投入金額")
+        .or_else(|| header_map.get("交割款"))
+        .copied();
+    let net_idx = header_map
+        .get("目前淨值")
+        .or_else(|| header_map.get("餘額"))
+        .copied();
+    let rate_idx = header_map
+        .get("利率")
+        .or_else(|| header_map.get("定存利率"))
+        .or_else(|| header_map.get("殖利率"))
+        .copied();
+    let estimated_dividend_idx = header_map
+        .get("估計配息")
+        .or_else(|| header_map.get("估計配息金額"))
+        .copied();
+    let institution_idx = header_map.get("往來機構").copied();
+    let tenor_idx = TENOR_COLUMNS
+        .iter()
+        .find_map(|column| header_map.get(*column))
+        .copied();
+
+    let interest_labels = ["定存資金", "股債息(平均)", "合計(平均)"];
+
+    let mut deposit_interest: Option<f64> = None;
+    let mut unrated_deposits: Vec<String> = Vec::new();
+    let mut average_dividend_total = 0.0;
+
+    if net_idx.is_some() {
+        for row in rows {
+            let label = row.get(label_idx).map(|value| value.trim()).unwrap_or("");
+            if label.is_empty()
+                || is_summary_label(label)
+                || interest_labels.iter().any(|token| label.contains(token))
+            {
+                continue;
+            }
+
+            if label.contains("定存") {
+                let balance = net_idx
+                    .and_then(|idx| row.get(idx))
+                    .and_then(|raw| parse_numeric_value(raw))
+                    .filter(|balance| *balance > 0.0);
+                if let Some(balance) = balance {
+                    let institution = institution_idx
+                        .map(|idx| row_value(row, idx))
+                        .unwrap_or_default();
+                    let tenor = tenor_idx.map(|idx| row_value(row, idx)).unwrap_or_default();
+                    let rate =
+                        deposit_rate_for(deposit_rates, &institution, &tenor).or_else(|| {
+                            rate_idx
+                                .and_then(|idx| row.get(idx))
+                                .and_then(|raw| parse_numeric_value(raw))
+                        });
+                    match rate {
+                        Some(rate) => *deposit_interest.get_or_insert(0.0) += balance * rate,
+                        None => {
+                            let name = match format!("{institution} {tenor}").trim() {
+                                "" => label.to_string(),
+                                name => name.to_string(),
+                            };
+                            if !unrated_deposits.contains(&name) {
+                                unrated_deposits.push(name);
+                            }
+                        }
+                    }
+                }
+            }
+
+            if let Some(estimate_idx) = estimated_dividend_idx {
+                if label.contains("投資") || label.contains('股') || label.contains('債') {
+                    if let Some(value) = row
+                        .get(estimate_idx)
+                        .and_then(|raw| parse_numeric_value(raw))
+                    {
+                        average_dividend_total += value;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut derived_interest: HashMap<&str, (Option<f64>, Option<f64>)> = HashMap::new();
+    if let Some(annual) = deposit_interest {
+        let monthly = annual / 12.0;
+        derived_interest.insert("定存資金", (Some(annual), Some(monthly)));
+    }
+    if !unrated_deposits.is_empty() {
+        report.notes.push(format!(
+            "定存利率表中找不到：{}，未計入定存利息",
+            unrated_deposits.join("、")
+        ));
+    }
+    if average_dividend_total > 0.0 {
+        let monthly = average_dividend_total / 12.0;
+        derived_interest.insert(
+            "股債息(平均)",
+            (Some(average_dividend_total), Some(monthly)),
+        );
+    }
+    let total_average = derived_interest
+        .get("定存資金")
+        .and_then(|entry| entry.0)
+        .unwrap_or(0.0)
+        + derived_interest
+            .get("股債息(平均)")
+            .and_then(|entry| entry.0)
+            .unwrap_or(0.0);
+    if total_average > 0.0 {
+        let monthly = total_average / 12.0;
+        derived_interest.insert("合計(平均)", (Some(total_average), Some(monthly)));
+    }
+
+    if let (Some(cost_idx), Some(net_idx)) = (cost_idx, net_idx) {
+        let mut total_cost = 0.0;
+        let mut total_net = 0.0;
+
+        for row in rows {
+            let label = row.get(label_idx).map(|value| value.trim()).unwrap_or("");
+            if label.is_empty()
+                || is_summary_label(label)
+                || interest_labels.iter().any(|token| label.contains(token))
+            {
+                continue;
+            }
+            if let Some(value) = row.get(cost_idx).and_then(|raw| parse_numeric_value(raw)) {
+                total_cost += value;
+            }
+            if let Some(value) = row.get(net_idx).and_then(|raw| parse_numeric_value(raw)) {
+                total_net += value;
+            }
+        }
+
+        let total_profit = total_net - total_cost;
+        let total_rate = safe_div(total_profit, total_cost);
+
+        report.totals.push(SummaryEntry {
+            label: "合計-投入金額".to_string(),
+            value: format_f64(total_cost),
+        });
+        report.totals.push(SummaryEntry {
+            label: "合計-目前淨值".to_string(),
+            value: format_f64(total_net),
+        });
+        report.totals.push(SummaryEntry {
+            label: "合計-損益率".to_string(),
+            value: format_percent(total_rate),
+        });
+        report.totals.push(SummaryEntry {
+            label: "合計-損益".to_string(),
+            value: format_f64(total_profit),
+        });
+    } else {
+        report.notes.push("找不到投入金額/目前淨值欄位".to_string());
+    }
+
+    for label in interest_labels {
+        let row = find_row_by_first_cell(rows, label);
+        let derived = derived_interest.get(label);
+        let annual = resolve_summary_value(row.as_ref(), 1, derived.and_then(|entry| entry.0));
+        let monthly = resolve_summary_value(row.as_ref(), 2, derived.and_then(|entry| entry.1));
+        if !annual.trim().is_empty() {
+            report.totals.push(SummaryEntry {
+                label: format!("{label}-年化"),
+                value: annual,
+            });
+        }
+        if !monthly.trim().is_empty() {
+            report.totals.push(SummaryEntry {
+                label: format!("{label}-月化"),
+                value: monthly,
+            });
+        }
+    }
+
+    if report.totals.is_empty() {
+        report.notes.push("找不到可計算的資產總結資料".to_string());
+    }
+
+    report
+}
@@ -0,0 +1,507 @@
+//! Small line-based language for user-defined summary reports, so reports beyond
+//! [`compute_summary_report`](crate::report::compute_summary_report) need no code changes.
+//!
+//! One statement per line; `#` starts a comment and values containing spaces are quoted:
+//!
+//! ```text
+//! title 配息總覽
+//! where 類別 = 股票
+//! where 市價 > 20
+//! group 所有權人
+//! count as 檔數
+//! sum 估計配息 as 年配息
+//! avg "國內 /國外"
+//! note 只含股票
+//! ```
+//!
+//! - `where <欄位> <op> <值>` keeps matching rows; `op` is `=`, `!=`, `contains`, or a numeric
+//!   `>`, `<`, `>=`, `<=`. Several `where` lines must all match.
+//! - `count`, `sum`, `avg`, `min`, `max` add one entry each, optionally renamed with `as`.
+//! - `group <欄位>` repeats every entry for each distinct value of that column. Co-owned
+//!   rows count towards each co-owner by their share when grouping or filtering by 所有權人.
+//! - `title` and `note` set the report title and append a note.
+
+use std::cmp::Ordering;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::domain::entities::owner_share::OwnerSplits;
+use crate::numeric::{format_f64, parse_numeric_value};
+use crate::owner_split::{split_rows_by_owner, OWNER_COLUMN};
+use crate::report::{OwnerSummary, SummaryEntry, SummaryReport};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    pub const ALL: [Aggregate; 5] = [
+        Aggregate::Count,
+        Aggregate::Sum,
+        Aggregate::Avg,
+        Aggregate::Min,
+        Aggregate::Max,
+    ];
+
+    /// The script keyword.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Aggregate::Count => "count",
+            Aggregate::Sum => "sum",
+            Aggregate::Avg => "avg",
+            Aggregate::Min => "min",
+            Aggregate::Max => "max",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|aggregate| aggregate.as_str() == value)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Aggregate::Count => "筆數",
+            Aggregate::Sum => "加總",
+            Aggregate::Avg => "平均",
+            Aggregate::Min => "最小",
+            Aggregate::Max => "最大",
+        }
+    }
+
+    /// Entry label when the script gives none.
+    pub fn default_label(self, column: Option<&str>) -> String {
+        match (self, column) {
+            (Aggregate::Count, _) | (_, None) => "筆數".to_string(),
+            (Aggregate::Sum, Some(name)) => name.to_string(),
+            (aggregate, Some(name)) => format!("{name} {}", aggregate.label()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Contains,
+    Gt,
+    Lt,
+    Ge,
+    Le,
+}
+
+impl Comparison {
+    pub const ALL: [Comparison; 7] = [
+        Comparison::Eq,
+        Comparison::Ne,
+        Comparison::Contains,
+        Comparison::Gt,
+        Comparison::Lt,
+        Comparison::Ge,
+        Comparison::Le,
+    ];
+
+    /// The script operator.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Comparison::Eq => "=",
+            Comparison::Ne => "!=",
+            Comparison::Contains => "contains",
+            Comparison::Gt => ">",
+            Comparison::Lt => "<",
+            Comparison::Ge => ">=",
+            Comparison::Le => "<=",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Comparison::Eq => "等於",
+            Comparison::Ne => "不等於",
+            Comparison::Contains => "包含",
+            Comparison::Gt => "大於",
+            Comparison::Lt => "小於",
+            Comparison::Ge => "大於等於",
+            Comparison::Le => "小於等於",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportFilter {
+    pub column: String,
+    pub comparison: Comparison,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportMetric {
+    pub aggregate: Aggregate,
+    /// Unused by `count`.
+    pub column: Option<String>,
+    pub label: String,
+}
+
+/// A report as the builder edits it; [`ReportDefinition::to_script`] and
+/// [`parse_report_script`] convert to and from the script form it is saved in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportDefinition {
+    pub title: String,
+    pub filters: Vec<ReportFilter>,
+    pub group_by: Option<String>,
+    pub metrics: Vec<ReportMetric>,
+    pub notes: Vec<String>,
+}
+
+impl Default for ReportDefinition {
+    fn default() -> Self {
+        Self {
+            title: "自訂報表".to_string(),
+            filters: Vec::new(),
+            group_by: None,
+            metrics: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+}
+
+impl ReportDefinition {
+    /// Column names referenced by filters, grouping and metrics that `headers` lacks.
+    fn missing_column<'a>(&'a self, headers: &[String]) -> Option<&'a str> {
+        self.filters
+            .iter()
+            .map(|filter| filter.column.as_str())
+            .chain(self.group_by.as_deref())
+            .chain(
+                self.metrics
+                    .iter()
+                    .filter_map(|metric| metric.column.as_deref()),
+            )
+            .find(|name| !headers.iter().any(|header| header == name))
+    }
+
+    pub fn to_script(&self) -> String {
+        let mut lines = vec![format!("title {}", quote(&self.title))];
+        for filter in &self.filters {
+            lines.push(format!(
+                "where {} {} {}",
+                quote(&filter.column),
+                filter.comparison.as_str(),
+                quote(&filter.value)
+            ));
+        }
+        if let Some(group) = &self.group_by {
+            lines.push(format!("group {}", quote(group)));
+        }
+        for metric in &self.metrics {
+            let column = metric
+                .column
+                .as_deref()
+                .filter(|_| metric.aggregate != Aggregate::Count);
+            let mut line = metric.aggregate.as_str().to_string();
+            if let Some(column) = column {
+                line.push(' ');
+                line.push_str(&quote(column));
+            }
+            if metric.label != metric.aggregate.default_label(column) {
+                line.push_str(" as ");
+                line.push_str(&quote(&metric.label));
+            }
+            lines.push(line);
+        }
+        for note in &self.notes {
+            lines.push(format!("note {}", quote(note)));
+        }
+        lines.join("\n") + "\n"
+    }
+
+    /// [`Self::run`] over a dataset with co-owned rows. A report that groups or filters
+    /// on 所有權人 sees each co-owner's share of those rows as a row of its own.
+    pub fn run_with_owner_splits(
+        &self,
+        headers: &[String],
+        rows: &[Vec<String>],
+        splits: &OwnerSplits,
+    ) -> Result<SummaryReport> {
+        let by_owner = self.group_by.as_deref() == Some(OWNER_COLUMN)
+            || self
+                .filters
+                .iter()
+                .any(|filter| filter.column == OWNER_COLUMN);
+        if by_owner && !splits.is_empty() {
+            return self.run(headers, &split_rows_by_owner(headers, rows, splits));
+        }
+        self.run(headers, rows)
+    }
+
+    /// Runs the report over a dataset.
+    pub fn run(&self, headers: &[String], rows: &[Vec<String>]) -> Result<SummaryReport> {
+        if let Some(name) = self.missing_column(headers) {
+            bail!("找不到欄位：{name}");
+        }
+        let column = |name: &str| headers.iter().position(|header| header == name);
+        let conditions = self
+            .filters
+            .iter()
+            .filter_map(|filter| {
+                Some(Condition {
+                    column: column(&filter.column)?,
+                    comparison: filter.comparison,
+                    value: filter.value.clone(),
+                })
+            })
+            .collect::<Vec<_>>();
+        let entries = self
+            .metrics
+            .iter()
+            .map(|metric| Entry {
+                aggregate: metric.aggregate,
+                column: metric.column.as_deref().and_then(column),
+                label: metric.label.clone(),
+            })
+            .collect::<Vec<_>>();
+        let group = self.group_by.as_deref().and_then(column);
+
+        let mut report = SummaryReport {
+            title: self.title.clone(),
+            notes: self.notes.clone(),
+            ..SummaryReport::default()
+        };
+        let selected = rows
+            .iter()
+            .filter(|row| conditions.iter().all(|condition| condition.matches(row)))
+            .collect::<Vec<_>>();
+        report.totals = evaluate(&entries, &selected);
+        if let Some(group_idx) = group {
+            report.group_heading = format!("依{}", headers[group_idx]);
+            let mut keys: Vec<String> = Vec::new();
+            for row in &selected {
+                let key = row.get(group_idx).cloned().unwrap_or_default();
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+            for key in keys {
+                let members = selected
+                    .iter()
+                    .copied()
+                    .filter(|row| row.get(group_idx).map(String::as_str).unwrap_or_default() == key)
+                    .collect::<Vec<_>>();
+                report.owner_totals.push(OwnerSummary {
+                    owner: key,
+                    entries: evaluate(&entries, &members),
+                });
+            }
+        }
+        Ok(report)
+    }
+}
+
+struct Condition {
+    column: usize,
+    comparison: Comparison,
+    value: String,
+}
+
+struct Entry {
+    aggregate: Aggregate,
+    column: Option<usize>,
+    label: String,
+}
+
+/// Runs `script` over a dataset and returns the report shown in the summary dialog.
+/// Errors name the offending line.
+pub fn run_report_script(
+    script: &str,
+    headers: &[String],
+    rows: &[Vec<String>],
+    splits: &OwnerSplits,
+) -> Result<SummaryReport> {
+    let definition = parse_lines(script, Some(headers))?;
+    definition.run_with_owner_splits(headers, rows, splits)
+}
+
+/// Reads a script into its [`ReportDefinition`] without checking column names.
+pub fn parse_report_script(script: &str) -> Result<ReportDefinition> {
+    parse_lines(script, None)
+}
+
+fn parse_lines(script: &str, headers: Option<&[String]>) -> Result<ReportDefinition> {
+    let mut definition = ReportDefinition::default();
+    for (line_idx, line) in script.lines().enumerate() {
+        let at_line = |err: anyhow::Error| anyhow!("第 {} 行：{err}", line_idx + 1);
+        let tokens = tokenize(line).map_err(at_line)?;
+        let Some((keyword, args)) = tokens.split_first() else {
+            continue;
+        };
+        parse_statement(keyword, args, &mut definition).map_err(at_line)?;
+        if let Some(name) = headers.and_then(|headers| definition.missing_column(headers)) {
+            return Err(at_line(anyhow!("找不到欄位：{name}")));
+        }
+    }
+    Ok(definition)
+}
+
+fn parse_statement(
+    keyword: &str,
+    args: &[String],
+    definition: &mut ReportDefinition,
+) -> Result<()> {
+    match keyword {
+        "title" => definition.title = args.join(" "),
+        "note" => definition.notes.push(args.join(" ")),
+        "group" => match args {
+            [name] => definition.group_by = Some(name.clone()),
+            _ => bail!("group 需要一個欄位"),
+        },
+        "where" => match args {
+            [name, op, value] => definition.filters.push(ReportFilter {
+                column: name.clone(),
+                comparison: parse_comparison(op)?,
+                value: value.clone(),
+            }),
+            _ => bail!("where 格式應為：where 欄位 運算子 值"),
+        },
+        "count" => {
+            let label = parse_label(args, &Aggregate::Count.default_label(None))?;
+            definition.metrics.push(ReportMetric {
+                aggregate: Aggregate::Count,
+                column: None,
+                label,
+            });
+        }
+        "sum" | "avg" | "min" | "max" => {
+            let aggregate = Aggregate::parse(keyword).unwrap_or(Aggregate::Sum);
+            let Some((name, rest)) = args.split_first() else {
+                bail!("{keyword} 需要一個欄位");
+            };
+            definition.metrics.push(ReportMetric {
+                aggregate,
+                column: Some(name.clone()),
+                label: parse_label(rest, &aggregate.default_label(Some(name)))?,
+            });
+        }
+        other => bail!("未知的指令：{other}"),
+    }
+    Ok(())
+}
+
+/// Quotes a token that would otherwise split or start a comment.
+fn quote(value: &str) -> String {
+    if value.is_empty() || value.contains(|ch: char| ch.is_whitespace() || ch == '#' || ch == '"') {
+        format!("\"{}\"", value.replace('"', ""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn parse_label(args: &[String], default: &str) -> Result<String> {
+    match args {
+        [] => Ok(default.to_string()),
+        [as_keyword, label] if as_keyword == "as" => Ok(label.clone()),
+        _ => bail!("多餘的參數；要改名請用 as 標籤"),
+    }
+}
+
+fn parse_comparison(op: &str) -> Result<Comparison> {
+    Ok(match op {
+        "=" | "==" => Comparison::Eq,
+        "!=" => Comparison::Ne,
+        "contains" => Comparison::Contains,
+        ">" => Comparison::Gt,
+        "<" => Comparison::Lt,
+        ">=" => Comparison::Ge,
+        "<=" => Comparison::Le,
+        other => bail!("未知的運算子：{other}"),
+    })
+}
+
+impl Condition {
+    fn matches(&self, row: &[String]) -> bool {
+        let cell = row.get(self.column).map(String::as_str).unwrap_or_default();
+        let numeric = || {
+            parse_numeric_value(cell)
+                .zip(parse_numeric_value(&self.value))
+                .map(|(left, right)| left.partial_cmp(&right))
+        };
+        match self.comparison {
+            Comparison::Eq => cell.trim() == self.value,
+            Comparison::Ne => cell.trim() != self.value,
+            Comparison::Contains => cell.contains(&self.value),
+            Comparison::Gt => matches!(numeric(), Some(Some(Ordering::Greater))),
+            Comparison::Lt => matches!(numeric(), Some(Some(Ordering::Less))),
+            Comparison::Ge => matches!(numeric(), Some(Some(Ordering::Greater | Ordering::Equal))),
+            Comparison::Le => matches!(numeric(), Some(Some(Ordering::Less | Ordering::Equal))),
+        }
+    }
+}
+
+fn evaluate(entries: &[Entry], rows: &[&Vec<String>]) -> Vec<SummaryEntry> {
+    entries
+        .iter()
+        .map(|entry| {
+            let values = entry
+                .column
+                .map(|idx| {
+                    rows.iter()
+                        .filter_map(|row| row.get(idx).and_then(|cell| parse_numeric_value(cell)))
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+            let value = match entry.aggregate {
+                Aggregate::Count => Some(rows.len() as f64),
+                Aggregate::Sum => Some(values.iter().sum()),
+                Aggregate::Avg => {
+                    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+                }
+                Aggregate::Min => values.iter().copied().reduce(f64::min),
+                Aggregate::Max => values.iter().copied().reduce(f64::max),
+            };
+            SummaryEntry {
+                label: entry.label.clone(),
+                value: value.map(format_f64).unwrap_or_else(|| "N/A".to_string()),
+            }
+        })
+        .collect()
+}
+
+/// Splits on whitespace, keeping `"quoted values"` together and dropping `#` comments.
+fn tokenize(line: &str) -> Result<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(&ch) = chars.peek() {
+        if ch.is_whitespace() {
+            chars.next();
+        } else if ch == '#' {
+            break;
+        } else if ch == '"' {
+            chars.next();
+            let mut token = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(ch) => token.push(ch),
+                    None => bail!("引號未結束"),
+                }
+            }
+            tokens.push(token);
+        } else {
+            let mut token = String::new();
+            while let Some(&ch) = chars.peek() {
+                if ch.is_whitespace() {
+                    break;
+                }
+                token.push(ch);
+                chars.next();
+            }
+            tokens.push(token);
+        }
+    }
+    Ok(tokens)
+}
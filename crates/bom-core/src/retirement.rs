@@ -0,0 +1,384 @@
+//! Monte Carlo projection of the portfolio into retirement. What is held today is split
+//! by 性質 into 股, 債 and 定存; each trial draws a yearly return for every class from a
+//! normal distribution with the class's expected return and volatility, and the spread of
+//! the trials gives percentile bands of the value and the income it pays. Amounts are
+//! given in today's dollars, discounted at the inflation rate of the income goal.
+
+use anyhow::Result;
+
+use crate::dividend_year::live_datasets;
+use crate::domain::entities::dataset::DatasetKind;
+use crate::income_goal::discount_to_today;
+use crate::numeric::{format_f64, is_summary_label, parse_numeric_value, row_value};
+use crate::usecase::services::query_service::QueryService;
+
+/// Holds [`RetirementPlan::to_setting`].
+pub const RETIREMENT_PLAN_KEY: &str = "retirement_plan";
+/// Percentiles of the trials each band is drawn between, lowest first.
+pub const PERCENTILES: [f64; 5] = [0.1, 0.25, 0.5, 0.75, 0.9];
+pub const MAX_YEARS: u32 = 60;
+pub const MAX_TRIALS: u32 = 10_000;
+
+/// The same seed every run, so the bands only move when the inputs do.
+const SEED: u64 = 0x5EED_B0B5;
+/// Holdings value columns, in order of preference.
+const VALUE_COLUMNS: [&str; 2] = ["淨值", "總成本"];
+const NATURE_COLUMN: &str = "性質";
+const NAME_COLUMN: &str = "名稱";
+const FORM_COLUMN: &str = "資產形式";
+const BALANCE_COLUMN: &str = "餘額";
+
+/// What an amount is held as, by the 性質 of a holding or the 資產形式 of an account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetClass {
+    Stock,
+    Bond,
+    Deposit,
+}
+
+impl AssetClass {
+    pub const ALL: [AssetClass; 3] = [AssetClass::Stock, AssetClass::Bond, AssetClass::Deposit];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AssetClass::Stock => "股",
+            AssetClass::Bond => "債",
+            AssetClass::Deposit => "定存",
+        }
+    }
+
+    /// A holding's class by its 性質: 債 for bonds, 定存 for deposits and 股 for anything
+    /// else.
+    pub fn of_nature(nature: &str) -> Self {
+        if nature.contains('債') {
+            AssetClass::Bond
+        } else if nature.contains("定存") {
+            AssetClass::Deposit
+        } else {
+            AssetClass::Stock
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Yearly assumptions for one [`AssetClass`], as ratios.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClassAssumption {
+    /// Mean total return, income included.
+    pub expected_return: f64,
+    /// Standard deviation of the return.
+    pub volatility: f64,
+    /// Share of the value paid out as dividends or interest.
+    pub income_yield: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetirementPlan {
+    pub years: u32,
+    pub trials: u32,
+    /// Indexed as [`AssetClass::ALL`].
+    pub assumptions: [ClassAssumption; 3],
+}
+
+impl Default for RetirementPlan {
+    fn default() -> Self {
+        Self {
+            years: 30,
+            trials: 1000,
+            assumptions: [
+                ClassAssumption {
+                    expected_return: 0.06,
+                    volatility: 0.18,
+                    income_yield: 0.04,
+                },
+                ClassAssumption {
+                    expected_return: 0.03,
+                    volatility: 0.06,
+                    income_yield: 0.04,
+                },
+                ClassAssumption {
+                    expected_return: 0.016,
+                    volatility: 0.0,
+                    income_yield: 0.016,
+                },
+            ],
+        }
+    }
+}
+
+impl RetirementPlan {
+    pub fn assumption(&self, class: AssetClass) -> ClassAssumption {
+        self.assumptions[class.index()]
+    }
+
+    /// `years=30;trials=1000;股=0.06,0.18,0.04;…`, one class per item as return,
+    /// volatility and yield.
+    pub fn to_setting(&self) -> String {
+        let mut items = vec![
+            format!("years={}", self.years),
+            format!("trials={}", self.trials),
+        ];
+        for class in AssetClass::ALL {
+            let assumption = self.assumption(class);
+            items.push(format!(
+                "{}={},{},{}",
+                class.label(),
+                format_f64(assumption.expected_return),
+                format_f64(assumption.volatility),
+                format_f64(assumption.income_yield)
+            ));
+        }
+        items.join(";")
+    }
+
+    /// Reads [`RetirementPlan::to_setting`]; items missing or out of range keep their
+    /// defaults.
+    pub fn parse_setting(value: &str) -> Self {
+        let mut plan = Self::default();
+        for item in value.split(';') {
+            let Some((name, value)) = item.split_once('=') else {
+                continue;
+            };
+            let name = name.trim();
+            match name {
+                "years" => {
+                    if let Some(years) = value
+                        .trim()
+                        .parse()
+                        .ok()
+                        .filter(|y| (1..=MAX_YEARS).contains(y))
+                    {
+                        plan.years = years;
+                    }
+                }
+                "trials" => {
+                    if let Some(trials) = value
+                        .trim()
+                        .parse()
+                        .ok()
+                        .filter(|t| (1..=MAX_TRIALS).contains(t))
+                    {
+                        plan.trials = trials;
+                    }
+                }
+                _ => {
+                    let Some(class) = AssetClass::ALL
+                        .into_iter()
+                        .find(|class| class.label() == name)
+                    else {
+                        continue;
+                    };
+                    let numbers = value
+                        .split(',')
+                        .map(|number| number.trim().parse::<f64>().ok())
+                        .collect::<Option<Vec<_>>>();
+                    if let Some([expected_return, volatility, income_yield]) = numbers.as_deref() {
+                        plan.assumptions[class.index()] = ClassAssumption {
+                            expected_return: *expected_return,
+                            volatility: volatility.abs(),
+                            income_yield: *income_yield,
+                        };
+                    }
+                }
+            }
+        }
+        plan
+    }
+}
+
+/// One year of the projection: the [`PERCENTILES`] of the trials, in today's dollars.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectionBand {
+    pub year: u32,
+    pub value: [f64; 5],
+    /// Income a month paid over the year.
+    pub monthly_income: [f64; 5],
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RetirementProjection {
+    /// Indexed as [`AssetClass::ALL`].
+    pub start: [f64; 3],
+    /// From year 0, today, to the plan's last year.
+    pub bands: Vec<ProjectionBand>,
+}
+
+pub fn load_retirement_plan(query: &QueryService) -> Result<RetirementPlan> {
+    Ok(query
+        .load_setting(RETIREMENT_PLAN_KEY)?
+        .as_deref()
+        .map(RetirementPlan::parse_setting)
+        .unwrap_or_default())
+}
+
+pub fn save_retirement_plan(query: &QueryService, plan: &RetirementPlan) -> Result<()> {
+    query.save_setting(RETIREMENT_PLAN_KEY, &plan.to_setting())?;
+    Ok(())
+}
+
+/// What is held today by class: 淨值 of the 持股 rows by 性質, subtotal rows left out,
+/// and the 餘額 of the 定存 accounts of the 資產總表. 另存 copies and year archives are
+/// left out.
+pub fn starting_allocation(query: &QueryService) -> Result<[f64; 3]> {
+    let mut start = [0.0; 3];
+    for dataset in live_datasets(&query.list_datasets(false)?) {
+        let kind = dataset.effective_kind();
+        if !matches!(kind, DatasetKind::Holdings | DatasetKind::Assets) {
+            continue;
+        }
+        let page = query.load_tabular_data(dataset.id)?;
+        let position = |name: &str| page.columns.iter().position(|column| column == name);
+        if kind == DatasetKind::Assets {
+            let (Some(form_idx), Some(balance_idx)) =
+                (position(FORM_COLUMN), position(BALANCE_COLUMN))
+            else {
+                continue;
+            };
+            start[AssetClass::Deposit.index()] += page
+                .rows
+                .iter()
+                .filter(|row| row_value(row, form_idx).contains("定存"))
+                .filter_map(|row| parse_numeric_value(&row_value(row, balance_idx)))
+                .sum::<f64>();
+            continue;
+        }
+        let Some(value_idx) = VALUE_COLUMNS.into_iter().find_map(position) else {
+            continue;
+        };
+        let nature_idx = position(NATURE_COLUMN);
+        let name_idx = position(NAME_COLUMN);
+        for row in &page.rows {
+            if name_idx.is_some_and(|idx| is_summary_label(&row_value(row, idx))) {
+                continue;
+            }
+            let Some(value) = parse_numeric_value(&row_value(row, value_idx)) else {
+                continue;
+            };
+            let nature = nature_idx
+                .map(|idx| row_value(row, idx))
+                .unwrap_or_default();
+            start[AssetClass::of_nature(&nature).index()] += value;
+        }
+    }
+    Ok(start)
+}
+
+/// Runs `plan.trials` trials of `plan.years` years from `start`. Classes are drawn
+/// independently and income is reinvested, as the expected return includes it.
+pub fn simulate_retirement(
+    start: [f64; 3],
+    plan: &RetirementPlan,
+    inflation_rate: f64,
+) -> RetirementProjection {
+    let years = plan.years.clamp(1, MAX_YEARS) as usize;
+    let trials = plan.trials.clamp(1, MAX_TRIALS) as usize;
+    let mut rng = SplitMix64(SEED);
+    let mut values = vec![vec![0.0; trials]; years + 1];
+    let mut incomes = vec![vec![0.0; trials]; years + 1];
+    for trial in 0..trials {
+        let mut held = start;
+        for year in 0..=years {
+            values[year][trial] = held.iter().sum();
+            incomes[year][trial] = AssetClass::ALL
+                .iter()
+                .map(|class| held[class.index()] * plan.assumption(*class).income_yield)
+                .sum::<f64>()
+                / 12.0;
+            for class in AssetClass::ALL {
+                let assumption = plan.assumption(class);
+                let drawn = assumption.expected_return + assumption.volatility * rng.normal();
+                held[class.index()] *= (1.0 + drawn).max(0.0);
+            }
+        }
+    }
+    let bands = values
+        .iter_mut()
+        .zip(incomes.iter_mut())
+        .enumerate()
+        .map(|(year, (values, incomes))| {
+            let today = |amount: f64| discount_to_today(amount, inflation_rate, year as f64);
+            ProjectionBand {
+                year: year as u32,
+                value: percentiles(values).map(today),
+                monthly_income: percentiles(incomes).map(today),
+            }
+        })
+        .collect();
+    RetirementProjection { start, bands }
+}
+
+/// The projection as a table: 年後, then the 10th percentile, median and 90th percentile
+/// of 淨值 and of 每月收入.
+pub fn retirement_projection_table(
+    projection: &RetirementProjection,
+) -> (Vec<String>, Vec<Vec<String>>) {
+    let shown = [0, 2, 4];
+    let percentile_label = |idx: usize| {
+        if idx == 2 {
+            "中位數".to_string()
+        } else {
+            format!("P{}", format_f64(PERCENTILES[idx] * 100.0))
+        }
+    };
+    let mut headers = vec!["年後".to_string()];
+    for name in ["淨值", "每月收入"] {
+        headers.extend(
+            shown
+                .iter()
+                .map(|idx| format!("{name} {}", percentile_label(*idx))),
+        );
+    }
+    let rows = projection
+        .bands
+        .iter()
+        .map(|band| {
+            std::iter::once(band.year.to_string())
+                .chain(shown.iter().map(|idx| format_f64(band.value[*idx].round())))
+                .chain(
+                    shown
+                        .iter()
+                        .map(|idx| format_f64(band.monthly_income[*idx].round())),
+                )
+                .collect()
+        })
+        .collect();
+    (headers, rows)
+}
+
+fn percentiles(samples: &mut [f64]) -> [f64; 5] {
+    samples.sort_by(f64::total_cmp);
+    let last = samples.len().saturating_sub(1);
+    PERCENTILES.map(|p| {
+        samples
+            .get((p * last as f64).round() as usize)
+            .copied()
+            .unwrap_or(0.0)
+    })
+}
+
+/// Small deterministic generator, so a projection can be repeated exactly.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in (0, 1].
+    fn next_f64(&mut self) -> f64 {
+        ((self.next_u64() >> 11) + 1) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Standard normal, by Box–Muller.
+    fn normal(&mut self) -> f64 {
+        let (u, v) = (self.next_f64(), self.next_f64());
+        (-2.0 * u.ln()).sqrt() * (std::f64::consts::TAU * v).cos()
+    }
+}
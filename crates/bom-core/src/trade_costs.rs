@@ -0,0 +1,207 @@
+//! What a trade costs as a Taiwanese broker bills it: 手續費 of 0.1425% of the amount,
+//! usually discounted, with a minimum per trade, and on sells 證券交易稅 of 0.3% for
+//! stocks or 0.1% for ETFs. Both are rounded down to whole dollars. Buys add them to the
+//! cost basis and sells take them off the proceeds, so realized gains are net of them.
+
+use crate::numeric::{parse_numeric_value, row_value};
+use crate::prices::CODE_COLUMN;
+
+pub const COMMISSION_RATE: f64 = 0.001425;
+pub const MIN_COMMISSION: f64 = 20.0;
+pub const STOCK_TAX_RATE: f64 = 0.003;
+pub const ETF_TAX_RATE: f64 = 0.001;
+
+/// Column of a trade ledger holding what [`TradeSide::parse`] reads.
+pub const SIDE_COLUMN: &str = "買賣";
+const QUANTITY_COLUMN: &str = "數量";
+/// Price columns of a trade ledger, tried in this order.
+const PRICE_COLUMNS: [&str; 2] = ["成交價", "價格"];
+const FEE_COLUMN: &str = "手續費";
+const TAX_COLUMN: &str = "交易稅";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+impl TradeSide {
+    /// `買`, `買進`, `賣`, `賣出` and the like.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.starts_with('買') {
+            Some(TradeSide::Buy)
+        } else if value.starts_with('賣') {
+            Some(TradeSide::Sell)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeeSchedule {
+    pub commission_rate: f64,
+    /// Share of the commission charged, e.g. `0.6` for a 六折 discount.
+    pub commission_discount: f64,
+    pub min_commission: f64,
+}
+
+impl Default for FeeSchedule {
+    fn default() -> Self {
+        Self {
+            commission_rate: COMMISSION_RATE,
+            commission_discount: 1.0,
+            min_commission: MIN_COMMISSION,
+        }
+    }
+}
+
+impl FeeSchedule {
+    pub fn commission(&self, amount: f64) -> f64 {
+        if amount <= 0.0 {
+            return 0.0;
+        }
+        (amount * self.commission_rate * self.commission_discount)
+            .floor()
+            .max(self.min_commission)
+    }
+}
+
+/// 證券交易稅 on a trade of `code`; ETFs, whose 代號 start with `00`, pay the lower rate
+/// and buys pay none.
+pub fn transaction_tax(code: &str, side: TradeSide, amount: f64) -> f64 {
+    if side == TradeSide::Buy || amount <= 0.0 {
+        return 0.0;
+    }
+    let rate = if code.trim().starts_with("00") {
+        ETF_TAX_RATE
+    } else {
+        STOCK_TAX_RATE
+    };
+    (amount * rate).floor()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trade {
+    pub code: String,
+    pub side: TradeSide,
+    pub quantity: f64,
+    pub price: f64,
+    /// As billed; `None` to work it out from the schedule.
+    pub fee: Option<f64>,
+    pub tax: Option<f64>,
+}
+
+impl Trade {
+    pub fn amount(&self) -> f64 {
+        self.quantity * self.price
+    }
+
+    pub fn fee(&self, schedule: &FeeSchedule) -> f64 {
+        self.fee
+            .unwrap_or_else(|| schedule.commission(self.amount()))
+    }
+
+    pub fn tax(&self) -> f64 {
+        self.tax
+            .unwrap_or_else(|| transaction_tax(&self.code, self.side, self.amount()))
+    }
+
+    /// Paid out for a buy, amount plus costs; received for a sell, amount less costs.
+    pub fn settlement(&self, schedule: &FeeSchedule) -> f64 {
+        let costs = self.fee(schedule) + self.tax();
+        match self.side {
+            TradeSide::Buy => self.amount() + costs,
+            TradeSide::Sell => self.amount() - costs,
+        }
+    }
+}
+
+/// A position after a run of trades, at average cost.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PositionCost {
+    pub quantity: f64,
+    /// What the shares still held cost, fees and taxes included.
+    pub cost_basis: f64,
+    /// Gains of the sells over the average cost of what they sold, net of their costs.
+    pub realized_gain: f64,
+    pub fees: f64,
+    pub taxes: f64,
+}
+
+impl PositionCost {
+    pub fn average_cost(&self) -> Option<f64> {
+        (self.quantity > 0.0).then(|| self.cost_basis / self.quantity)
+    }
+}
+
+/// A sell of more shares than the trades before it bought. Its proceeds would count as
+/// gain on shares that were never paid for, so the position is not worked out.
+#[derive(Debug, Clone, Copy, PartialEq, thiserror::Error)]
+#[error("賣出 {sold} 股，但當時只持有 {held} 股")]
+pub struct Oversold {
+    pub sold: f64,
+    pub held: f64,
+}
+
+/// Folds `trades` of one 代號, oldest first, into its position; fails at the first sell
+/// of more than is held.
+pub fn position_cost(trades: &[Trade], schedule: &FeeSchedule) -> Result<PositionCost, Oversold> {
+    let mut position = PositionCost::default();
+    for trade in trades {
+        if trade.side == TradeSide::Sell && trade.quantity > position.quantity {
+            return Err(Oversold {
+                sold: trade.quantity,
+                held: position.quantity,
+            });
+        }
+        position.fees += trade.fee(schedule);
+        position.taxes += trade.tax();
+        match trade.side {
+            TradeSide::Buy => {
+                position.quantity += trade.quantity;
+                position.cost_basis += trade.settlement(schedule);
+            }
+            TradeSide::Sell => {
+                let sold_cost = position
+                    .average_cost()
+                    .map(|average| average * trade.quantity)
+                    .unwrap_or_default();
+                position.realized_gain += trade.settlement(schedule) - sold_cost;
+                position.quantity -= trade.quantity;
+                position.cost_basis -= sold_cost;
+            }
+        }
+    }
+    Ok(position)
+}
+
+/// The trades `rows` of a ledger table record, in row order, read from its 代號, 買賣,
+/// 數量 and 成交價 (or 價格) columns, with 手續費 and 交易稅 as billed where filled in.
+/// Rows that are not a buy or a sell, such as 股利, are left out. `None` when the table
+/// lacks one of those columns.
+pub fn ledger_trades(columns: &[String], rows: &[Vec<String>]) -> Option<Vec<Trade>> {
+    let position = |name: &str| columns.iter().position(|column| column == name);
+    let code_idx = position(CODE_COLUMN)?;
+    let side_idx = position(SIDE_COLUMN)?;
+    let quantity_idx = position(QUANTITY_COLUMN)?;
+    let price_idx = PRICE_COLUMNS.iter().find_map(|name| position(name))?;
+    let billed = |idx: Option<usize>, row: &[String]| {
+        idx.and_then(|idx| parse_numeric_value(&row_value(row, idx)))
+    };
+    let trades = rows
+        .iter()
+        .filter_map(|row| {
+            Some(Trade {
+                code: row_value(row, code_idx).trim().to_string(),
+                side: TradeSide::parse(&row_value(row, side_idx))?,
+                quantity: parse_numeric_value(&row_value(row, quantity_idx))?,
+                price: parse_numeric_value(&row_value(row, price_idx))?,
+                fee: billed(position(FEE_COLUMN), row),
+                tax: billed(position(TAX_COLUMN), row),
+            })
+        })
+        .collect();
+    Some(trades)
+}
@@ -0,0 +1,98 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::domain::entities::cell_origin::CellOrigin;
+use crate::holdings::DuplicateCode;
+
+/// Why an import failed, for callers that react differently per case.
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+    #[error("unsupported import file: {}", .0.display())]
+    Unsupported(PathBuf),
+    #[error("file not found: {}", .0.display())]
+    FileNotFound(PathBuf),
+    #[error("missing sheet: {0}")]
+    MissingSheet(String),
+    #[error("read-only mode is on")]
+    ReadOnly,
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl ImportError {
+    /// Recovers the typed error an importer raised, or classifies I/O failures.
+    pub fn from_anyhow(err: anyhow::Error, path: &Path) -> Self {
+        let err = match err.downcast::<ImportError>() {
+            Ok(import_err) => return import_err,
+            Err(err) => err,
+        };
+        let missing_file = err.chain().any(|cause| {
+            cause
+                .downcast_ref::<std::io::Error>()
+                .is_some_and(|io_err| io_err.kind() == std::io::ErrorKind::NotFound)
+        });
+        if missing_file {
+            ImportError::FileNotFound(path.to_path_buf())
+        } else {
+            ImportError::Other(err)
+        }
+    }
+}
+
+/// One dataset read from a source file, before it is stored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedDataset {
+    pub name: String,
+    pub source_path: String,
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    /// Where each cell of `rows` was read from, for importers whose transform keeps
+    /// track; empty otherwise.
+    pub origins: Vec<Vec<Option<CellOrigin>>>,
+}
+
+/// What a file's import needs the user to decide on, found before anything is stored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportValidation {
+    /// 代號 listed on more than one row of 持股明細.
+    pub duplicate_codes: Vec<DuplicateCode>,
+}
+
+impl ImportValidation {
+    pub fn is_clean(&self) -> bool {
+        self.duplicate_codes.is_empty()
+    }
+}
+
+/// A file format that can be imported. Implementations only read and reshape data;
+/// storing it is shared by every importer.
+pub trait Importer: Send + Sync {
+    /// Shown in file dialogs and error messages.
+    fn name(&self) -> &str;
+
+    /// Lower-case extensions without the dot, e.g. `["csv"]`.
+    fn extensions(&self) -> &[&str];
+
+    /// Whether this importer handles `path`; by default decided by extension.
+    fn matches(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase())
+            .is_some_and(|ext| self.extensions().contains(&ext.as_str()))
+    }
+
+    fn parse(&self, path: &Path) -> Result<Vec<ParsedDataset>>;
+
+    /// Reshapes parsed sheets into the stored datasets; the default keeps them as-is.
+    fn transform(&self, datasets: Vec<ParsedDataset>) -> Result<Vec<ParsedDataset>> {
+        Ok(datasets)
+    }
+
+    /// Whether the parsed sheets are stored with the datasets [`Self::transform`] makes of
+    /// them, so the transform can be run again without the file. Only worth it for
+    /// importers whose transform reshapes the sheets.
+    fn keeps_source_sheets(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,2 @@
+pub mod importer;
+pub mod repo;
@@ -0,0 +1,232 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use crate::domain::entities::cell_origin::CellOrigins;
+use crate::domain::entities::column_set::ColumnSet;
+use crate::domain::entities::computed_column::ComputedColumn;
+use crate::domain::entities::dataset::{
+    ColumnFormat, ColumnStats, ColumnType, DatasetDisplay, DatasetId, DatasetKind, PageQuery,
+    PageResult,
+};
+use crate::domain::entities::deposit_rate::DepositRate;
+use crate::domain::entities::edit::{SavedChanges, StagedEdits};
+use crate::domain::entities::import_profile::ImportProfile;
+use crate::domain::entities::import_record::ImportRecord;
+use crate::domain::entities::owner_share::{OwnerShare, OwnerSplits};
+use crate::domain::entities::price_point::{PriceHistory, PricePoint};
+use crate::domain::entities::saved_report::SavedReport;
+use crate::report::{StoredSummaryReport, SummaryReport};
+
+/// Failures of a [`DatasetRepository`], split by what the caller can do about them. Each
+/// variant carries the full technical message for the log.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RepoError {
+    #[error("not found: {0}")]
+    NotFound(String),
+    #[error("file not found: {0}")]
+    FileNotFound(String),
+    #[error("database schema mismatch: {0}")]
+    SchemaMismatch(String),
+    #[error("database is busy: {0}")]
+    Busy(String),
+    #[error("read-only mode is on")]
+    ReadOnly,
+    #[error("{0}")]
+    Message(String),
+}
+
+pub trait DatasetRepository: Send + Sync {
+    fn init(&self) -> Result<(), RepoError>;
+
+    fn list_datasets(&self, include_deleted: bool) -> Result<Vec<DatasetMeta>, RepoError>;
+    fn query_page(&self, query: PageQuery) -> Result<PageResult, RepoError>;
+    /// Every stored cell of a dataset, without computed columns.
+    fn load_tabular_data(&self, id: DatasetId) -> Result<TabularData, RepoError>;
+
+    fn create_dataset(
+        &self,
+        meta: NewDatasetMeta,
+        data: TabularData,
+    ) -> Result<DatasetId, RepoError>;
+    fn apply_edits(&self, id: DatasetId, edits: StagedEdits) -> Result<(), RepoError>;
+    #[allow(dead_code)]
+    fn soft_delete_dataset(&self, id: DatasetId) -> Result<(), RepoError>;
+    fn purge_dataset(&self, id: DatasetId) -> Result<(), RepoError>;
+    fn restore_dataset(&self, id: DatasetId) -> Result<(), RepoError>;
+    fn set_keep_forever(&self, id: DatasetId, keep_forever: bool) -> Result<(), RepoError>;
+    fn expired_datasets(&self, retention_days: u32) -> Result<Vec<DatasetId>, RepoError>;
+    fn load_column_visibility(&self, id: DatasetId) -> Result<BTreeMap<i64, bool>, RepoError>;
+    fn upsert_column_visibility(
+        &self,
+        id: DatasetId,
+        visibility: BTreeMap<i64, bool>,
+    ) -> Result<(), RepoError>;
+    fn load_visibility_presets(
+        &self,
+        id: DatasetId,
+    ) -> Result<BTreeMap<String, BTreeMap<i64, bool>>, RepoError>;
+    fn save_visibility_preset(
+        &self,
+        id: DatasetId,
+        name: String,
+        visibility: BTreeMap<i64, bool>,
+    ) -> Result<(), RepoError>;
+    fn delete_visibility_preset(&self, id: DatasetId, name: String) -> Result<(), RepoError>;
+    fn load_column_aliases(&self, id: DatasetId) -> Result<BTreeMap<i64, String>, RepoError>;
+    fn upsert_column_aliases(
+        &self,
+        id: DatasetId,
+        aliases: BTreeMap<i64, String>,
+    ) -> Result<(), RepoError>;
+    fn load_column_formats(&self, id: DatasetId) -> Result<BTreeMap<i64, ColumnFormat>, RepoError>;
+    fn upsert_column_formats(
+        &self,
+        id: DatasetId,
+        formats: BTreeMap<i64, ColumnFormat>,
+    ) -> Result<(), RepoError>;
+    fn load_column_types(&self, id: DatasetId) -> Result<BTreeMap<i64, ColumnType>, RepoError>;
+    fn upsert_column_types(
+        &self,
+        id: DatasetId,
+        types: BTreeMap<i64, ColumnType>,
+    ) -> Result<(), RepoError>;
+    fn load_saved_changes(&self, id: DatasetId) -> Result<SavedChanges, RepoError>;
+    fn load_cell_origins(&self, id: DatasetId) -> Result<CellOrigins, RepoError>;
+    fn load_dataset_display(&self, id: DatasetId) -> Result<DatasetDisplay, RepoError>;
+    fn save_dataset_display(&self, id: DatasetId, display: DatasetDisplay)
+        -> Result<(), RepoError>;
+    fn load_page_size(&self, id: DatasetId) -> Result<Option<i64>, RepoError>;
+    fn save_page_size(&self, id: DatasetId, page_size: Option<i64>) -> Result<(), RepoError>;
+    fn load_holdings_flags(&self) -> Result<BTreeMap<i64, bool>, RepoError>;
+    #[allow(dead_code)]
+    fn upsert_holdings_flag(&self, id: DatasetId, is_holdings: bool) -> Result<(), RepoError>;
+    fn rename_dataset(&self, id: DatasetId, name: String) -> Result<(), RepoError>;
+    fn set_dataset_kind(&self, id: DatasetId, kind: DatasetKind) -> Result<(), RepoError>;
+    fn backup_database(&self, dest_path: &Path) -> Result<(), RepoError>;
+    fn load_setting(&self, key: &str) -> Result<Option<String>, RepoError>;
+    fn save_setting(&self, key: &str, value: &str) -> Result<(), RepoError>;
+    fn load_import_profiles(&self) -> Result<Vec<ImportProfile>, RepoError>;
+    fn save_import_profile(&self, profile: ImportProfile) -> Result<(), RepoError>;
+    fn delete_import_profile(&self, name: String) -> Result<(), RepoError>;
+    fn load_column_set(&self, kind: DatasetKind) -> Result<Option<ColumnSet>, RepoError>;
+    fn save_column_set(&self, kind: DatasetKind, set: ColumnSet) -> Result<(), RepoError>;
+    fn delete_column_set(&self, kind: DatasetKind) -> Result<(), RepoError>;
+    fn load_saved_reports(&self) -> Result<Vec<SavedReport>, RepoError>;
+    fn save_saved_report(&self, report: SavedReport) -> Result<(), RepoError>;
+    fn delete_saved_report(&self, name: String) -> Result<(), RepoError>;
+    fn load_deposit_rates(&self) -> Result<Vec<DepositRate>, RepoError>;
+    fn save_deposit_rates(&self, rates: Vec<DepositRate>) -> Result<(), RepoError>;
+    fn load_import_history(&self) -> Result<Vec<ImportRecord>, RepoError>;
+    fn record_prices(&self, prices: Vec<PricePoint>) -> Result<(), RepoError>;
+    fn load_price_history(&self, codes: Vec<String>) -> Result<PriceHistory, RepoError>;
+    fn load_acknowledged_alerts(&self) -> Result<BTreeSet<String>, RepoError>;
+    fn acknowledge_alerts(&self, keys: Vec<String>) -> Result<(), RepoError>;
+    fn column_value_counts(
+        &self,
+        id: DatasetId,
+        col_idx: i64,
+    ) -> Result<Vec<(String, i64)>, RepoError>;
+    /// Columns blank in every row that passes the filters of `query`; its page and sort
+    /// are ignored.
+    fn empty_columns(&self, query: PageQuery) -> Result<BTreeSet<i64>, RepoError>;
+    /// Statistics of every column over the rows that pass the filters of `query`; its page
+    /// and sort are ignored.
+    fn column_stats(&self, query: PageQuery) -> Result<BTreeMap<i64, ColumnStats>, RepoError>;
+    fn load_computed_columns(&self, id: DatasetId) -> Result<Vec<ComputedColumn>, RepoError>;
+    fn save_computed_columns(
+        &self,
+        id: DatasetId,
+        columns: Vec<ComputedColumn>,
+    ) -> Result<(), RepoError>;
+    fn load_owner_splits(&self, id: DatasetId) -> Result<OwnerSplits, RepoError>;
+    fn save_owner_shares(
+        &self,
+        id: DatasetId,
+        row_idx: usize,
+        shares: Vec<OwnerShare>,
+    ) -> Result<(), RepoError>;
+    fn dataset_revision(&self, id: DatasetId) -> Result<i64, RepoError>;
+    fn save_summary_report(
+        &self,
+        id: DatasetId,
+        report: &SummaryReport,
+    ) -> Result<StoredSummaryReport, RepoError>;
+    fn load_summary_reports(&self, id: DatasetId) -> Result<Vec<StoredSummaryReport>, RepoError>;
+    /// Lists the datasets and runs the page query `select` builds from the list, reading
+    /// the column visibility of that dataset and the holdings flags in the same call.
+    /// Copies `source` with all of its per-dataset settings into a new dataset.
+    fn clone_dataset(
+        &self,
+        source: DatasetId,
+        meta: NewDatasetMeta,
+    ) -> Result<DatasetId, RepoError>;
+    fn load_dataset_context(
+        &self,
+        include_deleted: bool,
+        select: &dyn Fn(&[DatasetMeta]) -> Option<PageQuery>,
+    ) -> Result<DatasetContext, RepoError>;
+}
+
+/// Ends the `source_path` of a 另存 copy, followed by the id of the dataset it was saved
+/// from.
+pub const SAVE_AS_MARKER: &str = "#另存自:";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatasetMeta {
+    pub id: DatasetId,
+    pub name: String,
+    pub row_count: i64,
+    pub source_path: String,
+    pub deleted_at: Option<String>,
+    /// The stored type; `None` for datasets created before types were stored.
+    pub kind: Option<DatasetKind>,
+    /// Left in the trash by the auto-purge however long it has been there.
+    pub keep_forever: bool,
+}
+
+impl DatasetMeta {
+    /// The stored type, or the one the name suggests when none was stored.
+    pub fn effective_kind(&self) -> DatasetKind {
+        self.kind
+            .unwrap_or_else(|| DatasetKind::from_name(&self.name))
+    }
+
+    /// `source_path` of a 另存 copy of this dataset called `name`: in the same group,
+    /// marked as saved from this dataset, or from the one this dataset is itself a copy of.
+    pub fn save_as_source_path(&self, name: &str) -> String {
+        let prefix = self
+            .source_path
+            .split_once('#')
+            .map_or(self.source_path.as_str(), |(prefix, _)| prefix);
+        let primary = self.saved_as_copy_of().unwrap_or(self.id);
+        format!("{prefix}#{name}{SAVE_AS_MARKER}{}", primary.0)
+    }
+
+    /// The dataset this one is a 另存 copy of.
+    pub fn saved_as_copy_of(&self) -> Option<DatasetId> {
+        let (_, id) = self.source_path.rsplit_once(SAVE_AS_MARKER)?;
+        id.parse().ok().map(DatasetId)
+    }
+}
+
+/// What a workspace shows when it opens a dataset, read in one repository call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DatasetContext {
+    pub datasets: Vec<DatasetMeta>,
+    /// The dataset picked from `datasets`, with its first page and column visibility.
+    pub page: Option<(DatasetId, PageResult)>,
+    pub column_visibility: BTreeMap<i64, bool>,
+    pub holdings_flags: BTreeMap<i64, bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewDatasetMeta {
+    pub name: String,
+    pub source_path: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TabularData {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
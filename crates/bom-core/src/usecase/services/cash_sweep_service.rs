@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::dividend_sweep::{
+    cash_balance_updates, format_received, owner_deltas, parse_received, received_by_owner,
+    rows_before_save, sweep_enabled, DIVIDEND_SWEEP_KEY, DIVIDEND_SWEEP_RECEIVED_KEY_PREFIX,
+};
+use crate::domain::entities::dataset::{DatasetId, DatasetKind};
+use crate::domain::entities::edit::{CellKey, StagedEdits};
+use crate::domain::events::{DomainEvent, EventBus, SubscriptionId};
+use crate::usecase::ports::repo::{DatasetRepository, RepoError};
+use crate::usecase::services::recalc_service::in_group;
+
+/// Keeps the bank balances of a 資產總表 in step with the dividends entered in the
+/// 持股股息總表 of the same workbook: what a save adds to an owner's month columns is
+/// added to that owner's 活存 餘額. See [`crate::dividend_sweep`].
+pub struct CashSweepService {
+    repo: Arc<dyn DatasetRepository>,
+    events: EventBus,
+}
+
+impl CashSweepService {
+    /// Publishes [`DomainEvent::EditsApplied`] for every dataset it rewrites.
+    pub fn new(repo: Arc<dyn DatasetRepository>, events: EventBus) -> Self {
+        Self { repo, events }
+    }
+
+    /// Sweeps after every [`DomainEvent::EditsApplied`] published on the service's bus.
+    /// Failures are logged; the edit that triggered them is already saved.
+    pub fn subscribe(self: Arc<Self>) -> SubscriptionId {
+        let events = self.events.clone();
+        events.subscribe(move |event| {
+            let DomainEvent::EditsApplied { dataset_id } = event else {
+                return;
+            };
+            if let Err(err) = self.sweep(*dataset_id) {
+                tracing::warn!(error = %err, "股息入帳活存失敗");
+            }
+        })
+    }
+
+    /// Credits the dividends `dataset_id` received since the last sweep to the 資產總表
+    /// imported together with it. The first sweep of a dataset compares against its rows
+    /// before the latest save. 另存 copies are neither swept nor credited, as their rows
+    /// repeat those of the dataset they were saved from. Returns the dataset that was
+    /// rewritten, if any.
+    #[tracing::instrument(skip(self), err)]
+    pub fn sweep(&self, dataset_id: DatasetId) -> Result<Option<DatasetId>, RepoError> {
+        if !sweep_enabled(self.repo.load_setting(DIVIDEND_SWEEP_KEY)?.as_deref()) {
+            return Ok(None);
+        }
+        let datasets = self.repo.list_datasets(false)?;
+        let Some(dividends) = datasets.iter().find(|meta| meta.id == dataset_id) else {
+            return Ok(None);
+        };
+        if dividends.effective_kind() != DatasetKind::Holdings
+            || dividends.saved_as_copy_of().is_some()
+        {
+            return Ok(None);
+        }
+        let page = self.repo.load_tabular_data(dataset_id)?;
+        let Some(received) = received_by_owner(&page.columns, &page.rows) else {
+            return Ok(None);
+        };
+        let key = format!("{DIVIDEND_SWEEP_RECEIVED_KEY_PREFIX}{}", dataset_id.0);
+        let before = match self.repo.load_setting(&key)? {
+            Some(stored) => parse_received(&stored),
+            None => {
+                let changes = self.repo.load_saved_changes(dataset_id)?;
+                let rows = rows_before_save(&page.rows, &changes);
+                received_by_owner(&page.columns, &rows).unwrap_or_default()
+            }
+        };
+        let deltas = owner_deltas(&before, &received);
+        let assets = dividends
+            .source_path
+            .split_once('#')
+            .and_then(|(group, _)| {
+                datasets.iter().find(|meta| {
+                    in_group(meta, group)
+                        && meta.saved_as_copy_of().is_none()
+                        && meta.effective_kind() == DatasetKind::Assets
+                })
+            });
+        let mut rewritten = None;
+        if let Some(assets) = assets.filter(|_| !deltas.is_empty()) {
+            let assets_page = self.repo.load_tabular_data(assets.id)?;
+            let (updates, unmatched) =
+                cash_balance_updates(&assets_page.columns, &assets_page.rows, &deltas);
+            if !unmatched.is_empty() {
+                tracing::warn!(owners = ?unmatched, "資產總表中找不到所有權人的活存帳戶");
+            }
+            if !updates.is_empty() {
+                let staged_cells = updates
+                    .into_iter()
+                    .map(|(row_idx, col_idx, value)| {
+                        let column = assets_page.columns[col_idx].clone();
+                        (
+                            CellKey {
+                                row_idx,
+                                col_idx,
+                                column,
+                            },
+                            value,
+                        )
+                    })
+                    .collect::<HashMap<_, _>>();
+                self.repo.apply_edits(
+                    assets.id,
+                    StagedEdits {
+                        staged_cells,
+                        ..StagedEdits::default()
+                    },
+                )?;
+                rewritten = Some(assets.id);
+            }
+        }
+        self.repo.save_setting(&key, &format_received(&received))?;
+        if let Some(assets_id) = rewritten {
+            self.events.publish(DomainEvent::EditsApplied {
+                dataset_id: assets_id,
+            });
+        }
+        Ok(rewritten)
+    }
+}
@@ -0,0 +1,178 @@
+use std::sync::Arc;
+
+use crate::domain::entities::dataset::DatasetId;
+use crate::domain::entities::edit::StagedEdits;
+use crate::domain::entities::price_point::PricePoint;
+use crate::domain::events::{DomainEvent, EventBus};
+use crate::prices::{saved_prices, PRICE_COLUMN};
+use crate::usecase::ports::repo::{DatasetRepository, RepoError};
+use crate::usecase::ports::repo::{NewDatasetMeta, TabularData};
+use crate::usecase::services::read_only::ReadOnlyMode;
+
+#[allow(dead_code)]
+pub struct EditService {
+    repo: Arc<dyn DatasetRepository>,
+    events: EventBus,
+    read_only: ReadOnlyMode,
+}
+
+impl EditService {
+    pub fn new(repo: Arc<dyn DatasetRepository>) -> Self {
+        Self::with_events(repo, EventBus::new())
+    }
+
+    /// Publishes [`DomainEvent::EditsApplied`] and [`DomainEvent::DatasetDeleted`] on `events`.
+    pub fn with_events(repo: Arc<dyn DatasetRepository>, events: EventBus) -> Self {
+        Self {
+            repo,
+            events,
+            read_only: ReadOnlyMode::default(),
+        }
+    }
+
+    /// Refuses every operation with [`RepoError::ReadOnly`] while `read_only` is enabled.
+    pub fn with_read_only(mut self, read_only: ReadOnlyMode) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    fn ensure_writable(&self) -> Result<(), RepoError> {
+        if self.read_only.is_enabled() {
+            return Err(RepoError::ReadOnly);
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self, edits), err)]
+    pub fn apply_edits(&self, dataset_id: DatasetId, edits: StagedEdits) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        let may_set_prices = !edits.added_rows.is_empty()
+            || edits
+                .staged_cells
+                .keys()
+                .any(|key| key.column == PRICE_COLUMN);
+        self.repo.apply_edits(dataset_id, edits)?;
+        self.events
+            .publish(DomainEvent::EditsApplied { dataset_id });
+        if may_set_prices {
+            // The edit is saved either way; a price history gap is only logged.
+            if let Err(err) = self.record_saved_prices(dataset_id) {
+                tracing::warn!(error = %err, "記錄市價失敗");
+            }
+        }
+        Ok(())
+    }
+
+    /// Keeps the 市價 cells the last save of `dataset_id` changed in the price history.
+    fn record_saved_prices(&self, dataset_id: DatasetId) -> Result<(), RepoError> {
+        let changes = self.repo.load_saved_changes(dataset_id)?;
+        let page = self.repo.load_tabular_data(dataset_id)?;
+        let fetched_at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let prices = saved_prices(&page.columns, &page.rows, &changes, &fetched_at);
+        if prices.is_empty() {
+            return Ok(());
+        }
+        self.record_prices(dataset_id, prices)
+    }
+
+    #[tracing::instrument(skip(self, meta, data), fields(name = %meta.name), err)]
+    pub fn create_dataset(
+        &self,
+        meta: NewDatasetMeta,
+        data: TabularData,
+    ) -> Result<DatasetId, RepoError> {
+        self.ensure_writable()?;
+        self.repo.create_dataset(meta, data)
+    }
+
+    /// A complete copy of `source` as stored, settings included; used by 另存 before the
+    /// staged edits are written into the source.
+    #[tracing::instrument(skip(self, meta), fields(name = %meta.name), err)]
+    pub fn clone_dataset(
+        &self,
+        source: DatasetId,
+        meta: NewDatasetMeta,
+    ) -> Result<DatasetId, RepoError> {
+        self.ensure_writable()?;
+        self.repo.clone_dataset(source, meta)
+    }
+
+    /// Moves the dataset to the trash, where it stays until it is restored, deleted or
+    /// purged by [`Self::purge_expired_datasets`].
+    #[tracing::instrument(skip(self), err)]
+    pub fn soft_delete_dataset(&self, dataset_id: DatasetId) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.soft_delete_dataset(dataset_id)?;
+        self.events
+            .publish(DomainEvent::DatasetDeleted { dataset_id });
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self), err)]
+    pub fn purge_dataset(&self, dataset_id: DatasetId) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.purge_dataset(dataset_id)?;
+        self.events
+            .publish(DomainEvent::DatasetDeleted { dataset_id });
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self), err)]
+    pub fn restore_dataset(&self, dataset_id: DatasetId) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.restore_dataset(dataset_id)?;
+        self.events
+            .publish(DomainEvent::DatasetRestored { dataset_id });
+        Ok(())
+    }
+
+    /// Exempts a trashed dataset from [`Self::purge_expired_datasets`], or stops exempting it.
+    #[tracing::instrument(skip(self), err)]
+    pub fn set_keep_forever(
+        &self,
+        dataset_id: DatasetId,
+        keep_forever: bool,
+    ) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.set_keep_forever(dataset_id, keep_forever)
+    }
+
+    /// Permanently deletes the datasets in the trash for at least `retention_days` days,
+    /// except those kept forever, and returns them. A window of 0 days purges nothing.
+    #[tracing::instrument(skip(self), err)]
+    pub fn purge_expired_datasets(&self, retention_days: u32) -> Result<Vec<DatasetId>, RepoError> {
+        if retention_days == 0 {
+            return Ok(Vec::new());
+        }
+        self.ensure_writable()?;
+        let expired = self.repo.expired_datasets(retention_days)?;
+        for dataset_id in &expired {
+            self.purge_dataset(*dataset_id)?;
+        }
+        Ok(expired)
+    }
+
+    /// Keeps prices of the 代號 of `dataset_id` in the price history, as entered in its
+    /// 市價 column or fetched.
+    #[tracing::instrument(skip(self, prices), fields(count = prices.len()), err)]
+    pub fn record_prices(
+        &self,
+        dataset_id: DatasetId,
+        prices: Vec<PricePoint>,
+    ) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.record_prices(prices)?;
+        self.events
+            .publish(DomainEvent::PricesUpdated { dataset_id });
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self), err)]
+    pub fn hard_delete_dataset(&self, dataset_id: DatasetId) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.purge_dataset(dataset_id)?;
+        self.events
+            .publish(DomainEvent::DatasetDeleted { dataset_id });
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::domain::entities::dataset::PageQuery;
+use crate::infra::export::csv::{export_csv_with, CsvOptions};
+use crate::infra::export::xlsx::export_xlsx;
+use crate::usecase::ports::repo::DatasetRepository;
+
+/// A dataset's rows as exported: its name, the shown headers and their cells.
+struct ShownTable {
+    name: String,
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+/// Writes datasets out to files other programs open.
+pub struct ExportService {
+    repo: Arc<dyn DatasetRepository>,
+}
+
+impl ExportService {
+    pub fn new(repo: Arc<dyn DatasetRepository>) -> Self {
+        Self { repo }
+    }
+
+    /// Writes every row `query` matches, all pages in its sort order, to an XLSX workbook
+    /// at `path`, in a sheet named after the dataset. Only the columns `visibility` shows
+    /// are written, under their aliases; an empty map shows them all, like the table.
+    /// Returns the number of rows written.
+    pub fn export_xlsx(
+        &self,
+        query: PageQuery,
+        visibility: &BTreeMap<i64, bool>,
+        path: &Path,
+    ) -> Result<usize> {
+        let table = self.shown_table(query, visibility)?;
+        export_xlsx(path, &table.name, &table.headers, &table.rows)?;
+        Ok(table.rows.len())
+    }
+
+    /// The same rows and columns as [`ExportService::export_xlsx`], as a CSV file
+    /// separated and encoded as `options` say.
+    pub fn export_csv(
+        &self,
+        query: PageQuery,
+        visibility: &BTreeMap<i64, bool>,
+        options: &CsvOptions,
+        path: &Path,
+    ) -> Result<usize> {
+        let table = self.shown_table(query, visibility)?;
+        export_csv_with(path, &table.headers, &table.rows, options)?;
+        Ok(table.rows.len())
+    }
+
+    fn shown_table(
+        &self,
+        query: PageQuery,
+        visibility: &BTreeMap<i64, bool>,
+    ) -> Result<ShownTable> {
+        let dataset_id = query.dataset_id;
+        let name = self
+            .repo
+            .list_datasets(false)?
+            .into_iter()
+            .find(|dataset| dataset.id == dataset_id)
+            .map(|dataset| dataset.name)
+            .unwrap_or_default();
+        let aliases = self.repo.load_column_aliases(dataset_id)?;
+        let page = self.repo.query_page(PageQuery {
+            page: 0,
+            page_size: i64::MAX,
+            ..query
+        })?;
+        let shown: Vec<usize> = (0..page.columns.len())
+            .filter(|idx| {
+                visibility.is_empty() || visibility.get(&(*idx as i64)).copied().unwrap_or(true)
+            })
+            .collect();
+        let headers = shown
+            .iter()
+            .map(|idx| {
+                aliases
+                    .get(&(*idx as i64))
+                    .filter(|alias| !alias.trim().is_empty())
+                    .cloned()
+                    .unwrap_or_else(|| page.columns[*idx].clone())
+            })
+            .collect();
+        let rows = page
+            .rows
+            .iter()
+            .map(|row| {
+                shown
+                    .iter()
+                    .map(|idx| row.get(*idx).cloned().unwrap_or_default())
+                    .collect()
+            })
+            .collect();
+        Ok(ShownTable {
+            name,
+            headers,
+            rows,
+        })
+    }
+}
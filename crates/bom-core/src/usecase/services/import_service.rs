@@ -0,0 +1,285 @@
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use anyhow::Result;
+
+use crate::domain::entities::dataset::DatasetId;
+use crate::domain::entities::import_profile::ImportProfile;
+use crate::domain::entities::import_record::ImportEventKind;
+use crate::domain::events::{DomainEvent, EventBus};
+use crate::holdings::DuplicateCodePolicy;
+use crate::infra::import::csv::{import_csv_to_sqlite, CsvImporter};
+use crate::infra::import::mapped_csv::{find_matching_profile, MappedCsvImporter};
+use crate::infra::import::registry::ImporterRegistry;
+use crate::infra::import::xlsx::{import_xlsx_selected_sheets_to_sqlite, XlsxImporter};
+use crate::infra::import::{import_record, import_with_locale, prepare_for_storage};
+use crate::infra::sqlite::queries::{
+    load_import_profiles, load_source_sheets, record_import, replace_dataset_contents,
+};
+use crate::infra::sqlite::schema::init_db;
+use crate::numeric::NumberLocale;
+use crate::usecase::ports::importer::{ImportError, ImportValidation, Importer, ParsedDataset};
+use crate::usecase::services::number_locale::NumberLocaleSetting;
+use crate::usecase::services::read_only::ReadOnlyMode;
+use crate::ImportResult;
+
+#[allow(dead_code)]
+pub struct ImportService {
+    db_path: PathBuf,
+    registry: ImporterRegistry,
+    events: EventBus,
+    read_only: ReadOnlyMode,
+    number_locale: NumberLocaleSetting,
+}
+
+impl ImportService {
+    pub fn new(db_path: PathBuf) -> Self {
+        Self::with_registry(db_path, ImporterRegistry::with_defaults())
+    }
+
+    /// Uses `registry` instead of the built-in importers, e.g. to add a broker-specific CSV.
+    pub fn with_registry(db_path: PathBuf, registry: ImporterRegistry) -> Self {
+        Self {
+            db_path,
+            registry,
+            events: EventBus::new(),
+            read_only: ReadOnlyMode::default(),
+            number_locale: NumberLocaleSetting::default(),
+        }
+    }
+
+    /// Publishes [`DomainEvent::DatasetImported`] on `events` after each successful import.
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Refuses imports with [`ImportError::ReadOnly`] while `read_only` is enabled.
+    pub fn with_read_only(mut self, read_only: ReadOnlyMode) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Reads numbers in imported text files (CSV and the like) as written in
+    /// `number_locale`. XLSX cells carry typed numbers and are not affected.
+    pub fn with_number_locale(mut self, number_locale: NumberLocaleSetting) -> Self {
+        self.number_locale = number_locale;
+        self
+    }
+
+    pub fn registry(&self) -> &ImporterRegistry {
+        &self.registry
+    }
+
+    /// Files directly in `folder` that a registered importer accepts, sorted by name.
+    /// Hidden files and the `~$` lock files Excel leaves next to open workbooks are skipped.
+    pub fn importable_files(&self, folder: &Path) -> Result<Vec<PathBuf>, ImportError> {
+        let entries = std::fs::read_dir(folder)
+            .map_err(|err| ImportError::from_anyhow(err.into(), folder))?;
+        let mut files = Vec::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|err| ImportError::from_anyhow(err.into(), folder))?
+                .path();
+            let hidden = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_none_or(|name| name.starts_with('.') || name.starts_with("~$"));
+            if path.is_file() && !hidden && self.registry.find(&path).is_ok() {
+                files.push(path);
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    /// Imports `path` with the first registered importer that accepts it. A CSV whose
+    /// headers fit a saved import profile is read through that profile instead.
+    #[tracing::instrument(skip(self), err(Debug))]
+    pub fn import(&self, path: &Path) -> Result<Vec<ImportResult>, ImportError> {
+        if self.read_only.is_enabled() {
+            return Err(ImportError::ReadOnly);
+        }
+        if !path.exists() {
+            return Err(ImportError::FileNotFound(path.to_path_buf()));
+        }
+        let importer = self.registry.find(path)?;
+        if CsvImporter.matches(path) {
+            if let Some(profile) = self.matching_profile(path) {
+                return self.import_with_profile(path, profile);
+            }
+        }
+        self.run_import(importer, path)
+    }
+
+    /// Checks `path` before it is imported. Only BOM workbooks have anything to report:
+    /// 代號 that 持股明細 lists more than once.
+    pub fn validate(&self, path: &Path) -> Result<ImportValidation, ImportError> {
+        if !path.exists() {
+            return Err(ImportError::FileNotFound(path.to_path_buf()));
+        }
+        let importer = XlsxImporter::default();
+        if !importer.matches(path) {
+            return Ok(ImportValidation::default());
+        }
+        importer
+            .validate(path)
+            .map_err(|err| ImportError::from_anyhow(err, path))
+    }
+
+    /// Imports the BOM workbook at `path`, handling rows of 持股明細 that share a 代號
+    /// as `duplicate_codes` says.
+    #[tracing::instrument(skip(self), err(Debug))]
+    pub fn import_workbook(
+        &self,
+        path: &Path,
+        duplicate_codes: DuplicateCodePolicy,
+    ) -> Result<Vec<ImportResult>, ImportError> {
+        if self.read_only.is_enabled() {
+            return Err(ImportError::ReadOnly);
+        }
+        if !path.exists() {
+            return Err(ImportError::FileNotFound(path.to_path_buf()));
+        }
+        self.run_import(&XlsxImporter { duplicate_codes }, path)
+    }
+
+    /// Checks the sheets stored with dataset `dataset_id` before [`Self::retransform`]
+    /// runs them again, as [`Self::validate`] checks a file.
+    pub fn validate_sources(&self, dataset_id: DatasetId) -> Result<ImportValidation, ImportError> {
+        let (_, sheets) = self.source_sheets(dataset_id)?;
+        XlsxImporter::default()
+            .validate_sheets(&sheets)
+            .map_err(ImportError::Other)
+    }
+
+    /// Runs the workbook transform again on the sheets stored when dataset `dataset_id`
+    /// was imported and replaces the dataset's cells with the result, so changed transform
+    /// settings apply without the original file. Edits made since the import are lost.
+    /// The re-transform is added to the import history.
+    #[tracing::instrument(skip(self), err(Debug))]
+    pub fn retransform(
+        &self,
+        dataset_id: DatasetId,
+        duplicate_codes: DuplicateCodePolicy,
+    ) -> Result<ImportResult, ImportError> {
+        if self.read_only.is_enabled() {
+            return Err(ImportError::ReadOnly);
+        }
+        let started = Instant::now();
+        let (output, sheets) = self.source_sheets(dataset_id)?;
+        let mut datasets = XlsxImporter { duplicate_codes }
+            .transform(sheets)
+            .map_err(ImportError::Other)?;
+        datasets.retain(|dataset| dataset.name == output);
+        let types = prepare_for_storage(&mut datasets);
+        let (Some(dataset), Some(types)) = (datasets.first(), types.first()) else {
+            return Err(ImportError::MissingSheet(output));
+        };
+        replace_dataset_contents(&self.db_path, dataset_id.0, dataset, types)
+            .map_err(ImportError::Other)?;
+        tracing::info!(output = %output, rows = dataset.rows.len(), "transform re-run");
+        let result = ImportResult {
+            dataset_id: dataset_id.0,
+            row_count: dataset.rows.len() as i64,
+        };
+        let record = import_record(
+            ImportEventKind::Retransform,
+            &dataset.source_path,
+            String::new(),
+            &datasets[..1],
+            &[result],
+            started,
+        );
+        if let Err(err) = record_import(&self.db_path, &record) {
+            tracing::warn!(error = %err, "failed to record import history");
+        }
+        self.events
+            .publish(DomainEvent::EditsApplied { dataset_id });
+        Ok(result)
+    }
+
+    fn source_sheets(
+        &self,
+        dataset_id: DatasetId,
+    ) -> Result<(String, Vec<ParsedDataset>), ImportError> {
+        init_db(&self.db_path)
+            .and_then(|_| load_source_sheets(&self.db_path, dataset_id.0))
+            .map_err(ImportError::Other)?
+            .ok_or_else(|| {
+                ImportError::Other(anyhow::anyhow!(
+                    "此資料集沒有保存匯入時的工作表，請重新匯入檔案"
+                ))
+            })
+    }
+
+    /// The saved import profile that [`Self::import`] would apply to `path`, if any.
+    pub fn matching_profile(&self, path: &Path) -> Option<ImportProfile> {
+        if !CsvImporter.matches(path) {
+            return None;
+        }
+        let profiles = init_db(&self.db_path)
+            .and_then(|_| load_import_profiles(&self.db_path))
+            .inspect_err(|err| tracing::warn!(error = %err, "failed to load import profiles"))
+            .ok()?;
+        find_matching_profile(path, &profiles).cloned()
+    }
+
+    /// Imports the CSV at `path` through `profile`.
+    #[tracing::instrument(skip(self, profile), fields(profile = %profile.name), err(Debug))]
+    pub fn import_with_profile(
+        &self,
+        path: &Path,
+        profile: ImportProfile,
+    ) -> Result<Vec<ImportResult>, ImportError> {
+        if self.read_only.is_enabled() {
+            return Err(ImportError::ReadOnly);
+        }
+        if !path.exists() {
+            return Err(ImportError::FileNotFound(path.to_path_buf()));
+        }
+        self.run_import(&MappedCsvImporter { profile }, path)
+    }
+
+    fn run_import(
+        &self,
+        importer: &dyn Importer,
+        path: &Path,
+    ) -> Result<Vec<ImportResult>, ImportError> {
+        let locale = if XlsxImporter::default().matches(path) {
+            NumberLocale::default()
+        } else {
+            self.number_locale.get()
+        };
+        let imported = import_with_locale(importer, &self.db_path, path, locale)
+            .map_err(|err| ImportError::from_anyhow(err, path))?;
+        tracing::info!(
+            importer = importer.name(),
+            datasets = imported.len(),
+            rows = imported.iter().map(|item| item.row_count).sum::<i64>(),
+            "import finished"
+        );
+        self.events.publish(DomainEvent::DatasetImported {
+            dataset_ids: imported
+                .iter()
+                .map(|item| DatasetId(item.dataset_id))
+                .collect(),
+            source_path: path.to_path_buf(),
+        });
+        Ok(imported)
+    }
+
+    pub fn import_csv(&self, path: &Path) -> Result<ImportResult> {
+        if self.read_only.is_enabled() {
+            return Err(ImportError::ReadOnly.into());
+        }
+        import_csv_to_sqlite(&self.db_path, path)
+    }
+
+    pub fn import_xlsx(&self, path: &Path) -> Result<Vec<ImportResult>> {
+        if self.read_only.is_enabled() {
+            return Err(ImportError::ReadOnly.into());
+        }
+        import_xlsx_selected_sheets_to_sqlite(&self.db_path, path)
+    }
+}
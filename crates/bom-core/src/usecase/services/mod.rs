@@ -0,0 +1,8 @@
+pub mod cash_sweep_service;
+pub mod edit_service;
+pub mod export_service;
+pub mod import_service;
+pub mod number_locale;
+pub mod query_service;
+pub mod read_only;
+pub mod recalc_service;
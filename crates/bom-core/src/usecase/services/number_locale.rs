@@ -0,0 +1,26 @@
+use std::sync::{Arc, RwLock};
+
+use crate::numeric::NumberLocale;
+
+/// Shared number locale used to read imported text files and typed cell values. Clones
+/// share the same state, so changing it in settings affects every service holding it.
+#[derive(Clone, Debug, Default)]
+pub struct NumberLocaleSetting {
+    locale: Arc<RwLock<NumberLocale>>,
+}
+
+impl NumberLocaleSetting {
+    pub fn new(locale: NumberLocale) -> Self {
+        Self {
+            locale: Arc::new(RwLock::new(locale)),
+        }
+    }
+
+    pub fn get(&self) -> NumberLocale {
+        *self.locale.read().unwrap_or_else(|err| err.into_inner())
+    }
+
+    pub fn set(&self, locale: NumberLocale) {
+        *self.locale.write().unwrap_or_else(|err| err.into_inner()) = locale;
+    }
+}
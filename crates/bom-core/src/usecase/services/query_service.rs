@@ -0,0 +1,438 @@
+use std::sync::Arc;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use crate::diff::{diff_datasets, DatasetDiff, DIFF_KEY_COLUMN};
+use crate::domain::entities::cell_origin::CellOrigins;
+use crate::domain::entities::column_set::ColumnSet;
+use crate::domain::entities::computed_column::ComputedColumn;
+use crate::domain::entities::dataset::{
+    ColumnFormat, ColumnStats, ColumnType, DatasetDisplay, DatasetId, DatasetKind, PageQuery,
+    PageResult,
+};
+use crate::domain::entities::deposit_rate::DepositRate;
+use crate::domain::entities::edit::SavedChanges;
+use crate::domain::entities::import_profile::ImportProfile;
+use crate::domain::entities::import_record::ImportRecord;
+use crate::domain::entities::owner_share::{OwnerShare, OwnerSplits};
+use crate::domain::entities::price_point::PriceHistory;
+use crate::domain::entities::saved_report::SavedReport;
+use crate::holdings::{builtin_column_set, validate_column_set};
+use crate::owner_split::validate_owner_shares;
+use crate::report::{StoredSummaryReport, SummaryReport};
+use crate::usecase::ports::repo::{
+    DatasetContext, DatasetMeta, DatasetRepository, RepoError, TabularData,
+};
+use crate::usecase::services::read_only::ReadOnlyMode;
+
+#[allow(dead_code)]
+pub struct QueryService {
+    repo: Arc<dyn DatasetRepository>,
+    read_only: ReadOnlyMode,
+}
+
+impl QueryService {
+    pub fn new(repo: Arc<dyn DatasetRepository>) -> Self {
+        Self {
+            repo,
+            read_only: ReadOnlyMode::default(),
+        }
+    }
+
+    /// Refuses every change to the database with [`RepoError::ReadOnly`] while
+    /// `read_only` is enabled, view preferences and settings included.
+    pub fn with_read_only(mut self, read_only: ReadOnlyMode) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    fn ensure_writable(&self) -> Result<(), RepoError> {
+        if self.read_only.is_enabled() {
+            return Err(RepoError::ReadOnly);
+        }
+        Ok(())
+    }
+
+    pub fn list_datasets(&self, include_deleted: bool) -> Result<Vec<DatasetMeta>, RepoError> {
+        self.repo.list_datasets(include_deleted)
+    }
+
+    #[tracing::instrument(level = "debug", skip(self), err)]
+    pub fn query_page(&self, query: PageQuery) -> Result<PageResult, RepoError> {
+        self.repo.query_page(query)
+    }
+
+    /// The dataset as stored, computed columns left out; what a dataset bundle carries.
+    pub fn load_tabular_data(&self, dataset_id: DatasetId) -> Result<TabularData, RepoError> {
+        self.repo.load_tabular_data(dataset_id)
+    }
+
+    /// Opens a dataset in one repository call: the dataset list, the page `select` asks for
+    /// and that dataset's column visibility, plus the holdings flags. Used at startup and
+    /// on tab switches, which would otherwise issue each of these separately.
+    #[tracing::instrument(level = "debug", skip(self, select), err)]
+    pub fn load_dataset_context(
+        &self,
+        include_deleted: bool,
+        select: impl Fn(&[DatasetMeta]) -> Option<PageQuery>,
+    ) -> Result<DatasetContext, RepoError> {
+        self.repo.load_dataset_context(include_deleted, &select)
+    }
+
+    pub fn load_column_visibility(
+        &self,
+        dataset_id: DatasetId,
+    ) -> Result<BTreeMap<i64, bool>, RepoError> {
+        self.repo.load_column_visibility(dataset_id)
+    }
+
+    pub fn upsert_column_visibility(
+        &self,
+        dataset_id: DatasetId,
+        visibility: BTreeMap<i64, bool>,
+    ) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.upsert_column_visibility(dataset_id, visibility)
+    }
+
+    pub fn load_visibility_presets(
+        &self,
+        dataset_id: DatasetId,
+    ) -> Result<BTreeMap<String, BTreeMap<i64, bool>>, RepoError> {
+        self.repo.load_visibility_presets(dataset_id)
+    }
+
+    pub fn save_visibility_preset(
+        &self,
+        dataset_id: DatasetId,
+        name: String,
+        visibility: BTreeMap<i64, bool>,
+    ) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo
+            .save_visibility_preset(dataset_id, name, visibility)
+    }
+
+    pub fn delete_visibility_preset(
+        &self,
+        dataset_id: DatasetId,
+        name: String,
+    ) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.delete_visibility_preset(dataset_id, name)
+    }
+
+    pub fn load_column_aliases(
+        &self,
+        dataset_id: DatasetId,
+    ) -> Result<BTreeMap<i64, String>, RepoError> {
+        self.repo.load_column_aliases(dataset_id)
+    }
+
+    pub fn upsert_column_aliases(
+        &self,
+        dataset_id: DatasetId,
+        aliases: BTreeMap<i64, String>,
+    ) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.upsert_column_aliases(dataset_id, aliases)
+    }
+
+    pub fn load_column_formats(
+        &self,
+        dataset_id: DatasetId,
+    ) -> Result<BTreeMap<i64, ColumnFormat>, RepoError> {
+        self.repo.load_column_formats(dataset_id)
+    }
+
+    pub fn upsert_column_formats(
+        &self,
+        dataset_id: DatasetId,
+        formats: BTreeMap<i64, ColumnFormat>,
+    ) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.upsert_column_formats(dataset_id, formats)
+    }
+
+    /// Cells the last save of the dataset changed, for highlighting them.
+    pub fn load_saved_changes(&self, dataset_id: DatasetId) -> Result<SavedChanges, RepoError> {
+        self.repo.load_saved_changes(dataset_id)
+    }
+
+    /// Where the dataset's imported cells came from, with their values as the file had
+    /// them.
+    pub fn load_cell_origins(&self, dataset_id: DatasetId) -> Result<CellOrigins, RepoError> {
+        self.repo.load_cell_origins(dataset_id)
+    }
+
+    /// Currency and unit the dataset's amounts are shown in.
+    pub fn load_dataset_display(&self, dataset_id: DatasetId) -> Result<DatasetDisplay, RepoError> {
+        self.repo.load_dataset_display(dataset_id)
+    }
+
+    /// Currency and unit to show the dataset's amounts in.
+    pub fn save_dataset_display(
+        &self,
+        dataset_id: DatasetId,
+        display: DatasetDisplay,
+    ) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.save_dataset_display(dataset_id, display)
+    }
+
+    pub fn load_page_size(&self, dataset_id: DatasetId) -> Result<Option<i64>, RepoError> {
+        self.repo.load_page_size(dataset_id)
+    }
+
+    pub fn save_page_size(
+        &self,
+        dataset_id: DatasetId,
+        page_size: Option<i64>,
+    ) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.save_page_size(dataset_id, page_size)
+    }
+
+    /// Stored column types; columns missing here are typed from their values.
+    pub fn load_column_types(
+        &self,
+        dataset_id: DatasetId,
+    ) -> Result<BTreeMap<i64, ColumnType>, RepoError> {
+        self.repo.load_column_types(dataset_id)
+    }
+
+    pub fn upsert_column_types(
+        &self,
+        dataset_id: DatasetId,
+        types: BTreeMap<i64, ColumnType>,
+    ) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.upsert_column_types(dataset_id, types)
+    }
+
+    pub fn load_holdings_flags(&self) -> Result<BTreeMap<i64, bool>, RepoError> {
+        self.repo.load_holdings_flags()
+    }
+
+    #[allow(dead_code)]
+    pub fn upsert_holdings_flag(
+        &self,
+        dataset_id: DatasetId,
+        is_holdings: bool,
+    ) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.upsert_holdings_flag(dataset_id, is_holdings)
+    }
+
+    #[tracing::instrument(skip(self), err)]
+    pub fn rename_dataset(&self, dataset_id: DatasetId, name: String) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.rename_dataset(dataset_id, name)
+    }
+
+    /// Changes which tab buttons and editable columns `dataset_id` gets.
+    #[tracing::instrument(skip(self), err)]
+    pub fn set_dataset_kind(
+        &self,
+        dataset_id: DatasetId,
+        kind: DatasetKind,
+    ) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.set_dataset_kind(dataset_id, kind)
+    }
+
+    #[tracing::instrument(skip(self), err)]
+    pub fn backup_database(&self, dest_path: &Path) -> Result<(), RepoError> {
+        self.repo.backup_database(dest_path)
+    }
+
+    pub fn load_setting(&self, key: &str) -> Result<Option<String>, RepoError> {
+        self.repo.load_setting(key)
+    }
+
+    pub fn save_setting(&self, key: &str, value: &str) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.save_setting(key, value)
+    }
+
+    pub fn load_import_profiles(&self) -> Result<Vec<ImportProfile>, RepoError> {
+        self.repo.load_import_profiles()
+    }
+
+    pub fn save_import_profile(&self, profile: ImportProfile) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.save_import_profile(profile)
+    }
+
+    pub fn delete_import_profile(&self, name: String) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.delete_import_profile(name)
+    }
+
+    /// Required and derived columns of `kind`: the set saved in 設定, or the built-in one.
+    pub fn load_column_set(&self, kind: DatasetKind) -> Result<ColumnSet, RepoError> {
+        Ok(self
+            .repo
+            .load_column_set(kind)?
+            .unwrap_or_else(|| builtin_column_set(kind)))
+    }
+
+    /// Checked with [`validate_column_set`] first.
+    pub fn save_column_set(&self, kind: DatasetKind, set: ColumnSet) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        validate_column_set(kind, &set).map_err(|err| RepoError::Message(err.to_string()))?;
+        self.repo.save_column_set(kind, set)
+    }
+
+    /// Goes back to the built-in column set of `kind`.
+    pub fn reset_column_set(&self, kind: DatasetKind) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.delete_column_set(kind)
+    }
+
+    pub fn load_saved_reports(&self) -> Result<Vec<SavedReport>, RepoError> {
+        self.repo.load_saved_reports()
+    }
+
+    pub fn save_saved_report(&self, report: SavedReport) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.save_saved_report(report)
+    }
+
+    pub fn delete_saved_report(&self, name: String) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.delete_saved_report(name)
+    }
+
+    pub fn load_deposit_rates(&self) -> Result<Vec<DepositRate>, RepoError> {
+        self.repo.load_deposit_rates()
+    }
+
+    /// Replaces the 定存 rate table.
+    pub fn save_deposit_rates(&self, rates: Vec<DepositRate>) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.save_deposit_rates(rates)
+    }
+
+    /// Every recorded import and re-transform, newest first.
+    pub fn load_import_history(&self) -> Result<Vec<ImportRecord>, RepoError> {
+        self.repo.load_import_history()
+    }
+
+    /// Stored prices of `codes`, oldest first; codes never priced are left out.
+    pub fn load_price_history(&self, codes: Vec<String>) -> Result<PriceHistory, RepoError> {
+        self.repo.load_price_history(codes)
+    }
+
+    pub fn load_acknowledged_alerts(&self) -> Result<BTreeSet<String>, RepoError> {
+        self.repo.load_acknowledged_alerts()
+    }
+
+    /// Marks alerts as seen.
+    pub fn acknowledge_alerts(&self, keys: Vec<String>) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.acknowledge_alerts(keys)
+    }
+
+    pub fn column_value_counts(
+        &self,
+        dataset_id: DatasetId,
+        col_idx: i64,
+    ) -> Result<Vec<(String, i64)>, RepoError> {
+        self.repo.column_value_counts(dataset_id, col_idx)
+    }
+
+    /// Rows added, removed and changed from `before` to `after`, matched on 代號. Both are
+    /// read whole, computed columns included.
+    #[tracing::instrument(skip(self), err)]
+    pub fn dataset_diff(
+        &self,
+        before: DatasetId,
+        after: DatasetId,
+    ) -> Result<DatasetDiff, RepoError> {
+        let load = |dataset_id| {
+            self.repo.query_page(PageQuery {
+                dataset_id,
+                page: 0,
+                page_size: i64::MAX,
+                global_search: String::new(),
+                column_filter: None,
+                value_filters: Vec::new(),
+                column_conditions: Vec::new(),
+                sort: None,
+            })
+        };
+        let (before, after) = (load(before)?, load(after)?);
+        diff_datasets(
+            DIFF_KEY_COLUMN,
+            (&before.columns, &before.rows),
+            (&after.columns, &after.rows),
+        )
+        .map_err(|err| RepoError::Message(err.to_string()))
+    }
+
+    pub fn empty_columns(&self, query: PageQuery) -> Result<BTreeSet<i64>, RepoError> {
+        self.repo.empty_columns(query)
+    }
+
+    pub fn column_stats(&self, query: PageQuery) -> Result<BTreeMap<i64, ColumnStats>, RepoError> {
+        self.repo.column_stats(query)
+    }
+
+    pub fn load_computed_columns(
+        &self,
+        dataset_id: DatasetId,
+    ) -> Result<Vec<ComputedColumn>, RepoError> {
+        self.repo.load_computed_columns(dataset_id)
+    }
+
+    pub fn save_computed_columns(
+        &self,
+        dataset_id: DatasetId,
+        columns: Vec<ComputedColumn>,
+    ) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        self.repo.save_computed_columns(dataset_id, columns)
+    }
+
+    pub fn load_owner_splits(&self, dataset_id: DatasetId) -> Result<OwnerSplits, RepoError> {
+        self.repo.load_owner_splits(dataset_id)
+    }
+
+    /// Splits row `row_idx` of `dataset_id` between co-owners; empty `shares` undoes it.
+    /// Shares are checked with [`validate_owner_shares`] first.
+    #[tracing::instrument(skip(self, shares), err)]
+    pub fn save_owner_shares(
+        &self,
+        dataset_id: DatasetId,
+        row_idx: usize,
+        shares: Vec<OwnerShare>,
+    ) -> Result<(), RepoError> {
+        self.ensure_writable()?;
+        validate_owner_shares(&shares).map_err(|err| RepoError::Message(err.to_string()))?;
+        self.repo.save_owner_shares(dataset_id, row_idx, shares)
+    }
+
+    pub fn dataset_revision(&self, dataset_id: DatasetId) -> Result<i64, RepoError> {
+        self.repo.dataset_revision(dataset_id)
+    }
+
+    /// Keeps `report` in the dataset's summary history.
+    pub fn save_summary_report(
+        &self,
+        dataset_id: DatasetId,
+        report: &SummaryReport,
+    ) -> Result<StoredSummaryReport, RepoError> {
+        self.ensure_writable()?;
+        self.repo.save_summary_report(dataset_id, report)
+    }
+
+    /// Newest first.
+    pub fn load_summary_reports(
+        &self,
+        dataset_id: DatasetId,
+    ) -> Result<Vec<StoredSummaryReport>, RepoError> {
+        self.repo.load_summary_reports(dataset_id)
+    }
+}
@@ -0,0 +1,25 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Shared switch that makes the edit, import and rename operations of every service
+/// holding it fail with a read-only error. Clones share the same state.
+#[derive(Clone, Debug, Default)]
+pub struct ReadOnlyMode {
+    enabled: Arc<AtomicBool>,
+}
+
+impl ReadOnlyMode {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled: Arc::new(AtomicBool::new(enabled)),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::dividend_year::live_datasets;
+use crate::domain::entities::dataset::{DatasetId, DatasetKind};
+use crate::domain::entities::edit::{CellKey, StagedEdits};
+use crate::domain::events::{DomainEvent, EventBus, SubscriptionId};
+use crate::holdings::{holdings_total_net, investment_balance_updates};
+use crate::usecase::ports::repo::{DatasetMeta, DatasetRepository, RepoError};
+
+/// Keeps totals derived from one dataset in step with the datasets that show them. The
+/// import fills the investment 餘額 of 資產總表 from the 淨值 of 持股股息總表; after an edit
+/// to the holdings the same rows are rewritten, so the two sheets of a workbook agree.
+pub struct RecalcService {
+    repo: Arc<dyn DatasetRepository>,
+    events: EventBus,
+}
+
+impl RecalcService {
+    /// Publishes [`DomainEvent::EditsApplied`] for every dataset it rewrites.
+    pub fn new(repo: Arc<dyn DatasetRepository>, events: EventBus) -> Self {
+        Self { repo, events }
+    }
+
+    /// Recalculates after every [`DomainEvent::EditsApplied`] published on the service's
+    /// bus. Failures are logged; the edit that triggered them is already saved.
+    pub fn subscribe(self: Arc<Self>) -> SubscriptionId {
+        let events = self.events.clone();
+        events.subscribe(move |event| {
+            let DomainEvent::EditsApplied { dataset_id } = event else {
+                return;
+            };
+            if let Err(err) = self.recalculate(*dataset_id) {
+                tracing::warn!(error = %err, "更新資產總表投資餘額失敗");
+            }
+        })
+    }
+
+    /// Updates the 資產總表 imported together with `dataset_id` when that is a 持股 dataset.
+    /// 另存 copies and closed-year archives take no part on either side. Returns the dataset
+    /// that was rewritten, if any cell changed.
+    #[tracing::instrument(skip(self), err)]
+    pub fn recalculate(&self, dataset_id: DatasetId) -> Result<Option<DatasetId>, RepoError> {
+        let datasets = live_datasets(&self.repo.list_datasets(false)?);
+        let Some(holdings) = datasets.iter().find(|meta| meta.id == dataset_id) else {
+            return Ok(None);
+        };
+        if holdings.effective_kind() != DatasetKind::Holdings {
+            return Ok(None);
+        }
+        let Some((group, _)) = holdings.source_path.split_once('#') else {
+            return Ok(None);
+        };
+        let Some(assets) = datasets
+            .iter()
+            .find(|meta| in_group(meta, group) && meta.effective_kind() == DatasetKind::Assets)
+        else {
+            return Ok(None);
+        };
+
+        let holdings_page = self.repo.load_tabular_data(holdings.id)?;
+        let Some(total_net) = holdings_total_net(&holdings_page.columns, &holdings_page.rows)
+        else {
+            return Ok(None);
+        };
+        let assets_page = self.repo.load_tabular_data(assets.id)?;
+        let staged_cells =
+            investment_balance_updates(&assets_page.columns, &assets_page.rows, total_net)
+                .into_iter()
+                .map(|(row_idx, col_idx, value)| {
+                    let column = assets_page.columns[col_idx].clone();
+                    (
+                        CellKey {
+                            row_idx,
+                            col_idx,
+                            column,
+                        },
+                        value,
+                    )
+                })
+                .collect::<HashMap<_, _>>();
+        if staged_cells.is_empty() {
+            return Ok(None);
+        }
+
+        self.repo.apply_edits(
+            assets.id,
+            StagedEdits {
+                staged_cells,
+                ..StagedEdits::default()
+            },
+        )?;
+        self.events.publish(DomainEvent::EditsApplied {
+            dataset_id: assets.id,
+        });
+        Ok(Some(assets.id))
+    }
+}
+
+/// Whether `meta` came from the same workbook as the datasets of `group`.
+pub(crate) fn in_group(meta: &DatasetMeta, group: &str) -> bool {
+    meta.source_path
+        .split_once('#')
+        .is_some_and(|(prefix, _)| prefix == group)
+}
@@ -0,0 +1,225 @@
+//! Two summaries of the portfolio side by side, typically this year's 持股股息總表 against
+//! last year's or against a snapshot from the summary history: 總成本, 淨值, dividends
+//! received and the yield they make on the cost, with the change between them.
+
+use anyhow::{anyhow, Result};
+
+use crate::domain::entities::dataset::{DatasetId, DatasetKind};
+use crate::numeric::{format_f64, format_percent, parse_numeric_value};
+use crate::report::{dataset_summary_report, SummaryReport};
+use crate::usecase::services::query_service::QueryService;
+
+/// Summary totals read as the year's dividends, in order of preference.
+const DIVIDEND_TOTALS: [&str; 2] = ["今年度累積", "已收配息"];
+
+/// Where one side of a comparison comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SummarySource {
+    /// The dataset as it is stored now.
+    Dataset(DatasetId),
+    /// An entry of the dataset's summary history.
+    Snapshot { dataset_id: DatasetId, id: i64 },
+}
+
+impl SummarySource {
+    /// Stable text for a `<select>` value: `d:{dataset}` or `s:{dataset}:{snapshot}`.
+    pub fn key(self) -> String {
+        match self {
+            SummarySource::Dataset(dataset_id) => format!("d:{}", dataset_id.0),
+            SummarySource::Snapshot { dataset_id, id } => format!("s:{}:{id}", dataset_id.0),
+        }
+    }
+
+    pub fn parse(key: &str) -> Option<Self> {
+        let mut parts = key.split(':');
+        let source = match (parts.next()?, parts.next()?.parse().ok()?, parts.next()) {
+            ("d", dataset_id, None) => SummarySource::Dataset(DatasetId(dataset_id)),
+            ("s", dataset_id, Some(id)) => SummarySource::Snapshot {
+                dataset_id: DatasetId(dataset_id),
+                id: id.parse().ok()?,
+            },
+            _ => return None,
+        };
+        parts.next().is_none().then_some(source)
+    }
+}
+
+/// A [`SummarySource`] with the name it is listed under.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceOption {
+    pub source: SummarySource,
+    pub label: String,
+}
+
+/// What is compared, in the order the table lists it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComparisonMetric {
+    TotalCost,
+    NetValue,
+    Dividends,
+    /// Dividends over 總成本.
+    Yield,
+}
+
+impl ComparisonMetric {
+    pub const ALL: [ComparisonMetric; 4] = [
+        ComparisonMetric::TotalCost,
+        ComparisonMetric::NetValue,
+        ComparisonMetric::Dividends,
+        ComparisonMetric::Yield,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ComparisonMetric::TotalCost => "總成本",
+            ComparisonMetric::NetValue => "淨值",
+            ComparisonMetric::Dividends => "已收股息",
+            ComparisonMetric::Yield => "殖利率",
+        }
+    }
+
+    fn value(self, report: &SummaryReport) -> Option<f64> {
+        let total = |label: &str| {
+            report
+                .totals
+                .iter()
+                .find(|entry| entry.label == label)
+                .and_then(|entry| parse_numeric_value(&entry.value))
+        };
+        match self {
+            ComparisonMetric::TotalCost => total("總成本"),
+            ComparisonMetric::NetValue => total("淨值"),
+            ComparisonMetric::Dividends => DIVIDEND_TOTALS.into_iter().find_map(total),
+            ComparisonMetric::Yield => {
+                let cost = ComparisonMetric::TotalCost.value(report)?;
+                let dividends = ComparisonMetric::Dividends.value(report)?;
+                (cost != 0.0).then(|| dividends / cost)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComparisonRow {
+    pub metric: ComparisonMetric,
+    pub before: Option<f64>,
+    pub after: Option<f64>,
+}
+
+impl ComparisonRow {
+    pub fn delta(&self) -> Option<f64> {
+        Some(self.after? - self.before?)
+    }
+
+    /// The delta as a ratio of the earlier value; not given for the yield, whose delta is
+    /// already in percentage points.
+    pub fn change_ratio(&self) -> Option<f64> {
+        let before = self.before.filter(|before| *before != 0.0)?;
+        if self.metric == ComparisonMetric::Yield {
+            return None;
+        }
+        Some(self.delta()? / before.abs())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct YearComparison {
+    pub before_label: String,
+    pub after_label: String,
+    pub rows: Vec<ComparisonRow>,
+}
+
+/// The holdings datasets, each followed by the entries of its summary history, newest
+/// first.
+pub fn summary_sources(query: &QueryService) -> Result<Vec<SourceOption>> {
+    let mut options = Vec::new();
+    for dataset in query.list_datasets(false)? {
+        if dataset.effective_kind() != DatasetKind::Holdings {
+            continue;
+        }
+        options.push(SourceOption {
+            source: SummarySource::Dataset(dataset.id),
+            label: dataset.name.clone(),
+        });
+        for stored in query.load_summary_reports(dataset.id)? {
+            options.push(SourceOption {
+                source: SummarySource::Snapshot {
+                    dataset_id: dataset.id,
+                    id: stored.id,
+                },
+                label: format!("{}（{}）", dataset.name, stored.created_at),
+            });
+        }
+    }
+    Ok(options)
+}
+
+/// The summary `source` stands for: a dataset's current summary, computed from its rows,
+/// or a stored one as it was saved. Only its totals are compared, so owners are left in
+/// the order found.
+pub fn load_summary(query: &QueryService, source: SummarySource) -> Result<SummaryReport> {
+    match source {
+        SummarySource::Dataset(dataset_id) => dataset_summary_report(query, dataset_id, &[]),
+        SummarySource::Snapshot { dataset_id, id } => query
+            .load_summary_reports(dataset_id)?
+            .into_iter()
+            .find(|stored| stored.id == id)
+            .map(|stored| stored.report)
+            .ok_or_else(|| anyhow!("找不到總結紀錄 {id}")),
+    }
+}
+
+pub fn compare_summaries(
+    before_label: &str,
+    before: &SummaryReport,
+    after_label: &str,
+    after: &SummaryReport,
+) -> YearComparison {
+    YearComparison {
+        before_label: before_label.to_string(),
+        after_label: after_label.to_string(),
+        rows: ComparisonMetric::ALL
+            .into_iter()
+            .map(|metric| ComparisonRow {
+                metric,
+                before: metric.value(before),
+                after: metric.value(after),
+            })
+            .collect(),
+    }
+}
+
+/// The comparison as a table for display and export: 項目, both sides, 增減 and 增減幅度.
+/// Amounts are plain numbers; the yield and the change are percentages. Missing values
+/// are left empty.
+pub fn year_comparison_table(comparison: &YearComparison) -> (Vec<String>, Vec<Vec<String>>) {
+    let headers = vec![
+        "項目".to_string(),
+        comparison.before_label.clone(),
+        comparison.after_label.clone(),
+        "增減".to_string(),
+        "增減幅度".to_string(),
+    ];
+    let rows = comparison
+        .rows
+        .iter()
+        .map(|row| {
+            let format = |value: Option<f64>| {
+                value
+                    .map(|value| match row.metric {
+                        ComparisonMetric::Yield => format_percent(value),
+                        _ => format_f64(value),
+                    })
+                    .unwrap_or_default()
+            };
+            vec![
+                row.metric.label().to_string(),
+                format(row.before),
+                format(row.after),
+                format(row.delta()),
+                row.change_ratio().map(format_percent).unwrap_or_default(),
+            ]
+        })
+        .collect();
+    (headers, rows)
+}
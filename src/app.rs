@@ -1,272 +1,770 @@
-use std::cell::RefCell;
-use std::collections::BTreeMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
 
-use anyhow::anyhow;
+use anyhow::Context;
+use chrono::Datelike;
 use dioxus::prelude::*;
+use futures_util::StreamExt;
 use rfd::{FileDialog, MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
 
-use crate::domain::entities::dataset::{DatasetId, PageQuery};
-use crate::domain::entities::edit::{CellKey, StagedEdits};
-use crate::infra::sqlite::repo::SqliteRepo;
+use crate::bundle::BUNDLE_EXTENSION;
+use crate::domain::entities::cell_origin::CellOrigins;
+use crate::domain::entities::computed_column::ComputedColumn;
+use crate::domain::entities::dataset::{
+    ColumnFormat, ColumnStats, ColumnType, DatasetDisplay, DatasetId, DatasetKind,
+};
+use crate::domain::entities::edit::{CellKey, SavedChanges};
+use crate::domain::entities::import_profile::ImportProfile;
+use crate::domain::entities::import_record::ImportRecord;
+use crate::domain::entities::owner_share::OwnerShare;
+use crate::domain::entities::price_point::PriceHistory;
+use crate::domain::entities::saved_report::SavedReport;
+use crate::domain::events::DomainEvent;
+use crate::infra::export::csv::{export_csv, CsvOptions};
+use crate::infra::remote_backup::RemoteBackupConfig;
+use crate::owner_split::OWNER_COLUMN;
 use crate::platform::desktop::blocking::run_blocking;
-use crate::ui::state::app_state::AppState;
-use crate::usecase::ports::repo::{DatasetRepository, NewDatasetMeta, TabularData};
-use crate::usecase::services::edit_service::EditService;
-use crate::usecase::services::import_service::ImportService;
-use crate::usecase::services::query_service::QueryService;
+use crate::platform::desktop::close_guard::{current_window_id, use_close_guard};
+use crate::platform::desktop::menu::{
+    set_recent_imports_menu, set_saved_reports_menu, MenuCommand,
+};
+use crate::platform::desktop::notify::notify_if_unfocused;
+use crate::platform::desktop::paths::{log_file_path, open_with_system, report_scripts_dir};
+use crate::platform::desktop::single_instance::{Handoff, InstanceListener};
+use crate::report_script::{run_report_script, ReportDefinition};
+use crate::ui::components::account_form::AccountForm;
+use crate::ui::components::app_lock::{AppLockSettings, LockScreen};
+use crate::ui::components::balance_update::BalanceUpdateDialog;
+use crate::ui::components::batch_import::BatchImportDialog;
+use crate::ui::components::chart::{ChartPanel, Sparkline};
+use crate::ui::components::column_format::ColumnFormatDialog;
+use crate::ui::components::command_palette::{CommandPalette, PaletteEntry};
+use crate::ui::components::computed_columns::ComputedColumnsDialog;
+use crate::ui::components::dataset_manager::DatasetManager;
+use crate::ui::components::dataset_picker::DatasetPicker;
+use crate::ui::components::dividend_receipts::DividendReceiptsDialog;
+use crate::ui::components::dropdown::{ColumnVisibilityDropdown, DropdownId, DropdownOption};
+use crate::ui::components::error_dialog::ErrorDialog;
+use crate::ui::components::filter_bar::{FilterBar, SearchHistoryAction};
+use crate::ui::components::formula_bar::{FormulaBar, FormulaCell};
+use crate::ui::components::import_mapping::ImportMappingDialog;
+use crate::ui::components::import_review::DuplicateCodesDialog;
+use crate::ui::components::modal::{ModalDialog, FOCUS_STYLES};
+use crate::ui::components::owners::DividendOwnersDialog;
+use crate::ui::components::quick_filters::{QuickFilterChips, QuickFilterColumn};
+use crate::ui::components::remote_backup::RemoteBackupSettings;
+use crate::ui::components::report_builder::ReportBuilderDialog;
+use crate::ui::components::row_detail::{RowDetailField, RowDetailPanel};
+use crate::ui::components::save_prompt::{SaveAsPrompt, SavePrompt};
+use crate::ui::components::status_bar::StatusBar;
+use crate::ui::components::summary_dialog::SummaryDialog;
+use crate::ui::components::sync_folder::SyncFolderNotice;
+use crate::ui::components::table_image::{export_table_image, table_image};
+use crate::ui::components::toolbar::Toolbar;
+use crate::ui::components::welcome::WelcomeScreen;
+use crate::ui::pages::alerts::AlertsPage;
+use crate::ui::pages::dashboard::DashboardPage;
+use crate::ui::pages::dataset_manager::DatasetManagerPage;
+use crate::ui::pages::holding::HoldingPage;
+use crate::ui::pages::nav::NavBar;
+use crate::ui::pages::reports::ReportsPage;
+use crate::ui::pages::retirement::RetirementPage;
+use crate::ui::pages::settings::SettingsPage;
+use crate::ui::state::app_lock::AppLockState;
+use crate::ui::state::app_state::{
+    AppState, BusyKind, ColumnSetState, MenuCommandState, ReadOnlyState, Route, RouteState,
+    SplitViewState,
+};
+use crate::ui::state::controller::{WorkspaceController, WorkspacePlace};
+use crate::ui::state::errors::report_error;
+use crate::ui::state::services::AppServices;
+use crate::ui::state::session::{load_session, save_session, UiSession};
+use crate::ui::state::sync_folder::enter_sync_folder_mode;
+use crate::usecase::ports::repo::{DatasetMeta, DatasetRepository};
 use crate::{
-    apply_column_visibility, build_dataset_groups, choose_default_dataset_id,
-    choose_next_dataset_after_delete, column_alignment, compute_summary_report, dataset_tab_kind,
-    default_dataset_name_mmdd, default_db_path, editable_columns_for_assets,
-    editable_columns_for_holdings, format_cell_value, is_holdings_table,
-    normalize_column_visibility, parse_numeric_value, reload_page_data_usecase,
-    required_columns_for_holdings, root_container_style_for_scroll,
+    apply_column_visibility, blank_holdings_dataset, build_dataset_groups, build_page_query,
+    builtin_visibility_presets, cached_summary_report, cell_raw_value, cell_tooltip,
+    choose_default_dataset_id, column_display_name, column_raw_text, column_stats_tooltip,
+    column_type_at, default_dataset_name_mmdd, default_db_path, default_webview_data_dir,
+    display_export_table, display_header, format_display_cell_value, format_number_with_commas,
+    format_page, group_rows, group_subtotals, hide_empty_columns, is_holdings_table, load_all_rows,
+    load_snapshot_schedule, load_trash_retention_days, modified_rows, normalize_column_visibility,
+    order_owner_totals, origin_tooltip, page_alignments, page_count, pane_element_id,
+    parse_recent_imports, pending_change_count, push_search_history, reload_page_data_usecase,
+    resolve_column_types, root_container_style_for_scroll, run_scheduled_snapshots,
+    selection_stats, step_modified_row, stored_cell_edit, summary_report_table,
     table_container_style_for_scroll, table_header_cell_style, table_overflow_style_for_scroll,
-    table_scroll_mode,
-    validate_required_holdings_row, DatasetTabKind, PendingAction, QueryOptions, SummaryReport,
-    NONE_OPTION_VALUE, PAGE_SIZE,
+    table_scroll_mode, toggle_search_history_pin, validate_cell_edit,
+    validate_required_holdings_row, PendingAction, QueryOptions, SearchHistoryEntry,
+    StoredSummaryReport, SummaryReport, SummarySections, DEFAULT_TRASH_RETENTION_DAYS,
+    NONE_OPTION_VALUE, PAGE_SIZE, QUICK_FILTER_COLUMNS, RECENT_IMPORTS_KEY, THEME_KEY,
 };
+use bom_core::assets::{typed_balance_rows, AssetAccount};
+use bom_core::dividend_receipts::month_column;
+use bom_core::filter_expr::parse_filter_expr;
+use bom_core::holdings::DuplicateCodePolicy;
+use bom_core::prices::{row_codes, CODE_COLUMN};
+use bom_core::{LOAD_ALL_CONFIRM_ROWS, PAGE_SIZE_CHOICES};
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum DropdownId {
-    Dataset,
-    Sheet,
-    Column,
-    ColumnVisibility,
-    Sort,
-}
+/// How often the main window checks whether it has been idle long enough to lock.
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+/// How often the main window checks whether a scheduled summary snapshot is due.
+const SNAPSHOT_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Backgrounds of the cells the last save changed and of the rows it added, while
+/// 標示上次儲存的變更 is on.
+const CHANGED_CELL_BACKGROUND: &str = "#fff3bf";
+const ADDED_CELL_BACKGROUND: &str = "#e3f5e1";
+/// Cells of the filter row under the table header.
+const COLUMN_CONDITION_CELL_STYLE: &str =
+    "border: 1px solid #bbb; padding: 2px; background: #fafafa;";
 
+/// A line of the table body: either a data row (by index into the page rows) or the
+/// header of a group when grouping is on.
 #[derive(Clone, Debug, PartialEq)]
-struct DropdownOption {
-    value: String,
-    label: String,
+enum TableEntry {
+    Group {
+        key: String,
+        row_indices: Vec<usize>,
+        subtotals: Vec<Option<f64>>,
+        collapsed: bool,
+    },
+    Row(usize),
 }
 
-fn dropdown_label(options: &[DropdownOption], selected: Option<&str>) -> String {
-    selected
-        .and_then(|value| options.iter().find(|opt| opt.value == value))
-        .map(|opt| opt.label.clone())
-        .unwrap_or_else(|| "(未選擇)".to_string())
+/// What a command-palette entry does when chosen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum PaletteAction {
+    Menu(MenuCommand),
+    SwitchDataset(i64),
+    SetColumnVisible(i64, bool),
+    SummaryReport,
+    ShowTab(WorkspaceTab),
+    Navigate(Route),
 }
 
-#[component]
-fn DropdownSelect(
-    id: DropdownId,
-    label: &'static str,
-    options: Vec<DropdownOption>,
-    selected: Option<String>,
-    mut open_dropdown: Signal<Option<DropdownId>>,
-    mut dropdown_pos: Signal<Option<(f64, f64)>>,
-    on_select: EventHandler<String>,
-) -> Element {
-    let is_open = open_dropdown() == Some(id);
-    let selected_label = dropdown_label(&options, selected.as_deref());
-    let (left, top) = dropdown_pos().unwrap_or((0.0, 0.0));
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum WorkspaceTab {
+    Table,
+    Chart,
+}
 
-    rsx! {
-        div {
-            style: "position: relative; display: inline-flex; align-items: center; gap: 6px;",
-            span { "{label}" }
-            button {
-                style: "border: 1px solid #bbb; background: #fff; padding: 4px 10px; border-radius: 6px; cursor: pointer;",
-                onclick: move |event| {
-                    event.stop_propagation();
-                    if open_dropdown() == Some(id) {
-                        open_dropdown.set(None);
-                        return;
-                    }
-                    let point = event.client_coordinates();
-                    dropdown_pos.set(Some((point.x, point.y + 24.0)));
-                    open_dropdown.set(Some(id));
-                },
-                "{selected_label}"
+#[component]
+pub fn App() -> Element {
+    let db_path = match default_db_path() {
+        Ok(path) => path,
+        Err(err) => {
+            return rsx! {
+                div {
+                    p { "無法取得資料庫路徑：{err}" }
+                }
+            };
+        }
+    };
+    let services = use_context_provider(|| AppServices::new(db_path));
+    // Holds the sync-folder writer lock (if any) for the lifetime of the main window.
+    let sync_folder = use_hook(|| enter_sync_folder_mode(&services));
+    let sync_status = sync_folder.map(|(status, _)| status);
+    let mut show_sync_notice = use_signal(|| {
+        sync_status
+            .as_ref()
+            .is_some_and(|status| status.needs_attention())
+    });
+    let mut app_lock = use_context_provider(|| AppLockState::load(&services));
+    use_context_provider(|| ColumnSetState::load(&services));
+    let idle_checks = use_coroutine(move |mut rx: UnboundedReceiver<()>| async move {
+        while rx.next().await.is_some() {
+            app_lock.lock_if_idle();
+        }
+    });
+    use_hook(move || {
+        let tx = idle_checks.tx();
+        std::thread::spawn(move || {
+            while tx.unbounded_send(()).is_ok() {
+                std::thread::sleep(IDLE_CHECK_INTERVAL);
+            }
+        });
+    });
+    // Datasets past the trash retention window go before the first list is loaded. In
+    // read-only mode they wait for a launch that can write.
+    use_hook({
+        let services = services.clone();
+        move || {
+            if services.read_only.is_enabled() {
+                return;
+            }
+            let purged = run_blocking(|| {
+                load_trash_retention_days(&services.query_service).and_then(|days| {
+                    services
+                        .edit_service
+                        .purge_expired_datasets(days)
+                        .map_err(anyhow::Error::from)
+                })
+            });
+            match purged {
+                Ok(purged) if !purged.is_empty() => {
+                    tracing::info!(count = purged.len(), "已清除垃圾桶中過期的資料集");
+                }
+                Ok(_) => {}
+                Err(err) => tracing::warn!(error = %err, "清除垃圾桶失敗"),
             }
         }
-
-        if is_open {
-            div {
-                style: "position: fixed; left: {left}px; top: {top}px; min-width: 200px; max-height: 320px; overflow-y: auto; background: #fff; border: 1px solid #bbb; border-radius: 8px; box-shadow: 0 10px 24px rgba(0,0,0,0.15); z-index: 1200;",
-                onclick: move |event| event.stop_propagation(),
-                {options.iter().map(|opt| {
-                    let value = opt.value.clone();
-                    let label = opt.label.clone();
-                    let is_selected = selected.as_deref() == Some(value.as_str());
-                    let background = if is_selected { "#eef4ff" } else { "transparent" };
-                    rsx!(
-                        div {
-                            style: "padding: 8px 10px; cursor: pointer; background: {background};",
-                            onclick: move |_| {
-                                on_select.call(value.clone());
-                                open_dropdown.set(None);
-                            },
-                            "{label}"
+    });
+    // Snapshots need nothing from the UI, so they run on a thread of their own. The schedule
+    // is read on every pass, so a change in 設定 applies without a restart.
+    use_hook({
+        let query_service = services.query_service.clone();
+        move || {
+            std::thread::spawn(move || loop {
+                let recorded = load_snapshot_schedule(&query_service)
+                    .and_then(|schedule| run_scheduled_snapshots(&query_service, schedule));
+                if let Err(err) = recorded {
+                    tracing::warn!(error = %err, "自動記錄總結失敗");
+                }
+                std::thread::sleep(SNAPSHOT_CHECK_INTERVAL);
+            });
+        }
+    });
+    let mut read_only = use_context_provider(|| ReadOnlyState {
+        enabled: Signal::new(services.read_only.is_enabled()),
+    });
+    // Leaving read-only mode needs the passphrase when one is set: the app locks, and
+    // read-only ends once it is unlocked.
+    let mut leave_read_only_on_unlock = use_signal(|| false);
+    // A file handed over by another launch while the app is locked waits for the unlock.
+    let mut open_on_unlock = use_signal(|| None::<PathBuf>);
+    let mut unlock_failed = use_signal(|| false);
+    let mut lock_settings_error = use_signal(|| None::<String>);
+    let split_view = use_context_provider(|| SplitViewState {
+        enabled: Signal::new(false),
+        secondary_dataset_id: Signal::new(None),
+    });
+    let mut menu_commands = use_context_provider(|| MenuCommandState {
+        pending: Signal::new(None),
+        open_file: Signal::new(None),
+    });
+    let route = use_context_provider(|| RouteState {
+        current: Signal::new(Route::Dataset),
+        open_dataset: Signal::new(None),
+        holding_code: Signal::new(None),
+    });
+    let current_route = (route.current)();
+    let instance_listener = try_use_context::<InstanceListener>();
+    let launch_request = try_use_context::<Handoff>();
+    let handoffs = use_coroutine({
+        let services = services.clone();
+        move |mut rx: UnboundedReceiver<Handoff>| {
+            let services = services.clone();
+            async move {
+                while let Some(handoff) = rx.next().await {
+                    let window = dioxus::desktop::window();
+                    window.set_minimized(false);
+                    window.set_focus();
+                    match handoff {
+                        Handoff::Focus => {}
+                        Handoff::ReadOnly => read_only.set(&services, true),
+                        Handoff::Open(path) if (app_lock.locked)() => {
+                            open_on_unlock.set(Some(path))
                         }
-                    )
-                })}
+                        Handoff::Open(path) => menu_commands.open_file.set(Some(path)),
+                    }
+                }
             }
         }
-    }
-}
-
-#[component]
-fn ColumnVisibilityDropdown(
-    id: DropdownId,
-    label: &'static str,
-    columns: Vec<String>,
-    visibility: BTreeMap<i64, bool>,
-    mut open_dropdown: Signal<Option<DropdownId>>,
-    mut dropdown_pos: Signal<Option<(f64, f64)>>,
-    on_toggle: EventHandler<(i64, bool)>,
-) -> Element {
-    let is_open = open_dropdown() == Some(id);
-    let (left, top) = dropdown_pos().unwrap_or((0.0, 0.0));
+    });
+    use_hook(move || {
+        if let Some(request) = launch_request {
+            handoffs.send(request);
+        }
+        if let Some(listener) = instance_listener {
+            let tx = handoffs.tx();
+            listener.serve(move |handoff| {
+                let _ = tx.unbounded_send(handoff);
+            });
+        }
+    });
+    let mut dark_theme = use_signal(|| load_dark_theme(&services));
+    use_hook({
+        let services = services.clone();
+        move || services.reload_number_locale()
+    });
+    // Theme and idle timeout can change underneath us when a preferences file is imported.
+    let preference_events = use_coroutine({
+        let services = services.clone();
+        move |mut events: UnboundedReceiver<DomainEvent>| {
+            let services = services.clone();
+            async move {
+                while let Some(event) = events.next().await {
+                    if event == DomainEvent::PreferencesImported {
+                        dark_theme.set(load_dark_theme(&services));
+                        services.reload_number_locale();
+                        app_lock.reload_idle_minutes(&services);
+                    }
+                }
+            }
+        }
+    });
+    let events_for_subscribe = services.events.clone();
+    use_hook(move || {
+        let tx = preference_events.tx();
+        events_for_subscribe.subscribe(move |event| {
+            let _ = tx.unbounded_send(event.clone());
+        })
+    });
+    let services_for_menu = services.clone();
+    dioxus::desktop::use_muda_event_handler(move |event| {
+        if (app_lock.locked)() {
+            return;
+        }
+        match MenuCommand::from_id(event.id().as_ref()) {
+            Some(MenuCommand::LockNow) if app_lock.has_passphrase() => app_lock.lock(),
+            Some(MenuCommand::LockNow | MenuCommand::AppLockSettings) => {
+                lock_settings_error.set(None);
+                app_lock.show_settings.set(true);
+            }
+            Some(MenuCommand::ToggleReadOnly) if !(read_only.enabled)() => {
+                read_only.set(&services_for_menu, true);
+            }
+            Some(MenuCommand::ToggleReadOnly) if app_lock.has_passphrase() => {
+                leave_read_only_on_unlock.set(true);
+                app_lock.lock();
+            }
+            Some(MenuCommand::ToggleReadOnly) => read_only.set(&services_for_menu, false),
+            Some(MenuCommand::LightTheme) => {
+                dark_theme.set(false);
+                save_theme(&services_for_menu, false);
+            }
+            Some(MenuCommand::DarkTheme) => {
+                dark_theme.set(true);
+                save_theme(&services_for_menu, true);
+            }
+            Some(MenuCommand::NumberLocale(locale)) => services_for_menu.save_number_locale(locale),
+            Some(command) => menu_commands.pending.set(Some(command)),
+            None => {}
+        }
+    });
+    let split_enabled = (split_view.enabled)();
+    let secondary_dataset_id = (split_view.secondary_dataset_id)();
+    let locked = (app_lock.locked)();
+    let filters = [
+        dark_theme().then_some("invert(0.92) hue-rotate(180deg)"),
+        locked.then_some("blur(12px)"),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+    let root_filter = if filters.is_empty() {
+        String::new()
+    } else {
+        format!(" filter: {};", filters.join(" "))
+    };
+    let root_pointer = if locked { " pointer-events: none;" } else { "" };
 
     rsx! {
-        div {
-            style: "position: relative; display: inline-flex; align-items: center; gap: 6px;",
-            span { "{label}" }
-            button {
-                style: "border: 1px solid #bbb; background: #fff; padding: 4px 10px; border-radius: 6px; cursor: pointer;",
-                onclick: move |event| {
-                    event.stop_propagation();
-                    if open_dropdown() == Some(id) {
-                        open_dropdown.set(None);
-                        return;
+        if locked {
+            LockScreen {
+                failed: unlock_failed(),
+                on_unlock: {
+                    let services = services.clone();
+                    move |passphrase: String| {
+                        let unlocked = app_lock.unlock(&passphrase);
+                        unlock_failed.set(!unlocked);
+                        if unlocked && leave_read_only_on_unlock() {
+                            leave_read_only_on_unlock.set(false);
+                            read_only.set(&services, false);
+                        }
+                        let pending_file = unlocked.then(|| open_on_unlock.write().take());
+                        if let Some(path) = pending_file.flatten() {
+                            menu_commands.open_file.set(Some(path));
+                        }
                     }
-                    let point = event.client_coordinates();
-                    dropdown_pos.set(Some((point.x, point.y + 24.0)));
-                    open_dropdown.set(Some(id));
                 },
-                "顯示欄位"
             }
         }
-
-        if is_open {
-            div {
-                style: "position: fixed; left: {left}px; top: {top}px; min-width: 220px; max-height: 320px; overflow-y: auto; background: #fff; border: 1px solid #bbb; border-radius: 8px; box-shadow: 0 10px 24px rgba(0,0,0,0.15); z-index: 1200; padding: 6px;",
-                onclick: move |event| event.stop_propagation(),
-                {columns.iter().enumerate().map(|(idx, header)| {
-                    let checked = visibility.get(&(idx as i64)).copied().unwrap_or(true);
-                    let header = header.clone();
-                    rsx!(
-                        label {
-                            style: "display: flex; align-items: center; gap: 8px; padding: 6px 4px; cursor: pointer;",
-                            input {
-                                r#type: "checkbox",
-                                checked: checked,
-                                onclick: move |_| {
-                                    on_toggle.call((idx as i64, !checked));
-                                }
-                            }
-                            span { "{header}" }
+        if let Some(status) = sync_status.filter(|_| show_sync_notice()) {
+            SyncFolderNotice { status, on_close: move |_| show_sync_notice.set(false) }
+        }
+        if (app_lock.show_settings)() && !locked {
+            AppLockSettings {
+                has_passphrase: app_lock.has_passphrase(),
+                idle_minutes: (app_lock.idle_minutes)(),
+                error: lock_settings_error(),
+                on_save: {
+                    let services = services.clone();
+                    move |(current, new_passphrase, idle_minutes): (String, String, u32)| {
+                        match app_lock.save(&services, &current, &new_passphrase, idle_minutes) {
+                            Ok(()) => app_lock.show_settings.set(false),
+                            Err(err) => lock_settings_error.set(Some(format!("{err:#}"))),
                         }
-                    )
-                })}
+                    }
+                },
+                on_close: move |_| app_lock.show_settings.set(false),
+            }
+        }
+        div {
+            style: "display: flex; flex-direction: column; height: 100vh; overflow: hidden;{root_filter}{root_pointer}",
+            aria_hidden: if locked { "true" } else { "false" },
+            onmousedown: move |_| app_lock.touch(),
+            onkeydown: move |_| app_lock.touch(),
+            onwheel: move |_| app_lock.touch(),
+            NavBar {}
+            // Hidden rather than unmounted on other pages, so staged edits are kept.
+            div {
+                style: if current_route == Route::Dataset { "display: flex; flex: 1 1 0; min-height: 0;" } else { "display: none;" },
+                div { style: "flex: 1 1 0; min-width: 0;", DatasetWorkspace {} }
+                if split_enabled {
+                    div { style: "flex: 1 1 0; min-width: 0; border-left: 2px solid #ccc;",
+                        DatasetWorkspace { initial_dataset_id: secondary_dataset_id, pane: 1 }
+                    }
+                }
+            }
+            if current_route != Route::Dataset {
+                div { style: "flex: 1 1 0; min-height: 0;",
+                    match current_route {
+                        Route::Dashboard => rsx! { DashboardPage {} },
+                        Route::DatasetManager => rsx! { DatasetManagerPage {} },
+                        Route::Reports => rsx! { ReportsPage {} },
+                        Route::Alerts => rsx! { AlertsPage {} },
+                        Route::Holding => rsx! { HoldingPage {} },
+                        Route::Retirement => rsx! { RetirementPage {} },
+                        Route::Settings => rsx! { SettingsPage {} },
+                        Route::Dataset => rsx! {},
+                    }
+                }
             }
         }
     }
 }
 
-#[component]
-pub fn App() -> Element {
-    let db_path = match default_db_path() {
-        Ok(path) => path,
+fn load_dark_theme(services: &AppServices) -> bool {
+    match services.query_service.load_setting(THEME_KEY) {
+        Ok(value) => value.as_deref() == Some("dark"),
         Err(err) => {
-            return rsx! {
-                div {
-                    p { "無法取得資料庫路徑：{err}" }
-                }
-            };
+            tracing::warn!(error = %err, "讀取主題設定失敗");
+            false
         }
-    };
+    }
+}
+
+fn save_theme(services: &AppServices, dark: bool) {
+    let value = if dark { "dark" } else { "light" };
+    if let Err(err) = services.query_service.save_setting(THEME_KEY, value) {
+        tracing::warn!(error = %err, "保存主題設定失敗");
+    }
+}
+
+/// Opens another top-level window bound to `dataset_id`, reusing the same services.
+fn open_dataset_window(
+    services: AppServices,
+    dataset_id: i64,
+    title: String,
+) -> anyhow::Result<()> {
+    let dom = VirtualDom::new_with_props(
+        DatasetWorkspace,
+        DatasetWorkspaceProps {
+            initial_dataset_id: Some(dataset_id),
+            pane: 0,
+        },
+    )
+    .with_root_context(services);
+    let config = dioxus::desktop::Config::new()
+        .with_window(dioxus::desktop::WindowBuilder::new().with_title(title))
+        .with_data_directory(default_webview_data_dir()?);
+    // Menu commands are handled by the main window only, so extra windows go without.
+    let config = config.with_menu(None);
+    dioxus::desktop::window().new_window(dom, config);
+    Ok(())
+}
 
+/// One window's (or split pane's) worth of UI: dataset selection, filters and table state
+/// are independent per instance. `pane` keeps element ids unique when two panes share a window.
+#[component]
+pub fn DatasetWorkspace(initial_dataset_id: Option<i64>, #[props(default)] pane: usize) -> Element {
+    let services = use_context::<AppServices>();
+    // Read every render; a double-click that starts editing re-renders first.
+    let number_locale = services.number_locale.get();
+    let mut split_view = try_use_context::<SplitViewState>();
+    // Only the first pane of the main window follows page navigation.
+    let route = try_use_context::<RouteState>().filter(|_| pane == 0);
+    // Secondary windows have no ReadOnlyState of their own and read the shared switch.
+    let read_only_state = try_use_context::<ReadOnlyState>();
+    // Secondary windows read the column sets once when they open.
+    let column_sets = use_hook(|| {
+        try_consume_context::<ColumnSetState>().unwrap_or_else(|| ColumnSetState::load(&services))
+    });
+    let root_id = pane_element_id("app-root", pane);
+    let scroll_id = pane_element_id("workspace-scroll", pane);
+    let table_head_id = pane_element_id("table-head", pane);
+
+    let state = AppState::new();
+    let services_handle = use_hook(|| CopyValue::new(services.clone()));
+    let workspace_entry = use_hook(|| Rc::new(services.workspaces.register(current_window_id())));
+    let place = WorkspacePlace {
+        id: workspace_entry.id(),
+        pane,
+        main_window: try_use_context::<MenuCommandState>().is_some(),
+    };
+    let mut controller = WorkspaceController::new(state, services_handle, place);
+    // Closing the window asks about unsaved edits like every other way of leaving them,
+    // in every pane and, for the main window, every other window.
+    use_close_guard(move || controller.request_close());
     let AppState {
         mut datasets,
         mut selected_group_key,
         mut selected_dataset_id,
         mut columns,
         mut column_visibility,
+        mut column_formats,
+        mut column_types,
+        mut dataset_display,
+        mut column_aliases,
+        mut visibility_presets,
         mut rows,
         mut holdings_flags,
         mut page,
         mut total_rows,
+        mut page_size,
+        mut load_all,
         mut global_search,
         mut column_search_col,
         mut column_search_text,
+        mut value_filters,
+        mut column_conditions,
         mut sort_col,
         mut sort_desc,
-        mut show_deleted,
+        show_deleted,
         mut busy,
         mut status,
+        mut last_error,
         mut staged_cells,
         mut deleted_rows,
         mut selected_rows,
         mut edit_mode,
         mut editing_cell,
         mut editing_value,
+        mut focused_cell,
         mut added_rows,
         mut show_add_row,
         mut new_row_inputs,
         mut context_menu,
-        mut context_row,
-        mut pending_action,
+        mut context_cell,
+        pending_action: _,
         mut show_save_prompt,
         mut show_save_as_prompt,
         mut save_as_name,
-    } = AppState::new();
+        mut last_saved_at,
+        mut recent_imports,
+        import_review,
+        mut import_mapping,
+        mut batch_import,
+        page_cache: _,
+        mut prefetched,
+    } = state;
+    // Lets 結束 and closing a window see this pane's unsaved edits from any other pane.
+    use_effect(move || {
+        let unsaved = !staged_cells.read().is_empty()
+            || !deleted_rows.read().is_empty()
+            || !added_rows.read().is_empty();
+        let name = selected_dataset_id().and_then(|dataset_id| {
+            datasets
+                .read()
+                .iter()
+                .find(|dataset| dataset.id.0 == dataset_id)
+                .map(|dataset| dataset.name.clone())
+        });
+        workspace_entry.set_unsaved(unsaved.then(|| name.unwrap_or_else(|| "未命名".to_string())));
+    });
 
     let mut show_summary_report = use_signal(|| false);
     let mut summary_report = use_signal(SummaryReport::default);
+    let mut summary_history = use_signal(Vec::<StoredSummaryReport>::new);
+    let mut summary_sections = use_signal(SummarySections::default);
     let mut show_dataset_manager = use_signal(|| false);
-    let mut manage_dataset_id = use_signal(|| None::<i64>);
-    let mut manage_name_input = use_signal(String::new);
-
-    let db_path = Arc::new(db_path);
-    let repo = Arc::new(SqliteRepo {
-        db_path: (*db_path).clone(),
+    // Loaded while the manager is open, and again after imports, re-transforms and
+    // datasets moving in or out of the trash.
+    let mut import_history = use_signal(Vec::<ImportRecord>::new);
+    let mut trash = use_signal(Vec::<DatasetMeta>::new);
+    let mut trash_retention_days = use_signal(|| DEFAULT_TRASH_RETENTION_DAYS);
+    use_effect(move || {
+        let _ = datasets();
+        let _ = last_saved_at();
+        if show_dataset_manager() {
+            import_history.set(controller.load_import_history());
+            trash.set(controller.load_trash());
+            trash_retention_days.set(controller.trash_retention_days());
+        }
     });
-    let query_service = Arc::new(QueryService::new(repo.clone()));
-    let edit_service = Arc::new(EditService::new(repo.clone()));
-    let import_service = Arc::new(ImportService::new((*db_path).clone()));
+    let mut show_account_form = use_signal(|| false);
+    let mut show_balance_update = use_signal(|| false);
+    let mut show_dividend_receipts = use_signal(|| false);
+    // Row last jumped to with 上一個/下一個修改.
+    let mut modified_cursor = use_signal(|| None::<usize>);
+
+    let AppServices {
+        repo,
+        query_service,
+        edit_service,
+        ..
+    } = services.clone();
     let repo_for_init = repo.clone();
-    let query_service_for_init = query_service.clone();
     let query_service_for_visibility = query_service.clone();
     let query_service_for_holdings_flags = query_service.clone();
+    let query_service_for_formats = query_service.clone();
+    let query_service_for_types = query_service.clone();
+    let query_service_for_blank = query_service.clone();
+    let query_service_for_backup = query_service.clone();
+    let query_service_for_recent = query_service.clone();
+    let query_service_for_recent_missing = query_service.clone();
+    let query_service_for_session_save = query_service.clone();
+    let edit_service_for_blank = edit_service.clone();
+    let query_service_for_aliases = query_service.clone();
+    let query_service_for_presets = query_service.clone();
+    let query_service_for_preset_apply = query_service.clone();
+    let query_service_for_preset_save = query_service.clone();
+    let query_service_for_preset_delete = query_service.clone();
+    let query_service_for_alias_update = query_service.clone();
+    let query_service_for_format_update = query_service.clone();
+    let query_service_for_type_update = query_service.clone();
+    let query_service_for_empty_columns = query_service.clone();
+    let query_service_for_column_stats = query_service.clone();
+    let query_service_for_export = query_service.clone();
+    let mut show_column_format = use_signal(|| false);
+    let mut computed_columns = use_signal(Vec::<ComputedColumn>::new);
+    let mut quick_filters = use_signal(Vec::<QuickFilterColumn>::new);
+    let mut search_history = use_signal(Vec::<SearchHistoryEntry>::new);
+    let query_service_for_quick_filters = query_service.clone();
+    let mut show_computed_columns = use_signal(|| false);
+    let mut remote_backup_dialog = use_signal(|| None::<(RemoteBackupConfig, Option<String>)>);
+    let mut dividend_owners_dialog = use_signal(|| None::<Vec<String>>);
+    let mut show_report_builder = use_signal(|| false);
+    let mut saved_reports = use_signal(Vec::<SavedReport>::new);
+    let mut show_error_dialog = use_signal(|| false);
+    let mut show_command_palette = use_signal(|| false);
+    let mut workspace_tab = use_signal(|| WorkspaceTab::Table);
+    let mut detail_row = use_signal(|| None::<usize>);
+    let mut group_by_col = use_signal(|| None::<usize>);
+    let mut collapsed_groups = use_signal(BTreeSet::<String>::new);
+    let mut hide_empty = use_signal(|| false);
+    // Loaded only while highlighting is on, and again after every save.
+    let mut show_saved_changes = use_signal(|| false);
+    let mut saved_changes = use_signal(SavedChanges::new);
+    use_effect(move || {
+        let _ = last_saved_at();
+        let changes = match selected_dataset_id().filter(|_| show_saved_changes()) {
+            Some(dataset_id) => controller.load_saved_changes(dataset_id),
+            None => SavedChanges::new(),
+        };
+        saved_changes.set(changes);
+    });
+    // Reloaded after saves, which move them with their rows, and after imports.
+    let mut show_raw_values = use_signal(|| false);
+    let mut cell_origins = use_signal(CellOrigins::new);
+    use_effect(move || {
+        let _ = last_saved_at();
+        let _ = datasets();
+        let origins = match selected_dataset_id() {
+            Some(dataset_id) => controller.load_cell_origins(dataset_id),
+            None => CellOrigins::new(),
+        };
+        cell_origins.set(origins);
+    });
+    // Prices of the 代號 on this page, drawn as sparklines; reloaded when new ones are stored.
+    let mut prices_revision = use_signal(|| 0_u64);
+    let mut price_history = use_signal(PriceHistory::new);
+    use_effect(move || {
+        let _ = prices_revision();
+        let codes = row_codes(&columns(), &rows());
+        let history = if codes.is_empty() {
+            PriceHistory::new()
+        } else {
+            controller.load_price_history(codes)
+        };
+        price_history.set(history);
+    });
+    let mut empty_columns = use_signal(BTreeSet::<i64>::new);
+    let mut column_stats = use_signal(BTreeMap::<i64, ColumnStats>::new);
+    // The first pane of the main window reopens where the last session left off.
+    let restores_session = route.is_some();
+    let mut session_restored = use_signal(|| false);
+    let mut session_scroll = use_signal(|| 0.0_f64);
+    // Applied once the restored dataset is selected, which closes the panel otherwise.
+    let mut restored_detail_row = use_signal(|| None::<usize>);
+    use_effect(move || {
+        let _ = selected_dataset_id();
+        detail_row.set(restored_detail_row.write().take());
+    });
+    // 載入全部 is confirmed per visit; coming back to the dataset pages it again.
+    use_effect(move || {
+        let selected = selected_dataset_id();
+        if load_all
+            .peek()
+            .is_some_and(|dataset_id| Some(dataset_id) != selected)
+        {
+            load_all.set(None);
+        }
+    });
+    // Saving edits renumbers rows, and the shares move with them.
+    let mut detail_shares = use_signal(Vec::<OwnerShare>::new);
+    use_effect(move || {
+        let _ = last_saved_at();
+        let shares = match (selected_dataset_id(), detail_row()) {
+            (Some(dataset_id), Some(row_idx)) => controller
+                .load_owner_splits(dataset_id)
+                .remove(&row_idx)
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        detail_shares.set(shares);
+    });
+    let read_only = match read_only_state {
+        Some(state) => (state.enabled)(),
+        None => services.read_only.is_enabled(),
+    };
+    use_effect(move || {
+        if read_only_state.is_some_and(|state| (state.enabled)()) {
+            edit_mode.set(false);
+        }
+    });
     let mut open_dropdown = use_signal(|| None::<DropdownId>);
     let dropdown_pos = use_signal(|| None::<(f64, f64)>);
     let mut table_header_stuck = use_signal(|| false);
     let mut eval_started = use_signal(|| false);
     let mut eval_handle = use_signal(|| None::<document::Eval>);
+    let watch_script_ids = (table_head_id.clone(), root_id.clone());
     use_effect(move || {
+        let (table_head_id, root_id) = &watch_script_ids;
         if eval_started() {
             return;
         }
         eval_started.set(true);
-        let mut eval = document::eval(
+        let eval = document::eval(&format!(
             r#"
-const sendState = () => {
-  const header = document.getElementById("table-head");
-  if (!header) {
+const headerId = {table_head_id:?};
+const rootId = {root_id:?};
+const sendState = () => {{
+  const header = document.getElementById(headerId);
+  if (!header) {{
     dioxus.send(false);
     return;
-  }
+  }}
   const top = header.getBoundingClientRect().top;
   dioxus.send(top <= 0);
-};
-const root = document.getElementById("app-root");
+}};
+const root = document.getElementById(rootId);
 const scrollTarget = root ?? window;
-const scrollOptions = { passive: true };
+const scrollOptions = {{ passive: true }};
 scrollTarget.addEventListener("scroll", sendState, scrollOptions);
 window.addEventListener("resize", sendState);
 sendState();
 await dioxus.recv();
 scrollTarget.removeEventListener("scroll", sendState, scrollOptions);
 window.removeEventListener("resize", sendState);
-"#,
-        );
-        let mut eval_for_recv = eval.clone();
+"#
+        ));
+        let mut eval_for_recv = eval;
         eval_handle.set(Some(eval));
         spawn(async move {
             loop {
@@ -278,54 +776,96 @@ window.removeEventListener("resize", sendState);
             }
         });
     });
-    use_on_unmount(move || {
+    use_drop(move || {
         if let Some(eval) = eval_handle() {
             let _ = eval.send(());
         }
     });
+    let query_service_for_session = query_service.clone();
+    let scroll_id_for_restore = scroll_id.clone();
     use_effect(move || {
-        *busy.write() = true;
-        let init_result = run_blocking(|| {
-            repo_for_init
-                .init()
-                .map_err(|err| anyhow!(err.to_string()))
-                .and_then(|_| {
-                    query_service_for_init
-                        .list_datasets(false)
-                        .map_err(|err| anyhow!(err.to_string()))
-                })
-        });
-        match init_result {
-            Ok(available) => {
-                let groups = build_dataset_groups(&available);
-                let first_dataset = groups
-                    .first()
-                    .and_then(|g| choose_default_dataset_id(&g.datasets));
-                *datasets.write() = available;
-                *selected_group_key.write() = groups.first().map(|g| g.key.clone());
-                *selected_dataset_id.write() = first_dataset;
-                *page.write() = 0;
-
-                match reload_page_data_usecase(
-                    &query_service_for_init,
-                    first_dataset,
-                    0,
-                    &QueryOptions::default(),
-                ) {
-                    Ok((loaded_columns, loaded_rows, loaded_total, loaded_page)) => {
-                        *columns.write() = loaded_columns;
-                        *rows.write() = loaded_rows;
-                        *total_rows.write() = loaded_total;
-                        *page.write() = loaded_page;
-                        *status.write() = "已載入資料集".to_string();
-                    }
-                    Err(err) => {
-                        *columns.write() = Vec::new();
-                        *rows.write() = Vec::new();
-                        *total_rows.write() = 0;
-                        *page.write() = 0;
-                        *status.write() = format!("載入資料失敗：{err}");
+        busy.write().begin(BusyKind::Query);
+        match run_blocking(|| repo_for_init.init()) {
+            Ok(()) => {
+                let session = if restores_session && !*session_restored.peek() {
+                    run_blocking(|| load_session(&query_service_for_session))
+                } else {
+                    UiSession::default()
+                };
+                let opened = controller.open_dataset(
+                    |available| {
+                        initial_dataset_id
+                            .or(session.dataset_id)
+                            .filter(|id| available.iter().any(|d| d.id.0 == *id))
+                            .or_else(|| {
+                                build_dataset_groups(available)
+                                    .first()
+                                    .and_then(|g| choose_default_dataset_id(&g.datasets))
+                            })
+                    },
+                    &session.options,
+                    "載入資料失敗",
+                );
+                if opened {
+                    let dataset_id = *selected_dataset_id.peek();
+                    if dataset_id.is_some() && dataset_id == session.dataset_id {
+                        let options = session.options.clone();
+                        global_search.set(options.global_search.clone());
+                        column_search_col.set(options.column_search_col);
+                        column_search_text.set(options.column_search_text.clone());
+                        value_filters.set(options.value_filters.clone());
+                        column_conditions.set(options.column_conditions.clone());
+                        sort_col.set(options.sort_col);
+                        sort_desc.set(options.sort_desc);
+                        if session.page > 0 {
+                            controller.load_page(
+                                dataset_id,
+                                session.page,
+                                &options,
+                                "載入資料失敗",
+                            );
+                        }
+                        if session.chart_tab {
+                            workspace_tab.set(WorkspaceTab::Chart);
+                        }
+                        restored_detail_row.set(session.detail_row);
+                        if let (Some(split), Some(secondary)) =
+                            (split_view.as_mut(), session.split_dataset_id)
+                        {
+                            split.secondary_dataset_id.set(secondary);
+                            split.enabled.set(true);
+                        }
+                        session_scroll.set(session.scroll_top);
+                        // The table fills in over a few frames; retry until it is tall enough.
+                        let _ = document::eval(&format!(
+                            r#"
+const target = {top};
+let frames = 0;
+const restore = () => {{
+  const scroller = document.getElementById({scroll_id_for_restore:?});
+  if (scroller) {{
+    scroller.scrollTop = target;
+    if (scroller.scrollTop >= target - 1) return;
+  }}
+  if (++frames < 60) requestAnimationFrame(restore);
+}};
+requestAnimationFrame(restore);
+"#,
+                            top = session.scroll_top,
+                        ));
+                    } else if session.options != QueryOptions::default() {
+                        // The saved filters belong to a dataset that is gone.
+                        controller.load_page(
+                            dataset_id,
+                            0,
+                            &QueryOptions::default(),
+                            "載入資料失敗",
+                        );
                     }
+                    *status.write() = "已載入資料集".to_string();
+                }
+                if restores_session {
+                    session_restored.set(true);
                 }
             }
             Err(err) => {
@@ -333,14 +873,119 @@ window.removeEventListener("resize", sendState);
                 *selected_group_key.write() = None;
                 *selected_dataset_id.write() = None;
                 *columns.write() = Vec::new();
-                *rows.write() = Vec::new();
+                *rows.write() = Arc::default();
                 *total_rows.write() = 0;
                 *page.write() = 0;
-                *status.write() = format!("初始化資料庫失敗：{err}");
+                report_error(status, last_error, "初始化資料庫失敗", &err);
+            }
+        }
+        busy.write().end(BusyKind::Query);
+    });
+
+    use_effect(move || {
+        if !restores_session || !session_restored() {
+            return;
+        }
+        let session = UiSession {
+            dataset_id: selected_dataset_id(),
+            options: controller.query_options(),
+            page: page(),
+            scroll_top: session_scroll(),
+            chart_tab: workspace_tab() == WorkspaceTab::Chart,
+            detail_row: detail_row(),
+            split_dataset_id: split_view
+                .filter(|split| (split.enabled)())
+                .map(|split| (split.secondary_dataset_id)()),
+        };
+        run_blocking(|| save_session(&query_service_for_session_save, &session));
+    });
+    let scroll_id_for_watch = scroll_id.clone();
+    use_effect(move || {
+        if !restores_session {
+            return;
+        }
+        // Scroll events do not bubble, so the listener catches them on the way down.
+        let mut eval = document::eval(&format!(
+            r#"
+const scrollId = {scroll_id_for_watch:?};
+let timer = null;
+document.addEventListener("scroll", (event) => {{
+  if (event.target.id !== scrollId) return;
+  clearTimeout(timer);
+  timer = setTimeout(() => dioxus.send(event.target.scrollTop), 300);
+}}, {{ capture: true, passive: true }});
+await new Promise(() => {{}});
+"#
+        ));
+        spawn(async move {
+            while let Ok(top) = eval.recv::<f64>().await {
+                session_scroll.set(top);
+            }
+        });
+    });
+
+    use_effect(move || {
+        match run_blocking(|| query_service_for_recent.load_setting(RECENT_IMPORTS_KEY)) {
+            Ok(value) => recent_imports.set(parse_recent_imports(&value.unwrap_or_default())),
+            Err(err) => {
+                report_error(status, last_error, "載入最近匯入失敗", &err);
+            }
+        }
+    });
+
+    use_effect(move || saved_reports.set(controller.load_saved_reports()));
+    use_effect(move || {
+        let sections = selected_group_key()
+            .map(|group_key| controller.load_summary_sections(&group_key))
+            .unwrap_or_default();
+        summary_sections.set(sections);
+    });
+
+    // Data changes made anywhere (this pane, the other pane or another window) arrive here
+    // instead of every call site updating the status bar and 最近匯入 by hand.
+    let query_service_for_events = query_service.clone();
+    let data_events = use_coroutine(move |mut events: UnboundedReceiver<DomainEvent>| {
+        let query_service = query_service_for_events.clone();
+        async move {
+            while let Some(event) = events.next().await {
+                match event {
+                    DomainEvent::DatasetImported { .. } => {
+                        match run_blocking(|| query_service.load_setting(RECENT_IMPORTS_KEY)) {
+                            Ok(value) => {
+                                recent_imports.set(parse_recent_imports(&value.unwrap_or_default()))
+                            }
+                            Err(err) => {
+                                report_error(status, last_error, "載入最近匯入失敗", &err);
+                            }
+                        }
+                    }
+                    DomainEvent::EditsApplied { dataset_id } => {
+                        if *selected_dataset_id.peek() == Some(dataset_id.0) {
+                            last_saved_at.set(Some(
+                                chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                            ));
+                        }
+                    }
+                    DomainEvent::PreferencesImported => {
+                        // Re-selecting the dataset reruns the column preference loaders.
+                        let current = *selected_dataset_id.peek();
+                        selected_dataset_id.set(current);
+                    }
+                    DomainEvent::PricesUpdated { .. } => *prices_revision.write() += 1,
+                    DomainEvent::DatasetDeleted { .. } | DomainEvent::DatasetRestored { .. } => {}
+                }
             }
         }
-        *busy.write() = false;
     });
+    let events_for_subscribe = services.events.clone();
+    let subscription = use_hook(move || {
+        let tx = data_events.tx();
+        events_for_subscribe.subscribe(move |event| {
+            let _ = tx.unbounded_send(event.clone());
+        })
+    });
+    let events_for_unsubscribe = services.events.clone();
+    use_drop(move || events_for_unsubscribe.unsubscribe(subscription));
 
     use_effect(move || {
         let dataset_id = selected_dataset_id();
@@ -350,33 +995,30 @@ window.removeEventListener("resize", sendState);
                 column_visibility.set(BTreeMap::new());
                 return;
             }
-            let visibility_result = run_blocking(|| {
-                query_service_for_visibility
-                    .load_column_visibility(DatasetId(id))
-                    .map_err(|err| anyhow!(err.to_string()))
-            });
+            let prefetched_visibility = prefetched
+                .write()
+                .column_visibility
+                .take()
+                .filter(|(prefetched_id, _)| *prefetched_id == id);
+            let visibility_result = match prefetched_visibility {
+                Some((_, visibility)) => Ok(visibility),
+                None => run_blocking(|| {
+                    query_service_for_visibility
+                        .load_column_visibility(DatasetId(id))
+                        .map_err(anyhow::Error::from)
+                }),
+            };
 
-            let visibility_loaded = visibility_result.is_ok();
             let visibility = match visibility_result {
                 Ok(map) => map,
                 Err(err) => {
-                    *status.write() = format!("載入欄位顯示失敗：{err}");
+                    report_error(status, last_error, "載入欄位顯示失敗", &err);
                     BTreeMap::new()
                 }
             };
-            let normalized = normalize_column_visibility(&columns_snapshot, &visibility);
-            let should_persist_default =
-                visibility_loaded && visibility.is_empty() && is_holdings_table(&columns_snapshot);
-            if should_persist_default {
-                let save_result = run_blocking(|| {
-                    query_service_for_visibility
-                        .upsert_column_visibility(DatasetId(id), normalized.clone())
-                        .map_err(|err| anyhow!(err.to_string()))
-                });
-                if let Err(err) = save_result {
-                    *status.write() = format!("保存欄位顯示失敗：{err}");
-                }
-            }
+            let holdings_set = column_sets.get(DatasetKind::Holdings);
+            let normalized =
+                normalize_column_visibility(&columns_snapshot, &visibility, &holdings_set);
             column_visibility.set(normalized);
         } else {
             column_visibility.set(BTreeMap::new());
@@ -384,74 +1026,253 @@ window.removeEventListener("resize", sendState);
     });
 
     use_effect(move || {
-        let dataset_count = datasets().len();
-        if dataset_count == 0 {
-            holdings_flags.set(BTreeMap::new());
+        let Some(id) = selected_dataset_id() else {
+            column_formats.set(BTreeMap::new());
+            dataset_display.set(DatasetDisplay::default());
             return;
+        };
+        let formats_result = run_blocking(|| {
+            query_service_for_formats
+                .load_column_formats(DatasetId(id))
+                .map_err(anyhow::Error::from)
+        });
+        match formats_result {
+            Ok(formats) => column_formats.set(formats),
+            Err(err) => {
+                column_formats.set(BTreeMap::new());
+                report_error(status, last_error, "載入數字格式失敗", &err);
+            }
         }
-        let flags_result = run_blocking(|| {
-            query_service_for_holdings_flags
-                .load_holdings_flags()
-                .map_err(|err| anyhow!(err.to_string()))
+        let display_result = run_blocking(|| {
+            query_service_for_formats
+                .load_dataset_display(DatasetId(id))
+                .map_err(anyhow::Error::from)
         });
-        match flags_result {
-            Ok(flags) => {
-                holdings_flags.set(flags);
+        match display_result {
+            Ok(display) => dataset_display.set(display),
+            Err(err) => {
+                dataset_display.set(DatasetDisplay::default());
+                report_error(status, last_error, "載入幣別與單位失敗", &err);
             }
+        }
+    });
+
+    use_effect(move || {
+        let Some(id) = selected_dataset_id() else {
+            column_types.set(BTreeMap::new());
+            return;
+        };
+        let types_result = run_blocking(|| {
+            query_service_for_types
+                .load_column_types(DatasetId(id))
+                .map_err(anyhow::Error::from)
+        });
+        match types_result {
+            Ok(types) => column_types.set(types),
             Err(err) => {
-                *status.write() = format!("載入持股標記失敗：{err}");
+                column_types.set(BTreeMap::new());
+                report_error(status, last_error, "載入欄位類型失敗", &err);
             }
         }
     });
 
-    let current_total_rows = total_rows();
-    let report_snapshot = summary_report();
+    use_effect(move || {
+        let loaded = selected_dataset_id()
+            .map(|id| controller.load_computed_columns(id))
+            .unwrap_or_default();
+        computed_columns.set(loaded);
+    });
 
-    let query_service_for_import = query_service.clone();
-    let import_service_for_import = import_service.clone();
-    let query_service_for_dataset_change = query_service.clone();
-    let query_service_for_global_search = query_service.clone();
-    let query_service_for_column_search = query_service.clone();
-    let query_service_for_sort_select = query_service.clone();
-    let query_service_for_sort_toggle = query_service.clone();
-    let query_service_for_tab_switch = query_service.clone();
-    let query_service_for_show_deleted = query_service.clone();
-    let query_service_for_summary = query_service.clone();
-    let query_service_for_visibility_update = query_service.clone();
-    let query_service_for_save = query_service.clone();
-    let query_service_for_save_as = query_service.clone();
-    let query_service_for_import_overwrite = query_service.clone();
-    let query_service_for_import_save_as = query_service.clone();
-    let query_service_for_manage = query_service.clone();
-    let edit_service_for_save = edit_service.clone();
-    let edit_service_for_save_as = edit_service.clone();
-    let edit_service_for_manage = edit_service.clone();
-    let query_service_for_manage_rename = query_service_for_manage.clone();
-    let query_service_for_manage_delete = query_service_for_manage.clone();
-    let import_service_for_import_overwrite = import_service.clone();
-    let import_service_for_import_save_as = import_service.clone();
-    let grouped_datasets = build_dataset_groups(&datasets());
-    let active_group =
-        selected_group_key().and_then(|k| grouped_datasets.iter().find(|g| g.key == k).cloned());
-    let query_service_for_dataset_change_dropdown = query_service_for_dataset_change.clone();
-    let query_service_for_tab_switch_dropdown = query_service_for_tab_switch.clone();
-    let dataset_options = std::iter::once(DropdownOption {
-        value: NONE_OPTION_VALUE.to_string(),
-        label: "(未選擇)".to_string(),
-    })
-    .chain(grouped_datasets.iter().map(|group| DropdownOption {
-        value: group.key.clone(),
-        label: group.label.clone(),
-    }))
-    .collect::<Vec<_>>();
-    let sheet_options = active_group
-        .as_ref()
-        .map(|group| {
-            group
-                .datasets
-                .iter()
-                .map(|sheet| DropdownOption {
-                    value: sheet.id.0.to_string(),
+    use_effect(move || {
+        let loaded = selected_dataset_id()
+            .map(|id| controller.load_search_history(id))
+            .unwrap_or_default();
+        search_history.set(loaded);
+    });
+
+    // Blank columns are judged over every row that passes the applied filters, not just
+    // this page, and judged again whenever the table reloads.
+    use_effect(move || {
+        let _ = rows();
+        let Some(id) = selected_dataset_id().filter(|_| hide_empty()) else {
+            empty_columns.set(BTreeSet::new());
+            return;
+        };
+        let query = build_page_query(id, 0, &controller.peek_query_options());
+        let result = run_blocking(|| {
+            query_service_for_empty_columns
+                .empty_columns(query)
+                .map_err(anyhow::Error::from)
+        });
+        match result {
+            Ok(empty) => empty_columns.set(empty),
+            Err(err) => {
+                empty_columns.set(BTreeSet::new());
+                report_error(status, last_error, "計算空白欄位失敗", &err);
+            }
+        }
+    });
+
+    // Header tooltips sum up every row that passes the applied filters, so they are
+    // worked out again whenever the table reloads.
+    use_effect(move || {
+        let _ = rows();
+        let Some(id) = selected_dataset_id() else {
+            column_stats.set(BTreeMap::new());
+            return;
+        };
+        let query = build_page_query(id, 0, &controller.peek_query_options());
+        let result = run_blocking(|| {
+            query_service_for_column_stats
+                .column_stats(query)
+                .map_err(anyhow::Error::from)
+        });
+        match result {
+            Ok(stats) => column_stats.set(stats),
+            Err(err) => {
+                column_stats.set(BTreeMap::new());
+                report_error(status, last_error, "計算欄位統計失敗", &err);
+            }
+        }
+    });
+
+    // Counts cover the whole dataset, so they are refreshed whenever the table reloads.
+    use_effect(move || {
+        let headers = columns();
+        let Some(id) = selected_dataset_id() else {
+            quick_filters.set(Vec::new());
+            return;
+        };
+        let loaded = run_blocking(|| {
+            QUICK_FILTER_COLUMNS
+                .iter()
+                .filter_map(|name| headers.iter().position(|header| header == name))
+                .map(|col_idx| {
+                    let values = query_service_for_quick_filters
+                        .column_value_counts(DatasetId(id), col_idx as i64)?;
+                    Ok(QuickFilterColumn {
+                        col_idx: col_idx as i64,
+                        label: headers[col_idx].clone(),
+                        values,
+                    })
+                })
+                .collect::<anyhow::Result<Vec<_>>>()
+        });
+        match loaded {
+            Ok(loaded) => quick_filters.set(loaded),
+            Err(err) => {
+                quick_filters.set(Vec::new());
+                report_error(status, last_error, "載入快速篩選失敗", &err);
+            }
+        }
+    });
+
+    use_effect(move || {
+        let Some(id) = selected_dataset_id() else {
+            column_aliases.set(BTreeMap::new());
+            return;
+        };
+        let aliases_result = run_blocking(|| {
+            query_service_for_aliases
+                .load_column_aliases(DatasetId(id))
+                .map_err(anyhow::Error::from)
+        });
+        match aliases_result {
+            Ok(aliases) => column_aliases.set(aliases),
+            Err(err) => {
+                column_aliases.set(BTreeMap::new());
+                report_error(status, last_error, "載入欄位別名失敗", &err);
+            }
+        }
+    });
+
+    use_effect(move || {
+        let Some(id) = selected_dataset_id() else {
+            visibility_presets.set(BTreeMap::new());
+            return;
+        };
+        let presets_result = run_blocking(|| {
+            query_service_for_presets
+                .load_visibility_presets(DatasetId(id))
+                .map_err(anyhow::Error::from)
+        });
+        match presets_result {
+            Ok(presets) => visibility_presets.set(presets),
+            Err(err) => {
+                visibility_presets.set(BTreeMap::new());
+                report_error(status, last_error, "載入欄位檢視失敗", &err);
+            }
+        }
+    });
+
+    use_effect(move || {
+        let dataset_count = datasets().len();
+        if dataset_count == 0 {
+            holdings_flags.set(BTreeMap::new());
+            return;
+        }
+        let flags_result = match prefetched.write().holdings_flags.take() {
+            Some(flags) => Ok(flags),
+            None => run_blocking(|| {
+                query_service_for_holdings_flags
+                    .load_holdings_flags()
+                    .map_err(anyhow::Error::from)
+            }),
+        };
+        match flags_result {
+            Ok(flags) => {
+                holdings_flags.set(flags);
+            }
+            Err(err) => {
+                report_error(status, last_error, "載入持股標記失敗", &err);
+            }
+        }
+    });
+
+    let current_total_rows = total_rows();
+    let report_snapshot = summary_report();
+
+    let query_service_for_summary = query_service.clone();
+    let query_service_for_report_script = query_service.clone();
+    let query_service_for_visibility_update = query_service.clone();
+    let grouped_datasets = build_dataset_groups(&datasets());
+    // A 另存 copy holds an old state of another dataset; editing it by mistake leaves that
+    // one behind, so it names the dataset it was saved from.
+    let save_as_primary = {
+        let available = datasets();
+        selected_dataset_id()
+            .and_then(|id| available.iter().find(|meta| meta.id.0 == id))
+            .and_then(DatasetMeta::saved_as_copy_of)
+            .map(|primary| {
+                let live = available
+                    .iter()
+                    .find(|meta| meta.id == primary && meta.deleted_at.is_none());
+                let group = grouped_datasets
+                    .iter()
+                    .find(|group| group.datasets.iter().any(|meta| meta.id == primary))
+                    .map(|group| group.key.clone());
+                (primary.0, live.map(|meta| meta.name.clone()), group)
+            })
+    };
+    let active_group =
+        selected_group_key().and_then(|k| grouped_datasets.iter().find(|g| g.key == k).cloned());
+    let dataset_options = std::iter::once(DropdownOption {
+        value: NONE_OPTION_VALUE.to_string(),
+        label: "(未選擇)".to_string(),
+    })
+    .chain(grouped_datasets.iter().map(|group| DropdownOption {
+        value: group.key.clone(),
+        label: group.label.clone(),
+    }))
+    .collect::<Vec<_>>();
+    let sheet_options = active_group
+        .as_ref()
+        .map(|group| {
+            group
+                .datasets
+                .iter()
+                .map(|sheet| DropdownOption {
+                    value: sheet.id.0.to_string(),
                     label: sheet.name.clone(),
                 })
                 .collect::<Vec<_>>()
@@ -463,97 +1284,107 @@ window.removeEventListener("resize", sendState);
             let mut assets = None;
             let mut holdings = None;
             for sheet in &group.datasets {
-                match dataset_tab_kind(&sheet.name) {
-                    Some(DatasetTabKind::Assets) => assets = Some(sheet.id.0),
-                    Some(DatasetTabKind::Holdings) => holdings = Some(sheet.id.0),
-                    None => {}
+                match sheet.effective_kind() {
+                    DatasetKind::Assets => assets = Some(sheet.id.0),
+                    DatasetKind::Holdings => holdings = Some(sheet.id.0),
+                    DatasetKind::Other => {}
                 }
             }
             (assets, holdings)
         })
         .unwrap_or((None, None));
+    // Formatting and alignment only change when a page loads or the number formats do,
+    // not on every render (selection, typing, menus).
+    let page_column_types =
+        use_memo(move || resolve_column_types(&columns(), &rows(), &column_types()));
+    let page_alignments_memo = use_memo(move || page_alignments(&page_column_types()));
+    let formatted_page = use_memo(move || {
+        format_page(
+            &columns(),
+            &rows(),
+            &column_formats(),
+            &page_column_types(),
+            &dataset_display(),
+        )
+    });
+    // The visible projection of the page is shared by the table, grouping and selection
+    // stats; rows are rendered by reference into it rather than copied per render.
+    // What the table shows: the stored choices, with 隱藏空白欄位 layered on top of them.
+    let shown_visibility = use_memo(move || {
+        if hide_empty() {
+            hide_empty_columns(&column_visibility(), &empty_columns())
+        } else {
+            column_visibility()
+        }
+    });
+    let visible_page = use_memo(move || {
+        let (visible_columns, visible_rows) =
+            apply_column_visibility(&columns(), &rows(), &shown_visibility());
+        (Arc::new(visible_columns), Arc::new(visible_rows))
+    });
     let current_columns = columns();
     let current_rows = rows();
     let visibility_snapshot = column_visibility();
-    let (visible_columns, visible_rows) =
-        apply_column_visibility(&current_columns, &current_rows, &visibility_snapshot);
-    let column_options = if current_columns.is_empty() {
-        Vec::new()
-    } else {
-        std::iter::once(DropdownOption {
-            value: NONE_OPTION_VALUE.to_string(),
-            label: "選擇欄位".to_string(),
-        })
-        .chain(
-            current_columns
-                .iter()
-                .enumerate()
-                .map(|(idx, header)| DropdownOption {
-                    value: idx.to_string(),
-                    label: header.clone(),
-                }),
-        )
-        .collect::<Vec<_>>()
-    };
-    let sort_options = if current_columns.is_empty() {
-        Vec::new()
-    } else {
-        std::iter::once(DropdownOption {
-            value: NONE_OPTION_VALUE.to_string(),
-            label: "選擇排序欄位".to_string(),
-        })
-        .chain(
-            current_columns
-                .iter()
-                .enumerate()
-                .map(|(idx, header)| DropdownOption {
-                    value: idx.to_string(),
-                    label: header.clone(),
-                }),
-        )
-        .collect::<Vec<_>>()
-    };
+    let shown_visibility_snapshot = shown_visibility();
+    let aliases_snapshot = column_aliases();
+    let (visible_columns, visible_rows) = visible_page();
     let added_rows_snapshot = added_rows();
-    let (_, visible_added_rows) =
-        apply_column_visibility(&current_columns, &added_rows_snapshot, &visibility_snapshot);
+    let (_, visible_added_rows) = apply_column_visibility(
+        &current_columns,
+        &added_rows_snapshot,
+        &shown_visibility_snapshot,
+    );
     let datasets_snapshot = datasets();
     let staged_cells_snapshot = Arc::new(staged_cells());
     let deleted_rows_snapshot = deleted_rows();
     let selected_rows_snapshot = selected_rows();
     let editing_cell_snapshot = editing_cell();
-    let column_alignments: Vec<&'static str> = visible_columns
-        .iter()
-        .map(|(idx, header)| column_alignment(header, &current_rows, *idx))
-        .collect();
-    let holdings_flags_snapshot = holdings_flags();
-    let selected_dataset_name = selected_dataset_id().and_then(|id| {
-        datasets_snapshot
+    let column_alignments: Arc<[&'static str]> = {
+        let alignments = page_alignments_memo.read();
+        visible_columns
             .iter()
-            .find(|dataset| dataset.id.0 == id)
-            .map(|dataset| dataset.name.clone())
-    });
-    let dataset_kind = selected_dataset_name.as_deref().and_then(dataset_tab_kind);
-    let auto_holdings = dataset_kind
-        .map(|kind| kind == DatasetTabKind::Holdings)
-        .unwrap_or(false)
-        || is_holdings_table(&current_columns);
-    let is_assets = dataset_kind
-        .map(|kind| kind == DatasetTabKind::Assets)
-        .unwrap_or(false);
+            .map(|(idx, _)| alignments.get(*idx).copied().unwrap_or("left"))
+            .collect()
+    };
+    let holdings_flags_snapshot = holdings_flags();
+    let selected_dataset = selected_dataset_id()
+        .and_then(|id| datasets_snapshot.iter().find(|dataset| dataset.id.0 == id));
+    let selected_dataset_name = selected_dataset.map(|dataset| dataset.name.clone());
+    let dataset_kind = selected_dataset.map(|dataset| dataset.effective_kind());
+    let dataset_row_count = selected_dataset.map(|dataset| dataset.row_count);
+    let pending_changes = pending_change_count(
+        &staged_cells_snapshot,
+        &deleted_rows_snapshot,
+        &added_rows_snapshot,
+    );
+    let db_path_text = services.db_path.display().to_string();
+    let holdings_set = column_sets.get(DatasetKind::Holdings);
+    let auto_holdings = dataset_kind == Some(DatasetKind::Holdings)
+        || is_holdings_table(&current_columns, &holdings_set);
+    let is_assets = dataset_kind == Some(DatasetKind::Assets);
     let is_holdings = selected_dataset_id()
         .and_then(|id| holdings_flags_snapshot.get(&id).copied())
         .unwrap_or(auto_holdings);
     let is_editable_table = is_holdings || is_assets;
     let scroll_mode = table_scroll_mode(is_assets, is_holdings);
+    // Computed columns come last and are never edited or entered by hand.
+    let computed_snapshot = computed_columns();
+    let stored_columns = &current_columns[..current_columns
+        .len()
+        .saturating_sub(computed_snapshot.len())];
     let editable_columns = Arc::new(if is_holdings {
-        editable_columns_for_holdings()
+        holdings_set.editable_columns(stored_columns)
     } else if is_assets {
-        editable_columns_for_assets(&current_columns)
+        column_sets
+            .get(DatasetKind::Assets)
+            .editable_columns(stored_columns)
     } else {
         Vec::new()
     });
     let required_columns = Arc::new(if is_holdings {
-        required_columns_for_holdings()
+        holdings_set.required.clone()
+    } else if is_assets {
+        column_sets.get(DatasetKind::Assets).required
     } else {
         Vec::new()
     });
@@ -562,630 +1393,1552 @@ window.removeEventListener("resize", sendState);
         || !deleted_rows_snapshot.is_empty()
         || !added_rows_snapshot.is_empty();
     let edit_mode_snapshot = edit_mode();
-    let editing_enabled = is_editable_table && edit_mode_snapshot;
-    let current_columns_for_add = Arc::new(current_columns.clone());
-    let current_columns_for_save = current_columns.clone();
-    let current_rows_for_save = current_rows.clone();
-    let datasets_for_save = datasets_snapshot.clone();
-    let current_columns_for_save_as = current_columns_for_save.clone();
-    let current_rows_for_save_as = current_rows_for_save.clone();
-    let table_columns = Arc::new(visible_columns.clone());
-    let table_rows = Arc::new(visible_rows.clone());
-    let table_added_rows = Arc::new(visible_added_rows.clone());
+    // Staged edits address rows by their position in the whole dataset, which a later page
+    // does not start at, so a paged result is view-only.
+    let current_page_size = page_size();
+    let paged = current_total_rows > current_page_size;
+    let saved_changes_snapshot = Arc::new(saved_changes());
+    let cell_origins_snapshot = Arc::new(cell_origins());
+    let price_history_snapshot = Arc::new(price_history());
+    let show_raw_values_snapshot = show_raw_values() && !cell_origins_snapshot.is_empty();
+    let changed_cell_count = saved_changes_snapshot
+        .values()
+        .filter(|previous| previous.is_some())
+        .count();
+    let added_row_count = saved_changes_snapshot
+        .iter()
+        .filter(|(_, previous)| previous.is_none())
+        .map(|((row_idx, _), _)| *row_idx)
+        .collect::<BTreeSet<_>>()
+        .len();
+    // Changes are keyed by stored row; a paged table starts part way through the dataset.
+    let saved_row_offset = if paged {
+        (page() * current_page_size) as usize
+    } else {
+        0
+    };
+    let editing_enabled = is_editable_table && edit_mode_snapshot && !read_only && !paged;
+    let focused_snapshot = focused_cell();
+    let detail_shares_snapshot = detail_shares();
+    let formula_cell = focused_snapshot
+        .clone()
+        .filter(|key| key.row_idx < current_rows.len() && key.col_idx < current_columns.len())
+        .map(|key| {
+            let (value, editable) = match key.col_idx.checked_sub(stored_columns.len()) {
+                Some(computed_idx) => {
+                    let expression = computed_snapshot
+                        .get(computed_idx)
+                        .map(|column| column.expression.clone())
+                        .unwrap_or_default();
+                    (format!("={expression}"), true)
+                }
+                None => (
+                    staged_cells_snapshot
+                        .get(&key)
+                        .cloned()
+                        .unwrap_or_else(|| current_rows[key.row_idx][key.col_idx].clone()),
+                    editing_enabled && editable_columns.contains(&key.column),
+                ),
+            };
+            FormulaCell {
+                key: format!("{}:{}", key.row_idx, key.col_idx),
+                label: format!(
+                    "第 {} 列 · {}",
+                    key.row_idx + 1,
+                    column_display_name(&key.column, key.col_idx, &aliases_snapshot)
+                ),
+                value,
+                editable,
+            }
+        });
+    let current_columns_for_add = Arc::new(stored_columns.to_vec());
+    // Accounts the quick balance update walks through, skipping rows marked for deletion,
+    // with their 餘額 as currently staged.
+    let balance_update = current_columns_for_add
+        .iter()
+        .position(|column| column == "餘額")
+        .filter(|_| is_assets)
+        .map(|balance_idx| {
+            let row_indices: Vec<usize> =
+                typed_balance_rows(&current_columns_for_add, &current_rows)
+                    .into_iter()
+                    .filter(|row_idx| !deleted_rows_snapshot.contains(row_idx))
+                    .collect();
+            let balances: BTreeMap<usize, String> = row_indices
+                .iter()
+                .map(|row_idx| {
+                    let key = CellKey {
+                        row_idx: *row_idx,
+                        col_idx: balance_idx,
+                        column: current_columns_for_add[balance_idx].clone(),
+                    };
+                    let value = staged_cells_snapshot.get(&key).cloned().unwrap_or_else(|| {
+                        current_rows[*row_idx]
+                            .get(balance_idx)
+                            .cloned()
+                            .unwrap_or_default()
+                    });
+                    (*row_idx, value)
+                })
+                .collect();
+            (balance_idx, row_indices, balances)
+        });
+    // A 持股股息總表 gets 標記本月已收 over its rows as currently staged.
+    let dividend_receipt_rows =
+        (is_holdings && month_column(&current_columns_for_add, 1).is_some()).then(|| {
+            current_rows
+                .iter()
+                .enumerate()
+                .map(|(row_idx, row)| {
+                    let mut row = row.clone();
+                    for (col_idx, cell) in row.iter_mut().enumerate() {
+                        let key = CellKey {
+                            row_idx,
+                            col_idx,
+                            column: current_columns_for_add[col_idx].clone(),
+                        };
+                        if let Some(value) = staged_cells_snapshot.get(&key) {
+                            *cell = value.clone();
+                        }
+                    }
+                    row
+                })
+                .collect::<Vec<_>>()
+        });
+    // What a new account must not repeat: stored rows still kept, plus rows added since.
+    let account_rows: Vec<Vec<String>> = if show_account_form() {
+        current_rows
+            .iter()
+            .enumerate()
+            .filter(|(row_idx, _)| !deleted_rows_snapshot.contains(row_idx))
+            .map(|(_, row)| row.clone())
+            .chain(added_rows_snapshot.iter().cloned())
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let required_columns_for_add = required_columns.clone();
+    let table_columns = visible_columns.clone();
+    // The row under the header: each column's filter expression and its border, red when
+    // it cannot be read and so does not filter.
+    let column_conditions_snapshot = column_conditions();
+    let condition_cells: Vec<(i64, String, &str)> = table_columns
+        .iter()
+        .map(|(col_idx, _)| {
+            let col_idx = *col_idx as i64;
+            let expression = column_conditions_snapshot
+                .get(&col_idx)
+                .cloned()
+                .unwrap_or_default();
+            let border =
+                if !expression.trim().is_empty() && parse_filter_expr(&expression).is_none() {
+                    "#d24"
+                } else {
+                    "#ccc"
+                };
+            (col_idx, expression, border)
+        })
+        .collect();
+    let column_formats_snapshot = column_formats();
+    let table_formats = Arc::new(column_formats_snapshot.clone());
+    let table_types = Arc::new(page_column_types());
+    let column_stats_snapshot = column_stats();
+    let header_tooltips: Vec<String> = table_columns
+        .iter()
+        .map(|(col_idx, _)| {
+            column_stats_snapshot
+                .get(&(*col_idx as i64))
+                .filter(|_| column_type_at(&table_types, *col_idx).is_numeric())
+                .map(column_stats_tooltip)
+                .unwrap_or_default()
+        })
+        .collect();
+    let table_display = Arc::new(dataset_display());
+    let table_rows = visible_rows.clone();
+    let group_by_snapshot = group_by_col().filter(|idx| *idx < current_columns.len());
+    let collapsed_snapshot = collapsed_groups();
+    let visible_types: Vec<ColumnType> = visible_columns
+        .iter()
+        .map(|(idx, _)| column_type_at(&table_types, *idx))
+        .collect();
+    let table_entries: Vec<TableEntry> = match group_by_snapshot {
+        Some(group_col) => group_rows(&current_rows, group_col)
+            .into_iter()
+            .flat_map(|group| {
+                let collapsed = collapsed_snapshot.contains(&group.key);
+                let subtotals = group_subtotals(&visible_types, &visible_rows, &group.row_indices);
+                let mut entries = vec![TableEntry::Group {
+                    key: group.key,
+                    row_indices: group.row_indices.clone(),
+                    subtotals,
+                    collapsed,
+                }];
+                if !collapsed {
+                    entries.extend(group.row_indices.into_iter().map(TableEntry::Row));
+                }
+                entries
+            })
+            .collect(),
+        None => (0..visible_rows.len()).map(TableEntry::Row).collect(),
+    };
+    let table_added_rows = Arc::new(visible_added_rows);
     let table_rows_len = table_rows.len();
     let table_added_rows_len = table_added_rows.len();
     let total_row_count = table_rows_len + table_added_rows_len;
+    let selection_stats_snapshot = {
+        let all_rows: Vec<&Vec<String>> =
+            visible_rows.iter().chain(table_added_rows.iter()).collect();
+        selection_stats(&visible_types, &all_rows, &selected_rows_snapshot)
+    };
     let all_rows_selected = total_row_count > 0 && selected_rows_snapshot.len() == total_row_count;
-
-    let switch_dataset = Rc::new(RefCell::new(move |next_dataset: Option<i64>| {
-        let query_service_for_tab_switch = query_service_for_tab_switch_dropdown.clone();
-        if is_editable_table && has_pending_changes {
-            if let Some(id) = next_dataset {
-                pending_action.set(Some(PendingAction::TabSwitch { dataset_id: id }));
-                show_save_prompt.set(true);
-            }
-            return;
+    // Rows in the order the table shows them, added rows last, and those with changes.
+    let row_order: Vec<usize> = table_entries
+        .iter()
+        .filter_map(|entry| match entry {
+            TableEntry::Row(row_idx) => Some(*row_idx),
+            TableEntry::Group { .. } => None,
+        })
+        .chain((0..table_added_rows_len).map(|idx| base_row_count + idx))
+        .collect();
+    let mut modified_row_set = modified_rows(&staged_cells_snapshot, &deleted_rows_snapshot);
+    modified_row_set.extend((0..table_added_rows_len).map(|idx| base_row_count + idx));
+    let modified_row_set = Rc::new(modified_row_set);
+    let modified_cursor_snapshot = modified_cursor();
+    let row_id_prefix = pane_element_id("table-row", pane);
+    let go_to_modified = {
+        let modified_row_set = modified_row_set.clone();
+        let row_id_prefix = row_id_prefix.clone();
+        move |forward: bool| {
+            let Some(row) =
+                step_modified_row(&row_order, &modified_row_set, modified_cursor(), forward)
+            else {
+                return;
+            };
+            modified_cursor.set(Some(row));
+            let row_id = format!("{row_id_prefix}-{row}");
+            let _ = document::eval(&format!(
+                "document.getElementById({row_id:?})?.scrollIntoView({{ block: \"center\" }});"
+            ));
         }
+    };
 
-        staged_cells.write().clear();
-        deleted_rows.write().clear();
-        selected_rows.write().clear();
-        *editing_cell.write() = None;
-        editing_value.set(String::new());
-        added_rows.write().clear();
-        show_add_row.set(false);
-        new_row_inputs.write().clear();
-        context_menu.set(None);
-        context_row.set(None);
-        edit_mode.set(true);
-        *selected_dataset_id.write() = next_dataset;
-        *page.write() = 0;
-        *busy.write() = true;
-        match reload_page_data_usecase(
-            &query_service_for_tab_switch,
-            next_dataset,
-            0,
-            &QueryOptions::default(),
-        ) {
-            Ok((loaded_columns, loaded_rows, loaded_total, loaded_page)) => {
-                *columns.write() = loaded_columns;
-                *rows.write() = loaded_rows;
-                *total_rows.write() = loaded_total;
-                *page.write() = loaded_page;
-            }
-            Err(err) => {
-                *status.write() = format!("載入工作表失敗：{err}");
-            }
-        }
-        *busy.write() = false;
+    // Raw values from the table's right-click menu; the menu closes with the click.
+    let copy_to_clipboard = move |text: String, copied_status: String| {
+        let script = format!(
+            "navigator.clipboard.writeText({text:?}).then(() => dioxus.send(true)).catch(() => dioxus.send(false));"
+        );
+        spawn(async move {
+            let mut eval = document::eval(&script);
+            let copied: bool = eval.recv().await.unwrap_or(false);
+            *status.write() = if copied {
+                copied_status
+            } else {
+                "複製失敗".to_string()
+            };
+        });
+    };
+
+    let switch_dataset = Rc::new(RefCell::new(move |next_dataset: Option<i64>| {
+        controller.guard_pending_changes(PendingAction::TabSwitch {
+            dataset_id: next_dataset,
+        });
     }));
 
-    let switch_dataset_for_assets = switch_dataset.clone();
-    let switch_dataset_for_holdings = switch_dataset.clone();
-    let switch_dataset_for_sheet = switch_dataset.clone();
+    let switch_dataset_for_picker = switch_dataset.clone();
+
+    let import_file = Rc::new(RefCell::new(move |file_path: PathBuf| {
+        controller.guard_pending_changes(PendingAction::Import(file_path));
+    }));
 
-    let handle_import = Rc::new(RefCell::new(move || {
-        let query_service_for_import = query_service_for_import.clone();
-        let import_service_for_import = import_service_for_import.clone();
+    let import_file_with_profile = Rc::new(RefCell::new(
+        move |file_path: PathBuf, profile: ImportProfile| {
+            controller.guard_pending_changes(PendingAction::ImportWithProfile(file_path, profile));
+        },
+    ));
 
-        if is_editable_table && has_pending_changes {
+    let handle_import = Rc::new(RefCell::new({
+        let import_file = import_file.clone();
+        move || {
             if let Some(file_path) = FileDialog::new()
                 .add_filter("Excel", &["xlsx"])
                 .add_filter("CSV", &["csv"])
                 .add_filter("所有檔案", &["*"])
                 .pick_file()
             {
-                pending_action.set(Some(PendingAction::Import(file_path)));
-                show_save_prompt.set(true);
+                import_file.borrow_mut()(file_path);
             }
-            return;
         }
+    }));
+
+    let handle_import_folder = Rc::new(RefCell::new(move || {
+        if let Some(folder) = FileDialog::new().pick_folder() {
+            controller.guard_pending_changes(PendingAction::ImportFolder(folder));
+        }
+    }));
 
-        if let Some(file_path) = FileDialog::new()
-            .add_filter("Excel", &["xlsx"])
+    let selected_dataset_name_for_export = selected_dataset_name.clone();
+    let handle_export = Rc::new(RefCell::new(move || {
+        let Some(dataset_id) = selected_dataset_id() else {
+            *status.write() = "請先選擇資料集".to_string();
+            return;
+        };
+        let default_name = format!(
+            "{}.csv",
+            selected_dataset_name_for_export
+                .clone()
+                .unwrap_or_else(default_dataset_name_mmdd)
+        );
+        let Some(file_path) = FileDialog::new()
             .add_filter("CSV", &["csv"])
-            .add_filter("所有檔案", &["*"])
-            .pick_file()
-        {
-            *busy.write() = true;
-            *status.write() = format!("正在匯入 {}", file_path.display());
-            let ext = file_path
-                .extension()
-                .and_then(|e| e.to_str())
-                .map(|s| s.to_ascii_lowercase())
-                .unwrap_or_default();
-            let import_result = run_blocking(|| {
-                if ext == "xlsx" {
-                    import_service_for_import
-                        .import_xlsx(&file_path)
-                        .map(|items| {
-                            (
-                                items.first().map(|it| it.dataset_id),
-                                items.len() as i64,
-                                true,
-                            )
-                        })
-                } else {
-                    import_service_for_import
-                        .import_csv(&file_path)
-                        .map(|item| (Some(item.dataset_id), item.row_count, false))
-                }
-            });
+            .set_file_name(default_name.as_str())
+            .save_file()
+        else {
+            return;
+        };
+        // The table holds one page; the file gets every row the filters keep.
+        let options = controller.peek_query_options();
+        let loaded =
+            run_blocking(|| load_all_rows(&query_service_for_export, dataset_id, &options));
+        let (all_columns, all_rows) = match loaded {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                report_error(status, last_error, "匯出失敗", &err);
+                return;
+            }
+        };
+        let (exported_columns, exported_rows) =
+            apply_column_visibility(&all_columns, &all_rows, &shown_visibility());
+        let (headers, exported_rows) = display_export_table(
+            &exported_columns,
+            &exported_rows,
+            &column_aliases(),
+            &page_column_types(),
+            &dataset_display(),
+        );
+        match export_csv(&file_path, &headers, &exported_rows) {
+            Ok(()) => {
+                *status.write() = format!(
+                    "已匯出 {} 筆至 {}",
+                    exported_rows.len(),
+                    file_path.display()
+                );
+            }
+            Err(err) => {
+                report_error(status, last_error, "匯出失敗", &err);
+            }
+        }
+    }));
 
-            match import_result {
-                Ok((selected_id, imported_count, is_xlsx)) => {
-                    match run_blocking(|| query_service_for_import.list_datasets(show_deleted())) {
-                        Ok(available) => {
-                            let groups = build_dataset_groups(&available);
-                            *datasets.write() = available;
-                            let next_group_key = selected_id.and_then(|id| {
-                                groups
-                                    .iter()
-                                    .find(|g| g.datasets.iter().any(|d| d.id.0 == id))
-                                    .map(|g| g.key.clone())
-                            });
-                            *selected_group_key.write() = next_group_key;
-                            *selected_dataset_id.write() = selected_id;
-                            *column_search_col.write() = None;
-                            *column_search_text.write() = String::new();
-                            *sort_col.write() = None;
-                            *sort_desc.write() = false;
-                            *page.write() = 0;
-                            match reload_page_data_usecase(
-                                &query_service_for_import,
-                                selected_id,
-                                0,
-                                &QueryOptions::default(),
-                            ) {
-                                Ok((loaded_columns, loaded_rows, loaded_total, loaded_page)) => {
-                                    *columns.write() = loaded_columns;
-                                    *rows.write() = loaded_rows;
-                                    *total_rows.write() = loaded_total;
-                                    *page.write() = loaded_page;
-                                    *status.write() = if is_xlsx {
-                                        format!("已匯入 XLSX，共 {} 個資料表", imported_count)
-                                    } else {
-                                        format!("已匯入 CSV（{} 筆）", imported_count)
-                                    };
-                                }
-                                Err(err) => {
-                                    *status.write() = format!("匯入成功，但載入資料失敗：{err}");
-                                }
-                            }
-                        }
-                        Err(err) => {
-                            *status.write() = format!("匯入成功，但刷新資料集失敗：{err}");
-                        }
-                    }
-                }
-                Err(err) => {
-                    *status.write() = format!("匯入失敗：{err}");
+    let selected_dataset_name_for_image = selected_dataset_name.clone();
+    // The page as it is shown: visible columns under their display names, formatted cells.
+    let handle_export_image = Rc::new(RefCell::new(move || {
+        if selected_dataset_id().is_none() {
+            *status.write() = "請先選擇資料集".to_string();
+            return;
+        }
+        let (shown_columns, shown_rows) =
+            apply_column_visibility(&columns(), &formatted_page(), &shown_visibility());
+        let types = page_column_types();
+        let aliases = column_aliases();
+        let display = dataset_display();
+        let alignments = page_alignments_memo();
+        let headers: Vec<String> = shown_columns
+            .iter()
+            .map(|(col_idx, header)| {
+                let label = column_display_name(header, *col_idx, &aliases);
+                display_header(header, &label, column_type_at(&types, *col_idx), &display)
+            })
+            .collect();
+        let right_aligned: Vec<bool> = shown_columns
+            .iter()
+            .map(|(col_idx, _)| alignments.get(*col_idx) == Some(&"right"))
+            .collect();
+        let title = selected_dataset_name_for_image
+            .clone()
+            .unwrap_or_else(default_dataset_name_mmdd);
+        let image = table_image(&title, &headers, &shown_rows, &right_aligned);
+        let row_count = shown_rows.len();
+        spawn(async move {
+            match export_table_image(image, &format!("{title}.png")).await {
+                Ok(true) => *status.write() = format!("已匯出 {row_count} 列為圖片"),
+                Ok(false) => {}
+                Err(err) => report_error(status, last_error, "匯出圖片失敗", &err),
+            }
+        });
+    }));
+
+    let handle_backup = Rc::new(RefCell::new(move || {
+        let default_name = format!(
+            "bom-backup-{}.sqlite",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        );
+        let Some(file_path) = FileDialog::new()
+            .add_filter("SQLite", &["sqlite", "db"])
+            .set_file_name(default_name.as_str())
+            .save_file()
+        else {
+            return;
+        };
+        busy.write().begin(BusyKind::Save);
+        match run_blocking(|| query_service_for_backup.backup_database(&file_path)) {
+            Ok(()) => {
+                *status.write() = format!("已備份資料庫至 {}", file_path.display());
+                if let Some(url) = controller.upload_remote_backup(&file_path) {
+                    *status.write() =
+                        format!("已備份資料庫至 {}，並上傳至 {url}", file_path.display());
                 }
             }
-            *busy.write() = false;
+            Err(err) => {
+                report_error(status, last_error, "備份失敗", &err);
+            }
         }
+        busy.write().end(BusyKind::Save);
+        notify_if_unfocused("BOM 備份", &status());
     }));
 
-    let handle_import_for_manager = handle_import.clone();
-
-    rsx! {
-        div {
-            id: "app-root",
-            onclick: move |_| {
-                context_menu.set(None);
-                context_row.set(None);
-                open_dropdown.set(None);
-            },
-            oncontextmenu: move |event| {
-                event.prevent_default();
-            },
-            style: "{root_container_style_for_scroll(scroll_mode)}",
+    let handle_summary_report = Rc::new(RefCell::new(move || {
+        busy.write().begin(BusyKind::Query);
+        let Some(dataset_id) = selected_dataset_id() else {
+            *status.write() = "請先選擇資料集".to_string();
+            busy.write().end(BusyKind::Query);
+            return;
+        };
+        let owners = controller.load_dividend_owners();
+        let report_result = run_blocking(|| {
+            let dataset_id = DatasetId(dataset_id);
+            let current = cached_summary_report(&query_service_for_summary, dataset_id, &owners)?;
+            let mut history = query_service_for_summary.load_summary_reports(dataset_id)?;
+            if current.id == 0 {
+                // Read-only mode computed it without saving.
+                history.insert(0, current.clone());
+            }
+            for stored in &mut history {
+                order_owner_totals(&mut stored.report, &owners);
+            }
+            Ok::<_, anyhow::Error>((current, history))
+        });
+        match report_result {
+            Ok((current, history)) => {
+                summary_report.set(current.report);
+                summary_history.set(history);
+                show_summary_report.set(true);
+            }
+            Err(err) => {
+                report_error(status, last_error, "載入總結報表失敗", &err);
+            }
+        }
+        busy.write().end(BusyKind::Query);
+    }));
 
-            div {
-                style: "flex: 1 1 auto; min-height: 0; overflow: auto;",
-                h2 { "BOM" }
+    // Scripts picked from disk, saved reports and the report builder all run through here.
+    let run_script = Rc::new(RefCell::new(move |script: String| {
+        let Some(dataset_id) = selected_dataset_id() else {
+            *status.write() = "請先選擇資料集".to_string();
+            return;
+        };
+        busy.write().begin(BusyKind::Query);
+        let report_result = run_blocking(|| {
+            let page = query_service_for_report_script
+                .load_tabular_data(DatasetId(dataset_id))
+                .map_err(anyhow::Error::from)?;
+            let splits = query_service_for_report_script
+                .load_owner_splits(DatasetId(dataset_id))
+                .map_err(anyhow::Error::from)?;
+            run_report_script(&script, &page.columns, &page.rows, &splits)
+        });
+        match report_result {
+            Ok(report) => {
+                summary_report.set(report);
+                summary_history.set(Vec::new());
+                show_summary_report.set(true);
+            }
+            Err(err) => {
+                report_error(status, last_error, "執行自訂報表失敗", &err);
+            }
+        }
+        busy.write().end(BusyKind::Query);
+    }));
 
-                div {
-                    style: "display: flex; gap: 8px; align-items: center; margin-bottom: 12px; background: #fff; padding: 8px 0;",
-                    button {
-                        disabled: busy(),
-                        onclick: move |_| {
-                            manage_dataset_id.set(selected_dataset_id());
-                            let datasets_snapshot = datasets();
-                            let current_name = selected_dataset_id()
-                                .and_then(|id| datasets_snapshot.iter().find(|d| d.id.0 == id))
-                                .map(|d| d.name.clone())
-                                .unwrap_or_default();
-                            manage_name_input.set(current_name);
-                            show_dataset_manager.set(true);
-                        },
-                        "資料集管理"
-                    }
+    let handle_report_script = Rc::new(RefCell::new({
+        let run_script = run_script.clone();
+        move || {
+            if selected_dataset_id().is_none() {
+                *status.write() = "請先選擇資料集".to_string();
+                return;
+            }
+            let mut dialog = FileDialog::new().add_filter("報表腳本", &["bomr", "txt"]);
+            if let Ok(dir) = report_scripts_dir() {
+                dialog = dialog.set_directory(dir);
+            }
+            let Some(script_path) = dialog.pick_file() else {
+                return;
+            };
+            match std::fs::read_to_string(&script_path)
+                .with_context(|| format!("failed to read script: {}", script_path.display()))
+            {
+                Ok(script) => run_script.borrow_mut()(script),
+                Err(err) => report_error(status, last_error, "執行自訂報表失敗", &err),
+            }
+        }
+    }));
 
-                    button {
-                        disabled: busy(),
-                        onclick: move |_| {
-                            *busy.write() = true;
-                            let Some(dataset_id) = selected_dataset_id() else {
-                                *status.write() = "請先選擇資料集".to_string();
-                                *busy.write() = false;
-                                return;
-                            };
-                            let report_result = run_blocking(|| {
-                                query_service_for_summary
-                                    .query_page(PageQuery {
-                                        dataset_id: DatasetId(dataset_id),
-                                        page: 0,
-                                        page_size: i64::MAX,
-                                        global_search: String::new(),
-                                        column_filter: None,
-                                        sort: None,
-                                    })
-                                    .map_err(|err| anyhow!(err.to_string()))
-                            });
-                            match report_result {
-                                Ok(page) => {
-                                    let report = compute_summary_report(&page.columns, &page.rows);
-                                    summary_report.set(report);
-                                    show_summary_report.set(true);
-                                }
-                                Err(err) => {
-                                    *status.write() = format!("載入總結報表失敗：{err}");
-                                }
-                            }
-                            *busy.write() = false;
-                        },
-                        "總結報表"
-                    }
+    let handle_summary_export = Rc::new(RefCell::new(move |report: SummaryReport| {
+        let Some(file_path) = FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .set_file_name(format!("{}.csv", report.title).as_str())
+            .save_file()
+        else {
+            return;
+        };
+        let (headers, table_rows) = summary_report_table(&report, summary_sections());
+        match export_csv(&file_path, &headers, &table_rows) {
+            Ok(()) => {
+                *status.write() = format!("已匯出報表至 {}", file_path.display());
+            }
+            Err(err) => {
+                report_error(status, last_error, "匯出報表失敗", &err);
+            }
+        }
+    }));
+
+    let set_column_visible = Rc::new(RefCell::new(move |col_idx: i64, visible: bool| {
+        let mut next_visibility = column_visibility();
+        next_visibility.insert(col_idx, visible);
+        column_visibility.set(next_visibility.clone());
+        if let Some(dataset_id) = selected_dataset_id() {
+            let result = run_blocking(|| {
+                query_service_for_visibility_update
+                    .upsert_column_visibility(DatasetId(dataset_id), next_visibility.clone())
+                    .map_err(anyhow::Error::from)
+            });
+            if let Err(err) = result {
+                report_error(status, last_error, "更新欄位顯示失敗", &err);
+            }
+        }
+    }));
 
-                    span { " {status}" }
+    // Rebuilt every render so menu commands always run against the current state.
+    let menu_dispatch = use_hook(|| Rc::new(RefCell::new(None::<Box<dyn FnMut(MenuCommand)>>)));
+    *menu_dispatch.borrow_mut() = Some(Box::new({
+        let handle_import = handle_import.clone();
+        let handle_import_folder = handle_import_folder.clone();
+        let import_file = import_file.clone();
+        let handle_export = handle_export.clone();
+        let handle_export_image = handle_export_image.clone();
+        let handle_backup = handle_backup.clone();
+        let handle_report_script = handle_report_script.clone();
+        let handle_summary_report = handle_summary_report.clone();
+        let run_script = run_script.clone();
+        let mut split_view = split_view;
+        move |command| match command {
+            MenuCommand::Import => handle_import.borrow_mut()(),
+            MenuCommand::ImportFolder => handle_import_folder.borrow_mut()(),
+            MenuCommand::ImportWithMapping => {
+                if let Some(file_path) = FileDialog::new().add_filter("CSV", &["csv"]).pick_file() {
+                    controller.open_import_mapping(&file_path, false);
                 }
+            }
+            MenuCommand::RecentImport(idx) => {
+                let Some(path) = recent_imports().get(idx).cloned() else {
+                    return;
+                };
+                let file_path = PathBuf::from(&path);
+                if file_path.is_file() {
+                    import_file.borrow_mut()(file_path);
+                    return;
+                }
+                *status.write() = format!("找不到檔案，已從最近匯入移除：{path}");
+                let remaining = recent_imports()
+                    .into_iter()
+                    .filter(|item| *item != path)
+                    .collect::<Vec<_>>();
+                if let Err(err) = query_service_for_recent_missing
+                    .save_setting(RECENT_IMPORTS_KEY, &remaining.join("\n"))
+                {
+                    report_error(status, last_error, "保存最近匯入失敗", &err);
+                }
+                recent_imports.set(remaining);
+            }
+            MenuCommand::ExportCsv => handle_export.borrow_mut()(),
+            MenuCommand::ExportXlsx => {
+                let Some(dataset_id) = selected_dataset_id() else {
+                    *status.write() = "請先選擇資料集".to_string();
+                    return;
+                };
+                let name = datasets()
+                    .iter()
+                    .find(|dataset| dataset.id.0 == dataset_id)
+                    .map(|dataset| dataset.name.clone())
+                    .unwrap_or_else(default_dataset_name_mmdd);
+                if let Some(file_path) = FileDialog::new()
+                    .add_filter("Excel 活頁簿", &["xlsx"])
+                    .set_file_name(format!("{name}.xlsx"))
+                    .save_file()
+                {
+                    controller.export_xlsx(dataset_id, &file_path, shown_visibility());
+                }
+            }
+            MenuCommand::ExportImage => handle_export_image.borrow_mut()(),
+            MenuCommand::BackupDatabase => handle_backup.borrow_mut()(),
+            MenuCommand::RemoteBackupSettings => {
+                remote_backup_dialog.set(controller.load_remote_backup());
+            }
+            MenuCommand::Columns => {
+                if selected_dataset_id().is_some() {
+                    open_dropdown.set(Some(DropdownId::ColumnVisibility));
+                }
+            }
+            MenuCommand::NumberFormat => {
+                if selected_dataset_id().is_some() {
+                    show_column_format.set(true);
+                }
+            }
+            MenuCommand::ComputedColumns => {
+                if selected_dataset_id().is_some() {
+                    show_computed_columns.set(true);
+                }
+            }
+            MenuCommand::ToggleSplitView => {
+                if let Some(split_view) = split_view.as_mut() {
+                    split_view.toggle(selected_dataset_id());
+                }
+            }
+            MenuCommand::OpenLogFile => {
+                if let Err(err) = log_file_path().and_then(|path| open_with_system(&path)) {
+                    report_error(status, last_error, "開啟記錄檔失敗", &err);
+                }
+            }
+            MenuCommand::CommandPalette => show_command_palette.set(true),
+            MenuCommand::SummaryReport => handle_summary_report.borrow_mut()(),
+            MenuCommand::ReportBuilder => {
+                if selected_dataset_id().is_none() {
+                    *status.write() = "請先選擇資料集".to_string();
+                    return;
+                }
+                show_report_builder.set(true);
+            }
+            MenuCommand::SavedReport(idx) => {
+                if let Some(report) = saved_reports().get(idx).cloned() {
+                    run_script.borrow_mut()(report.script);
+                }
+            }
+            MenuCommand::RunReportScript => handle_report_script.borrow_mut()(),
+            MenuCommand::DividendOwners => {
+                dividend_owners_dialog.set(Some(controller.load_dividend_owners()));
+            }
+            MenuCommand::DividendYearRollover => {
+                let Some(dataset_id) = selected_dataset_id() else {
+                    *status.write() = "請先選擇資料集".to_string();
+                    return;
+                };
+                controller.guard_pending_changes(PendingAction::DividendRollover { dataset_id });
+            }
+            MenuCommand::ExportPreferences => {
+                if let Some(file_path) = FileDialog::new()
+                    .add_filter("JSON", &["json"])
+                    .set_file_name("bom-settings.json")
+                    .save_file()
+                {
+                    controller.export_preferences(&file_path);
+                }
+            }
+            MenuCommand::ImportPreferences => {
+                if let Some(file_path) = FileDialog::new().add_filter("JSON", &["json"]).pick_file()
+                {
+                    controller.import_preferences(&file_path);
+                }
+            }
+            MenuCommand::ExportBundle => {
+                let Some(dataset_id) = selected_dataset_id() else {
+                    *status.write() = "請先選擇資料集".to_string();
+                    return;
+                };
+                let name = datasets()
+                    .iter()
+                    .find(|dataset| dataset.id.0 == dataset_id)
+                    .map(|dataset| dataset.name.clone())
+                    .unwrap_or_default();
+                if let Some(file_path) = FileDialog::new()
+                    .add_filter("BOM 資料集", &[BUNDLE_EXTENSION])
+                    .set_file_name(format!("{name}.{BUNDLE_EXTENSION}"))
+                    .save_file()
+                {
+                    controller.export_bundle(dataset_id, &file_path);
+                }
+            }
+            MenuCommand::ImportBundle => {
+                if let Some(file_path) = FileDialog::new()
+                    .add_filter("BOM 資料集", &[BUNDLE_EXTENSION])
+                    .pick_file()
+                {
+                    controller.import_bundle(&file_path);
+                }
+            }
+            MenuCommand::LoadDemoData => controller.load_demo_data(),
+            MenuCommand::Quit => controller.request_quit(),
+            MenuCommand::LightTheme
+            | MenuCommand::DarkTheme
+            | MenuCommand::LockNow
+            | MenuCommand::AppLockSettings
+            | MenuCommand::ToggleReadOnly
+            | MenuCommand::NumberLocale(_) => {}
+        }
+    }));
+    // Like `menu_dispatch`, so the route effect below switches with the current state.
+    let open_dataset_dispatch = use_hook(|| Rc::new(RefCell::new(None::<Box<dyn FnMut(i64)>>)));
+    *open_dataset_dispatch.borrow_mut() = Some(Box::new({
+        let switch_dataset = switch_dataset.clone();
+        move |dataset_id| {
+            switch_dataset.borrow_mut()(Some(dataset_id));
+            if selected_dataset_id() == Some(dataset_id) {
+                let groups = build_dataset_groups(&datasets());
+                *selected_group_key.write() = groups
+                    .iter()
+                    .find(|g| g.datasets.iter().any(|d| d.id.0 == dataset_id))
+                    .map(|g| g.key.clone());
+            }
+        }
+    }));
+    // Other pages may have renamed or deleted datasets or saved reports meanwhile; the
+    // dashboard may also have picked a dataset to open.
+    let shown_route = use_hook(|| Rc::new(Cell::new(Route::Dataset)));
+    use_effect({
+        let open_dataset_dispatch = open_dataset_dispatch.clone();
+        move || {
+            let Some(mut route) = route else {
+                return;
+            };
+            let current = (route.current)();
+            let previous = shown_route.replace(current);
+            if current != Route::Dataset {
+                return;
+            }
+            if previous != Route::Dataset {
+                controller.refresh_datasets();
+                saved_reports.set(controller.load_saved_reports());
+            }
+            let Some(dataset_id) = (route.open_dataset)() else {
+                return;
+            };
+            route.open_dataset.set(None);
+            if let Some(open) = open_dataset_dispatch.borrow_mut().as_mut() {
+                open(dataset_id);
+            }
+        }
+    });
+    let menu_commands = try_use_context::<MenuCommandState>().filter(|_| pane == 0);
+    use_effect(move || {
+        let paths = recent_imports();
+        if menu_commands.is_some() {
+            set_recent_imports_menu(&paths);
+        }
+    });
+    use_effect(move || {
+        let names = saved_reports()
+            .into_iter()
+            .map(|report| report.name)
+            .collect::<Vec<_>>();
+        if menu_commands.is_some() {
+            set_saved_reports_menu(&names);
+        }
+    });
+    use_effect({
+        let menu_dispatch = menu_dispatch.clone();
+        move || {
+            let Some(mut menu_commands) = menu_commands else {
+                return;
+            };
+            let Some(command) = (menu_commands.pending)() else {
+                return;
+            };
+            menu_commands.pending.set(None);
+            if let Some(dispatch) = menu_dispatch.borrow_mut().as_mut() {
+                dispatch(command);
+            }
+        }
+    });
+    use_effect({
+        let import_file = import_file.clone();
+        move || {
+            let Some(mut menu_commands) = menu_commands else {
+                return;
+            };
+            let Some(file_path) = (menu_commands.open_file)() else {
+                return;
+            };
+            menu_commands.open_file.set(None);
+            import_file.borrow_mut()(file_path);
+        }
+    });
 
-                div {
-                    DropdownSelect {
-                        id: DropdownId::Dataset,
-                        label: "資料集",
-                        options: dataset_options.clone(),
-                        selected: selected_group_key(),
-                        open_dropdown: open_dropdown,
-                        dropdown_pos: dropdown_pos,
-                        on_select: move |value: String| {
-                            let query_service_for_dataset_change =
-                                query_service_for_dataset_change_dropdown.clone();
-                            let groups = build_dataset_groups(&datasets());
-                            let next_group = if value == NONE_OPTION_VALUE {
-                                None::<String>
-                            } else {
-                                Some(value)
-                            };
-                            let next_dataset = next_group
-                                .as_ref()
-                                .and_then(|group_key| groups.iter().find(|g| &g.key == group_key))
-                                .and_then(|g| choose_default_dataset_id(&g.datasets));
-
-                            if is_editable_table && has_pending_changes {
-                                pending_action.set(Some(PendingAction::DatasetChange {
-                                    next_group: next_group.clone(),
-                                    next_dataset,
-                                }));
-                                show_save_prompt.set(true);
-                                return;
-                            }
+    let mut palette_items = vec![
+        (
+            PaletteEntry {
+                label: "匯入 CSV / XLSX…".to_string(),
+                hint: "檔案".to_string(),
+            },
+            PaletteAction::Menu(MenuCommand::Import),
+        ),
+        (
+            PaletteEntry {
+                label: "以對應設定匯入 CSV…".to_string(),
+                hint: "檔案".to_string(),
+            },
+            PaletteAction::Menu(MenuCommand::ImportWithMapping),
+        ),
+        (
+            PaletteEntry {
+                label: "匯入資料夾…".to_string(),
+                hint: "檔案".to_string(),
+            },
+            PaletteAction::Menu(MenuCommand::ImportFolder),
+        ),
+        (
+            PaletteEntry {
+                label: "匯出 CSV…".to_string(),
+                hint: "檔案".to_string(),
+            },
+            PaletteAction::Menu(MenuCommand::ExportCsv),
+        ),
+        (
+            PaletteEntry {
+                label: "匯出 Excel…".to_string(),
+                hint: "檔案".to_string(),
+            },
+            PaletteAction::Menu(MenuCommand::ExportXlsx),
+        ),
+        (
+            PaletteEntry {
+                label: "匯出為圖片…".to_string(),
+                hint: "檔案".to_string(),
+            },
+            PaletteAction::Menu(MenuCommand::ExportImage),
+        ),
+        (
+            PaletteEntry {
+                label: "備份資料庫…".to_string(),
+                hint: "檔案".to_string(),
+            },
+            PaletteAction::Menu(MenuCommand::BackupDatabase),
+        ),
+        (
+            PaletteEntry {
+                label: "遠端備份…".to_string(),
+                hint: "檔案".to_string(),
+            },
+            PaletteAction::Menu(MenuCommand::RemoteBackupSettings),
+        ),
+        (
+            PaletteEntry {
+                label: "匯出資料集封裝…".to_string(),
+                hint: "檔案".to_string(),
+            },
+            PaletteAction::Menu(MenuCommand::ExportBundle),
+        ),
+        (
+            PaletteEntry {
+                label: "匯入資料集封裝…".to_string(),
+                hint: "檔案".to_string(),
+            },
+            PaletteAction::Menu(MenuCommand::ImportBundle),
+        ),
+        (
+            PaletteEntry {
+                label: "總結報表".to_string(),
+                hint: "報表".to_string(),
+            },
+            PaletteAction::SummaryReport,
+        ),
+        (
+            PaletteEntry {
+                label: "報表產生器…".to_string(),
+                hint: "報表".to_string(),
+            },
+            PaletteAction::Menu(MenuCommand::ReportBuilder),
+        ),
+        (
+            PaletteEntry {
+                label: "執行自訂報表…".to_string(),
+                hint: "報表".to_string(),
+            },
+            PaletteAction::Menu(MenuCommand::RunReportScript),
+        ),
+        (
+            PaletteEntry {
+                label: "所有權人…".to_string(),
+                hint: "報表".to_string(),
+            },
+            PaletteAction::Menu(MenuCommand::DividendOwners),
+        ),
+        (
+            PaletteEntry {
+                label: "年度結轉…".to_string(),
+                hint: "報表".to_string(),
+            },
+            PaletteAction::Menu(MenuCommand::DividendYearRollover),
+        ),
+        (
+            PaletteEntry {
+                label: "數字格式…".to_string(),
+                hint: "檢視".to_string(),
+            },
+            PaletteAction::Menu(MenuCommand::NumberFormat),
+        ),
+        (
+            PaletteEntry {
+                label: "計算欄位…".to_string(),
+                hint: "檢視".to_string(),
+            },
+            PaletteAction::Menu(MenuCommand::ComputedColumns),
+        ),
+        (
+            PaletteEntry {
+                label: "顯示表格".to_string(),
+                hint: "檢視".to_string(),
+            },
+            PaletteAction::ShowTab(WorkspaceTab::Table),
+        ),
+        (
+            PaletteEntry {
+                label: "顯示圖表".to_string(),
+                hint: "檢視".to_string(),
+            },
+            PaletteAction::ShowTab(WorkspaceTab::Chart),
+        ),
+        (
+            PaletteEntry {
+                label: "載入示範資料".to_string(),
+                hint: "說明".to_string(),
+            },
+            PaletteAction::Menu(MenuCommand::LoadDemoData),
+        ),
+        (
+            PaletteEntry {
+                label: "開啟記錄檔".to_string(),
+                hint: "說明".to_string(),
+            },
+            PaletteAction::Menu(MenuCommand::OpenLogFile),
+        ),
+    ];
+    if route.is_some() {
+        for page in Route::ALL {
+            palette_items.push((
+                PaletteEntry {
+                    label: format!("前往：{}", page.label()),
+                    hint: "頁面".to_string(),
+                },
+                PaletteAction::Navigate(page),
+            ));
+        }
+    }
+    if split_view.is_some() {
+        palette_items.push((
+            PaletteEntry {
+                label: "切換分割檢視".to_string(),
+                hint: "檢視".to_string(),
+            },
+            PaletteAction::Menu(MenuCommand::ToggleSplitView),
+        ));
+    }
+    for (idx, report) in saved_reports().iter().enumerate() {
+        palette_items.push((
+            PaletteEntry {
+                label: format!("執行報表：{}", report.name),
+                hint: "報表".to_string(),
+            },
+            PaletteAction::Menu(MenuCommand::SavedReport(idx)),
+        ));
+    }
+    for dataset in datasets_snapshot
+        .iter()
+        .filter(|dataset| dataset.deleted_at.is_none())
+    {
+        palette_items.push((
+            PaletteEntry {
+                label: format!("切換資料集：{}", dataset.name),
+                hint: "資料集".to_string(),
+            },
+            PaletteAction::SwitchDataset(dataset.id.0),
+        ));
+    }
+    for (col_idx, header) in current_columns.iter().enumerate() {
+        let visible = visibility_snapshot
+            .get(&(col_idx as i64))
+            .copied()
+            .unwrap_or(true);
+        let name = column_display_name(header, col_idx, &aliases_snapshot);
+        palette_items.push((
+            PaletteEntry {
+                label: if visible {
+                    format!("隱藏欄位：{name}")
+                } else {
+                    format!("顯示欄位：{name}")
+                },
+                hint: "欄位".to_string(),
+            },
+            PaletteAction::SetColumnVisible(col_idx as i64, !visible),
+        ));
+    }
+    let (palette_entries, palette_actions): (Vec<_>, Vec<_>) = palette_items.into_iter().unzip();
+    let run_palette_action = {
+        let menu_dispatch = menu_dispatch.clone();
+        let handle_summary_report = handle_summary_report.clone();
+        let set_column_visible = set_column_visible.clone();
+        let open_dataset_dispatch = open_dataset_dispatch.clone();
+        move |entry_idx: usize| {
+            show_command_palette.set(false);
+            let Some(action) = palette_actions.get(entry_idx).cloned() else {
+                return;
+            };
+            match action {
+                PaletteAction::Menu(command) => {
+                    if let Some(dispatch) = menu_dispatch.borrow_mut().as_mut() {
+                        dispatch(command);
+                    }
+                }
+                PaletteAction::SwitchDataset(dataset_id) => {
+                    if let Some(open) = open_dataset_dispatch.borrow_mut().as_mut() {
+                        open(dataset_id);
+                    }
+                }
+                PaletteAction::SetColumnVisible(col_idx, visible) => {
+                    set_column_visible.borrow_mut()(col_idx, visible);
+                }
+                PaletteAction::SummaryReport => handle_summary_report.borrow_mut()(),
+                PaletteAction::ShowTab(tab) => workspace_tab.set(tab),
+                PaletteAction::Navigate(page) => {
+                    if let Some(mut route) = route {
+                        route.navigate(page);
+                    }
+                }
+            }
+        }
+    };
 
-                            *selected_group_key.write() = next_group;
-                            *selected_dataset_id.write() = next_dataset;
-                            *column_search_col.write() = None;
-                            *column_search_text.write() = String::new();
-                            *sort_col.write() = None;
-                            *sort_desc.write() = false;
-                            *page.write() = 0;
-                            staged_cells.write().clear();
-                            deleted_rows.write().clear();
-                            selected_rows.write().clear();
-                            edit_mode.set(false);
-                            *editing_cell.write() = None;
-                            editing_value.set(String::new());
-                            added_rows.write().clear();
-                            show_add_row.set(false);
-                            new_row_inputs.write().clear();
-                            context_menu.set(None);
-                            context_row.set(None);
-                            *busy.write() = true;
-
-                            let options = QueryOptions {
-                                global_search: global_search(),
-                                column_search_col: column_search_col(),
-                                column_search_text: column_search_text(),
-                                sort_col: sort_col(),
-                                sort_desc: sort_desc(),
-                            };
+    let handle_import_for_manager = handle_import.clone();
+    let handle_summary_report_for_toolbar = handle_summary_report.clone();
+    let handle_export_for_toolbar = handle_export.clone();
+    let handle_backup_for_toolbar = handle_backup.clone();
 
-                            match reload_page_data_usecase(
-                                &query_service_for_dataset_change,
-                                next_dataset,
-                                0,
-                                &options,
-                            ) {
-                                Ok((loaded_columns, loaded_rows, loaded_total, loaded_page)) => {
-                                    *columns.write() = loaded_columns;
-                                    *rows.write() = loaded_rows;
-                                    *total_rows.write() = loaded_total;
-                                    *page.write() = loaded_page;
-                                }
-                                Err(err) => {
-                                    *status.write() = format!("載入資料集失敗：{err}");
-                                }
+    // Also rendered over the welcome screen, where the first CSV is usually imported.
+    let import_mapping_view = rsx! {
+        if let Some(mapping) = import_mapping() {
+            ImportMappingDialog {
+                file_path: mapping.file_path.clone(),
+                profile: mapping.draft,
+                saved: mapping.saved,
+                required_columns: holdings_set.required.clone(),
+                unknown_headers: mapping.unknown_headers,
+                busy: busy().is(BusyKind::Import),
+                on_import: {
+                    let import_file_with_profile = import_file_with_profile.clone();
+                    let file_path = mapping.file_path.clone();
+                    move |profile: ImportProfile| {
+                        import_mapping.set(None);
+                        import_file_with_profile.borrow_mut()(file_path.clone(), profile);
+                    }
+                },
+                on_import_unmapped: {
+                    let file_path = mapping.file_path.clone();
+                    move |_| {
+                        import_mapping.set(None);
+                        controller.import_unmapped(&file_path);
+                    }
+                },
+                on_delete: {
+                    let file_path = mapping.file_path.clone();
+                    move |name: String| {
+                        controller.delete_import_profile(&name);
+                        if let Some((_, saved)) = controller.prepare_mapping_import(&file_path) {
+                            if let Some(current) = import_mapping.write().as_mut() {
+                                current.saved = saved;
                             }
-
-                            *busy.write() = false;
                         }
                     }
+                },
+                on_close: move |_| {
+                    import_mapping.set(None);
+                    controller.set_status("已取消匯入");
+                },
+            }
+        }
+    };
 
-                    if let Some(_active_group) = active_group {
-                        if assets_sheet.is_some() || holdings_sheet.is_some() {
-                            div { style: "display: flex; gap: 8px; align-items: center;",
-                                if let Some(assets_id) = assets_sheet {
-                                    button {
-                                        style: if selected_dataset_id() == Some(assets_id) {
-                                            "padding: 4px 10px; border: 1px solid #4c6ef5; background: #eef4ff; border-radius: 6px;"
-                                        } else {
-                                            "padding: 4px 10px; border: 1px solid #bbb; background: #fff; border-radius: 6px;"
-                                        },
-                                        onclick: move |_| {
-                                            switch_dataset_for_assets.borrow_mut()(Some(assets_id));
-                                        },
-                                        "資產總表"
+    if datasets_snapshot.is_empty() {
+        let handle_import_for_welcome = handle_import.clone();
+        let handle_import_folder_for_welcome = handle_import_folder.clone();
+        return rsx! {
+            div {
+                id: "{root_id}",
+                style: "{root_container_style_for_scroll(scroll_mode)}",
+                style { {FOCUS_STYLES} }
+                WelcomeScreen {
+                    busy: busy().any() || read_only,
+                    status: status(),
+                    holdings_columns: holdings_set.required.clone(),
+                    on_import: move |_| {
+                        handle_import_for_welcome.borrow_mut()();
+                    },
+                    on_import_folder: move |_| {
+                        handle_import_folder_for_welcome.borrow_mut()();
+                    },
+                    on_load_demo: move |_| controller.load_demo_data(),
+                    on_create_blank: move |_| {
+                        busy.write().begin(BusyKind::Save);
+                        let (meta, data) =
+                            blank_holdings_dataset(&column_sets.get(DatasetKind::Holdings));
+                        let created = run_blocking(|| {
+                            edit_service_for_blank
+                                .create_dataset(meta, data)
+                                .map_err(anyhow::Error::from)
+                        });
+                        match created {
+                            Ok(dataset_id) => {
+                                match query_service_for_blank.list_datasets(show_deleted()) {
+                                    Ok(available) => {
+                                        let groups = build_dataset_groups(&available);
+                                        *selected_group_key.write() = groups
+                                            .iter()
+                                            .find(|g| g.datasets.iter().any(|d| d.id == dataset_id))
+                                            .map(|g| g.key.clone());
+                                        *datasets.write() = available;
+                                        *selected_dataset_id.write() = Some(dataset_id.0);
+                                        match reload_page_data_usecase(
+                                            &query_service_for_blank,
+                                            Some(dataset_id.0),
+                                            0,
+                                            &QueryOptions::default(),
+                                        ) {
+                                            Ok((loaded_columns, loaded_rows, loaded_total, loaded_page)) => {
+                                                *columns.write() = loaded_columns;
+                                                *rows.write() = Arc::new(loaded_rows);
+                                                *total_rows.write() = loaded_total;
+                                                *page.write() = loaded_page;
+                                                page_size.set(PAGE_SIZE);
+                                                *status.write() = "已建立空白資料集".to_string();
+                                            }
+                                            Err(err) => {
+                                                report_error(status, last_error, "載入資料集失敗", &err);
+                                            }
+                                        }
                                     }
-                                }
-                                if let Some(holdings_id) = holdings_sheet {
-                                    button {
-                                        style: if selected_dataset_id() == Some(holdings_id) {
-                                            "padding: 4px 10px; border: 1px solid #4c6ef5; background: #eef4ff; border-radius: 6px;"
-                                        } else {
-                                            "padding: 4px 10px; border: 1px solid #bbb; background: #fff; border-radius: 6px;"
-                                        },
-                                        onclick: move |_| {
-                                            switch_dataset_for_holdings.borrow_mut()(Some(holdings_id));
-                                        },
-                                        "持股股息總表"
+                                    Err(err) => {
+                                        report_error(status, last_error, "更新資料集清單失敗", &err);
                                     }
                                 }
                             }
-                        } else {
-                            DropdownSelect {
-                                id: DropdownId::Sheet,
-                                label: "工作表",
-                                options: sheet_options.clone(),
-                                selected: selected_dataset_id().map(|id| id.to_string()),
-                                open_dropdown: open_dropdown,
-                                dropdown_pos: dropdown_pos,
-                                on_select: move |value: String| {
-                                    let next_dataset = value.parse::<i64>().ok();
-                                    switch_dataset_for_sheet.borrow_mut()(next_dataset);
-                                }
+                            Err(err) => {
+                                report_error(status, last_error, "建立資料集失敗", &err);
                             }
                         }
-                    }
-
-                    if selected_dataset_id().is_some() {
-                        label { "編輯模式" }
-                        input {
-                            r#type: "checkbox",
-                            checked: edit_mode_snapshot,
-                            onchange: move |event| {
-                            let checked = event.value().parse::<bool>().unwrap_or(false);
-                            edit_mode.set(checked);
-                        }
+                        busy.write().end(BusyKind::Save);
+                    },
+                }
+                if let Some(batch) = batch_import() {
+                    BatchImportDialog {
+                        batch,
+                        on_close: move |_| batch_import.set(None),
                     }
                 }
+                {import_mapping_view}
             }
+        };
+    }
+
+    rsx! {
+        div {
+            id: "{root_id}",
+            onclick: move |_| {
+                context_menu.set(None);
+                context_cell.set(None);
+                open_dropdown.set(None);
+            },
+            onkeydown: move |event| {
+                let modifiers = event.modifiers();
+                let is_k = matches!(event.key(), Key::Character(ref key) if key.eq_ignore_ascii_case("k"));
+                if is_k && (modifiers.ctrl() || modifiers.meta()) {
+                    event.prevent_default();
+                    show_command_palette.set(true);
+                }
+            },
+            oncontextmenu: move |event| {
+                event.prevent_default();
+            },
+            style: "{root_container_style_for_scroll(scroll_mode)}",
+            style { {FOCUS_STYLES} }
 
             div {
-                style: "display: flex; gap: 12px; align-items: center; margin: 12px 0;",
-                input {
-                    placeholder: "全域搜尋",
-                    oninput: move |event| global_search.set(event.value()),
-                }
-                button {
-                    disabled: busy(),
-                    onclick: {
-                        let query_service_for_global_search =
-                            query_service_for_global_search.clone();
-                        move |_| {
-                        if selected_dataset_id().is_none() {
+                id: "{scroll_id}",
+                style: "flex: 1 1 auto; min-height: 0; overflow: auto;",
+                h2 { "BOM" }
+
+                Toolbar {
+                    busy: busy(),
+                    read_only,
+                    has_dataset: selected_dataset_id().is_some(),
+                    status: status(),
+                    has_error: last_error().is_some(),
+                    selection_summary: selection_stats_snapshot.map(|stats| {
+                        format!(
+                            "已選 {} 列｜數值 {} 個｜總和 {}｜平均 {}",
+                            stats.rows,
+                            stats.count,
+                            format_number_with_commas(stats.sum, 2),
+                            format_number_with_commas(stats.average(), 2)
+                        )
+                    }),
+                    split_enabled: split_view.map(|split_view| (split_view.enabled)()),
+                    on_manage: move |_| match route {
+                        Some(mut route) => route.navigate(Route::DatasetManager),
+                        None => show_dataset_manager.set(true),
+                    },
+                    on_open_window: move |_| {
+                        let Some(dataset_id) = selected_dataset_id() else {
                             return;
+                        };
+                        let title = datasets()
+                            .iter()
+                            .find(|d| d.id.0 == dataset_id)
+                            .map(|d| format!("BOM - {}", d.name))
+                            .unwrap_or_else(|| "BOM".to_string());
+                        if let Err(err) = open_dataset_window(controller.services(), dataset_id, title) {
+                            controller.report("開啟新視窗失敗", &err);
                         }
-                        *busy.write() = true;
-                        let options = QueryOptions {
-                            global_search: global_search(),
-                            column_search_col: column_search_col(),
-                            column_search_text: column_search_text(),
-                            sort_col: sort_col(),
-                            sort_desc: sort_desc(),
+                    },
+                    on_export: move |_| handle_export_for_toolbar.borrow_mut()(),
+                    on_backup: move |_| handle_backup_for_toolbar.borrow_mut()(),
+                    on_toggle_split: move |_| {
+                        if let Some(split_view) = split_view.as_mut() {
+                            split_view.toggle(selected_dataset_id());
+                        }
+                    },
+                    on_summary: move |_| handle_summary_report_for_toolbar.borrow_mut()(),
+                    on_show_error: move |_| show_error_dialog.set(true),
+                }
+
+                DatasetPicker {
+                    dataset_options: dataset_options.clone(),
+                    sheet_options: sheet_options.clone(),
+                    selected_group: selected_group_key(),
+                    selected_dataset: selected_dataset_id(),
+                    has_active_group: active_group.is_some(),
+                    assets_sheet: assets_sheet,
+                    holdings_sheet: holdings_sheet,
+                    edit_mode: edit_mode_snapshot,
+                    read_only,
+                    open_dropdown: open_dropdown,
+                    dropdown_pos: dropdown_pos,
+                    on_group: move |value: String| {
+                        let groups = build_dataset_groups(&datasets());
+                        let next_group = if value == NONE_OPTION_VALUE {
+                            None::<String>
+                        } else {
+                            Some(value)
                         };
-                        match reload_page_data_usecase(
-                            &query_service_for_global_search,
-                            selected_dataset_id(),
-                            0,
-                            &options,
-                        ) {
-                            Ok((loaded_columns, loaded_rows, loaded_total, loaded_page)) => {
-                                *columns.write() = loaded_columns;
-                                *rows.write() = loaded_rows;
-                                *total_rows.write() = loaded_total;
-                                *page.write() = loaded_page;
+                        let next_dataset = next_group
+                            .as_ref()
+                            .and_then(|group_key| groups.iter().find(|g| &g.key == group_key))
+                            .and_then(|g| choose_default_dataset_id(&g.datasets));
+
+                        controller.guard_pending_changes(PendingAction::DatasetChange {
+                            next_group,
+                            next_dataset,
+                        });
+                    },
+                    on_sheet: move |next_dataset: Option<i64>| {
+                        switch_dataset_for_picker.borrow_mut()(next_dataset);
+                    },
+                    on_edit_mode: move |checked: bool| edit_mode.set(checked),
+                }
+
+                if let Some((primary_id, primary_name, primary_group)) = save_as_primary.clone() {
+                    div {
+                        role: "alert",
+                        style: "margin: 8px 0; padding: 6px 10px; background: #fff4d6; border: 1px solid #e0b44c; border-radius: 4px; display: flex; gap: 8px; align-items: center;",
+                        if let Some(name) = primary_name {
+                            span { "這是從「{name}」另存的舊內容，修改不會反映到原資料集。" }
+                            button {
+                                onclick: move |_| {
+                                    controller.guard_pending_changes(PendingAction::DatasetChange {
+                                        next_group: primary_group.clone(),
+                                        next_dataset: Some(primary_id),
+                                    });
+                                },
+                                "前往原資料集"
+                            }
+                        } else {
+                            span { "這是另存的舊內容，原資料集已刪除。" }
+                        }
+                    }
+                }
+
+            FilterBar {
+                busy: busy().is(BusyKind::Query),
+                columns: current_columns.clone(),
+                aliases: aliases_snapshot.clone(),
+                group_by: group_by_snapshot,
+                global_search,
+                column_search_col,
+                column_search_text,
+                sort_col,
+                sort_desc,
+                open_dropdown,
+                dropdown_pos,
+                on_search: move |error_context| controller.apply_filters(error_context),
+                on_global_search: move |_| {
+                    controller.apply_filters("搜尋失敗");
+                    let Some(dataset_id) = selected_dataset_id() else {
+                        return;
+                    };
+                    let current = search_history();
+                    let next = push_search_history(&current, &global_search());
+                    if next != current {
+                        controller.save_search_history(dataset_id, &next);
+                        search_history.set(next);
+                    }
+                },
+                search_history: search_history(),
+                on_search_history: move |action: SearchHistoryAction| {
+                    let Some(dataset_id) = selected_dataset_id() else {
+                        return;
+                    };
+                    let current = search_history();
+                    let next = match action {
+                        SearchHistoryAction::TogglePin(term) => {
+                            toggle_search_history_pin(&current, &term)
+                        }
+                        SearchHistoryAction::Delete(term) => current
+                            .iter()
+                            .filter(|entry| entry.term != term)
+                            .cloned()
+                            .collect(),
+                    };
+                    controller.save_search_history(dataset_id, &next);
+                    search_history.set(next);
+                },
+                on_group: move |next_group: Option<usize>| {
+                    group_by_col.set(next_group);
+                    collapsed_groups.set(BTreeSet::new());
+                },
+                if !current_columns.is_empty() {
+                    div { style: "margin-bottom: 12px;",
+                        ColumnVisibilityDropdown {
+                            id: DropdownId::ColumnVisibility,
+                            label: "欄位顯示",
+                            columns: current_columns.clone(),
+                            visibility: visibility_snapshot.clone(),
+                            aliases: aliases_snapshot.clone(),
+                            builtin_presets: builtin_visibility_presets(&current_columns, &holdings_set),
+                            saved_presets: visibility_presets(),
+                            open_dropdown: open_dropdown,
+                            dropdown_pos: dropdown_pos,
+                            on_rename: move |(col_idx, alias): (i64, String)| {
+                                let mut next_aliases = column_aliases();
+                                let alias = alias.trim().to_string();
+                                if alias.is_empty() {
+                                    next_aliases.remove(&col_idx);
+                                } else {
+                                    next_aliases.insert(col_idx, alias);
+                                }
+                                let Some(dataset_id) = selected_dataset_id() else {
+                                    return;
+                                };
+                                let result = run_blocking(|| {
+                                    query_service_for_alias_update
+                                        .upsert_column_aliases(DatasetId(dataset_id), next_aliases.clone())
+                                        .map_err(anyhow::Error::from)
+                                });
+                                match result {
+                                    Ok(()) => column_aliases.set(next_aliases),
+                                    Err(err) => {
+                                        report_error(status, last_error, "更新欄位別名失敗", &err);
+                                    }
+                                }
+                            },
+                            on_toggle: {
+                                let set_column_visible = set_column_visible.clone();
+                                move |(col_idx, visible)| set_column_visible.borrow_mut()(col_idx, visible)
+                            },
+                            on_apply_preset: {
+                                let columns_for_preset = current_columns.clone();
+                                let holdings_for_preset = holdings_set.clone();
+                                move |preset: BTreeMap<i64, bool>| {
+                                    let next_visibility = normalize_column_visibility(
+                                        &columns_for_preset,
+                                        &preset,
+                                        &holdings_for_preset,
+                                    );
+                                    column_visibility.set(next_visibility.clone());
+                                    if let Some(dataset_id) = selected_dataset_id() {
+                                        let result = run_blocking(|| {
+                                            query_service_for_preset_apply
+                                                .upsert_column_visibility(
+                                                    DatasetId(dataset_id),
+                                                    next_visibility.clone(),
+                                                )
+                                                .map_err(anyhow::Error::from)
+                                        });
+                                        if let Err(err) = result {
+                                            report_error(status, last_error, "更新欄位顯示失敗", &err);
+                                        }
+                                    }
+                                }
+                            },
+                            on_save_preset: move |name: String| {
+                                let name = name.trim().to_string();
+                                if name.is_empty() {
+                                    *status.write() = "檢視名稱不可空白".to_string();
+                                    return;
+                                }
+                                let Some(dataset_id) = selected_dataset_id() else {
+                                    return;
+                                };
+                                let snapshot = column_visibility();
+                                let result = run_blocking(|| {
+                                    query_service_for_preset_save
+                                        .save_visibility_preset(
+                                            DatasetId(dataset_id),
+                                            name.clone(),
+                                            snapshot.clone(),
+                                        )
+                                        .map_err(anyhow::Error::from)
+                                });
+                                match result {
+                                    Ok(()) => {
+                                        visibility_presets.write().insert(name.clone(), snapshot);
+                                        *status.write() = format!("已儲存檢視：{name}");
+                                    }
+                                    Err(err) => {
+                                        report_error(status, last_error, "儲存檢視失敗", &err);
+                                    }
+                                }
+                            },
+                            on_delete_preset: move |name: String| {
+                                let Some(dataset_id) = selected_dataset_id() else {
+                                    return;
+                                };
+                                let result = run_blocking(|| {
+                                    query_service_for_preset_delete
+                                        .delete_visibility_preset(DatasetId(dataset_id), name.clone())
+                                        .map_err(anyhow::Error::from)
+                                });
+                                match result {
+                                    Ok(()) => {
+                                        visibility_presets.write().remove(&name);
+                                    }
+                                    Err(err) => {
+                                        report_error(status, last_error, "刪除檢視失敗", &err);
+                                    }
+                                }
+                            },
+                        }
+                        button {
+                            style: "margin-left: 8px;",
+                            onclick: move |_| {
+                                show_column_format.set(true);
+                            },
+                            "數字格式"
+                        }
+                        label { style: "margin-left: 12px;",
+                            input {
+                                r#type: "checkbox",
+                                checked: hide_empty(),
+                                onchange: move |event| hide_empty.set(event.checked()),
                             }
-                            Err(err) => {
-                                *status.write() = format!("搜尋失敗：{err}");
+                            " 隱藏空白欄位"
+                            if hide_empty() && !empty_columns().is_empty() {
+                                span { style: "margin-left: 4px; color: #666;",
+                                    "（{empty_columns().len()} 欄）"
+                                }
                             }
                         }
-                        *busy.write() = false;
+                        label { style: "margin-left: 12px;",
+                            input {
+                                r#type: "checkbox",
+                                checked: show_saved_changes(),
+                                onchange: move |event| show_saved_changes.set(event.checked()),
+                            }
+                            " 標示上次儲存的變更"
                         }
-                    },
-                    "搜尋"
-                }
-            }
-
-            if !current_columns.is_empty() {
-                div { style: "margin-bottom: 12px;",
-                    ColumnVisibilityDropdown {
-                        id: DropdownId::ColumnVisibility,
-                        label: "欄位顯示",
-                        columns: current_columns.clone(),
-                        visibility: visibility_snapshot.clone(),
-                        open_dropdown: open_dropdown,
-                        dropdown_pos: dropdown_pos,
-                        on_toggle: move |(col_idx, visible)| {
-                            let mut next_visibility = column_visibility();
-                            next_visibility.insert(col_idx, visible);
-                            column_visibility.set(next_visibility.clone());
-                            if let Some(dataset_id) = selected_dataset_id() {
-                                let result = run_blocking(|| {
-                                    query_service_for_visibility_update
-                                        .upsert_column_visibility(
-                                            DatasetId(dataset_id),
-                                            next_visibility.clone(),
-                                        )
-                                        .map_err(|err| anyhow!(err.to_string()))
-                                });
-                                if let Err(err) = result {
-                                    *status.write() = format!("更新欄位顯示失敗：{err}");
+                        if !cell_origins_snapshot.is_empty() {
+                            label { style: "margin-left: 12px;",
+                                title: "以匯入檔案中的值取代轉換後的值顯示",
+                                input {
+                                    r#type: "checkbox",
+                                    checked: show_raw_values(),
+                                    onchange: move |event| show_raw_values.set(event.checked()),
                                 }
+                                " 顯示原始值"
                             }
                         }
-                    }
-                }
-            }
-
-            if !current_columns.is_empty() {
-                div { style: "margin-bottom: 12px;",
-                    DropdownSelect {
-                        id: DropdownId::Column,
-                        label: "欄位",
-                        options: column_options.clone(),
-                        selected: Some(
-                            column_search_col()
-                                .map(|idx| idx.to_string())
-                                .unwrap_or_else(|| NONE_OPTION_VALUE.to_string()),
-                        ),
-                        open_dropdown: open_dropdown,
-                        dropdown_pos: dropdown_pos,
-                        on_select: move |value: String| {
-                            if value == NONE_OPTION_VALUE {
-                                column_search_col.set(None);
-                                return;
+                        if show_saved_changes() {
+                            span { style: "margin-left: 8px; font-size: 12px; color: #555;",
+                                span { style: "display: inline-block; width: 10px; height: 10px; margin: 0 4px 0 0; background: {CHANGED_CELL_BACKGROUND}; border: 1px solid #bbb;" }
+                                "已修改 {changed_cell_count} 格"
+                                span { style: "display: inline-block; width: 10px; height: 10px; margin: 0 4px 0 10px; background: {ADDED_CELL_BACKGROUND}; border: 1px solid #bbb;" }
+                                "新增 {added_row_count} 列"
                             }
-                            let idx = value.parse::<i64>().ok();
-                            column_search_col.set(idx);
                         }
                     }
-                    input {
-                        placeholder: "欄位搜尋",
-                        value: column_search_text(),
-                        oninput: move |event| column_search_text.set(event.value()),
-                    }
-                    button {
-                        disabled: busy(),
-                        onclick: move |_| {
-                            if selected_dataset_id().is_none() {
-                                return;
-                            }
-                            *busy.write() = true;
-                            let options = QueryOptions {
-                                global_search: global_search(),
-                                column_search_col: column_search_col(),
-                                column_search_text: column_search_text(),
-                                sort_col: sort_col(),
-                                sort_desc: sort_desc(),
-                            };
-                            match reload_page_data_usecase(
-                                &query_service_for_column_search,
-                                selected_dataset_id(),
-                                0,
-                                &options,
-                            ) {
-                                Ok((loaded_columns, loaded_rows, loaded_total, loaded_page)) => {
-                                    *columns.write() = loaded_columns;
-                                    *rows.write() = loaded_rows;
-                                    *total_rows.write() = loaded_total;
-                                    *page.write() = loaded_page;
-                                }
-                                Err(err) => {
-                                    *status.write() = format!("欄位搜尋失敗：{err}");
-                                }
-                            }
-                            *busy.write() = false;
-                        },
-                        "欄位搜尋"
-                    }
                 }
             }
 
-            if !current_columns.is_empty() {
-                div { style: "margin-bottom: 12px;",
-                    DropdownSelect {
-                        id: DropdownId::Sort,
-                        label: "排序",
-                        options: sort_options.clone(),
-                        selected: Some(
-                            sort_col()
-                                .map(|idx| idx.to_string())
-                                .unwrap_or_else(|| NONE_OPTION_VALUE.to_string()),
-                        ),
-                        open_dropdown: open_dropdown,
-                        dropdown_pos: dropdown_pos,
-                        on_select: move |value: String| {
-                            if value == NONE_OPTION_VALUE {
-                                sort_col.set(None);
-                                return;
-                            }
-                            let idx = value.parse::<i64>().ok();
-                            sort_col.set(idx);
-                        }
-                    }
-                    button {
-                        disabled: busy(),
-                        onclick: move |_| {
-                            if selected_dataset_id().is_none() {
-                                return;
-                            }
-                            sort_desc.set(!sort_desc());
-                            *busy.write() = true;
-                            let options = QueryOptions {
-                                global_search: global_search(),
-                                column_search_col: column_search_col(),
-                                column_search_text: column_search_text(),
-                                sort_col: sort_col(),
-                                sort_desc: sort_desc(),
-                            };
-                            match reload_page_data_usecase(
-                                &query_service_for_sort_toggle,
-                                selected_dataset_id(),
-                                0,
-                                &options,
-                            ) {
-                                Ok((loaded_columns, loaded_rows, loaded_total, loaded_page)) => {
-                                    *columns.write() = loaded_columns;
-                                    *rows.write() = loaded_rows;
-                                    *total_rows.write() = loaded_total;
-                                    *page.write() = loaded_page;
-                                }
-                                Err(err) => {
-                                    *status.write() = format!("排序失敗：{err}");
-                                }
+            if !quick_filters().is_empty() {
+                QuickFilterChips {
+                    columns: quick_filters(),
+                    selected: value_filters(),
+                    busy: busy().is(BusyKind::Query),
+                    on_toggle: move |(col_idx, value): (i64, String)| {
+                        {
+                            let mut filters = value_filters.write();
+                            let values = filters.entry(col_idx).or_default();
+                            if !values.remove(&value) {
+                                values.insert(value);
                             }
-                            *busy.write() = false;
-                        },
-                        if sort_desc() { "降冪" } else { "升冪" }
-                    }
-                    button {
-                        disabled: busy(),
-                        onclick: move |_| {
-                            if selected_dataset_id().is_none() {
-                                return;
-                            }
-                            *busy.write() = true;
-                            let options = QueryOptions {
-                                global_search: global_search(),
-                                column_search_col: column_search_col(),
-                                column_search_text: column_search_text(),
-                                sort_col: sort_col(),
-                                sort_desc: sort_desc(),
-                            };
-                            match reload_page_data_usecase(
-                                &query_service_for_sort_select,
-                                selected_dataset_id(),
-                                0,
-                                &options,
-                            ) {
-                                Ok((loaded_columns, loaded_rows, loaded_total, loaded_page)) => {
-                                    *columns.write() = loaded_columns;
-                                    *rows.write() = loaded_rows;
-                                    *total_rows.write() = loaded_total;
-                                    *page.write() = loaded_page;
-                                }
-                                Err(err) => {
-                                    *status.write() = format!("排序失敗：{err}");
-                                }
+                            if values.is_empty() {
+                                filters.remove(&col_idx);
                             }
-                            *busy.write() = false;
-                        },
-                        "套用排序"
-                    }
+                        }
+                        controller.apply_filters("快速篩選失敗");
+                    },
+                    on_clear: move |_| {
+                        value_filters.set(BTreeMap::new());
+                        controller.apply_filters("快速篩選失敗");
+                    },
                 }
             }
 
             if editing_enabled {
                 div { style: "margin-bottom: 12px; display: flex; gap: 8px;",
-                    button {
-                        disabled: busy(),
-                        onclick: move |_| {
-                            show_add_row.set(true);
-                        },
-                        "新增列"
+                    if is_assets {
+                        button {
+                            disabled: busy().is(BusyKind::Save),
+                            onclick: move |_| show_account_form.set(true),
+                            "新增帳戶"
+                        }
+                        button {
+                            disabled: busy().is(BusyKind::Save) || balance_update.is_none(),
+                            onclick: move |_| show_balance_update.set(true),
+                            "更新餘額"
+                        }
+                    } else {
+                        button {
+                            disabled: busy().is(BusyKind::Save),
+                            onclick: move |_| {
+                                show_add_row.set(true);
+                            },
+                            "新增列"
+                        }
+                        if dividend_receipt_rows.is_some() {
+                            button {
+                                disabled: busy().is(BusyKind::Save),
+                                onclick: move |_| show_dividend_receipts.set(true),
+                                "標記本月已收"
+                            }
+                        }
                     }
                     button {
-                        disabled: busy() || selected_rows_snapshot.is_empty(),
+                        disabled: busy().is(BusyKind::Save) || selected_rows_snapshot.is_empty(),
                         onclick: move |_| {
                             let targets = selected_rows();
                             if targets.is_empty() {
@@ -1197,10 +2950,10 @@ window.removeEventListener("resize", sendState);
                             selected_rows.write().clear();
                             *status.write() = "已標記刪除（待儲存）".to_string();
                         },
-                        "刪除選取列"
+                        if is_assets { "刪除選取帳戶" } else { "刪除選取列" }
                     }
                     button {
-                        disabled: busy() || selected_rows_snapshot.is_empty(),
+                        disabled: busy().is(BusyKind::Save) || selected_rows_snapshot.is_empty(),
                         onclick: move |_| {
                             let targets = selected_rows();
                             if targets.is_empty() {
@@ -1215,257 +2968,466 @@ window.removeEventListener("resize", sendState);
                         "恢復選取列"
                     }
                     button {
-                        disabled: busy() || !has_pending_changes,
+                        disabled: modified_row_set.is_empty(),
+                        onclick: {
+                            let mut go_to_modified = go_to_modified.clone();
+                            move |_| go_to_modified(false)
+                        },
+                        "上一個修改"
+                    }
+                    button {
+                        disabled: modified_row_set.is_empty(),
+                        onclick: {
+                            let mut go_to_modified = go_to_modified.clone();
+                            move |_| go_to_modified(true)
+                        },
+                        "下一個修改"
+                    }
+                    button {
+                        disabled: busy().is(BusyKind::Save) || !has_pending_changes,
                         onclick: move |_| {
                             show_save_prompt.set(true);
                         },
                         "儲存變更"
+                        if pending_changes > 0 {
+                            span {
+                                style: "margin-left: 6px; padding: 0 6px; border-radius: 9px; background: #d24; color: #fff; font-size: 12px;",
+                                aria_label: "{pending_changes} 項未儲存變更",
+                                "{pending_changes}"
+                            }
+                        }
                     }
                 }
             }
 
+            if show_account_form() {
+                AccountForm {
+                    columns: current_columns_for_add.to_vec(),
+                    rows: account_rows,
+                    locale: number_locale,
+                    on_add: {
+                        let current_columns_for_add = current_columns_for_add.clone();
+                        move |account: AssetAccount| {
+                            added_rows.write().push(account.to_row(&current_columns_for_add));
+                            show_account_form.set(false);
+                            *status.write() =
+                                format!("已新增帳戶 {} {}（待儲存）", account.institution, account.account);
+                        }
+                    },
+                    on_close: move |_| show_account_form.set(false),
+                }
+            }
+
+            if let (true, Some((balance_idx, row_indices, balances))) =
+                (show_balance_update(), balance_update.clone())
+            {
+                BalanceUpdateDialog {
+                    columns: current_columns_for_add.to_vec(),
+                    rows: current_rows.to_vec(),
+                    row_indices,
+                    balances: balances.clone(),
+                    locale: number_locale,
+                    on_finish: {
+                        let current_rows = current_rows.clone();
+                        let column = current_columns_for_add[balance_idx].clone();
+                        move |updated: BTreeMap<usize, String>| {
+                            let mut changed = 0;
+                            let mut staged = staged_cells.write();
+                            for (row_idx, value) in updated {
+                                if balances.get(&row_idx) != Some(&value) {
+                                    changed += 1;
+                                }
+                                let key = CellKey {
+                                    row_idx,
+                                    col_idx: balance_idx,
+                                    column: column.clone(),
+                                };
+                                let stored = current_rows
+                                    .get(row_idx)
+                                    .and_then(|row| row.get(balance_idx));
+                                if stored == Some(&value) {
+                                    staged.remove(&key);
+                                } else {
+                                    staged.insert(key, value);
+                                }
+                            }
+                            drop(staged);
+                            show_balance_update.set(false);
+                            *status.write() = format!("已更新 {changed} 筆餘額（待儲存）");
+                        }
+                    },
+                    on_close: move |_| show_balance_update.set(false),
+                }
+            }
+
+            if let (true, Some(receipt_rows)) =
+                (show_dividend_receipts(), dividend_receipt_rows.clone())
+            {
+                DividendReceiptsDialog {
+                    columns: current_columns_for_add.to_vec(),
+                    rows: receipt_rows,
+                    deleted_rows: deleted_rows_snapshot.clone(),
+                    initial_month: chrono::Local::now().month(),
+                    locale: number_locale,
+                    on_finish: {
+                        let columns = current_columns_for_add.clone();
+                        move |(month_idx, marked): (usize, BTreeMap<usize, String>)| {
+                            let count = marked.len();
+                            let mut staged = staged_cells.write();
+                            for (row_idx, value) in marked {
+                                let key = CellKey {
+                                    row_idx,
+                                    col_idx: month_idx,
+                                    column: columns[month_idx].clone(),
+                                };
+                                staged.insert(key, value);
+                            }
+                            drop(staged);
+                            show_dividend_receipts.set(false);
+                            *status.write() =
+                                format!("已標記 {count} 筆{}已收（待儲存）", columns[month_idx]);
+                        }
+                    },
+                    on_close: move |_| show_dividend_receipts.set(false),
+                }
+            }
+
             if show_add_row() {
-                div {
-                    style: "position: fixed; inset: 0; background: rgba(0,0,0,0.35); display: flex; align-items: center; justify-content: center; z-index: 1100;",
-                    div {
-                        style: "background: #fff; padding: 16px; border: 1px solid #999; min-width: 300px;",
-                        div { style: "margin-bottom: 8px; font-weight: 600;", "新增列" }
-                        div { style: "display: grid; grid-template-columns: 120px 1fr; gap: 6px;",
-                            {current_columns_for_add.iter().map(|header| {
-                                let header_for_input = header.clone();
-                                rsx!(
-                                    label { "{header}" }
-                                    input {
-                                        value: new_row_inputs().get(header).cloned().unwrap_or_default(),
-                                        oninput: move |event| {
-                                            new_row_inputs
-                                                .write()
-                                                .insert(header_for_input.clone(), event.value());
-                                        }
+                ModalDialog {
+                    label: "新增列".to_string(),
+                    z_index: 1100,
+                    panel_style: "min-width: 300px;",
+                    on_close: move |_| {
+                        show_add_row.set(false);
+                        new_row_inputs.write().clear();
+                    },
+                    div { style: "margin-bottom: 8px; font-weight: 600;", "新增列" }
+                    div { style: "display: grid; grid-template-columns: 120px 1fr; gap: 6px;",
+                        {current_columns_for_add.iter().map(|header| {
+                            let header_for_input = header.clone();
+                            rsx!(
+                                label { "{header}" }
+                                input {
+                                    value: new_row_inputs().get(header).cloned().unwrap_or_default(),
+                                    oninput: move |event| {
+                                        new_row_inputs
+                                            .write()
+                                            .insert(header_for_input.clone(), event.value());
                                     }
-                                )
-                            })}
-                        }
-                        div { style: "display: flex; gap: 8px;",
-                            button {
-                                onclick: move |_| {
-                                    let current_columns_for_add = current_columns_for_add.clone();
-                                    let mut row = vec![String::new(); current_columns_for_add.len()];
-                                    for (idx, header) in current_columns_for_add.iter().enumerate() {
-                                        if let Some(value) = new_row_inputs().get(header).cloned() {
-                                            row[idx] = value;
-                                        }
+                                }
+                            )
+                        })}
+                    }
+                    div { style: "display: flex; gap: 8px;",
+                        button {
+                            onclick: move |_| {
+                                let current_columns_for_add = current_columns_for_add.clone();
+                                let mut row = vec![String::new(); current_columns_for_add.len()];
+                                for (idx, header) in current_columns_for_add.iter().enumerate() {
+                                    if let Some(value) = new_row_inputs().get(header).cloned() {
+                                        row[idx] = value;
                                     }
-                                    let validation = if is_holdings {
-                                        validate_required_holdings_row(&current_columns_for_add, &row)
-                                    } else {
-                                        Ok(())
-                                    };
-                                    match validation {
-                                        Ok(_) => {
-                                            added_rows.write().push(row);
-                                            show_add_row.set(false);
-                                            new_row_inputs.write().clear();
-                                            *status.write() = "已新增列（待儲存）".to_string();
-                                        }
-                                        Err(err) => {
-                                            *status.write() = format!("新增列失敗：{err}");
-                                        }
+                                }
+                                let validation = if is_holdings {
+                                    validate_required_holdings_row(
+                                        &current_columns_for_add,
+                                        &row,
+                                        &required_columns_for_add,
+                                    )
+                                } else {
+                                    Ok(())
+                                };
+                                match validation {
+                                    Ok(_) => {
+                                        added_rows.write().push(row);
+                                        show_add_row.set(false);
+                                        new_row_inputs.write().clear();
+                                        *status.write() = "已新增列（待儲存）".to_string();
                                     }
-                                },
-                                "新增"
-                            }
-                            button {
-                                onclick: move |_| {
-                                    show_add_row.set(false);
-                                    new_row_inputs.write().clear();
-                                },
-                                "取消"
-                            }
+                                    Err(err) => {
+                                        report_error(status, last_error, "新增列失敗", &err);
+                                    }
+                                }
+                            },
+                            "新增"
+                        }
+                        button {
+                            onclick: move |_| {
+                                show_add_row.set(false);
+                                new_row_inputs.write().clear();
+                            },
+                            "取消"
                         }
                     }
                 }
             }
 
-            div {
-                style: "{table_container_style_for_scroll(scroll_mode)}{table_overflow_style_for_scroll(scroll_mode, table_header_stuck())} flex: 0 0 auto; min-height: calc(100vh - 72px); overflow: visible;",
-                table { style: "border-collapse: collapse; width: 100%; background: #fff;",
-                    thead { id: "table-head",
-                        tr {
-                            if editing_enabled {
-                                th { style: "{table_header_cell_style()}",
-                                    input {
-                                        r#type: "checkbox",
-                                        checked: all_rows_selected,
-                                        onclick: move |_| {
-                                            if all_rows_selected {
-                                                selected_rows.write().clear();
-                                                return;
-                                            }
-                                            let mut next = selected_rows.write();
-                                            next.clear();
-                                            for idx in 0..table_rows_len {
-                                                next.insert(idx);
-                                            }
-                                            for idx in 0..table_added_rows_len {
-                                                next.insert(base_row_count + idx);
-                                            }
-                                        }
+            div { style: "display: flex; gap: 4px; margin-bottom: 8px;",
+                for (tab, tab_label) in [(WorkspaceTab::Table, "表格"), (WorkspaceTab::Chart, "圖表")] {
+                    button {
+                        style: if workspace_tab() == tab { "background: #eef4ff; border: 1px solid #4a7bd0;" } else { "" },
+                        onclick: move |_| workspace_tab.set(tab),
+                        "{tab_label}"
+                    }
+                }
+            }
+
+            if workspace_tab() == WorkspaceTab::Chart {
+                ChartPanel {
+                    key: "{selected_dataset_id():?}",
+                    columns: current_columns.clone(),
+                    labels: current_columns
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, header)| column_display_name(header, idx, &aliases_snapshot))
+                        .collect::<Vec<_>>(),
+                    rows: current_rows.clone(),
+                    formats: column_formats_snapshot.clone(),
+                    svg_id: pane_element_id("chart-svg", pane),
+                    on_status: move |message: String| {
+                        *status.write() = message;
+                    },
+                }
+            } else {
+                if !current_columns.is_empty() {
+                    FormulaBar {
+                        cell: formula_cell.clone(),
+                        on_commit: {
+                            let required_columns = required_columns.clone();
+                            move |text: String| {
+                                let Some(key) = focused_cell() else {
+                                    return;
+                                };
+                                let computed_now = computed_columns();
+                                let stored_len = columns().len().saturating_sub(computed_now.len());
+                                if let Some(computed_idx) = key.col_idx.checked_sub(stored_len) {
+                                    let Some(dataset_id) = selected_dataset_id() else {
+                                        return;
+                                    };
+                                    let mut next = computed_now;
+                                    let Some(column) = next.get_mut(computed_idx) else {
+                                        return;
+                                    };
+                                    column.expression =
+                                        text.trim().trim_start_matches('=').trim().to_string();
+                                    if controller.save_computed_columns(dataset_id, next.clone()) {
+                                        computed_columns.set(next);
                                     }
+                                    return;
                                 }
+                                let column_type = column_type_at(&page_column_types(), key.col_idx);
+                                let next_value = stored_cell_edit(
+                                    column_type,
+                                    &text,
+                                    number_locale,
+                                    column_formats().get(&(key.col_idx as i64)),
+                                );
+                                if let Err(message) = validate_cell_edit(
+                                    &key.column,
+                                    column_type,
+                                    &next_value,
+                                    &required_columns,
+                                ) {
+                                    *status.write() = message;
+                                    return;
+                                }
+                                staged_cells.write().insert(key, next_value);
                             }
-                            for (_col_idx, header) in table_columns.iter() {
-                                th { style: "{table_header_cell_style()}", "{header}" }
-                            }
-                        }
+                        },
                     }
-                    tbody {
-                        {table_rows.iter().enumerate().map(|(row_idx, row)| {
-                        let table_columns = table_columns.clone();
-                        let editable_columns = editable_columns.clone();
-                        let required_columns = required_columns.clone();
-                        let column_alignments = column_alignments.clone();
-                        let staged_cells_for_row = staged_cells_snapshot.clone();
-                        let row = row.clone();
-                        let row_selected = selected_rows_snapshot.contains(&row_idx);
-                        let row_deleted = deleted_rows_snapshot.contains(&row_idx);
-                        let row_background = if row_selected { "#eef4ff" } else { "transparent" };
-                        let row_border = if row_deleted { "#d24" } else { "transparent" };
-                        let row_style =
-                            format!("background: {row_background}; border-top: 2px solid {row_border}; border-bottom: 2px solid {row_border};");
-                        rsx!(
+                }
+                if paged {
+                    div {
+                        role: "status",
+                        style: "margin: 8px 0; padding: 6px 10px; background: #fff4d6; border: 1px solid #e0b44c; border-radius: 4px;",
+                        "共 {current_total_rows} 列，超過每頁 {current_page_size} 列，已改為分頁顯示（第 {page() + 1} / {page_count(current_total_rows, current_page_size)} 頁）。分頁時無法編輯，可先篩選縮小範圍或載入全部。"
+                    }
+                }
+                if !table_display.is_default() {
+                    div { style: "margin: 4px 0; color: #555; font-size: 12px;",
+                        "金額單位：{table_display.caption()}"
+                    }
+                }
+                div {
+                    style: "{table_container_style_for_scroll(scroll_mode)}{table_overflow_style_for_scroll(scroll_mode, table_header_stuck())} flex: 0 0 auto; min-height: calc(100vh - 72px); overflow: visible;",
+                    table { style: "border-collapse: collapse; width: 100%; background: #fff;",
+                        thead { id: "{table_head_id}",
                             tr {
-                                style: "{row_style}",
                                 if editing_enabled {
-                                    td { style: "border: 1px solid #bbb; padding: 4px; text-align: center;",
+                                    th { style: "{table_header_cell_style()}",
                                         input {
                                             r#type: "checkbox",
-                                            checked: selected_rows_snapshot.contains(&row_idx),
+                                            aria_label: "選取全部列",
+                                            checked: all_rows_selected,
                                             onclick: move |_| {
-                                                let mut selected = selected_rows.write();
-                                                if selected.contains(&row_idx) {
-                                                    selected.remove(&row_idx);
-                                                } else {
-                                                    selected.insert(row_idx);
+                                                if all_rows_selected {
+                                                    selected_rows.write().clear();
+                                                    return;
+                                                }
+                                                let mut next = selected_rows.write();
+                                                next.clear();
+                                                for idx in 0..table_rows_len {
+                                                    next.insert(idx);
+                                                }
+                                                for idx in 0..table_added_rows_len {
+                                                    next.insert(base_row_count + idx);
                                                 }
                                             }
                                         }
                                     }
                                 }
-                                {row.iter().enumerate().map(|(visible_idx, value)| {
-                                    let value = value.clone();
-                                    let (col_idx, header) = table_columns
-                                        .get(visible_idx)
-                                        .cloned()
-                                        .unwrap_or((0, String::new()));
-                                    let alignment = column_alignments
-                                        .get(visible_idx)
-                                        .copied()
-                                        .unwrap_or("left");
-                                    let required_columns_for_cell = required_columns.clone();
-                                    let editable_columns_for_cell = editable_columns.clone();
-                                    let cell_key = CellKey {
-                                        row_idx,
-                                        col_idx,
-                                        column: header.clone(),
-                                    };
-                                    let staged_value = staged_cells_for_row
-                                        .get(&cell_key)
-                                        .cloned()
-                                        .unwrap_or_else(|| value.clone());
-                                    let formatted = format_cell_value(&header, &staged_value);
-                                    let is_editing = editing_cell_snapshot.as_ref() == Some(&cell_key);
-                                    if is_editing {
-                                        rsx!(
-                                            td {
-                                                style: "border: 1px solid #bbb; padding: 4px; text-align: {alignment};",
-                                                input {
-                                                    value: editing_value(),
-                                                    oninput: move |event| {
-                                                        editing_value.set(event.value());
-                                                    },
-                                                    onkeydown: move |event| {
-                                                        if event.key() == Key::Enter {
-                                                            let next_value = editing_value();
-                                                            if required_columns_for_cell.contains(&header)
-                                                                && next_value.trim().is_empty()
-                                                            {
-                                                                *status.write() = "必填欄位不可空白".to_string();
-                                                                return;
-                                                            }
-                                                            let numeric_required = matches!(
-                                                                header.as_str(),
-                                                                "買進" | "市價" | "數量" | "期數"
-                                                            );
-                                                            if numeric_required
-                                                                && parse_numeric_value(&next_value).is_none()
-                                                            {
-                                                                *status.write() =
-                                                                    format!("欄位 {} 必須是數字", header);
-                                                                return;
+                                for ((col_idx, header), tooltip) in table_columns.iter().zip(header_tooltips.iter()) {
+                                    th { style: "{table_header_cell_style()}", title: "{tooltip}",
+                                        "{column_display_name(header, *col_idx, &aliases_snapshot)}"
+                                    }
+                                }
+                            }
+                            tr {
+                                if editing_enabled {
+                                    th { style: "{COLUMN_CONDITION_CELL_STYLE}" }
+                                }
+                                for (col_idx, expression, border) in condition_cells.iter().cloned() {
+                                    th { style: "{COLUMN_CONDITION_CELL_STYLE}",
+                                        input {
+                                            aria_label: "篩選條件",
+                                            placeholder: "篩選",
+                                            title: "例：> 1000、<= 0、!= 0、1~5、空、非空；Enter 套用",
+                                            style: "width: 100%; min-width: 48px; box-sizing: border-box; font-size: 12px; border: 1px solid {border};",
+                                            value: expression,
+                                            oninput: move |event| {
+                                                let value = event.value();
+                                                let mut conditions = column_conditions.write();
+                                                if value.trim().is_empty() {
+                                                    conditions.remove(&col_idx);
+                                                } else {
+                                                    conditions.insert(col_idx, value);
+                                                }
+                                            },
+                                            onchange: move |_| controller.apply_filters("篩選失敗"),
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        tbody {
+                            {table_entries.iter().map(|entry| {
+                            let row_idx = match entry {
+                                TableEntry::Row(row_idx) => *row_idx,
+                                TableEntry::Group { key, row_indices, subtotals, collapsed } => {
+                                    let key_for_toggle = key.clone();
+                                    let group_rows_for_select = row_indices.clone();
+                                    let group_selected = row_indices
+                                        .iter()
+                                        .all(|idx| selected_rows_snapshot.contains(idx));
+                                    let arrow = if *collapsed { "▸" } else { "▾" };
+                                    let label = if key.is_empty() { "(空白)" } else { key.as_str() };
+                                    let count = row_indices.len();
+                                    let cells: Vec<String> = table_columns
+                                        .iter()
+                                        .zip(subtotals.iter())
+                                        .map(|((col_idx, header), subtotal)| {
+                                            subtotal
+                                                .map(|sum| {
+                                                    format_display_cell_value(
+                                                        header,
+                                                        column_type_at(&table_types, *col_idx),
+                                                        &sum.to_string(),
+                                                        table_formats.get(&(*col_idx as i64)),
+                                                        &table_display,
+                                                    )
+                                                })
+                                                .unwrap_or_default()
+                                        })
+                                        .collect();
+                                    let first_cell = cells.first().cloned().unwrap_or_default();
+                                    return rsx!(
+                                        tr {
+                                            key: "group-{key}",
+                                            style: "background: #f4f6fa; font-weight: 600;",
+                                            if editing_enabled {
+                                                td { style: "border: 1px solid #bbb; padding: 4px; text-align: center;",
+                                                    input {
+                                                        r#type: "checkbox",
+                                                        aria_label: "選取群組 {label}",
+                                                        checked: group_selected,
+                                                        onclick: move |_| {
+                                                            let mut selected = selected_rows.write();
+                                                            for idx in group_rows_for_select.iter() {
+                                                                if group_selected {
+                                                                    selected.remove(idx);
+                                                                } else {
+                                                                    selected.insert(*idx);
+                                                                }
                                                             }
-                                                            staged_cells
-                                                                .write()
-                                                                .insert(cell_key.clone(), next_value.clone());
-                                                            *editing_cell.write() = None;
-                                                            editing_value.set(String::new());
-                                                        } else if event.key() == Key::Escape {
-                                                            *editing_cell.write() = None;
-                                                            editing_value.set(String::new());
                                                         }
                                                     }
                                                 }
                                             }
-                                        )
-                                    } else {
-                                        rsx!(
                                             td {
-                                                style: "border: 1px solid #bbb; padding: 4px; text-align: {alignment};",
-                                            ondoubleclick: move |_| {
-                                                    if !editing_enabled {
-                                                        return;
-                                                    }
-                                                    if editable_columns_for_cell.contains(&header) {
-                                                        *editing_cell.write() = Some(cell_key.clone());
-                                                        editing_value.set(staged_value.clone());
+                                                style: "border: 1px solid #bbb; padding: 4px; cursor: pointer; white-space: nowrap;",
+                                                onclick: move |_| {
+                                                    let mut collapsed = collapsed_groups.write();
+                                                    if !collapsed.remove(&key_for_toggle) {
+                                                        collapsed.insert(key_for_toggle.clone());
                                                     }
                                                 },
-                                                "{formatted}"
+                                                "{arrow} {label}（{count} 列） {first_cell}"
                                             }
-                                        )
-                                    }
-                                })}
-                            }
-                        )
-                    })}
-
-                        if !table_added_rows.is_empty() {
-                            {table_added_rows.iter().enumerate().map(|(row_idx, row)| {
+                                            for text in cells.iter().skip(1) {
+                                                td { style: "border: 1px solid #bbb; padding: 4px; text-align: right;", "{text}" }
+                                            }
+                                        }
+                                    );
+                                }
+                            };
+                            let row = &table_rows[row_idx];
                             let table_columns = table_columns.clone();
+                            let editable_columns = editable_columns.clone();
+                            let required_columns = required_columns.clone();
                             let column_alignments = column_alignments.clone();
-                            let row = row.clone();
-                            let display_row = base_row_count + row_idx;
-                            let added_selected = selected_rows_snapshot.contains(&display_row);
-                            let added_deleted = deleted_rows_snapshot.contains(&display_row);
-                            let added_background = if added_selected { "#eef4ff" } else { "#d9f7d9" };
-                            let added_border = if added_deleted { "#d24" } else { "transparent" };
-                            let row_style = format!(
-                                "background: {added_background}; border-top: 2px solid {added_border}; border-bottom: 2px solid {added_border};"
-                            );
+                            let table_formats = table_formats.clone();
+                            let table_types = table_types.clone();
+                            let staged_cells_for_row = staged_cells_snapshot.clone();
+                            let saved_changes_for_row = saved_changes_snapshot.clone();
+                            let cell_origins_for_row = cell_origins_snapshot.clone();
+                            let price_history_for_row = price_history_snapshot.clone();
+                            let row_selected = selected_rows_snapshot.contains(&row_idx);
+                            let row_deleted = deleted_rows_snapshot.contains(&row_idx);
+                            let row_background = if row_selected { "#eef4ff" } else { "transparent" };
+                            let row_border = if row_deleted { "#d24" } else { "transparent" };
+                            let modified_marker = if modified_cursor_snapshot == Some(row_idx) {
+                                " box-shadow: inset 4px 0 0 #f0b429; outline: 2px solid #f0b429; outline-offset: -2px;"
+                            } else if modified_row_set.contains(&row_idx) {
+                                " box-shadow: inset 4px 0 0 #f0b429;"
+                            } else {
+                                ""
+                            };
+                            let row_style =
+                                format!("background: {row_background}; border-top: 2px solid {row_border}; border-bottom: 2px solid {row_border};{modified_marker}");
                             rsx!(
                                 tr {
+                                    key: "row-{row_idx}",
+                                    id: "{row_id_prefix}-{row_idx}",
                                     style: "{row_style}",
+                                    tabindex: "0",
+                                    ondoubleclick: move |_| detail_row.set(Some(row_idx)),
+                                    onkeydown: move |event| {
+                                        if event.key() == Key::Enter && editing_cell().is_none() {
+                                            detail_row.set(Some(row_idx));
+                                        }
+                                    },
                                     if editing_enabled {
                                         td { style: "border: 1px solid #bbb; padding: 4px; text-align: center;",
                                             input {
                                                 r#type: "checkbox",
-                                                checked: selected_rows_snapshot.contains(&display_row),
+                                                aria_label: "選取第 {row_idx + 1} 列",
+                                                checked: selected_rows_snapshot.contains(&row_idx),
                                                 onclick: move |_| {
                                                     let mut selected = selected_rows.write();
-                                                    if selected.contains(&display_row) {
-                                                        selected.remove(&display_row);
+                                                    if selected.contains(&row_idx) {
+                                                        selected.remove(&row_idx);
                                                     } else {
-                                                        selected.insert(display_row);
+                                                        selected.insert(row_idx);
                                                     }
                                                 }
                                             }
@@ -1473,7 +3435,7 @@ window.removeEventListener("resize", sendState);
                                     }
                                     {row.iter().enumerate().map(|(visible_idx, value)| {
                                         let value = value.clone();
-                                        let (_col_idx, header) = table_columns
+                                        let (col_idx, header) = table_columns
                                             .get(visible_idx)
                                             .cloned()
                                             .unwrap_or((0, String::new()));
@@ -1481,16 +3443,252 @@ window.removeEventListener("resize", sendState);
                                             .get(visible_idx)
                                             .copied()
                                             .unwrap_or("left");
-                                        rsx!(
-                                            td {
-                                                style: "border: 1px solid #bbb; padding: 4px; text-align: {alignment};",
-                                                "{format_cell_value(&header, &value)}"
+                                        let column_type = column_type_at(&table_types, col_idx);
+                                        let required_columns_for_cell = required_columns.clone();
+                                        let editable_columns_for_cell = editable_columns.clone();
+                                        let cell_key = CellKey {
+                                            row_idx,
+                                            col_idx,
+                                            column: header.clone(),
+                                        };
+                                        let staged_value = staged_cells_for_row
+                                            .get(&cell_key)
+                                            .cloned()
+                                            .unwrap_or_else(|| value.clone());
+                                        let formatted = match staged_cells_for_row.get(&cell_key) {
+                                            Some(staged) => format_display_cell_value(
+                                                &header,
+                                                column_type,
+                                                staged,
+                                                table_formats.get(&(col_idx as i64)),
+                                                &table_display,
+                                            ),
+                                            None => formatted_page
+                                                .read()
+                                                .get(row_idx)
+                                                .and_then(|cells| cells.get(col_idx))
+                                                .cloned()
+                                                .unwrap_or_else(|| value.clone()),
+                                        };
+                                        let mut tooltip = cell_tooltip(
+                                            &staged_value,
+                                            &formatted,
+                                            staged_cells_for_row.get(&cell_key).map(|_| value.as_str()),
+                                        );
+                                        let saved_change = saved_changes_for_row
+                                            .get(&(row_idx + saved_row_offset, col_idx));
+                                        let change_background = match saved_change {
+                                            Some(Some(previous)) => {
+                                                tooltip.push_str(&format!("\n上次儲存前：{previous}"));
+                                                format!(" background: {CHANGED_CELL_BACKGROUND};")
                                             }
-                                        )
+                                            Some(None) => {
+                                                tooltip.push_str("\n上次儲存時新增");
+                                                format!(" background: {ADDED_CELL_BACKGROUND};")
+                                            }
+                                            None => String::new(),
+                                        };
+                                        let origin = cell_origins_for_row
+                                            .get(&(row_idx + saved_row_offset, col_idx));
+                                        if let Some(origin) = origin {
+                                            tooltip.push('\n');
+                                            tooltip.push_str(&origin_tooltip(origin));
+                                        }
+                                        // Raw values replace, in italics, the stored ones they differ from.
+                                        let (formatted, raw_style) = match origin
+                                            .filter(|origin| show_raw_values_snapshot && origin.value != value)
+                                        {
+                                            Some(origin) => (origin.value.clone(), " font-style: italic;"),
+                                            None => (formatted, ""),
+                                        };
+                                        let is_editing = editing_cell_snapshot.as_ref() == Some(&cell_key);
+                                        let focus_outline = if focused_snapshot.as_ref() == Some(&cell_key) {
+                                            " outline: 2px solid #4a7bd0; outline-offset: -2px;"
+                                        } else {
+                                            ""
+                                        };
+                                        let cell_format = table_formats.get(&(col_idx as i64)).cloned();
+                                        // Outside edit mode a 代號 opens its holding page.
+                                        let holding_link = route
+                                            .filter(|_| header == CODE_COLUMN && !editing_enabled && !value.trim().is_empty())
+                                            .map(|route| (route, value.trim().to_string()));
+                                        let sparkline = price_history_for_row
+                                            .get(value.trim())
+                                            .filter(|points| header == CODE_COLUMN && points.len() > 1)
+                                            .map(|points| {
+                                                let latest = &points[points.len() - 1];
+                                                (
+                                                    points.iter().map(|point| point.price).collect::<Vec<_>>(),
+                                                    format!(
+                                                        "{} 筆價格紀錄，最新 {}（{}）",
+                                                        points.len(),
+                                                        latest.price,
+                                                        latest.fetched_at
+                                                    ),
+                                                )
+                                            });
+                                        if is_editing {
+                                            rsx!(
+                                                td {
+                                                    style: "border: 1px solid #bbb; padding: 4px; text-align: {alignment};",
+                                                    input {
+                                                        value: editing_value(),
+                                                        oninput: move |event| {
+                                                            editing_value.set(event.value());
+                                                        },
+                                                        onkeydown: move |event| {
+                                                            if event.key() == Key::Enter {
+                                                                let next_value = stored_cell_edit(
+                                                                    column_type,
+                                                                    &editing_value(),
+                                                                    number_locale,
+                                                                    cell_format.as_ref(),
+                                                                );
+                                                                if let Err(message) = validate_cell_edit(
+                                                                    &header,
+                                                                    column_type,
+                                                                    &next_value,
+                                                                    &required_columns_for_cell,
+                                                                ) {
+                                                                    *status.write() = message;
+                                                                    return;
+                                                                }
+                                                                staged_cells
+                                                                    .write()
+                                                                    .insert(cell_key.clone(), next_value.clone());
+                                                                *editing_cell.write() = None;
+                                                                editing_value.set(String::new());
+                                                            } else if event.key() == Key::Escape {
+                                                                *editing_cell.write() = None;
+                                                                editing_value.set(String::new());
+                                                            }
+                                                        }
+                                                    }
+                                                }
+                                            )
+                                        } else {
+                                            rsx!(
+                                                td {
+                                                    style: "border: 1px solid #bbb; padding: 4px; text-align: {alignment};{change_background}{raw_style}{focus_outline}",
+                                                    title: "{tooltip}",
+                                                    onclick: {
+                                                        let cell_key = cell_key.clone();
+                                                        move |_| focused_cell.set(Some(cell_key.clone()))
+                                                    },
+                                                    oncontextmenu: {
+                                                        let cell_key = cell_key.clone();
+                                                        move |event: MouseEvent| {
+                                                            event.prevent_default();
+                                                            event.stop_propagation();
+                                                            let point = event.client_coordinates();
+                                                            context_menu.set(Some((point.x, point.y)));
+                                                            context_cell.set(Some(cell_key.clone()));
+                                                        }
+                                                    },
+                                                    ondoubleclick: move |event| {
+                                                        if !editing_enabled {
+                                                            return;
+                                                        }
+                                                        if editable_columns_for_cell.contains(&header) {
+                                                            event.stop_propagation();
+                                                            *editing_cell.write() = Some(cell_key.clone());
+                                                            editing_value.set(staged_value.clone());
+                                                        }
+                                                    },
+                                                    if let Some((mut route, code)) = holding_link {
+                                                        span {
+                                                            style: "color: #2563eb; text-decoration: underline; cursor: pointer;",
+                                                            title: "開啟個股頁",
+                                                            onclick: move |event| {
+                                                                event.stop_propagation();
+                                                                route.open_holding(&code);
+                                                            },
+                                                            "{formatted}"
+                                                        }
+                                                    } else {
+                                                        "{formatted}"
+                                                    }
+                                                    if let Some((values, title)) = sparkline {
+                                                        Sparkline { values, title }
+                                                    }
+                                                }
+                                            )
+                                        }
                                     })}
                                 }
                             )
-                            })}
+                        })}
+
+                            if !table_added_rows.is_empty() {
+                                {table_added_rows.iter().enumerate().map(|(row_idx, row)| {
+                                let table_columns = table_columns.clone();
+                                let column_alignments = column_alignments.clone();
+                                let table_formats = table_formats.clone();
+                                let table_types = table_types.clone();
+                                let display_row = base_row_count + row_idx;
+                                let added_selected = selected_rows_snapshot.contains(&display_row);
+                                let added_deleted = deleted_rows_snapshot.contains(&display_row);
+                                let added_background = if added_selected { "#eef4ff" } else { "#d9f7d9" };
+                                let added_border = if added_deleted { "#d24" } else { "transparent" };
+                                let added_outline = if modified_cursor_snapshot == Some(display_row) {
+                                    " outline: 2px solid #f0b429; outline-offset: -2px;"
+                                } else {
+                                    ""
+                                };
+                                let row_style = format!(
+                                    "background: {added_background}; border-top: 2px solid {added_border}; border-bottom: 2px solid {added_border};{added_outline}"
+                                );
+                                rsx!(
+                                    tr {
+                                        key: "added-{row_idx}",
+                                        id: "{row_id_prefix}-{display_row}",
+                                        style: "{row_style}",
+                                        if editing_enabled {
+                                            td { style: "border: 1px solid #bbb; padding: 4px; text-align: center;",
+                                                input {
+                                                    r#type: "checkbox",
+                                                    aria_label: "選取新增的第 {row_idx + 1} 列",
+                                                    checked: selected_rows_snapshot.contains(&display_row),
+                                                    onclick: move |_| {
+                                                        let mut selected = selected_rows.write();
+                                                        if selected.contains(&display_row) {
+                                                            selected.remove(&display_row);
+                                                        } else {
+                                                            selected.insert(display_row);
+                                                        }
+                                                    }
+                                                }
+                                            }
+                                        }
+                                        {row.iter().enumerate().map(|(visible_idx, value)| {
+                                            let value = value.clone();
+                                            let (col_idx, header) = table_columns
+                                                .get(visible_idx)
+                                                .cloned()
+                                                .unwrap_or((0, String::new()));
+                                            let alignment = column_alignments
+                                                .get(visible_idx)
+                                                .copied()
+                                                .unwrap_or("left");
+                                            let formatted = format_display_cell_value(
+                                                &header,
+                                                column_type_at(&table_types, col_idx),
+                                                &value,
+                                                table_formats.get(&(col_idx as i64)),
+                                                &table_display,
+                                            );
+                                            rsx!(
+                                                td {
+                                                    style: "border: 1px solid #bbb; padding: 4px; text-align: {alignment};",
+                                                    title: "{cell_tooltip(&value, &formatted, None)}",
+                                                    "{formatted}"
+                                                }
+                                            )
+                                        })}
+                                    }
+                                )
+                                })}
+                            }
                         }
                     }
                 }
@@ -1499,869 +3697,419 @@ window.removeEventListener("resize", sendState);
             if let Some(dataset_id) = selected_dataset_id() {
                 div { style: "display: flex; gap: 8px; align-items: center; margin-top: 12px; background: #fff; padding: 8px 0;",
                     button {
-                        disabled: busy() || page() == 0,
-                        onclick: {
-                            let query_service_for_global_search =
-                                query_service_for_global_search.clone();
-                            move |_| {
-                            let next_page = (page() - 1).max(0);
-                            let options = QueryOptions {
-                                global_search: global_search(),
-                                column_search_col: column_search_col(),
-                                column_search_text: column_search_text(),
-                                sort_col: sort_col(),
-                                sort_desc: sort_desc(),
-                            };
-                            match reload_page_data_usecase(
-                                &query_service_for_global_search,
-                                Some(dataset_id),
-                                next_page,
-                                &options,
-                            ) {
-                                Ok((loaded_columns, loaded_rows, loaded_total, loaded_page)) => {
-                                    *columns.write() = loaded_columns;
-                                    *rows.write() = loaded_rows;
-                                    *total_rows.write() = loaded_total;
-                                    *page.write() = loaded_page;
-                                }
-                                Err(err) => {
-                                    *status.write() = format!("上一頁失敗：{err}");
+                        disabled: busy().is(BusyKind::Query) || page() == 0,
+                        onclick: move |_| {
+                            let options = controller.query_options();
+                            controller.load_page(Some(dataset_id), (page() - 1).max(0), &options, "上一頁失敗");
+                        },
+                        "上一頁"
+                    }
+                    button {
+                        disabled: busy().is(BusyKind::Query) || !paged || page() + 1 >= page_count(current_total_rows, current_page_size),
+                        onclick: move |_| {
+                            let options = controller.query_options();
+                            controller.load_page(Some(dataset_id), page() + 1, &options, "下一頁失敗");
+                        },
+                        "下一頁"
+                    }
+                    span { "第 {page() + 1} / {page_count(current_total_rows, current_page_size)} 頁" }
+                    label { style: "display: flex; gap: 4px; align-items: center; margin-left: 8px;",
+                        "每頁"
+                        select {
+                            disabled: busy().is(BusyKind::Query),
+                            value: if load_all() == Some(dataset_id) { String::new() } else { current_page_size.to_string() },
+                            onchange: move |event| {
+                                if let Ok(size) = event.value().parse::<i64>() {
+                                    controller.set_page_size(dataset_id, size);
                                 }
+                            },
+                            if load_all() == Some(dataset_id) {
+                                option { value: "", "全部" }
                             }
+                            for size in PAGE_SIZE_CHOICES {
+                                option { value: "{size}", "{size}" }
                             }
-                        },
-                        "上一頁"
+                        }
+                        "列"
                     }
                     button {
-                        disabled: busy() || (page() + 1) * PAGE_SIZE >= current_total_rows,
-                        onclick: {
-                            let query_service_for_global_search =
-                                query_service_for_global_search.clone();
-                            move |_| {
-                            let next_page = page() + 1;
-                            let options = QueryOptions {
-                                global_search: global_search(),
-                                column_search_col: column_search_col(),
-                                column_search_text: column_search_text(),
-                                sort_col: sort_col(),
-                                sort_desc: sort_desc(),
-                            };
-                            match reload_page_data_usecase(
-                                &query_service_for_global_search,
-                                Some(dataset_id),
-                                next_page,
-                                &options,
-                            ) {
-                                Ok((loaded_columns, loaded_rows, loaded_total, loaded_page)) => {
-                                    *columns.write() = loaded_columns;
-                                    *rows.write() = loaded_rows;
-                                    *total_rows.write() = loaded_total;
-                                    *page.write() = loaded_page;
-                                }
-                                Err(err) => {
-                                    *status.write() = format!("下一頁失敗：{err}");
+                        disabled: busy().is(BusyKind::Query) || !paged,
+                        title: "不分頁，一次顯示所有符合的列",
+                        onclick: move |_| {
+                            if current_total_rows > LOAD_ALL_CONFIRM_ROWS {
+                                let confirm = MessageDialog::new()
+                                    .set_level(MessageLevel::Warning)
+                                    .set_title("載入全部")
+                                    .set_description(format!(
+                                        "共 {current_total_rows} 列，一次全部顯示可能讓畫面變得很慢或停止回應。確定要載入全部？",
+                                    ))
+                                    .set_buttons(MessageButtons::YesNo)
+                                    .show();
+                                if confirm != MessageDialogResult::Yes {
+                                    return;
                                 }
                             }
-                            }
+                            controller.load_all_rows(dataset_id);
                         },
-                        "下一頁"
+                        "載入全部"
                     }
-                    span { "第 {page() + 1} 頁" }
                 }
             }
+
+            StatusBar {
+                db_path: db_path_text,
+                dataset_name: selected_dataset_name.clone(),
+                shown_rows: current_total_rows,
+                dataset_rows: dataset_row_count,
+                pending_changes,
+                last_saved_at: last_saved_at(),
+            }
         }
 
-            if show_summary_report() {
-                div {
-                    style: "position: fixed; inset: 0; background: rgba(0,0,0,0.35); display: flex; align-items: center; justify-content: center; z-index: 1200;",
-                    div {
-                        style: "background: #fff; padding: 16px; border: 1px solid #999; min-width: 360px; max-width: 720px; max-height: 80vh; overflow: auto;",
-                        div { style: "margin-bottom: 8px; font-weight: 600;", "{report_snapshot.title}" }
-                        if report_snapshot.totals.is_empty() {
-                            div { "沒有可計算的摘要欄位" }
-                        } else {
-                            div { style: "display: grid; grid-template-columns: repeat(auto-fill, minmax(180px, 1fr)); gap: 6px 12px;",
-                                for entry in report_snapshot.totals.clone() {
-                                    div { "{entry.label}: {entry.value}" }
-                                }
-                            }
+            if show_report_builder() {
+                ReportBuilderDialog {
+                    columns: current_columns.clone(),
+                    saved: saved_reports(),
+                    busy: busy().is(BusyKind::Query) || busy().is(BusyKind::Save),
+                    on_save: move |report: SavedReport| {
+                        if controller.save_saved_report(report) {
+                            saved_reports.set(controller.load_saved_reports());
                         }
-                        if !report_snapshot.owner_totals.is_empty() {
-                            div { style: "margin-top: 12px; font-weight: 600;", "依所有權人" }
-                            for owner in report_snapshot.owner_totals.clone() {
-                                div { style: "margin-top: 6px; font-weight: 600;", "{owner.owner}" }
-                                div { style: "display: grid; grid-template-columns: repeat(auto-fill, minmax(180px, 1fr)); gap: 6px 12px;",
-                                    for entry in owner.entries {
-                                        div { "{entry.label}: {entry.value}" }
-                                    }
-                                }
-                            }
+                    },
+                    on_run: {
+                        let run_script = run_script.clone();
+                        move |definition: ReportDefinition| {
+                            run_script.borrow_mut()(definition.to_script())
                         }
-                        if !report_snapshot.notes.is_empty() {
-                            div { style: "margin-top: 12px; font-weight: 600;", "備註" }
-                            for note in report_snapshot.notes.clone() {
-                                div { "{note}" }
-                            }
+                    },
+                    on_delete: move |name: String| {
+                        if controller.delete_saved_report(&name) {
+                            saved_reports.set(controller.load_saved_reports());
                         }
-                        div { style: "display: flex; justify-content: flex-end; margin-top: 12px;",
-                            button {
-                                onclick: move |_| {
-                                    show_summary_report.set(false);
-                                },
-                                "關閉"
-                            }
+                    },
+                    on_close: move |_| show_report_builder.set(false),
+                }
+            }
+
+            if show_summary_report() {
+                SummaryDialog {
+                    report: report_snapshot.clone(),
+                    history: summary_history(),
+                    sections: summary_sections(),
+                    on_sections_change: move |sections: SummarySections| {
+                        summary_sections.set(sections);
+                        if let Some(group_key) = selected_group_key() {
+                            controller.save_summary_sections(&group_key, sections);
                         }
-                    }
+                    },
+                    on_export: move |report: SummaryReport| {
+                        handle_summary_export.borrow_mut()(report)
+                    },
+                    on_close: move |_| show_summary_report.set(false),
                 }
             }
 
-            if show_dataset_manager() {
+            if let (Some((menu_x, menu_y)), Some(cell)) = (context_menu(), context_cell()) {
                 div {
-                    style: "position: fixed; inset: 0; background: rgba(0,0,0,0.35); display: flex; align-items: center; justify-content: center; z-index: 1200;",
-                    div {
-                        style: "background: #fff; padding: 16px; border: 1px solid #999; min-width: 420px; max-width: 720px; max-height: 80vh; overflow: auto;",
-                        div { style: "margin-bottom: 8px; font-weight: 600;", "資料集管理" }
-                        div { style: "display: flex; gap: 16px;",
-                            div { style: "flex: 1;",
-                                div { style: "margin-bottom: 6px; font-weight: 600;", "資料集" }
-                                div { style: "border: 1px solid #ddd; max-height: 240px; overflow: auto; padding: 6px;",
-                                    {datasets().iter().map(|dataset| {
-                                        let dataset_id = dataset.id.0;
-                                        let name = dataset.name.clone();
-                                        let is_selected = manage_dataset_id() == Some(dataset_id);
-                                        rsx!(
-                                            label {
-                                                style: "display: flex; align-items: center; gap: 8px; padding: 4px 2px; cursor: pointer;",
-                                                input {
-                                                    r#type: "radio",
-                                                    name: "dataset-manager",
-                                                    checked: is_selected,
-                                                    onclick: move |_| {
-                                                        manage_dataset_id.set(Some(dataset_id));
-                                                        manage_name_input.set(name.clone());
-                                                    }
-                                                }
-                                                span { "{name}" }
-                                            }
-                                        )
-                                    })}
-                                }
+                    role: "menu",
+                    style: "position: fixed; left: {menu_x}px; top: {menu_y}px; display: flex; flex-direction: column; background: #fff; border: 1px solid #bbb; border-radius: 6px; box-shadow: 0 10px 24px rgba(0,0,0,0.15); z-index: 1200; padding: 4px;",
+                    button {
+                        role: "menuitem",
+                        onclick: {
+                            let text = cell_raw_value(&current_rows, &staged_cells_snapshot, &cell);
+                            let label = column_display_name(&cell.column, cell.col_idx, &aliases_snapshot);
+                            move |_| copy_to_clipboard(text.clone(), format!("已複製{label}的儲存格"))
+                        },
+                        "複製儲存格"
+                    }
+                    button {
+                        role: "menuitem",
+                        onclick: {
+                            let text = column_raw_text(
+                                &current_rows,
+                                &added_rows_snapshot,
+                                &staged_cells_snapshot,
+                                cell.col_idx,
+                                &cell.column,
+                            );
+                            let count = current_rows.len() + added_rows_snapshot.len();
+                            let label = column_display_name(&cell.column, cell.col_idx, &aliases_snapshot);
+                            move |_| copy_to_clipboard(text.clone(), format!("已複製{label}整欄（{count} 列）"))
+                        },
+                        "複製整欄"
+                    }
+                }
+            }
+
+            if let Some(row_idx) = detail_row().filter(|idx| *idx < current_rows.len()) {
+                RowDetailPanel {
+                    row_number: row_idx + 1,
+                    fields: current_columns
+                        .iter()
+                        .enumerate()
+                        .map(|(col_idx, header)| {
+                            let cell_key = CellKey {
+                                row_idx,
+                                col_idx,
+                                column: header.clone(),
+                            };
+                            let value = staged_cells_snapshot
+                                .get(&cell_key)
+                                .cloned()
+                                .or_else(|| current_rows[row_idx].get(col_idx).cloned())
+                                .unwrap_or_default();
+                            RowDetailField {
+                                col_idx,
+                                header: header.clone(),
+                                label: column_display_name(header, col_idx, &aliases_snapshot),
+                                value,
+                                column_type: column_type_at(&table_types, col_idx),
+                                hidden: !shown_visibility_snapshot
+                                    .get(&(col_idx as i64))
+                                    .copied()
+                                    .unwrap_or(true),
+                                editable: editing_enabled && editable_columns.contains(header),
                             }
-                            div { style: "flex: 1;",
-                                div { style: "margin-bottom: 6px; font-weight: 600;", "操作" }
-                                button {
-                                    disabled: busy(),
-                                    onclick: move |_| {
-                                        handle_import_for_manager.borrow_mut()();
-                                    },
-                                    "匯入 CSV / XLSX"
-                                }
-                                div { style: "margin-top: 12px;",
-                                    label { "重新命名" }
-                                    input {
-                                        value: manage_name_input(),
-                                        oninput: move |event| {
-                                            manage_name_input.set(event.value());
-                                        }
-                                    }
-                                    button {
-                                        disabled: busy(),
-                                        onclick: move |_| {
-                                            let Some(dataset_id) = manage_dataset_id() else {
-                                                *status.write() = "請先選擇資料集".to_string();
-                                                return;
-                                            };
-                                            let name = manage_name_input().trim().to_string();
-                                            if name.is_empty() {
-                                                *status.write() = "資料集名稱不可空白".to_string();
-                                                return;
-                                            }
-                                            *busy.write() = true;
-                                            let result = run_blocking(|| {
-                                                query_service_for_manage_rename
-                                                    .rename_dataset(DatasetId(dataset_id), name.clone())
-                                                    .map_err(|err| anyhow!(err.to_string()))
-                                            });
-                                            if let Err(err) = result {
-                                                *status.write() = format!("重新命名失敗：{err}");
-                                            } else {
-                                                if let Ok(available) = query_service_for_manage_rename.list_datasets(show_deleted()) {
-                                                    *datasets.write() = available;
-                                                }
-                                                *status.write() = "已重新命名".to_string();
-                                            }
-                                            *busy.write() = false;
-                                        },
-                                        "套用" }
-                                }
-                                div { style: "margin-top: 12px;",
-                                    button {
-                                        disabled: busy(),
-                                        onclick: move |_| {
-                                            let Some(dataset_id) = manage_dataset_id() else {
-                                                *status.write() = "請先選擇資料集".to_string();
-                                                return;
-                                            };
-                                            let next_dataset_candidate =
-                                                choose_next_dataset_after_delete(&datasets(), dataset_id);
-                                            let confirm = MessageDialog::new()
-                                                .set_level(MessageLevel::Warning)
-                                                .set_title("永久刪除資料集")
-                                                .set_description("確定要永久刪除資料集？此動作不可復原。")
-                                                .set_buttons(MessageButtons::YesNo)
-                                                .show();
-                                            if confirm != MessageDialogResult::Yes {
-                                                return;
-                                            }
-                                            *busy.write() = true;
-                                            let result = run_blocking(|| {
-                                                edit_service_for_manage
-                                                    .hard_delete_dataset(DatasetId(dataset_id))
-                                                    .map_err(|err| anyhow!(err.to_string()))
-                                            });
-                                            if let Err(err) = result {
-                                                *status.write() = format!("刪除資料集失敗：{err}");
-                                            } else if let Ok(available) = query_service_for_manage_delete.list_datasets(show_deleted()) {
-                                                let groups = build_dataset_groups(&available);
-                                                *datasets.write() = available;
-                                                let next_dataset = next_dataset_candidate
-                                                    .and_then(|id| {
-                                                        groups
-                                                            .iter()
-                                                            .flat_map(|g| g.datasets.iter())
-                                                            .find(|d| d.id.0 == id)
-                                                            .map(|d| d.id.0)
-                                                    })
-                                                    .or_else(|| {
-                                                        selected_group_key()
-                                                            .and_then(|key| groups.iter().find(|g| g.key == key))
-                                                            .or_else(|| groups.first())
-                                                            .and_then(|g| choose_default_dataset_id(&g.datasets))
-                                                    });
-                                                *selected_group_key.write() = groups
-                                                    .iter()
-                                                    .find(|g| g.datasets.iter().any(|d| d.id.0 == next_dataset.unwrap_or(-1)))
-                                                    .map(|g| g.key.clone());
-                                                *selected_dataset_id.write() = next_dataset;
-                                                *page.write() = 0;
-                                                match reload_page_data_usecase(
-                                                    &query_service_for_manage_delete,
-                                                    next_dataset,
-                                                    0,
-                                                    &QueryOptions::default(),
-                                                ) {
-                                                    Ok((loaded_columns, loaded_rows, loaded_total, loaded_page)) => {
-                                                        *columns.write() = loaded_columns;
-                                                        *rows.write() = loaded_rows;
-                                                        *total_rows.write() = loaded_total;
-                                                        *page.write() = loaded_page;
-                                                    }
-                                                    Err(err) => {
-                                                        *status.write() = format!("載入資料集失敗：{err}");
-                                                    }
-                                                }
-                                                manage_dataset_id.set(next_dataset);
-                                                *status.write() = "已永久刪除資料集".to_string();
-                                            }
-                                            *busy.write() = false;
-                                        },
-                                        "刪除" }
-                                }
+                        })
+                        .collect::<Vec<_>>(),
+                    formats: column_formats_snapshot.clone(),
+                    display: table_display.as_ref().clone(),
+                    owner_shares: current_columns
+                        .iter()
+                        .any(|header| header == OWNER_COLUMN)
+                        .then_some(detail_shares_snapshot.clone()),
+                    shares_editable: editing_enabled,
+                    on_commit: {
+                        let columns_for_detail = current_columns.clone();
+                        let required_columns = required_columns.clone();
+                        let formats = column_formats_snapshot.clone();
+                        let types = table_types.clone();
+                        move |(col_idx, value): (usize, String)| {
+                            let Some(header) = columns_for_detail.get(col_idx).cloned() else {
+                                return;
+                            };
+                            let column_type = column_type_at(&types, col_idx);
+                            let value = stored_cell_edit(
+                                column_type,
+                                &value,
+                                number_locale,
+                                formats.get(&(col_idx as i64)),
+                            );
+                            if let Err(message) =
+                                validate_cell_edit(&header, column_type, &value, &required_columns)
+                            {
+                                *status.write() = message;
+                                return;
                             }
-                        }
-                        div { style: "display: flex; justify-content: flex-end; margin-top: 12px;",
-                            button {
-                                onclick: move |_| {
-                                    show_dataset_manager.set(false);
+                            staged_cells.write().insert(
+                                CellKey {
+                                    row_idx,
+                                    col_idx,
+                                    column: header,
                                 },
-                                "關閉"
-                            }
+                                value,
+                            );
                         }
-                    }
+                    },
+                    on_shares_commit: move |text: String| {
+                        let Some(dataset_id) = selected_dataset_id() else {
+                            return;
+                        };
+                        if let Some(shares) = controller.save_owner_shares(dataset_id, row_idx, &text)
+                        {
+                            detail_shares.set(shares);
+                        }
+                    },
+                    on_close: move |_| detail_row.set(None),
                 }
             }
 
-            if show_save_prompt() {
-                div {
-                    style: "position: fixed; inset: 0; background: rgba(0,0,0,0.35); display: flex; align-items: center; justify-content: center; z-index: 1100;",
-                    div {
-                        style: "background: #fff; padding: 16px; border: 1px solid #999; min-width: 280px;",
-                        div { style: "margin-bottom: 8px; font-weight: 600;", "未儲存變更" }
-                        div { style: "margin-bottom: 12px;", "你要覆蓋目前資料集，或另存舊內容？" }
-                        div { style: "display: flex; gap: 8px;",
-                            button {
-                                onclick: {
-                                    let query_service_for_dataset_change =
-                                        query_service_for_dataset_change.clone();
-                                    let query_service_for_tab_switch =
-                                        query_service_for_tab_switch.clone();
-                                    move |_| {
-                                        let Some(dataset_id) = selected_dataset_id() else {
-                                            show_save_prompt.set(false);
-                                            pending_action.set(None);
-                                            return;
-                                        };
-
-                                        let edits = StagedEdits {
-                                            staged_cells: staged_cells(),
-                                            deleted_rows: deleted_rows(),
-                                            added_rows: added_rows(),
-                                        };
-                                        if let Err(err) = edit_service_for_save
-                                            .apply_edits(DatasetId(dataset_id), edits)
-                                            .map_err(|err| anyhow!(err.to_string()))
-                                        {
-                                            *status.write() = format!("覆蓋失敗：{err}");
-                                            return;
-                                        }
-
-                                        staged_cells.write().clear();
-                                        deleted_rows.write().clear();
-                                        selected_rows.write().clear();
-                                        added_rows.write().clear();
-                                        *editing_cell.write() = None;
-                                        editing_value.set(String::new());
-                                        show_add_row.set(false);
-                                        new_row_inputs.write().clear();
-
-                                        match reload_page_data_usecase(
-                                            &query_service_for_save,
-                                            Some(dataset_id),
-                                            0,
-                                            &QueryOptions {
-                                                global_search: global_search(),
-                                                column_search_col: column_search_col(),
-                                                column_search_text: column_search_text(),
-                                                sort_col: sort_col(),
-                                                sort_desc: sort_desc(),
-                                            },
-                                        ) {
-                                            Ok((loaded_columns, loaded_rows, loaded_total, loaded_page)) => {
-                                                *columns.write() = loaded_columns;
-                                                *rows.write() = loaded_rows;
-                                                *total_rows.write() = loaded_total;
-                                                *page.write() = loaded_page;
-                                            }
-                                            Err(err) => {
-                                                *status.write() = format!("覆蓋後重新載入失敗：{err}");
-                                            }
-                                        }
+            if show_command_palette() {
+                CommandPalette {
+                    entries: palette_entries,
+                    on_select: run_palette_action,
+                    on_close: move |_| show_command_palette.set(false),
+                }
+            }
 
-                                        show_save_prompt.set(false);
-                                        if let Some(action) = pending_action() {
-                                            pending_action.set(None);
-                                            match action {
-                                                PendingAction::Import(file_path) => {
-                                                    *busy.write() = true;
-                                                    *status.write() =
-                                                        format!("正在匯入 {}", file_path.display());
-                                                    let ext = file_path
-                                                        .extension()
-                                                        .and_then(|e| e.to_str())
-                                                        .map(|s| s.to_ascii_lowercase())
-                                                        .unwrap_or_default();
-                                                    let import_result = run_blocking(|| {
-                                                        if ext == "xlsx" {
-                                                            import_service_for_import_overwrite
-                                                                .import_xlsx(&file_path)
-                                                                .map(|items| {
-                                                                    (
-                                                                        items.first().map(|it| it.dataset_id),
-                                                                        items.len() as i64,
-                                                                        true,
-                                                                    )
-                                                                })
-                                                        } else {
-                                                            import_service_for_import_overwrite
-                                                                .import_csv(&file_path)
-                                                                .map(|item| {
-                                                                    (Some(item.dataset_id), item.row_count, false)
-                                                                })
-                                                        }
-                                                    });
-                                                    match import_result {
-                                                        Ok((selected_id, imported_count, is_xlsx)) => {
-                                                            match run_blocking(|| {
-                                                                query_service_for_import_overwrite
-                                                                    .list_datasets(show_deleted())
-                                                            }) {
-                                                                Ok(available) => {
-                                                                    let groups =
-                                                                        build_dataset_groups(&available);
-                                                                    *datasets.write() = available;
-                                                                    let next_group_key =
-                                                                        selected_id.and_then(|id| {
-                                                                            groups
-                                                                                .iter()
-                                                                                .find(|g| {
-                                                                                    g.datasets
-                                                                                        .iter()
-                                                                                        .any(|d| d.id.0 == id)
-                                                                                })
-                                                                                .map(|g| g.key.clone())
-                                                                        });
-                                                                    *selected_group_key.write() = next_group_key;
-                                                                    *selected_dataset_id.write() = selected_id;
-                                                                    *column_search_col.write() = None;
-                                                                    *column_search_text.write() = String::new();
-                                                                    *sort_col.write() = None;
-                                                                    *sort_desc.write() = false;
-                                                                    *page.write() = 0;
-                                                                    match reload_page_data_usecase(
-                                                                        &query_service_for_import_overwrite,
-                                                                        selected_id,
-                                                                        0,
-                                                                        &QueryOptions::default(),
-                                                                    ) {
-                                                                        Ok((
-                                                                            loaded_columns,
-                                                                            loaded_rows,
-                                                                            loaded_total,
-                                                                            loaded_page,
-                                                                        )) => {
-                                                                            *columns.write() = loaded_columns;
-                                                                            *rows.write() = loaded_rows;
-                                                                            *total_rows.write() = loaded_total;
-                                                                            *page.write() = loaded_page;
-                                                                            *status.write() = if is_xlsx {
-                                                                                format!(
-                                                                                    "已匯入 XLSX，共 {} 個資料表",
-                                                                                    imported_count
-                                                                                )
-                                                                            } else {
-                                                                                format!(
-                                                                                    "已匯入 CSV（{} 筆）",
-                                                                                    imported_count
-                                                                                )
-                                                                            };
-                                                                        }
-                                                                        Err(err) => {
-                                                                            *status.write() =
-                                                                                format!("匯入成功，但載入資料失敗：{err}");
-                                                                        }
-                                                                    }
-                                                                }
-                                                                Err(err) => {
-                                                                    *status.write() = format!(
-                                                                        "匯入成功，但刷新資料集失敗：{err}"
-                                                                    );
-                                                                }
-                                                            }
-                                                        }
-                                                        Err(err) => {
-                                                            *status.write() = format!("匯入失敗：{err}");
-                                                        }
-                                                    }
-                                                    *busy.write() = false;
-                                                }
-                                                PendingAction::DatasetChange { next_group, next_dataset } => {
-                                                    *selected_group_key.write() = next_group;
-                                                    *selected_dataset_id.write() = next_dataset;
-                                                    *column_search_col.write() = None;
-                                                    *column_search_text.write() = String::new();
-                                                    *sort_col.write() = None;
-                                                    *sort_desc.write() = false;
-                                                    *page.write() = 0;
-                                                    *busy.write() = true;
-                                                    match reload_page_data_usecase(
-                                                        &query_service_for_dataset_change,
-                                                        next_dataset,
-                                                        0,
-                                                        &QueryOptions::default(),
-                                                    ) {
-                                                        Ok((
-                                                            loaded_columns,
-                                                            loaded_rows,
-                                                            loaded_total,
-                                                            loaded_page,
-                                                        )) => {
-                                                            *columns.write() = loaded_columns;
-                                                            *rows.write() = loaded_rows;
-                                                            *total_rows.write() = loaded_total;
-                                                            *page.write() = loaded_page;
-                                                            *status.write() =
-                                                                "已切換資料集".to_string();
-                                                        }
-                                                        Err(err) => {
-                                                            *status.write() =
-                                                                format!("載入資料集失敗：{err}");
-                                                        }
-                                                    }
-                                                    *busy.write() = false;
-                                                }
-                                                PendingAction::TabSwitch { dataset_id } => {
-                                                    *selected_dataset_id.write() = Some(dataset_id);
-                                                    *page.write() = 0;
-                                                    *busy.write() = true;
-                                                    match reload_page_data_usecase(
-                                                        &query_service_for_tab_switch,
-                                                        Some(dataset_id),
-                                                        0,
-                                                        &QueryOptions::default(),
-                                                    ) {
-                                                        Ok((
-                                                            loaded_columns,
-                                                            loaded_rows,
-                                                            loaded_total,
-                                                            loaded_page,
-                                                        )) => {
-                                                            *columns.write() = loaded_columns;
-                                                            *rows.write() = loaded_rows;
-                                                            *total_rows.write() = loaded_total;
-                                                            *page.write() = loaded_page;
-                                                            *status.write() =
-                                                                "已切換工作表".to_string();
-                                                        }
-                                                        Err(err) => {
-                                                            *status.write() =
-                                                                format!("切換工作表失敗：{err}");
-                                                        }
-                                                    }
-                                                    *busy.write() = false;
-                                                }
-                                            }
-                                        }
-                                    }
-                                },
-                            "覆蓋"
-                            }
-                            button {
-                                onclick: move |_| {
-                                    save_as_name.set(default_dataset_name_mmdd());
-                                    show_save_prompt.set(false);
-                                    show_save_as_prompt.set(true);
-                                },
-                                "另存"
+            if show_error_dialog() {
+                if let Some(report) = last_error() {
+                    ErrorDialog {
+                        report,
+                        on_close: move |_| {
+                            show_error_dialog.set(false);
+                            last_error.set(None);
+                        },
+                    }
+                }
+            }
+            if show_column_format() {
+                ColumnFormatDialog {
+                    columns: current_columns.clone(),
+                    aliases: aliases_snapshot.clone(),
+                    formats: column_formats_snapshot.clone(),
+                    types: table_types.to_vec(),
+                    display: table_display.as_ref().clone(),
+                    on_save: move |(next_formats, changed_types, next_display): (BTreeMap<i64, ColumnFormat>, BTreeMap<i64, ColumnType>, DatasetDisplay)| {
+                        let Some(dataset_id) = selected_dataset_id() else {
+                            show_column_format.set(false);
+                            return;
+                        };
+                        let result = run_blocking(|| {
+                            query_service_for_format_update
+                                .upsert_column_formats(DatasetId(dataset_id), next_formats.clone())?;
+                            query_service_for_type_update
+                                .upsert_column_types(DatasetId(dataset_id), changed_types.clone())?;
+                            query_service_for_type_update
+                                .save_dataset_display(DatasetId(dataset_id), next_display.clone())
+                                .map_err(anyhow::Error::from)
+                        });
+                        match result {
+                            Ok(()) => {
+                                column_formats.set(next_formats);
+                                column_types.write().extend(changed_types);
+                                dataset_display.set(next_display);
+                                *status.write() = "已更新數字格式".to_string();
                             }
-                            button {
-                                onclick: move |_| {
-                                    show_save_prompt.set(false);
-                                    pending_action.set(None);
-                                },
-                                "取消"
+                            Err(err) => {
+                                report_error(status, last_error, "更新數字格式失敗", &err);
                             }
                         }
-                    }
+                        show_column_format.set(false);
+                    },
+                    on_close: move |_| {
+                        show_column_format.set(false);
+                    },
                 }
             }
 
-            if show_save_as_prompt() {
-                div {
-                    style: "position: fixed; inset: 0; background: rgba(0,0,0,0.35); display: flex; align-items: center; justify-content: center; z-index: 1200;",
-                    div {
-                        style: "background: #fff; padding: 16px; border: 1px solid #999; min-width: 280px;",
-                        div { style: "margin-bottom: 8px; font-weight: 600;", "另存舊內容" }
-                        div { style: "margin-bottom: 8px;", "請輸入新資料集名稱（預設 MMDD）" }
-                        input {
-                            value: save_as_name(),
-                            oninput: move |event| {
-                                save_as_name.set(event.value());
-                            }
+            if show_computed_columns() {
+                ComputedColumnsDialog {
+                    columns: stored_columns.to_vec(),
+                    computed: computed_snapshot.clone(),
+                    on_save: move |next: Vec<ComputedColumn>| {
+                        let Some(dataset_id) = selected_dataset_id() else {
+                            show_computed_columns.set(false);
+                            return;
+                        };
+                        if controller.save_computed_columns(dataset_id, next.clone()) {
+                            computed_columns.set(next);
+                            show_computed_columns.set(false);
                         }
-                        div { style: "display: flex; gap: 8px; margin-top: 12px;",
-                            button {
-                                onclick: {
-                                    let query_service_for_dataset_change =
-                                        query_service_for_dataset_change.clone();
-                                    let query_service_for_tab_switch =
-                                        query_service_for_tab_switch.clone();
-                                    let query_service_for_import_save_as =
-                                        query_service_for_import_save_as.clone();
-                                    let import_service_for_import_save_as =
-                                        import_service_for_import_save_as.clone();
-                                    move |_| {
-                                        let name = save_as_name().trim().to_string();
-                                        if name.is_empty() {
-                                            *status.write() = "資料集名稱不可空白".to_string();
-                                            return;
-                                        }
-                                        let Some(dataset_id) = selected_dataset_id() else {
-                                            show_save_as_prompt.set(false);
-                                            pending_action.set(None);
-                                            return;
-                                        };
-                                        if let Some(current) =
-                                            datasets_for_save.iter().find(|d| d.id.0 == dataset_id)
-                                        {
-                                            if current.name == name {
-                                                *status.write() = "資料集名稱必須不同".to_string();
-                                                return;
-                                            }
-                                        }
-                                        let existing =
-                                            datasets_for_save.iter().find(|d| d.name == name).cloned();
-                                        if let Some(existing) = existing {
-                                            let overwrite = MessageDialog::new()
-                                                .set_level(MessageLevel::Warning)
-                                                .set_title("名稱已存在")
-                                                .set_description("已有相同名稱，是否覆蓋？")
-                                                .set_buttons(MessageButtons::YesNo)
-                                                .show();
-                                            if overwrite != MessageDialogResult::Yes {
-                                                return;
-                                            }
-                                            if let Err(err) = edit_service_for_save_as
-                                                .purge_dataset(existing.id)
-                                                .map_err(|err| anyhow!(err.to_string()))
-                                            {
-                                                *status.write() = format!("覆蓋失敗：{err}");
-                                                return;
-                                            }
-                                        }
+                    },
+                    on_close: move |_| show_computed_columns.set(false),
+                }
+            }
 
-                                        let Some(current) =
-                                            datasets_for_save.iter().find(|d| d.id.0 == dataset_id)
-                                        else {
-                                            *status.write() = "找不到目前資料集".to_string();
-                                            return;
-                                        };
-                                        let prefix = current
-                                            .source_path
-                                            .split_once('#')
-                                            .map(|(p, _)| p)
-                                            .unwrap_or(&current.source_path);
-                                        let backup_source = format!("{prefix}#{name}");
-
-                                        if let Err(err) = edit_service_for_save_as
-                                            .create_dataset(
-                                                NewDatasetMeta {
-                                                    name: name.clone(),
-                                                    source_path: backup_source,
-                                                },
-                                                TabularData {
-                                                    columns: current_columns_for_save_as.clone(),
-                                                    rows: current_rows_for_save_as.clone(),
-                                                },
-                                            )
-                                            .map_err(|err| anyhow!(err.to_string()))
-                                        {
-                                            *status.write() = format!("另存失敗：{err}");
-                                            return;
-                                        }
+            {import_mapping_view}
 
-                                        let edits = StagedEdits {
-                                            staged_cells: staged_cells(),
-                                            deleted_rows: deleted_rows(),
-                                            added_rows: added_rows(),
-                                        };
-                                        if let Err(err) = edit_service_for_save_as
-                                            .apply_edits(DatasetId(dataset_id), edits)
-                                            .map_err(|err| anyhow!(err.to_string()))
-                                        {
-                                            *status.write() = format!("覆蓋失敗：{err}");
-                                            return;
-                                        }
+            if let Some((config, last_status)) = remote_backup_dialog() {
+                RemoteBackupSettings {
+                    config,
+                    last_status,
+                    busy: busy().is(BusyKind::Save),
+                    on_save: move |config: RemoteBackupConfig| {
+                        if controller.save_remote_backup(&config) {
+                            remote_backup_dialog.set(None);
+                        }
+                    },
+                    on_upload: move |config: RemoteBackupConfig| {
+                        if controller.save_remote_backup(&config) {
+                            controller.back_up_to_remote();
+                            remote_backup_dialog.set(controller.load_remote_backup());
+                        }
+                    },
+                    on_close: move |_| remote_backup_dialog.set(None),
+                }
+            }
 
-                                        match query_service_for_save_as.list_datasets(show_deleted()) {
-                                            Ok(available) => {
-                                                *datasets.write() = available;
-                                            }
-                                            Err(err) => {
-                                                *status.write() =
-                                                    format!("更新資料集清單失敗：{err}");
-                                            }
-                                        }
+            if let Some(owners) = dividend_owners_dialog() {
+                DividendOwnersDialog {
+                    owners,
+                    busy: busy().is(BusyKind::Save),
+                    on_save: move |owners: Vec<String>| {
+                        if controller.save_dividend_owners(&owners) {
+                            dividend_owners_dialog.set(None);
+                        }
+                    },
+                    on_close: move |_| dividend_owners_dialog.set(None),
+                }
+            }
 
-                                        staged_cells.write().clear();
-                                        deleted_rows.write().clear();
-                                        selected_rows.write().clear();
-                                        added_rows.write().clear();
-                                        *editing_cell.write() = None;
-                                        editing_value.set(String::new());
-                                        show_add_row.set(false);
-                                        new_row_inputs.write().clear();
+            if show_dataset_manager() {
+                DatasetManager {
+                    datasets: datasets_snapshot.clone(),
+                    initial_dataset_id: selected_dataset_id(),
+                    busy: busy().is(BusyKind::Save) || busy().is(BusyKind::Import),
+                    read_only,
+                    history: import_history(),
+                    trash: trash(),
+                    retention_days: trash_retention_days(),
+                    on_import: move |_| handle_import_for_manager.borrow_mut()(),
+                    on_rename: move |(dataset_id, name): (i64, String)| {
+                        controller.rename_dataset(dataset_id, &name);
+                    },
+                    on_set_kind: move |(dataset_id, kind): (i64, DatasetKind)| {
+                        controller.set_dataset_kind(dataset_id, kind);
+                    },
+                    on_export_diff: move |(before, after, path): (i64, i64, PathBuf)| {
+                        controller.export_dataset_diff(before, after, &path);
+                    },
+                    on_export_csv: move |(dataset_id, path, options): (i64, PathBuf, CsvOptions)| {
+                        controller.export_dataset_csv(dataset_id, &path, options);
+                    },
+                    on_retransform: move |dataset_id| controller.retransform_dataset(dataset_id),
+                    on_trash: move |dataset_id| controller.trash_dataset(dataset_id),
+                    on_restore: move |dataset_id| controller.restore_dataset(dataset_id),
+                    on_keep_forever: move |(dataset_id, keep_forever): (i64, bool)| {
+                        controller.set_keep_forever(dataset_id, keep_forever);
+                        trash.set(controller.load_trash());
+                    },
+                    on_delete: move |dataset_id| {
+                        controller.delete_dataset(dataset_id);
+                        trash.set(controller.load_trash());
+                    },
+                    on_close: move |_| show_dataset_manager.set(false),
+                }
+            }
 
-                                        show_save_as_prompt.set(false);
-
-                                        if let Some(action) = pending_action() {
-                                            pending_action.set(None);
-                                            match action {
-                                                PendingAction::DatasetChange { next_group, next_dataset } => {
-                                                    *selected_group_key.write() = next_group;
-                                                    *selected_dataset_id.write() = next_dataset;
-                                                    *column_search_col.write() = None;
-                                                    *column_search_text.write() = String::new();
-                                                    *sort_col.write() = None;
-                                                    *sort_desc.write() = false;
-                                                    *page.write() = 0;
-                                                    *busy.write() = true;
-                                                    match reload_page_data_usecase(
-                                                        &query_service_for_dataset_change,
-                                                        next_dataset,
-                                                        0,
-                                                        &QueryOptions::default(),
-                                                    ) {
-                                                        Ok((
-                                                            loaded_columns,
-                                                            loaded_rows,
-                                                            loaded_total,
-                                                            loaded_page,
-                                                        )) => {
-                                                            *columns.write() = loaded_columns;
-                                                            *rows.write() = loaded_rows;
-                                                            *total_rows.write() = loaded_total;
-                                                            *page.write() = loaded_page;
-                                                            *status.write() =
-                                                                "已切換資料集".to_string();
-                                                        }
-                                                        Err(err) => {
-                                                            *status.write() =
-                                                                format!("載入資料集失敗：{err}");
-                                                        }
-                                                    }
-                                                    *busy.write() = false;
-                                                }
-                                                PendingAction::TabSwitch { dataset_id } => {
-                                                    *selected_dataset_id.write() = Some(dataset_id);
-                                                    *page.write() = 0;
-                                                    *busy.write() = true;
-                                                    match reload_page_data_usecase(
-                                                        &query_service_for_tab_switch,
-                                                        Some(dataset_id),
-                                                        0,
-                                                        &QueryOptions::default(),
-                                                    ) {
-                                                        Ok((
-                                                            loaded_columns,
-                                                            loaded_rows,
-                                                            loaded_total,
-                                                            loaded_page,
-                                                        )) => {
-                                                            *columns.write() = loaded_columns;
-                                                            *rows.write() = loaded_rows;
-                                                            *total_rows.write() = loaded_total;
-                                                            *page.write() = loaded_page;
-                                                            *status.write() =
-                                                                "已切換工作表".to_string();
-                                                        }
-                                                        Err(err) => {
-                                                            *status.write() =
-                                                                format!("切換工作表失敗：{err}");
-                                                        }
-                                                    }
-                                                    *busy.write() = false;
-                                                }
-                                                PendingAction::Import(file_path) => {
-                                                    *busy.write() = true;
-                                                    *status.write() =
-                                                        format!("正在匯入 {}", file_path.display());
-                                                    let ext = file_path
-                                                        .extension()
-                                                        .and_then(|e| e.to_str())
-                                                        .map(|s| s.to_ascii_lowercase())
-                                                        .unwrap_or_default();
-                                                    let import_result = run_blocking(|| {
-                                                        if ext == "xlsx" {
-                                                            import_service_for_import_save_as
-                                                                .import_xlsx(&file_path)
-                                                                .map(|items| {
-                                                                    (
-                                                                        items.first().map(|it| it.dataset_id),
-                                                                        items.len() as i64,
-                                                                        true,
-                                                                    )
-                                                                })
-                                                        } else {
-                                                            import_service_for_import_save_as
-                                                                .import_csv(&file_path)
-                                                                .map(|item| {
-                                                                    (Some(item.dataset_id), item.row_count, false)
-                                                                })
-                                                        }
-                                                    });
-                                                    match import_result {
-                                                        Ok((selected_id, imported_count, is_xlsx)) => {
-                                                            match run_blocking(|| {
-                                                                query_service_for_import_save_as
-                                                                    .list_datasets(show_deleted())
-                                                            }) {
-                                                                Ok(available) => {
-                                                                    let groups =
-                                                                        build_dataset_groups(&available);
-                                                                    *datasets.write() = available;
-                                                                    let next_group_key =
-                                                                        selected_id.and_then(|id| {
-                                                                            groups
-                                                                                .iter()
-                                                                                .find(|g| {
-                                                                                    g.datasets
-                                                                                        .iter()
-                                                                                        .any(|d| d.id.0 == id)
-                                                                                })
-                                                                                .map(|g| g.key.clone())
-                                                                        });
-                                                                    *selected_group_key.write() = next_group_key;
-                                                                    *selected_dataset_id.write() = selected_id;
-                                                                    *column_search_col.write() = None;
-                                                                    *column_search_text.write() = String::new();
-                                                                    *sort_col.write() = None;
-                                                                    *sort_desc.write() = false;
-                                                                    *page.write() = 0;
-                                                                    match reload_page_data_usecase(
-                                                                        &query_service_for_import_save_as,
-                                                                        selected_id,
-                                                                        0,
-                                                                        &QueryOptions::default(),
-                                                                    ) {
-                                                                        Ok((
-                                                                            loaded_columns,
-                                                                            loaded_rows,
-                                                                            loaded_total,
-                                                                            loaded_page,
-                                                                        )) => {
-                                                                            *columns.write() = loaded_columns;
-                                                                            *rows.write() = loaded_rows;
-                                                                            *total_rows.write() = loaded_total;
-                                                                            *page.write() = loaded_page;
-                                                                            *status.write() = if is_xlsx {
-                                                                                format!(
-                                                                                    "已匯入 XLSX，共 {} 個資料表",
-                                                                                    imported_count
-                                                                                )
-                                                                            } else {
-                                                                                format!(
-                                                                                    "已匯入 CSV（{} 筆）",
-                                                                                    imported_count
-                                                                                )
-                                                                            };
-                                                                        }
-                                                                        Err(err) => {
-                                                                            *status.write() = format!(
-                                                                                "匯入成功，但載入資料失敗：{err}"
-                                                                            );
-                                                                        }
-                                                                    }
-                                                                }
-                                                                Err(err) => {
-                                                                    *status.write() = format!(
-                                                                        "匯入成功，但刷新資料集失敗：{err}"
-                                                                    );
-                                                                }
-                                                            }
-                                                        }
-                                                        Err(err) => {
-                                                            *status.write() = format!("匯入失敗：{err}");
-                                                        }
-                                                    }
-                                                    *busy.write() = false;
-                                                }
-                                            }
-                                        }
-                                    }
-                                },
-                                "確認"
-                            }
-                            button {
-                                onclick: move |_| {
-                                    show_save_as_prompt.set(false);
-                                    pending_action.set(None);
-                                },
-                                "取消"
-                            }
-                        }
-                    }
+            if let Some((review_target, validation)) = import_review() {
+                DuplicateCodesDialog {
+                    file_name: review_target.label(),
+                    duplicates: validation.duplicate_codes,
+                    on_merge: move |_| controller.import_reviewed(DuplicateCodePolicy::Merge),
+                    on_keep_both: move |_| {
+                        controller.import_reviewed(DuplicateCodePolicy::KeepBoth)
+                    },
+                    on_cancel: move |_| controller.cancel_import_review(),
+                }
+            }
+
+            if let Some(batch) = batch_import() {
+                BatchImportDialog {
+                    batch,
+                    on_close: move |_| batch_import.set(None),
                 }
+            }
+
+            if show_save_prompt() {
+                SavePrompt {
+                    on_overwrite: move |_| controller.overwrite_and_continue(),
+                    on_save_as: move |_| {
+                        save_as_name.set(default_dataset_name_mmdd());
+                        show_save_prompt.set(false);
+                        show_save_as_prompt.set(true);
+                    },
+                    on_cancel: move |_| controller.cancel_save_prompt(),
+                }
+            }
 
+            if show_save_as_prompt() {
+                SaveAsPrompt {
+                    name: save_as_name,
+                    on_confirm: move |name: String| controller.save_as_and_continue(&name),
+                    on_cancel: move |_| controller.cancel_save_prompt(),
+                }
             }
         }
     }
@@ -0,0 +1,338 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Map, Value};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::domain::entities::computed_column::ComputedColumn;
+use crate::domain::entities::dataset::{ColumnType, DatasetId, DatasetKind};
+use crate::domain::entities::owner_share::OwnerShare;
+use crate::preferences::{display_from_json, format_from_json, format_to_json};
+use crate::usecase::ports::repo::{NewDatasetMeta, TabularData};
+use crate::usecase::services::edit_service::EditService;
+use crate::usecase::services::query_service::QueryService;
+
+pub const BUNDLE_EXTENSION: &str = "bomdata";
+const BUNDLE_FORMAT: &str = "bom-dataset";
+const BUNDLE_VERSION: u64 = 1;
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const DATA_ENTRY: &str = "data.json";
+const SETTINGS_ENTRY: &str = "settings.json";
+
+/// What [`import_bundle`] created.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleImportSummary {
+    pub dataset_id: DatasetId,
+    pub name: String,
+    pub row_count: usize,
+}
+
+/// Writes one dataset to `path` as a `.bomdata` bundle: a zip holding `manifest.json`
+/// (name, type and origin), `data.json` (the stored columns and rows, computed columns
+/// left out) and `settings.json` (per-column type, visibility, alias and number format,
+/// saved views, display, page size, computed columns and co-owner splits). Columns are
+/// referred to by their position in `data.json`, which the bundle fixes.
+pub fn export_bundle(service: &QueryService, dataset_id: DatasetId, path: &Path) -> Result<()> {
+    let dataset = service
+        .list_datasets(true)?
+        .into_iter()
+        .find(|dataset| dataset.id == dataset_id)
+        .context("資料集不存在")?;
+    let data = service.load_tabular_data(dataset_id)?;
+
+    let manifest = json!({
+        "format": BUNDLE_FORMAT,
+        "version": BUNDLE_VERSION,
+        "name": dataset.name,
+        "kind": dataset.effective_kind().as_str(),
+        "source_path": dataset.source_path,
+        "row_count": data.rows.len(),
+        "exported_at": chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+    });
+    let settings = bundle_settings(service, dataset_id, data.columns.len())?;
+    let data = json!({
+        "columns": data.columns,
+        "rows": data.rows,
+    });
+
+    let file =
+        File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    let mut zip = ZipWriter::new(file);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (entry, document) in [
+        (MANIFEST_ENTRY, &manifest),
+        (DATA_ENTRY, &data),
+        (SETTINGS_ENTRY, &settings),
+    ] {
+        zip.start_file(entry, options)
+            .with_context(|| format!("failed to add {entry}"))?;
+        zip.write_all(serde_json::to_string_pretty(document)?.as_bytes())
+            .with_context(|| format!("failed to write {entry}"))?;
+    }
+    zip.finish().context("failed to finish bundle")?;
+    Ok(())
+}
+
+/// Reads a bundle from [`export_bundle`] into a new dataset and applies its settings.
+/// The dataset keeps the bundle's file as its source, so it stands alone in the dataset
+/// list. A bundle whose settings cannot be applied leaves no dataset behind.
+pub fn import_bundle(
+    query_service: &QueryService,
+    edit_service: &EditService,
+    path: &Path,
+) -> Result<BundleImportSummary> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let mut zip = ZipArchive::new(file).context("不是 BOM 資料集封裝")?;
+    let manifest = read_entry(&mut zip, MANIFEST_ENTRY)?;
+    if manifest.get("format").and_then(Value::as_str) != Some(BUNDLE_FORMAT) {
+        bail!("不是 BOM 資料集封裝");
+    }
+    let version = manifest.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if version > BUNDLE_VERSION {
+        bail!("資料集封裝版本 {version} 比目前程式新，請先更新 BOM");
+    }
+    let name = manifest
+        .get("name")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .context("資料集封裝缺少名稱")?
+        .to_string();
+
+    let data = read_entry(&mut zip, DATA_ENTRY)?;
+    let data = tabular_from_json(&data).context("資料集封裝的資料格式不正確")?;
+    let settings = read_entry(&mut zip, SETTINGS_ENTRY)?;
+
+    let row_count = data.rows.len();
+    let column_count = data.columns.len();
+    let dataset_id = edit_service.create_dataset(
+        NewDatasetMeta {
+            name: name.clone(),
+            source_path: path.to_string_lossy().into_owned(),
+        },
+        data,
+    )?;
+    let kind = manifest
+        .get("kind")
+        .and_then(Value::as_str)
+        .and_then(DatasetKind::parse);
+    let applied = apply_bundle_settings(query_service, dataset_id, kind, column_count, &settings);
+    if let Err(err) = applied {
+        if let Err(cleanup) = edit_service.hard_delete_dataset(dataset_id) {
+            tracing::warn!(error = %cleanup, "failed to remove partially imported bundle");
+        }
+        return Err(err.context(format!("failed to apply bundle settings to {name}")));
+    }
+    Ok(BundleImportSummary {
+        dataset_id,
+        name,
+        row_count,
+    })
+}
+
+fn bundle_settings(
+    service: &QueryService,
+    dataset_id: DatasetId,
+    column_count: usize,
+) -> Result<Value> {
+    let types = service.load_column_types(dataset_id)?;
+    let visibility = service.load_column_visibility(dataset_id)?;
+    let aliases = service.load_column_aliases(dataset_id)?;
+    let formats = service.load_column_formats(dataset_id)?;
+
+    let columns = (0..column_count as i64)
+        .map(|idx| {
+            let mut prefs = Map::new();
+            if let Some(column_type) = types.get(&idx) {
+                prefs.insert("type".to_string(), Value::from(column_type.as_str()));
+            }
+            if let Some(visible) = visibility.get(&idx) {
+                prefs.insert("visible".to_string(), Value::Bool(*visible));
+            }
+            if let Some(alias) = aliases.get(&idx).filter(|alias| !alias.is_empty()) {
+                prefs.insert("alias".to_string(), Value::String(alias.clone()));
+            }
+            if let Some(format) = formats.get(&idx) {
+                prefs.insert("format".to_string(), format_to_json(format));
+            }
+            Value::Object(prefs)
+        })
+        .collect::<Vec<_>>();
+    let presets = service
+        .load_visibility_presets(dataset_id)?
+        .into_iter()
+        .map(|(name, preset)| {
+            let preset = preset
+                .into_iter()
+                .map(|(idx, visible)| (idx.to_string(), Value::Bool(visible)))
+                .collect::<Map<_, _>>();
+            (name, Value::Object(preset))
+        })
+        .collect::<Map<_, _>>();
+    let display = service.load_dataset_display(dataset_id)?;
+    let computed_columns = service
+        .load_computed_columns(dataset_id)?
+        .into_iter()
+        .map(|column| json!({ "name": column.name, "expression": column.expression }))
+        .collect::<Vec<_>>();
+    let owner_splits = service
+        .load_owner_splits(dataset_id)?
+        .into_iter()
+        .map(|(row_idx, shares)| {
+            let shares = shares
+                .into_iter()
+                .map(|share| json!({ "owner": share.owner, "percent": share.percent }))
+                .collect::<Vec<_>>();
+            (row_idx.to_string(), Value::Array(shares))
+        })
+        .collect::<Map<_, _>>();
+
+    Ok(json!({
+        "columns": columns,
+        "presets": presets,
+        "display": {
+            "currency": display.currency,
+            "unit": display.unit.as_str(),
+        },
+        "page_size": service.load_page_size(dataset_id)?,
+        "computed_columns": computed_columns,
+        "owner_splits": owner_splits,
+    }))
+}
+
+fn apply_bundle_settings(
+    service: &QueryService,
+    dataset_id: DatasetId,
+    kind: Option<DatasetKind>,
+    column_count: usize,
+    settings: &Value,
+) -> Result<()> {
+    if let Some(kind) = kind {
+        service.set_dataset_kind(dataset_id, kind)?;
+    }
+    let mut types = service.load_column_types(dataset_id)?;
+    let mut visibility = BTreeMap::new();
+    let mut aliases = BTreeMap::new();
+    let mut formats = BTreeMap::new();
+    let columns = settings
+        .get("columns")
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    for (idx, prefs) in columns.iter().enumerate().take(column_count) {
+        let idx = idx as i64;
+        if let Some(column_type) = prefs
+            .get("type")
+            .and_then(Value::as_str)
+            .and_then(ColumnType::parse)
+        {
+            types.insert(idx, column_type);
+        }
+        if let Some(visible) = prefs.get("visible").and_then(Value::as_bool) {
+            visibility.insert(idx, visible);
+        }
+        if let Some(alias) = prefs.get("alias").and_then(Value::as_str) {
+            aliases.insert(idx, alias.to_string());
+        }
+        if let Some(format) = prefs.get("format").and_then(format_from_json) {
+            formats.insert(idx, format);
+        }
+    }
+    service.upsert_column_types(dataset_id, types)?;
+    service.upsert_column_visibility(dataset_id, visibility)?;
+    service.upsert_column_aliases(dataset_id, aliases)?;
+    service.upsert_column_formats(dataset_id, formats)?;
+
+    let presets = settings.get("presets").and_then(Value::as_object);
+    for (preset_name, preset) in presets.into_iter().flatten() {
+        let preset = preset
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter_map(|(idx, visible)| {
+                let idx = idx.parse::<i64>().ok()?;
+                ((0..column_count as i64).contains(&idx)).then_some((idx, visible.as_bool()?))
+            })
+            .collect::<BTreeMap<_, _>>();
+        if !preset.is_empty() {
+            service.save_visibility_preset(dataset_id, preset_name.clone(), preset)?;
+        }
+    }
+
+    if let Some(display) = settings.get("display").and_then(display_from_json) {
+        service.save_dataset_display(dataset_id, display)?;
+    }
+    if let Some(page_size) = settings.get("page_size").and_then(Value::as_i64) {
+        service.save_page_size(dataset_id, Some(page_size))?;
+    }
+
+    let computed_columns = settings
+        .get("computed_columns")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|column| {
+            Some(ComputedColumn {
+                name: column.get("name")?.as_str()?.to_string(),
+                expression: column.get("expression")?.as_str()?.to_string(),
+            })
+        })
+        .collect::<Vec<_>>();
+    if !computed_columns.is_empty() {
+        service.save_computed_columns(dataset_id, computed_columns)?;
+    }
+
+    let owner_splits = settings.get("owner_splits").and_then(Value::as_object);
+    for (row_idx, shares) in owner_splits.into_iter().flatten() {
+        let Ok(row_idx) = row_idx.parse::<usize>() else {
+            continue;
+        };
+        let shares = shares
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|share| {
+                Some(OwnerShare {
+                    owner: share.get("owner")?.as_str()?.to_string(),
+                    percent: share.get("percent")?.as_f64()?,
+                })
+            })
+            .collect::<Vec<_>>();
+        if !shares.is_empty() {
+            service.save_owner_shares(dataset_id, row_idx, shares)?;
+        }
+    }
+    Ok(())
+}
+
+fn tabular_from_json(data: &Value) -> Option<TabularData> {
+    let strings = |value: &Value| -> Option<Vec<String>> {
+        value
+            .as_array()?
+            .iter()
+            .map(|cell| cell.as_str().map(str::to_string))
+            .collect()
+    };
+    let columns = strings(data.get("columns")?)?;
+    let rows = data
+        .get("rows")?
+        .as_array()?
+        .iter()
+        .map(|row| strings(row).filter(|row| row.len() == columns.len()))
+        .collect::<Option<Vec<_>>>()?;
+    Some(TabularData { columns, rows })
+}
+
+fn read_entry(zip: &mut ZipArchive<File>, entry: &str) -> Result<Value> {
+    let mut text = String::new();
+    zip.by_name(entry)
+        .with_context(|| format!("資料集封裝缺少 {entry}"))?
+        .read_to_string(&mut text)
+        .with_context(|| format!("failed to read {entry}"))?;
+    serde_json::from_str(&text).with_context(|| format!("{entry} 不是有效的 JSON"))
+}
@@ -0,0 +1,425 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context, Result};
+use bom_core::holdings::DuplicateCodePolicy;
+use bom_core::numeric::NumberLocale;
+use bom_core::report::SnapshotSchedule;
+
+use crate::infra::export::csv::export_csv;
+use crate::infra::import::registry::ImporterRegistry;
+use crate::infra::sqlite::queries::load_setting;
+use crate::infra::sqlite::repo::SqliteRepo;
+use crate::infra::sqlite::schema::init_db;
+use crate::infra::sqlite::sync_folder::{
+    acquire_writer_lock, detect_sync_folder, machine_name, use_sync_safe_journal, WriterLock,
+    WriterLockOutcome,
+};
+use crate::platform::desktop::single_instance::Handoff;
+use crate::remote_backup::{load_remote_backup_config, upload_and_record};
+use crate::server::{serve, DEFAULT_SERVER_ADDR};
+use crate::usecase::ports::repo::DatasetRepository;
+use crate::usecase::services::import_service::ImportService;
+use crate::usecase::services::number_locale::NumberLocaleSetting;
+use crate::usecase::services::query_service::QueryService;
+use crate::{
+    default_db_path, display_export_table, load_all_rows, load_snapshot_schedule,
+    resolve_column_types, run_scheduled_snapshots, QueryOptions, NUMBER_LOCALE_KEY,
+};
+
+pub const CLI_USAGE: &str = "\
+用法：
+  bom [--db <路徑>] list
+  bom [--db <路徑>] import [--merge-duplicates] <檔案.xlsx|檔案.csv>
+  bom [--db <路徑>] export --dataset <id> [--format csv] [--output <檔案>]
+  bom [--db <路徑>] query --dataset <id> [--filter <欄位>=<文字>] [--search <文字>] [--sort <欄位>] [--desc]
+  bom [--db <路徑>] backup [<檔案>]
+  bom [--db <路徑>] snapshot
+  bom [--db <路徑>] serve [--bind <位址:埠>]
+  bom <檔案.xlsx|檔案.csv>
+  bom --read-only
+
+不帶子命令時啟動桌面程式；只給一個可匯入的檔案時，啟動桌面程式並匯入該檔案；
+--read-only 以唯讀模式啟動，無法編輯、刪除或匯入。
+backup 在已設定遠端備份（檔案 → 遠端備份）時會一併上傳；
+import 依 檢視 → 數字寫法 的設定解讀 CSV 中的數字；持股明細有重複代號時預設保留各筆並提出警告，
+--merge-duplicates 改為合併成一筆（數量加總、買進價以數量加權平均）；
+snapshot 依 設定 → 自動記錄總結 的頻率記錄總結（關閉時視為每日），適合由 cron 定期執行。
+serve 啟動時印出本次有效的存取權杖，請求需帶 Authorization: Bearer <權杖> 或 ?token=<權杖>。";
+
+/// A headless subcommand; the GUI is launched only when none is given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliCommand {
+    List,
+    Import {
+        path: PathBuf,
+        merge_duplicates: bool,
+    },
+    Export {
+        dataset_id: i64,
+        output: Option<PathBuf>,
+    },
+    Query {
+        dataset_id: i64,
+        filter: Option<(String, String)>,
+        search: String,
+        sort: Option<String>,
+        sort_desc: bool,
+    },
+    Backup {
+        output: Option<PathBuf>,
+    },
+    /// Records the scheduled summary snapshots that are due, for running from cron.
+    Snapshot,
+    /// Read-only JSON API; see [`crate::server::serve`].
+    Serve {
+        addr: String,
+    },
+    Help,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliInvocation {
+    pub db_path: Option<PathBuf>,
+    pub command: CliCommand,
+}
+
+/// Arguments that launch the GUI rather than a subcommand: nothing, `--read-only`, or a
+/// lone importable file as passed by "open with" or a double-click on an associated file.
+/// The result is what the GUI should do first, or what to ask a running instance to do.
+pub fn parse_gui_args(args: &[String]) -> Option<Handoff> {
+    let arg = match args {
+        [] => return Some(Handoff::Focus),
+        [flag] if flag == "--read-only" => return Some(Handoff::ReadOnly),
+        [arg] => arg,
+        _ => return None,
+    };
+    let path = PathBuf::from(arg);
+    let importable = ImporterRegistry::with_defaults().find(&path).is_ok();
+    if !importable || !path.is_file() {
+        return None;
+    }
+    // A running instance may have another working directory, so hand over an absolute path.
+    std::path::absolute(&path).ok().map(Handoff::Open)
+}
+
+/// Parses the arguments after the program name. `Ok(None)` means "launch the GUI".
+pub fn parse_cli_args(args: &[String]) -> Result<Option<CliInvocation>> {
+    let mut db_path = None;
+    let mut rest = args;
+    while let Some(first) = rest.first() {
+        if first == "--db" {
+            let value = rest.get(1).ok_or_else(|| anyhow!("--db 需要路徑"))?;
+            db_path = Some(PathBuf::from(value));
+            rest = &rest[2..];
+        } else {
+            break;
+        }
+    }
+    let Some((name, options)) = rest.split_first() else {
+        return Ok(db_path.map(|_| CliInvocation {
+            db_path: None,
+            command: CliCommand::Help,
+        }));
+    };
+
+    let command = match name.as_str() {
+        "list" => {
+            expect_no_args(options)?;
+            CliCommand::List
+        }
+        "import" => {
+            let merge_duplicates = options.iter().any(|option| option == "--merge-duplicates");
+            let paths: Vec<&String> = options
+                .iter()
+                .filter(|option| *option != "--merge-duplicates")
+                .collect();
+            match paths.as_slice() {
+                [path] => CliCommand::Import {
+                    path: PathBuf::from(path),
+                    merge_duplicates,
+                },
+                _ => bail!("import 需要一個檔案路徑"),
+            }
+        }
+        "export" => {
+            let mut dataset_id = None;
+            let mut output = None;
+            let mut iter = options.iter();
+            while let Some(flag) = iter.next() {
+                match flag.as_str() {
+                    "--dataset" => dataset_id = Some(parse_dataset_id(iter.next())?),
+                    "--format" => {
+                        let format = flag_value(flag, iter.next())?;
+                        if !format.eq_ignore_ascii_case("csv") {
+                            bail!("不支援的匯出格式：{format}");
+                        }
+                    }
+                    "--output" => output = Some(PathBuf::from(flag_value(flag, iter.next())?)),
+                    other => bail!("未知的選項：{other}"),
+                }
+            }
+            CliCommand::Export {
+                dataset_id: dataset_id.ok_or_else(|| anyhow!("export 需要 --dataset"))?,
+                output,
+            }
+        }
+        "query" => {
+            let mut dataset_id = None;
+            let mut filter = None;
+            let mut search = String::new();
+            let mut sort = None;
+            let mut sort_desc = false;
+            let mut iter = options.iter();
+            while let Some(flag) = iter.next() {
+                match flag.as_str() {
+                    "--dataset" => dataset_id = Some(parse_dataset_id(iter.next())?),
+                    "--filter" => {
+                        let value = flag_value(flag, iter.next())?;
+                        let (column, term) = value
+                            .split_once('=')
+                            .ok_or_else(|| anyhow!("--filter 格式應為 欄位=文字"))?;
+                        filter = Some((column.trim().to_string(), term.trim().to_string()));
+                    }
+                    "--search" => search = flag_value(flag, iter.next())?.to_string(),
+                    "--sort" => sort = Some(flag_value(flag, iter.next())?.to_string()),
+                    "--desc" => sort_desc = true,
+                    other => bail!("未知的選項：{other}"),
+                }
+            }
+            CliCommand::Query {
+                dataset_id: dataset_id.ok_or_else(|| anyhow!("query 需要 --dataset"))?,
+                filter,
+                search,
+                sort,
+                sort_desc,
+            }
+        }
+        "backup" => match options {
+            [] => CliCommand::Backup { output: None },
+            [path] => CliCommand::Backup {
+                output: Some(PathBuf::from(path)),
+            },
+            _ => bail!("backup 最多接受一個檔案路徑"),
+        },
+        "snapshot" => {
+            expect_no_args(options)?;
+            CliCommand::Snapshot
+        }
+        "serve" => match options {
+            [] => CliCommand::Serve {
+                addr: DEFAULT_SERVER_ADDR.to_string(),
+            },
+            [flag, addr] if flag == "--bind" => CliCommand::Serve { addr: addr.clone() },
+            _ => bail!("serve 只接受 --bind <位址:埠>"),
+        },
+        "help" | "--help" | "-h" => CliCommand::Help,
+        other => bail!("未知的子命令：{other}"),
+    };
+    Ok(Some(CliInvocation { db_path, command }))
+}
+
+fn expect_no_args(options: &[String]) -> Result<()> {
+    match options.first() {
+        Some(extra) => bail!("多餘的參數：{extra}"),
+        None => Ok(()),
+    }
+}
+
+fn flag_value<'a>(flag: &str, value: Option<&'a String>) -> Result<&'a str> {
+    value
+        .map(String::as_str)
+        .ok_or_else(|| anyhow!("{flag} 需要一個值"))
+}
+
+fn parse_dataset_id(value: Option<&String>) -> Result<i64> {
+    let value = flag_value("--dataset", value)?;
+    value
+        .parse()
+        .with_context(|| format!("資料集編號無效：{value}"))
+}
+
+fn column_index(columns: &[String], name: &str) -> Result<i64> {
+    columns
+        .iter()
+        .position(|column| column == name)
+        .map(|idx| idx as i64)
+        .ok_or_else(|| anyhow!("找不到欄位：{name}"))
+}
+
+/// Writes rows as tab-separated lines so the output pipes into `cut`, `awk` or a spreadsheet.
+pub fn format_tsv(headers: &[String], rows: &[Vec<String>]) -> String {
+    let clean = |value: &String| value.replace(['\t', '\n', '\r'], " ");
+    let mut text = String::new();
+    for line in std::iter::once(headers).chain(rows.iter().map(Vec::as_slice)) {
+        text.push_str(&line.iter().map(clean).collect::<Vec<_>>().join("\t"));
+        text.push('\n');
+    }
+    text
+}
+
+pub fn run_cli(invocation: CliInvocation) -> Result<()> {
+    if invocation.command == CliCommand::Help {
+        println!("{CLI_USAGE}");
+        return Ok(());
+    }
+    let db_path = match invocation.db_path {
+        Some(path) => path,
+        None => default_db_path()?,
+    };
+    if let Some(parent) = db_path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create data dir: {}", parent.display()))?;
+    }
+    let repo = Arc::new(SqliteRepo {
+        db_path: db_path.clone(),
+    });
+    repo.init().map_err(anyhow::Error::from)?;
+    let query_service = Arc::new(QueryService::new(repo));
+
+    match invocation.command {
+        CliCommand::List => {
+            let datasets = query_service
+                .list_datasets(false)
+                .map_err(anyhow::Error::from)?;
+            for dataset in datasets {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    dataset.id.0, dataset.name, dataset.row_count, dataset.source_path
+                );
+            }
+        }
+        CliCommand::Import {
+            path,
+            merge_duplicates,
+        } => {
+            let _lock = claim_sync_folder_writer(&db_path)?;
+            run_import(&db_path, &path, merge_duplicates)?;
+        }
+        CliCommand::Export { dataset_id, output } => {
+            let (headers, rows) =
+                load_all_rows(&query_service, dataset_id, &QueryOptions::default())?;
+            let aliases = query_service
+                .load_column_aliases(dataset_id.into())
+                .map_err(anyhow::Error::from)?;
+            let stored_types = query_service
+                .load_column_types(dataset_id.into())
+                .map_err(anyhow::Error::from)?;
+            let display = query_service
+                .load_dataset_display(dataset_id.into())
+                .map_err(anyhow::Error::from)?;
+            let types = resolve_column_types(&headers, &rows, &stored_types);
+            let columns: Vec<(usize, String)> = headers.into_iter().enumerate().collect();
+            let (headers, rows) = display_export_table(&columns, &rows, &aliases, &types, &display);
+            let output =
+                output.unwrap_or_else(|| PathBuf::from(format!("dataset-{dataset_id}.csv")));
+            export_csv(&output, &headers, &rows)?;
+            eprintln!("已匯出 {} 筆至 {}", rows.len(), output.display());
+        }
+        CliCommand::Query {
+            dataset_id,
+            filter,
+            search,
+            sort,
+            sort_desc,
+        } => {
+            let mut options = QueryOptions {
+                global_search: search,
+                sort_desc,
+                ..QueryOptions::default()
+            };
+            if filter.is_some() || sort.is_some() {
+                // Columns are addressed by header, so resolve them against the dataset first.
+                let (columns, _) =
+                    load_all_rows(&query_service, dataset_id, &QueryOptions::default())?;
+                if let Some((column, term)) = filter {
+                    options.column_search_col = Some(column_index(&columns, &column)?);
+                    options.column_search_text = term;
+                }
+                if let Some(column) = sort {
+                    options.sort_col = Some(column_index(&columns, &column)?);
+                }
+            }
+            let (headers, rows) = load_all_rows(&query_service, dataset_id, &options)?;
+            print!("{}", format_tsv(&headers, &rows));
+        }
+        CliCommand::Backup { output } => {
+            let output = output.unwrap_or_else(|| {
+                PathBuf::from(format!(
+                    "bom-backup-{}.sqlite",
+                    chrono::Local::now().format("%Y%m%d-%H%M%S")
+                ))
+            });
+            query_service
+                .backup_database(&output)
+                .map_err(anyhow::Error::from)?;
+            eprintln!("已備份資料庫至 {}", output.display());
+            let remote = load_remote_backup_config(&query_service)?;
+            if remote.is_configured() {
+                let url = upload_and_record(&query_service, &remote, &output)?;
+                eprintln!("已上傳至 {url}");
+            }
+        }
+        CliCommand::Snapshot => {
+            let _lock = claim_sync_folder_writer(&db_path)?;
+            // Running it is the schedule when the app's own is off.
+            let schedule = match load_snapshot_schedule(&query_service)? {
+                SnapshotSchedule::Off => SnapshotSchedule::Daily,
+                schedule => schedule,
+            };
+            let recorded = run_scheduled_snapshots(&query_service, schedule)?;
+            for dataset_id in &recorded {
+                println!("{}", dataset_id.0);
+            }
+            eprintln!("已記錄 {} 個資料集的總結", recorded.len());
+        }
+        CliCommand::Serve { addr } => serve(query_service, &addr)?,
+        CliCommand::Help => unreachable!("handled above"),
+    }
+    Ok(())
+}
+
+/// When the database sits in a sync folder, refuses to write while another machine holds
+/// the writer lock; otherwise holds it until the returned guard is dropped.
+fn claim_sync_folder_writer(db_path: &Path) -> Result<Option<WriterLock>> {
+    let Some(provider) = detect_sync_folder(db_path) else {
+        return Ok(None);
+    };
+    use_sync_safe_journal(db_path)?;
+    match acquire_writer_lock(db_path, &machine_name())? {
+        WriterLockOutcome::Acquired(lock) => Ok(Some(lock)),
+        WriterLockOutcome::HeldBy(holder) => bail!(
+            "電腦「{holder}」正在使用這個資料庫（位於 {}），請待對方關閉 BOM 後再試",
+            provider.label()
+        ),
+    }
+}
+
+fn run_import(db_path: &Path, path: &Path, merge_duplicates: bool) -> Result<()> {
+    init_db(db_path)?;
+    let locale = load_setting(db_path, NUMBER_LOCALE_KEY)?
+        .as_deref()
+        .and_then(NumberLocale::parse)
+        .unwrap_or_default();
+    let import_service = ImportService::new(db_path.to_path_buf())
+        .with_number_locale(NumberLocaleSetting::new(locale));
+    let validation = import_service.validate(path)?;
+    let results = if validation.is_clean() {
+        import_service.import(path)?
+    } else if merge_duplicates {
+        import_service.import_workbook(path, DuplicateCodePolicy::Merge)?
+    } else {
+        for duplicate in &validation.duplicate_codes {
+            eprintln!(
+                "警告：代號 {} 重複（{}），已保留各筆；改用 --merge-duplicates 可合併",
+                duplicate.code,
+                duplicate.names.join("、")
+            );
+        }
+        import_service.import_workbook(path, DuplicateCodePolicy::KeepBoth)?
+    };
+    for result in results {
+        println!("{}\t{}", result.dataset_id, result.row_count);
+    }
+    Ok(())
+}
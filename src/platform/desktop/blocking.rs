@@ -5,3 +5,18 @@ where
 {
     f()
 }
+
+/// Lets the UI render once before a task continues, e.g. between the steps of a long
+/// [`run_blocking`] loop so progress shows up.
+pub async fn yield_to_renderer() {
+    let mut yielded = false;
+    std::future::poll_fn(|cx| {
+        if yielded {
+            return std::task::Poll::Ready(());
+        }
+        yielded = true;
+        cx.waker().wake_by_ref();
+        std::task::Poll::Pending
+    })
+    .await
+}
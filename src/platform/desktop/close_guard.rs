@@ -0,0 +1,62 @@
+use dioxus::desktop::tao::event::{Event, WindowEvent};
+use dioxus::desktop::tao::window::WindowId;
+use dioxus::desktop::{use_wry_event_handler, window, WindowCloseBehaviour};
+use dioxus::prelude::spawn;
+
+/// Lets `intercept` veto closing the current window: when it returns `true` the close
+/// request is dropped and the window stays open.
+pub fn use_close_guard(mut intercept: impl FnMut() -> bool + 'static) {
+    let desktop = window();
+    let window_id = desktop.window.id();
+    use_wry_event_handler(move |event, _| {
+        let Event::WindowEvent {
+            event: WindowEvent::CloseRequested,
+            window_id: closing,
+            ..
+        } = event
+        else {
+            return;
+        };
+        if *closing != window_id || !intercept() {
+            return;
+        }
+        // The request is carried out right after the handlers run and cannot be cancelled;
+        // hiding instead of closing, then showing the window again, keeps it alive.
+        desktop.set_close_behavior(WindowCloseBehaviour::WindowHides);
+        let desktop = desktop.clone();
+        spawn(async move {
+            desktop.window.set_visible(true);
+            desktop.set_close_behavior(WindowCloseBehaviour::WindowCloses);
+        });
+    });
+}
+
+/// Closes the current window without asking the [`use_close_guard`] callback.
+pub fn close_window() {
+    let desktop = window();
+    desktop.set_close_behavior(WindowCloseBehaviour::WindowCloses);
+    desktop.close();
+}
+
+/// Closes every window in `windows` and the current one last, which ends the app.
+pub fn quit_app(windows: impl IntoIterator<Item = WindowId>) {
+    close_other_windows(windows);
+    close_window();
+}
+
+/// Closes every window in `windows` except the current one, without asking their
+/// [`use_close_guard`] callbacks.
+pub fn close_other_windows(windows: impl IntoIterator<Item = WindowId>) {
+    let desktop = window();
+    let current = desktop.window.id();
+    for id in windows {
+        if id != current {
+            desktop.close_window(id);
+        }
+    }
+}
+
+/// The window the calling component is shown in.
+pub fn current_window_id() -> WindowId {
+    window().window.id()
+}
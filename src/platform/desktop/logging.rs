@@ -0,0 +1,148 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Context, Result};
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::platform::desktop::paths::log_file_path;
+
+/// Size at which `bom.log` is rotated to `bom.log.1`.
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+/// Rotated files kept besides the active one.
+const KEPT_LOG_FILES: usize = 3;
+
+/// Appends to a log file and rotates it to `<name>.1` … `<name>.N` once it grows past
+/// `max_bytes`, dropping the oldest.
+pub struct RotatingFileWriter {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    file: Option<File>,
+    written: u64,
+}
+
+impl RotatingFileWriter {
+    pub fn new(path: PathBuf, max_bytes: u64, keep: usize) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create log dir: {}", parent.display()))?;
+        }
+        let mut writer = Self {
+            path,
+            max_bytes,
+            keep,
+            file: None,
+            written: 0,
+        };
+        writer.open()?;
+        Ok(writer)
+    }
+
+    fn open(&mut self) -> io::Result<()> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.written = file.metadata().map(|meta| meta.len()).unwrap_or(0);
+        self.file = Some(file);
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file = None;
+        for idx in (1..self.keep).rev() {
+            let from = rotated_path(&self.path, idx);
+            if from.exists() {
+                std::fs::rename(&from, rotated_path(&self.path, idx + 1))?;
+            }
+        }
+        if self.keep > 0 {
+            std::fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        } else {
+            std::fs::remove_file(&self.path)?;
+        }
+        self.open()
+    }
+}
+
+pub fn rotated_path(path: &Path, idx: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{idx}"));
+    PathBuf::from(name)
+}
+
+impl Write for RotatingFileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        if self.file.is_none() {
+            self.open()?;
+        }
+        let written = self
+            .file
+            .as_mut()
+            .map(|file| file.write(buf))
+            .unwrap_or(Ok(0))?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.file.as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Shares one rotating writer between every event the subscriber formats.
+#[derive(Clone)]
+struct SharedLogWriter(Arc<Mutex<RotatingFileWriter>>);
+
+struct SharedLogGuard<'a>(std::sync::MutexGuard<'a, RotatingFileWriter>);
+
+impl Write for SharedLogGuard<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl<'a> MakeWriter<'a> for SharedLogWriter {
+    type Writer = SharedLogGuard<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        SharedLogGuard(
+            self.0
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        )
+    }
+}
+
+/// Sends `tracing` events from the app and `bom-core` to the rotating `bom.log`. Logging is
+/// best-effort: if the file cannot be opened the app still starts.
+pub fn init_logging() {
+    let Ok(writer) = log_file_path()
+        .and_then(|path| RotatingFileWriter::new(path, MAX_LOG_BYTES, KEPT_LOG_FILES))
+    else {
+        return;
+    };
+    let level = if cfg!(debug_assertions) {
+        tracing::Level::DEBUG
+    } else {
+        tracing::Level::INFO
+    };
+    let _ = tracing_subscriber::fmt()
+        .with_writer(SharedLogWriter(Arc::new(Mutex::new(writer))))
+        .with_ansi(false)
+        .with_max_level(level)
+        .with_target(true)
+        .try_init();
+}
@@ -0,0 +1,385 @@
+use std::cell::RefCell;
+
+use bom_core::numeric::NumberLocale;
+use dioxus::desktop::muda::accelerator::Accelerator;
+use dioxus::desktop::muda::{AboutMetadata, Menu, MenuItem, PredefinedMenuItem, Submenu};
+
+/// Application commands reachable from the native menu. Each maps to a stable menu id so
+/// the event handler can translate muda events back into a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuCommand {
+    Import,
+    ImportWithMapping,
+    ImportFolder,
+    ExportCsv,
+    ExportXlsx,
+    ExportImage,
+    BackupDatabase,
+    RemoteBackupSettings,
+    LightTheme,
+    DarkTheme,
+    Columns,
+    NumberFormat,
+    ComputedColumns,
+    ToggleSplitView,
+    OpenLogFile,
+    CommandPalette,
+    SummaryReport,
+    ReportBuilder,
+    RunReportScript,
+    DividendOwners,
+    DividendYearRollover,
+    LockNow,
+    AppLockSettings,
+    ToggleReadOnly,
+    ExportPreferences,
+    ImportPreferences,
+    ExportBundle,
+    ImportBundle,
+    LoadDemoData,
+    /// 結束; asks about unsaved edits first, as closing the window does.
+    Quit,
+    /// Index into the recent-imports list, most recent first.
+    RecentImport(usize),
+    /// Index into the saved reports, in name order.
+    SavedReport(usize),
+    /// How numbers in imported CSV files and edited cells are written.
+    NumberLocale(NumberLocale),
+}
+
+const RECENT_IMPORT_ID_PREFIX: &str = "bom-recent-";
+const NUMBER_LOCALE_ID_PREFIX: &str = "bom-number-locale-";
+const SAVED_REPORT_ID_PREFIX: &str = "bom-saved-report-";
+
+thread_local! {
+    // The 最近匯入 submenu, kept so its entries can be rebuilt when the list changes.
+    static RECENT_IMPORTS_MENU: RefCell<Option<Submenu>> = const { RefCell::new(None) };
+    // The 已存報表 submenu, likewise rebuilt when reports are saved or deleted.
+    static SAVED_REPORTS_MENU: RefCell<Option<Submenu>> = const { RefCell::new(None) };
+}
+
+impl MenuCommand {
+    pub const ALL: [MenuCommand; 30] = [
+        MenuCommand::Import,
+        MenuCommand::ImportWithMapping,
+        MenuCommand::ImportFolder,
+        MenuCommand::ExportCsv,
+        MenuCommand::ExportXlsx,
+        MenuCommand::ExportImage,
+        MenuCommand::BackupDatabase,
+        MenuCommand::RemoteBackupSettings,
+        MenuCommand::LightTheme,
+        MenuCommand::DarkTheme,
+        MenuCommand::Columns,
+        MenuCommand::NumberFormat,
+        MenuCommand::ComputedColumns,
+        MenuCommand::ToggleSplitView,
+        MenuCommand::OpenLogFile,
+        MenuCommand::CommandPalette,
+        MenuCommand::SummaryReport,
+        MenuCommand::ReportBuilder,
+        MenuCommand::RunReportScript,
+        MenuCommand::DividendOwners,
+        MenuCommand::DividendYearRollover,
+        MenuCommand::LockNow,
+        MenuCommand::AppLockSettings,
+        MenuCommand::ToggleReadOnly,
+        MenuCommand::ExportPreferences,
+        MenuCommand::ImportPreferences,
+        MenuCommand::ExportBundle,
+        MenuCommand::ImportBundle,
+        MenuCommand::LoadDemoData,
+        MenuCommand::Quit,
+    ];
+
+    pub fn id(self) -> String {
+        let id = match self {
+            MenuCommand::Import => "bom-import",
+            MenuCommand::ImportWithMapping => "bom-import-with-mapping",
+            MenuCommand::ImportFolder => "bom-import-folder",
+            MenuCommand::ExportCsv => "bom-export-csv",
+            MenuCommand::ExportXlsx => "bom-export-xlsx",
+            MenuCommand::ExportImage => "bom-export-image",
+            MenuCommand::BackupDatabase => "bom-backup",
+            MenuCommand::RemoteBackupSettings => "bom-remote-backup",
+            MenuCommand::LightTheme => "bom-theme-light",
+            MenuCommand::DarkTheme => "bom-theme-dark",
+            MenuCommand::Columns => "bom-columns",
+            MenuCommand::NumberFormat => "bom-number-format",
+            MenuCommand::ComputedColumns => "bom-computed-columns",
+            MenuCommand::ToggleSplitView => "bom-split-view",
+            MenuCommand::OpenLogFile => "bom-open-log",
+            MenuCommand::CommandPalette => "bom-command-palette",
+            MenuCommand::SummaryReport => "bom-summary-report",
+            MenuCommand::ReportBuilder => "bom-report-builder",
+            MenuCommand::RunReportScript => "bom-run-report-script",
+            MenuCommand::DividendOwners => "bom-dividend-owners",
+            MenuCommand::DividendYearRollover => "bom-dividend-year-rollover",
+            MenuCommand::LockNow => "bom-lock-now",
+            MenuCommand::AppLockSettings => "bom-app-lock-settings",
+            MenuCommand::ToggleReadOnly => "bom-read-only",
+            MenuCommand::ExportPreferences => "bom-export-preferences",
+            MenuCommand::ImportPreferences => "bom-import-preferences",
+            MenuCommand::ExportBundle => "bom-export-bundle",
+            MenuCommand::ImportBundle => "bom-import-bundle",
+            MenuCommand::LoadDemoData => "bom-load-demo-data",
+            MenuCommand::Quit => "bom-quit",
+            MenuCommand::RecentImport(idx) => return format!("{RECENT_IMPORT_ID_PREFIX}{idx}"),
+            MenuCommand::SavedReport(idx) => return format!("{SAVED_REPORT_ID_PREFIX}{idx}"),
+            MenuCommand::NumberLocale(locale) => {
+                return format!("{NUMBER_LOCALE_ID_PREFIX}{}", locale.as_str())
+            }
+        };
+        id.to_string()
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        if let Some(idx) = id.strip_prefix(RECENT_IMPORT_ID_PREFIX) {
+            return idx.parse().ok().map(MenuCommand::RecentImport);
+        }
+        if let Some(idx) = id.strip_prefix(SAVED_REPORT_ID_PREFIX) {
+            return idx.parse().ok().map(MenuCommand::SavedReport);
+        }
+        if let Some(locale) = id.strip_prefix(NUMBER_LOCALE_ID_PREFIX) {
+            return NumberLocale::parse(locale).map(MenuCommand::NumberLocale);
+        }
+        Self::ALL.into_iter().find(|command| command.id() == id)
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            MenuCommand::Import => "匯入…",
+            MenuCommand::ImportWithMapping => "以對應設定匯入 CSV…",
+            MenuCommand::ImportFolder => "匯入資料夾…",
+            MenuCommand::ExportCsv => "匯出 CSV…",
+            MenuCommand::ExportXlsx => "匯出 Excel…",
+            MenuCommand::ExportImage => "匯出為圖片…",
+            MenuCommand::BackupDatabase => "備份資料庫…",
+            MenuCommand::RemoteBackupSettings => "遠端備份…",
+            MenuCommand::LightTheme => "淺色",
+            MenuCommand::DarkTheme => "深色",
+            MenuCommand::Columns => "欄位…",
+            MenuCommand::NumberFormat => "數字格式…",
+            MenuCommand::ComputedColumns => "計算欄位…",
+            MenuCommand::ToggleSplitView => "分割檢視",
+            MenuCommand::OpenLogFile => "開啟記錄檔",
+            MenuCommand::CommandPalette => "命令面板…",
+            MenuCommand::SummaryReport => "總結報表",
+            MenuCommand::ReportBuilder => "報表產生器…",
+            MenuCommand::RunReportScript => "執行自訂報表…",
+            MenuCommand::DividendOwners => "所有權人…",
+            MenuCommand::DividendYearRollover => "年度結轉…",
+            MenuCommand::LockNow => "立即鎖定",
+            MenuCommand::AppLockSettings => "啟動密碼…",
+            MenuCommand::ToggleReadOnly => "唯讀模式",
+            MenuCommand::ExportPreferences => "匯出設定…",
+            MenuCommand::ImportPreferences => "匯入設定…",
+            MenuCommand::ExportBundle => "匯出資料集封裝…",
+            MenuCommand::ImportBundle => "匯入資料集封裝…",
+            MenuCommand::LoadDemoData => "載入示範資料",
+            MenuCommand::Quit if cfg!(target_os = "macos") => "結束 BOM",
+            MenuCommand::Quit => "結束",
+            MenuCommand::RecentImport(_) => "最近匯入",
+            MenuCommand::SavedReport(_) => "已存報表",
+            MenuCommand::NumberLocale(locale) => locale.label(),
+        }
+    }
+
+    /// `CmdOrCtrl` resolves to ⌘ on macOS and Ctrl elsewhere.
+    pub fn accelerator(self) -> Option<&'static str> {
+        match self {
+            MenuCommand::Import => Some("CmdOrCtrl+O"),
+            MenuCommand::ExportCsv => Some("CmdOrCtrl+E"),
+            MenuCommand::BackupDatabase => Some("CmdOrCtrl+Shift+B"),
+            MenuCommand::ToggleSplitView => Some("CmdOrCtrl+\\"),
+            MenuCommand::CommandPalette => Some("CmdOrCtrl+K"),
+            MenuCommand::LockNow => Some("CmdOrCtrl+L"),
+            MenuCommand::Quit if cfg!(target_os = "macos") => Some("CmdOrCtrl+Q"),
+            _ => None,
+        }
+    }
+
+    fn menu_item(self) -> MenuItem {
+        let accelerator = self
+            .accelerator()
+            .and_then(|text| text.parse::<Accelerator>().ok());
+        MenuItem::with_id(self.id(), self.label(), true, accelerator)
+    }
+}
+
+/// Menu bar of the main window: 檔案, 編輯, 檢視, 總結報表 and 說明.
+pub fn build_app_menu() -> Menu {
+    let menu = Menu::new();
+
+    #[cfg(target_os = "macos")]
+    {
+        let app_menu = Submenu::new("BOM", true);
+        let _ = app_menu.append_items(&[
+            &PredefinedMenuItem::about(Some("關於 BOM"), Some(about_metadata())),
+            &PredefinedMenuItem::separator(),
+            &PredefinedMenuItem::hide(None),
+            &PredefinedMenuItem::hide_others(None),
+            &PredefinedMenuItem::show_all(None),
+            &PredefinedMenuItem::separator(),
+            &MenuCommand::Quit.menu_item(),
+        ]);
+        let _ = menu.append(&app_menu);
+    }
+
+    let recent_menu = Submenu::new("最近匯入", true);
+    fill_recent_imports_menu(&recent_menu, &[]);
+    RECENT_IMPORTS_MENU.with(|slot| *slot.borrow_mut() = Some(recent_menu.clone()));
+
+    let file_menu = Submenu::new("檔案", true);
+    let _ = file_menu.append_items(&[
+        &MenuCommand::Import.menu_item(),
+        &MenuCommand::ImportWithMapping.menu_item(),
+        &MenuCommand::ImportFolder.menu_item(),
+        &recent_menu,
+        &PredefinedMenuItem::separator(),
+        &MenuCommand::ExportCsv.menu_item(),
+        &MenuCommand::ExportXlsx.menu_item(),
+        &MenuCommand::ExportImage.menu_item(),
+        &MenuCommand::BackupDatabase.menu_item(),
+        &MenuCommand::RemoteBackupSettings.menu_item(),
+        &PredefinedMenuItem::separator(),
+        &MenuCommand::ExportPreferences.menu_item(),
+        &MenuCommand::ImportPreferences.menu_item(),
+        &MenuCommand::ExportBundle.menu_item(),
+        &MenuCommand::ImportBundle.menu_item(),
+        &PredefinedMenuItem::separator(),
+        &MenuCommand::LockNow.menu_item(),
+        &MenuCommand::AppLockSettings.menu_item(),
+        &MenuCommand::ToggleReadOnly.menu_item(),
+    ]);
+    #[cfg(not(target_os = "macos"))]
+    let _ = file_menu.append_items(&[
+        &PredefinedMenuItem::separator(),
+        &MenuCommand::Quit.menu_item(),
+    ]);
+
+    let edit_menu = Submenu::new("編輯", true);
+    let _ = edit_menu.append_items(&[
+        &PredefinedMenuItem::undo(Some("復原")),
+        &PredefinedMenuItem::redo(Some("取消復原")),
+        &PredefinedMenuItem::separator(),
+        &PredefinedMenuItem::cut(Some("剪下")),
+        &PredefinedMenuItem::copy(Some("複製")),
+        &PredefinedMenuItem::paste(Some("貼上")),
+        &PredefinedMenuItem::select_all(Some("全選")),
+    ]);
+
+    let theme_menu = Submenu::new("主題", true);
+    let _ = theme_menu.append_items(&[
+        &MenuCommand::LightTheme.menu_item(),
+        &MenuCommand::DarkTheme.menu_item(),
+    ]);
+    let number_locale_menu = Submenu::new("數字寫法", true);
+    for locale in NumberLocale::ALL {
+        let _ = number_locale_menu.append(&MenuCommand::NumberLocale(locale).menu_item());
+    }
+    let view_menu = Submenu::new("檢視", true);
+    let _ = view_menu.append_items(&[
+        &MenuCommand::CommandPalette.menu_item(),
+        &theme_menu,
+        &PredefinedMenuItem::separator(),
+        &MenuCommand::Columns.menu_item(),
+        &MenuCommand::NumberFormat.menu_item(),
+        &MenuCommand::ComputedColumns.menu_item(),
+        &number_locale_menu,
+        &MenuCommand::ToggleSplitView.menu_item(),
+    ]);
+
+    let saved_reports_menu = Submenu::new("已存報表", true);
+    fill_saved_reports_menu(&saved_reports_menu, &[]);
+    SAVED_REPORTS_MENU.with(|slot| *slot.borrow_mut() = Some(saved_reports_menu.clone()));
+
+    let report_menu = Submenu::new("總結報表", true);
+    let _ = report_menu.append_items(&[
+        &MenuCommand::SummaryReport.menu_item(),
+        &PredefinedMenuItem::separator(),
+        &MenuCommand::ReportBuilder.menu_item(),
+        &saved_reports_menu,
+        &MenuCommand::RunReportScript.menu_item(),
+        &PredefinedMenuItem::separator(),
+        &MenuCommand::DividendOwners.menu_item(),
+        &MenuCommand::DividendYearRollover.menu_item(),
+    ]);
+
+    let help_menu = Submenu::new("說明", true);
+    let _ = help_menu.append_items(&[
+        &MenuCommand::LoadDemoData.menu_item(),
+        &MenuCommand::OpenLogFile.menu_item(),
+    ]);
+    #[cfg(not(target_os = "macos"))]
+    let _ = help_menu.append_items(&[
+        &PredefinedMenuItem::separator(),
+        &PredefinedMenuItem::about(Some("關於 BOM"), Some(about_metadata())),
+    ]);
+
+    let _ = menu.append_items(&[&file_menu, &edit_menu, &view_menu, &report_menu, &help_menu]);
+
+    #[cfg(target_os = "macos")]
+    help_menu.set_as_help_menu_for_nsapp();
+
+    menu
+}
+
+/// Replaces the entries of 檔案 → 最近匯入 with `paths`, most recent first.
+pub fn set_recent_imports_menu(paths: &[String]) {
+    RECENT_IMPORTS_MENU.with(|slot| {
+        if let Some(recent_menu) = slot.borrow().as_ref() {
+            fill_recent_imports_menu(recent_menu, paths);
+        }
+    });
+}
+
+fn fill_recent_imports_menu(recent_menu: &Submenu, paths: &[String]) {
+    while recent_menu.remove_at(0).is_some() {}
+    if paths.is_empty() {
+        let _ = recent_menu.append(&MenuItem::new("（沒有紀錄）", false, None));
+        return;
+    }
+    for (idx, path) in paths.iter().enumerate() {
+        let label = format!("{} {path}", idx + 1);
+        let _ = recent_menu.append(&MenuItem::with_id(
+            MenuCommand::RecentImport(idx).id(),
+            label,
+            true,
+            None,
+        ));
+    }
+}
+
+/// Replaces the entries of 總結報表 → 已存報表 with `names`, in the order the app indexes them.
+pub fn set_saved_reports_menu(names: &[String]) {
+    SAVED_REPORTS_MENU.with(|slot| {
+        if let Some(saved_menu) = slot.borrow().as_ref() {
+            fill_saved_reports_menu(saved_menu, names);
+        }
+    });
+}
+
+fn fill_saved_reports_menu(saved_menu: &Submenu, names: &[String]) {
+    while saved_menu.remove_at(0).is_some() {}
+    if names.is_empty() {
+        let _ = saved_menu.append(&MenuItem::new("（沒有報表）", false, None));
+        return;
+    }
+    for (idx, name) in names.iter().enumerate() {
+        let _ = saved_menu.append(&MenuItem::with_id(
+            MenuCommand::SavedReport(idx).id(),
+            name,
+            true,
+            None,
+        ));
+    }
+}
+
+fn about_metadata() -> AboutMetadata {
+    AboutMetadata {
+        name: Some("BOM".to_string()),
+        version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        ..Default::default()
+    }
+}
@@ -1,2 +1,7 @@
 pub mod blocking;
+pub mod close_guard;
+pub mod logging;
+pub mod menu;
+pub mod notify;
 pub mod paths;
+pub mod single_instance;
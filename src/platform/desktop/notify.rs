@@ -0,0 +1,82 @@
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result};
+
+/// Raises an OS notification for a background event, but only while the window is not
+/// focused; the status bar already shows the result otherwise.
+pub fn notify_if_unfocused(title: &str, body: &str) {
+    if dioxus::desktop::window().window.is_focused() {
+        return;
+    }
+    if let Err(err) = show_notification(title, body) {
+        tracing::warn!("通知失敗：{err:#}");
+    }
+}
+
+/// Shows a notification through the platform's own tooling, so no extra runtime is needed.
+/// The command is waited on from a detached thread, so it neither blocks the UI nor is left
+/// behind as a zombie process.
+pub fn show_notification(title: &str, body: &str) -> Result<()> {
+    let mut child = notification_command(title, body)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("failed to start notification command")?;
+    std::thread::spawn(move || {
+        if let Err(err) = child.wait() {
+            tracing::warn!("通知程序結束失敗：{err}");
+        }
+    });
+    Ok(())
+}
+
+/// Windows reads the title and body from these variables, so neither is ever part of the
+/// PowerShell script and no quote in them can end a string early.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub const TITLE_ENV: &str = "BOM_NOTIFY_TITLE";
+#[cfg_attr(not(windows), allow(dead_code))]
+pub const BODY_ENV: &str = "BOM_NOTIFY_BODY";
+
+#[cfg(windows)]
+pub fn notification_command(title: &str, body: &str) -> Command {
+    let script = format!(
+        "[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] > $null; \
+         $template = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02); \
+         $texts = $template.GetElementsByTagName('text'); \
+         $texts.Item(0).AppendChild($template.CreateTextNode($env:{TITLE_ENV})) > $null; \
+         $texts.Item(1).AppendChild($template.CreateTextNode($env:{BODY_ENV})) > $null; \
+         [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('BOM').Show([Windows.UI.Notifications.ToastNotification]::new($template))"
+    );
+    let mut command = Command::new("powershell");
+    command
+        .args(["-NoProfile", "-WindowStyle", "Hidden", "-Command", &script])
+        .env(TITLE_ENV, title)
+        .env(BODY_ENV, body);
+    command
+}
+
+/// The title and body go in as `argv` of the run handler rather than into the script text.
+#[cfg(target_os = "macos")]
+pub fn notification_command(title: &str, body: &str) -> Command {
+    let mut command = Command::new("osascript");
+    command.args([
+        "-e",
+        "on run argv",
+        "-e",
+        "display notification (item 2 of argv) with title (item 1 of argv)",
+        "-e",
+        "end run",
+        title,
+        body,
+    ]);
+    command
+}
+
+/// `--` keeps a title or body starting with `-` from being read as an option.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn notification_command(title: &str, body: &str) -> Command {
+    let mut command = Command::new("notify-send");
+    command.args(["--app-name=BOM", "--", title, body]);
+    command
+}
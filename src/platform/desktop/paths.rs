@@ -1,2 +1,55 @@
-#[allow(dead_code)]
-pub fn paths_placeholder() {}
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use directories::ProjectDirs;
+
+/// Log file next to the database in the app's local data directory; older entries rotate
+/// to `bom.log.1`, `bom.log.2`, ….
+pub fn log_file_path() -> Result<PathBuf> {
+    let project_dirs = ProjectDirs::from("com", "hellhbbd", "bom")
+        .ok_or_else(|| anyhow!("unable to resolve data directory"))?;
+    Ok(project_dirs.data_local_dir().join("bom.log"))
+}
+
+/// Records the loopback port of the running instance so later launches can hand over to it.
+pub fn instance_lock_path() -> Result<PathBuf> {
+    let project_dirs = ProjectDirs::from("com", "hellhbbd", "bom")
+        .ok_or_else(|| anyhow!("unable to resolve data directory"))?;
+    Ok(project_dirs.data_local_dir().join("instance.lock"))
+}
+
+/// Folder scanned for custom report scripts (`*.bomr`); created on first use.
+pub fn report_scripts_dir() -> Result<PathBuf> {
+    let project_dirs = ProjectDirs::from("com", "hellhbbd", "bom")
+        .ok_or_else(|| anyhow!("unable to resolve data directory"))?;
+    let dir = project_dirs.data_local_dir().join("scripts");
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("failed to create scripts dir: {}", dir.display()))?;
+    Ok(dir)
+}
+
+/// Opens a file or folder with the system's default handler.
+pub fn open_with_system(path: &Path) -> Result<()> {
+    #[cfg(windows)]
+    let mut command = {
+        let mut command = std::process::Command::new("explorer");
+        command.arg(path);
+        command
+    };
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = std::process::Command::new("open");
+        command.arg(path);
+        command
+    };
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let mut command = {
+        let mut command = std::process::Command::new("xdg-open");
+        command.arg(path);
+        command
+    };
+    command
+        .spawn()
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    Ok(())
+}
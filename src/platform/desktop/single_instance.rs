@@ -0,0 +1,137 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+/// Prefix of every handoff line, so a stale lock pointing at some other program's port is
+/// never mistaken for a running BOM.
+const HANDOFF_MAGIC: &str = "BOM1";
+const HANDOFF_ACK: &str = "BOM1 OK";
+const HANDOFF_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// What a second launch asks the running instance to do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Handoff {
+    Focus,
+    /// Switch to read-only viewing mode.
+    ReadOnly,
+    Open(PathBuf),
+}
+
+impl Handoff {
+    pub fn to_line(&self) -> String {
+        match self {
+            Handoff::Focus => format!("{HANDOFF_MAGIC} FOCUS"),
+            Handoff::ReadOnly => format!("{HANDOFF_MAGIC} READONLY"),
+            Handoff::Open(path) => format!("{HANDOFF_MAGIC} OPEN {}", path.display()),
+        }
+    }
+
+    pub fn parse(line: &str) -> Option<Handoff> {
+        let rest = line
+            .trim_end_matches(['\r', '\n'])
+            .strip_prefix(HANDOFF_MAGIC)?;
+        let rest = rest.strip_prefix(' ')?;
+        match rest {
+            "FOCUS" => return Some(Handoff::Focus),
+            "READONLY" => return Some(Handoff::ReadOnly),
+            _ => {}
+        }
+        let path = rest.strip_prefix("OPEN ")?;
+        (!path.is_empty()).then(|| Handoff::Open(PathBuf::from(path)))
+    }
+}
+
+/// Outcome of [`acquire_instance`]: either this process owns the lock, or the request has
+/// been delivered to the instance that does and this one should exit.
+pub enum InstanceRole {
+    Primary(InstanceListener),
+    Secondary,
+}
+
+/// Loopback listener owned by the primary instance. Cloneable so it can travel through the
+/// launch context; only the first [`InstanceListener::serve`] call takes the socket.
+#[derive(Clone)]
+pub struct InstanceListener {
+    listener: Arc<Mutex<Option<TcpListener>>>,
+}
+
+impl InstanceListener {
+    /// Accepts handoffs on a background thread for the rest of the process.
+    pub fn serve(&self, on_handoff: impl Fn(Handoff) + Send + 'static) {
+        let Some(listener) = self
+            .listener
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take()
+        else {
+            return;
+        };
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                match read_handoff(stream) {
+                    Ok(Some(handoff)) => on_handoff(handoff),
+                    Ok(None) => {}
+                    Err(err) => tracing::warn!("single-instance handoff failed: {err:#}"),
+                }
+            }
+        });
+    }
+}
+
+fn read_handoff(stream: TcpStream) -> Result<Option<Handoff>> {
+    stream.set_read_timeout(Some(HANDOFF_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let Some(handoff) = Handoff::parse(&line) else {
+        return Ok(None);
+    };
+    writeln!(reader.get_mut(), "{HANDOFF_ACK}")?;
+    Ok(Some(handoff))
+}
+
+/// Hands `request` to the instance recorded in `lock_path` if one answers; otherwise binds
+/// a fresh loopback port and records it, making this process the primary. A lock left by
+/// a crashed instance simply fails to answer and is overwritten.
+pub fn acquire_instance(lock_path: &Path, request: &Handoff) -> Result<InstanceRole> {
+    if let Some(port) = read_lock_port(lock_path) {
+        if send_handoff(port, request).is_ok() {
+            return Ok(InstanceRole::Secondary);
+        }
+    }
+
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .context("failed to bind single-instance listener")?;
+    let port = listener.local_addr()?.port();
+    if let Some(parent) = lock_path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create lock dir: {}", parent.display()))?;
+    }
+    std::fs::write(lock_path, port.to_string())
+        .with_context(|| format!("failed to write lock file: {}", lock_path.display()))?;
+    Ok(InstanceRole::Primary(InstanceListener {
+        listener: Arc::new(Mutex::new(Some(listener))),
+    }))
+}
+
+fn read_lock_port(lock_path: &Path) -> Option<u16> {
+    std::fs::read_to_string(lock_path).ok()?.trim().parse().ok()
+}
+
+fn send_handoff(port: u16, request: &Handoff) -> Result<()> {
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let mut stream = TcpStream::connect_timeout(&addr, HANDOFF_TIMEOUT)?;
+    stream.set_read_timeout(Some(HANDOFF_TIMEOUT))?;
+    writeln!(stream, "{}", request.to_line())?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    anyhow::ensure!(reply.trim_end() == HANDOFF_ACK, "unexpected handoff reply");
+    Ok(())
+}
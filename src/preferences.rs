@@ -0,0 +1,364 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Context, Result};
+use bom_core::dividend_sweep::DIVIDEND_SWEEP_KEY;
+use bom_core::income_goal::{INFLATION_RATE_KEY, MONTHLY_INCOME_TARGET_KEY};
+use bom_core::retirement::RETIREMENT_PLAN_KEY;
+use serde_json::{json, Map, Value};
+
+use crate::domain::entities::dataset::{
+    ColumnFormat, DatasetDisplay, DatasetId, DisplayUnit, PageQuery,
+};
+use crate::domain::entities::deposit_rate::DepositRate;
+use crate::domain::entities::import_profile::{ColumnMapping, ImportProfile};
+use crate::domain::entities::saved_report::SavedReport;
+use crate::usecase::services::query_service::QueryService;
+use crate::{
+    ALERT_LOSS_THRESHOLD_KEY, APP_LOCK_IDLE_KEY, DIVIDEND_OWNERS_KEY, NUMBER_LOCALE_KEY,
+    SNAPSHOT_SCHEDULE_KEY, THEME_KEY, TRASH_RETENTION_KEY,
+};
+
+const PREFERENCES_FORMAT: &str = "bom-preferences";
+const PREFERENCES_VERSION: u64 = 1;
+
+/// Settings that make sense on another machine. Recent imports are local paths and the
+/// lock passphrase is deliberately never written to a file.
+pub const PORTABLE_SETTING_KEYS: [&str; 11] = [
+    THEME_KEY,
+    APP_LOCK_IDLE_KEY,
+    NUMBER_LOCALE_KEY,
+    DIVIDEND_OWNERS_KEY,
+    SNAPSHOT_SCHEDULE_KEY,
+    ALERT_LOSS_THRESHOLD_KEY,
+    TRASH_RETENTION_KEY,
+    DIVIDEND_SWEEP_KEY,
+    MONTHLY_INCOME_TARGET_KEY,
+    INFLATION_RATE_KEY,
+    RETIREMENT_PLAN_KEY,
+];
+
+/// What [`import_preferences`] applied. `settings` counts the portable settings, each
+/// saved report and import profile, and the deposit rate table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PreferencesImportSummary {
+    pub settings: usize,
+    pub datasets: usize,
+}
+
+/// Collects the portable settings, saved reports, import profiles, deposit rates and
+/// every dataset's column preferences (visibility, aliases, number formats, saved views)
+/// and display currency and unit into one JSON document. Datasets are keyed by
+/// name and columns by header text, since ids differ between machines.
+pub fn export_preferences(service: &QueryService) -> Result<Value> {
+    let mut settings = Map::new();
+    for key in PORTABLE_SETTING_KEYS {
+        if let Some(value) = service.load_setting(key)? {
+            settings.insert(key.to_string(), Value::String(value));
+        }
+    }
+    let reports = service
+        .load_saved_reports()?
+        .iter()
+        .map(|report| json!({ "name": report.name, "script": report.script }))
+        .collect::<Vec<_>>();
+    let profiles = service
+        .load_import_profiles()?
+        .iter()
+        .map(profile_to_json)
+        .collect::<Vec<_>>();
+    let rates = service
+        .load_deposit_rates()?
+        .iter()
+        .map(|rate| {
+            json!({
+                "institution": rate.institution,
+                "tenor": rate.tenor,
+                "rate": rate.rate,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut datasets = Vec::new();
+    for dataset in service.list_datasets(false)? {
+        let columns = dataset_columns(service, dataset.id)?;
+        let visibility = service.load_column_visibility(dataset.id)?;
+        let aliases = service.load_column_aliases(dataset.id)?;
+        let formats = service.load_column_formats(dataset.id)?;
+        let presets = service.load_visibility_presets(dataset.id)?;
+        let display = service.load_dataset_display(dataset.id)?;
+        if visibility.is_empty()
+            && aliases.is_empty()
+            && formats.is_empty()
+            && presets.is_empty()
+            && display.is_default()
+        {
+            continue;
+        }
+
+        let by_name = |map: &BTreeMap<i64, bool>| -> Map<String, Value> {
+            map.iter()
+                .filter_map(|(idx, visible)| {
+                    let name = columns.get(usize::try_from(*idx).ok()?)?;
+                    Some((name.clone(), Value::Bool(*visible)))
+                })
+                .collect()
+        };
+        let mut column_prefs = Map::new();
+        for (idx, name) in columns.iter().enumerate() {
+            let idx = idx as i64;
+            let mut prefs = Map::new();
+            if let Some(visible) = visibility.get(&idx) {
+                prefs.insert("visible".to_string(), Value::Bool(*visible));
+            }
+            if let Some(alias) = aliases.get(&idx).filter(|alias| !alias.is_empty()) {
+                prefs.insert("alias".to_string(), Value::String(alias.clone()));
+            }
+            if let Some(format) = formats.get(&idx) {
+                prefs.insert("format".to_string(), format_to_json(format));
+            }
+            if !prefs.is_empty() {
+                column_prefs.insert(name.clone(), Value::Object(prefs));
+            }
+        }
+        let presets = presets
+            .iter()
+            .map(|(name, preset)| (name.clone(), Value::Object(by_name(preset))))
+            .collect::<Map<_, _>>();
+        let mut entry = json!({
+            "name": dataset.name,
+            "columns": column_prefs,
+            "presets": presets,
+        });
+        if !display.is_default() {
+            entry["display"] = json!({
+                "currency": display.currency,
+                "unit": display.unit.as_str(),
+            });
+        }
+        datasets.push(entry);
+    }
+
+    Ok(json!({
+        "format": PREFERENCES_FORMAT,
+        "version": PREFERENCES_VERSION,
+        "settings": settings,
+        "saved_reports": reports,
+        "import_profiles": profiles,
+        "deposit_rates": rates,
+        "datasets": datasets,
+    }))
+}
+
+/// Applies a document from [`export_preferences`]. Column preferences go to every dataset
+/// with the same name, matched column by column on header text; anything that does not
+/// match is skipped rather than treated as an error.
+pub fn import_preferences(
+    service: &QueryService,
+    document: &Value,
+) -> Result<PreferencesImportSummary> {
+    if document.get("format").and_then(Value::as_str) != Some(PREFERENCES_FORMAT) {
+        bail!("不是 BOM 設定檔");
+    }
+    let version = document.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if version > PREFERENCES_VERSION {
+        bail!("設定檔版本 {version} 比目前程式新，請先更新 BOM");
+    }
+
+    let mut summary = PreferencesImportSummary::default();
+    if let Some(settings) = document.get("settings").and_then(Value::as_object) {
+        for key in PORTABLE_SETTING_KEYS {
+            if let Some(value) = settings.get(key).and_then(Value::as_str) {
+                service.save_setting(key, value)?;
+                summary.settings += 1;
+            }
+        }
+    }
+    let array = |key: &str| {
+        document
+            .get(key)
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    };
+    for report in array("saved_reports").iter().filter_map(report_from_json) {
+        service.save_saved_report(report)?;
+        summary.settings += 1;
+    }
+    for profile in array("import_profiles")
+        .iter()
+        .filter_map(profile_from_json)
+    {
+        service.save_import_profile(profile)?;
+        summary.settings += 1;
+    }
+    // The rates replace the table as a whole; a file without any leaves it as it is.
+    let rates = array("deposit_rates")
+        .iter()
+        .filter_map(rate_from_json)
+        .collect::<Vec<_>>();
+    if !rates.is_empty() {
+        service.save_deposit_rates(rates)?;
+        summary.settings += 1;
+    }
+
+    let datasets = service.list_datasets(false)?;
+    for entry in array("datasets") {
+        let Some(name) = entry.get("name").and_then(Value::as_str) else {
+            continue;
+        };
+        for dataset in datasets.iter().filter(|dataset| dataset.name == name) {
+            let columns = dataset_columns(service, dataset.id)?;
+            apply_dataset_preferences(service, dataset.id, &columns, entry)
+                .with_context(|| format!("failed to apply preferences to {name}"))?;
+            summary.datasets += 1;
+        }
+    }
+    Ok(summary)
+}
+
+fn apply_dataset_preferences(
+    service: &QueryService,
+    dataset_id: DatasetId,
+    columns: &[String],
+    entry: &Value,
+) -> Result<()> {
+    let column_idx = |name: &str| columns.iter().position(|column| column == name);
+    let mut visibility = service.load_column_visibility(dataset_id)?;
+    let mut aliases = service.load_column_aliases(dataset_id)?;
+    let mut formats = service.load_column_formats(dataset_id)?;
+
+    let column_prefs = entry.get("columns").and_then(Value::as_object);
+    for (name, prefs) in column_prefs.into_iter().flatten() {
+        let Some(idx) = column_idx(name) else {
+            continue;
+        };
+        let idx = idx as i64;
+        if let Some(visible) = prefs.get("visible").and_then(Value::as_bool) {
+            visibility.insert(idx, visible);
+        }
+        if let Some(alias) = prefs.get("alias").and_then(Value::as_str) {
+            aliases.insert(idx, alias.to_string());
+        }
+        if let Some(format) = prefs.get("format").and_then(format_from_json) {
+            formats.insert(idx, format);
+        }
+    }
+    service.upsert_column_visibility(dataset_id, visibility)?;
+    service.upsert_column_aliases(dataset_id, aliases)?;
+    service.upsert_column_formats(dataset_id, formats)?;
+
+    if let Some(display) = entry.get("display").and_then(display_from_json) {
+        service.save_dataset_display(dataset_id, display)?;
+    }
+
+    let presets = entry.get("presets").and_then(Value::as_object);
+    for (preset_name, preset) in presets.into_iter().flatten() {
+        let Some(preset) = preset.as_object() else {
+            continue;
+        };
+        let preset = preset
+            .iter()
+            .filter_map(|(name, visible)| Some((column_idx(name)? as i64, visible.as_bool()?)))
+            .collect::<BTreeMap<_, _>>();
+        if !preset.is_empty() {
+            service.save_visibility_preset(dataset_id, preset_name.clone(), preset)?;
+        }
+    }
+    Ok(())
+}
+
+fn dataset_columns(service: &QueryService, dataset_id: DatasetId) -> Result<Vec<String>> {
+    let page = service.query_page(PageQuery {
+        dataset_id,
+        page: 0,
+        page_size: 1,
+        global_search: String::new(),
+        column_filter: None,
+        value_filters: Vec::new(),
+        column_conditions: Vec::new(),
+        sort: None,
+    })?;
+    Ok(page.columns)
+}
+
+pub fn format_to_json(format: &ColumnFormat) -> Value {
+    json!({
+        "decimals": format.decimals,
+        "percent": format.percent,
+        "thousands_separator": format.thousands_separator,
+        "currency_symbol": format.currency_symbol,
+    })
+}
+
+pub fn format_from_json(value: &Value) -> Option<ColumnFormat> {
+    Some(ColumnFormat {
+        decimals: u32::try_from(value.get("decimals")?.as_u64()?).ok()?,
+        percent: value.get("percent")?.as_bool()?,
+        thousands_separator: value.get("thousands_separator")?.as_bool()?,
+        currency_symbol: value
+            .get("currency_symbol")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+    })
+}
+
+pub fn display_from_json(value: &Value) -> Option<DatasetDisplay> {
+    let currency = value.get("currency")?.as_str()?.trim();
+    if currency.is_empty() {
+        return None;
+    }
+    Some(DatasetDisplay {
+        currency: currency.to_string(),
+        unit: DisplayUnit::parse(value.get("unit")?.as_str()?)?,
+    })
+}
+
+fn report_from_json(value: &Value) -> Option<SavedReport> {
+    Some(SavedReport {
+        name: value.get("name")?.as_str()?.to_string(),
+        script: value.get("script")?.as_str()?.to_string(),
+    })
+}
+
+fn profile_to_json(profile: &ImportProfile) -> Value {
+    let columns = profile
+        .columns
+        .iter()
+        .map(|mapping| json!({ "source": mapping.source, "target": mapping.target }))
+        .collect::<Vec<_>>();
+    json!({
+        "name": profile.name,
+        "header_row": profile.header_row,
+        "encoding": profile.encoding,
+        "columns": columns,
+    })
+}
+
+fn profile_from_json(value: &Value) -> Option<ImportProfile> {
+    let columns = value
+        .get("columns")?
+        .as_array()?
+        .iter()
+        .map(|mapping| {
+            Some(ColumnMapping {
+                source: mapping.get("source")?.as_str()?.to_string(),
+                target: mapping.get("target")?.as_str()?.to_string(),
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(ImportProfile {
+        name: value.get("name")?.as_str()?.to_string(),
+        header_row: usize::try_from(value.get("header_row")?.as_u64()?).ok()?,
+        encoding: value.get("encoding")?.as_str()?.to_string(),
+        columns,
+    })
+}
+
+fn rate_from_json(value: &Value) -> Option<DepositRate> {
+    Some(DepositRate {
+        institution: value.get("institution")?.as_str()?.to_string(),
+        tenor: value.get("tenor")?.as_str()?.to_string(),
+        rate: value.get("rate")?.as_f64()?,
+    })
+}
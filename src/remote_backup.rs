@@ -0,0 +1,61 @@
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::infra::remote_backup::{upload_backup, RemoteBackupConfig, RemoteBackupKind};
+use crate::usecase::services::query_service::QueryService;
+use crate::{
+    REMOTE_BACKUP_KIND_KEY, REMOTE_BACKUP_REGION_KEY, REMOTE_BACKUP_SECRET_KEY,
+    REMOTE_BACKUP_STATUS_KEY, REMOTE_BACKUP_URL_KEY, REMOTE_BACKUP_USERNAME_KEY,
+};
+
+/// Reads the remote backup target from settings; unset fields come back empty.
+pub fn load_remote_backup_config(service: &QueryService) -> Result<RemoteBackupConfig> {
+    let load = |key| -> Result<String> { Ok(service.load_setting(key)?.unwrap_or_default()) };
+    Ok(RemoteBackupConfig {
+        kind: RemoteBackupKind::parse(&load(REMOTE_BACKUP_KIND_KEY)?).unwrap_or_default(),
+        url: load(REMOTE_BACKUP_URL_KEY)?,
+        username: load(REMOTE_BACKUP_USERNAME_KEY)?,
+        secret: load(REMOTE_BACKUP_SECRET_KEY)?,
+        region: load(REMOTE_BACKUP_REGION_KEY)?,
+    })
+}
+
+/// Stores the remote backup target. An empty URL turns remote backups off.
+pub fn save_remote_backup_config(
+    service: &QueryService,
+    config: &RemoteBackupConfig,
+) -> Result<()> {
+    service.save_setting(REMOTE_BACKUP_KIND_KEY, config.kind.as_str())?;
+    service.save_setting(REMOTE_BACKUP_URL_KEY, config.url.trim())?;
+    service.save_setting(REMOTE_BACKUP_USERNAME_KEY, &config.username)?;
+    service.save_setting(REMOTE_BACKUP_SECRET_KEY, &config.secret)?;
+    service.save_setting(REMOTE_BACKUP_REGION_KEY, config.region.trim())?;
+    Ok(())
+}
+
+/// Outcome of the most recent upload, e.g. `2026-10-16 09:30 上傳成功：…`.
+pub fn load_remote_backup_status(service: &QueryService) -> Result<Option<String>> {
+    Ok(service
+        .load_setting(REMOTE_BACKUP_STATUS_KEY)?
+        .filter(|status| !status.is_empty()))
+}
+
+/// Uploads `backup_path` to the configured target and records the outcome as the last
+/// upload status, so a failure stays visible after the status bar moves on.
+pub fn upload_and_record(
+    service: &QueryService,
+    config: &RemoteBackupConfig,
+    backup_path: &Path,
+) -> Result<String> {
+    let result = upload_backup(config, backup_path);
+    let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M");
+    let status = match &result {
+        Ok(url) => format!("{timestamp} 上傳成功：{url}"),
+        Err(err) => format!("{timestamp} 上傳失敗：{err:#}"),
+    };
+    if let Err(err) = service.save_setting(REMOTE_BACKUP_STATUS_KEY, &status) {
+        tracing::warn!(error = %err, "保存遠端備份狀態失敗");
+    }
+    result
+}
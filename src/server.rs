@@ -0,0 +1,383 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde_json::{json, Value};
+
+use bom_core::domain::entities::dataset::DatasetId;
+use bom_core::report::{dataset_summary_report, parse_owner_list, SummaryEntry};
+use bom_core::usecase::services::query_service::QueryService;
+use bom_core::{build_page_query, QueryOptions};
+
+use crate::DIVIDEND_OWNERS_KEY;
+
+pub const DEFAULT_SERVER_ADDR: &str = "127.0.0.1:8787";
+
+/// Connections served at once; further ones are turned away with 503.
+const MAX_CONNECTIONS: usize = 16;
+/// How long a client may take to send its request or read the response.
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+/// Longest request line or header line read; longer requests are dropped unanswered.
+const MAX_LINE_BYTES: u64 = 8 * 1024;
+/// Most header lines read before the request is dropped.
+const MAX_HEADER_LINES: usize = 64;
+/// Most rows returned in one response; larger datasets are read page by page.
+pub const MAX_PAGE_SIZE: i64 = 10_000;
+
+/// Read-only JSON API over the dataset database. Every connection is handled on its own
+/// thread and closed after one response, which is plenty for a personal dashboard.
+///
+/// The database holds the family's finances, so every request must carry the access token
+/// printed at launch, as `Authorization: Bearer <token>` or `?token=<token>`. No CORS
+/// header is sent, so pages open in a browser cannot read the responses.
+///
+/// - `GET /api/datasets`
+/// - `GET /api/datasets/{id}/rows?search=&column=&term=&sort=&desc=1&page=&page_size=`
+///   (at most [`MAX_PAGE_SIZE`] rows per page)
+/// - `GET /api/datasets/{id}/summary`
+pub fn serve(service: Arc<QueryService>, addr: &str) -> Result<()> {
+    let listener = TcpListener::bind(addr).with_context(|| format!("failed to bind {addr}"))?;
+    let token: Arc<str> = new_access_token().into();
+    eprintln!("API 伺服器已啟動：http://{addr}/api/datasets");
+    eprintln!("存取權杖（僅本次啟動有效）：{token}");
+    eprintln!("請求需帶標頭 Authorization: Bearer <權杖>，或查詢參數 token=<權杖>");
+    let active = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else {
+            continue;
+        };
+        let Some(slot) = ConnectionSlot::acquire(&active) else {
+            let _ = stream.set_write_timeout(Some(IO_TIMEOUT));
+            let _ = write_response(
+                &mut stream,
+                503,
+                &json!({ "error": "連線過多，請稍後再試" }),
+            );
+            continue;
+        };
+        let service = service.clone();
+        let token = token.clone();
+        std::thread::spawn(move || {
+            let _slot = slot;
+            if let Err(err) = handle_connection(&service, stream, &token) {
+                eprintln!("請求處理失敗：{err:#}");
+            }
+        });
+    }
+    Ok(())
+}
+
+/// One of the [`MAX_CONNECTIONS`] places, given back when dropped, so a handler that
+/// panics still frees its place.
+pub struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    /// `None` while every place is taken.
+    pub fn acquire(active: &Arc<AtomicUsize>) -> Option<Self> {
+        if active.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+            active.fetch_sub(1, Ordering::SeqCst);
+            return None;
+        }
+        Some(ConnectionSlot(active.clone()))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 128 random bits from the operating system's random source in hex, new at every launch.
+pub fn new_access_token() -> String {
+    let mut bytes = [0u8; 16];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .expect("system random source unavailable");
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Whether a request to `target` with the `Authorization` header `authorization`
+/// carries `token`.
+pub fn is_authorized(target: &str, authorization: Option<&str>, token: &str) -> bool {
+    let from_header = authorization
+        .and_then(|value| value.trim().strip_prefix("Bearer "))
+        .map(str::trim);
+    let query = target.split_once('?').map_or("", |(_, query)| query);
+    let from_query = parse_query_string(query).remove("token");
+    [from_header.map(str::to_string), from_query]
+        .into_iter()
+        .flatten()
+        .any(|given| constant_time_eq(given.as_bytes(), token.as_bytes()))
+}
+
+/// Compares without returning early, so the time taken does not give the token away.
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len()
+        && left
+            .iter()
+            .zip(right)
+            .fold(0u8, |diff, (left, right)| diff | (left ^ right))
+            == 0
+}
+
+fn handle_connection(service: &QueryService, mut stream: TcpStream, token: &str) -> Result<()> {
+    stream
+        .set_read_timeout(Some(IO_TIMEOUT))
+        .context("failed to set read timeout")?;
+    stream
+        .set_write_timeout(Some(IO_TIMEOUT))
+        .context("failed to set write timeout")?;
+    let mut reader = BufReader::new(stream.try_clone().context("failed to clone stream")?);
+    let (request_line, authorization) = read_request_head(&mut reader)?;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+    let (status, body) = if method != "GET" {
+        (405, json!({ "error": "只支援 GET" }))
+    } else if !is_authorized(target, authorization.as_deref(), token) {
+        (401, json!({ "error": "缺少或錯誤的存取權杖" }))
+    } else {
+        route(service, target)
+    };
+    write_response(&mut stream, status, &body)
+}
+
+/// Reads the request line and the `Authorization` header, if any; GET requests carry no
+/// body. Fails on a line longer than [`MAX_LINE_BYTES`] or more than [`MAX_HEADER_LINES`]
+/// headers.
+pub fn read_request_head(reader: &mut impl BufRead) -> Result<(String, Option<String>)> {
+    let request_line = read_limited_line(reader).context("failed to read request line")?;
+    let mut authorization = None;
+    for _ in 0..=MAX_HEADER_LINES {
+        let line = read_limited_line(reader).context("failed to read header")?;
+        if line.trim().is_empty() {
+            return Ok((request_line, authorization));
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("authorization") {
+                authorization = Some(value.trim().to_string());
+            }
+        }
+    }
+    bail!("more than {MAX_HEADER_LINES} headers")
+}
+
+fn read_limited_line(reader: &mut impl BufRead) -> Result<String> {
+    let mut line = String::new();
+    reader.by_ref().take(MAX_LINE_BYTES).read_line(&mut line)?;
+    if line.len() as u64 >= MAX_LINE_BYTES && !line.ends_with('\n') {
+        bail!("line longer than {MAX_LINE_BYTES} bytes");
+    }
+    Ok(line)
+}
+
+fn write_response(stream: &mut TcpStream, status: u16, body: &Value) -> Result<()> {
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {status} {}\r\nContent-Type: application/json; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        reason_phrase(status),
+        body.len()
+    );
+    stream
+        .write_all(response.as_bytes())
+        .context("failed to write response")?;
+    Ok(())
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
+
+/// Maps a request target such as `/api/datasets/5/rows?sort=代號` to a status and JSON body.
+pub fn route(service: &QueryService, target: &str) -> (u16, Value) {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let params = parse_query_string(query);
+    let segments = path
+        .trim_matches('/')
+        .split('/')
+        .map(percent_decode)
+        .collect::<Vec<_>>();
+    let segments = segments.iter().map(String::as_str).collect::<Vec<_>>();
+
+    let result = match segments.as_slice() {
+        ["api", "datasets"] => datasets_json(service),
+        ["api", "datasets", id, "rows"] => match id.parse::<i64>() {
+            Ok(id) => rows_json(service, id, &params),
+            Err(_) => return (400, json!({ "error": "資料集編號無效" })),
+        },
+        ["api", "datasets", id, "summary"] => match id.parse::<i64>() {
+            Ok(id) => summary_json(service, id),
+            Err(_) => return (400, json!({ "error": "資料集編號無效" })),
+        },
+        _ => return (404, json!({ "error": "找不到路徑" })),
+    };
+    match result {
+        Ok(body) => (200, body),
+        Err(ApiError::BadRequest(message)) => (400, json!({ "error": message })),
+        Err(ApiError::Internal(message)) => (500, json!({ "error": message })),
+    }
+}
+
+enum ApiError {
+    BadRequest(String),
+    Internal(String),
+}
+
+fn internal(err: impl std::fmt::Display) -> ApiError {
+    ApiError::Internal(err.to_string())
+}
+
+fn datasets_json(service: &QueryService) -> Result<Value, ApiError> {
+    let datasets = service.list_datasets(false).map_err(internal)?;
+    Ok(Value::Array(
+        datasets
+            .into_iter()
+            .map(|dataset| {
+                json!({
+                    "id": dataset.id.0,
+                    "name": dataset.name,
+                    "row_count": dataset.row_count,
+                    "source_path": dataset.source_path,
+                })
+            })
+            .collect(),
+    ))
+}
+
+fn rows_json(
+    service: &QueryService,
+    dataset_id: i64,
+    params: &HashMap<String, String>,
+) -> Result<Value, ApiError> {
+    let mut options = QueryOptions {
+        global_search: params.get("search").cloned().unwrap_or_default(),
+        sort_desc: matches!(params.get("desc").map(String::as_str), Some("1" | "true")),
+        ..QueryOptions::default()
+    };
+    if params.contains_key("column") || params.contains_key("sort") {
+        // Columns are addressed by header name, so resolve them against the dataset first.
+        let columns = service
+            .query_page(build_page_query(dataset_id, 0, &QueryOptions::default()))
+            .map_err(internal)?
+            .columns;
+        let column_index = |name: &String| {
+            columns
+                .iter()
+                .position(|column| column == name)
+                .map(|idx| idx as i64)
+                .ok_or_else(|| ApiError::BadRequest(format!("找不到欄位：{name}")))
+        };
+        if let Some(column) = params.get("column") {
+            options.column_search_col = Some(column_index(column)?);
+            options.column_search_text = params.get("term").cloned().unwrap_or_default();
+        }
+        if let Some(column) = params.get("sort") {
+            options.sort_col = Some(column_index(column)?);
+        }
+    }
+
+    let parse_number = |key: &str| -> Result<Option<i64>, ApiError> {
+        params
+            .get(key)
+            .map(|value| {
+                value
+                    .parse::<i64>()
+                    .map_err(|_| ApiError::BadRequest(format!("{key} 必須是整數")))
+            })
+            .transpose()
+    };
+    let page_size = parse_number("page_size")?
+        .unwrap_or(MAX_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let page = parse_number("page")?.unwrap_or(0).max(0);
+    if page.checked_mul(page_size).is_none() {
+        return Err(ApiError::BadRequest("page 超出範圍".to_string()));
+    }
+    let mut query = build_page_query(dataset_id, page, &options);
+    query.page_size = page_size;
+    let result = service.query_page(query).map_err(internal)?;
+    Ok(json!({
+        "columns": result.columns,
+        "rows": result.rows,
+        "total_rows": result.total_rows,
+        "page": page,
+        "page_size": page_size,
+    }))
+}
+
+fn summary_json(service: &QueryService, dataset_id: i64) -> Result<Value, ApiError> {
+    let owners = service
+        .load_setting(DIVIDEND_OWNERS_KEY)
+        .map_err(internal)?
+        .map(|value| parse_owner_list(&value))
+        .unwrap_or_default();
+    let report =
+        dataset_summary_report(service, DatasetId(dataset_id), &owners).map_err(internal)?;
+    let entries = |entries: &[SummaryEntry]| {
+        entries
+            .iter()
+            .map(|entry| json!({ "label": entry.label, "value": entry.value }))
+            .collect::<Vec<_>>()
+    };
+    Ok(json!({
+        "title": report.title,
+        "totals": entries(&report.totals),
+        "owner_totals": report
+            .owner_totals
+            .iter()
+            .map(|owner| json!({ "owner": owner.owner, "entries": entries(&owner.entries) }))
+            .collect::<Vec<_>>(),
+        "notes": report.notes,
+    }))
+}
+
+fn parse_query_string(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect()
+}
+
+/// Decodes `%XX` escapes (UTF-8) and `+` as space, as browsers send them in URLs.
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'+' => decoded.push(b' '),
+            b'%' if idx + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[idx + 1..idx + 3]).ok();
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        idx += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        idx += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -1,21 +1,129 @@
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use bom_core::alerts::{collect_alerts, AlertKind, DEFAULT_LOSS_THRESHOLD};
+use bom_core::assets::{
+    format_deposit_rates, parse_balance, parse_deposit_rates, typed_balance_rows, validate_account,
+    AssetAccount,
+};
+use bom_core::demo::{create_demo_datasets, DEMO_SOURCE};
+use bom_core::dividend_receipts::{expected_payouts, month_column};
+use bom_core::dividend_sweep::{received_by_owner, DIVIDEND_SWEEP_KEY};
+use bom_core::dividend_year::{open_dividend_year, roll_over_dividend_dataset};
+use bom_core::expression::{validate_computed_columns, Expression};
+use bom_core::filter_expr::{parse_filter_expr, CompareOp, FilterExpr};
+use bom_core::holding_detail::{
+    collect_holding_detail, dividend_history, holding_codes, save_holding_note,
+};
+use bom_core::holdings::{
+    builtin_column_set, find_duplicate_codes, holdings_total_net, parse_column_names,
+    reorder_headers_and_rows, required_columns_for_holdings, transform_assets_sheet,
+    transform_dividend_sheet, transform_holdings_sheet, DividendColumns, DuplicateCode,
+    DuplicateCodePolicy, HoldingDerived,
+};
+use bom_core::income_goal::{
+    estimated_monthly_income, income_projection_table, load_income_goal, project_income,
+    save_income_goal, IncomeGoal,
+};
+use bom_core::infra::security::passphrase::{hash_passphrase, verify_passphrase};
+use bom_core::numeric::{
+    canonical_cell_value, default_column_format, format_cell_value, format_f64, format_ratio_or_na,
+    normalize_numeric_text, parse_numeric_value_in, NumberLocale,
+};
+use bom_core::owner_split::{format_owner_shares, parse_owner_shares};
+use bom_core::prices::{market_value_at, price_at, row_codes};
+use bom_core::report::{
+    cached_summary_report, compute_summary_report, dataset_summary_report, format_summary_percent,
+    order_owner_totals, parse_owner_list, read_xlsx_summary_report, record_scheduled_snapshots,
+    summary_report_table, summary_report_text, OwnerSummary, SnapshotSchedule, SummaryEntry,
+    SummarySections,
+};
+use bom_core::report_script::{
+    parse_report_script, run_report_script, Aggregate, Comparison, ReportDefinition, ReportFilter,
+    ReportMetric,
+};
+use bom_core::retirement::{
+    load_retirement_plan, retirement_projection_table, save_retirement_plan, simulate_retirement,
+    starting_allocation, AssetClass, RetirementPlan,
+};
+use bom_core::trade_costs::{
+    ledger_trades, position_cost, transaction_tax, FeeSchedule, Oversold, Trade, TradeSide,
+};
+use bom_core::year_comparison::{
+    compare_summaries, load_summary, summary_sources, year_comparison_table, SummarySource,
+};
+use chrono::NaiveDate;
+use dioxus::desktop::muda::accelerator::Accelerator;
 use rusqlite::{params, Connection};
 
-use crate::domain::entities::edit::CellKey;
+use crate::bundle::{export_bundle, import_bundle};
+use crate::cli::{format_tsv, parse_cli_args, parse_gui_args, run_cli, CliCommand, CliInvocation};
+use crate::domain::entities::cell_origin::CellOrigin;
+use crate::domain::entities::column_set::ColumnSet;
+use crate::domain::entities::computed_column::ComputedColumn;
+use crate::domain::entities::dataset::{
+    ColumnFormat, ColumnStats, ColumnType, DatasetDisplay, DatasetId, DatasetKind, DisplayUnit,
+};
+use crate::domain::entities::edit::{CellKey, SavedChanges, StagedEdits};
+use crate::domain::entities::import_profile::{ColumnMapping, ImportProfile};
+use crate::domain::entities::import_record::ImportEventKind;
+use crate::domain::entities::owner_share::{OwnerShare, OwnerSplits};
+use crate::domain::entities::price_point::PricePoint;
+use crate::domain::entities::saved_report::SavedReport;
+use crate::domain::events::DomainEvent;
+use crate::infra::export::csv::{export_csv, CsvDelimiter, CsvEncoding, CsvOptions};
 use crate::infra::import::csv::import_csv_to_sqlite;
-use crate::infra::import::xlsx::import_xlsx_selected_sheets_to_sqlite;
+use crate::infra::import::mapped_csv::{csv_headers, header_similarity, suggest_column_mappings};
+use crate::infra::import::registry::ImporterRegistry;
+use crate::infra::import::xlsx::{import_xlsx_selected_sheets_to_sqlite, XlsxImporter};
+use crate::infra::import::{file_hash, import_with};
+use crate::infra::remote_backup::{RemoteBackupConfig, RemoteBackupKind};
 use crate::infra::sqlite::queries::{
-    apply_changes_to_dataset, build_updated_rows, create_dataset_from_rows, list_datasets,
-    load_column_visibility, load_holdings_flags, purge_dataset, query_page, rename_dataset,
-    soft_delete_dataset, upsert_column_visibility, upsert_holdings_flag,
+    apply_changes_to_dataset, backup_database, build_updated_rows, column_value_counts,
+    create_dataset_from_rows, delete_visibility_preset, empty_columns, list_datasets,
+    load_cell_origins, load_column_aliases, load_column_formats, load_column_types,
+    load_column_visibility, load_dataset_display, load_holdings_flags, load_import_history,
+    load_setting, load_visibility_presets, purge_dataset, query_page, rename_dataset,
+    save_dataset_display, save_setting, save_visibility_preset, soft_delete_dataset,
+    upsert_column_aliases, upsert_column_formats, upsert_column_types, upsert_column_visibility,
+    upsert_holdings_flag,
 };
 use crate::infra::sqlite::repo::SqliteRepo;
 use crate::infra::sqlite::schema::init_db;
+use crate::infra::sqlite::sync_folder::{
+    acquire_writer_lock, detect_sync_folder, find_conflicted_copies, writer_lock_path,
+    SyncProvider, WriterLockOutcome,
+};
+use crate::platform::desktop::logging::{rotated_path, RotatingFileWriter};
+use crate::platform::desktop::menu::MenuCommand;
+use crate::platform::desktop::notify::{notification_command, BODY_ENV, TITLE_ENV};
+use crate::platform::desktop::single_instance::{acquire_instance, Handoff, InstanceRole};
+use crate::preferences::{export_preferences, import_preferences, PORTABLE_SETTING_KEYS};
+use crate::remote_backup::{
+    load_remote_backup_config, load_remote_backup_status, save_remote_backup_config,
+    upload_and_record,
+};
+use crate::server::{
+    is_authorized, new_access_token, percent_decode, read_request_head, route, ConnectionSlot,
+    MAX_PAGE_SIZE,
+};
+use crate::ui::components::chart::{
+    chart_points, decode_png_data_url, default_chart_columns, sparkline_points, value_range,
+};
+use crate::ui::components::table_image::table_image;
+use crate::ui::state::app_state::{AppState, BatchImport, BusyKind, BusyState, Route};
+use crate::ui::state::controller::{WorkspaceController, WorkspacePlace};
+use crate::ui::state::errors::{ErrorChain, ErrorReport};
+use crate::ui::state::open_workspaces::OpenWorkspaces;
+use crate::ui::state::services::AppServices;
+use crate::ui::state::session::UiSession;
+use crate::usecase::ports::importer::{ImportError, Importer, ParsedDataset};
+use crate::usecase::ports::repo::{DatasetRepository, NewDatasetMeta, RepoError, TabularData};
 use crate::usecase::services::edit_service::EditService;
+use crate::usecase::services::import_service::ImportService;
 use crate::*;
 
 fn unique_test_dir(prefix: &str) -> PathBuf {
@@ -86,6 +194,189 @@ fn column_visibility_persists_per_dataset() {
     fs::remove_dir_all(&temp_dir).expect("should cleanup temp dir");
 }
 
+#[test]
+fn empty_columns_follow_filters_and_leave_stored_visibility_alone() {
+    let temp_dir = unique_test_dir("empty-columns");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    let row = |cells: [&str; 4]| cells.map(str::to_string).to_vec();
+    let dataset_id = create_dataset_from_rows(
+        &db_path,
+        "sample",
+        "sample.csv",
+        &row(["名稱", "股息", "備註", "代號"]),
+        &[
+            row(["A", "", " ", "0050"]),
+            row(["B", "12", "", "2330"]),
+            row(["C", "", "", "0056"]),
+        ],
+    )
+    .expect("dataset should be created");
+
+    let all_rows = QueryOptions::default();
+    assert_eq!(
+        empty_columns(&db_path, dataset_id, &all_rows).expect("empty columns"),
+        BTreeSet::from([2])
+    );
+    let without_b = QueryOptions {
+        value_filters: BTreeMap::from([(0, BTreeSet::from(["A".to_string(), "C".to_string()]))]),
+        ..QueryOptions::default()
+    };
+    assert_eq!(
+        empty_columns(&db_path, dataset_id, &without_b).expect("empty columns"),
+        BTreeSet::from([1, 2])
+    );
+    let no_match = QueryOptions {
+        global_search: "zzz".to_string(),
+        ..QueryOptions::default()
+    };
+    assert!(empty_columns(&db_path, dataset_id, &no_match)
+        .expect("empty columns")
+        .is_empty());
+
+    let stored = BTreeMap::from([(0, true), (1, true), (2, true), (3, false)]);
+    let shown = hide_empty_columns(&stored, &BTreeSet::from([1, 2]));
+    assert_eq!(
+        shown,
+        BTreeMap::from([(0, true), (1, false), (2, false), (3, false)])
+    );
+    assert!(stored[&1], "the stored choice is kept");
+
+    fs::remove_dir_all(&temp_dir).expect("should cleanup temp dir");
+}
+
+#[test]
+fn column_formats_persist_and_are_purged_with_dataset() {
+    let temp_dir = unique_test_dir("column-format");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+
+    init_db(&db_path).expect("init_db should succeed");
+
+    let dataset_id = create_dataset_from_rows(
+        &db_path,
+        "sample",
+        "sample.csv",
+        &["Price".to_string(), "Yield".to_string()],
+        &[vec!["1".to_string(), "0.05".to_string()]],
+    )
+    .expect("dataset should be created");
+
+    let mut formats = BTreeMap::new();
+    formats.insert(
+        0,
+        ColumnFormat {
+            decimals: 1,
+            percent: false,
+            thousands_separator: false,
+            currency_symbol: "$".to_string(),
+        },
+    );
+    upsert_column_formats(&db_path, dataset_id, &formats).expect("should store column formats");
+
+    let loaded = load_column_formats(&db_path, dataset_id).expect("should load column formats");
+    assert_eq!(loaded, formats);
+
+    purge_dataset(&db_path, dataset_id).expect("purge should succeed");
+    let loaded = load_column_formats(&db_path, dataset_id).expect("should load column formats");
+    assert!(
+        loaded.is_empty(),
+        "formats should be removed with the dataset"
+    );
+
+    fs::remove_dir_all(&temp_dir).expect("should cleanup temp dir");
+}
+
+#[test]
+fn column_aliases_survive_visibility_updates() {
+    let temp_dir = unique_test_dir("column-alias");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+
+    init_db(&db_path).expect("init_db should succeed");
+
+    let dataset_id = create_dataset_from_rows(
+        &db_path,
+        "sample",
+        "sample.csv",
+        &["國內 /國外".to_string(), "B".to_string()],
+        &[vec!["國內".to_string(), "2".to_string()]],
+    )
+    .expect("dataset should be created");
+
+    let mut aliases = BTreeMap::new();
+    aliases.insert(0, "Region".to_string());
+    aliases.insert(1, "  ".to_string());
+    upsert_column_aliases(&db_path, dataset_id, &aliases).expect("should store column aliases");
+
+    let mut visibility = BTreeMap::new();
+    visibility.insert(0, false);
+    visibility.insert(1, true);
+    upsert_column_visibility(&db_path, dataset_id, &visibility)
+        .expect("should store column visibility");
+
+    let loaded = load_column_aliases(&db_path, dataset_id).expect("should load column aliases");
+    let expected = BTreeMap::from([(0, "Region".to_string())]);
+    assert_eq!(loaded, expected, "blank aliases are dropped, others kept");
+    let loaded_visibility =
+        load_column_visibility(&db_path, dataset_id).expect("should load column visibility");
+    assert_eq!(loaded_visibility, visibility);
+
+    fs::remove_dir_all(&temp_dir).expect("should cleanup temp dir");
+}
+
+#[test]
+fn visibility_presets_save_replace_and_delete() {
+    let temp_dir = unique_test_dir("visibility-preset");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+
+    init_db(&db_path).expect("init_db should succeed");
+
+    let dataset_id = create_dataset_from_rows(
+        &db_path,
+        "sample",
+        "sample.csv",
+        &["A".to_string(), "B".to_string()],
+        &[vec!["1".to_string(), "2".to_string()]],
+    )
+    .expect("dataset should be created");
+
+    let quick = BTreeMap::from([(0, true), (1, false)]);
+    let full = BTreeMap::from([(0, true), (1, true)]);
+    save_visibility_preset(&db_path, dataset_id, "quick", &full).expect("should save preset");
+    save_visibility_preset(&db_path, dataset_id, "quick", &quick).expect("should replace preset");
+    save_visibility_preset(&db_path, dataset_id, "full", &full).expect("should save preset");
+
+    let loaded = load_visibility_presets(&db_path, dataset_id).expect("should load presets");
+    assert_eq!(loaded.get("quick"), Some(&quick));
+    assert_eq!(loaded.get("full"), Some(&full));
+
+    delete_visibility_preset(&db_path, dataset_id, "quick").expect("should delete preset");
+    let loaded = load_visibility_presets(&db_path, dataset_id).expect("should load presets");
+    assert_eq!(
+        loaded.keys().cloned().collect::<Vec<_>>(),
+        vec!["full".to_string()]
+    );
+
+    fs::remove_dir_all(&temp_dir).expect("should cleanup temp dir");
+}
+
+#[test]
+fn builtin_visibility_presets_include_quick_view_for_holdings() {
+    let mut headers = required_columns_for_holdings();
+    headers.push("備註".to_string());
+    let holdings = builtin_column_set(DatasetKind::Holdings);
+    let presets = builtin_visibility_presets(&headers, &holdings);
+    let names: Vec<&str> = presets.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(names, vec!["精簡檢視", "完整檢視"]);
+    assert_eq!(presets[0].1.get(&(headers.len() as i64 - 1)), Some(&false));
+    assert!(presets[1].1.values().all(|visible| *visible));
+
+    let presets = builtin_visibility_presets(&["A".to_string()], &holdings);
+    assert_eq!(presets.len(), 1);
+}
+
 #[test]
 fn holdings_flag_persists_per_dataset() {
     let temp_dir = unique_test_dir("holdings-flag");
@@ -185,7 +476,8 @@ fn default_holdings_visibility_is_required_only() {
     headers.push("備註".to_string());
     let visibility = BTreeMap::new();
 
-    let normalized = normalize_column_visibility(&headers, &visibility);
+    let holdings = builtin_column_set(DatasetKind::Holdings);
+    let normalized = normalize_column_visibility(&headers, &visibility, &holdings);
 
     for (idx, header) in headers.iter().enumerate() {
         let expected = required_columns_for_holdings().iter().any(|c| c == header);
@@ -201,6 +493,37 @@ fn default_holdings_visibility_is_required_only() {
     assert_eq!(visible_names, required_columns_for_holdings());
 }
 
+#[test]
+fn holdings_imports_store_their_default_visibility() {
+    let temp_dir = unique_test_dir("holdings_visibility");
+    fs::create_dir_all(&temp_dir).expect("create temp dir");
+    let db_path = temp_dir.join("bom.sqlite");
+    let mut headers = required_columns_for_holdings();
+    headers.push("備註".to_string());
+    let values = vec!["1"; headers.len()];
+    let holdings_csv = temp_dir.join("持股.csv");
+    fs::write(
+        &holdings_csv,
+        format!("{}\n{}\n", headers.join(","), values.join(",")),
+    )
+    .expect("write csv");
+    let other_csv = temp_dir.join("其他.csv");
+    fs::write(&other_csv, "名稱,金額\n甲,1\n").expect("write csv");
+
+    let holdings = import_csv_to_sqlite(&db_path, &holdings_csv).expect("import holdings");
+    let expected = builtin_column_set(DatasetKind::Holdings).default_visibility(&headers);
+    assert_eq!(expected.get(&(headers.len() as i64 - 1)), Some(&false));
+    assert_eq!(
+        load_column_visibility(&db_path, holdings.dataset_id).unwrap(),
+        expected
+    );
+    let other = import_csv_to_sqlite(&db_path, &other_csv).expect("import other");
+    assert!(load_column_visibility(&db_path, other.dataset_id)
+        .unwrap()
+        .is_empty());
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
 #[test]
 fn normalize_column_visibility_fills_missing_and_keeps_existing() {
     let headers = vec!["A".to_string(), "B".to_string(), "C".to_string()];
@@ -208,7 +531,8 @@ fn normalize_column_visibility_fills_missing_and_keeps_existing() {
     visibility.insert(0, false);
     visibility.insert(2, true);
 
-    let normalized = normalize_column_visibility(&headers, &visibility);
+    let holdings = builtin_column_set(DatasetKind::Holdings);
+    let normalized = normalize_column_visibility(&headers, &visibility, &holdings);
 
     assert_eq!(normalized.get(&0), Some(&false));
     assert_eq!(normalized.get(&1), Some(&true));
@@ -257,6 +581,12 @@ fn table_container_style_for_scroll_is_flexible() {
     }
 }
 
+#[test]
+fn pane_element_id_keeps_first_pane_plain() {
+    assert_eq!(pane_element_id("table-head", 0), "table-head");
+    assert_eq!(pane_element_id("table-head", 1), "table-head-1");
+}
+
 #[test]
 fn table_scroll_mode_defaults_to_table_only() {
     assert_eq!(table_scroll_mode(true, false), TableScrollMode::TableOnly);
@@ -293,7 +623,7 @@ fn read_xlsx_summary_report_reads_bottom_rows() {
         .expect("should have deposit summary");
     assert_eq!(deposit.annual, "53840");
     assert_eq!(deposit.monthly, "4486.666667");
-    assert_eq!(deposit.yield_rate, "0.016");
+    assert_eq!(deposit.yield_rate, "1.60%");
 
     assert_eq!(report.dividend_total.as_deref(), Some("23719.85119"));
 
@@ -307,19 +637,180 @@ fn read_xlsx_summary_report_reads_bottom_rows() {
 }
 
 #[test]
-#[cfg(target_os = "linux")]
-fn linux_menu_disabled_in_config() {
-    assert!(linux_menu_disabled());
+fn menu_command_ids_round_trip_and_accelerators_parse() {
+    let mut ids = BTreeSet::new();
+    for command in MenuCommand::ALL {
+        assert!(ids.insert(command.id()), "duplicate id {}", command.id());
+        assert_eq!(MenuCommand::from_id(&command.id()), Some(command));
+        if let Some(accelerator) = command.accelerator() {
+            assert!(
+                accelerator.parse::<Accelerator>().is_ok(),
+                "bad accelerator {accelerator}"
+            );
+        }
+    }
+    assert_eq!(MenuCommand::from_id("dioxus-toggle-dev-tools"), None);
+    assert_eq!(
+        MenuCommand::from_id(&MenuCommand::RecentImport(3).id()),
+        Some(MenuCommand::RecentImport(3))
+    );
+    assert_eq!(
+        MenuCommand::from_id(&MenuCommand::SavedReport(2).id()),
+        Some(MenuCommand::SavedReport(2))
+    );
+    for locale in NumberLocale::ALL {
+        let command = MenuCommand::NumberLocale(locale);
+        assert_eq!(MenuCommand::from_id(&command.id()), Some(command));
+    }
+}
+
+#[test]
+fn guard_pending_changes_holds_actions_back_only_while_edits_are_staged() {
+    use dioxus::prelude::*;
+
+    #[derive(Clone)]
+    struct Outcomes(Arc<Mutex<Vec<(bool, bool, Option<i64>)>>>);
+
+    #[allow(non_snake_case)]
+    fn Workspace() -> Element {
+        let services = use_context::<AppServices>();
+        let outcomes = use_context::<Outcomes>();
+        let dataset_id = services.query_service.list_datasets(false).unwrap()[0].id.0;
+        let state = AppState::new();
+        let services_handle = use_hook(|| CopyValue::new(services.clone()));
+        let place = WorkspacePlace {
+            id: 0,
+            pane: 0,
+            main_window: false,
+        };
+        let mut controller = WorkspaceController::new(state, services_handle, place);
+        use_hook(move || {
+            let key = CellKey {
+                row_idx: 0,
+                col_idx: 1,
+                column: "city".to_string(),
+            };
+            controller
+                .state
+                .staged_cells
+                .write()
+                .insert(key, "Lyon".to_string());
+            for _ in 0..2 {
+                controller.guard_pending_changes(PendingAction::TabSwitch {
+                    dataset_id: Some(dataset_id),
+                });
+                let state = &controller.state;
+                outcomes.0.lock().unwrap().push((
+                    state.pending_action.peek().is_some(),
+                    *state.show_save_prompt.peek(),
+                    *state.selected_dataset_id.peek(),
+                ));
+                controller.state.pending_action.set(None);
+                controller.state.show_save_prompt.set(false);
+                controller.discard_edits();
+            }
+        });
+        rsx! {}
+    }
+
+    let temp_dir = unique_test_dir("guard_pending_changes");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    let csv_path = temp_dir.join("people.csv");
+    fs::write(&csv_path, "name,city\nAlice,Paris\n").expect("should write csv fixture");
+    init_db(&db_path).expect("init db");
+    let imported = import_csv_to_sqlite(&db_path, &csv_path).expect("import");
+
+    let outcomes = Outcomes(Arc::new(Mutex::new(Vec::new())));
+    let mut dom = VirtualDom::new(Workspace)
+        .with_root_context(AppServices::new(db_path))
+        .with_root_context(outcomes.clone());
+    dom.rebuild_in_place();
+    let outcomes = outcomes.0.lock().unwrap().clone();
+    // Staged edits: the switch waits behind the save prompt and the sheet stays unselected.
+    assert_eq!(outcomes[0], (true, true, None));
+    // Nothing staged: the switch runs at once.
+    assert_eq!(outcomes[1], (false, false, Some(imported.dataset_id)));
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn open_workspaces_report_unsaved_edits_by_window() {
+    let workspaces = OpenWorkspaces::<u32>::default();
+    let left = workspaces.register(1);
+    let right = workspaces.register(1);
+    let other_window = workspaces.register(2);
+    assert_eq!(workspaces.windows(), [1, 2]);
+    assert!(workspaces.unsaved_elsewhere(left.id(), None).is_empty());
+
+    right.set_unsaved(Some("持股".to_string()));
+    other_window.set_unsaved(Some("股利".to_string()));
+    assert_eq!(workspaces.unsaved_elsewhere(left.id(), Some(1)), ["持股"]);
+    assert_eq!(
+        workspaces.unsaved_elsewhere(left.id(), None),
+        ["持股", "股利"]
+    );
+    assert!(
+        workspaces.unsaved_elsewhere(right.id(), Some(1)).is_empty(),
+        "a pane leaves itself out"
+    );
+
+    right.set_unsaved(None);
+    drop(other_window);
+    assert!(workspaces.unsaved_elsewhere(left.id(), None).is_empty());
+    assert_eq!(workspaces.windows(), [1]);
 }
 
 #[test]
-fn dataset_tab_kind_detects_assets_and_holdings() {
-    assert_eq!(dataset_tab_kind("資產總表"), Some(DatasetTabKind::Assets));
+fn dataset_kind_defaults_from_name() {
+    assert_eq!(DatasetKind::from_name("資產總表"), DatasetKind::Assets);
     assert_eq!(
-        dataset_tab_kind("持股股息總表"),
-        Some(DatasetTabKind::Holdings)
+        DatasetKind::from_name("持股股息總表"),
+        DatasetKind::Holdings
     );
-    assert_eq!(dataset_tab_kind("其他"), None);
+    assert_eq!(DatasetKind::from_name("其他"), DatasetKind::Other);
+    for kind in DatasetKind::ALL {
+        assert_eq!(DatasetKind::parse(kind.as_str()), Some(kind));
+    }
+}
+
+#[test]
+fn dataset_kind_is_stored_and_survives_renames() {
+    let temp_dir = unique_test_dir("dataset_kind");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let services = AppServices::new(db_path.clone());
+    let ids = create_demo_datasets(&services.edit_service).expect("demo datasets");
+    let kind_of = |id: DatasetId| {
+        services
+            .query_service
+            .list_datasets(false)
+            .unwrap()
+            .into_iter()
+            .find(|meta| meta.id == id)
+            .unwrap()
+    };
+    assert_eq!(kind_of(ids[0]).kind, Some(DatasetKind::Assets));
+    assert_eq!(kind_of(ids[1]).kind, Some(DatasetKind::Holdings));
+
+    services
+        .query_service
+        .rename_dataset(ids[1], "2025 部位".to_string())
+        .expect("rename");
+    assert_eq!(kind_of(ids[1]).effective_kind(), DatasetKind::Holdings);
+    services
+        .query_service
+        .set_dataset_kind(ids[1], DatasetKind::Other)
+        .expect("set kind");
+    assert_eq!(kind_of(ids[1]).effective_kind(), DatasetKind::Other);
+
+    // Datasets from before types were stored fall back to their name.
+    let conn = Connection::open(&db_path).expect("open db");
+    conn.execute("UPDATE dataset SET kind = NULL", [])
+        .expect("clear kinds");
+    assert_eq!(kind_of(ids[0]).kind, None);
+    assert_eq!(kind_of(ids[0]).effective_kind(), DatasetKind::Assets);
+    fs::remove_dir_all(&temp_dir).ok();
 }
 
 #[test]
@@ -331,6 +822,8 @@ fn choose_default_dataset_id_prefers_assets() {
             row_count: 0,
             source_path: "x.xlsx#持股".to_string(),
             deleted_at: None,
+            kind: None,
+            keep_forever: false,
         },
         DatasetMeta {
             id: 2.into(),
@@ -338,6 +831,8 @@ fn choose_default_dataset_id_prefers_assets() {
             row_count: 0,
             source_path: "x.xlsx#資產".to_string(),
             deleted_at: None,
+            kind: None,
+            keep_forever: false,
         },
     ];
 
@@ -352,11 +847,37 @@ fn choose_default_dataset_id_falls_back_to_first() {
         row_count: 0,
         source_path: "x.csv".to_string(),
         deleted_at: None,
+        kind: None,
+        keep_forever: false,
     }];
 
     assert_eq!(choose_default_dataset_id(&datasets), Some(5));
 }
 
+#[test]
+fn save_as_copies_point_back_to_the_dataset_they_were_saved_from() {
+    let meta = |id: i64, name: &str, source_path: String| DatasetMeta {
+        id: id.into(),
+        name: name.to_string(),
+        row_count: 0,
+        source_path,
+        deleted_at: None,
+        kind: None,
+        keep_forever: false,
+    };
+    let primary = meta(2, "持股股息總表", "x.xlsx#持股".to_string());
+    assert_eq!(primary.saved_as_copy_of(), None);
+
+    let copy = meta(7, "持股_0301", primary.save_as_source_path("持股_0301"));
+    assert_eq!(copy.saved_as_copy_of(), Some(DatasetId(2)));
+    let copy_of_copy = meta(9, "持股_0302", copy.save_as_source_path("持股_0302"));
+    assert_eq!(copy_of_copy.saved_as_copy_of(), Some(DatasetId(2)));
+
+    let groups = build_dataset_groups(&[primary, copy, copy_of_copy]);
+    assert_eq!(groups.len(), 1, "copies stay in the workbook's group");
+    assert_eq!(groups[0].key, "x.xlsx");
+}
+
 #[test]
 fn choose_next_dataset_after_delete_prefers_next_then_previous() {
     let datasets = vec![
@@ -366,6 +887,8 @@ fn choose_next_dataset_after_delete_prefers_next_then_previous() {
             row_count: 0,
             source_path: "x.csv".to_string(),
             deleted_at: None,
+            kind: None,
+            keep_forever: false,
         },
         DatasetMeta {
             id: 2.into(),
@@ -373,6 +896,8 @@ fn choose_next_dataset_after_delete_prefers_next_then_previous() {
             row_count: 0,
             source_path: "x.csv".to_string(),
             deleted_at: None,
+            kind: None,
+            keep_forever: false,
         },
         DatasetMeta {
             id: 1.into(),
@@ -380,6 +905,8 @@ fn choose_next_dataset_after_delete_prefers_next_then_previous() {
             row_count: 0,
             source_path: "x.csv".to_string(),
             deleted_at: None,
+            kind: None,
+            keep_forever: false,
         },
     ];
 
@@ -908,6 +1435,84 @@ fn soft_delete_hides_dataset_from_default_list() {
     fs::remove_dir_all(&temp_dir).expect("should cleanup temp dir");
 }
 
+#[test]
+fn trashed_datasets_are_purged_after_the_retention_window_unless_kept() {
+    let temp_dir = unique_test_dir("trash-purge");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    let csv_path = temp_dir.join("sample.csv");
+    fs::write(&csv_path, "name\nAlice\n").expect("should write csv fixture");
+    let [expired, kept, recent, listed] = [(); 4].map(|_| {
+        import_csv_to_sqlite(&db_path, &csv_path)
+            .expect("import should succeed")
+            .dataset_id
+    });
+    let edit_service = EditService::new(std::sync::Arc::new(SqliteRepo {
+        db_path: db_path.clone(),
+    }));
+    for dataset_id in [expired, kept, recent] {
+        edit_service
+            .soft_delete_dataset(DatasetId(dataset_id))
+            .expect("soft delete should succeed");
+    }
+    edit_service
+        .set_keep_forever(DatasetId(kept), true)
+        .expect("keep forever");
+    let conn = Connection::open(&db_path).expect("open db");
+    conn.execute(
+        "UPDATE dataset SET deleted_at = datetime('now', '-40 days') WHERE id IN (?1, ?2)",
+        params![expired, kept],
+    )
+    .expect("age trashed datasets");
+
+    assert!(edit_service
+        .purge_expired_datasets(0)
+        .expect("purge with no window")
+        .is_empty());
+    assert_eq!(
+        edit_service
+            .purge_expired_datasets(30)
+            .expect("purge expired"),
+        vec![DatasetId(expired)]
+    );
+    let remaining: Vec<i64> = list_datasets(&db_path, true)
+        .expect("list datasets")
+        .iter()
+        .map(|dataset| dataset.id.0)
+        .collect();
+    assert_eq!(remaining, vec![listed, recent, kept]);
+    assert!(list_datasets(&db_path, true).unwrap()[2].keep_forever);
+
+    edit_service
+        .restore_dataset(DatasetId(recent))
+        .expect("restore should succeed");
+    let visible: Vec<i64> = list_datasets(&db_path, false)
+        .expect("list visible")
+        .iter()
+        .map(|dataset| dataset.id.0)
+        .collect();
+    assert_eq!(visible, vec![listed, recent]);
+
+    fs::remove_dir_all(&temp_dir).expect("should cleanup temp dir");
+}
+
+#[test]
+fn trash_dates_add_the_retention_window_to_the_local_deletion_date() {
+    let (deleted, purge) = trash_dates("2026-03-01 12:00:00", 30).expect("valid time");
+    let local = chrono::NaiveDateTime::parse_from_str("2026-03-01 12:00:00", "%Y-%m-%d %H:%M:%S")
+        .unwrap()
+        .and_utc()
+        .with_timezone(&chrono::Local)
+        .date_naive();
+    assert_eq!(deleted, local);
+    assert_eq!(purge, Some(local + chrono::Duration::days(30)));
+    assert_eq!(
+        trash_dates("2026-03-01 12:00:00", 0).map(|(_, purge)| purge),
+        Some(None)
+    );
+    assert_eq!(trash_dates("昨天", 30), None);
+}
+
 #[test]
 fn purge_dataset_removes_related_records() {
     let temp_dir = unique_test_dir("purge-dataset");
@@ -1172,6 +1777,131 @@ fn format_number_with_commas_handles_decimals() {
     assert_eq!(format_number_with_commas(-1234.5, 2), "-1,234.50");
 }
 
+#[test]
+fn locale_numbers_round_trip_through_parsing_and_import() {
+    let values = [0.0, 7.0, 1234.56, -9876543.21, 0.25];
+    for value in values {
+        let dot = format_number_with_commas(value, 2);
+        let comma = dot.replace(',', " ").replace('.', ",").replace(' ', ".");
+        let space = dot.replace(',', "\u{a0}").replace('.', ",");
+        for (text, locale) in [
+            (&dot, NumberLocale::DotDecimal),
+            (&comma, NumberLocale::CommaDecimal),
+            (&space, NumberLocale::SpaceCommaDecimal),
+        ] {
+            let parsed = parse_numeric_value_in(text, locale).expect("should parse");
+            assert!((parsed - value).abs() < 0.005, "{text} in {locale:?}");
+            assert_eq!(
+                parse_numeric_value(&normalize_numeric_text(text, locale)),
+                Some(parsed)
+            );
+        }
+    }
+    let comma = NumberLocale::CommaDecimal;
+    assert_eq!(normalize_numeric_text("1.234,5%", comma), "1234.5%");
+    assert_eq!(normalize_numeric_text("-0,75", comma), "-0.75");
+    // Codes, dates and malformed grouping are left alone.
+    assert_eq!(normalize_numeric_text("0056", comma), "0056");
+    assert_eq!(normalize_numeric_text("2024.05.01", comma), "2024.05.01");
+    assert_eq!(normalize_numeric_text("12.34", comma), "12.34");
+    assert_eq!(normalize_numeric_text("台積電", comma), "台積電");
+    assert_eq!(
+        normalize_numeric_text("1,234.56", NumberLocale::DotDecimal),
+        "1,234.56"
+    );
+    assert_eq!(NumberLocale::parse(comma.as_str()), Some(comma));
+
+    let temp_dir = unique_test_dir("number-locale");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    let csv_path = temp_dir.join("depot.csv");
+    fs::write(
+        &csv_path,
+        "代號,名稱,市價,數量\n0056,元大高股息,\"36,80\",\"1.234.000\"\n",
+    )
+    .expect("should write csv");
+
+    let services = AppServices::new(db_path.clone());
+    services.repo.init().expect("init");
+    services.save_number_locale(comma);
+    services.number_locale.set(NumberLocale::default());
+    services.reload_number_locale();
+    assert_eq!(services.number_locale.get(), comma);
+    let imported = services
+        .import_service
+        .import(&csv_path)
+        .expect("import should succeed");
+    let (_, rows, _) = query_page(
+        &db_path,
+        imported[0].dataset_id,
+        0,
+        10,
+        &QueryOptions::default(),
+    )
+    .expect("query should succeed");
+    assert_eq!(rows[0], vec!["0056", "元大高股息", "36.80", "1234000"]);
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn percent_columns_store_ratios_and_display_as_percent() {
+    let percent = ColumnType::Percent;
+    assert_eq!(canonical_cell_value(percent, "5%", None), "0.05");
+    assert_eq!(canonical_cell_value(percent, "0.05", None), "0.05");
+    assert_eq!(canonical_cell_value(percent, "N/A", None), "N/A");
+    assert_eq!(canonical_cell_value(ColumnType::Text, "5%", None), "5%");
+    let mut plain = default_column_format("損益率", percent);
+    plain.percent = false;
+    assert_eq!(canonical_cell_value(percent, "5%", Some(&plain)), "5%");
+    assert_eq!(
+        stored_cell_edit(percent, "12,5%", NumberLocale::CommaDecimal, None),
+        "0.125"
+    );
+    assert_eq!(format_cell_value("殖利率", "0.016"), "1.60%");
+    assert_eq!(format_summary_percent(Some(&"1.6%".to_string())), "1.60%");
+    assert_eq!(format_summary_percent(Some(&"0.016".to_string())), "1.60%");
+
+    let temp_dir = unique_test_dir("percent-ratios");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    let csv_path = temp_dir.join("rates.csv");
+    fs::write(&csv_path, "名稱,殖利率,備註\nA,5%,5%\nB,0.03,\n").expect("write csv");
+    let imported = import_csv_to_sqlite(&db_path, &csv_path).expect("import");
+    let (_, rows, _) = query_page(
+        &db_path,
+        imported.dataset_id,
+        0,
+        10,
+        &QueryOptions::default(),
+    )
+    .expect("query");
+    assert_eq!(rows[0], vec!["A", "0.05", "5%"]);
+    assert_eq!(rows[1], vec!["B", "0.03", ""]);
+
+    // Databases written before the migration still hold `%` strings.
+    let conn = Connection::open(&db_path).expect("open");
+    conn.execute(
+        "UPDATE cell SET value = '7.5%' WHERE dataset_id = ?1 AND row_idx = 1 AND col_idx = 1",
+        params![imported.dataset_id],
+    )
+    .expect("update");
+    conn.execute_batch("PRAGMA user_version = 0")
+        .expect("reset version");
+    drop(conn);
+    init_db(&db_path).expect("migrate");
+    let (_, rows, _) = query_page(
+        &db_path,
+        imported.dataset_id,
+        0,
+        10,
+        &QueryOptions::default(),
+    )
+    .expect("query");
+    assert_eq!(rows[1], vec!["B", "0.075", ""]);
+    assert_eq!(rows[0], vec!["A", "0.05", "5%"]);
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
 #[test]
 fn format_cell_value_applies_header_rules() {
     assert_eq!(format_cell_value("買進", "1234.5"), "1,234.50");
@@ -1180,35 +1910,424 @@ fn format_cell_value_applies_header_rules() {
 }
 
 #[test]
-fn column_alignment_prefers_text_headers() {
-    let rows = vec![vec!["0050".to_string()], vec!["006208".to_string()]];
-    assert_eq!(column_alignment("代號", &rows, 0), "left");
+fn format_cell_value_with_uses_column_format() {
+    let format = ColumnFormat {
+        decimals: 1,
+        percent: false,
+        thousands_separator: true,
+        currency_symbol: "NT$".to_string(),
+    };
+    assert_eq!(
+        format_cell_value_with("Price", "-1234.56", Some(&format)),
+        "-NT$1,234.6"
+    );
+    let percent = ColumnFormat {
+        decimals: 0,
+        percent: true,
+        thousands_separator: false,
+        currency_symbol: String::new(),
+    };
+    assert_eq!(format_cell_value_with("代號", "0.5", Some(&percent)), "50%");
+    assert_eq!(format_cell_value_with("Price", "n/a", Some(&format)), "n/a");
+    assert_eq!(format_cell_value_with("買進", "1234.5", None), "1,234.50");
 }
 
 #[test]
-fn format_ratio_or_na_handles_zero_denominator() {
-    assert_eq!(format_ratio_or_na(10.0, 0.0), "N/A");
-    assert_eq!(format_ratio_or_na(25.0, 200.0), "0.125");
+fn column_display_name_prefers_alias() {
+    let aliases = BTreeMap::from([(1, "Region".to_string()), (2, " ".to_string())]);
+    assert_eq!(column_display_name("名稱", 0, &aliases), "名稱");
+    assert_eq!(column_display_name("國內 /國外", 1, &aliases), "Region");
+    assert_eq!(column_display_name("幣別", 2, &aliases), "幣別");
 }
 
 #[test]
-fn transform_assets_sheet_keeps_required_columns_and_adds_settlement_column() {
+fn chart_points_skip_non_numeric_values() {
     let rows = vec![
-        vec![
-            "證券戶".to_string(),
-            "王小明".to_string(),
-            "元大證券".to_string(),
-            "A12345".to_string(),
-            "TWD".to_string(),
-            "100000".to_string(),
-        ],
-        vec![
-            "銀行活存".to_string(),
-            "王小明".to_string(),
-            "台灣銀行".to_string(),
-            "B67890".to_string(),
-            "TWD".to_string(),
-            "50000".to_string(),
+        vec!["0050".to_string(), "1,200".to_string()],
+        vec!["2330".to_string(), "".to_string()],
+        vec!["元大".to_string(), "5%".to_string()],
+    ];
+    let points = chart_points(&rows, 0, 1);
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[0].label, "0050");
+    assert_eq!(points[0].x, Some(50.0));
+    assert_eq!(points[0].y, 1200.0);
+    assert_eq!(points[1].x, None);
+    assert!((points[1].y - 0.05).abs() < 1e-9);
+}
+
+#[test]
+fn default_chart_columns_pick_label_and_value() {
+    let rows = vec![vec!["1".to_string(), "名稱".to_string(), "3.5".to_string()]];
+    assert_eq!(default_chart_columns(&rows, 3), (1, Some(0)));
+    let rows = vec![vec!["a".to_string(), "b".to_string()]];
+    assert_eq!(default_chart_columns(&rows, 2), (0, None));
+}
+
+#[test]
+fn value_range_includes_zero_for_bars_and_pads_flat_series() {
+    assert_eq!(value_range(&[3.0, 5.0], true), (0.0, 5.0));
+    assert_eq!(value_range(&[3.0, 5.0], false), (3.0, 5.0));
+    assert_eq!(value_range(&[2.0, 2.0], false), (1.0, 3.0));
+    assert_eq!(value_range(&[], false), (0.0, 1.0));
+}
+
+#[test]
+fn sparkline_points_span_the_box_with_the_highest_value_on_top() {
+    assert_eq!(
+        sparkline_points(&[1.0, 3.0, 2.0], 40.0, 10.0),
+        "0.0,10.0 20.0,0.0 40.0,5.0"
+    );
+    assert_eq!(sparkline_points(&[5.0], 40.0, 10.0), "0.0,5.0");
+}
+
+#[test]
+fn decode_png_data_url_requires_png_prefix() {
+    let bytes = decode_png_data_url("data:image/png;base64,iVBORw==").expect("should decode");
+    assert_eq!(bytes, vec![0x89, b'P', b'N', b'G']);
+    assert!(decode_png_data_url("").is_err());
+}
+
+#[test]
+fn table_image_fits_the_widest_text_and_escapes_it() {
+    let headers = ["名稱", "淨值"].map(String::from).to_vec();
+    let rows = vec![
+        vec!["台積電".to_string(), "1,234".to_string()],
+        vec!["A&B <ETF>".to_string(), String::new()],
+    ];
+    let image = table_image("持股", &headers, &rows, &[false, true]);
+    assert_eq!(image.height, 12.0 + 32.0 + 24.0 * 3.0 + 12.0);
+    // 9 narrow characters in the first column, 5 in the second, with padding and margins.
+    assert_eq!(image.width, (9.0 * 7.5 + 16.0) + (5.0 * 7.5 + 16.0) + 24.0);
+    assert!(image
+        .svg
+        .starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert!(image.svg.contains(">A&amp;B &lt;ETF&gt;</text>"));
+    assert!(image
+        .svg
+        .contains("text-anchor=\"end\" fill=\"#222\">1,234</text>"));
+    assert!(!image.svg.contains("<ETF>"));
+}
+
+#[test]
+fn validate_cell_edit_checks_required_and_numeric_columns() {
+    let required = required_columns_for_holdings();
+    let (text, number) = (ColumnType::Text, ColumnType::Number);
+    assert_eq!(
+        validate_cell_edit("名稱", text, " ", &required),
+        Err("必填欄位不可空白".to_string())
+    );
+    assert_eq!(
+        validate_cell_edit("數量", number, "abc", &required),
+        Err("欄位 數量 必須是數字".to_string())
+    );
+    assert_eq!(
+        validate_cell_edit("數量", number, "1,000", &required),
+        Ok(())
+    );
+    assert_eq!(validate_cell_edit("備註", text, "", &required), Ok(()));
+    // Any column is checked against its type, whatever its header.
+    assert_eq!(
+        validate_cell_edit("Amount", number, "n/a", &required),
+        Err("欄位 Amount 必須是數字".to_string())
+    );
+    assert!(validate_cell_edit("Paid on", ColumnType::Date, "soon", &required).is_err());
+    assert_eq!(
+        validate_cell_edit("Paid on", ColumnType::Date, "2024/1/31", &required),
+        Ok(())
+    );
+}
+
+#[test]
+fn cell_tooltip_shows_raw_formatted_and_original() {
+    assert_eq!(
+        cell_tooltip("0.1234", "12.34%", None),
+        "原始值：0.1234\n顯示值：12.34%"
+    );
+    assert_eq!(
+        cell_tooltip("5", "5", Some("3")),
+        "原始值：5\n顯示值：5\n修改前：3"
+    );
+    assert_eq!(cell_tooltip("5", "5", Some("5")), "原始值：5\n顯示值：5");
+}
+
+#[test]
+fn selection_stats_sum_numeric_cells_of_selected_rows() {
+    let headers = vec!["代號".to_string(), "市價".to_string(), "數量".to_string()];
+    let rows = vec![
+        vec!["0050".to_string(), "100".to_string(), "2".to_string()],
+        vec!["2330".to_string(), "600".to_string(), "".to_string()],
+        vec!["0056".to_string(), "30".to_string(), "1".to_string()],
+    ];
+    let types = resolve_column_types(&headers, &rows, &BTreeMap::new());
+    assert_eq!(selection_stats(&types, &rows, &BTreeSet::new()), None);
+
+    let stats = selection_stats(&types, &rows, &BTreeSet::from([0, 1, 9]))
+        .expect("selection should produce stats");
+    assert_eq!(stats.rows, 2);
+    assert_eq!(stats.count, 3);
+    assert_eq!(stats.sum, 702.0);
+    assert_eq!(stats.average(), 234.0);
+
+    // Loaded and added rows are combined by reference, without copying either.
+    let added = [vec!["00878".to_string(), "8".to_string(), "".to_string()]];
+    let combined: Vec<&Vec<String>> = rows.iter().chain(added.iter()).collect();
+    let stats = selection_stats(&types, &combined, &BTreeSet::from([0, 3]))
+        .expect("selection should produce stats");
+    assert_eq!(stats.rows, 2);
+    assert_eq!(stats.sum, 110.0);
+}
+
+#[test]
+fn group_rows_keeps_first_appearance_order() {
+    let rows = vec![
+        vec!["B".to_string(), "1".to_string()],
+        vec!["A".to_string(), "2".to_string()],
+        vec![" B ".to_string(), "3".to_string()],
+        vec!["".to_string(), "4".to_string()],
+    ];
+    let groups = group_rows(&rows, 0);
+    let keys: Vec<&str> = groups.iter().map(|g| g.key.as_str()).collect();
+    assert_eq!(keys, vec!["B", "A", ""]);
+    assert_eq!(groups[0].row_indices, vec![0, 2]);
+}
+
+#[test]
+fn group_subtotals_skip_text_columns() {
+    let headers = vec![
+        "所有權人".to_string(),
+        "市價".to_string(),
+        "備註".to_string(),
+    ];
+    let rows = vec![
+        vec!["甲".to_string(), "1,000".to_string(), "x".to_string()],
+        vec!["甲".to_string(), "250.5".to_string(), "y".to_string()],
+        vec!["乙".to_string(), "9".to_string(), "z".to_string()],
+    ];
+    let types = resolve_column_types(&headers, &rows, &BTreeMap::new());
+    assert_eq!(
+        group_subtotals(&types, &rows, &[0, 1]),
+        vec![None, Some(1250.5), None]
+    );
+}
+
+#[test]
+fn blank_holdings_dataset_is_an_editable_holdings_table() {
+    let holdings = builtin_column_set(DatasetKind::Holdings);
+    let (meta, data) = blank_holdings_dataset(&holdings);
+    assert_eq!(DatasetKind::from_name(&meta.name), DatasetKind::Holdings);
+    assert!(is_holdings_table(&data.columns, &holdings));
+    assert!(data.rows.is_empty());
+}
+
+#[test]
+fn column_alignment_follows_inferred_types() {
+    let headers = vec![
+        "代號".to_string(),
+        "Ticker".to_string(),
+        "Price".to_string(),
+    ];
+    let rows = vec![
+        vec!["2330".to_string(), "0050".to_string(), "12.5".to_string()],
+        vec!["2454".to_string(), "2330".to_string(), "".to_string()],
+    ];
+    let types = resolve_column_types(&headers, &rows, &BTreeMap::new());
+    assert_eq!(
+        types,
+        vec![ColumnType::Text, ColumnType::Text, ColumnType::Number]
+    );
+    assert_eq!(page_alignments(&types), vec!["left", "left", "right"]);
+    // A stored type wins over what the values suggest.
+    let stored = BTreeMap::from([(2, ColumnType::Text)]);
+    let types = resolve_column_types(&headers, &rows, &stored);
+    assert_eq!(column_alignment(types[2]), "left");
+}
+
+#[test]
+fn import_infers_column_types_and_sorts_numbers_by_value() {
+    let temp_dir = unique_test_dir("column_types");
+    let db_path = temp_dir.join("app.sqlite");
+    let csv_path = temp_dir.join("trades.csv");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    fs::write(
+        &csv_path,
+        "Ticker,Price,Yield,Paid on,Note\n\
+         0050,10.5,5%,2024-01-05,a\n\
+         2330,9,4.5%,2024/02/01,b\n\
+         00878,100,,2024-03-01,c\n",
+    )
+    .expect("write csv");
+    let imported = import_csv_to_sqlite(&db_path, &csv_path).expect("import should succeed");
+    let dataset_id = imported.dataset_id;
+
+    let types = load_column_types(&db_path, dataset_id).expect("load types");
+    assert_eq!(
+        types.into_values().collect::<Vec<_>>(),
+        vec![
+            ColumnType::Text,
+            ColumnType::Number,
+            ColumnType::Percent,
+            ColumnType::Date,
+            ColumnType::Text,
+        ]
+    );
+
+    let by_price = QueryOptions {
+        sort_col: Some(1),
+        ..QueryOptions::default()
+    };
+    let prices = |db_path: &std::path::Path| {
+        let (_, rows, _) = query_page(db_path, dataset_id, 0, 10, &by_price).expect("query");
+        rows.into_iter()
+            .map(|row| (row[1].clone(), row[2].clone()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        prices(&db_path),
+        vec![
+            ("9".to_string(), "0.045".to_string()),
+            ("10.5".to_string(), "0.05".to_string()),
+            ("100".to_string(), String::new()),
+        ]
+    );
+
+    // Overriding the type back to text sorts the column as text.
+    upsert_column_types(
+        &db_path,
+        dataset_id,
+        &BTreeMap::from([(1, ColumnType::Text)]),
+    )
+    .expect("override type");
+    let sorted: Vec<String> = prices(&db_path)
+        .into_iter()
+        .map(|(price, _)| price)
+        .collect();
+    assert_eq!(sorted, vec!["10.5", "100", "9"]);
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn page_formatting_applies_column_formats_to_every_cell() {
+    let columns = vec!["代號".to_string(), "市值".to_string()];
+    let rows = vec![
+        vec!["0050".to_string(), "1234.5".to_string()],
+        vec!["006208".to_string(), "99".to_string()],
+    ];
+    let formats = BTreeMap::from([(
+        1,
+        ColumnFormat {
+            decimals: 1,
+            percent: false,
+            thousands_separator: true,
+            currency_symbol: String::new(),
+        },
+    )]);
+
+    let types = resolve_column_types(&columns, &rows, &BTreeMap::new());
+    let formatted = format_page(
+        &columns,
+        &rows,
+        &formats,
+        &types,
+        &DatasetDisplay::default(),
+    );
+    assert_eq!(formatted.len(), 2);
+    assert_eq!(formatted[0][0], "0050");
+    assert_eq!(
+        formatted[0][1],
+        format_cell_value_with("市值", "1234.5", formats.get(&1))
+    );
+    assert_eq!(
+        formatted[1][1],
+        format_cell_value_with("市值", "99", formats.get(&1))
+    );
+    assert_eq!(page_alignments(&types), vec!["left", "right"]);
+}
+
+#[test]
+fn dataset_display_unit_scales_amounts_on_screen_and_in_exports() {
+    let temp_dir = unique_test_dir("dataset-display");
+    fs::create_dir_all(&temp_dir).expect("create temp dir");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let headers = vec![
+        "名稱".to_string(),
+        "數量".to_string(),
+        "市價".to_string(),
+        "淨值".to_string(),
+    ];
+    let rows = vec![vec![
+        "Apple".to_string(),
+        "2000".to_string(),
+        "150.5".to_string(),
+        "1234000".to_string(),
+    ]];
+    let dataset_id = create_dataset_from_rows(&db_path, "美股", "us.xlsx#美股", &headers, &rows)
+        .expect("create");
+    let services = AppServices::new(db_path.clone());
+    let query = &services.query_service;
+    assert_eq!(
+        query.load_dataset_display(DatasetId(dataset_id)).unwrap(),
+        DatasetDisplay::default()
+    );
+    let display = DatasetDisplay {
+        currency: "USD".to_string(),
+        unit: DisplayUnit::Thousand,
+    };
+    services.read_only.set(true);
+    assert!(matches!(
+        query.save_dataset_display(DatasetId(dataset_id), display.clone()),
+        Err(RepoError::ReadOnly)
+    ));
+    services.read_only.set(false);
+    query
+        .save_dataset_display(DatasetId(dataset_id), display.clone())
+        .expect("save display");
+    assert_eq!(
+        query.load_dataset_display(DatasetId(dataset_id)).unwrap(),
+        display
+    );
+
+    let types = resolve_column_types(&headers, &rows, &BTreeMap::new());
+    let formatted = format_page(&headers, &rows, &BTreeMap::new(), &types, &display);
+    assert_eq!(formatted[0], vec!["Apple", "2,000", "150.50", "1,234"]);
+
+    let columns: Vec<(usize, String)> = headers.iter().cloned().enumerate().collect();
+    let aliases = BTreeMap::from([(3, "Net".to_string())]);
+    let (exported_headers, exported_rows) =
+        display_export_table(&columns, &rows, &aliases, &types, &display);
+    assert_eq!(
+        exported_headers,
+        vec!["名稱", "數量", "市價", "Net (USD 千元)"]
+    );
+    assert_eq!(exported_rows[0], vec!["Apple", "2000", "150.5", "1234"]);
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn format_ratio_or_na_handles_zero_denominator() {
+    assert_eq!(format_ratio_or_na(10.0, 0.0), "N/A");
+    assert_eq!(format_ratio_or_na(25.0, 200.0), "0.125");
+}
+
+#[test]
+fn transform_assets_sheet_keeps_required_columns_and_adds_settlement_column() {
+    let rows = vec![
+        vec![
+            "證券戶".to_string(),
+            "王小明".to_string(),
+            "元大證券".to_string(),
+            "A12345".to_string(),
+            "TWD".to_string(),
+            "100000".to_string(),
+        ],
+        vec![
+            "銀行活存".to_string(),
+            "王小明".to_string(),
+            "台灣銀行".to_string(),
+            "B67890".to_string(),
+            "TWD".to_string(),
+            "50000".to_string(),
         ],
         vec![
             "銀行活存".to_string(),
@@ -1236,7 +2355,7 @@ fn transform_assets_sheet_keeps_required_columns_and_adds_settlement_column() {
         ],
     ];
 
-    let (headers, data) = transform_assets_sheet(&rows, 0.0, 0.0);
+    let (headers, data, _) = transform_assets_sheet(&rows, 0.0, 0.0);
 
     assert_eq!(
         headers,
@@ -1273,7 +2392,7 @@ fn reorder_headers_and_rows_applies_preferred_order() {
         "期數".to_string(),
         "其他".to_string(),
     ];
-    let rows = vec![headers.iter().map(|h| h.clone()).collect::<Vec<_>>()];
+    let rows = vec![headers.to_vec()];
     let preferred = [
         "所有權人",
         "名稱",
@@ -1313,20 +2432,375 @@ fn reorder_headers_and_rows_applies_preferred_order() {
 #[test]
 fn holdings_editable_and_required_columns_match_spec() {
     let required = required_columns_for_holdings();
-    let editable = editable_columns_for_holdings();
+    let mut headers = required.clone();
+    headers.push("總成本".to_string());
+    let editable = builtin_column_set(DatasetKind::Holdings).editable_columns(&headers);
     assert!(required.iter().all(|c| editable.contains(c)));
     assert!(required.contains(&"所有權人".to_string()));
     assert!(required.contains(&"配息方式".to_string()));
     assert!(!editable.contains(&"總成本".to_string()));
 }
 
+#[test]
+fn column_sets_are_saved_per_dataset_type_and_reset_to_builtin() {
+    let temp_dir = unique_test_dir("column-sets");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    init_db(&db_path).expect("should init db");
+    let query = AppServices::new(db_path).query_service;
+    assert_eq!(
+        query.load_column_set(DatasetKind::Holdings).unwrap(),
+        builtin_column_set(DatasetKind::Holdings)
+    );
+
+    let custom = ColumnSet {
+        required: parse_column_names("持有人\n名稱\n\n代碼\n名稱\n"),
+        derived: vec!["成本".to_string()],
+    };
+    assert_eq!(custom.required, vec!["持有人", "名稱", "代碼"]);
+    query
+        .save_column_set(DatasetKind::Holdings, custom.clone())
+        .expect("should save column set");
+    assert_eq!(
+        query.load_column_set(DatasetKind::Holdings).unwrap(),
+        custom
+    );
+    assert_eq!(
+        query.load_column_set(DatasetKind::Assets).unwrap(),
+        ColumnSet::default()
+    );
+    let headers = vec![
+        "持有人".to_string(),
+        "名稱".to_string(),
+        "代碼".to_string(),
+        "成本".to_string(),
+    ];
+    assert!(is_holdings_table(&headers, &custom));
+    assert_eq!(custom.editable_columns(&headers), headers[..3].to_vec());
+
+    let overlapping = ColumnSet {
+        required: vec!["名稱".to_string()],
+        derived: vec!["名稱".to_string()],
+    };
+    assert!(query
+        .save_column_set(DatasetKind::Assets, overlapping)
+        .is_err());
+    assert!(query
+        .save_column_set(DatasetKind::Holdings, ColumnSet::default())
+        .is_err());
+
+    query
+        .reset_column_set(DatasetKind::Holdings)
+        .expect("should reset column set");
+    assert_eq!(
+        query.load_column_set(DatasetKind::Holdings).unwrap(),
+        builtin_column_set(DatasetKind::Holdings)
+    );
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
 #[test]
 fn assets_editable_columns_include_all_headers() {
     let headers = vec!["欄位A".to_string(), "欄位B".to_string()];
-    let editable = editable_columns_for_assets(&headers);
+    let editable = builtin_column_set(DatasetKind::Assets).editable_columns(&headers);
     assert_eq!(editable, headers);
 }
 
+#[test]
+fn asset_accounts_are_validated_before_they_are_added() {
+    let columns = [
+        "資產形式",
+        "所有權人",
+        "往來機構",
+        "帳號",
+        "幣別",
+        "餘額",
+        "交割款",
+    ]
+    .map(String::from)
+    .to_vec();
+    let rows = vec![[
+        "活存",
+        "王小明",
+        "台新銀行",
+        "2888-10-0123456",
+        "TWD",
+        "286000",
+        "",
+    ]
+    .map(String::from)
+    .to_vec()];
+    let account = AssetAccount {
+        form: "定存".to_string(),
+        owner: " 陳美華".to_string(),
+        institution: "中華郵政".to_string(),
+        account: "0001234-5678901".to_string(),
+        currency: "twd".to_string(),
+        balance: "1,000,000".to_string(),
+    };
+
+    let stored = validate_account(&account, &columns, &rows, NumberLocale::default())
+        .expect("valid account");
+    assert_eq!(stored.owner, "陳美華");
+    assert_eq!(stored.currency, "TWD");
+    assert_eq!(stored.balance, "1000000");
+    let row = stored.to_row(&columns);
+    assert_eq!(
+        row,
+        [
+            "定存",
+            "陳美華",
+            "中華郵政",
+            "0001234-5678901",
+            "TWD",
+            "1000000",
+            ""
+        ]
+        .map(String::from)
+        .to_vec()
+    );
+    assert_eq!(AssetAccount::from_row(&columns, &row), stored);
+
+    let rejected = |account: AssetAccount| {
+        validate_account(&account, &columns, &rows, NumberLocale::default())
+            .unwrap_err()
+            .to_string()
+    };
+    assert_eq!(
+        rejected(AssetAccount {
+            owner: "  ".to_string(),
+            ..account.clone()
+        }),
+        "請填寫所有權人"
+    );
+    assert!(rejected(AssetAccount {
+        currency: "新台幣".to_string(),
+        ..account.clone()
+    })
+    .starts_with("幣別"));
+    assert_eq!(
+        rejected(AssetAccount {
+            balance: "很多".to_string(),
+            ..account.clone()
+        }),
+        "餘額必須是數字"
+    );
+    assert!(rejected(AssetAccount {
+        institution: "台新銀行".to_string(),
+        account: "2888-10-0123456".to_string(),
+        ..account
+    })
+    .contains("已在資產總表中"));
+}
+
+#[test]
+fn balance_update_walks_the_accounts_with_typed_balances() {
+    let columns = ["資產形式", "所有權人", "往來機構", "帳號", "幣別", "餘額"]
+        .map(String::from)
+        .to_vec();
+    let rows = [
+        [
+            "活存",
+            "王小明",
+            "台新銀行",
+            "2888-10-0123456",
+            "TWD",
+            "286000",
+        ],
+        [
+            "股票投資",
+            "王小明",
+            "元大證券",
+            "9A95-0012345",
+            "TWD",
+            "60400",
+        ],
+        [
+            "外幣存款",
+            "陳美華",
+            "國泰世華",
+            "0150-35-067890",
+            "USD",
+            "8200",
+        ],
+    ]
+    .map(|row| row.map(String::from).to_vec())
+    .to_vec();
+    assert_eq!(typed_balance_rows(&columns, &rows), vec![0, 2]);
+    assert!(typed_balance_rows(&columns[..5], &rows).is_empty());
+
+    assert_eq!(
+        parse_balance("1,234.50", NumberLocale::default()).unwrap(),
+        "1234.5"
+    );
+    assert_eq!(
+        parse_balance("1.234,5", NumberLocale::CommaDecimal).unwrap(),
+        "1234.5"
+    );
+    assert!(parse_balance("", NumberLocale::default()).is_err());
+}
+
+#[test]
+fn deposit_interest_comes_from_the_rate_table() {
+    let rates = parse_deposit_rates("臺灣銀行 1年 1.6%\n\n臺灣銀行, 1.2\n合作金庫\t3個月\t1.5%")
+        .expect("parse rates");
+    assert_eq!(rates.len(), 3);
+    assert_eq!(rates[1].tenor, "");
+    assert_eq!(
+        format_deposit_rates(&rates),
+        "臺灣銀行 1年 1.6%\n臺灣銀行 1.2%\n合作金庫 3個月 1.5%"
+    );
+    assert!(parse_deposit_rates("臺灣銀行 1年 一點六").is_err());
+    assert!(parse_deposit_rates("臺灣銀行 1.6%\n臺灣銀行 1.2%").is_err());
+
+    let temp_dir = unique_test_dir("deposit_rates");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let services = AppServices::new(db_path);
+    let dataset_id = services
+        .edit_service
+        .create_dataset(
+            NewDatasetMeta {
+                name: "資產總表".to_string(),
+                source_path: "test".to_string(),
+            },
+            TabularData {
+                columns: ["資產形式", "所有權人", "往來機構", "存期", "餘額"]
+                    .map(String::from)
+                    .to_vec(),
+                rows: [
+                    ["定存", "王小明", "臺灣銀行", "1年", "100000"],
+                    ["定存", "王小明", "臺灣銀行", "6個月", "50000"],
+                    ["定存", "陳美華", "合作金庫", "6個月", "100000"],
+                    ["活存", "陳美華", "合作金庫", "", "20000"],
+                ]
+                .map(|row| row.map(String::from).to_vec())
+                .to_vec(),
+            },
+        )
+        .expect("create dataset");
+    let query = &services.query_service;
+    let revision = query.dataset_revision(dataset_id).expect("revision");
+    query.save_deposit_rates(rates.clone()).expect("save rates");
+    assert_eq!(query.load_deposit_rates().expect("load rates"), rates);
+    assert!(query.dataset_revision(dataset_id).expect("revision") > revision);
+
+    let report = dataset_summary_report(query, dataset_id, &[]).expect("summary");
+    let value_for = |label: &str| {
+        report
+            .totals
+            .iter()
+            .find(|entry| entry.label == label)
+            .map(|entry| entry.value.clone())
+    };
+    assert_eq!(value_for("定存資金-年化").as_deref(), Some("2200"));
+    assert_eq!(value_for("定存資金-月化").as_deref(), Some("183.333333"));
+    assert!(report
+        .notes
+        .iter()
+        .any(|note| note.contains("合作金庫 6個月")));
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn alerts_cover_checks_mismatches_losses_and_maturities() {
+    let temp_dir = unique_test_dir("alerts");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let services = AppServices::new(db_path);
+    let create = |name: &str, columns: &[&str], rows: &[&[&str]]| {
+        services
+            .edit_service
+            .create_dataset(
+                NewDatasetMeta {
+                    name: name.to_string(),
+                    source_path: format!("帳本.xlsx#{name}"),
+                },
+                TabularData {
+                    columns: columns.iter().map(|column| column.to_string()).collect(),
+                    rows: rows
+                        .iter()
+                        .map(|row| row.iter().map(|value| value.to_string()).collect())
+                        .collect(),
+                },
+            )
+            .expect("create dataset")
+    };
+    let holdings_id = create(
+        "持股股息總表",
+        &["所有權人", "代號", "名稱", "淨值", "損益率"],
+        &[
+            &["王小明", "2330", "台積電", "50000", "25%"],
+            &["王小明", "0056", "元大高股息", "8000", "-12%"],
+            &["陳美華", "2330", "台積電", "2400", "-5%"],
+        ],
+    );
+    let assets_id = create(
+        "資產總表",
+        &["資產形式", "往來機構", "帳號", "餘額", "到期日"],
+        &[
+            &["股票投資", "元大證券", "9A95", "60000", ""],
+            &["活存", "台新銀行", "2888", "待查", ""],
+            &["定存", "臺灣銀行", "0001", "100000", "2026/11/01"],
+            &["定存", "臺灣銀行", "0002", "100000", "2027/06/01"],
+        ],
+    );
+
+    let query = &services.query_service;
+    let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+    let alerts = collect_alerts(query, today, DEFAULT_LOSS_THRESHOLD).expect("collect alerts");
+    let summary: Vec<(AlertKind, DatasetId, &str)> = alerts
+        .iter()
+        .map(|alert| (alert.kind, alert.dataset_id, alert.message.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (
+                AlertKind::Validation,
+                holdings_id,
+                "代號 2330 出現在 2 列：台積電、台積電"
+            ),
+            (
+                AlertKind::Validation,
+                assets_id,
+                "台新銀行 2888 的餘額「待查」不是數字"
+            ),
+            (
+                AlertKind::Reconciliation,
+                assets_id,
+                "股票投資餘額 60000 與持股淨值 60400 不符"
+            ),
+            (
+                AlertKind::Threshold,
+                holdings_id,
+                "元大高股息（0056）損益率 -12.00%，已達 -10.00%"
+            ),
+            (
+                AlertKind::Maturity,
+                assets_id,
+                "臺灣銀行 0001 的定存將於 2026-11-01 到期（剩 16 天）"
+            ),
+        ]
+    );
+    assert!(collect_alerts(query, today, 0.2)
+        .expect("collect alerts")
+        .iter()
+        .all(|alert| alert.kind != AlertKind::Threshold));
+
+    assert!(query.load_acknowledged_alerts().expect("load").is_empty());
+    let keys: Vec<String> = alerts[..2].iter().map(|alert| alert.key.clone()).collect();
+    query.acknowledge_alerts(keys.clone()).expect("acknowledge");
+    query
+        .acknowledge_alerts(keys.clone())
+        .expect("acknowledge twice");
+    assert_eq!(
+        query.load_acknowledged_alerts().expect("load"),
+        keys.into_iter().collect::<BTreeSet<_>>()
+    );
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
 #[test]
 fn build_updated_rows_applies_staged_values() {
     let columns = vec!["所有權人".to_string(), "名稱".to_string()];
@@ -1359,7 +2833,7 @@ fn validate_required_holdings_row_rejects_empty_required_field() {
         .expect("should have required header");
     row[owner_idx] = String::new();
 
-    let result = validate_required_holdings_row(&headers, &row);
+    let result = validate_required_holdings_row(&headers, &row, &headers);
 
     assert!(result.is_err());
 }
@@ -1456,19 +2930,108 @@ fn apply_changes_to_dataset_updates_rows() {
 }
 
 #[test]
-fn create_dataset_from_rows_inserts_dataset() {
-    let temp_dir = unique_test_dir("create-dataset");
+fn saving_records_the_cells_it_changed_for_highlighting() {
+    let temp_dir = unique_test_dir("saved-changes");
     fs::create_dir_all(&temp_dir).expect("should create temp dir");
     let db_path = temp_dir.join("app.sqlite");
     init_db(&db_path).expect("init_db should succeed");
+    let columns = vec!["name".to_string(), "city".to_string()];
+    let rows = vec![
+        vec!["Alice".to_string(), "Paris".to_string()],
+        vec!["Bob".to_string(), "Tokyo".to_string()],
+        vec!["Dan".to_string(), "Oslo".to_string()],
+    ];
+    let dataset_id =
+        create_dataset_from_rows(&db_path, "people", "people.csv#people", &columns, &rows)
+            .expect("create dataset");
+    let services = AppServices::new(db_path.clone());
+    let changes = || {
+        services
+            .query_service
+            .load_saved_changes(DatasetId(dataset_id))
+            .expect("load saved changes")
+    };
+    assert!(changes().is_empty());
+
+    // Dan moves up past the deleted Bob, so his edit is recorded at row 1.
+    let staged = HashMap::from([
+        (
+            CellKey {
+                row_idx: 2,
+                col_idx: 1,
+                column: "city".to_string(),
+            },
+            "Bergen".to_string(),
+        ),
+        (
+            CellKey {
+                row_idx: 0,
+                col_idx: 0,
+                column: "name".to_string(),
+            },
+            "Alice".to_string(),
+        ),
+    ]);
+    services
+        .edit_service
+        .apply_edits(
+            DatasetId(dataset_id),
+            StagedEdits {
+                staged_cells: staged,
+                deleted_rows: BTreeSet::from([1]),
+                added_rows: vec![vec!["Cara".to_string(), "Rome".to_string()]],
+            },
+        )
+        .expect("apply edits");
+    assert_eq!(
+        changes(),
+        SavedChanges::from([
+            ((1, 1), Some("Oslo".to_string())),
+            ((2, 0), None),
+            ((2, 1), None),
+        ])
+    );
 
-    let columns = vec!["col1".to_string(), "col2".to_string()];
-    let rows = vec![vec!["a".to_string(), "b".to_string()]];
-    let dataset_id = create_dataset_from_rows(&db_path, "backup", "test#backup", &columns, &rows)
-        .expect("create dataset should succeed");
-
-    let conn = Connection::open(&db_path).expect("should open sqlite db");
-    let count: i64 = conn
+    // Only the latest save is highlighted.
+    let staged = HashMap::from([(
+        CellKey {
+            row_idx: 0,
+            col_idx: 1,
+            column: "city".to_string(),
+        },
+        "Lyon".to_string(),
+    )]);
+    services
+        .edit_service
+        .apply_edits(
+            DatasetId(dataset_id),
+            StagedEdits {
+                staged_cells: staged,
+                ..StagedEdits::default()
+            },
+        )
+        .expect("apply edits");
+    assert_eq!(
+        changes(),
+        SavedChanges::from([((0, 1), Some("Paris".to_string()))])
+    );
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn create_dataset_from_rows_inserts_dataset() {
+    let temp_dir = unique_test_dir("create-dataset");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    init_db(&db_path).expect("init_db should succeed");
+
+    let columns = vec!["col1".to_string(), "col2".to_string()];
+    let rows = vec![vec!["a".to_string(), "b".to_string()]];
+    let dataset_id = create_dataset_from_rows(&db_path, "backup", "test#backup", &columns, &rows)
+        .expect("create dataset should succeed");
+
+    let conn = Connection::open(&db_path).expect("should open sqlite db");
+    let count: i64 = conn
         .query_row(
             "SELECT COUNT(*) FROM dataset WHERE id = ?1 AND name = ?2",
             params![dataset_id, "backup"],
@@ -1497,3 +3060,4366 @@ fn create_dataset_from_rows_inserts_dataset() {
 
     fs::remove_dir_all(&temp_dir).expect("should cleanup temp dir");
 }
+
+#[test]
+fn error_report_keeps_full_chain() {
+    let err = anyhow::anyhow!("disk full").context("failed to write dataset");
+    let report = ErrorReport::new("保存失敗", err.chain_messages());
+
+    assert_eq!(
+        report.chain,
+        vec![
+            "failed to write dataset".to_string(),
+            "disk full".to_string()
+        ]
+    );
+    assert_eq!(report.summary(), "保存失敗：failed to write dataset");
+    let text = report.to_text();
+    assert!(text.contains("錯誤：failed to write dataset"));
+    assert!(text.contains("原因：disk full"));
+}
+
+#[test]
+fn rotating_log_writer_appends_and_rotates() {
+    use std::io::Write;
+
+    let temp_dir = unique_test_dir("error-log");
+    let log_path = temp_dir.join("logs").join("bom.log");
+
+    let mut writer = RotatingFileWriter::new(log_path.clone(), 12, 2).expect("should open log");
+    writer
+        .write_all(b"first\n")
+        .expect("should write first entry");
+    writer
+        .write_all(b"second\n")
+        .expect("should rotate and write");
+    writer
+        .write_all(b"third\n")
+        .expect("should write third entry");
+    writer.write_all(b"fourth\n").expect("should rotate again");
+    writer
+        .write_all(b"fifth\n")
+        .expect("should drop the oldest file");
+    writer.flush().expect("should flush");
+
+    let read = |path: &PathBuf| fs::read_to_string(path).expect("should read log");
+    assert_eq!(read(&log_path), "fifth\n");
+    assert_eq!(read(&rotated_path(&log_path, 1)), "fourth\n");
+    assert_eq!(read(&rotated_path(&log_path, 2)), "third\n");
+    assert!(!rotated_path(&log_path, 3).exists());
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn notification_commands_keep_title_and_body_out_of_scripts_and_options() {
+    let title = "-備份完成";
+    let body = "已上傳 'a\u{2018}\u{2019}\u{201A}\u{201B}\"b\"; Remove-Item x";
+    let command = notification_command(title, body);
+    let program = command.get_program().to_string_lossy().into_owned();
+    let args = command
+        .get_args()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    if cfg!(windows) {
+        assert_eq!(program, "powershell");
+        assert!(args.iter().all(|arg| !arg.contains("Remove-Item")));
+        let envs = command
+            .get_envs()
+            .filter_map(|(key, value)| Some((key.to_str()?, value?.to_str()?)))
+            .collect::<Vec<_>>();
+        assert!(envs.contains(&(TITLE_ENV, title)));
+        assert!(envs.contains(&(BODY_ENV, body)));
+    } else if cfg!(target_os = "macos") {
+        assert_eq!(program, "osascript");
+        assert_eq!(&args[args.len() - 2..], [title, body]);
+        assert!(args[..args.len() - 2]
+            .iter()
+            .all(|arg| !arg.contains("Remove-Item")));
+    } else {
+        assert_eq!(program, "notify-send");
+        assert_eq!(args, ["--app-name=BOM", "--", title, body]);
+    }
+}
+
+#[test]
+fn copied_cells_use_raw_staged_values() {
+    let rows = vec![
+        vec!["2330".to_string(), "1000".to_string()],
+        vec!["0056".to_string(), "2500.5".to_string()],
+    ];
+    let edited = CellKey {
+        row_idx: 1,
+        col_idx: 1,
+        column: "股數".to_string(),
+    };
+    let staged = HashMap::from([(edited.clone(), "3000".to_string())]);
+    let added = vec![vec!["00878".to_string(), "120".to_string()]];
+
+    assert_eq!(cell_raw_value(&rows, &staged, &edited), "3000");
+    let stored = CellKey {
+        row_idx: 0,
+        ..edited.clone()
+    };
+    assert_eq!(cell_raw_value(&rows, &staged, &stored), "1000");
+    assert_eq!(
+        column_raw_text(&rows, &added, &staged, 1, "股數"),
+        "1000\n3000\n120"
+    );
+    assert_eq!(
+        column_raw_text(&rows, &[], &staged, 0, "代號"),
+        "2330\n0056"
+    );
+}
+
+#[test]
+fn modified_row_navigation_follows_table_order_and_wraps() {
+    let staged = HashMap::from([(
+        CellKey {
+            row_idx: 4,
+            col_idx: 1,
+            column: "股數".to_string(),
+        },
+        "10".to_string(),
+    )]);
+    let modified = modified_rows(&staged, &BTreeSet::from([1_usize]));
+    assert_eq!(modified, BTreeSet::from([1, 4]));
+
+    // Grouped tables show rows out of index order.
+    let order = [4, 0, 2, 1, 3];
+    assert_eq!(step_modified_row(&order, &modified, None, true), Some(4));
+    assert_eq!(step_modified_row(&order, &modified, None, false), Some(1));
+    assert_eq!(step_modified_row(&order, &modified, Some(4), true), Some(1));
+    assert_eq!(step_modified_row(&order, &modified, Some(1), true), Some(4));
+    assert_eq!(
+        step_modified_row(&order, &modified, Some(0), false),
+        Some(4)
+    );
+    assert_eq!(
+        step_modified_row(&order, &modified, Some(4), false),
+        Some(1)
+    );
+    assert_eq!(
+        step_modified_row(&order, &BTreeSet::new(), Some(0), true),
+        None
+    );
+}
+
+#[test]
+fn pending_change_count_skips_edits_on_deleted_rows() {
+    let mut staged = HashMap::new();
+    staged.insert(
+        CellKey {
+            row_idx: 0,
+            col_idx: 1,
+            column: "股數".to_string(),
+        },
+        "10".to_string(),
+    );
+    staged.insert(
+        CellKey {
+            row_idx: 2,
+            col_idx: 1,
+            column: "股數".to_string(),
+        },
+        "20".to_string(),
+    );
+    let deleted = BTreeSet::from([2_usize]);
+    let added = vec![vec!["2330".to_string()]];
+
+    assert_eq!(pending_change_count(&staged, &deleted, &added), 3);
+    assert_eq!(
+        pending_change_count(&HashMap::new(), &BTreeSet::new(), &[]),
+        0
+    );
+}
+
+#[test]
+fn backup_database_copies_datasets() {
+    let temp_dir = unique_test_dir("backup");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    let backup_path = temp_dir.join("backup.sqlite");
+    init_db(&db_path).expect("init db should succeed");
+    create_dataset_from_rows(
+        &db_path,
+        "持股",
+        "test",
+        &["代號".to_string()],
+        &[vec!["2330".to_string()]],
+    )
+    .expect("create dataset should succeed");
+
+    backup_database(&db_path, &backup_path).expect("first backup should succeed");
+    backup_database(&db_path, &backup_path).expect("backup should overwrite");
+
+    let datasets = list_datasets(&backup_path, false).expect("should read backup");
+    assert_eq!(datasets.len(), 1);
+    assert_eq!(datasets[0].name, "持股");
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn export_csv_writes_header_and_rows() {
+    let temp_dir = unique_test_dir("export-csv");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let csv_path = temp_dir.join("out.csv");
+
+    export_csv(
+        &csv_path,
+        &["代號".to_string(), "名稱".to_string()],
+        &[vec!["2330".to_string(), "台積電, 普通股".to_string()]],
+    )
+    .expect("export should succeed");
+
+    let content = fs::read_to_string(&csv_path).expect("should read csv");
+    assert_eq!(content, "代號,名稱\n2330,\"台積電, 普通股\"\n");
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn dataset_diff_exports_added_removed_and_changed_rows() {
+    use calamine::Reader;
+
+    let temp_dir = unique_test_dir("export-diff");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    let cells = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+    let before = create_dataset_from_rows(
+        &db_path,
+        "0131",
+        "test",
+        &cells(&["代號", "名稱", "數量"]),
+        &[
+            cells(&["0050", "元大台灣50", "10"]),
+            cells(&["2330", "台積電", "5"]),
+            cells(&["0056", "元大高股息", "3"]),
+        ],
+    )
+    .expect("create older");
+    let after = create_dataset_from_rows(
+        &db_path,
+        "0228",
+        "test",
+        &cells(&["代號", "名稱", "數量", "備註"]),
+        &[
+            cells(&["0050", "元大台灣50", "10", ""]),
+            cells(&["2330", "台積電", "8", "加碼"]),
+            cells(&["00878", "國泰永續高股息", "20", ""]),
+            cells(&["", "小計", "38", ""]),
+        ],
+    )
+    .expect("create newer");
+    let services = AppServices::new(db_path);
+
+    let xlsx_path = temp_dir.join("diff.xlsx");
+    let diff = export_dataset_diff(&services.query_service, before, after, &xlsx_path)
+        .expect("export xlsx");
+    assert_eq!(diff.columns, cells(&["代號", "名稱", "數量", "備註"]));
+    assert_eq!(
+        dataset_diff_summary(&diff),
+        "已匯出差異：新增 1、刪除 1、變更 1"
+    );
+    let expected = vec![
+        cells(&["變動", "代號", "名稱", "數量", "備註"]),
+        cells(&["新增", "00878", "國泰永續高股息", "20", ""]),
+        cells(&["刪除", "0056", "元大高股息", "3", ""]),
+        cells(&["變更", "2330", "台積電", "5 → 8", " → 加碼"]),
+    ];
+    let mut workbook = calamine::open_workbook_auto(&xlsx_path).expect("open xlsx");
+    let range = workbook.worksheet_range("差異").expect("diff sheet");
+    let read: Vec<Vec<String>> = range
+        .rows()
+        .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+        .collect();
+    assert_eq!(read, expected);
+
+    let csv_path = temp_dir.join("diff.csv");
+    export_dataset_diff(&services.query_service, before, after, &csv_path).expect("export csv");
+    let content = fs::read_to_string(&csv_path).expect("read csv");
+    assert!(content.starts_with("變動,代號,名稱,數量,備註\n新增,00878,"));
+
+    let no_key = create_dataset_from_rows(
+        &temp_dir.join("app.sqlite"),
+        "other",
+        "test",
+        &cells(&["名稱"]),
+        &[cells(&["甲"])],
+    )
+    .expect("create");
+    assert!(export_dataset_diff(&services.query_service, no_key, after, &csv_path).is_err());
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn xlsx_export_writes_every_matching_row_with_shown_columns() {
+    use calamine::Reader;
+
+    let temp_dir = unique_test_dir("export-xlsx");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    let cells = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+    let dataset_id = create_dataset_from_rows(
+        &db_path,
+        "持股",
+        "test",
+        &cells(&["代號", "名稱", "數量"]),
+        &[
+            cells(&["2330", "台積電", "5"]),
+            cells(&["0056", "元大高股息", "3"]),
+            cells(&["0050", "元大台灣50", "8"]),
+        ],
+    )
+    .expect("create dataset");
+    let services = AppServices::new(db_path);
+    services
+        .query_service
+        .upsert_column_aliases(
+            DatasetId(dataset_id),
+            BTreeMap::from([(0, "股票代號".into())]),
+        )
+        .expect("alias");
+
+    let options = QueryOptions {
+        global_search: "台".to_string(),
+        sort_col: Some(2),
+        sort_desc: true,
+        ..QueryOptions::default()
+    };
+    let xlsx_path = temp_dir.join("持股.xlsx");
+    let written = services
+        .export_service
+        .export_xlsx(
+            build_page_query(dataset_id, 3, &options),
+            &BTreeMap::from([(1, false)]),
+            &xlsx_path,
+        )
+        .expect("export xlsx");
+    assert_eq!(written, 2);
+    let mut workbook = calamine::open_workbook_auto(&xlsx_path).expect("open xlsx");
+    let range = workbook.worksheet_range("持股").expect("dataset sheet");
+    let read: Vec<Vec<String>> = range
+        .rows()
+        .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+        .collect();
+    assert_eq!(
+        read,
+        vec![
+            cells(&["股票代號", "數量"]),
+            cells(&["0050", "8"]),
+            cells(&["2330", "5"]),
+        ]
+    );
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn csv_export_uses_the_chosen_delimiter_and_encoding() {
+    let temp_dir = unique_test_dir("export-csv-options");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    let cells = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+    let dataset_id = create_dataset_from_rows(
+        &db_path,
+        "持股",
+        "test",
+        &cells(&["代號", "名稱", "備註"]),
+        &[
+            cells(&["2330", "台積電", "定期定額; 每月"]),
+            cells(&["0056", "元大高股息", ""]),
+        ],
+    )
+    .expect("create dataset");
+    let services = AppServices::new(db_path);
+    let query = build_page_query(dataset_id, 0, &QueryOptions::default());
+    let export = |path: &PathBuf, delimiter, encoding| {
+        services.export_service.export_csv(
+            query.clone(),
+            &BTreeMap::new(),
+            &CsvOptions {
+                delimiter,
+                encoding,
+            },
+            path,
+        )
+    };
+
+    let big5_path = temp_dir.join("big5.csv");
+    let written =
+        export(&big5_path, CsvDelimiter::Semicolon, CsvEncoding::Big5).expect("export big5");
+    assert_eq!(written, 2);
+    let bytes = fs::read(&big5_path).expect("read big5");
+    let (text, _, had_errors) = encoding_rs::BIG5.decode(&bytes);
+    assert!(!had_errors);
+    assert_eq!(
+        text,
+        "代號;名稱;備註\n2330;台積電;\"定期定額; 每月\"\n0056;元大高股息;\n"
+    );
+
+    let utf8_path = temp_dir.join("utf8.tsv");
+    export(&utf8_path, CsvDelimiter::Tab, CsvEncoding::Utf8Bom).expect("export utf-8");
+    let bytes = fs::read(&utf8_path).expect("read utf-8");
+    assert!(bytes.starts_with(&[0xEF, 0xBB, 0xBF]));
+    assert!(String::from_utf8_lossy(&bytes[3..]).starts_with("代號\t名稱\t備註\n2330\t"));
+
+    let emoji_id = create_dataset_from_rows(
+        &temp_dir.join("app.sqlite"),
+        "備註",
+        "test",
+        &cells(&["備註"]),
+        &[cells(&["加碼😀"])],
+    )
+    .expect("create emoji dataset");
+    let err = services
+        .export_service
+        .export_csv(
+            build_page_query(emoji_id, 0, &QueryOptions::default()),
+            &BTreeMap::new(),
+            &CsvOptions {
+                delimiter: CsvDelimiter::Comma,
+                encoding: CsvEncoding::Big5,
+            },
+            &temp_dir.join("emoji.csv"),
+        )
+        .expect_err("emoji has no Big5 form");
+    assert!(err.to_string().contains("😀"));
+    assert!(!temp_dir.join("emoji.csv").exists());
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn settings_round_trip_and_overwrite() {
+    let temp_dir = unique_test_dir("settings");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    init_db(&db_path).expect("init db should succeed");
+
+    assert_eq!(load_setting(&db_path, "theme").expect("load"), None);
+    save_setting(&db_path, "theme", "light").expect("save");
+    save_setting(&db_path, "theme", "dark").expect("overwrite");
+    assert_eq!(
+        load_setting(&db_path, "theme").expect("load"),
+        Some("dark".to_string())
+    );
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn push_recent_import_moves_path_to_front_and_caps_list() {
+    let recent = parse_recent_imports("a.xlsx\n\nb.csv\nc.csv\n");
+    assert_eq!(recent, vec!["a.xlsx", "b.csv", "c.csv"]);
+
+    let recent = push_recent_import(&recent, "c.csv");
+    assert_eq!(recent, vec!["c.csv", "a.xlsx", "b.csv"]);
+
+    let mut recent = Vec::new();
+    for idx in 0..RECENT_IMPORTS_LIMIT + 2 {
+        recent = push_recent_import(&recent, &format!("{idx}.csv"));
+    }
+    assert_eq!(recent.len(), RECENT_IMPORTS_LIMIT);
+    assert_eq!(recent[0], format!("{}.csv", RECENT_IMPORTS_LIMIT + 1));
+}
+
+#[test]
+fn fuzzy_match_prefers_prefix_and_consecutive_matches() {
+    assert_eq!(fuzzy_match_score("", "匯入"), Some(0));
+    assert!(fuzzy_match_score("xyz", "Export CSV").is_none());
+    assert!(fuzzy_match_score("ecsv", "Export CSV").is_some());
+
+    let texts = vec![
+        "切換資料集：持股 0101".to_string(),
+        "匯出 CSV…".to_string(),
+        "顯示欄位：持股數".to_string(),
+    ];
+    assert_eq!(rank_fuzzy_matches("持股", &texts), vec![0, 2]);
+    assert_eq!(rank_fuzzy_matches("csv", &texts), vec![1]);
+    assert_eq!(rank_fuzzy_matches("", &texts), vec![0, 1, 2]);
+}
+
+#[test]
+fn scheduled_snapshots_record_the_latest_summaries_when_due() {
+    let at = |value: &str| {
+        chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").expect("date")
+    };
+    let monday = at("2026-03-02 09:00:00");
+    assert!(!SnapshotSchedule::Off.is_due(None, monday));
+    assert!(SnapshotSchedule::Daily.is_due(None, monday));
+    assert!(!SnapshotSchedule::Daily.is_due(Some("2026-03-02 01:00:00"), monday));
+    assert!(SnapshotSchedule::Daily.is_due(Some("2026-03-01 23:59:00"), monday));
+    assert!(!SnapshotSchedule::Weekly.is_due(Some("2026-02-24 09:00:00"), monday));
+    assert!(SnapshotSchedule::Weekly.is_due(Some("2026-02-23 23:00:00"), monday));
+    assert!(SnapshotSchedule::Weekly.is_due(Some("garbled"), monday));
+
+    let temp_dir = unique_test_dir("snapshots");
+    fs::create_dir_all(&temp_dir).expect("create temp dir");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let services = AppServices::new(db_path);
+    let ids = create_demo_datasets(&services.edit_service).expect("demo datasets");
+    let query = &services.query_service;
+    let now = chrono::Local::now().naive_local();
+
+    let off = record_scheduled_snapshots(query, SnapshotSchedule::Off, &[], now).unwrap();
+    assert!(off.is_empty());
+    let mut recorded = record_scheduled_snapshots(query, SnapshotSchedule::Daily, &[], now)
+        .expect("record snapshots");
+    recorded.sort_by_key(|id| id.0);
+    let mut expected = ids.clone();
+    expected.sort_by_key(|id| id.0);
+    assert_eq!(recorded, expected);
+    // Unlike the cached summary, a snapshot is kept even though nothing changed since.
+    let cached = cached_summary_report(query, ids[0], &[]).expect("cached summary");
+    assert!(cached.id > 0);
+    assert!(
+        record_scheduled_snapshots(query, SnapshotSchedule::Daily, &[], now)
+            .unwrap()
+            .is_empty(),
+        "one snapshot per day"
+    );
+    let next_week = now + chrono::Duration::days(7);
+    let again =
+        record_scheduled_snapshots(query, SnapshotSchedule::Weekly, &[], next_week).unwrap();
+    assert_eq!(again.len(), 2);
+    assert_eq!(query.load_summary_reports(ids[0]).unwrap().len(), 2);
+
+    services.read_only.set(true);
+    let read_only = record_scheduled_snapshots(
+        query,
+        SnapshotSchedule::Daily,
+        &[],
+        next_week + chrono::Duration::days(1),
+    )
+    .expect("read-only skips quietly");
+    assert!(read_only.is_empty());
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn parse_cli_args_reads_subcommands_and_flags() {
+    let args = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(parse_cli_args(&[]).expect("empty args"), None);
+    let invocation = parse_cli_args(&args(&[
+        "--db",
+        "x.sqlite",
+        "query",
+        "--dataset",
+        "5",
+        "--filter",
+        "代號=0056",
+        "--desc",
+    ]))
+    .expect("query should parse")
+    .expect("should be a cli invocation");
+    assert_eq!(invocation.db_path, Some(PathBuf::from("x.sqlite")));
+    assert_eq!(
+        invocation.command,
+        CliCommand::Query {
+            dataset_id: 5,
+            filter: Some(("代號".to_string(), "0056".to_string())),
+            search: String::new(),
+            sort: None,
+            sort_desc: true,
+        }
+    );
+    assert!(parse_cli_args(&args(&["export", "--dataset", "5", "--format", "pdf"])).is_err());
+    assert!(parse_cli_args(&args(&["export"])).is_err());
+    assert!(parse_cli_args(&args(&["frobnicate"])).is_err());
+    assert_eq!(
+        parse_cli_args(&args(&["snapshot"]))
+            .unwrap()
+            .unwrap()
+            .command,
+        CliCommand::Snapshot
+    );
+    assert!(parse_cli_args(&args(&["snapshot", "now"])).is_err());
+}
+
+#[test]
+fn run_cli_imports_and_exports_without_gui() {
+    let temp_dir = unique_test_dir("cli");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    let csv_path = temp_dir.join("in.csv");
+    let out_path = temp_dir.join("out.csv");
+    fs::write(&csv_path, "代號,名稱\n0056,高股息\n2330,台積電\n").expect("should write csv");
+
+    run_cli(CliInvocation {
+        db_path: Some(db_path.clone()),
+        command: CliCommand::Import {
+            path: csv_path.clone(),
+            merge_duplicates: false,
+        },
+    })
+    .expect("import should succeed");
+    let dataset_id = list_datasets(&db_path, false).expect("should list")[0].id.0;
+    run_cli(CliInvocation {
+        db_path: Some(db_path.clone()),
+        command: CliCommand::Export {
+            dataset_id,
+            output: Some(out_path.clone()),
+        },
+    })
+    .expect("export should succeed");
+
+    let content = fs::read_to_string(&out_path).expect("should read export");
+    assert_eq!(content, "代號,名稱\n0056,高股息\n2330,台積電\n");
+    assert_eq!(
+        format_tsv(&["a".to_string()], &[vec!["x\ty".to_string()]]),
+        "a\nx y\n"
+    );
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn api_route_serves_datasets_rows_and_errors() {
+    let temp_dir = unique_test_dir("api");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    init_db(&db_path).expect("init db should succeed");
+    let dataset_id = create_dataset_from_rows(
+        &db_path,
+        "持股",
+        "test",
+        &["代號".to_string(), "名稱".to_string()],
+        &[
+            vec!["0056".to_string(), "高股息".to_string()],
+            vec!["2330".to_string(), "台積電".to_string()],
+        ],
+    )
+    .expect("create dataset should succeed");
+    let service = QueryService::new(std::sync::Arc::new(SqliteRepo {
+        db_path: db_path.clone(),
+    }));
+
+    let (status, body) = route(&service, "/api/datasets");
+    assert_eq!(status, 200);
+    assert_eq!(body[0]["name"], "持股");
+
+    let (status, body) = route(
+        &service,
+        &format!("/api/datasets/{dataset_id}/rows?column=%E4%BB%A3%E8%99%9F&term=0056"),
+    );
+    assert_eq!(status, 200);
+    assert_eq!(body["total_rows"], 1);
+    assert_eq!(body["rows"][0][1], "高股息");
+
+    let (status, _) = route(
+        &service,
+        &format!("/api/datasets/{dataset_id}/rows?sort=nope"),
+    );
+    assert_eq!(status, 400);
+    let (status, body) = route(&service, &format!("/api/datasets/{dataset_id}/rows"));
+    assert_eq!(status, 200);
+    assert_eq!(body["page_size"], MAX_PAGE_SIZE);
+    let (status, body) = route(
+        &service,
+        &format!(
+            "/api/datasets/{dataset_id}/rows?page=1&page_size={}",
+            i64::MAX
+        ),
+    );
+    assert_eq!(status, 200, "page_size is clamped");
+    assert_eq!(body["page_size"], MAX_PAGE_SIZE);
+    assert_eq!(body["rows"].as_array().map(Vec::len), Some(0));
+    let (status, _) = route(
+        &service,
+        &format!(
+            "/api/datasets/{dataset_id}/rows?page={}&page_size=100",
+            i64::MAX
+        ),
+    );
+    assert_eq!(status, 400, "page beyond any offset");
+    assert_eq!(route(&service, "/api/unknown").0, 404);
+    assert_eq!(percent_decode("a+b%2Cc%"), "a b,c%");
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn api_connections_cap_request_lines_and_free_their_slot_on_panic() {
+    let mut reader = std::io::Cursor::new(
+        "GET /api/datasets HTTP/1.1\r\nHost: x\r\nAuthorization: Bearer abc\r\n\r\n",
+    );
+    let (request_line, authorization) = read_request_head(&mut reader).expect("head");
+    assert!(request_line.starts_with("GET /api/datasets "));
+    assert_eq!(authorization.as_deref(), Some("Bearer abc"));
+
+    let long_target = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(20_000));
+    assert!(read_request_head(&mut std::io::Cursor::new(long_target)).is_err());
+    let long_header = format!("GET / HTTP/1.1\r\nX: {}\r\n\r\n", "a".repeat(20_000));
+    assert!(read_request_head(&mut std::io::Cursor::new(long_header)).is_err());
+    let many_headers = format!("GET / HTTP/1.1\r\n{}\r\n", "X: 1\r\n".repeat(1_000));
+    assert!(read_request_head(&mut std::io::Cursor::new(many_headers)).is_err());
+
+    let active = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let slots = std::iter::from_fn(|| ConnectionSlot::acquire(&active)).collect::<Vec<_>>();
+    assert_eq!(slots.len(), 16);
+    assert!(ConnectionSlot::acquire(&active).is_none());
+    let slot = slots.into_iter().next().expect("slot");
+    let handler = std::thread::spawn(move || {
+        let _slot = slot;
+        panic!("handler failed");
+    });
+    assert!(handler.join().is_err());
+    assert!(
+        ConnectionSlot::acquire(&active).is_some(),
+        "the panicked slot is free"
+    );
+}
+
+#[test]
+fn api_requests_need_the_launch_token() {
+    let token = new_access_token();
+    assert_eq!(token.len(), 32);
+    assert_ne!(token, new_access_token(), "every launch gets its own token");
+
+    let bearer = format!("Bearer {token}");
+    assert!(is_authorized("/api/datasets", Some(&bearer), &token));
+    assert!(is_authorized(
+        &format!("/api/datasets/1/rows?sort=代號&token={token}"),
+        None,
+        &token
+    ));
+    assert!(!is_authorized("/api/datasets", None, &token));
+    assert!(!is_authorized(
+        "/api/datasets",
+        Some("Bearer wrong"),
+        &token
+    ));
+    assert!(!is_authorized("/api/datasets?token=", None, &token));
+    assert!(
+        !is_authorized("/api/datasets", Some(&token), &token),
+        "needs the Bearer scheme"
+    );
+}
+
+#[test]
+fn report_script_filters_groups_and_aggregates() {
+    let headers = vec![
+        "所有權人".to_string(),
+        "類別".to_string(),
+        "市值".to_string(),
+    ];
+    let rows = vec![
+        vec!["甲".to_string(), "股票".to_string(), "1,000".to_string()],
+        vec!["乙".to_string(), "股票".to_string(), "500".to_string()],
+        vec!["甲".to_string(), "債券".to_string(), "300".to_string()],
+        vec!["甲".to_string(), "股票".to_string(), "200".to_string()],
+    ];
+    let script = "title 股票市值 # comment\nwhere 類別 = 股票\nwhere 市值 >= 300\ngroup 所有權人\ncount as 檔數\nsum 市值\navg 市值\n";
+
+    let report =
+        run_report_script(script, &headers, &rows, &OwnerSplits::new()).expect("script should run");
+    assert_eq!(report.title, "股票市值");
+    assert_eq!(report.group_heading, "依所有權人");
+    let totals = report
+        .totals
+        .iter()
+        .map(|entry| (entry.label.as_str(), entry.value.as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        totals,
+        vec![("檔數", "2"), ("市值", "1500"), ("市值 平均", "750")]
+    );
+    assert_eq!(report.owner_totals.len(), 2);
+    assert_eq!(report.owner_totals[0].owner, "甲");
+    assert_eq!(report.owner_totals[0].entries[1].value, "1000");
+
+    let Err(err) = run_report_script("sum 不存在", &headers, &rows, &OwnerSplits::new()) else {
+        panic!("unknown column should fail");
+    };
+    assert!(err.to_string().contains("第 1 行"));
+}
+
+#[test]
+fn owner_splits_divide_co_owned_rows_in_summaries_and_reports() {
+    let shares = parse_owner_shares("甲 60%\n\n乙 40").expect("parse shares");
+    assert_eq!(
+        shares,
+        vec![
+            OwnerShare {
+                owner: "甲".to_string(),
+                percent: 60.0,
+            },
+            OwnerShare {
+                owner: "乙".to_string(),
+                percent: 40.0,
+            },
+        ]
+    );
+    assert_eq!(format_owner_shares(&shares), "甲 60%\n乙 40%");
+    assert!(parse_owner_shares("甲 60\n乙 30").is_err());
+    assert!(parse_owner_shares("甲 50\n甲 50").is_err());
+    assert!(parse_owner_shares("甲").is_err());
+    assert!(parse_owner_shares("").expect("clearing").is_empty());
+
+    let temp_dir = unique_test_dir("owner_splits");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let services = AppServices::new(db_path);
+    let query = &services.query_service;
+    let headers = ["所有權人", "名稱", "市價", "數量", "估計配息"].map(String::from);
+    let dataset_id = services
+        .edit_service
+        .create_dataset(
+            NewDatasetMeta {
+                name: "持股".to_string(),
+                source_path: "test".to_string(),
+            },
+            TabularData {
+                columns: headers.to_vec(),
+                rows: vec![
+                    ["甲", "台積電", "600", "10", "100"]
+                        .map(String::from)
+                        .to_vec(),
+                    ["甲", "中華電", "120", "1000", "5000"]
+                        .map(String::from)
+                        .to_vec(),
+                    ["乙", "國泰永續", "20", "500", "300"]
+                        .map(String::from)
+                        .to_vec(),
+                ],
+            },
+        )
+        .expect("create dataset");
+    let mut invalid = shares.clone();
+    invalid[1].percent = 50.0;
+    assert!(query.save_owner_shares(dataset_id, 1, invalid).is_err());
+    query
+        .save_owner_shares(dataset_id, 1, shares.clone())
+        .expect("save shares");
+
+    let owner_value = |report: &SummaryReport, owner: &str, label: &str| {
+        report
+            .owner_totals
+            .iter()
+            .find(|summary| summary.owner == owner)
+            .and_then(|summary| summary.entries.iter().find(|entry| entry.label == label))
+            .map(|entry| entry.value.clone())
+    };
+    let report = dataset_summary_report(query, dataset_id, &[]).expect("summary");
+    assert_eq!(owner_value(&report, "甲", "數量").as_deref(), Some("610"));
+    assert_eq!(
+        owner_value(&report, "甲", "估計配息").as_deref(),
+        Some("3100")
+    );
+    assert_eq!(
+        owner_value(&report, "乙", "估計配息").as_deref(),
+        Some("2300")
+    );
+    let total = report.totals.iter().find(|entry| entry.label == "估計配息");
+    assert_eq!(total.map(|entry| entry.value.as_str()), Some("5400"));
+
+    let rows = query
+        .query_page(PageQuery {
+            dataset_id,
+            page: 0,
+            page_size: i64::MAX,
+            global_search: String::new(),
+            column_filter: None,
+            value_filters: Vec::new(),
+            column_conditions: Vec::new(),
+            sort: None,
+        })
+        .expect("rows")
+        .rows;
+    let splits = query.load_owner_splits(dataset_id).expect("load splits");
+    let script = "group 所有權人\ncount as 檔數\nsum 估計配息\nmax 市價 as 最高市價\n";
+    let report = run_report_script(script, &headers, &rows, &splits).expect("script");
+    assert_eq!(owner_value(&report, "乙", "檔數").as_deref(), Some("2"));
+    assert_eq!(
+        owner_value(&report, "乙", "估計配息").as_deref(),
+        Some("2300")
+    );
+    assert_eq!(
+        owner_value(&report, "乙", "最高市價").as_deref(),
+        Some("120")
+    );
+    // Without 所有權人 in the script, a co-owned row is still one row.
+    let report = run_report_script("count", &headers, &rows, &splits).expect("script");
+    assert_eq!(report.totals[0].value, "3");
+
+    // Deleting the row above moves the shares along with their row.
+    services
+        .edit_service
+        .apply_edits(
+            dataset_id,
+            StagedEdits {
+                deleted_rows: BTreeSet::from([0]),
+                ..StagedEdits::default()
+            },
+        )
+        .expect("apply edits");
+    let splits = query.load_owner_splits(dataset_id).expect("load splits");
+    assert_eq!(splits.keys().copied().collect::<Vec<_>>(), vec![0]);
+    let copy_id = services
+        .edit_service
+        .clone_dataset(
+            dataset_id,
+            NewDatasetMeta {
+                name: "持股 副本".to_string(),
+                source_path: "test".to_string(),
+            },
+        )
+        .expect("clone dataset");
+    assert_eq!(query.load_owner_splits(copy_id).expect("copied"), splits);
+    query
+        .save_owner_shares(dataset_id, 0, Vec::new())
+        .expect("clear shares");
+    assert!(query.load_owner_splits(dataset_id).unwrap().is_empty());
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+struct SemicolonImporter;
+
+impl Importer for SemicolonImporter {
+    fn name(&self) -> &str {
+        "Semicolon"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ssv"]
+    }
+
+    fn parse(&self, path: &std::path::Path) -> anyhow::Result<Vec<ParsedDataset>> {
+        let text = fs::read_to_string(path)?;
+        let mut lines = text
+            .lines()
+            .map(|line| line.split(';').map(str::to_string).collect::<Vec<String>>());
+        Ok(vec![ParsedDataset {
+            name: "ssv".to_string(),
+            source_path: path.display().to_string(),
+            headers: lines.next().unwrap_or_default(),
+            rows: lines.collect(),
+            origins: Vec::new(),
+        }])
+    }
+}
+
+#[test]
+fn import_service_dispatches_to_registered_importers() {
+    let temp_dir = unique_test_dir("importer-registry");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    let ssv_path = temp_dir.join("holdings.SSV");
+    let csv_path = temp_dir.join("holdings.csv");
+    fs::write(&ssv_path, "代號;名稱\n0056;高股息\n").expect("should write ssv");
+    fs::write(&csv_path, "代號\n2330\n2317\n").expect("should write csv");
+
+    let mut registry = ImporterRegistry::with_defaults();
+    registry.register(Box::new(SemicolonImporter));
+    let service = ImportService::with_registry(db_path.clone(), registry);
+
+    let imported = service
+        .import(&ssv_path)
+        .expect("ssv import should succeed");
+    assert_eq!(imported[0].row_count, 1);
+    let imported = service
+        .import(&csv_path)
+        .expect("csv import should succeed");
+    assert_eq!(imported[0].row_count, 2);
+    assert!(service.import(&temp_dir.join("notes.txt")).is_err());
+
+    let names = list_datasets(&db_path, false)
+        .expect("should list")
+        .into_iter()
+        .map(|dataset| dataset.name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["holdings".to_string(), "ssv".to_string()]);
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn import_profile_maps_big5_csv_and_is_reapplied() {
+    let temp_dir = unique_test_dir("import-profile");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    let broker_csv = "券商對帳單\n股票代號,股票名稱,庫存股數,備註\n2330,台積電,1000,\n,,,\n0056,元大高股息,5000,零股\n";
+    let (big5, _, _) = encoding_rs::BIG5.encode(broker_csv);
+    let first_path = temp_dir.join("對帳單-5月.csv");
+    fs::write(&first_path, &big5).expect("should write csv");
+
+    let services = AppServices::new(db_path.clone());
+    services.repo.init().expect("init");
+    let profile = ImportProfile {
+        name: "券商對帳單".to_string(),
+        header_row: 1,
+        encoding: "big5".to_string(),
+        columns: vec![
+            ColumnMapping {
+                source: "股票名稱".to_string(),
+                target: "名稱".to_string(),
+            },
+            ColumnMapping {
+                source: "股票代號".to_string(),
+                target: "代號".to_string(),
+            },
+            ColumnMapping {
+                source: "庫存股數".to_string(),
+                target: "數量".to_string(),
+            },
+        ],
+    };
+    assert_eq!(
+        csv_headers(&first_path, "big5", 1).expect("headers"),
+        vec!["股票代號", "股票名稱", "庫存股數", "備註"]
+    );
+    assert!(services
+        .import_service
+        .matching_profile(&first_path)
+        .is_none());
+    services
+        .query_service
+        .save_import_profile(profile.clone())
+        .expect("save profile");
+    assert_eq!(
+        services.query_service.load_import_profiles().expect("load"),
+        vec![profile.clone()]
+    );
+
+    // A later statement with the same headers picks the profile up on a plain import.
+    let second_path = temp_dir.join("對帳單-6月.csv");
+    fs::write(&second_path, &big5).expect("should write csv");
+    assert_eq!(
+        services.import_service.matching_profile(&second_path),
+        Some(profile.clone())
+    );
+    let imported = services
+        .import_service
+        .import(&second_path)
+        .expect("import should succeed");
+    assert_eq!(imported[0].row_count, 2);
+    let (columns, rows, _) = query_page(
+        &db_path,
+        imported[0].dataset_id,
+        0,
+        10,
+        &QueryOptions::default(),
+    )
+    .expect("query should succeed");
+    assert_eq!(columns, vec!["名稱", "代號", "數量"]);
+    assert_eq!(rows[0], vec!["台積電", "2330", "1000"]);
+    assert_eq!(rows[1], vec!["元大高股息", "0056", "5000"]);
+
+    services
+        .query_service
+        .delete_import_profile(profile.name.clone())
+        .expect("delete profile");
+    assert!(services
+        .import_service
+        .matching_profile(&second_path)
+        .is_none());
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn mapping_suggestions_pair_similar_headers_once() {
+    assert_eq!(header_similarity(" 代號 ", "代號"), 1.0);
+    assert_eq!(header_similarity("Ｃｏｄｅ", "code"), 1.0);
+    assert!(header_similarity("代號（股票）", "代號") >= 0.5);
+    assert_eq!(header_similarity("名稱", "代號"), 0.0);
+
+    let headers = [
+        "股票代號",
+        "股票名稱",
+        "庫存股數",
+        "買進價",
+        "市 價",
+        "備註",
+    ]
+    .map(String::from)
+    .to_vec();
+    let targets = builtin_column_set(DatasetKind::Holdings).required;
+    let suggested = suggest_column_mappings(&headers, &targets)
+        .into_iter()
+        .map(|mapping| (mapping.target, mapping.source))
+        .collect::<Vec<_>>();
+    let expected = [
+        ("名稱", "股票名稱"),
+        ("代號", "股票代號"),
+        ("買進", "買進價"),
+        ("市價", "市 價"),
+    ]
+    .map(|(target, source)| (target.to_string(), source.to_string()))
+    .to_vec();
+    assert_eq!(suggested, expected);
+
+    // A header already taken by a closer match is not offered again.
+    let suggested = suggest_column_mappings(
+        &["代號".to_string()],
+        &["代號".to_string(), "股票代號".to_string()],
+    );
+    assert_eq!(suggested.len(), 1);
+    assert_eq!(suggested[0].target, "代號");
+}
+
+#[test]
+fn typed_errors_map_to_user_messages() {
+    let temp_dir = unique_test_dir("typed-errors");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let not_a_db = temp_dir.join("broken.sqlite");
+    fs::write(
+        &not_a_db,
+        "this is not sqlite, just text padding the header out",
+    )
+    .expect("write");
+    let repo = SqliteRepo { db_path: not_a_db };
+    let err = repo.list_datasets(false).expect_err("should fail");
+    assert!(matches!(err, RepoError::SchemaMismatch(_)), "{err:?}");
+    assert_eq!(
+        anyhow::Error::from(err).chain_messages()[0],
+        "資料庫格式不相容，請先備份後再更新程式"
+    );
+
+    let service = ImportService::new(temp_dir.join("app.sqlite"));
+    let missing = service
+        .import(&temp_dir.join("missing.csv"))
+        .expect_err("missing file");
+    assert!(matches!(missing, ImportError::FileNotFound(_)));
+    let notes = temp_dir.join("notes.txt");
+    fs::write(&notes, "hello").expect("write notes");
+    let unsupported = service.import(&notes).expect_err("unsupported");
+    assert_eq!(
+        anyhow::Error::from(unsupported).chain_messages()[0],
+        "不支援的檔案格式：notes.txt"
+    );
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn services_publish_data_change_events() {
+    let temp_dir = unique_test_dir("domain-events");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    let csv_path = temp_dir.join("holdings.csv");
+    fs::write(&csv_path, "代號\n2330\n").expect("should write csv");
+
+    let services = AppServices::new(db_path.clone());
+    services.repo.init().expect("init");
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    let subscription = services
+        .events
+        .subscribe(move |event| sink.lock().unwrap().push(event.clone()));
+
+    let imported = services
+        .import_service
+        .import(&csv_path)
+        .expect("import should succeed");
+    let dataset_id = DatasetId(imported[0].dataset_id);
+    services
+        .edit_service
+        .apply_edits(dataset_id, StagedEdits::default())
+        .expect("apply edits");
+    services.events.unsubscribe(subscription);
+    services
+        .edit_service
+        .hard_delete_dataset(dataset_id)
+        .expect("delete");
+
+    assert_eq!(
+        *received.lock().unwrap(),
+        vec![
+            DomainEvent::DatasetImported {
+                dataset_ids: vec![dataset_id],
+                source_path: csv_path.clone(),
+            },
+            DomainEvent::EditsApplied { dataset_id },
+        ]
+    );
+    // The built-in subscriber records the import in 最近匯入.
+    let recent = load_setting(&db_path, RECENT_IMPORTS_KEY).expect("load setting");
+    assert_eq!(recent, Some(csv_path.to_string_lossy().into_owned()));
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn stored_prices_value_holdings_as_of_earlier_dates() {
+    let temp_dir = unique_test_dir("price-history");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let services = AppServices::new(temp_dir.join("app.sqlite"));
+    services.repo.init().expect("init");
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let sink = received.clone();
+    services
+        .events
+        .subscribe(move |event| sink.lock().unwrap().push(event.clone()));
+
+    let point = |code: &str, price: f64, fetched_at: &str| PricePoint {
+        code: code.to_string(),
+        price,
+        fetched_at: fetched_at.to_string(),
+    };
+    let dataset_id = DatasetId(1);
+    services
+        .edit_service
+        .record_prices(
+            dataset_id,
+            vec![
+                point("2330", 600.0, "2026-01-05 13:30:00"),
+                point("0050", 150.0, "2026-01-05 13:30:00"),
+                point("2330", 610.0, "2026-01-06 13:30:00"),
+            ],
+        )
+        .expect("record prices");
+    // A second fetch at the same time replaces the first.
+    services
+        .edit_service
+        .record_prices(
+            dataset_id,
+            vec![point("2330", 620.0, "2026-01-06 13:30:00")],
+        )
+        .expect("record again");
+    assert_eq!(
+        *received.lock().unwrap(),
+        vec![DomainEvent::PricesUpdated { dataset_id }; 2]
+    );
+
+    let history = services
+        .query_service
+        .load_price_history(vec!["2330".into(), "0050".into(), "9999".into()])
+        .expect("load history");
+    assert_eq!(history.keys().collect::<Vec<_>>(), ["0050", "2330"]);
+    let prices = &history["2330"];
+    assert_eq!(
+        prices.iter().map(|point| point.price).collect::<Vec<_>>(),
+        [600.0, 620.0]
+    );
+    assert_eq!(price_at(prices, "2026-01-04"), None);
+    assert_eq!(price_at(prices, "2026-01-05"), Some(600.0));
+    assert_eq!(price_at(prices, "2026-01-06 09:00:00"), Some(600.0));
+    assert_eq!(price_at(prices, "2026-02-01"), Some(620.0));
+
+    let holdings = [("2330".to_string(), 10.0), ("0050".to_string(), 2.0)];
+    assert_eq!(
+        market_value_at(&holdings, &history, "2026-01-06"),
+        Some(6500.0)
+    );
+    assert_eq!(market_value_at(&holdings, &history, "2026-01-04"), None);
+
+    let headers = ["名稱", "代號"].map(str::to_string);
+    let rows = [
+        ["台積電", "2330"],
+        ["元大50", " 0050 "],
+        ["台積電", "2330"],
+        ["現金", ""],
+    ]
+    .map(|row| row.map(str::to_string).to_vec());
+    assert_eq!(row_codes(&headers, &rows), ["2330", "0050"]);
+    assert!(row_codes(&headers[..1], &rows).is_empty());
+
+    services.read_only.set(true);
+    assert!(matches!(
+        services.edit_service.record_prices(dataset_id, Vec::new()),
+        Err(RepoError::ReadOnly)
+    ));
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn saved_market_prices_go_into_the_price_history() {
+    let temp_dir = unique_test_dir("saved-prices");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let services = AppServices::new(db_path);
+    let columns = ["代號", "名稱", "市價"].map(String::from).to_vec();
+    let dataset_id = services
+        .edit_service
+        .create_dataset(
+            NewDatasetMeta {
+                name: "持股".to_string(),
+                source_path: "帳本.xlsx#持股".to_string(),
+            },
+            TabularData {
+                columns: columns.clone(),
+                rows: vec![
+                    ["2330", "台積電", "600"].map(String::from).to_vec(),
+                    ["", "合計", ""].map(String::from).to_vec(),
+                ],
+            },
+        )
+        .expect("create dataset");
+    let save = |cells: &[(usize, usize, &str)], added_rows: Vec<Vec<String>>| {
+        let staged_cells = cells
+            .iter()
+            .map(|&(row_idx, col_idx, value)| {
+                let key = CellKey {
+                    row_idx,
+                    col_idx,
+                    column: columns[col_idx].clone(),
+                };
+                (key, value.to_string())
+            })
+            .collect();
+        services
+            .edit_service
+            .apply_edits(
+                dataset_id,
+                StagedEdits {
+                    staged_cells,
+                    added_rows,
+                    ..StagedEdits::default()
+                },
+            )
+            .expect("apply edits");
+    };
+    let history = || {
+        services
+            .query_service
+            .load_price_history(vec!["2330".into(), "0050".into()])
+            .expect("load history")
+            .into_iter()
+            .map(|(code, points)| {
+                let prices = points.iter().map(|point| point.price).collect::<Vec<_>>();
+                (code, prices)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    save(&[(0, 1, "台積電股份")], Vec::new());
+    assert!(history().is_empty());
+    save(
+        &[(0, 2, "615.5"), (1, 2, "999")],
+        vec![["0050", "元大台灣50", "150"].map(String::from).to_vec()],
+    );
+    assert_eq!(
+        history(),
+        [
+            ("0050".to_string(), vec![150.0]),
+            ("2330".to_string(), vec![615.5]),
+        ]
+    );
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn second_launch_hands_off_to_running_instance() {
+    let temp_dir = unique_test_dir("single-instance");
+    let lock_path = temp_dir.join("instance.lock");
+
+    // A lock left behind by a dead instance is taken over.
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    fs::write(&lock_path, "1").expect("write stale lock");
+    let InstanceRole::Primary(listener) =
+        acquire_instance(&lock_path, &Handoff::Focus).expect("acquire")
+    else {
+        panic!("first launch should become primary");
+    };
+    let (tx, rx) = std::sync::mpsc::channel();
+    listener.serve(move |handoff| {
+        let _ = tx.send(handoff);
+    });
+
+    let request = Handoff::Open(PathBuf::from("C:/資料/持股.xlsx"));
+    let role = acquire_instance(&lock_path, &request).expect("hand off");
+    assert!(matches!(role, InstanceRole::Secondary));
+    let received = rx
+        .recv_timeout(std::time::Duration::from_secs(2))
+        .expect("handoff delivered");
+    assert_eq!(received, request);
+
+    assert_eq!(
+        Handoff::parse(&Handoff::Focus.to_line()),
+        Some(Handoff::Focus)
+    );
+    assert_eq!(Handoff::parse("GET / HTTP/1.1"), None);
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn lone_importable_file_argument_opens_gui() {
+    let temp_dir = unique_test_dir("open-with");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let csv_path = temp_dir.join("holdings.csv");
+    fs::write(&csv_path, "a,b\n1,2\n").expect("write csv");
+    let arg = |path: &std::path::Path| vec![path.to_string_lossy().into_owned()];
+
+    assert_eq!(
+        parse_gui_args(&arg(&csv_path)),
+        Some(Handoff::Open(csv_path.clone()))
+    );
+    assert_eq!(parse_gui_args(&arg(&temp_dir.join("missing.csv"))), None);
+    let text_path = temp_dir.join("notes.txt");
+    fs::write(&text_path, "x").expect("write txt");
+    assert_eq!(parse_gui_args(&arg(&text_path)), None);
+    assert_eq!(parse_gui_args(&["list".to_string()]), None);
+    assert_eq!(parse_gui_args(&[]), Some(Handoff::Focus));
+    assert_eq!(
+        parse_gui_args(&["--read-only".to_string()]),
+        Some(Handoff::ReadOnly)
+    );
+    assert_eq!(
+        parse_gui_args(&[
+            "import".to_string(),
+            csv_path.to_string_lossy().into_owned()
+        ]),
+        None
+    );
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn app_lock_passphrase_hashes_verify_and_idle_setting_parses() {
+    let hash = hash_passphrase("全家的帳").expect("hash");
+    assert!(hash.starts_with("$argon2id$v=19$"));
+    assert_ne!(
+        hash,
+        hash_passphrase("全家的帳").expect("hash"),
+        "salt is random"
+    );
+    assert!(verify_passphrase("全家的帳", &hash));
+    assert!(!verify_passphrase("全家的賬", &hash));
+    assert!(!verify_passphrase("全家的帳", ""));
+    assert!(!verify_passphrase(
+        "全家的帳",
+        "$argon2id$v=19$m=19456,t=2,p=1$abc$def"
+    ));
+    assert!(!verify_passphrase(
+        "全家的帳",
+        "pbkdf2-sha256$600000$c2FsdA$aGFzaA"
+    ));
+
+    assert_eq!(parse_lock_idle_minutes(None), DEFAULT_APP_LOCK_IDLE_MINUTES);
+    assert_eq!(parse_lock_idle_minutes(Some(" 0 ")), 0);
+    assert_eq!(parse_lock_idle_minutes(Some("30")), 30);
+    assert_eq!(
+        parse_lock_idle_minutes(Some("soon")),
+        DEFAULT_APP_LOCK_IDLE_MINUTES
+    );
+}
+
+#[test]
+fn read_only_mode_refuses_changes_at_the_service_layer() {
+    let temp_dir = unique_test_dir("read-only");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    init_db(&db_path).expect("init db");
+    let csv_path = temp_dir.join("holdings.csv");
+    fs::write(&csv_path, "代號,股數\n2330,10\n").expect("write csv");
+    let services = AppServices::new(db_path.clone());
+    let imported = services
+        .import_service
+        .import(&csv_path)
+        .expect("import before read-only");
+    let dataset_id = DatasetId(imported[0].dataset_id);
+
+    services.read_only.set(true);
+    assert!(matches!(
+        services.import_service.import(&csv_path),
+        Err(ImportError::ReadOnly)
+    ));
+    assert!(matches!(
+        services
+            .edit_service
+            .apply_edits(dataset_id, StagedEdits::default()),
+        Err(RepoError::ReadOnly)
+    ));
+    assert!(matches!(
+        services.edit_service.hard_delete_dataset(dataset_id),
+        Err(RepoError::ReadOnly)
+    ));
+    assert!(matches!(
+        services
+            .query_service
+            .rename_dataset(dataset_id, "改名".to_string()),
+        Err(RepoError::ReadOnly)
+    ));
+    // Every other change through the query service is refused too, view preferences and
+    // settings included.
+    let query = &services.query_service;
+    let refused = [
+        (
+            "upsert_column_visibility",
+            query.upsert_column_visibility(dataset_id, BTreeMap::from([(0, false)])),
+        ),
+        (
+            "save_visibility_preset",
+            query.save_visibility_preset(dataset_id, "精簡".to_string(), BTreeMap::new()),
+        ),
+        (
+            "delete_visibility_preset",
+            query.delete_visibility_preset(dataset_id, "精簡".to_string()),
+        ),
+        (
+            "upsert_column_aliases",
+            query.upsert_column_aliases(dataset_id, BTreeMap::new()),
+        ),
+        (
+            "upsert_column_formats",
+            query.upsert_column_formats(dataset_id, BTreeMap::new()),
+        ),
+        (
+            "save_dataset_display",
+            query.save_dataset_display(dataset_id, DatasetDisplay::default()),
+        ),
+        ("save_page_size", query.save_page_size(dataset_id, Some(50))),
+        (
+            "upsert_column_types",
+            query.upsert_column_types(dataset_id, BTreeMap::new()),
+        ),
+        (
+            "upsert_holdings_flag",
+            query.upsert_holdings_flag(dataset_id, true),
+        ),
+        (
+            "set_dataset_kind",
+            query.set_dataset_kind(dataset_id, DatasetKind::Holdings),
+        ),
+        ("save_setting", query.save_setting("theme", "dark")),
+        (
+            "save_import_profile",
+            query.save_import_profile(ImportProfile {
+                name: "券商".to_string(),
+                header_row: 0,
+                encoding: "utf-8".to_string(),
+                columns: Vec::new(),
+            }),
+        ),
+        (
+            "delete_import_profile",
+            query.delete_import_profile("券商".to_string()),
+        ),
+        (
+            "save_column_set",
+            query.save_column_set(DatasetKind::Holdings, ColumnSet::default()),
+        ),
+        (
+            "reset_column_set",
+            query.reset_column_set(DatasetKind::Holdings),
+        ),
+        (
+            "save_saved_report",
+            query.save_saved_report(SavedReport {
+                name: "配息".to_string(),
+                script: "sum 估計配息".to_string(),
+            }),
+        ),
+        (
+            "delete_saved_report",
+            query.delete_saved_report("配息".to_string()),
+        ),
+        ("save_deposit_rates", query.save_deposit_rates(Vec::new())),
+        (
+            "acknowledge_alerts",
+            query.acknowledge_alerts(vec!["1:2330".to_string()]),
+        ),
+        (
+            "save_computed_columns",
+            query.save_computed_columns(dataset_id, Vec::new()),
+        ),
+        (
+            "save_owner_shares",
+            query.save_owner_shares(dataset_id, 0, Vec::new()),
+        ),
+        (
+            "save_summary_report",
+            query
+                .save_summary_report(dataset_id, &SummaryReport::default())
+                .map(|_| ()),
+        ),
+    ];
+    for (method, result) in refused {
+        assert!(matches!(result, Err(RepoError::ReadOnly)), "{method}");
+    }
+    // Reading keeps working.
+    assert_eq!(query.list_datasets(false).unwrap().len(), 1);
+    assert_eq!(query.load_setting("theme").unwrap(), None);
+
+    services.read_only.set(false);
+    services
+        .query_service
+        .rename_dataset(dataset_id, "改名".to_string())
+        .expect("rename after leaving read-only");
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn preferences_round_trip_by_dataset_and_column_name() {
+    let temp_dir = unique_test_dir("preferences");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let source_db = temp_dir.join("source.sqlite");
+    let target_db = temp_dir.join("target.sqlite");
+    let columns = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>()
+    };
+
+    let source_id = create_dataset_from_rows(
+        &source_db,
+        "持股",
+        "a.xlsx#持股",
+        &columns(&["代號", "股數", "市值"]),
+        &[vec!["2330".into(), "10".into(), "6000".into()]],
+    )
+    .expect("source dataset");
+    let source = AppServices::new(source_db.clone());
+    let query = &source.query_service;
+    let portable = [
+        (THEME_KEY, "dark"),
+        (SNAPSHOT_SCHEDULE_KEY, "weekly"),
+        (ALERT_LOSS_THRESHOLD_KEY, "0.15"),
+        (TRASH_RETENTION_KEY, "0"),
+    ];
+    for (key, value) in portable {
+        query.save_setting(key, value).expect("portable setting");
+    }
+    query
+        .save_setting(RECENT_IMPORTS_KEY, "C:/a.xlsx")
+        .expect("recent");
+    query
+        .upsert_column_visibility(DatasetId(source_id), BTreeMap::from([(1, false)]))
+        .expect("visibility");
+    query
+        .upsert_column_aliases(
+            DatasetId(source_id),
+            BTreeMap::from([(2, "Value".to_string())]),
+        )
+        .expect("aliases");
+    let format = ColumnFormat {
+        decimals: 0,
+        percent: false,
+        thousands_separator: true,
+        currency_symbol: "NT$".to_string(),
+    };
+    query
+        .upsert_column_formats(DatasetId(source_id), BTreeMap::from([(2, format.clone())]))
+        .expect("formats");
+    query
+        .save_visibility_preset(
+            DatasetId(source_id),
+            "精簡".to_string(),
+            BTreeMap::from([(0, true), (1, false)]),
+        )
+        .expect("preset");
+    let document = export_preferences(query).expect("export");
+    assert!(document["settings"].get(RECENT_IMPORTS_KEY).is_none());
+
+    // Same sheet on another machine, columns in a different order.
+    let target_id = create_dataset_from_rows(
+        &target_db,
+        "持股",
+        "b.xlsx#持股",
+        &columns(&["市值", "代號", "股數"]),
+        &[vec!["6000".into(), "2330".into(), "10".into()]],
+    )
+    .expect("target dataset");
+    let target = AppServices::new(target_db.clone());
+    let text = serde_json::to_string(&document).expect("serialize");
+    let summary = import_preferences(
+        &target.query_service,
+        &serde_json::from_str(&text).expect("parse"),
+    )
+    .expect("import");
+    assert_eq!(summary.settings, portable.len());
+    assert_eq!(summary.datasets, 1);
+
+    let query = &target.query_service;
+    let target_id = DatasetId(target_id);
+    for (key, value) in portable {
+        assert_eq!(query.load_setting(key).unwrap().as_deref(), Some(value));
+    }
+    assert_eq!(query.load_setting(RECENT_IMPORTS_KEY).unwrap(), None);
+    assert_eq!(
+        query.load_column_visibility(target_id).unwrap().get(&2),
+        Some(&false)
+    );
+    assert_eq!(
+        query.load_column_aliases(target_id).unwrap().get(&0),
+        Some(&"Value".to_string())
+    );
+    assert_eq!(
+        query.load_column_formats(target_id).unwrap().get(&0),
+        Some(&format)
+    );
+    assert_eq!(
+        query.load_visibility_presets(target_id).unwrap()["精簡"],
+        BTreeMap::from([(1, true), (2, false)])
+    );
+
+    let err = import_preferences(query, &serde_json::json!({ "format": "other" }))
+        .expect_err("foreign file");
+    assert!(err.to_string().contains("不是 BOM 設定檔"));
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn preferences_carry_every_portable_setting_and_saved_definition() {
+    let temp_dir = unique_test_dir("preferences-all");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let source_db = temp_dir.join("source.sqlite");
+    let target_db = temp_dir.join("target.sqlite");
+    init_db(&source_db).expect("init source");
+    init_db(&target_db).expect("init target");
+    let source = AppServices::new(source_db);
+    let query = &source.query_service;
+    for key in PORTABLE_SETTING_KEYS {
+        query
+            .save_setting(key, &format!("{key} 的值"))
+            .expect("portable setting");
+    }
+    let report = SavedReport {
+        name: "配息".to_string(),
+        script: "group 所有權人\nsum 估計配息\n".to_string(),
+    };
+    query.save_saved_report(report.clone()).expect("report");
+    let profile = ImportProfile {
+        name: "券商".to_string(),
+        header_row: 1,
+        encoding: "big5".to_string(),
+        columns: vec![ColumnMapping {
+            source: "股票代號".to_string(),
+            target: "代號".to_string(),
+        }],
+    };
+    query.save_import_profile(profile.clone()).expect("profile");
+    let rates = parse_deposit_rates("臺灣銀行 1年 1.6%\n合作金庫 1.2%").expect("parse rates");
+    query.save_deposit_rates(rates.clone()).expect("rates");
+    let document = export_preferences(query).expect("export");
+
+    let target = AppServices::new(target_db);
+    let text = serde_json::to_string(&document).expect("serialize");
+    let summary = import_preferences(
+        &target.query_service,
+        &serde_json::from_str(&text).expect("parse"),
+    )
+    .expect("import");
+    assert_eq!(summary.settings, PORTABLE_SETTING_KEYS.len() + 3);
+
+    let query = &target.query_service;
+    for key in PORTABLE_SETTING_KEYS {
+        let value = query.load_setting(key).expect("load setting");
+        assert_eq!(value, Some(format!("{key} 的值")), "{key}");
+    }
+    assert_eq!(query.load_saved_reports().expect("reports"), vec![report]);
+    assert_eq!(
+        query.load_import_profiles().expect("profiles"),
+        vec![profile]
+    );
+    assert_eq!(query.load_deposit_rates().expect("rates"), rates);
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn dataset_bundle_carries_rows_and_settings_to_another_database() {
+    let temp_dir = unique_test_dir("bundle");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let bundle_path = temp_dir.join("持股.bomdata");
+    let columns = ["代號", "股數", "市值"].map(str::to_string).to_vec();
+    let rows = vec![
+        vec!["2330".to_string(), "10".to_string(), "6000".to_string()],
+        vec!["0050".to_string(), "5".to_string(), "".to_string()],
+    ];
+    let source_id = DatasetId(
+        create_dataset_from_rows(
+            &temp_dir.join("source.sqlite"),
+            "我的持股",
+            "a.xlsx#持股",
+            &columns,
+            &rows,
+        )
+        .expect("source dataset"),
+    );
+    let source = AppServices::new(temp_dir.join("source.sqlite"));
+    let query = &source.query_service;
+    query
+        .set_dataset_kind(source_id, DatasetKind::Holdings)
+        .expect("kind");
+    query
+        .upsert_column_types(source_id, BTreeMap::from([(0, ColumnType::Text)]))
+        .expect("types");
+    query
+        .upsert_column_visibility(source_id, BTreeMap::from([(1, false)]))
+        .expect("visibility");
+    query
+        .upsert_column_aliases(source_id, BTreeMap::from([(2, "Value".to_string())]))
+        .expect("aliases");
+    query
+        .save_visibility_preset(source_id, "精簡".to_string(), BTreeMap::from([(2, false)]))
+        .expect("preset");
+    let display = DatasetDisplay {
+        currency: "USD".to_string(),
+        unit: DisplayUnit::Thousand,
+    };
+    query
+        .save_dataset_display(source_id, display.clone())
+        .expect("display");
+    query
+        .save_page_size(source_id, Some(50))
+        .expect("page size");
+    let computed = vec![ComputedColumn {
+        name: "兩倍".to_string(),
+        expression: "市值 * 2".to_string(),
+    }];
+    query
+        .save_computed_columns(source_id, computed.clone())
+        .expect("computed columns");
+    let shares = vec![
+        OwnerShare {
+            owner: "甲".to_string(),
+            percent: 60.0,
+        },
+        OwnerShare {
+            owner: "乙".to_string(),
+            percent: 40.0,
+        },
+    ];
+    query
+        .save_owner_shares(source_id, 0, shares.clone())
+        .expect("owner shares");
+    export_bundle(query, source_id, &bundle_path).expect("export");
+
+    let target = AppServices::new(temp_dir.join("target.sqlite"));
+    let summary =
+        import_bundle(&target.query_service, &target.edit_service, &bundle_path).expect("import");
+    assert_eq!(summary.name, "我的持股");
+    assert_eq!(summary.row_count, 2);
+
+    let query = &target.query_service;
+    let target_id = summary.dataset_id;
+    let meta = query.list_datasets(false).expect("list");
+    assert_eq!(meta.len(), 1);
+    assert_eq!(meta[0].kind, Some(DatasetKind::Holdings));
+    assert_eq!(meta[0].source_path, bundle_path.to_string_lossy());
+    let data = query.load_tabular_data(target_id).expect("data");
+    assert_eq!(data.columns, columns);
+    assert_eq!(data.rows, rows);
+    assert_eq!(
+        query.load_column_types(target_id).unwrap().get(&0),
+        Some(&ColumnType::Text)
+    );
+    assert_eq!(
+        query.load_column_visibility(target_id).unwrap().get(&1),
+        Some(&false)
+    );
+    assert_eq!(
+        query.load_column_aliases(target_id).unwrap().get(&2),
+        Some(&"Value".to_string())
+    );
+    assert_eq!(
+        query.load_visibility_presets(target_id).unwrap()["精簡"],
+        BTreeMap::from([(2, false)])
+    );
+    assert_eq!(query.load_dataset_display(target_id).unwrap(), display);
+    assert_eq!(query.load_page_size(target_id).unwrap(), Some(50));
+    assert_eq!(query.load_computed_columns(target_id).unwrap(), computed);
+    assert_eq!(query.load_owner_splits(target_id).unwrap()[&0], shares);
+
+    let foreign = temp_dir.join("other.bomdata");
+    fs::write(&foreign, b"not a zip").expect("write foreign file");
+    let err = import_bundle(&target.query_service, &target.edit_service, &foreign)
+        .expect_err("foreign file");
+    assert!(err.to_string().contains("不是 BOM 資料集封裝"));
+    assert_eq!(query.list_datasets(false).unwrap().len(), 1);
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+/// Head and body of one request seen by [`fake_http_server`].
+type RecordedRequest = (String, Vec<u8>);
+
+/// Accepts one connection per entry in `statuses`, answering with that status, and hands
+/// back each request's head and body.
+fn fake_http_server(statuses: Vec<u16>) -> (String, std::thread::JoinHandle<Vec<RecordedRequest>>) {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    let handle = std::thread::spawn(move || {
+        let mut requests = Vec::new();
+        for status in statuses {
+            let (stream, _) = listener.accept().expect("accept");
+            let mut reader = BufReader::new(stream.try_clone().expect("clone"));
+            let mut head = String::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).expect("read head");
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    content_length = value.trim().parse().expect("length");
+                }
+                if line.trim().is_empty() {
+                    break;
+                }
+                head.push_str(&line);
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).expect("read body");
+            let reply = format!("HTTP/1.1 {status} X\r\nContent-Length: 6\r\n\r\ndenied");
+            (&stream).write_all(reply.as_bytes()).expect("reply");
+            requests.push((head, body));
+        }
+        requests
+    });
+    (format!("http://{addr}"), handle)
+}
+
+#[test]
+fn remote_backup_uploads_to_webdav_and_s3_and_records_status() {
+    let temp_dir = unique_test_dir("remote_backup");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let backup_path = temp_dir.join("bom-backup-1.sqlite");
+    fs::write(&backup_path, b"sqlite bytes").expect("backup file");
+    let services = AppServices::new(db_path);
+    let query = &services.query_service;
+    assert!(!load_remote_backup_config(query).unwrap().is_configured());
+
+    let (base_url, server) = fake_http_server(vec![201, 200, 403]);
+    let webdav = RemoteBackupConfig {
+        kind: RemoteBackupKind::WebDav,
+        url: format!("{base_url}/dav/bom/"),
+        username: "me".to_string(),
+        secret: "pw".to_string(),
+        region: String::new(),
+    };
+    save_remote_backup_config(query, &webdav).expect("save");
+    assert_eq!(load_remote_backup_config(query).unwrap(), webdav);
+    let url = upload_and_record(query, &webdav, &backup_path).expect("webdav upload");
+    assert_eq!(url, format!("{base_url}/dav/bom/bom-backup-1.sqlite"));
+    assert!(load_remote_backup_status(query)
+        .unwrap()
+        .unwrap()
+        .contains("上傳成功"));
+
+    let s3 = RemoteBackupConfig {
+        kind: RemoteBackupKind::S3,
+        url: format!("{base_url}/bucket"),
+        username: "AKID".to_string(),
+        secret: "secret".to_string(),
+        region: "ap-northeast-1".to_string(),
+    };
+    upload_and_record(query, &s3, &backup_path).expect("s3 upload");
+    let err = upload_and_record(query, &webdav, &backup_path).expect_err("rejected");
+    assert!(err.to_string().contains("403"));
+    let status = load_remote_backup_status(query).unwrap().unwrap();
+    assert!(status.contains("上傳失敗") && status.contains("denied"));
+    // Credentials only leave this machine over TLS.
+    let remote = RemoteBackupConfig {
+        url: "http://backup.example.com/dav".to_string(),
+        ..webdav
+    };
+    let err = upload_and_record(query, &remote, &backup_path).expect_err("plain http");
+    assert!(err.to_string().contains("https://"));
+
+    let requests = server.join().expect("server");
+    let (head, body) = &requests[0];
+    assert!(head.starts_with("PUT /dav/bom/bom-backup-1.sqlite HTTP/1.1"));
+    assert!(head.contains("Authorization: Basic bWU6cHc="));
+    assert_eq!(body, b"sqlite bytes");
+    let (head, _) = &requests[1];
+    assert!(head.starts_with("PUT /bucket/bom-backup-1.sqlite HTTP/1.1"));
+    assert!(head.contains("Credential=AKID/"));
+    assert!(head.contains("/ap-northeast-1/s3/aws4_request"));
+    assert!(head.contains("SignedHeaders=host;x-amz-content-sha256;x-amz-date"));
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn sync_folder_database_gets_writer_lock_and_conflict_warnings() {
+    let temp_dir = unique_test_dir("sync_folder");
+    let local_db = temp_dir.join("local").join("datasets.sqlite");
+    assert_eq!(detect_sync_folder(&local_db), None);
+
+    let synced_dir = temp_dir.join("OneDrive - Contoso").join("bom");
+    fs::create_dir_all(&synced_dir).expect("should create sync dir");
+    let db_path = synced_dir.join("datasets.sqlite");
+    init_db(&db_path).expect("init db");
+    assert_eq!(detect_sync_folder(&db_path), Some(SyncProvider::OneDrive));
+
+    for name in [
+        "datasets (Ann's conflicted copy 2024-05-01).sqlite",
+        "datasets-LAPTOP.sqlite",
+        "datasets (1).sqlite",
+        "datasets.sqlite-journal",
+        "bom-backup-20240501.sqlite",
+        "datasets.csv",
+    ] {
+        fs::write(synced_dir.join(name), b"").expect("sibling file");
+    }
+    let copies = find_conflicted_copies(&db_path)
+        .iter()
+        .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        copies,
+        vec![
+            "datasets (1).sqlite",
+            "datasets (Ann's conflicted copy 2024-05-01).sqlite",
+            "datasets-LAPTOP.sqlite",
+        ]
+    );
+
+    let WriterLockOutcome::Acquired(lock) = acquire_writer_lock(&db_path, "DESKTOP").unwrap()
+    else {
+        panic!("first machine should get the lock");
+    };
+    match acquire_writer_lock(&db_path, "LAPTOP").unwrap() {
+        WriterLockOutcome::HeldBy(holder) => assert_eq!(holder, "DESKTOP"),
+        WriterLockOutcome::Acquired(_) => panic!("lock is held by another machine"),
+    }
+    // A lock the same machine left behind (e.g. after a crash) is simply taken over.
+    let WriterLockOutcome::Acquired(again) = acquire_writer_lock(&db_path, "DESKTOP").unwrap()
+    else {
+        panic!("own lock should be taken over");
+    };
+    drop(again);
+    assert!(!writer_lock_path(&db_path).exists());
+    drop(lock);
+
+    // A lock whose heartbeat stopped long ago no longer blocks anyone.
+    fs::write(writer_lock_path(&db_path), "LAPTOP\n1000\n").expect("stale lock");
+    assert!(matches!(
+        acquire_writer_lock(&db_path, "DESKTOP").unwrap(),
+        WriterLockOutcome::Acquired(_)
+    ));
+    assert!(!writer_lock_path(&db_path).exists());
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn demo_data_creates_workbook_shaped_datasets() {
+    let temp_dir = unique_test_dir("demo_data");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let services = AppServices::new(db_path);
+
+    let ids = create_demo_datasets(&services.edit_service).expect("demo datasets");
+    assert_eq!(ids.len(), 2);
+    let datasets = services.query_service.list_datasets(false).unwrap();
+    let groups = build_dataset_groups(&datasets);
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].label, DEMO_SOURCE);
+    assert_eq!(choose_default_dataset_id(&datasets), Some(ids[0].0));
+
+    let load = |id: DatasetId| {
+        let options = QueryOptions::default();
+        reload_page_data_usecase(&services.query_service, Some(id.0), 0, &options).unwrap()
+    };
+    let (holdings_headers, holdings_rows, _, _) = load(ids[1]);
+    assert!(is_holdings_table(
+        &holdings_headers,
+        &builtin_column_set(DatasetKind::Holdings)
+    ));
+    assert_eq!(holdings_rows.len(), 6);
+    let holdings_report = compute_summary_report(&holdings_headers, &holdings_rows);
+    let net = holdings_report
+        .totals
+        .iter()
+        .find(|entry| entry.label == "淨值")
+        .expect("net value total");
+    assert!(net.value.parse::<f64>().unwrap() > 0.0);
+
+    // The 股票投資 line of 資產總表 carries the holdings' net value, as after an import.
+    let (assets_headers, assets_rows, _, _) = load(ids[0]);
+    let balance_idx = assets_headers.iter().position(|h| h == "餘額").unwrap();
+    let investment = assets_rows.iter().find(|row| row[0] == "股票投資").unwrap();
+    assert_eq!(
+        investment[balance_idx].replace(',', ""),
+        net.value.replace(',', "")
+    );
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn holdings_edits_update_investment_balance_of_assets_sheet() {
+    let temp_dir = unique_test_dir("recalc_assets");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let services = AppServices::new(db_path);
+    let ids = create_demo_datasets(&services.edit_service).expect("demo datasets");
+    let load = |id: DatasetId| {
+        let options = QueryOptions::default();
+        reload_page_data_usecase(&services.query_service, Some(id.0), 0, &options).unwrap()
+    };
+
+    let (holdings_headers, holdings_rows, _, _) = load(ids[1]);
+    let net_idx = holdings_headers.iter().position(|h| h == "淨值").unwrap();
+    let old_net = parse_numeric_value(&holdings_rows[0][net_idx]).unwrap();
+    let staged_cells = HashMap::from([(
+        CellKey {
+            row_idx: 0,
+            col_idx: net_idx,
+            column: "淨值".to_string(),
+        },
+        format_f64(old_net + 1000.0),
+    )]);
+    services
+        .edit_service
+        .apply_edits(
+            ids[1],
+            StagedEdits {
+                staged_cells,
+                ..StagedEdits::default()
+            },
+        )
+        .expect("apply edits");
+
+    let (holdings_headers, holdings_rows, _, _) = load(ids[1]);
+    let total_net = holdings_total_net(&holdings_headers, &holdings_rows).unwrap();
+    let (assets_headers, assets_rows, _, _) = load(ids[0]);
+    let balance_idx = assets_headers.iter().position(|h| h == "餘額").unwrap();
+    let investment = assets_rows.iter().find(|row| row[0] == "股票投資").unwrap();
+    assert_eq!(investment[balance_idx], format_f64(total_net));
+    // Other lines keep their typed amounts.
+    let savings = assets_rows.iter().find(|row| row[0] == "活存").unwrap();
+    assert_eq!(savings[balance_idx], "286000");
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn save_as_copies_take_no_part_in_the_investment_balance() {
+    let temp_dir = unique_test_dir("recalc_copies");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let services = AppServices::new(db_path);
+    let ids = create_demo_datasets(&services.edit_service).expect("demo datasets");
+    let load = |id: DatasetId| {
+        let options = QueryOptions::default();
+        reload_page_data_usecase(&services.query_service, Some(id.0), 0, &options).unwrap()
+    };
+    let save_as = |id: DatasetId, name: &str| {
+        let meta = services
+            .query_service
+            .list_datasets(false)
+            .unwrap()
+            .into_iter()
+            .find(|meta| meta.id == id)
+            .unwrap();
+        services
+            .edit_service
+            .clone_dataset(
+                id,
+                NewDatasetMeta {
+                    name: name.to_string(),
+                    source_path: meta.save_as_source_path(name),
+                },
+            )
+            .expect("save as")
+    };
+    let investment = |id: DatasetId| {
+        let (headers, rows, _, _) = load(id);
+        let balance_idx = headers.iter().position(|h| h == "餘額").unwrap();
+        let row = rows.iter().find(|row| row[0] == "股票投資").unwrap();
+        row[balance_idx].clone()
+    };
+    let add_to_net = |id: DatasetId, amount: f64| {
+        let (headers, rows, _, _) = load(id);
+        let net_idx = headers.iter().position(|h| h == "淨值").unwrap();
+        let old_net = parse_numeric_value(&rows[0][net_idx]).unwrap();
+        let staged_cells = HashMap::from([(
+            CellKey {
+                row_idx: 0,
+                col_idx: net_idx,
+                column: "淨值".to_string(),
+            },
+            format_f64(old_net + amount),
+        )]);
+        services
+            .edit_service
+            .apply_edits(
+                id,
+                StagedEdits {
+                    staged_cells,
+                    ..StagedEdits::default()
+                },
+            )
+            .expect("apply edits");
+    };
+    // Both copies are newer than the datasets they were saved from.
+    let assets_copy = save_as(ids[0], "資產總表 備份");
+    let holdings_copy = save_as(ids[1], "持股 備份");
+    let copy_balance = investment(assets_copy);
+
+    add_to_net(ids[1], 1000.0);
+    let (headers, rows, _, _) = load(ids[1]);
+    let total_net = holdings_total_net(&headers, &rows).unwrap();
+    assert_eq!(investment(ids[0]), format_f64(total_net));
+    assert_eq!(investment(assets_copy), copy_balance);
+
+    // Editing the 持股 copy leaves both 資產總表 alone.
+    add_to_net(holdings_copy, 5000.0);
+    assert_eq!(investment(ids[0]), format_f64(total_net));
+    assert_eq!(investment(assets_copy), copy_balance);
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn dividend_sheet_columns_follow_headers_and_owners_follow_settings() {
+    let mut header = vec![
+        "序".to_string(),
+        "名稱".to_string(),
+        "代號".to_string(),
+        "性質".to_string(),
+        "所有權人".to_string(),
+        "2024年".to_string(),
+    ];
+    header.extend((1..=12).map(|month| format!("{month}月")));
+    let mut row = vec![
+        "1".to_string(),
+        "高股息".to_string(),
+        "0056".to_string(),
+        "股票".to_string(),
+        "Jim".to_string(),
+        "12".to_string(),
+    ];
+    row.extend((1..=12).map(|month| month.to_string()));
+    let rows = vec![vec!["股息收入明細表".to_string()], header.clone(), row];
+
+    let (columns, header_row) = DividendColumns::detect(&rows).expect("header row");
+    assert_eq!(header_row, 1);
+    assert_eq!((columns.name, columns.owner, columns.last_year), (1, 4, 5));
+    assert_eq!(columns.months[0], 6);
+    let mut legacy_header = header.clone();
+    legacy_header.truncate(6);
+    assert!(DividendColumns::from_headers(&legacy_header).is_none());
+
+    let by_code = HashMap::from([(
+        "0056".to_string(),
+        HoldingDerived {
+            quantity: 1.0,
+            ..HoldingDerived::default()
+        },
+    )]);
+    let (headers, data, _) = transform_dividend_sheet(&rows, &by_code);
+    assert_eq!(data.len(), 1);
+    let value = |label: &str| &data[0][headers.iter().position(|h| h == label).unwrap()];
+    assert_eq!(value("名稱"), "高股息");
+    assert_eq!(value("所有權人"), "Jim");
+    assert_eq!(value("12月"), "12");
+    assert_eq!(value("今年度累積"), "78");
+
+    let owners = parse_owner_list("Paul\n\n Alex \nPaul\n");
+    assert_eq!(owners, vec!["Paul".to_string(), "Alex".to_string()]);
+    let temp_dir = unique_test_dir("dividend_owners");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let query = AppServices::new(db_path).query_service;
+    query
+        .save_setting(DIVIDEND_OWNERS_KEY, &owners.join("\n"))
+        .expect("save owners");
+    let saved = parse_owner_list(&query.load_setting(DIVIDEND_OWNERS_KEY).unwrap().unwrap());
+
+    let headers = vec!["所有權人".to_string(), "總成本".to_string()];
+    let rows = ["Alex", "Jim", "Paul"]
+        .iter()
+        .map(|owner| vec![owner.to_string(), "1".to_string()])
+        .collect::<Vec<_>>();
+    let mut report = compute_summary_report(&headers, &rows);
+    order_owner_totals(&mut report, &saved);
+    let order = report
+        .owner_totals
+        .iter()
+        .map(|summary| summary.owner.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(order, vec!["Paul", "Alex", "Jim"]);
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn duplicate_codes_in_holdings_are_reported_and_merged_on_request() {
+    let holding = |name: &str, code: &str, buy: &str, qty: &str| {
+        let mut row = vec![String::new(); 23];
+        row[1] = name.to_string();
+        row[5] = code.to_string();
+        row[6] = buy.to_string();
+        row[7] = "40".to_string();
+        row[8] = qty.to_string();
+        row
+    };
+    let rows = vec![
+        holding("高股息", "0056", "30", "1000"),
+        holding("台積電", "2330", "500", "10"),
+        holding("高股息 (新)", "0056", "36", "500"),
+    ];
+    let expected = vec![DuplicateCode {
+        code: "0056".to_string(),
+        names: vec!["高股息".to_string(), "高股息 (新)".to_string()],
+    }];
+    assert_eq!(find_duplicate_codes(&rows), expected);
+
+    let kept = transform_holdings_sheet(&rows, DuplicateCodePolicy::KeepBoth);
+    assert_eq!(kept.rows.len(), 3);
+    assert_eq!(kept.duplicates, expected);
+
+    let merged = transform_holdings_sheet(&rows, DuplicateCodePolicy::Merge);
+    assert_eq!(merged.rows.len(), 2);
+    assert_eq!(merged.duplicates, expected);
+    let derived = &merged.by_code["0056"];
+    assert_eq!(derived.quantity, 1500.0);
+    assert_eq!(derived.buy_price, 32.0);
+    assert_eq!(merged.total_cost, kept.total_cost);
+}
+
+/// The BOM workbook's sheets without a file: a 持股明細 listing 0056 twice.
+struct WorkbookSheets;
+
+impl Importer for WorkbookSheets {
+    fn name(&self) -> &str {
+        "Workbook sheets"
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["xlsx"]
+    }
+
+    fn parse(&self, path: &std::path::Path) -> anyhow::Result<Vec<ParsedDataset>> {
+        let holding = |name: &str, code: &str, buy: &str, qty: &str| {
+            let mut row = vec![String::new(); 23];
+            row[1] = name.to_string();
+            row[5] = code.to_string();
+            row[6] = buy.to_string();
+            row[7] = "40".to_string();
+            row[8] = qty.to_string();
+            row
+        };
+        let sheet = |name: &str, rows: Vec<Vec<String>>| ParsedDataset {
+            name: name.to_string(),
+            source_path: path.display().to_string(),
+            headers: Vec::new(),
+            rows,
+            origins: Vec::new(),
+        };
+        Ok(vec![
+            sheet("資產總表", Vec::new()),
+            sheet(
+                "持股明細",
+                vec![
+                    holding("高股息", "0056", "30", "1000"),
+                    holding("台積電", "2330", "500", "10"),
+                    holding("高股息 (新)", "0056", "36", "500"),
+                ],
+            ),
+            sheet(
+                "股息收入明細表",
+                vec![vec!["名稱".to_string(), "代號".to_string()]],
+            ),
+        ])
+    }
+
+    fn transform(&self, datasets: Vec<ParsedDataset>) -> anyhow::Result<Vec<ParsedDataset>> {
+        XlsxImporter::default().transform(datasets)
+    }
+
+    fn keeps_source_sheets(&self) -> bool {
+        true
+    }
+}
+
+#[test]
+fn retransform_rebuilds_a_dataset_from_its_stored_sheets() {
+    let temp_dir = unique_test_dir("retransform");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    let service = ImportService::new(db_path.clone());
+    let imported = import_with(&WorkbookSheets, &db_path, std::path::Path::new("bom.xlsx"))
+        .expect("import sheets");
+    let dataset_id = imported[1].dataset_id;
+    let load = || query_page(&db_path, dataset_id, 0, 100, &QueryOptions::default()).unwrap();
+    let (columns, rows, total) = load();
+    assert_eq!(total, 3);
+
+    let staged = HashMap::from([(
+        CellKey {
+            row_idx: 0,
+            col_idx: 0,
+            column: columns[0].clone(),
+        },
+        "改過".to_string(),
+    )]);
+    apply_changes_to_dataset(
+        &db_path,
+        dataset_id,
+        &columns,
+        &rows,
+        &staged,
+        &BTreeSet::from([1]),
+        &[],
+    )
+    .expect("edit dataset");
+    assert_ne!(load().1, rows);
+
+    let validation = service
+        .validate_sources(DatasetId(dataset_id))
+        .expect("validate stored sheets");
+    assert_eq!(validation.duplicate_codes.len(), 1);
+    let result = service
+        .retransform(DatasetId(dataset_id), DuplicateCodePolicy::KeepBoth)
+        .expect("retransform");
+    assert_eq!(result.row_count, 3);
+    assert_eq!(load(), (columns, rows, 3));
+
+    let merged = service
+        .retransform(DatasetId(dataset_id), DuplicateCodePolicy::Merge)
+        .expect("retransform merged");
+    assert_eq!(merged.row_count, 2);
+
+    let csv_path = temp_dir.join("plain.csv");
+    fs::write(&csv_path, "名稱,代號\n台積電,2330\n").expect("write csv");
+    let plain = service.import(&csv_path).expect("import csv")[0].dataset_id;
+    assert!(service
+        .retransform(DatasetId(plain), DuplicateCodePolicy::KeepBoth)
+        .is_err());
+
+    fs::remove_dir_all(&temp_dir).expect("should cleanup temp dir");
+}
+
+#[test]
+fn imports_and_retransforms_are_recorded_in_the_import_history() {
+    let temp_dir = unique_test_dir("import-history");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    let service = ImportService::new(db_path.clone());
+    let csv_path = temp_dir.join("持股.csv");
+    fs::write(
+        &csv_path,
+        "名稱,代號\n台積電,2330\n台積電,2330\n鴻海,2317\n",
+    )
+    .expect("write csv");
+    let csv_id = service.import(&csv_path).expect("import csv")[0].dataset_id;
+    let imported = import_with(&WorkbookSheets, &db_path, std::path::Path::new("bom.xlsx"))
+        .expect("import sheets");
+    service
+        .retransform(
+            DatasetId(imported[1].dataset_id),
+            DuplicateCodePolicy::Merge,
+        )
+        .expect("retransform");
+
+    let history = load_import_history(&db_path).expect("load history");
+    assert_eq!(history.len(), 3);
+    let retransform = &history[0];
+    assert_eq!(retransform.kind, ImportEventKind::Retransform);
+    assert!(retransform.file_hash.is_empty());
+    assert_eq!(retransform.sheets.len(), 1);
+    assert_eq!(retransform.sheets[0].row_count, 2);
+    assert!(retransform.includes(imported[1].dataset_id));
+    assert_eq!(history[1].sheets.len(), imported.len());
+
+    let csv_import = &history[2];
+    assert_eq!(csv_import.kind, ImportEventKind::Import);
+    assert_eq!(csv_import.source_path, csv_path.to_string_lossy());
+    assert_eq!(csv_import.file_hash.len(), 64);
+    assert_eq!(csv_import.file_hash, file_hash(&csv_path).unwrap());
+    assert_eq!(csv_import.sheets[0].dataset_id, csv_id);
+    assert_eq!(csv_import.sheets[0].row_count, 3);
+    assert!(csv_import.warnings[0].contains("代號 2330 出現 2 次"));
+    assert!(!csv_import.includes(imported[1].dataset_id));
+
+    fs::remove_dir_all(&temp_dir).expect("should cleanup temp dir");
+}
+
+#[test]
+fn folder_import_lists_only_files_an_importer_accepts() {
+    let temp_dir = unique_test_dir("import-folder");
+    fs::create_dir_all(temp_dir.join("older")).expect("should create temp dirs");
+    for name in ["b.csv", "A.XLSX", "notes.txt", "~$A.xlsx", ".hidden.csv"] {
+        fs::write(temp_dir.join(name), "名稱,代號\n台積電,2330\n").expect("write file");
+    }
+    fs::write(temp_dir.join("older").join("c.csv"), "名稱\n台積電\n").expect("write file");
+    let service = ImportService::new(temp_dir.join("app.sqlite"));
+
+    let files = service
+        .importable_files(&temp_dir)
+        .expect("list importable files");
+    assert_eq!(files, vec![temp_dir.join("A.XLSX"), temp_dir.join("b.csv")]);
+    assert!(service.importable_files(&temp_dir.join("missing")).is_err());
+
+    let mut batch = BatchImport {
+        folder: temp_dir.clone(),
+        files,
+        results: vec![Err("無法讀取活頁簿".to_string())],
+    };
+    assert!(!batch.is_done());
+    batch.results.push(Ok("1 筆".to_string()));
+    assert!(batch.is_done());
+    assert_eq!(batch.failed(), 1);
+
+    fs::remove_dir_all(&temp_dir).expect("should cleanup temp dir");
+}
+
+#[test]
+fn imported_cells_remember_their_source_cell() {
+    let temp_dir = unique_test_dir("cell-origins");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    let imported = import_with(&WorkbookSheets, &db_path, std::path::Path::new("bom.xlsx"))
+        .expect("import sheets");
+    let dataset_id = imported[1].dataset_id;
+    let (columns, rows, _) =
+        query_page(&db_path, dataset_id, 0, 100, &QueryOptions::default()).unwrap();
+    let col = |name: &str| columns.iter().position(|column| column == name).unwrap();
+
+    let origins = load_cell_origins(&db_path, dataset_id).expect("load origins");
+    let name = &origins[&(1, col("名稱"))];
+    assert_eq!(name.reference(), "持股明細!B4");
+    assert_eq!(name.value, "台積電");
+    assert_eq!(origins[&(1, col("數量"))].value, "10");
+    assert!(!origins.contains_key(&(1, col("總成本"))));
+    assert_eq!(
+        origin_tooltip(name),
+        "來源：持股明細!B4（台積電）".to_string()
+    );
+
+    apply_changes_to_dataset(
+        &db_path,
+        dataset_id,
+        &columns,
+        &rows,
+        &HashMap::new(),
+        &BTreeSet::from([0]),
+        &[],
+    )
+    .expect("delete row");
+    let shifted = load_cell_origins(&db_path, dataset_id).expect("reload origins");
+    assert_eq!(&shifted[&(0, col("名稱"))], name);
+    assert!(!shifted.contains_key(&(2, col("名稱"))));
+
+    let wide = CellOrigin {
+        sheet: "股息收入明細表".to_string(),
+        row_idx: 1,
+        col_idx: 27,
+        value: "5".to_string(),
+    };
+    assert_eq!(wide.reference(), "股息收入明細表!AB2");
+
+    fs::remove_dir_all(&temp_dir).expect("should cleanup temp dir");
+}
+
+#[test]
+fn dividend_year_rollover_archives_months_into_a_year_column() {
+    let mut header = vec!["名稱".to_string(), "代號".to_string(), "2024年".to_string()];
+    header.extend((1..=12).map(|month| format!("{month}月")));
+    let (headers, _, _) = transform_dividend_sheet(&[header], &HashMap::new());
+    assert!(headers.contains(&"2024年".to_string()));
+    assert!(headers.contains(&"2025年".to_string()));
+    assert_eq!(open_dividend_year(&headers), Some(2026));
+
+    let temp_dir = unique_test_dir("dividend_rollover");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let services = AppServices::new(db_path);
+    let ids = create_demo_datasets(&services.edit_service).expect("demo datasets");
+    let load = |id: DatasetId| {
+        let options = QueryOptions::default();
+        reload_page_data_usecase(&services.query_service, Some(id.0), 0, &options).unwrap()
+    };
+    let (headers, rows, _, _) = load(ids[1]);
+    let year = open_dividend_year(&headers).expect("year columns");
+    let aliases = BTreeMap::from([(0, "持有人".to_string())]);
+    services
+        .query_service
+        .upsert_column_aliases(ids[1], aliases.clone())
+        .unwrap();
+
+    let (new_id, closed_year) =
+        roll_over_dividend_dataset(&services.query_service, &services.edit_service, ids[1])
+            .expect("rollover");
+    assert_eq!(closed_year, year);
+    let (rolled_headers, rolled_rows, _, _) = load(new_id);
+    assert_eq!(rolled_headers.len(), headers.len() + 1);
+    assert_eq!(rolled_headers.last(), Some(&format!("{year}年")));
+    assert_eq!(open_dividend_year(&rolled_headers), Some(year + 1));
+    assert_eq!(
+        services.query_service.load_column_aliases(new_id).unwrap(),
+        aliases
+    );
+
+    let col = |name: &str| headers.iter().position(|h| h == name).unwrap();
+    let number = |value: &str| value.replace(',', "").parse::<f64>().unwrap_or(0.0);
+    for (before, after) in rows.iter().zip(&rolled_rows) {
+        let year_total: f64 = (1..=12)
+            .map(|m| number(&before[col(&format!("{m}月"))]))
+            .sum();
+        assert_eq!(number(after.last().unwrap()), year_total);
+        assert_eq!(number(&after[col("1月")]), 0.0);
+        assert_eq!(number(&after[col("今年度累積")]), 0.0);
+        assert!(
+            (number(&after[col("去年度累積")]) - (number(&before[col("去年度累積")]) + year_total))
+                .abs()
+                < 1e-6
+        );
+    }
+
+    let names = services
+        .query_service
+        .list_datasets(false)
+        .unwrap()
+        .into_iter()
+        .map(|meta| meta.name)
+        .collect::<Vec<_>>();
+    assert!(names.contains(&format!("持股股息總表 {year}年")));
+    assert!(names.contains(&"持股股息總表".to_string()));
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn dataset_summary_report_uses_saved_edits_without_the_source_file() {
+    let temp_dir = unique_test_dir("stored_summary");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let services = AppServices::new(db_path);
+    let missing = temp_dir.join("moved.xlsx").to_string_lossy().into_owned();
+    let dataset_id = services
+        .edit_service
+        .create_dataset(
+            NewDatasetMeta {
+                name: "持股股息總表".to_string(),
+                source_path: format!("{missing}#持股股息總表"),
+            },
+            TabularData {
+                columns: vec!["所有權人".to_string(), "總成本".to_string()],
+                rows: vec![
+                    vec!["Alex".to_string(), "100".to_string()],
+                    vec!["Paul".to_string(), "200".to_string()],
+                ],
+            },
+        )
+        .expect("create dataset");
+    let staged_cells = HashMap::from([(
+        CellKey {
+            row_idx: 1,
+            col_idx: 1,
+            column: "總成本".to_string(),
+        },
+        "250".to_string(),
+    )]);
+    services
+        .edit_service
+        .apply_edits(
+            dataset_id,
+            StagedEdits {
+                staged_cells,
+                ..StagedEdits::default()
+            },
+        )
+        .expect("apply edits");
+
+    let owners = vec!["Paul".to_string()];
+    let report = dataset_summary_report(&services.query_service, dataset_id, &owners)
+        .expect("summary from stored rows");
+    let total = report.totals.iter().find(|entry| entry.label == "總成本");
+    assert_eq!(total.map(|entry| entry.value.as_str()), Some("350"));
+    assert_eq!(report.owner_totals[0].owner, "Paul");
+
+    // Nothing to summarise and the workbook is gone: still a report, not an error.
+    let empty_id = services
+        .edit_service
+        .create_dataset(
+            NewDatasetMeta {
+                name: "備忘".to_string(),
+                source_path: format!("{missing}#備忘"),
+            },
+            TabularData {
+                columns: vec!["備註".to_string()],
+                rows: vec![vec!["x".to_string()]],
+            },
+        )
+        .expect("create dataset");
+    let report = dataset_summary_report(&services.query_service, empty_id, &[]).unwrap();
+    assert!(report.totals.is_empty());
+    assert!(!report.notes.is_empty());
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn summary_reports_are_cached_until_edits_are_applied() {
+    let temp_dir = unique_test_dir("summary_cache");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let services = AppServices::new(db_path);
+    let dataset_id = services
+        .edit_service
+        .create_dataset(
+            NewDatasetMeta {
+                name: "持股".to_string(),
+                source_path: "test".to_string(),
+            },
+            TabularData {
+                columns: vec!["所有權人".to_string(), "總成本".to_string()],
+                rows: vec![
+                    vec!["Alex".to_string(), "100".to_string()],
+                    vec!["Paul".to_string(), "200".to_string()],
+                ],
+            },
+        )
+        .expect("create dataset");
+    let query = &services.query_service;
+
+    let first = cached_summary_report(query, dataset_id, &[]).expect("first summary");
+    let again = cached_summary_report(query, dataset_id, &[]).expect("cached summary");
+    assert_eq!(again.id, first.id);
+    assert_eq!(again.report, first.report);
+    assert_eq!(query.load_summary_reports(dataset_id).unwrap().len(), 1);
+
+    services
+        .edit_service
+        .apply_edits(
+            dataset_id,
+            StagedEdits {
+                deleted_rows: BTreeSet::from([0]),
+                ..StagedEdits::default()
+            },
+        )
+        .expect("apply edits");
+    let rebuilt = cached_summary_report(query, dataset_id, &[]).expect("rebuilt summary");
+    assert_ne!(rebuilt.id, first.id);
+    assert!(rebuilt.revision > first.revision);
+    let total = rebuilt.report.totals.iter().find(|e| e.label == "總成本");
+    assert_eq!(total.map(|e| e.value.as_str()), Some("200"));
+
+    let history = query.load_summary_reports(dataset_id).unwrap();
+    assert_eq!(
+        history.iter().map(|stored| stored.id).collect::<Vec<_>>(),
+        vec![rebuilt.id, first.id]
+    );
+    assert_eq!(history[1].report, first.report);
+
+    services
+        .edit_service
+        .purge_dataset(dataset_id)
+        .expect("purge");
+    assert!(query.load_summary_reports(dataset_id).unwrap().is_empty());
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn report_builder_definitions_save_as_scripts_and_export_as_tables() {
+    let definition = ReportDefinition {
+        title: "股票 市值".to_string(),
+        filters: vec![ReportFilter {
+            column: "類別".to_string(),
+            comparison: Comparison::Eq,
+            value: "股票".to_string(),
+        }],
+        group_by: Some("所有權人".to_string()),
+        metrics: vec![
+            ReportMetric {
+                aggregate: Aggregate::Sum,
+                column: Some("市值".to_string()),
+                label: "市值".to_string(),
+            },
+            ReportMetric {
+                aggregate: Aggregate::Avg,
+                column: Some("市值".to_string()),
+                label: "平均市值".to_string(),
+            },
+        ],
+        notes: Vec::new(),
+    };
+    let script = definition.to_script();
+    assert_eq!(parse_report_script(&script).expect("parse"), definition);
+
+    let headers = vec![
+        "所有權人".to_string(),
+        "類別".to_string(),
+        "市值".to_string(),
+    ];
+    let rows = vec![
+        vec!["甲".to_string(), "股票".to_string(), "100".to_string()],
+        vec!["乙".to_string(), "股票".to_string(), "300".to_string()],
+        vec!["甲".to_string(), "債券".to_string(), "50".to_string()],
+    ];
+    let report = definition.run(&headers, &rows).expect("run");
+    assert_eq!(
+        run_report_script(&script, &headers, &rows, &OwnerSplits::new()).unwrap(),
+        report
+    );
+    let (table_headers, table_rows) = summary_report_table(&report, SummarySections::default());
+    assert_eq!(table_headers, vec!["分組", "項目", "數值"]);
+    assert_eq!(table_rows[0], vec!["合計", "市值", "400"]);
+    assert_eq!(table_rows[1], vec!["合計", "平均市值", "200"]);
+    assert_eq!(table_rows[2], vec!["甲", "市值", "100"]);
+    assert_eq!(table_rows.len(), 6);
+
+    let temp_dir = unique_test_dir("saved_reports");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let services = AppServices::new(db_path);
+    let query = &services.query_service;
+    for name in ["股票", "全部"] {
+        query
+            .save_saved_report(SavedReport {
+                name: name.to_string(),
+                script: "count\n".to_string(),
+            })
+            .expect("save report");
+    }
+    query
+        .save_saved_report(SavedReport {
+            name: "股票".to_string(),
+            script: script.clone(),
+        })
+        .expect("replace report");
+    let saved = query.load_saved_reports().expect("load reports");
+    assert_eq!(
+        saved
+            .iter()
+            .map(|report| report.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["全部", "股票"]
+    );
+    assert_eq!(saved[1].script, script);
+    query
+        .delete_saved_report("全部".to_string())
+        .expect("delete report");
+    assert_eq!(query.load_saved_reports().unwrap().len(), 1);
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn summary_report_text_copies_checked_sections_as_tsv() {
+    let entry = |label: &str, value: &str| SummaryEntry {
+        label: label.to_string(),
+        value: value.to_string(),
+    };
+    let report = SummaryReport {
+        title: "總結".to_string(),
+        totals: vec![entry("利息", "1,200")],
+        owner_totals: vec![
+            OwnerSummary {
+                owner: "Alex".to_string(),
+                entries: vec![entry("股息", "300"), entry("利息", "100")],
+            },
+            OwnerSummary {
+                owner: "Paul".to_string(),
+                entries: vec![entry("股息", "500")],
+            },
+        ],
+        group_heading: String::new(),
+        notes: vec!["備註一".to_string()],
+    };
+
+    assert_eq!(
+        summary_report_text(&report, SummarySections::default()),
+        "總結\n\n利息\t1,200\n\n所有權人\t股息\t利息\nAlex\t300\t100\nPaul\t500\t\n\n備註一\n"
+    );
+    let owners_only = SummarySections {
+        totals: false,
+        owner_totals: true,
+        notes: false,
+    };
+    assert_eq!(
+        summary_report_text(&report, owners_only),
+        "總結\n\n所有權人\t股息\t利息\nAlex\t300\t100\nPaul\t500\t\n"
+    );
+}
+
+#[test]
+fn summary_sections_round_trip_through_settings() {
+    let owners_only = SummarySections {
+        totals: false,
+        owner_totals: true,
+        notes: false,
+    };
+    assert_eq!(owners_only.to_setting(), "owner_totals");
+    assert_eq!(SummarySections::parse_setting("owner_totals"), owners_only);
+    assert_eq!(
+        SummarySections::parse_setting(&SummarySections::default().to_setting()),
+        SummarySections::default()
+    );
+    assert_eq!(
+        SummarySections::parse_setting(""),
+        SummarySections {
+            totals: false,
+            owner_totals: false,
+            notes: false,
+        }
+    );
+
+    let report = SummaryReport {
+        title: "總結".to_string(),
+        totals: vec![SummaryEntry {
+            label: "利息".to_string(),
+            value: "10".to_string(),
+        }],
+        owner_totals: vec![OwnerSummary {
+            owner: "Alex".to_string(),
+            entries: vec![SummaryEntry {
+                label: "股息".to_string(),
+                value: "5".to_string(),
+            }],
+        }],
+        group_heading: String::new(),
+        notes: vec!["備註".to_string()],
+    };
+    let (_, rows) = summary_report_table(&report, owners_only);
+    assert_eq!(rows, vec![vec!["Alex", "股息", "5"]]);
+}
+
+#[test]
+fn every_route_has_a_distinct_nav_label() {
+    let labels = Route::ALL
+        .iter()
+        .map(|route| route.label())
+        .collect::<BTreeSet<_>>();
+    assert_eq!(labels.len(), Route::ALL.len());
+    assert!(Route::ALL.contains(&Route::Dataset));
+}
+
+#[test]
+fn computed_columns_evaluate_at_query_time() {
+    let headers = vec![
+        "名稱".to_string(),
+        "淨值".to_string(),
+        "總成本".to_string(),
+        "估計配息".to_string(),
+    ];
+    let row = vec![
+        "甲".to_string(),
+        "1,200".to_string(),
+        "1000".to_string(),
+        "".to_string(),
+    ];
+    let ratio = Expression::parse("淨值 / 總成本 - 1").expect("parse");
+    assert_eq!(ratio.columns(), vec!["淨值", "總成本"]);
+    assert!((ratio.evaluate(&headers, &row).unwrap() - 0.2).abs() < 1e-9);
+    let quoted = Expression::parse("-(\"總成本\" * 2) / 4").expect("parse quoted");
+    assert_eq!(quoted.evaluate(&headers, &row), Some(-500.0));
+    let monthly = Expression::parse("估計配息 / 12").expect("parse");
+    assert_eq!(monthly.evaluate(&headers, &row), None);
+    let by_zero = Expression::parse("淨值 / (總成本 - 1000)").expect("parse");
+    assert_eq!(by_zero.evaluate(&headers, &row), None);
+    assert!(Expression::parse("淨值 +").is_err());
+    assert!(Expression::parse("(淨值").is_err());
+
+    let computed = |name: &str, expression: &str| ComputedColumn {
+        name: name.to_string(),
+        expression: expression.to_string(),
+    };
+    assert!(validate_computed_columns(&headers, &[computed("報酬率", "不存在 * 2")]).is_err());
+    assert!(validate_computed_columns(&headers, &[computed("淨值", "總成本")]).is_err());
+    assert!(validate_computed_columns(&headers, &[computed(" ", "總成本")]).is_err());
+    let chained = vec![
+        computed("報酬率", "淨值 / 總成本 - 1"),
+        computed("報酬率%", "報酬率 * 100"),
+    ];
+    validate_computed_columns(&headers, &chained).expect("later columns read earlier ones");
+
+    let temp_dir = unique_test_dir("computed_columns");
+    fs::create_dir_all(&temp_dir).expect("create temp dir");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let rows = vec![
+        vec![
+            "甲".to_string(),
+            "1200".to_string(),
+            "1000".to_string(),
+            "60".to_string(),
+        ],
+        vec![
+            "乙".to_string(),
+            "900".to_string(),
+            "1000".to_string(),
+            "".to_string(),
+        ],
+        vec![
+            "丙".to_string(),
+            "1500".to_string(),
+            "1000".to_string(),
+            "120".to_string(),
+        ],
+    ];
+    let dataset_id =
+        create_dataset_from_rows(&db_path, "資產", "test", &headers, &rows).expect("create");
+    let services = AppServices::new(db_path.clone());
+    services
+        .query_service
+        .save_computed_columns(DatasetId(dataset_id), chained.clone())
+        .expect("save computed");
+    assert_eq!(
+        services
+            .query_service
+            .load_computed_columns(DatasetId(dataset_id))
+            .expect("load computed"),
+        chained
+    );
+
+    let (columns, page_rows, total) =
+        query_page(&db_path, dataset_id, 0, 10, &QueryOptions::default()).expect("query");
+    assert_eq!(columns[4..], ["報酬率".to_string(), "報酬率%".to_string()]);
+    assert_eq!(total, 3);
+    assert_eq!(page_rows[1][4], "-0.1");
+    assert_eq!(page_rows[1][5], "-10");
+
+    let sorted = QueryOptions {
+        sort_col: Some(5),
+        sort_desc: true,
+        ..QueryOptions::default()
+    };
+    let (_, page_rows, total) = query_page(&db_path, dataset_id, 0, 2, &sorted).expect("sort");
+    assert_eq!(total, 3);
+    let names: Vec<&str> = page_rows.iter().map(|row| row[0].as_str()).collect();
+    assert_eq!(names, vec!["丙", "甲"]);
+
+    let filtered = QueryOptions {
+        column_search_col: Some(4),
+        column_search_text: "-".to_string(),
+        ..QueryOptions::default()
+    };
+    let (_, page_rows, total) = query_page(&db_path, dataset_id, 0, 10, &filtered).expect("filter");
+    assert_eq!(total, 1);
+    assert_eq!(page_rows[0][0], "乙");
+
+    services
+        .edit_service
+        .apply_edits(
+            DatasetId(dataset_id),
+            StagedEdits {
+                staged_cells: HashMap::from([(
+                    CellKey {
+                        row_idx: 1,
+                        col_idx: 1,
+                        column: "淨值".to_string(),
+                    },
+                    "1100".to_string(),
+                )]),
+                deleted_rows: BTreeSet::new(),
+                added_rows: vec![vec![
+                    "丁".to_string(),
+                    "500".to_string(),
+                    "1000".to_string(),
+                    "".to_string(),
+                    "".to_string(),
+                    "".to_string(),
+                ]],
+            },
+        )
+        .expect("apply edits");
+    let (columns, page_rows, total) =
+        query_page(&db_path, dataset_id, 0, 10, &QueryOptions::default()).expect("query");
+    assert_eq!(columns.len(), 6);
+    assert_eq!(total, 4);
+    assert_eq!(page_rows[1][5], "10");
+    assert_eq!(page_rows[3][4], "-0.5");
+}
+
+#[test]
+fn quick_filter_chips_count_values_and_filter_by_selection() {
+    let temp_dir = unique_test_dir("quick_filters");
+    fs::create_dir_all(&temp_dir).expect("create temp dir");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let headers = vec![
+        "所有權人".to_string(),
+        "類別".to_string(),
+        "市值".to_string(),
+    ];
+    let rows = [
+        ["甲", "股票", "100"],
+        ["乙", "股票", "200"],
+        ["甲", "債券", "300"],
+        ["丙", "", "400"],
+    ]
+    .iter()
+    .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+    .collect::<Vec<Vec<String>>>();
+    let dataset_id =
+        create_dataset_from_rows(&db_path, "資產", "test", &headers, &rows).expect("create");
+
+    assert_eq!(
+        column_value_counts(&db_path, dataset_id, 0).expect("owner counts"),
+        vec![
+            ("甲".to_string(), 2),
+            ("丙".to_string(), 1),
+            ("乙".to_string(), 1),
+        ]
+    );
+    assert_eq!(
+        column_value_counts(&db_path, dataset_id, 1).expect("category counts"),
+        vec![("股票".to_string(), 2), ("債券".to_string(), 1)]
+    );
+
+    let mut options = QueryOptions::default();
+    options
+        .value_filters
+        .insert(0, BTreeSet::from(["甲".to_string(), "乙".to_string()]));
+    let (_, filtered, total) = query_page(&db_path, dataset_id, 0, 10, &options).expect("query");
+    assert_eq!(total, 3);
+    assert_eq!(filtered.len(), 3);
+    options
+        .value_filters
+        .insert(1, BTreeSet::from(["股票".to_string()]));
+    let (_, filtered, total) = query_page(&db_path, dataset_id, 0, 10, &options).expect("query");
+    assert_eq!(total, 2);
+    assert_eq!(filtered[1][2], "200");
+
+    let query = build_page_query(dataset_id, 0, &options);
+    assert_eq!(query.value_filters.len(), 2);
+    let services = AppServices::new(db_path.clone());
+    let page = services
+        .query_service
+        .query_page(query)
+        .expect("service query");
+    assert_eq!(page.total_rows, 2);
+
+    options
+        .value_filters
+        .insert(9, BTreeSet::from(["x".to_string()]));
+    assert!(query_page(&db_path, dataset_id, 0, 10, &options).is_err());
+}
+
+#[test]
+fn search_history_keeps_pinned_terms_and_recent_searches() {
+    let mut history = Vec::new();
+    for term in ["台積電", "ETF", " ", "台積電"] {
+        history = push_search_history(&history, term);
+    }
+    let terms = |history: &[SearchHistoryEntry]| {
+        history
+            .iter()
+            .map(|entry| (entry.term.clone(), entry.pinned))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        terms(&history),
+        vec![("台積電".to_string(), false), ("ETF".to_string(), false)]
+    );
+
+    history = toggle_search_history_pin(&history, "ETF");
+    for idx in 0..SEARCH_HISTORY_LIMIT + 2 {
+        history = push_search_history(&history, &format!("term{idx}"));
+    }
+    assert_eq!(history[0].term, "ETF");
+    assert!(history[0].pinned);
+    assert_eq!(history[1].term, format!("term{}", SEARCH_HISTORY_LIMIT + 1));
+    assert_eq!(history.len(), SEARCH_HISTORY_LIMIT + 1);
+    assert!(!history.iter().any(|entry| entry.term == "台積電"));
+
+    let unchanged = push_search_history(&history, "ETF");
+    assert_eq!(unchanged, history);
+    assert_eq!(
+        parse_search_history(&search_history_setting(&history)),
+        history
+    );
+
+    let unpinned = toggle_search_history_pin(&history, "ETF");
+    assert_eq!(unpinned[0].term, "ETF");
+    assert!(!unpinned[0].pinned);
+}
+
+#[test]
+fn busy_state_tracks_each_kind_of_work_separately() {
+    let mut busy = BusyState::default();
+    assert!(!busy.any());
+
+    busy.begin(BusyKind::Save);
+    busy.begin(BusyKind::Query);
+    busy.begin(BusyKind::Query);
+    assert!(busy.is(BusyKind::Save));
+    assert!(!busy.is(BusyKind::Import));
+
+    busy.end(BusyKind::Query);
+    assert!(busy.is(BusyKind::Query), "the outer query is still running");
+    busy.end(BusyKind::Query);
+    busy.end(BusyKind::Import);
+    assert!(!busy.is(BusyKind::Query));
+    assert!(busy.any());
+
+    busy.end(BusyKind::Save);
+    assert_eq!(busy, BusyState::default());
+}
+
+#[test]
+fn results_larger_than_a_page_are_paged() {
+    let temp_dir = unique_test_dir("large_result");
+    fs::create_dir_all(&temp_dir).expect("create temp dir");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let headers = vec!["序號".to_string()];
+    let rows = (0..=PAGE_SIZE)
+        .map(|idx| vec![idx.to_string()])
+        .collect::<Vec<_>>();
+    let dataset_id =
+        create_dataset_from_rows(&db_path, "大量", "test", &headers, &rows).expect("create");
+    let services = AppServices::new(db_path);
+    let options = QueryOptions::default();
+
+    let (_, first, total_rows, _) =
+        reload_page_data_usecase(&services.query_service, Some(dataset_id), 0, &options).unwrap();
+    assert_eq!(total_rows, PAGE_SIZE + 1);
+    assert_eq!(first.len() as i64, PAGE_SIZE);
+    assert_eq!(page_count(total_rows, PAGE_SIZE), 2);
+    let (_, last, _, page) =
+        reload_page_data_usecase(&services.query_service, Some(dataset_id), 1, &options).unwrap();
+    assert_eq!((page, last), (1, vec![vec![PAGE_SIZE.to_string()]]));
+    assert_eq!(page_count(0, PAGE_SIZE), 1);
+
+    // A dataset keeps the page size chosen for it; 載入全部 pages by the whole result.
+    let query = &services.query_service;
+    assert_eq!(query.load_page_size(DatasetId(dataset_id)).unwrap(), None);
+    query
+        .save_page_size(DatasetId(dataset_id), Some(100))
+        .expect("save page size");
+    assert_eq!(
+        query.load_page_size(DatasetId(dataset_id)).unwrap(),
+        Some(100)
+    );
+    let mut cache = PageCache::default();
+    let ((_, rows, _, _), _) =
+        reload_page_data_cached(query, &mut cache, Some(dataset_id), 3, 100, &options).unwrap();
+    assert_eq!(rows.first(), Some(&vec!["300".to_string()]));
+    assert_eq!(page_count(total_rows, 100), 51);
+    let ((_, rows, _, _), _) =
+        reload_page_data_cached(query, &mut cache, Some(dataset_id), 0, i64::MAX, &options)
+            .unwrap();
+    assert_eq!(rows.len() as i64, total_rows);
+    assert_eq!(page_count(total_rows, i64::MAX), 1);
+    query
+        .save_page_size(DatasetId(dataset_id), None)
+        .expect("reset page size");
+    assert_eq!(query.load_page_size(DatasetId(dataset_id)).unwrap(), None);
+
+    // The command line is not paged.
+    let output = temp_dir.join("all.csv");
+    run_cli(CliInvocation {
+        db_path: Some(temp_dir.join("bom.sqlite")),
+        command: CliCommand::Export {
+            dataset_id,
+            output: Some(output.clone()),
+        },
+    })
+    .expect("export");
+    let exported = fs::read_to_string(&output).expect("read export");
+    assert_eq!(exported.lines().count() as i64, PAGE_SIZE + 2);
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn desktop_exports_write_every_page_of_the_shown_columns() {
+    let temp_dir = unique_test_dir("export_all_pages");
+    fs::create_dir_all(&temp_dir).expect("create temp dir");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let headers = vec!["序號".to_string(), "備註".to_string()];
+    let rows = (0..PAGE_SIZE + 10)
+        .map(|idx| vec![idx.to_string(), format!("第{idx}列")])
+        .collect::<Vec<_>>();
+    let dataset_id =
+        create_dataset_from_rows(&db_path, "大量", "test", &headers, &rows).expect("create");
+    let services = AppServices::new(db_path);
+    let options = QueryOptions::default();
+
+    let (columns, all_rows) =
+        load_all_rows(&services.query_service, dataset_id, &options).expect("load all");
+    let visibility = BTreeMap::from([(1, false)]);
+    let (shown_columns, shown_rows) = apply_column_visibility(&columns, &all_rows, &visibility);
+    assert_eq!(shown_columns, vec![(0, "序號".to_string())]);
+    assert_eq!(shown_rows.len() as i64, PAGE_SIZE + 10);
+    assert_eq!(shown_rows[0], vec!["0".to_string()]);
+    assert_eq!(shown_rows.last(), Some(&vec![(PAGE_SIZE + 9).to_string()]));
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn repeated_page_queries_reuse_the_cache_until_the_dataset_changes() {
+    let temp_dir = unique_test_dir("page_cache");
+    fs::create_dir_all(&temp_dir).expect("create temp dir");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let headers = vec!["名稱".to_string(), "市值".to_string()];
+    let rows = vec![
+        vec!["甲".to_string(), "100".to_string()],
+        vec!["乙".to_string(), "200".to_string()],
+    ];
+    let dataset_id =
+        create_dataset_from_rows(&db_path, "資產", "test", &headers, &rows).expect("create");
+    let services = AppServices::new(db_path);
+    let query = &services.query_service;
+    let mut cache = PageCache::default();
+    let options = QueryOptions::default();
+
+    let (first, reused) =
+        reload_page_data_cached(query, &mut cache, Some(dataset_id), 0, PAGE_SIZE, &options)
+            .unwrap();
+    assert!(!reused);
+    let (second, reused) =
+        reload_page_data_cached(query, &mut cache, Some(dataset_id), 0, PAGE_SIZE, &options)
+            .unwrap();
+    assert!(reused);
+    assert_eq!(first, second);
+
+    let searched = QueryOptions {
+        global_search: "甲".to_string(),
+        ..QueryOptions::default()
+    };
+    let (result, reused) =
+        reload_page_data_cached(query, &mut cache, Some(dataset_id), 0, PAGE_SIZE, &searched)
+            .unwrap();
+    assert!(!reused);
+    assert_eq!(result.2, 1);
+
+    services
+        .edit_service
+        .apply_edits(
+            DatasetId(dataset_id),
+            StagedEdits {
+                added_rows: vec![vec!["丙".to_string(), "300".to_string()]],
+                ..StagedEdits::default()
+            },
+        )
+        .expect("apply edits");
+    reload_page_data_cached(query, &mut cache, Some(dataset_id), 0, PAGE_SIZE, &options).unwrap();
+    let (result, reused) =
+        reload_page_data_cached(query, &mut cache, Some(dataset_id), 0, PAGE_SIZE, &options)
+            .unwrap();
+    assert!(reused);
+    assert_eq!(result.2, 3);
+
+    query
+        .save_computed_columns(
+            DatasetId(dataset_id),
+            vec![ComputedColumn {
+                name: "兩倍".to_string(),
+                expression: "市值 * 2".to_string(),
+            }],
+        )
+        .expect("save computed");
+    let (result, reused) =
+        reload_page_data_cached(query, &mut cache, Some(dataset_id), 0, PAGE_SIZE, &options)
+            .unwrap();
+    assert!(!reused, "new computed columns change the page");
+    assert_eq!(result.0.len(), 3);
+}
+
+#[test]
+fn dataset_context_reads_list_page_visibility_and_flags_together() {
+    let temp_dir = unique_test_dir("dataset_context");
+    fs::create_dir_all(&temp_dir).expect("create temp dir");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let headers = vec!["名稱".to_string(), "市值".to_string()];
+    let rows = vec![vec!["甲".to_string(), "100".to_string()]];
+    let first = create_dataset_from_rows(&db_path, "資產", "a", &headers, &rows).expect("create");
+    let second = create_dataset_from_rows(&db_path, "持股", "b", &headers, &rows).expect("create");
+    let services = AppServices::new(db_path);
+    let query = &services.query_service;
+    query
+        .upsert_column_visibility(DatasetId(first), BTreeMap::from([(1, false)]))
+        .expect("save visibility");
+    query
+        .upsert_holdings_flag(DatasetId(second), true)
+        .expect("save flag");
+
+    let context = query
+        .load_dataset_context(false, |datasets| {
+            assert_eq!(datasets.len(), 2);
+            Some(build_page_query(first, 0, &QueryOptions::default()))
+        })
+        .expect("load context");
+    assert_eq!(context.datasets.len(), 2);
+    let (dataset_id, page) = context.page.expect("page of the picked dataset");
+    assert_eq!(dataset_id, DatasetId(first));
+    assert_eq!(page.columns, headers);
+    assert_eq!(page.rows, rows);
+    assert_eq!(context.column_visibility, BTreeMap::from([(1, false)]));
+    assert_eq!(context.holdings_flags, BTreeMap::from([(second, true)]));
+
+    let empty = query
+        .load_dataset_context(false, |_| None)
+        .expect("load context without a pick");
+    assert!(empty.page.is_none());
+    assert!(empty.column_visibility.is_empty());
+    assert_eq!(empty.datasets.len(), 2);
+}
+
+#[test]
+fn clone_dataset_copies_cells_and_every_per_dataset_setting() {
+    let temp_dir = unique_test_dir("clone_dataset");
+    fs::create_dir_all(&temp_dir).expect("create temp dir");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let headers = vec!["名稱".to_string(), "市值".to_string()];
+    let rows = vec![
+        vec!["甲".to_string(), "100".to_string()],
+        vec!["乙".to_string(), "200".to_string()],
+    ];
+    let source =
+        create_dataset_from_rows(&db_path, "持股", "a.xlsx#持股", &headers, &rows).expect("create");
+    let visibility = BTreeMap::from([(0, true), (1, false)]);
+    upsert_column_visibility(&db_path, source, &visibility).expect("save visibility");
+    let aliases = BTreeMap::from([(1, "Value".to_string())]);
+    upsert_column_aliases(&db_path, source, &aliases).expect("save aliases");
+    save_visibility_preset(&db_path, source, "quick", &visibility).expect("save preset");
+    let formats = BTreeMap::from([(
+        1,
+        ColumnFormat {
+            decimals: 0,
+            percent: false,
+            thousands_separator: true,
+            currency_symbol: String::new(),
+        },
+    )]);
+    upsert_column_formats(&db_path, source, &formats).expect("save formats");
+    upsert_holdings_flag(&db_path, source, true).expect("save flag");
+    let display = DatasetDisplay {
+        currency: "USD".to_string(),
+        unit: DisplayUnit::TenThousand,
+    };
+    save_dataset_display(&db_path, source, &display).expect("save display");
+    let services = AppServices::new(db_path.clone());
+    let computed = vec![ComputedColumn {
+        name: "兩倍".to_string(),
+        expression: "市值 * 2".to_string(),
+    }];
+    services
+        .query_service
+        .save_computed_columns(DatasetId(source), computed.clone())
+        .expect("save computed columns");
+    services
+        .query_service
+        .save_page_size(DatasetId(source), Some(500))
+        .expect("save page size");
+
+    let copy = services
+        .edit_service
+        .clone_dataset(
+            DatasetId(source),
+            NewDatasetMeta {
+                name: "持股備份".to_string(),
+                source_path: "a.xlsx#持股備份".to_string(),
+            },
+        )
+        .expect("clone dataset")
+        .0;
+    assert_ne!(copy, source);
+
+    let (columns, copied_rows, total) =
+        query_page(&db_path, copy, 0, i64::MAX, &QueryOptions::default()).expect("query copy");
+    assert_eq!(columns, vec!["名稱", "市值", "兩倍"]);
+    assert_eq!(total, 2);
+    assert_eq!(copied_rows[1], vec!["乙", "200", "400"]);
+    assert_eq!(load_column_visibility(&db_path, copy).unwrap(), visibility);
+    assert_eq!(load_column_aliases(&db_path, copy).unwrap(), aliases);
+    assert_eq!(
+        load_visibility_presets(&db_path, copy).unwrap(),
+        load_visibility_presets(&db_path, source).unwrap()
+    );
+    assert_eq!(load_column_formats(&db_path, copy).unwrap(), formats);
+    assert_eq!(load_dataset_display(&db_path, copy).unwrap(), display);
+    assert_eq!(
+        load_holdings_flags(&db_path).unwrap().get(&copy),
+        Some(&true)
+    );
+    assert_eq!(
+        services
+            .query_service
+            .load_computed_columns(DatasetId(copy))
+            .unwrap(),
+        computed
+    );
+    assert_eq!(
+        services
+            .query_service
+            .load_page_size(DatasetId(copy))
+            .unwrap(),
+        Some(500)
+    );
+    let listed = list_datasets(&db_path, false).unwrap();
+    let meta = listed.iter().find(|d| d.id.0 == copy).expect("copy listed");
+    assert_eq!(meta.name, "持股備份");
+    assert_eq!(meta.row_count, 2);
+}
+
+#[test]
+fn ui_session_round_trips_and_tolerates_damaged_settings() {
+    let session = UiSession {
+        dataset_id: Some(7),
+        options: QueryOptions {
+            global_search: "台積電".to_string(),
+            column_search_col: Some(2),
+            column_search_text: "00".to_string(),
+            value_filters: BTreeMap::from([(3, BTreeSet::from(["股票".to_string()]))]),
+            column_conditions: BTreeMap::from([(5, "< 0".to_string())]),
+            sort_col: Some(1),
+            sort_desc: true,
+        },
+        page: 2,
+        scroll_top: 480.5,
+        chart_tab: true,
+        detail_row: Some(4),
+        split_dataset_id: Some(None),
+    };
+    assert_eq!(UiSession::parse(&session.to_setting()), session);
+
+    assert_eq!(UiSession::parse("not json"), UiSession::default());
+    let partial =
+        UiSession::parse(r#"{"dataset_id": 3, "page": -1, "value_filters": {"x": ["a"]}}"#);
+    assert_eq!(partial.dataset_id, Some(3));
+    assert_eq!(partial.page, 0);
+    assert!(partial.options.value_filters.is_empty());
+    assert_eq!(partial.split_dataset_id, None);
+}
+
+#[test]
+fn holding_detail_gathers_positions_dividends_prices_and_trades_of_one_code() {
+    let temp_dir = unique_test_dir("holding-detail");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let services = AppServices::new(db_path);
+    let create = |name: &str, columns: &[&str], rows: &[&[&str]]| {
+        services
+            .edit_service
+            .create_dataset(
+                NewDatasetMeta {
+                    name: name.to_string(),
+                    source_path: format!("帳本.xlsx#{name}"),
+                },
+                TabularData {
+                    columns: columns.iter().map(|column| column.to_string()).collect(),
+                    rows: rows
+                        .iter()
+                        .map(|row| row.iter().map(|value| value.to_string()).collect())
+                        .collect(),
+                },
+            )
+            .expect("create dataset")
+    };
+    let holdings_id = create(
+        "持股股息總表",
+        &["代號", "名稱", "淨值", "備註", "2024年", "2025年", "2月"],
+        &[
+            &["2330", "台積電", "60000", "長期持有", "800", "1000", "300"],
+            &["0056", "元大高股息", "8000", "", "400", "450", "50"],
+            &["2330", "台積電", "6000", "", "", "100", "30"],
+        ],
+    );
+    let trades_id = create(
+        "交易紀錄",
+        &["日期", "代號", "買賣", "數量", "成交價"],
+        &[
+            &["2025/03/01", "2330", "買進", "60", "500"],
+            &["2025/04/01", "0056", "買進", "500", "30"],
+            &["2025/05/01", "2330", "賣出", "50", "700"],
+        ],
+    );
+    services
+        .edit_service
+        .record_prices(
+            holdings_id,
+            vec![PricePoint {
+                code: "2330".to_string(),
+                price: 600.0,
+                fetched_at: "2026-01-05 13:30:00".to_string(),
+            }],
+        )
+        .expect("record prices");
+    let query = &services.query_service;
+    save_holding_note(query, "2330", "  法說會後再評估  ").expect("save note");
+
+    assert_eq!(
+        holding_codes(query).expect("codes"),
+        [
+            ("0056".to_string(), "元大高股息".to_string()),
+            ("2330".to_string(), "台積電".to_string()),
+        ]
+    );
+
+    let detail = collect_holding_detail(query, " 2330 ").expect("collect detail");
+    assert_eq!(detail.code, "2330");
+    assert_eq!(detail.name, "台積電");
+    assert_eq!(
+        detail
+            .positions
+            .iter()
+            .map(|position| (position.dataset_id, position.row_idx))
+            .collect::<Vec<_>>(),
+        [(holdings_id, 0), (holdings_id, 2)]
+    );
+    assert_eq!(
+        detail.positions[1].fields,
+        [("淨值".to_string(), "6000".to_string())]
+    );
+    assert_eq!(
+        detail
+            .dividends
+            .iter()
+            .map(|period| (period.label.as_str(), period.amount))
+            .collect::<Vec<_>>(),
+        [("2024年", 800.0), ("2025年", 1100.0), ("2026年2月", 330.0)]
+    );
+    assert_eq!(detail.prices.len(), 1);
+    assert_eq!(detail.remarks, ["長期持有"]);
+    assert_eq!(detail.note, "法說會後再評估");
+    assert_eq!(detail.related.len(), 1);
+    assert_eq!(detail.related[0].dataset_id, trades_id);
+    assert_eq!(
+        detail.related[0]
+            .rows
+            .iter()
+            .map(|(row_idx, _)| *row_idx)
+            .collect::<Vec<_>>(),
+        [0, 2]
+    );
+    // Bought 60 for 30,042 with fees, sold 50 for 34,846 after fees and tax.
+    let position = detail.related[0]
+        .trade_cost
+        .expect("a trade ledger")
+        .expect("no oversell");
+    assert_eq!(position.quantity, 10.0);
+    assert!((position.cost_basis - 5007.0).abs() < 1e-6);
+    assert!((position.realized_gain - 9811.0).abs() < 1e-6);
+
+    let missing = collect_holding_detail(query, "9999").expect("collect missing");
+    assert!(missing.positions.is_empty() && missing.dividends.is_empty());
+    assert!(missing.name.is_empty() && missing.related.is_empty());
+    assert!(dividend_history(&[]).is_empty());
+}
+
+#[test]
+fn holding_detail_leaves_out_save_as_copies_and_year_archives() {
+    let temp_dir = unique_test_dir("holding-detail-copies");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let services = AppServices::new(db_path);
+    let create = |name: &str, source_path: String| {
+        services
+            .edit_service
+            .create_dataset(
+                NewDatasetMeta {
+                    name: name.to_string(),
+                    source_path,
+                },
+                TabularData {
+                    columns: ["代號", "名稱", "淨值", "2025年", "2月"]
+                        .map(String::from)
+                        .to_vec(),
+                    rows: vec![["2330", "台積電", "60000", "1000", "300"]
+                        .map(String::from)
+                        .to_vec()],
+                },
+            )
+            .expect("create dataset")
+    };
+    let query = &services.query_service;
+    let live_id = create("持股股息總表", "帳本.xlsx#持股股息總表".to_string());
+    let live = query
+        .list_datasets(false)
+        .expect("list")
+        .into_iter()
+        .find(|meta| meta.id == live_id)
+        .expect("live dataset");
+    create("持股備份", live.save_as_source_path("持股備份"));
+    create("持股股息總表 2024年", live.source_path.clone());
+
+    let detail = collect_holding_detail(query, "2330").expect("collect detail");
+    assert_eq!(
+        detail
+            .positions
+            .iter()
+            .map(|position| position.dataset_id)
+            .collect::<Vec<_>>(),
+        [live_id]
+    );
+    assert_eq!(
+        detail
+            .dividends
+            .iter()
+            .map(|period| (period.label.as_str(), period.amount))
+            .collect::<Vec<_>>(),
+        [("2025年", 1000.0), ("2026年2月", 300.0)]
+    );
+    assert!(detail.related.is_empty());
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn year_comparison_sets_two_summaries_side_by_side() {
+    let temp_dir = unique_test_dir("year-comparison");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let services = AppServices::new(db_path);
+    let cells = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+    let headers = cells(&["代號", "總成本", "淨值", "今年度累積"]);
+    let last_year = compute_summary_report(
+        &headers,
+        &[
+            cells(&["2330", "40000", "50000", "1000"]),
+            cells(&["0056", "10000", "9000", "600"]),
+        ],
+    );
+    let dataset_id = services
+        .edit_service
+        .create_dataset(
+            NewDatasetMeta {
+                name: "持股股息總表".to_string(),
+                source_path: "帳本.xlsx#持股股息總表".to_string(),
+            },
+            TabularData {
+                columns: headers.clone(),
+                rows: vec![cells(&["2330", "60000", "90000", "2400"])],
+            },
+        )
+        .expect("create dataset");
+    let query = &services.query_service;
+    let snapshot = query
+        .save_summary_report(dataset_id, &last_year)
+        .expect("save snapshot");
+
+    let sources = summary_sources(query).expect("sources");
+    assert_eq!(
+        sources
+            .iter()
+            .map(|option| option.source)
+            .collect::<Vec<_>>(),
+        [
+            SummarySource::Dataset(dataset_id),
+            SummarySource::Snapshot {
+                dataset_id,
+                id: snapshot.id
+            },
+        ]
+    );
+    for option in &sources {
+        assert_eq!(
+            SummarySource::parse(&option.source.key()),
+            Some(option.source)
+        );
+    }
+    assert_eq!(SummarySource::parse("d:1:2"), None);
+
+    let before = load_summary(query, sources[1].source).expect("load snapshot");
+    let after = load_summary(query, sources[0].source).expect("load dataset");
+    let comparison = compare_summaries("2025", &before, "2026", &after);
+    let (headers, rows) = year_comparison_table(&comparison);
+    assert_eq!(
+        headers,
+        cells(&["項目", "2025", "2026", "增減", "增減幅度"])
+    );
+    assert_eq!(
+        rows,
+        vec![
+            cells(&["總成本", "50000", "60000", "10000", "20.00%"]),
+            cells(&["淨值", "59000", "90000", "31000", "52.54%"]),
+            cells(&["已收股息", "1600", "2400", "800", "50.00%"]),
+            cells(&["殖利率", "3.20%", "4.00%", "0.80%", ""]),
+        ]
+    );
+
+    let empty = compare_summaries("甲", &SummaryReport::default(), "乙", &after);
+    assert_eq!(empty.rows[0].delta(), None);
+    assert_eq!(empty.rows[0].change_ratio(), None);
+}
+
+#[test]
+fn income_goal_discounts_future_income_to_todays_dollars() {
+    let temp_dir = unique_test_dir("income-goal");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let services = AppServices::new(db_path);
+    let cells = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+    services
+        .edit_service
+        .create_dataset(
+            NewDatasetMeta {
+                name: "持股股息總表".to_string(),
+                source_path: "帳本.xlsx#持股股息總表".to_string(),
+            },
+            TabularData {
+                columns: cells(&["名稱", "代號", "總成本", "估計配息"]),
+                rows: vec![
+                    cells(&["台積電", "2330", "1", "12000"]),
+                    cells(&["高股息", "0056", "1", "24000"]),
+                    cells(&["合計", "", "2", "36000"]),
+                ],
+            },
+        )
+        .expect("create dataset");
+    let query = &services.query_service;
+
+    assert_eq!(
+        load_income_goal(query).expect("load"),
+        IncomeGoal::default()
+    );
+    let goal = IncomeGoal {
+        monthly_target: 5000.0,
+        inflation_rate: 0.02,
+    };
+    save_income_goal(query, &goal).expect("save goal");
+    assert_eq!(load_income_goal(query).expect("reload"), goal);
+
+    let monthly = estimated_monthly_income(query).expect("income");
+    assert_eq!(monthly, 3000.0);
+    let holdings = query.list_datasets(false).expect("list")[0].clone();
+    services
+        .edit_service
+        .clone_dataset(
+            holdings.id,
+            NewDatasetMeta {
+                name: "持股 備份".to_string(),
+                source_path: holdings.save_as_source_path("持股 備份"),
+            },
+        )
+        .expect("save as");
+    assert_eq!(estimated_monthly_income(query).expect("income"), 3000.0);
+    let rows = project_income(monthly, &goal, &[0, 10]);
+    assert_eq!(rows[0].real_monthly, 3000.0);
+    assert!((rows[1].real_monthly - 2461.04).abs() < 0.01);
+    let (headers, table) = income_projection_table(&rows, &goal);
+    assert_eq!(
+        headers,
+        cells(&["年後", "每月收入", "今日幣值", "目標（當年幣值）", "達成率"])
+    );
+    assert_eq!(
+        table,
+        vec![
+            cells(&["0", "3000", "3000", "5000", "60.00%"]),
+            cells(&["10", "3000", "2461", "6095", "49.22%"]),
+        ]
+    );
+
+    let (headers, _) = income_projection_table(&rows, &IncomeGoal::default());
+    assert_eq!(headers.len(), 3);
+}
+
+#[test]
+fn retirement_projection_spreads_trials_into_percentile_bands() {
+    let temp_dir = unique_test_dir("retirement");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let services = AppServices::new(db_path);
+    let cells = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+    for (name, columns, rows) in [
+        (
+            "持股",
+            cells(&["名稱", "性質", "淨值"]),
+            vec![
+                cells(&["台積電", "股票", "600000"]),
+                cells(&["美債", "債券", "300000"]),
+                cells(&["合計", "", "900000"]),
+            ],
+        ),
+        (
+            "資產總表",
+            cells(&["資產形式", "餘額"]),
+            vec![cells(&["定存", "100000"]), cells(&["活存", "50000"])],
+        ),
+    ] {
+        services
+            .edit_service
+            .create_dataset(
+                NewDatasetMeta {
+                    name: name.to_string(),
+                    source_path: format!("帳本.xlsx#{name}"),
+                },
+                TabularData { columns, rows },
+            )
+            .expect("create dataset");
+    }
+    let query = &services.query_service;
+
+    let start = starting_allocation(query).expect("allocation");
+    assert_eq!(start, [600000.0, 300000.0, 100000.0]);
+    for dataset in query.list_datasets(false).expect("list") {
+        let name = format!("{} 備份", dataset.name);
+        services
+            .edit_service
+            .clone_dataset(
+                dataset.id,
+                NewDatasetMeta {
+                    source_path: dataset.save_as_source_path(&name),
+                    name,
+                },
+            )
+            .expect("save as");
+    }
+    assert_eq!(starting_allocation(query).expect("allocation"), start);
+    assert_eq!(AssetClass::of_nature("債券"), AssetClass::Bond);
+
+    let mut plan = RetirementPlan {
+        years: 10,
+        trials: 200,
+        ..RetirementPlan::default()
+    };
+    assert_eq!(
+        load_retirement_plan(query).expect("load"),
+        RetirementPlan::default()
+    );
+    save_retirement_plan(query, &plan).expect("save plan");
+    assert_eq!(load_retirement_plan(query).expect("reload"), plan);
+    assert_eq!(
+        RetirementPlan::parse_setting("years=0;債=x"),
+        RetirementPlan::default()
+    );
+
+    let projection = simulate_retirement(start, &plan, 0.0);
+    assert_eq!(projection.bands.len(), 11);
+    assert_eq!(projection.bands[0].value, [1_000_000.0; 5]);
+    assert!((projection.bands[0].monthly_income[2] - 37600.0 / 12.0).abs() < 1e-6);
+    for band in &projection.bands {
+        assert!(band.value.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+    let last = &projection.bands[10];
+    assert!(last.value[0] < last.value[2] && last.value[2] < last.value[4]);
+    assert_eq!(projection, simulate_retirement(start, &plan, 0.0));
+
+    // Without volatility every trial compounds the same way.
+    for assumption in &mut plan.assumptions {
+        assumption.volatility = 0.0;
+    }
+    let steady = simulate_retirement(start, &plan, 0.02);
+    let expected =
+        600000.0 * 1.06_f64.powi(10) + 300000.0 * 1.03_f64.powi(10) + 100000.0 * 1.016_f64.powi(10);
+    let today = expected / 1.02_f64.powi(10);
+    assert!(steady.bands[10]
+        .value
+        .iter()
+        .all(|v| (v - today).abs() < 1e-6));
+
+    let (headers, rows) = retirement_projection_table(&steady);
+    assert_eq!(headers.len(), 7);
+    assert_eq!(headers[2], "淨值 中位數");
+    assert_eq!(rows[0][..2], cells(&["0", "1000000"]));
+}
+
+#[test]
+fn trade_costs_fold_broker_fees_and_tax_into_cost_and_gains() {
+    let trade = |side: &str, quantity: f64, price: f64| Trade {
+        code: "2330".to_string(),
+        side: TradeSide::parse(side).expect("side"),
+        quantity,
+        price,
+        fee: None,
+        tax: None,
+    };
+    let schedule = FeeSchedule::default();
+    assert_eq!(schedule.commission(500_000.0), 712.0);
+    assert_eq!(schedule.commission(10.0), 20.0);
+    let discounted = FeeSchedule {
+        commission_discount: 0.6,
+        ..schedule
+    };
+    assert_eq!(discounted.commission(500_000.0), 427.0);
+    assert_eq!(transaction_tax("2330", TradeSide::Sell, 350_000.0), 1050.0);
+    assert_eq!(transaction_tax("0056", TradeSide::Sell, 30_000.0), 30.0);
+    assert_eq!(transaction_tax("2330", TradeSide::Buy, 350_000.0), 0.0);
+    assert_eq!(TradeSide::parse("股利"), None);
+
+    let trades = [
+        trade("買進", 1000.0, 500.0),
+        trade("買", 1000.0, 600.0),
+        trade("賣出", 500.0, 700.0),
+    ];
+    let position = position_cost(&trades, &schedule).expect("position");
+    assert_eq!(position.quantity, 1500.0);
+    assert_eq!(position.fees, 712.0 + 855.0 + 498.0);
+    assert_eq!(position.taxes, 1050.0);
+    assert!((position.cost_basis - 826_175.25).abs() < 1e-6);
+    assert!((position.realized_gain - 73_060.25).abs() < 1e-6);
+
+    let billed = Trade {
+        fee: Some(100.0),
+        tax: Some(0.0),
+        ..trade("賣", 500.0, 700.0)
+    };
+    assert_eq!(billed.settlement(&schedule), 349_900.0);
+
+    // Selling more than was bought is refused rather than counted as gain.
+    assert_eq!(
+        position_cost(
+            &[trade("買", 100.0, 500.0), trade("賣", 150.0, 600.0)],
+            &schedule
+        ),
+        Err(Oversold {
+            sold: 150.0,
+            held: 100.0
+        })
+    );
+
+    let columns = ["日期", "代號", "買賣", "數量", "成交價", "手續費"]
+        .map(String::from)
+        .to_vec();
+    let rows = [
+        ["2025/01/02", "2330", "買進", "1,000", "500", ""],
+        ["2025/03/01", "2330", "股利", "", "", ""],
+        ["2025/04/01", "2330", "賣出", "500", "700", "300"],
+    ]
+    .map(|row| row.map(String::from).to_vec());
+    let ledger = ledger_trades(&columns, &rows).expect("ledger");
+    assert_eq!(ledger.len(), 2);
+    assert_eq!((ledger[0].quantity, ledger[0].fee), (1000.0, None));
+    assert_eq!(
+        (ledger[1].side, ledger[1].fee),
+        (TradeSide::Sell, Some(300.0))
+    );
+    assert_eq!(ledger_trades(&columns[..4], &rows), None);
+}
+
+#[test]
+fn dividends_saved_in_month_columns_are_swept_into_the_owners_cash() {
+    let temp_dir = unique_test_dir("dividend-sweep");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let services = AppServices::new(db_path);
+    let cells = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+    let create = |name: &str, columns: Vec<String>, rows: Vec<Vec<String>>| {
+        services
+            .edit_service
+            .create_dataset(
+                NewDatasetMeta {
+                    name: name.to_string(),
+                    source_path: format!("帳本.xlsx#{name}"),
+                },
+                TabularData { columns, rows },
+            )
+            .expect("create dataset")
+    };
+    let assets_id = create(
+        "資產總表",
+        cells(&["資產形式", "所有權人", "餘額"]),
+        vec![
+            cells(&["定存", "王小明", "5000"]),
+            cells(&["活存", "王小明", "1000"]),
+            cells(&["活存", "陳美華", "2000"]),
+        ],
+    );
+    let dividends_id = create(
+        "持股股息總表",
+        cells(&["名稱", "所有權人", "1月", "2月"]),
+        vec![
+            cells(&["台積電", "王小明", "", ""]),
+            cells(&["高股息", "陳美華", "300", ""]),
+        ],
+    );
+    let edit = |changes: &[(usize, usize, &str)]| {
+        let staged_cells = changes
+            .iter()
+            .map(|&(row_idx, col_idx, value)| {
+                let column = ["名稱", "所有權人", "1月", "2月"][col_idx].to_string();
+                let key = CellKey {
+                    row_idx,
+                    col_idx,
+                    column,
+                };
+                (key, value.to_string())
+            })
+            .collect();
+        services
+            .edit_service
+            .apply_edits(
+                dividends_id,
+                StagedEdits {
+                    staged_cells,
+                    ..StagedEdits::default()
+                },
+            )
+            .expect("apply edits");
+    };
+    let balances = || {
+        let page = services
+            .query_service
+            .query_page(build_page_query(assets_id.0, 0, &QueryOptions::default()))
+            .expect("assets");
+        page.rows
+            .iter()
+            .map(|row| row[2].clone())
+            .collect::<Vec<_>>()
+    };
+
+    // Nothing is swept until it is turned on.
+    edit(&[(0, 3, "50")]);
+    assert_eq!(balances(), cells(&["5000", "1000", "2000"]));
+    services
+        .query_service
+        .save_setting(DIVIDEND_SWEEP_KEY, "on")
+        .expect("turn on");
+
+    // The 300 entered before the first sweep was already in the balance.
+    edit(&[(0, 2, "700")]);
+    assert_eq!(balances(), cells(&["5000", "1700", "2000"]));
+    edit(&[(0, 2, "650"), (1, 3, "200")]);
+    assert_eq!(balances(), cells(&["5000", "1650", "2200"]));
+    assert_eq!(
+        received_by_owner(&cells(&["所有權人", "1月"]), &[cells(&["王小明", "1"])]),
+        Some(BTreeMap::from([("王小明".to_string(), 1.0)]))
+    );
+
+    services
+        .query_service
+        .save_setting(DIVIDEND_SWEEP_KEY, "off")
+        .expect("turn off");
+    edit(&[(1, 3, "400")]);
+    assert_eq!(balances(), cells(&["5000", "1650", "2200"]));
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn save_as_copies_take_no_part_in_the_dividend_sweep() {
+    let temp_dir = unique_test_dir("dividend-sweep-copies");
+    let db_path = temp_dir.join("bom.sqlite");
+    init_db(&db_path).expect("init db");
+    let services = AppServices::new(db_path);
+    let query = &services.query_service;
+    query
+        .save_setting(DIVIDEND_SWEEP_KEY, "on")
+        .expect("turn on");
+    let cells = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+    let create = |name: &str, source_path: String, columns: &[&str], rows: &[&[&str]]| {
+        services
+            .edit_service
+            .create_dataset(
+                NewDatasetMeta {
+                    name: name.to_string(),
+                    source_path,
+                },
+                TabularData {
+                    columns: cells(columns),
+                    rows: rows.iter().map(|row| cells(row)).collect(),
+                },
+            )
+            .expect("create dataset")
+    };
+    let meta = |id: DatasetId| {
+        query
+            .list_datasets(false)
+            .expect("list")
+            .into_iter()
+            .find(|meta| meta.id == id)
+            .expect("dataset")
+    };
+    let assets_columns = ["資產形式", "所有權人", "餘額"];
+    let assets_rows: &[&[&str]] = &[&["活存", "王小明", "1000"]];
+    let dividend_columns = ["名稱", "所有權人", "1月"];
+    let dividend_rows: &[&[&str]] = &[&["台積電", "王小明", ""]];
+    let assets_id = create(
+        "資產總表",
+        "帳本.xlsx#資產總表".to_string(),
+        &assets_columns,
+        assets_rows,
+    );
+    let dividends_id = create(
+        "持股股息總表",
+        "帳本.xlsx#持股股息總表".to_string(),
+        &dividend_columns,
+        dividend_rows,
+    );
+    // Both copies are newer than the datasets they were saved from.
+    let assets_copy_id = create(
+        "資產備份",
+        meta(assets_id).save_as_source_path("資產備份"),
+        &assets_columns,
+        assets_rows,
+    );
+    let dividends_copy_id = create(
+        "股息備份",
+        meta(dividends_id).save_as_source_path("股息備份"),
+        &dividend_columns,
+        dividend_rows,
+    );
+    let edit = |dataset_id: DatasetId, value: &str| {
+        let key = CellKey {
+            row_idx: 0,
+            col_idx: 2,
+            column: "1月".to_string(),
+        };
+        services
+            .edit_service
+            .apply_edits(
+                dataset_id,
+                StagedEdits {
+                    staged_cells: HashMap::from([(key, value.to_string())]),
+                    ..StagedEdits::default()
+                },
+            )
+            .expect("apply edits");
+    };
+    let balance = |dataset_id: DatasetId| {
+        query
+            .query_page(build_page_query(dataset_id.0, 0, &QueryOptions::default()))
+            .expect("assets")
+            .rows[0][2]
+            .clone()
+    };
+
+    edit(dividends_copy_id, "300");
+    assert_eq!(balance(assets_id), "1000");
+    assert_eq!(balance(assets_copy_id), "1000");
+    edit(dividends_id, "500");
+    assert_eq!(balance(assets_id), "1500");
+    assert_eq!(balance(assets_copy_id), "1000");
+    fs::remove_dir_all(&temp_dir).ok();
+}
+
+#[test]
+fn expected_payouts_follow_periods_and_months_received() {
+    let cells = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+    let headers = cells(&["名稱", "期數", "估計配息金額", "1月", "2月", "3月", "4月"]);
+    let rows = vec![
+        cells(&["月配", "12", "1200", "100", "100", "", ""]),
+        cells(&["季配", "4", "", "700", "0", "", ""]),
+        cells(&["年配", "1", "4700", "", "", "", ""]),
+        cells(&["不配", "", "", "", "", "", ""]),
+        cells(&["合計", "12", "9999", "", "", "", ""]),
+    ];
+    let payouts = |month: u32| {
+        expected_payouts(&headers, &rows, month)
+            .into_iter()
+            .map(|payout| (payout.name, payout.expected))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        payouts(3),
+        vec![
+            ("月配".to_string(), Some(100.0)),
+            ("年配".to_string(), Some(4700.0)),
+        ]
+    );
+    assert_eq!(
+        payouts(4),
+        vec![
+            ("月配".to_string(), Some(100.0)),
+            ("季配".to_string(), Some(700.0)),
+            ("年配".to_string(), Some(4700.0)),
+        ]
+    );
+    assert_eq!(payouts(2), vec![("年配".to_string(), Some(4700.0))]);
+    assert_eq!(month_column(&headers, 4), Some(6));
+    assert!(payouts(5).is_empty());
+}
+
+#[test]
+fn column_conditions_filter_stored_and_computed_columns_by_value() {
+    assert_eq!(
+        parse_filter_expr("＞＝ 1,000"),
+        Some(FilterExpr::Compare(CompareOp::Ge, 1000.0))
+    );
+    assert_eq!(
+        parse_filter_expr("5~1"),
+        Some(FilterExpr::Between(1.0, 5.0))
+    );
+    assert_eq!(parse_filter_expr("!="), Some(FilterExpr::NotEmpty));
+    assert_eq!(parse_filter_expr("> abc"), None);
+    assert!(parse_filter_expr("= 股票").is_some_and(|expr| expr.matches(" 股票 ")));
+
+    let temp_dir = unique_test_dir("column-conditions");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    let row = |cells: [&str; 3]| cells.map(str::to_string).to_vec();
+    let dataset_id = create_dataset_from_rows(
+        &db_path,
+        "持股",
+        "test",
+        &row(["名稱", "損益率", "數量"]),
+        &[
+            row(["甲", "-5%", "1,200"]),
+            row(["乙", "3%", "800"]),
+            row(["丙", "", "無"]),
+            row(["丁", "0", "1000"]),
+        ],
+    )
+    .expect("create");
+    let services = AppServices::new(db_path.clone());
+    services
+        .query_service
+        .save_computed_columns(
+            DatasetId(dataset_id),
+            vec![ComputedColumn {
+                name: "兩倍".to_string(),
+                expression: "數量 * 2".to_string(),
+            }],
+        )
+        .expect("save computed");
+    let names = |conditions: &[(i64, &str)]| {
+        let options = QueryOptions {
+            column_conditions: conditions
+                .iter()
+                .map(|(col, expression)| (*col, expression.to_string()))
+                .collect(),
+            ..QueryOptions::default()
+        };
+        let (_, rows, total) = query_page(&db_path, dataset_id, 0, 10, &options).expect("query");
+        assert_eq!(total as usize, rows.len());
+        rows.into_iter()
+            .map(|row| row[0].clone())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(names(&[(1, "< 0")]), ["甲"]);
+    assert_eq!(names(&[(2, "> 1000")]), ["甲"]);
+    assert_eq!(names(&[(1, "!= 0")]), ["甲", "乙", "丙"]);
+    assert_eq!(names(&[(1, "-0.05~0.03")]), ["甲", "乙", "丁"]);
+    assert_eq!(names(&[(1, "空")]), ["丙"]);
+    assert_eq!(names(&[(1, "非空"), (2, "<= 1000")]), ["乙", "丁"]);
+    assert_eq!(names(&[(2, "= 無")]), ["丙"]);
+    assert_eq!(
+        names(&[(1, "> abc")]).len(),
+        4,
+        "unreadable expressions do not filter"
+    );
+    assert_eq!(names(&[(3, ">= 2000")]), ["甲", "丁"]);
+    assert_eq!(names(&[(3, ">= 2000"), (1, "< 0")]), ["甲"]);
+
+    fs::remove_dir_all(&temp_dir).expect("should cleanup temp dir");
+}
+
+#[test]
+fn column_stats_cover_the_filtered_rows_with_or_without_computed_columns() {
+    let temp_dir = unique_test_dir("column-stats");
+    fs::create_dir_all(&temp_dir).expect("should create temp dir");
+    let db_path = temp_dir.join("app.sqlite");
+    let row = |cells: [&str; 3]| cells.map(str::to_string).to_vec();
+    let dataset_id = create_dataset_from_rows(
+        &db_path,
+        "持股",
+        "test",
+        &row(["名稱", "市值", "損益率"]),
+        &[
+            row(["甲", "1,000", "-5%"]),
+            row(["乙", "2,500", "10%"]),
+            row(["丙", "", ""]),
+            row(["丁", "無", ""]),
+        ],
+    )
+    .expect("create");
+    let services = AppServices::new(db_path.clone());
+    let stats = |options: &QueryOptions| {
+        services
+            .query_service
+            .column_stats(build_page_query(dataset_id, 0, options))
+            .expect("column stats")
+    };
+
+    let all = stats(&QueryOptions::default());
+    assert_eq!(
+        all[&1],
+        ColumnStats {
+            nonempty: 3,
+            numeric: 2,
+            min: Some(1000.0),
+            max: Some(2500.0),
+            sum: 3500.0,
+        }
+    );
+    assert_eq!(all[&1].average(), Some(1750.0));
+    assert_eq!(all[&2].min, Some(-0.05));
+    assert_eq!(all[&0].numeric, 0);
+    assert_eq!(
+        column_stats_tooltip(&all[&1]),
+        "最小 1,000.00\n最大 2,500.00\n平均 1,750.00\n總和 3,500.00\n非空 3 格"
+    );
+    assert_eq!(column_stats_tooltip(&all[&0]), "");
+
+    let large = QueryOptions {
+        column_conditions: BTreeMap::from([(1, "> 1500".to_string())]),
+        ..QueryOptions::default()
+    };
+    assert_eq!(stats(&large)[&1].sum, 2500.0);
+
+    services
+        .query_service
+        .save_computed_columns(
+            DatasetId(dataset_id),
+            vec![ComputedColumn {
+                name: "兩倍".to_string(),
+                expression: "市值 * 2".to_string(),
+            }],
+        )
+        .expect("save computed");
+    let with_computed = stats(&QueryOptions::default());
+    assert_eq!(with_computed[&1], all[&1]);
+    assert_eq!(with_computed[&2], all[&2]);
+    assert_eq!(with_computed[&3].sum, 7000.0);
+    assert_eq!(stats(&large)[&3].max, Some(5000.0));
+
+    fs::remove_dir_all(&temp_dir).expect("should cleanup temp dir");
+}
@@ -0,0 +1,100 @@
+use dioxus::prelude::*;
+
+use crate::ui::components::modal::ModalDialog;
+use bom_core::assets::{validate_account, AssetAccount, ACCOUNT_FIELDS, ASSET_FORMS};
+use bom_core::numeric::NumberLocale;
+
+/// Adds an account to a 資產總表. Each field has its own input, with the 資產形式, 所有權人
+/// and 幣別 already in use offered as suggestions, and the whole account is checked before
+/// it is staged.
+#[component]
+pub fn AccountForm(
+    columns: Vec<String>,
+    /// Rows the new account must not repeat: the stored ones not marked for deletion and
+    /// those added since the last save.
+    rows: Vec<Vec<String>>,
+    locale: NumberLocale,
+    on_add: EventHandler<AssetAccount>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let mut draft = use_signal(|| AssetAccount {
+        currency: "TWD".to_string(),
+        ..AssetAccount::default()
+    });
+    let mut error = use_signal(|| None::<String>);
+    let current = draft();
+    let suggestions = |field: &str, seed: &[&str]| {
+        let mut values: Vec<String> = seed.iter().map(|value| value.to_string()).collect();
+        if let Some(idx) = columns.iter().position(|column| column == field) {
+            for row in &rows {
+                let value = row.get(idx).map(|value| value.trim()).unwrap_or_default();
+                if !value.is_empty() && !values.iter().any(|known| known == value) {
+                    values.push(value.to_string());
+                }
+            }
+        }
+        values
+    };
+    let lists = [
+        ("account-form-forms", suggestions("資產形式", &ASSET_FORMS)),
+        ("account-form-owners", suggestions("所有權人", &[])),
+        (
+            "account-form-currencies",
+            suggestions("幣別", &["TWD", "USD"]),
+        ),
+    ];
+    let list_for = |field: &str| match field {
+        "資產形式" => "account-form-forms",
+        "所有權人" => "account-form-owners",
+        "幣別" => "account-form-currencies",
+        _ => "",
+    };
+
+    rsx! {
+        ModalDialog {
+            label: "新增帳戶".to_string(),
+            panel_style: "min-width: 360px;",
+            on_close: move |_| on_close.call(()),
+            div { style: "margin-bottom: 8px; font-weight: 600;", "新增帳戶" }
+            div { style: "display: grid; grid-template-columns: 120px 1fr; gap: 6px; align-items: center;",
+                for (field, value) in ACCOUNT_FIELDS.into_iter().zip(current.values()) {
+                    label { "{field}" }
+                    input {
+                        value: value.to_string(),
+                        list: list_for(field),
+                        inputmode: if field == "餘額" { "decimal" } else { "text" },
+                        oninput: move |event| {
+                            draft.write().set(field, event.value());
+                            error.set(None);
+                        },
+                    }
+                }
+            }
+            for (id, values) in lists {
+                datalist { id,
+                    for value in values {
+                        option { value }
+                    }
+                }
+            }
+            div { style: "margin-top: 8px; font-size: 12px; color: #555;",
+                "股票投資的餘額會在持股儲存後依淨值更新。"
+            }
+            if let Some(message) = error() {
+                div { style: "margin-top: 8px; color: #b00020;", "{message}" }
+            }
+            div { style: "display: flex; justify-content: flex-end; gap: 8px; margin-top: 12px;",
+                button {
+                    onclick: move |_| {
+                        match validate_account(&draft(), &columns, &rows, locale) {
+                            Ok(account) => on_add.call(account),
+                            Err(err) => error.set(Some(err.to_string())),
+                        }
+                    },
+                    "新增"
+                }
+                button { onclick: move |_| on_close.call(()), "取消" }
+            }
+        }
+    }
+}
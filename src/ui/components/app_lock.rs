@@ -0,0 +1,119 @@
+use dioxus::prelude::*;
+
+use crate::ui::components::modal::ModalDialog;
+
+/// Covers the (blurred) workspace until the startup passphrase is entered. There is no way
+/// to dismiss it other than unlocking.
+#[component]
+pub fn LockScreen(failed: bool, on_unlock: EventHandler<String>) -> Element {
+    let mut passphrase = use_signal(String::new);
+    let mut submit = move || {
+        on_unlock.call(passphrase());
+        passphrase.set(String::new());
+    };
+
+    rsx! {
+        ModalDialog {
+            label: "BOM 已鎖定".to_string(),
+            z_index: 3000,
+            on_close: move |_| {},
+            div { style: "margin-bottom: 8px; font-weight: 600;", "BOM 已鎖定" }
+            div { style: "margin-bottom: 8px;", "請輸入啟動密碼" }
+            input {
+                r#type: "password",
+                autofocus: true,
+                aria_label: "啟動密碼",
+                value: passphrase(),
+                oninput: move |event| passphrase.set(event.value()),
+                onkeydown: move |event| {
+                    if event.key() == Key::Enter {
+                        submit();
+                    }
+                },
+            }
+            if failed {
+                div { style: "margin-top: 8px; color: #b00020;", "密碼不正確" }
+            }
+            div { style: "display: flex; justify-content: flex-end; margin-top: 12px;",
+                button { onclick: move |_| submit(), "解鎖" }
+            }
+        }
+    }
+}
+
+/// Sets, changes or removes the startup passphrase and the idle auto-lock timeout. Leaving
+/// the new passphrase empty removes it.
+#[component]
+pub fn AppLockSettings(
+    has_passphrase: bool,
+    idle_minutes: u32,
+    error: Option<String>,
+    on_save: EventHandler<(String, String, u32)>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let mut current = use_signal(String::new);
+    let mut new_passphrase = use_signal(String::new);
+    let mut confirm = use_signal(String::new);
+    let mut idle_input = use_signal(|| idle_minutes.to_string());
+    let mut local_error = use_signal(|| None::<String>);
+    let shown_error = local_error().or(error);
+
+    rsx! {
+        ModalDialog {
+            label: "啟動密碼".to_string(),
+            z_index: 1200,
+            panel_style: "min-width: 320px;",
+            on_close: move |_| on_close.call(()),
+            div { style: "margin-bottom: 8px; font-weight: 600;", "啟動密碼" }
+            div { style: "display: grid; grid-template-columns: auto 1fr; gap: 8px; align-items: center;",
+                if has_passphrase {
+                    label { "目前密碼" }
+                    input {
+                        r#type: "password",
+                        value: current(),
+                        oninput: move |event| current.set(event.value()),
+                    }
+                }
+                label { "新密碼" }
+                input {
+                    r#type: "password",
+                    placeholder: if has_passphrase { "留空以移除密碼" } else { "" },
+                    value: new_passphrase(),
+                    oninput: move |event| new_passphrase.set(event.value()),
+                }
+                label { "確認新密碼" }
+                input {
+                    r#type: "password",
+                    value: confirm(),
+                    oninput: move |event| confirm.set(event.value()),
+                }
+                label { "閒置自動鎖定（分鐘，0 為停用）" }
+                input {
+                    value: idle_input(),
+                    oninput: move |event| idle_input.set(event.value()),
+                }
+            }
+            if let Some(message) = shown_error {
+                div { style: "margin-top: 8px; color: #b00020;", "{message}" }
+            }
+            div { style: "display: flex; justify-content: flex-end; gap: 8px; margin-top: 12px;",
+                button {
+                    onclick: move |_| {
+                        if new_passphrase() != confirm() {
+                            local_error.set(Some("兩次輸入的密碼不一致".to_string()));
+                            return;
+                        }
+                        let Ok(idle_minutes) = idle_input().trim().parse::<u32>() else {
+                            local_error.set(Some("閒置分鐘數需為整數".to_string()));
+                            return;
+                        };
+                        local_error.set(None);
+                        on_save.call((current(), new_passphrase(), idle_minutes));
+                    },
+                    "儲存"
+                }
+                button { onclick: move |_| on_close.call(()), "取消" }
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+
+use dioxus::prelude::*;
+
+use crate::ui::components::modal::ModalDialog;
+use bom_core::assets::{parse_balance, AssetAccount};
+use bom_core::numeric::NumberLocale;
+
+/// Walks through the accounts of a 資產總表 one at a time with only 餘額 editable. Enter
+/// moves on to the next account; finishing hands every balance back to be staged together.
+#[component]
+pub fn BalanceUpdateDialog(
+    columns: Vec<String>,
+    rows: Vec<Vec<String>>,
+    /// Rows to walk through, in order.
+    row_indices: Vec<usize>,
+    /// Current 餘額 per row, staged edits included.
+    balances: BTreeMap<usize, String>,
+    locale: NumberLocale,
+    on_finish: EventHandler<BTreeMap<usize, String>>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let mut step = use_signal(|| 0_usize);
+    let mut drafts = use_signal(|| balances.clone());
+    let mut error = use_signal(|| None::<String>);
+    let total = row_indices.len();
+    let current_step = step().min(total.saturating_sub(1));
+    let Some(&row_idx) = row_indices.get(current_step) else {
+        return rsx! {
+            ModalDialog {
+                label: "更新餘額".to_string(),
+                on_close: move |_| on_close.call(()),
+                div { style: "margin-bottom: 12px;", "沒有需要手動更新餘額的帳戶。" }
+                button { onclick: move |_| on_close.call(()), "關閉" }
+            }
+        };
+    };
+    let account = rows
+        .get(row_idx)
+        .map(|row| AssetAccount::from_row(&columns, row))
+        .unwrap_or_default();
+    let previous = balances.get(&row_idx).cloned().unwrap_or_default();
+    let value = drafts().get(&row_idx).cloned().unwrap_or_default();
+    let is_last = current_step + 1 >= total;
+
+    // Checks the balance on screen, then moves `by` accounts or, past the last, finishes.
+    let mut advance = move |by: isize| {
+        let typed = drafts().get(&row_idx).cloned().unwrap_or_default();
+        match parse_balance(&typed, locale) {
+            Ok(balance) => {
+                drafts.write().insert(row_idx, balance);
+                error.set(None);
+                let next = current_step as isize + by;
+                if next >= total as isize {
+                    on_finish.call(drafts());
+                } else {
+                    step.set(next.max(0) as usize);
+                }
+            }
+            Err(err) => error.set(Some(err.to_string())),
+        }
+    };
+
+    rsx! {
+        ModalDialog {
+            label: "更新餘額".to_string(),
+            panel_style: "min-width: 360px;",
+            on_close: move |_| on_close.call(()),
+            div { style: "display: flex; justify-content: space-between; margin-bottom: 8px;",
+                span { style: "font-weight: 600;", "更新餘額" }
+                span { style: "color: #555;", "{current_step + 1} / {total}" }
+            }
+            div { style: "margin-bottom: 4px;",
+                "{account.institution} {account.account}"
+            }
+            div { style: "margin-bottom: 8px; color: #555;",
+                "{account.form}・{account.owner}・{account.currency}"
+            }
+            div { style: "margin-bottom: 8px; color: #555;", "原餘額：{previous}" }
+            input {
+                autofocus: true,
+                inputmode: "decimal",
+                style: "width: 100%; box-sizing: border-box; font-size: 16px;",
+                value,
+                oninput: move |event| {
+                    drafts.write().insert(row_idx, event.value());
+                },
+                onkeydown: move |event| {
+                    if event.key() == Key::Enter {
+                        event.prevent_default();
+                        advance(if event.modifiers().shift() { -1 } else { 1 });
+                    }
+                },
+            }
+            if let Some(message) = error() {
+                div { style: "margin-top: 8px; color: #b00020;", "{message}" }
+            }
+            div { style: "margin-top: 8px; font-size: 12px; color: #555;",
+                "Enter 到下一筆、Shift+Enter 回上一筆。完成後所有變更一起等待儲存；股票投資依持股淨值更新，不在此列出。"
+            }
+            div { style: "display: flex; justify-content: flex-end; gap: 8px; margin-top: 12px;",
+                button {
+                    disabled: current_step == 0,
+                    onclick: move |_| advance(-1),
+                    "上一筆"
+                }
+                button {
+                    onclick: move |_| advance(1),
+                    if is_last { "完成" } else { "下一筆" }
+                }
+                if !is_last {
+                    button {
+                        onclick: move |_| advance(total as isize),
+                        "全部完成"
+                    }
+                }
+                button { onclick: move |_| on_close.call(()), "取消" }
+            }
+        }
+    }
+}
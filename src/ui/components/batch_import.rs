@@ -0,0 +1,71 @@
+use dioxus::prelude::*;
+
+use crate::ui::components::modal::ModalDialog;
+use crate::ui::state::app_state::BatchImport;
+
+/// Progress of a folder import and, per file, what it created or why it failed. Closing
+/// is only offered once every file has been tried.
+#[component]
+pub fn BatchImportDialog(batch: BatchImport, on_close: EventHandler<()>) -> Element {
+    let done = batch.results.len();
+    let total = batch.files.len();
+    let percent = if total == 0 { 100 } else { done * 100 / total };
+    let finished = batch.is_done();
+    let failed = batch.failed();
+    rsx! {
+        ModalDialog {
+            label: "匯入資料夾".to_string(),
+            z_index: 1100,
+            panel_style: "min-width: 360px; max-width: 560px;",
+            on_close: move |_| {
+                if finished {
+                    on_close.call(());
+                }
+            },
+            div { style: "margin-bottom: 8px; font-weight: 600;", "匯入資料夾" }
+            div { style: "margin-bottom: 8px; color: #555; word-break: break-all;",
+                "{batch.folder.display()}"
+            }
+            div { style: "margin-bottom: 4px;",
+                if finished {
+                    "完成：{done - failed} 個成功，{failed} 個失敗"
+                } else {
+                    "正在匯入 {done + 1}/{total}"
+                }
+            }
+            div { style: "height: 6px; background: #e5e5e5; border-radius: 3px; margin-bottom: 12px;",
+                div { style: "height: 100%; width: {percent}%; background: #1a5fd0; border-radius: 3px;" }
+            }
+            ul { style: "margin: 0 0 12px; padding-left: 20px; max-height: 280px; overflow-y: auto;",
+                for (idx, file_path) in batch.files.iter().enumerate() {
+                    li { key: "{idx}",
+                        span {
+                            {file_path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default()}
+                        }
+                        match batch.results.get(idx) {
+                            Some(Ok(summary)) => rsx! {
+                                span { style: "color: #2e7d32;", "：已匯入 {summary}" }
+                            },
+                            Some(Err(message)) => rsx! {
+                                span { style: "color: #c62828;", "：失敗（{message}）" }
+                            },
+                            None if idx == done => rsx! {
+                                span { style: "color: #555;", "：匯入中…" }
+                            },
+                            None => rsx! {
+                                span { style: "color: #999;", "：等待中" }
+                            },
+                        }
+                    }
+                }
+            }
+            div { style: "display: flex; justify-content: flex-end;",
+                button {
+                    disabled: !finished,
+                    onclick: move |_| on_close.call(()),
+                    "關閉"
+                }
+            }
+        }
+    }
+}
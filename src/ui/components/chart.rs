@@ -0,0 +1,392 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use dioxus::prelude::*;
+use rfd::FileDialog;
+
+use crate::domain::entities::dataset::ColumnFormat;
+use crate::{format_cell_value_with, format_number_with_commas, parse_numeric_value};
+
+const CHART_WIDTH: f64 = 860.0;
+const CHART_HEIGHT: f64 = 420.0;
+const MARGIN_LEFT: f64 = 80.0;
+const MARGIN_RIGHT: f64 = 24.0;
+const MARGIN_TOP: f64 = 24.0;
+const MARGIN_BOTTOM: f64 = 96.0;
+const TICK_COUNT: usize = 5;
+const SPARKLINE_WIDTH: f64 = 48.0;
+const SPARKLINE_HEIGHT: f64 = 14.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChartKind {
+    Bar,
+    Line,
+    Scatter,
+}
+
+impl ChartKind {
+    fn label(self) -> &'static str {
+        match self {
+            ChartKind::Bar => "長條圖",
+            ChartKind::Line => "折線圖",
+            ChartKind::Scatter => "散佈圖",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChartPoint {
+    pub label: String,
+    /// Numeric value of the label column; only scatter charts need it.
+    pub x: Option<f64>,
+    pub y: f64,
+    pub y_raw: String,
+}
+
+/// Collects one point per row whose value column is numeric.
+pub fn chart_points(rows: &[Vec<String>], label_col: usize, value_col: usize) -> Vec<ChartPoint> {
+    rows.iter()
+        .filter_map(|row| {
+            let y_raw = row.get(value_col)?.clone();
+            let y = parse_numeric_value(&y_raw)?;
+            let label = row.get(label_col).cloned().unwrap_or_default();
+            Some(ChartPoint {
+                x: parse_numeric_value(&label),
+                label,
+                y,
+                y_raw,
+            })
+        })
+        .collect()
+}
+
+/// Picks a text-ish label column and the first numeric column after it.
+pub fn default_chart_columns(rows: &[Vec<String>], column_count: usize) -> (usize, Option<usize>) {
+    let is_numeric = |idx: usize| {
+        rows.iter()
+            .any(|row| row.get(idx).and_then(|v| parse_numeric_value(v)).is_some())
+    };
+    let label_col = (0..column_count).find(|idx| !is_numeric(*idx)).unwrap_or(0);
+    let value_col = (0..column_count).find(|idx| *idx != label_col && is_numeric(*idx));
+    (label_col, value_col)
+}
+
+/// Domain for an axis; bars always start from zero and a flat series gets some padding.
+pub fn value_range(values: &[f64], include_zero: bool) -> (f64, f64) {
+    let mut min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let mut max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if !min.is_finite() || !max.is_finite() {
+        return (0.0, 1.0);
+    }
+    if include_zero {
+        min = min.min(0.0);
+        max = max.max(0.0);
+    }
+    if (max - min).abs() < f64::EPSILON {
+        min -= 1.0;
+        max += 1.0;
+    }
+    (min, max)
+}
+
+fn scale(value: f64, domain: (f64, f64), range: (f64, f64)) -> f64 {
+    let span = domain.1 - domain.0;
+    if span.abs() < f64::EPSILON {
+        return range.0;
+    }
+    range.0 + (value - domain.0) / span * (range.1 - range.0)
+}
+
+fn tick_label(value: f64) -> String {
+    let decimals = if value.fract().abs() < 1e-9 { 0 } else { 2 };
+    format_number_with_commas(value, decimals)
+}
+
+/// `points` of a polyline drawing `values` left to right across a `width` × `height` box,
+/// the highest value at the top.
+pub fn sparkline_points(values: &[f64], width: f64, height: f64) -> String {
+    let domain = value_range(values, false);
+    let step = width / values.len().saturating_sub(1).max(1) as f64;
+    values
+        .iter()
+        .enumerate()
+        .map(|(idx, value)| {
+            let y = scale(*value, domain, (height, 0.0));
+            format!("{:.1},{y:.1}", idx as f64 * step)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A small inline line of `values`, shown after the 代號 of holdings with stored prices.
+#[component]
+pub fn Sparkline(values: Vec<f64>, title: String) -> Element {
+    let points = sparkline_points(&values, SPARKLINE_WIDTH, SPARKLINE_HEIGHT);
+    rsx! {
+        svg {
+            width: "{SPARKLINE_WIDTH}",
+            height: "{SPARKLINE_HEIGHT}",
+            view_box: "0 0 {SPARKLINE_WIDTH} {SPARKLINE_HEIGHT}",
+            style: "margin-left: 6px; vertical-align: middle; overflow: visible;",
+            title { "{title}" }
+            polyline { points: "{points}", fill: "none", stroke: "#4a7bd0", stroke_width: "1.5" }
+        }
+    }
+}
+
+/// Decodes the `data:image/png;base64,...` URL produced by the webview canvas.
+pub fn decode_png_data_url(data_url: &str) -> Result<Vec<u8>> {
+    let payload = data_url
+        .strip_prefix("data:image/png;base64,")
+        .ok_or_else(|| anyhow!("unexpected image data"))?;
+    base64::engine::general_purpose::STANDARD
+        .decode(payload)
+        .context("failed to decode PNG data")
+}
+
+fn export_script(svg_id: &str) -> String {
+    format!(
+        r##"
+const svg = document.getElementById({svg_id:?});
+if (!svg) {{
+  dioxus.send("");
+}} else {{
+  const clone = svg.cloneNode(true);
+  clone.setAttribute("xmlns", "http://www.w3.org/2000/svg");
+  const text = new XMLSerializer().serializeToString(clone);
+  const image = new Image();
+  image.onload = () => {{
+    const ratio = 2;
+    const canvas = document.createElement("canvas");
+    canvas.width = {CHART_WIDTH} * ratio;
+    canvas.height = {CHART_HEIGHT} * ratio;
+    const ctx = canvas.getContext("2d");
+    ctx.fillStyle = "#fff";
+    ctx.fillRect(0, 0, canvas.width, canvas.height);
+    ctx.scale(ratio, ratio);
+    ctx.drawImage(image, 0, 0);
+    dioxus.send(canvas.toDataURL("image/png"));
+  }};
+  image.onerror = () => dioxus.send("");
+  image.src = "data:image/svg+xml;charset=utf-8," + encodeURIComponent(text);
+}}
+"##
+    )
+}
+
+/// Asks where to save the PNG in `data_url`, suggesting `file_name`. `Ok(false)` when the
+/// dialog was cancelled.
+pub fn save_png(data_url: &str, file_name: &str) -> Result<bool> {
+    let bytes = decode_png_data_url(data_url)?;
+    let Some(path) = FileDialog::new()
+        .add_filter("PNG", &["png"])
+        .set_file_name(file_name)
+        .save_file()
+    else {
+        return Ok(false);
+    };
+    std::fs::write(&path, bytes)
+        .with_context(|| format!("failed to write image: {}", path.display()))?;
+    Ok(true)
+}
+
+/// Chart tab for the current dataset: plots one column against another as SVG.
+#[component]
+pub fn ChartPanel(
+    columns: Vec<String>,
+    labels: Vec<String>,
+    rows: Arc<Vec<Vec<String>>>,
+    formats: BTreeMap<i64, ColumnFormat>,
+    svg_id: String,
+    on_status: EventHandler<String>,
+) -> Element {
+    let mut kind = use_signal(|| ChartKind::Bar);
+    let (default_label, default_value) = default_chart_columns(&rows, columns.len());
+    let mut label_col = use_signal(|| default_label);
+    let mut value_col = use_signal(|| default_value);
+
+    let kind_now = kind();
+    let label_idx = label_col().min(columns.len().saturating_sub(1));
+    let Some(value_idx) = value_col().filter(|idx| *idx < columns.len()) else {
+        return rsx! {
+            div { style: "padding: 24px; color: #666;", "沒有可繪製的數值欄位" }
+        };
+    };
+    let value_header = columns[value_idx].clone();
+    let value_format = formats.get(&(value_idx as i64)).cloned();
+    let points: Vec<ChartPoint> = chart_points(&rows, label_idx, value_idx)
+        .into_iter()
+        .filter(|point| kind_now != ChartKind::Scatter || point.x.is_some())
+        .collect();
+
+    let plot_left = MARGIN_LEFT;
+    let plot_right = CHART_WIDTH - MARGIN_RIGHT;
+    let plot_top = MARGIN_TOP;
+    let plot_bottom = CHART_HEIGHT - MARGIN_BOTTOM;
+    let y_values: Vec<f64> = points.iter().map(|p| p.y).collect();
+    let y_domain = value_range(&y_values, kind_now == ChartKind::Bar);
+    let y_of = |value: f64| scale(value, y_domain, (plot_bottom, plot_top));
+    let x_domain = value_range(
+        &points.iter().filter_map(|p| p.x).collect::<Vec<_>>(),
+        false,
+    );
+    let band = (plot_right - plot_left) / points.len().max(1) as f64;
+    let x_of = |idx: usize, point: &ChartPoint| match kind_now {
+        ChartKind::Scatter => scale(
+            point.x.unwrap_or_default(),
+            x_domain,
+            (plot_left, plot_right),
+        ),
+        _ => plot_left + band * (idx as f64 + 0.5),
+    };
+    let zero_y = y_of(0.0_f64.clamp(y_domain.0, y_domain.1));
+    let y_ticks: Vec<(f64, String)> = (0..=TICK_COUNT)
+        .map(|step| {
+            let value = y_domain.0 + (y_domain.1 - y_domain.0) * step as f64 / TICK_COUNT as f64;
+            (y_of(value), tick_label(value))
+        })
+        .collect();
+    let x_ticks: Vec<(f64, String)> = if kind_now == ChartKind::Scatter {
+        (0..=TICK_COUNT)
+            .map(|step| {
+                let value =
+                    x_domain.0 + (x_domain.1 - x_domain.0) * step as f64 / TICK_COUNT as f64;
+                (
+                    scale(value, x_domain, (plot_left, plot_right)),
+                    tick_label(value),
+                )
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let polyline = points
+        .iter()
+        .enumerate()
+        .map(|(idx, point)| format!("{:.1},{:.1}", x_of(idx, point), y_of(point.y)))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let svg_id_for_export = svg_id.clone();
+
+    rsx! {
+        div { style: "display: flex; flex-direction: column; gap: 12px; padding: 8px 0;",
+            div { style: "display: flex; flex-wrap: wrap; gap: 12px; align-items: center;",
+                div { style: "display: inline-flex; gap: 4px;",
+                    for option in [ChartKind::Bar, ChartKind::Line, ChartKind::Scatter] {
+                        button {
+                            style: if option == kind_now { "background: #eef4ff; border: 1px solid #4a7bd0;" } else { "" },
+                            onclick: move |_| kind.set(option),
+                            "{option.label()}"
+                        }
+                    }
+                }
+                label {
+                    if kind_now == ChartKind::Scatter { "X 軸 " } else { "類別 " }
+                    select {
+                        onchange: move |event| {
+                            if let Ok(idx) = event.value().parse::<usize>() {
+                                label_col.set(idx);
+                            }
+                        },
+                        for (idx, name) in labels.iter().enumerate() {
+                            option { value: "{idx}", selected: idx == label_idx, "{name}" }
+                        }
+                    }
+                }
+                label {
+                    "數值 "
+                    select {
+                        onchange: move |event| {
+                            if let Ok(idx) = event.value().parse::<usize>() {
+                                value_col.set(Some(idx));
+                            }
+                        },
+                        for (idx, name) in labels.iter().enumerate() {
+                            option { value: "{idx}", selected: idx == value_idx, "{name}" }
+                        }
+                    }
+                }
+                button {
+                    onclick: move |_| {
+                        let script = export_script(&svg_id_for_export);
+                        spawn(async move {
+                            let mut eval = document::eval(&script);
+                            let data_url: String = match eval.recv().await {
+                                Ok(value) => value,
+                                Err(err) => {
+                                    on_status.call(format!("匯出圖表失敗：{err}"));
+                                    return;
+                                }
+                            };
+                            match save_png(&data_url, "chart.png") {
+                                Ok(true) => on_status.call("已匯出圖表".to_string()),
+                                Ok(false) => {}
+                                Err(err) => on_status.call(format!("匯出圖表失敗：{err}")),
+                            }
+                        });
+                    },
+                    "匯出 PNG"
+                }
+            }
+
+            if points.is_empty() {
+                div { style: "padding: 24px; color: #666;", "所選欄位沒有數值資料" }
+            } else {
+                svg {
+                    id: "{svg_id}",
+                    width: "{CHART_WIDTH}",
+                    height: "{CHART_HEIGHT}",
+                    view_box: "0 0 {CHART_WIDTH} {CHART_HEIGHT}",
+                    style: "background: #fff; border: 1px solid #ddd; font-family: sans-serif;",
+                    for (y, text) in y_ticks.iter() {
+                        line { x1: "{plot_left}", y1: "{y}", x2: "{plot_right}", y2: "{y}", stroke: "#eee" }
+                        text { x: "{plot_left - 6.0}", y: "{y + 4.0}", text_anchor: "end", font_size: "11", fill: "#555", "{text}" }
+                    }
+                    for (x, text) in x_ticks.iter() {
+                        text { x: "{x}", y: "{plot_bottom + 16.0}", text_anchor: "middle", font_size: "11", fill: "#555", "{text}" }
+                    }
+                    line { x1: "{plot_left}", y1: "{plot_top}", x2: "{plot_left}", y2: "{plot_bottom}", stroke: "#888" }
+                    line { x1: "{plot_left}", y1: "{zero_y}", x2: "{plot_right}", y2: "{zero_y}", stroke: "#888" }
+                    if kind_now == ChartKind::Line {
+                        polyline { points: "{polyline}", fill: "none", stroke: "#4a7bd0", stroke_width: "2" }
+                    }
+                    for (idx, point) in points.iter().enumerate() {
+                        {
+                            let x = x_of(idx, point);
+                            let y = y_of(point.y);
+                            let value_text =
+                                format_cell_value_with(&value_header, &point.y_raw, value_format.as_ref());
+                            let label_y = if point.y < 0.0 && kind_now == ChartKind::Bar { y + 14.0 } else { y - 6.0 };
+                            let bar_top = y.min(zero_y);
+                            let bar_height = (y - zero_y).abs();
+                            let bar_width = (band * 0.7).max(1.0);
+                            let bar_x = x - bar_width / 2.0;
+                            let category_y = plot_bottom + 14.0;
+                            rsx! {
+                                if kind_now == ChartKind::Bar {
+                                    rect { x: "{bar_x}", y: "{bar_top}", width: "{bar_width}", height: "{bar_height}", fill: "#4a7bd0" }
+                                } else {
+                                    circle { cx: "{x}", cy: "{y}", r: "4", fill: "#4a7bd0" }
+                                }
+                                text { x: "{x}", y: "{label_y}", text_anchor: "middle", font_size: "10", fill: "#222", "{value_text}" }
+                                if kind_now != ChartKind::Scatter {
+                                    text {
+                                        x: "{x}",
+                                        y: "{category_y}",
+                                        text_anchor: "end",
+                                        font_size: "11",
+                                        fill: "#555",
+                                        transform: "rotate(-45 {x} {category_y})",
+                                        "{point.label}"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::BTreeMap;
+
+use dioxus::prelude::*;
+
+use crate::domain::entities::dataset::{ColumnFormat, ColumnType, DatasetDisplay, DisplayUnit};
+use crate::ui::components::modal::ModalDialog;
+use crate::{column_display_name, default_column_format, format_numeric_value};
+
+/// Number format and type of each column, and the currency and unit of the dataset's
+/// amounts. `types` holds the current type of every column; `on_save` receives the
+/// formats, the types that were changed and the display setting.
+#[component]
+pub fn ColumnFormatDialog(
+    columns: Vec<String>,
+    aliases: BTreeMap<i64, String>,
+    formats: BTreeMap<i64, ColumnFormat>,
+    types: Vec<ColumnType>,
+    display: DatasetDisplay,
+    on_save: EventHandler<(
+        BTreeMap<i64, ColumnFormat>,
+        BTreeMap<i64, ColumnType>,
+        DatasetDisplay,
+    )>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let mut draft = use_signal(|| formats.clone());
+    let mut draft_types = use_signal(BTreeMap::<i64, ColumnType>::new);
+    let mut selected_col = use_signal(|| None::<i64>);
+    let mut draft_display = use_signal(|| display.clone());
+    let display_snapshot = draft_display();
+    let draft_snapshot = draft();
+    let selected = selected_col().and_then(|idx| {
+        columns.get(idx as usize).map(|header| {
+            let column_type = draft_types()
+                .get(&idx)
+                .copied()
+                .or_else(|| types.get(idx as usize).copied())
+                .unwrap_or(ColumnType::Text);
+            let format = draft_snapshot
+                .get(&idx)
+                .cloned()
+                .unwrap_or_else(|| default_column_format(header, column_type));
+            (idx, draft_snapshot.contains_key(&idx), format, column_type)
+        })
+    });
+
+    rsx! {
+        ModalDialog {
+            label: "數字格式".to_string(),
+            z_index: 1200,
+            panel_style: "min-width: 420px; max-width: 720px; max-height: 80vh; overflow: auto;",
+            on_close: move |_| on_close.call(()),
+            div { style: "margin-bottom: 8px; font-weight: 600;", "數字格式" }
+            div { style: "display: flex; gap: 12px; align-items: center; margin-bottom: 10px;",
+                label {
+                    "幣別 "
+                    input {
+                        size: "6",
+                        value: "{display_snapshot.currency}",
+                        oninput: move |event: FormEvent| {
+                            draft_display.write().currency = event.value().trim().to_uppercase();
+                        }
+                    }
+                }
+                label {
+                    "金額單位 "
+                    select {
+                        onchange: move |event| {
+                            if let Some(unit) = DisplayUnit::parse(&event.value()) {
+                                draft_display.write().unit = unit;
+                            }
+                        },
+                        for unit in DisplayUnit::ALL {
+                            option {
+                                value: unit.as_str(),
+                                selected: unit == display_snapshot.unit,
+                                "{unit.label()}"
+                            }
+                        }
+                    }
+                }
+                span { style: "color: #666; font-size: 12px;", "套用於金額欄位的顯示與匯出" }
+            }
+            div { style: "display: flex; gap: 16px;",
+                div { style: "flex: 1; border: 1px solid #ddd; max-height: 280px; overflow: auto; padding: 6px;",
+                    {columns.iter().enumerate().map(|(idx, header)| {
+                        let idx = idx as i64;
+                        let is_selected = selected_col() == Some(idx);
+                        let marker = if draft_snapshot.contains_key(&idx) { " *" } else { "" };
+                        let display_name = column_display_name(header, idx as usize, &aliases);
+                        rsx!(
+                            label {
+                                style: "display: flex; align-items: center; gap: 8px; padding: 4px 2px; cursor: pointer;",
+                                input {
+                                    r#type: "radio",
+                                    name: "column-format",
+                                    checked: is_selected,
+                                    onclick: move |_| selected_col.set(Some(idx)),
+                                }
+                                span { "{display_name}{marker}" }
+                            }
+                        )
+                    })}
+                }
+                div { style: "flex: 1;",
+                    if let Some((idx, is_custom, format, column_type)) = selected {
+                        div { style: "display: flex; flex-direction: column; gap: 8px;",
+                            label {
+                                "類型 "
+                                select {
+                                    onchange: move |event| {
+                                        if let Some(next) = ColumnType::parse(&event.value()) {
+                                            draft_types.write().insert(idx, next);
+                                        }
+                                    },
+                                    for option_type in ColumnType::ALL {
+                                        option {
+                                            value: option_type.as_str(),
+                                            selected: option_type == column_type,
+                                            "{option_type.label()}"
+                                        }
+                                    }
+                                }
+                            }
+                            label {
+                                "小數位數 "
+                                input {
+                                    r#type: "number",
+                                    min: "0",
+                                    max: "10",
+                                    value: "{format.decimals}",
+                                    oninput: {
+                                        let format = format.clone();
+                                        move |event: FormEvent| {
+                                            if let Ok(decimals) = event.value().trim().parse::<u32>() {
+                                                let mut next = format.clone();
+                                                next.decimals = decimals.min(10);
+                                                draft.write().insert(idx, next);
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                            label {
+                                input {
+                                    r#type: "checkbox",
+                                    checked: format.percent,
+                                    onclick: {
+                                        let format = format.clone();
+                                        move |_| {
+                                            let mut next = format.clone();
+                                            next.percent = !next.percent;
+                                            draft.write().insert(idx, next);
+                                        }
+                                    }
+                                }
+                                " 百分比"
+                            }
+                            label {
+                                input {
+                                    r#type: "checkbox",
+                                    checked: format.thousands_separator,
+                                    onclick: {
+                                        let format = format.clone();
+                                        move |_| {
+                                            let mut next = format.clone();
+                                            next.thousands_separator = !next.thousands_separator;
+                                            draft.write().insert(idx, next);
+                                        }
+                                    }
+                                }
+                                " 千分位"
+                            }
+                            label {
+                                "貨幣符號 "
+                                input {
+                                    value: "{format.currency_symbol}",
+                                    oninput: {
+                                        let format = format.clone();
+                                        move |event: FormEvent| {
+                                            let mut next = format.clone();
+                                            next.currency_symbol = event.value().trim().to_string();
+                                            draft.write().insert(idx, next);
+                                        }
+                                    }
+                                }
+                            }
+                            div { "預覽：{format_numeric_value(-1234.5678, &format)}" }
+                            button {
+                                disabled: !is_custom,
+                                onclick: move |_| {
+                                    draft.write().remove(&idx);
+                                },
+                                "恢復預設"
+                            }
+                        }
+                    } else {
+                        div { style: "color: #666;", "請選擇欄位" }
+                    }
+                }
+            }
+            div { style: "display: flex; justify-content: flex-end; gap: 8px; margin-top: 12px;",
+                button {
+                    onclick: move |_| {
+                        let mut display = draft_display();
+                        if display.currency.is_empty() {
+                            display.currency = DatasetDisplay::default().currency;
+                        }
+                        on_save.call((draft(), draft_types(), display))
+                    },
+                    "儲存"
+                }
+                button {
+                    onclick: move |_| on_close.call(()),
+                    "取消"
+                }
+            }
+        }
+    }
+}
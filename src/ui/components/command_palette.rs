@@ -0,0 +1,95 @@
+use dioxus::prelude::*;
+
+use crate::rank_fuzzy_matches;
+
+const MAX_VISIBLE_ENTRIES: usize = 50;
+
+/// One action listed in the command palette.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PaletteEntry {
+    pub label: String,
+    pub hint: String,
+}
+
+/// Ctrl+K palette: type to fuzzy-filter, arrows to move, Enter to run, Escape to close.
+#[component]
+pub fn CommandPalette(
+    entries: Vec<PaletteEntry>,
+    on_select: EventHandler<usize>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let mut query = use_signal(String::new);
+    let mut highlighted = use_signal(|| 0_usize);
+
+    let search_texts = entries
+        .iter()
+        .map(|entry| format!("{} {}", entry.label, entry.hint))
+        .collect::<Vec<_>>();
+    let matches = rank_fuzzy_matches(&query(), &search_texts)
+        .into_iter()
+        .take(MAX_VISIBLE_ENTRIES)
+        .collect::<Vec<_>>();
+    let highlighted_idx = highlighted().min(matches.len().saturating_sub(1));
+    let matches_for_keys = matches.clone();
+
+    rsx! {
+        div {
+            style: "position: fixed; inset: 0; background: rgba(0,0,0,0.25); display: flex; justify-content: center; align-items: flex-start; padding-top: 12vh; z-index: 1400;",
+            onclick: move |_| on_close.call(()),
+            div {
+                style: "background: #fff; width: 520px; max-height: 60vh; display: flex; flex-direction: column; border: 1px solid #999; border-radius: 8px; box-shadow: 0 8px 24px rgba(0,0,0,0.2);",
+                role: "dialog",
+                aria_modal: "true",
+                aria_label: "命令面板",
+                onclick: move |event| event.stop_propagation(),
+                input {
+                    style: "padding: 10px 12px; font-size: 15px; border: none; border-bottom: 1px solid #ddd; outline: none;",
+                    placeholder: "輸入指令或資料集名稱…",
+                    aria_label: "搜尋指令",
+                    autofocus: true,
+                    value: "{query}",
+                    oninput: move |event| {
+                        query.set(event.value());
+                        highlighted.set(0);
+                    },
+                    onkeydown: move |event| match event.key() {
+                        Key::ArrowDown => {
+                            event.prevent_default();
+                            if highlighted_idx + 1 < matches_for_keys.len() {
+                                highlighted.set(highlighted_idx + 1);
+                            }
+                        }
+                        Key::ArrowUp => {
+                            event.prevent_default();
+                            highlighted.set(highlighted_idx.saturating_sub(1));
+                        }
+                        Key::Enter => {
+                            if let Some(entry_idx) = matches_for_keys.get(highlighted_idx) {
+                                on_select.call(*entry_idx);
+                            }
+                        }
+                        Key::Escape => on_close.call(()),
+                        _ => {}
+                    },
+                }
+                div { style: "overflow-y: auto;", role: "listbox",
+                    if matches.is_empty() {
+                        div { style: "padding: 10px 12px; color: #888;", "沒有符合的指令" }
+                    }
+                    for (position, entry_idx) in matches.iter().copied().enumerate() {
+                        div {
+                            key: "{entry_idx}",
+                            style: if position == highlighted_idx { "display: flex; justify-content: space-between; padding: 6px 12px; cursor: pointer; background: #eef4ff;" } else { "display: flex; justify-content: space-between; padding: 6px 12px; cursor: pointer;" },
+                            role: "option",
+                            aria_selected: "{position == highlighted_idx}",
+                            onmouseenter: move |_| highlighted.set(position),
+                            onclick: move |_| on_select.call(entry_idx),
+                            span { "{entries[entry_idx].label}" }
+                            span { style: "color: #888; font-size: 12px;", "{entries[entry_idx].hint}" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
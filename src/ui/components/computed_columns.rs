@@ -0,0 +1,94 @@
+use dioxus::prelude::*;
+
+use crate::domain::entities::computed_column::ComputedColumn;
+use crate::expression::validate_computed_columns;
+use crate::ui::components::modal::ModalDialog;
+
+/// Edits the computed columns of the selected dataset. `columns` are its stored columns,
+/// which the expressions can read along with the computed columns listed before them.
+#[component]
+pub fn ComputedColumnsDialog(
+    columns: Vec<String>,
+    computed: Vec<ComputedColumn>,
+    on_save: EventHandler<Vec<ComputedColumn>>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let mut draft = use_signal(|| computed.clone());
+    let draft_snapshot = draft();
+    let problem = validate_computed_columns(&columns, &draft_snapshot)
+        .err()
+        .map(|err| err.to_string());
+
+    rsx! {
+        ModalDialog {
+            label: "計算欄位".to_string(),
+            z_index: 1200,
+            panel_style: "min-width: 520px; max-width: 800px; max-height: 80vh; overflow: auto;",
+            on_close: move |_| on_close.call(()),
+            div { style: "margin-bottom: 8px; font-weight: 600;", "計算欄位" }
+            div { style: "margin-bottom: 8px; font-size: 12px; color: #555;",
+                "以欄位名稱與 + - * / 及括號組成運算式，例如：淨值 / 總成本 - 1。"
+                "名稱含空白或運算符號時請加上雙引號。"
+            }
+            for (idx, column) in draft_snapshot.iter().cloned().enumerate() {
+                div { style: "display: flex; gap: 6px; align-items: center; margin-top: 4px;",
+                    input {
+                        style: "width: 140px;",
+                        value: column.name.clone(),
+                        placeholder: "欄位名稱",
+                        oninput: move |event| draft.write()[idx].name = event.value(),
+                    }
+                    span { "=" }
+                    input {
+                        style: "flex: 1;",
+                        value: column.expression.clone(),
+                        placeholder: "運算式",
+                        oninput: move |event| draft.write()[idx].expression = event.value(),
+                    }
+                    button {
+                        title: "移除",
+                        onclick: move |_| {
+                            draft.write().remove(idx);
+                        },
+                        "×"
+                    }
+                }
+            }
+            button {
+                style: "margin-top: 4px;",
+                onclick: move |_| {
+                    draft.write().push(ComputedColumn {
+                        name: String::new(),
+                        expression: String::new(),
+                    });
+                },
+                "新增計算欄位"
+            }
+            if !columns.is_empty() {
+                div { style: "margin-top: 8px; font-size: 12px; color: #555;",
+                    "可用欄位：{columns.join(\"、\")}"
+                }
+            }
+            if let Some(problem) = problem.clone() {
+                div { style: "margin-top: 8px; color: #b00020;", "{problem}" }
+            }
+            div { style: "display: flex; justify-content: flex-end; gap: 8px; margin-top: 12px;",
+                button {
+                    disabled: problem.is_some(),
+                    onclick: move |_| {
+                        let columns = draft()
+                            .into_iter()
+                            .map(|column| ComputedColumn {
+                                name: column.name.trim().to_string(),
+                                expression: column.expression.trim().to_string(),
+                            })
+                            .collect();
+                        on_save.call(columns);
+                    },
+                    "儲存"
+                }
+                button { onclick: move |_| on_close.call(()), "取消" }
+            }
+        }
+    }
+}
@@ -0,0 +1,446 @@
+use std::path::PathBuf;
+
+use dioxus::prelude::*;
+use rfd::{FileDialog, MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+
+use crate::domain::entities::dataset::DatasetKind;
+use crate::domain::entities::import_record::ImportRecord;
+use crate::infra::export::csv::{CsvDelimiter, CsvEncoding, CsvOptions};
+use crate::trash_dates;
+use crate::ui::components::modal::ModalDialog;
+use crate::usecase::ports::repo::DatasetMeta;
+
+/// Lists every dataset for importing, exporting as CSV, renaming, setting its type,
+/// exporting its differences from an older version, re-running the workbook transform on
+/// its stored sheets and moving it to the trash, which lists `trash` for restoring, keeping forever and permanent
+/// deletion. Re-transforming and permanent deletion ask for confirmation here; the
+/// callbacks only run once the user has agreed. Every action but the exports is disabled in
+/// read-only mode. `page` renders it as the 資料集管理 page instead of a dialog. The
+/// imports and re-transforms in `history` that wrote the selected dataset are listed below.
+#[component]
+pub fn DatasetManager(
+    datasets: Vec<DatasetMeta>,
+    initial_dataset_id: Option<i64>,
+    busy: bool,
+    read_only: bool,
+    #[props(default)] page: bool,
+    /// Every recorded import, newest first.
+    #[props(default)]
+    history: Vec<ImportRecord>,
+    #[props(default)] trash: Vec<DatasetMeta>,
+    /// Days a dataset stays in the trash before the purge at launch; 0 keeps them all.
+    retention_days: u32,
+    on_import: EventHandler<()>,
+    on_rename: EventHandler<(i64, String)>,
+    on_set_kind: EventHandler<(i64, DatasetKind)>,
+    /// The older version, the selected dataset and the file chosen for their diff.
+    on_export_diff: EventHandler<(i64, i64, PathBuf)>,
+    /// The selected dataset, the file chosen for it and how to write the CSV.
+    on_export_csv: EventHandler<(i64, PathBuf, CsvOptions)>,
+    on_retransform: EventHandler<i64>,
+    on_trash: EventHandler<i64>,
+    on_restore: EventHandler<i64>,
+    on_keep_forever: EventHandler<(i64, bool)>,
+    on_delete: EventHandler<i64>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let mut selected = use_signal(|| initial_dataset_id);
+    let mut name_input = use_signal(|| {
+        initial_dataset_id
+            .and_then(|id| datasets.iter().find(|dataset| dataset.id.0 == id))
+            .map(|dataset| dataset.name.clone())
+            .unwrap_or_default()
+    });
+    let selected_id = selected().filter(|id| datasets.iter().any(|d| d.id.0 == *id));
+    let selected_kind = selected_id
+        .and_then(|id| datasets.iter().find(|d| d.id.0 == id))
+        .map(|dataset| dataset.effective_kind());
+    let mut compare_with = use_signal(|| None::<i64>);
+    let mut csv_options = use_signal(CsvOptions::default);
+    let compare_id = compare_with()
+        .filter(|id| Some(*id) != selected_id && datasets.iter().any(|d| d.id.0 == *id));
+    let selected_history: Vec<ImportRecord> = selected_id
+        .map(|id| {
+            history
+                .iter()
+                .filter(|record| record.includes(id))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+    let export_busy = busy;
+    let datasets_for_export = datasets.clone();
+    let datasets_for_csv = datasets.clone();
+    let busy = busy || read_only;
+
+    let content = rsx! {
+            div { style: "margin-bottom: 8px; font-weight: 600;", "資料集管理" }
+            div { style: "display: flex; gap: 16px;",
+                div { style: "flex: 1;",
+                    div { style: "margin-bottom: 6px; font-weight: 600;", "資料集" }
+                    div { style: "border: 1px solid #ddd; max-height: 240px; overflow: auto; padding: 6px;",
+                        for dataset in datasets.iter().cloned() {
+                            label {
+                                style: "display: flex; align-items: center; gap: 8px; padding: 4px 2px; cursor: pointer;",
+                                input {
+                                    r#type: "radio",
+                                    name: "dataset-manager",
+                                    checked: selected_id == Some(dataset.id.0),
+                                    onclick: {
+                                        let name = dataset.name.clone();
+                                        move |_| {
+                                            selected.set(Some(dataset.id.0));
+                                            name_input.set(name.clone());
+                                        }
+                                    }
+                                }
+                                span { "{dataset.name}" }
+                            }
+                        }
+                    }
+                }
+                div { style: "flex: 1;",
+                    div { style: "margin-bottom: 6px; font-weight: 600;", "操作" }
+                    button {
+                        disabled: busy,
+                        onclick: move |_| on_import.call(()),
+                        "匯入 CSV / XLSX"
+                    }
+                    button {
+                        style: "margin-left: 8px;",
+                        disabled: export_busy || selected_id.is_none(),
+                        onclick: move |_| {
+                            let Some(dataset_id) = selected_id else {
+                                return;
+                            };
+                            let name = datasets_for_csv
+                                .iter()
+                                .find(|dataset| dataset.id.0 == dataset_id)
+                                .map(|dataset| dataset.name.clone())
+                                .unwrap_or_default();
+                            if let Some(path) = FileDialog::new()
+                                .add_filter("CSV", &["csv", "txt"])
+                                .set_file_name(format!("{name}.csv").as_str())
+                                .save_file()
+                            {
+                                on_export_csv.call((dataset_id, path, csv_options()));
+                            }
+                        },
+                        "匯出 CSV"
+                    }
+                    div { style: "margin-top: 6px; display: flex; gap: 6px; align-items: center;",
+                        label { "分隔符號" }
+                        select {
+                            onchange: move |event| {
+                                if let Some(delimiter) = CsvDelimiter::parse(&event.value()) {
+                                    csv_options.write().delimiter = delimiter;
+                                }
+                            },
+                            for delimiter in CsvDelimiter::ALL {
+                                option {
+                                    value: delimiter.as_str(),
+                                    selected: delimiter == csv_options().delimiter,
+                                    "{delimiter.label()}"
+                                }
+                            }
+                        }
+                        label { "編碼" }
+                        select {
+                            onchange: move |event| {
+                                if let Some(encoding) = CsvEncoding::parse(&event.value()) {
+                                    csv_options.write().encoding = encoding;
+                                }
+                            },
+                            for encoding in CsvEncoding::ALL {
+                                option {
+                                    value: encoding.as_str(),
+                                    selected: encoding == csv_options().encoding,
+                                    "{encoding.label()}"
+                                }
+                            }
+                        }
+                    }
+                    div { style: "margin-top: 12px;",
+                        label { "重新命名" }
+                        input {
+                            value: name_input(),
+                            oninput: move |event| name_input.set(event.value()),
+                        }
+                        button {
+                            disabled: busy || selected_id.is_none(),
+                            onclick: move |_| {
+                                if let Some(dataset_id) = selected_id {
+                                    on_rename.call((dataset_id, name_input()));
+                                }
+                            },
+                            "套用"
+                        }
+                    }
+                    div { style: "margin-top: 12px;",
+                        label { "類型 " }
+                        select {
+                            disabled: busy || selected_id.is_none(),
+                            onchange: move |event| {
+                                let kind = DatasetKind::parse(&event.value());
+                                if let (Some(dataset_id), Some(kind)) = (selected_id, kind) {
+                                    on_set_kind.call((dataset_id, kind));
+                                }
+                            },
+                            for kind in DatasetKind::ALL {
+                                option {
+                                    value: kind.as_str(),
+                                    selected: selected_kind == Some(kind),
+                                    "{kind.label()}"
+                                }
+                            }
+                        }
+                    }
+                    div { style: "margin-top: 12px;",
+                        label { "與舊版本比較 " }
+                        select {
+                            disabled: export_busy || selected_id.is_none(),
+                            onchange: move |event| compare_with.set(event.value().parse().ok()),
+                            option { value: "", selected: compare_id.is_none(), "選擇資料集" }
+                            for dataset in datasets.iter().filter(|d| Some(d.id.0) != selected_id) {
+                                option {
+                                    value: "{dataset.id.0}",
+                                    selected: compare_id == Some(dataset.id.0),
+                                    "{dataset.name}"
+                                }
+                            }
+                        }
+                        button {
+                            disabled: export_busy || selected_id.is_none() || compare_id.is_none(),
+                            onclick: move |_| {
+                                let (Some(before), Some(after)) = (compare_id, selected_id) else {
+                                    return;
+                                };
+                                let name = datasets_for_export
+                                    .iter()
+                                    .find(|dataset| dataset.id.0 == after)
+                                    .map(|dataset| dataset.name.clone())
+                                    .unwrap_or_default();
+                                if let Some(path) = FileDialog::new()
+                                    .add_filter("XLSX", &["xlsx"])
+                                    .add_filter("CSV", &["csv"])
+                                    .set_file_name(format!("{name}-差異.xlsx").as_str())
+                                    .save_file()
+                                {
+                                    on_export_diff.call((before, after, path));
+                                }
+                            },
+                            "匯出差異"
+                        }
+                    }
+                    div { style: "margin-top: 12px;",
+                        button {
+                            disabled: busy || selected_id.is_none(),
+                            title: "以匯入時保存的工作表重新產生此資料集",
+                            onclick: move |_| {
+                                let Some(dataset_id) = selected_id else {
+                                    return;
+                                };
+                                let confirm = MessageDialog::new()
+                                    .set_level(MessageLevel::Warning)
+                                    .set_title("重新轉換")
+                                    .set_description(
+                                        "以匯入時的工作表重新轉換此資料集？匯入後的修改都會被覆蓋。",
+                                    )
+                                    .set_buttons(MessageButtons::YesNo)
+                                    .show();
+                                if confirm == MessageDialogResult::Yes {
+                                    on_retransform.call(dataset_id);
+                                }
+                            },
+                            "重新轉換"
+                        }
+                    }
+                    div { style: "margin-top: 12px;",
+                        button {
+                            disabled: busy || selected_id.is_none(),
+                            onclick: move |_| {
+                                let Some(dataset_id) = selected_id else {
+                                    return;
+                                };
+                                on_trash.call(dataset_id);
+                                selected.set(None);
+                                name_input.set(String::new());
+                            },
+                            "移到垃圾桶"
+                        }
+                    }
+                }
+            }
+            if let Some(dataset_id) = selected_id {
+                div { style: "margin-top: 12px;",
+                    div { style: "margin-bottom: 6px; font-weight: 600;", "匯入紀錄" }
+                    if selected_history.is_empty() {
+                        div { style: "color: #777;", "沒有此資料集的匯入紀錄" }
+                    }
+                    div { style: "max-height: 200px; overflow: auto;",
+                        for record in selected_history {
+                            ImportHistoryEntry { record, dataset_id }
+                        }
+                    }
+                }
+            }
+            TrashList {
+                trash,
+                retention_days,
+                busy,
+                on_restore,
+                on_keep_forever,
+                on_delete,
+            }
+            div { style: "display: flex; justify-content: flex-end; margin-top: 12px;",
+                button {
+                    onclick: move |_| on_close.call(()),
+                    if page { "返回資料集" } else { "關閉" }
+                }
+            }
+    };
+
+    if page {
+        return rsx! {
+            div { style: "padding: 16px; max-width: 720px;", {content} }
+        };
+    }
+    rsx! {
+        ModalDialog {
+            label: "資料集管理".to_string(),
+            z_index: 1200,
+            panel_style: "min-width: 420px; max-width: 720px; max-height: 80vh; overflow: auto;",
+            on_close: move |_| on_close.call(()),
+            {content}
+        }
+    }
+}
+
+/// One import of the selected dataset: when and from which file, the rows it received,
+/// how long it took and what looked off in the file.
+#[component]
+fn ImportHistoryEntry(record: ImportRecord, dataset_id: i64) -> Element {
+    let rows = record
+        .sheets
+        .iter()
+        .find(|sheet| sheet.dataset_id == dataset_id)
+        .map(|sheet| sheet.row_count)
+        .unwrap_or_default();
+    let hash = record.file_hash.get(..12).unwrap_or(&record.file_hash);
+    let seconds = record.duration_ms as f64 / 1000.0;
+    rsx! {
+        div { style: "border-top: 1px solid #eee; padding: 6px 2px; font-size: 13px;",
+            div {
+                span { style: "font-weight: 600;", "{record.kind.label()}" }
+                span { style: "margin-left: 8px; color: #555;", "{record.imported_at}" }
+                span { style: "margin-left: 8px;", "{rows} 筆" }
+                span { style: "margin-left: 8px; color: #555;", "{seconds:.1} 秒" }
+                if record.sheets.len() > 1 {
+                    span { style: "margin-left: 8px; color: #555;",
+                        "（共 {record.sheets.len()} 個工作表）"
+                    }
+                }
+            }
+            div {
+                style: "color: #555; overflow-wrap: anywhere;",
+                title: "{record.file_hash}",
+                "{record.source_path}"
+                if !hash.is_empty() {
+                    span { style: "margin-left: 8px; font-family: monospace;", "SHA-256 {hash}" }
+                }
+            }
+            for warning in record.warnings.iter() {
+                div { style: "color: #b00020;", "{warning}" }
+            }
+        }
+    }
+}
+
+/// The 垃圾桶 section: when each dataset went in and when the purge at launch will take it,
+/// unless it is kept forever.
+#[component]
+fn TrashList(
+    trash: Vec<DatasetMeta>,
+    retention_days: u32,
+    busy: bool,
+    on_restore: EventHandler<i64>,
+    on_keep_forever: EventHandler<(i64, bool)>,
+    on_delete: EventHandler<i64>,
+) -> Element {
+    let entries: Vec<(DatasetMeta, String)> = trash
+        .into_iter()
+        .map(|dataset| {
+            let dates = dataset
+                .deleted_at
+                .as_deref()
+                .and_then(|deleted_at| trash_dates(deleted_at, retention_days));
+            let when = match dates {
+                Some((deleted, Some(purge))) if !dataset.keep_forever => {
+                    format!("{deleted} 刪除，{purge} 清除")
+                }
+                Some((deleted, _)) => format!("{deleted} 刪除"),
+                None => String::new(),
+            };
+            (dataset, when)
+        })
+        .collect();
+    rsx! {
+        div { style: "margin-top: 12px;",
+            div { style: "margin-bottom: 6px; font-weight: 600;", "垃圾桶" }
+            div { style: "color: #666; font-size: 12px; margin-bottom: 4px;",
+                if retention_days == 0 {
+                    "不會自動清除；可在設定中調整。"
+                } else {
+                    "放入超過 {retention_days} 天的資料集會在程式開啟時永久刪除，勾選「永久保留」的除外。"
+                }
+            }
+            if entries.is_empty() {
+                div { style: "color: #777;", "垃圾桶是空的" }
+            }
+            div { style: "max-height: 200px; overflow: auto;",
+                for (dataset, when) in entries {
+                    div {
+                        key: "{dataset.id.0}",
+                        style: "display: flex; align-items: center; gap: 8px; border-top: 1px solid #eee; padding: 4px 2px;",
+                        span { style: "flex: 1;", "{dataset.name}" }
+                        span { style: "color: #555; font-size: 12px;", "{when}" }
+                        label { style: "display: flex; align-items: center; gap: 4px;",
+                            input {
+                                r#type: "checkbox",
+                                disabled: busy,
+                                checked: dataset.keep_forever,
+                                onchange: move |event: FormEvent| {
+                                    on_keep_forever.call((dataset.id.0, event.checked()));
+                                },
+                            }
+                            "永久保留"
+                        }
+                        button {
+                            disabled: busy,
+                            onclick: move |_| on_restore.call(dataset.id.0),
+                            "還原"
+                        }
+                        button {
+                            disabled: busy,
+                            onclick: {
+                                let name = dataset.name.clone();
+                                move |_| {
+                                    let confirm = MessageDialog::new()
+                                        .set_level(MessageLevel::Warning)
+                                        .set_title("永久刪除資料集")
+                                        .set_description(format!("確定要永久刪除「{name}」？此動作不可復原。"))
+                                        .set_buttons(MessageButtons::YesNo)
+                                        .show();
+                                    if confirm == MessageDialogResult::Yes {
+                                        on_delete.call(dataset.id.0);
+                                    }
+                                }
+                            },
+                            "永久刪除"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
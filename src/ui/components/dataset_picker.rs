@@ -0,0 +1,94 @@
+use dioxus::prelude::*;
+
+use crate::ui::components::dropdown::{DropdownId, DropdownOption, DropdownSelect};
+
+const SHEET_BUTTON_ACTIVE: &str =
+    "padding: 4px 10px; border: 1px solid #4c6ef5; background: #eef4ff; border-radius: 6px;";
+const SHEET_BUTTON_IDLE: &str =
+    "padding: 4px 10px; border: 1px solid #bbb; background: #fff; border-radius: 6px;";
+
+/// Dataset group dropdown, the sheet switcher for the active group and the edit-mode toggle.
+/// Workbooks with the known summary sheets get one button per sheet instead of a dropdown.
+/// The edit-mode toggle is hidden in read-only mode.
+#[component]
+pub fn DatasetPicker(
+    dataset_options: Vec<DropdownOption>,
+    sheet_options: Vec<DropdownOption>,
+    selected_group: Option<String>,
+    selected_dataset: Option<i64>,
+    has_active_group: bool,
+    assets_sheet: Option<i64>,
+    holdings_sheet: Option<i64>,
+    edit_mode: bool,
+    read_only: bool,
+    open_dropdown: Signal<Option<DropdownId>>,
+    dropdown_pos: Signal<Option<(f64, f64)>>,
+    on_group: EventHandler<String>,
+    on_sheet: EventHandler<Option<i64>>,
+    on_edit_mode: EventHandler<bool>,
+) -> Element {
+    let sheet_style = move |id: i64| {
+        if selected_dataset == Some(id) {
+            SHEET_BUTTON_ACTIVE
+        } else {
+            SHEET_BUTTON_IDLE
+        }
+    };
+
+    rsx! {
+        div {
+            DropdownSelect {
+                id: DropdownId::Dataset,
+                label: "資料集",
+                options: dataset_options,
+                selected: selected_group,
+                open_dropdown: open_dropdown,
+                dropdown_pos: dropdown_pos,
+                on_select: move |value: String| on_group.call(value),
+            }
+
+            if has_active_group {
+                if assets_sheet.is_some() || holdings_sheet.is_some() {
+                    div { style: "display: flex; gap: 8px; align-items: center;",
+                        if let Some(assets_id) = assets_sheet {
+                            button {
+                                style: sheet_style(assets_id),
+                                onclick: move |_| on_sheet.call(Some(assets_id)),
+                                "資產總表"
+                            }
+                        }
+                        if let Some(holdings_id) = holdings_sheet {
+                            button {
+                                style: sheet_style(holdings_id),
+                                onclick: move |_| on_sheet.call(Some(holdings_id)),
+                                "持股股息總表"
+                            }
+                        }
+                    }
+                } else {
+                    DropdownSelect {
+                        id: DropdownId::Sheet,
+                        label: "工作表",
+                        options: sheet_options,
+                        selected: selected_dataset.map(|id| id.to_string()),
+                        open_dropdown: open_dropdown,
+                        dropdown_pos: dropdown_pos,
+                        on_select: move |value: String| on_sheet.call(value.parse::<i64>().ok()),
+                    }
+                }
+            }
+
+            if selected_dataset.is_some() && !read_only {
+                label { "編輯模式" }
+                input {
+                    r#type: "checkbox",
+                    aria_label: "編輯模式",
+                    checked: edit_mode,
+                    onchange: move |event| {
+                        on_edit_mode.call(event.value().parse::<bool>().unwrap_or(false));
+                    }
+                }
+            }
+        }
+    }
+}
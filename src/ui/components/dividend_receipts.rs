@@ -0,0 +1,133 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use dioxus::prelude::*;
+
+use crate::ui::components::modal::ModalDialog;
+use bom_core::dividend_receipts::{expected_payouts, month_column};
+use bom_core::numeric::{
+    format_f64, format_number_with_commas, parse_numeric_value_in, NumberLocale,
+};
+
+/// Lists the rows of a 持股股息總表 expected to pay in a month, each with its amount filled
+/// in from the estimate. Finishing hands back the month column and the amounts to be
+/// staged together; rows left empty are not marked.
+#[component]
+pub fn DividendReceiptsDialog(
+    columns: Vec<String>,
+    /// Rows with their staged edits applied.
+    rows: Vec<Vec<String>>,
+    deleted_rows: BTreeSet<usize>,
+    /// Month shown first, usually the current one.
+    initial_month: u32,
+    locale: NumberLocale,
+    on_finish: EventHandler<(usize, BTreeMap<usize, String>)>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let mut month = use_signal(|| initial_month);
+    let mut drafts = use_signal(BTreeMap::<usize, String>::new);
+    let mut error = use_signal(|| None::<String>);
+    let payouts: Vec<_> = expected_payouts(&columns, &rows, month())
+        .into_iter()
+        .filter(|payout| !deleted_rows.contains(&payout.row_idx))
+        .collect();
+    let amounts: Vec<String> = payouts
+        .iter()
+        .map(|payout| {
+            drafts().get(&payout.row_idx).cloned().unwrap_or_else(|| {
+                payout
+                    .expected
+                    .map(|expected| format_f64(expected.round()))
+                    .unwrap_or_default()
+            })
+        })
+        .collect();
+    let month_idx = month_column(&columns, month());
+
+    rsx! {
+        ModalDialog {
+            label: "標記本月已收".to_string(),
+            panel_style: "min-width: 480px;",
+            on_close: move |_| on_close.call(()),
+            div { style: "display: flex; justify-content: space-between; align-items: center; margin-bottom: 8px;",
+                span { style: "font-weight: 600;", "標記本月已收" }
+                select {
+                    onchange: move |event: FormEvent| {
+                        if let Ok(next) = event.value().parse() {
+                            month.set(next);
+                            drafts.set(BTreeMap::new());
+                            error.set(None);
+                        }
+                    },
+                    for choice in 1..=12_u32 {
+                        option { value: "{choice}", selected: choice == month(), "{choice}月" }
+                    }
+                }
+            }
+            if payouts.is_empty() {
+                div { style: "margin-bottom: 12px; color: #555;", "這個月沒有預期尚未入帳的股息。" }
+            } else {
+                table { style: "border-collapse: collapse; width: 100%;",
+                    tr {
+                        th { style: "padding: 4px 8px; text-align: left;", "名稱" }
+                        th { style: "padding: 4px 8px; text-align: left;", "所有權人" }
+                        th { style: "padding: 4px 8px; text-align: right;", "預估" }
+                        th { style: "padding: 4px 8px; text-align: right;", "實收" }
+                    }
+                    for (payout, amount) in payouts.iter().cloned().zip(amounts.clone()) {
+                        tr { key: "{payout.row_idx}",
+                            td { style: "padding: 2px 8px;", "{payout.code} {payout.name}" }
+                            td { style: "padding: 2px 8px;", "{payout.owner}" }
+                            td { style: "padding: 2px 8px; text-align: right; color: #555;",
+                                {payout.expected.map(|expected| format_number_with_commas(expected, 0)).unwrap_or_default()}
+                            }
+                            td { style: "padding: 2px 8px; text-align: right;",
+                                input {
+                                    inputmode: "decimal",
+                                    style: "width: 100px; text-align: right;",
+                                    value: amount,
+                                    oninput: move |event| {
+                                        drafts.write().insert(payout.row_idx, event.value());
+                                    },
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            if let Some(message) = error() {
+                div { style: "margin-top: 8px; color: #b00020;", "{message}" }
+            }
+            div { style: "margin-top: 8px; font-size: 12px; color: #555;",
+                "依期數與今年已收的月份列出，金額預填為估計配息金額除以期數；清空的列不標記。完成後所有金額一起等待儲存。"
+            }
+            div { style: "display: flex; justify-content: flex-end; gap: 8px; margin-top: 12px;",
+                button {
+                    disabled: month_idx.is_none() || payouts.is_empty(),
+                    onclick: {
+                        let payouts = payouts.clone();
+                        let amounts = amounts.clone();
+                        move |_| {
+                            let Some(month_idx) = month_idx else {
+                                return;
+                            };
+                            let mut marked = BTreeMap::new();
+                            for (payout, amount) in payouts.iter().zip(&amounts) {
+                                if amount.trim().is_empty() {
+                                    continue;
+                                }
+                                let Some(value) = parse_numeric_value_in(amount, locale) else {
+                                    error.set(Some(format!("{} 的金額必須是數字", payout.name)));
+                                    return;
+                                };
+                                marked.insert(payout.row_idx, format_f64(value));
+                            }
+                            on_finish.call((month_idx, marked));
+                        }
+                    },
+                    "完成"
+                }
+                button { onclick: move |_| on_close.call(()), "取消" }
+            }
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::BTreeMap;
+
+use dioxus::prelude::*;
+
+/// Which dropdown of a workspace is open; only one is at a time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DropdownId {
+    Dataset,
+    Sheet,
+    Column,
+    ColumnVisibility,
+    Sort,
+    GroupBy,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DropdownOption {
+    pub value: String,
+    pub label: String,
+}
+
+fn dropdown_label(options: &[DropdownOption], selected: Option<&str>) -> String {
+    selected
+        .and_then(|value| options.iter().find(|opt| opt.value == value))
+        .map(|opt| opt.label.clone())
+        .unwrap_or_else(|| "(未選擇)".to_string())
+}
+
+#[component]
+pub fn DropdownSelect(
+    id: DropdownId,
+    label: &'static str,
+    options: Vec<DropdownOption>,
+    selected: Option<String>,
+    mut open_dropdown: Signal<Option<DropdownId>>,
+    mut dropdown_pos: Signal<Option<(f64, f64)>>,
+    on_select: EventHandler<String>,
+) -> Element {
+    let is_open = open_dropdown() == Some(id);
+    let selected_label = dropdown_label(&options, selected.as_deref());
+    let (left, top) = dropdown_pos().unwrap_or((0.0, 0.0));
+
+    rsx! {
+        div {
+            style: "position: relative; display: inline-flex; align-items: center; gap: 6px;",
+            span { "{label}" }
+            button {
+                style: "border: 1px solid #bbb; background: #fff; padding: 4px 10px; border-radius: 6px; cursor: pointer;",
+                aria_haspopup: "listbox",
+                aria_expanded: "{is_open}",
+                aria_label: "{label}{selected_label}",
+                onclick: move |event| {
+                    event.stop_propagation();
+                    if open_dropdown() == Some(id) {
+                        open_dropdown.set(None);
+                        return;
+                    }
+                    let point = event.client_coordinates();
+                    dropdown_pos.set(Some((point.x, point.y + 24.0)));
+                    open_dropdown.set(Some(id));
+                },
+                "{selected_label}"
+            }
+        }
+
+        if is_open {
+            div {
+                style: "position: fixed; left: {left}px; top: {top}px; min-width: 200px; max-height: 320px; overflow-y: auto; background: #fff; border: 1px solid #bbb; border-radius: 8px; box-shadow: 0 10px 24px rgba(0,0,0,0.15); z-index: 1200;",
+                role: "listbox",
+                aria_label: "{label}",
+                onclick: move |event| event.stop_propagation(),
+                onkeydown: move |event| {
+                    if event.key() == Key::Escape {
+                        open_dropdown.set(None);
+                    }
+                },
+                {options.iter().map(|opt| {
+                    let value = opt.value.clone();
+                    let value_for_key = opt.value.clone();
+                    let label = opt.label.clone();
+                    let is_selected = selected.as_deref() == Some(value.as_str());
+                    let background = if is_selected { "#eef4ff" } else { "transparent" };
+                    rsx!(
+                        div {
+                            style: "padding: 8px 10px; cursor: pointer; background: {background};",
+                            role: "option",
+                            aria_selected: "{is_selected}",
+                            tabindex: "0",
+                            onclick: move |_| {
+                                on_select.call(value.clone());
+                                open_dropdown.set(None);
+                            },
+                            onkeydown: move |event| {
+                                if event.key() == Key::Enter || event.key() == Key::Character(" ".to_string()) {
+                                    event.prevent_default();
+                                    on_select.call(value_for_key.clone());
+                                    open_dropdown.set(None);
+                                }
+                            },
+                            "{label}"
+                        }
+                    )
+                })}
+            }
+        }
+    }
+}
+
+#[component]
+pub fn ColumnVisibilityDropdown(
+    id: DropdownId,
+    label: &'static str,
+    columns: Vec<String>,
+    visibility: BTreeMap<i64, bool>,
+    aliases: BTreeMap<i64, String>,
+    builtin_presets: Vec<(String, BTreeMap<i64, bool>)>,
+    saved_presets: BTreeMap<String, BTreeMap<i64, bool>>,
+    mut open_dropdown: Signal<Option<DropdownId>>,
+    mut dropdown_pos: Signal<Option<(f64, f64)>>,
+    on_toggle: EventHandler<(i64, bool)>,
+    on_rename: EventHandler<(i64, String)>,
+    on_apply_preset: EventHandler<BTreeMap<i64, bool>>,
+    on_save_preset: EventHandler<String>,
+    on_delete_preset: EventHandler<String>,
+) -> Element {
+    let is_open = open_dropdown() == Some(id);
+    let mut preset_name = use_signal(String::new);
+    let (left, top) = dropdown_pos().unwrap_or((0.0, 0.0));
+
+    rsx! {
+        div {
+            style: "position: relative; display: inline-flex; align-items: center; gap: 6px;",
+            span { "{label}" }
+            button {
+                style: "border: 1px solid #bbb; background: #fff; padding: 4px 10px; border-radius: 6px; cursor: pointer;",
+                onclick: move |event| {
+                    event.stop_propagation();
+                    if open_dropdown() == Some(id) {
+                        open_dropdown.set(None);
+                        return;
+                    }
+                    let point = event.client_coordinates();
+                    dropdown_pos.set(Some((point.x, point.y + 24.0)));
+                    open_dropdown.set(Some(id));
+                },
+                aria_haspopup: "dialog",
+                aria_expanded: "{is_open}",
+                "顯示欄位"
+            }
+        }
+
+        if is_open {
+            div {
+                style: "position: fixed; left: {left}px; top: {top}px; min-width: 320px; max-height: 320px; overflow-y: auto; background: #fff; border: 1px solid #bbb; border-radius: 8px; box-shadow: 0 10px 24px rgba(0,0,0,0.15); z-index: 1200; padding: 6px;",
+                role: "dialog",
+                aria_label: "{label}",
+                onclick: move |event| event.stop_propagation(),
+                onkeydown: move |event| {
+                    if event.key() == Key::Escape {
+                        open_dropdown.set(None);
+                    }
+                },
+                div { style: "display: flex; flex-wrap: wrap; gap: 6px; padding: 4px 4px 8px; border-bottom: 1px solid #ddd;",
+                    {builtin_presets.iter().map(|(name, preset)| {
+                        let preset = preset.clone();
+                        rsx!(
+                            button {
+                                onclick: move |_| on_apply_preset.call(preset.clone()),
+                                "{name}"
+                            }
+                        )
+                    })}
+                    {saved_presets.iter().map(|(name, preset)| {
+                        let preset = preset.clone();
+                        let name_for_delete = name.clone();
+                        rsx!(
+                            span { style: "display: inline-flex;",
+                                button {
+                                    onclick: move |_| on_apply_preset.call(preset.clone()),
+                                    "{name}"
+                                }
+                                button {
+                                    title: "刪除檢視",
+                                    onclick: move |_| on_delete_preset.call(name_for_delete.clone()),
+                                    "×"
+                                }
+                            }
+                        )
+                    })}
+                }
+                div { style: "display: flex; gap: 6px; padding: 6px 4px; border-bottom: 1px solid #ddd;",
+                    input {
+                        style: "flex: 1;",
+                        placeholder: "檢視名稱",
+                        aria_label: "檢視名稱",
+                        value: preset_name(),
+                        oninput: move |event| preset_name.set(event.value()),
+                    }
+                    button {
+                        onclick: move |_| {
+                            on_save_preset.call(preset_name());
+                            preset_name.set(String::new());
+                        },
+                        "儲存目前檢視"
+                    }
+                }
+                {columns.iter().enumerate().map(|(idx, header)| {
+                    let checked = visibility.get(&(idx as i64)).copied().unwrap_or(true);
+                    let header = header.clone();
+                    let alias = aliases.get(&(idx as i64)).cloned().unwrap_or_default();
+                    rsx!(
+                        div {
+                            style: "display: flex; align-items: center; gap: 8px; padding: 6px 4px;",
+                            label {
+                                style: "display: flex; align-items: center; gap: 8px; flex: 1; cursor: pointer;",
+                                input {
+                                    r#type: "checkbox",
+                                    checked: checked,
+                                    onclick: move |_| {
+                                        on_toggle.call((idx as i64, !checked));
+                                    }
+                                }
+                                span { "{header}" }
+                            }
+                            input {
+                                style: "width: 110px;",
+                                placeholder: "別名",
+                                aria_label: "{header} 的別名",
+                                value: "{alias}",
+                                onchange: move |event: FormEvent| {
+                                    on_rename.call((idx as i64, event.value()));
+                                }
+                            }
+                        }
+                    )
+                })}
+            }
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use dioxus::prelude::*;
+
+use crate::platform::desktop::paths::{log_file_path, open_with_system};
+use crate::ui::components::modal::ModalDialog;
+use crate::ui::state::errors::ErrorReport;
+
+/// Full error chain for the last failure, with copy and log-file shortcuts.
+#[component]
+pub fn ErrorDialog(report: ErrorReport, on_close: EventHandler<()>) -> Element {
+    let mut copy_status = use_signal(String::new);
+    let log_path = log_file_path().ok();
+    let log_path_text = log_path
+        .as_ref()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|| "(無法取得記錄檔位置)".to_string());
+    let report_text = report.to_text();
+
+    rsx! {
+        ModalDialog {
+            label: report.context.clone(),
+            z_index: 1300,
+            panel_style: "min-width: 420px; max-width: 720px; max-height: 80vh; overflow: auto;",
+            on_close: move |_| on_close.call(()),
+            div { style: "margin-bottom: 8px; font-weight: 600; color: #b22;", "{report.context}" }
+            div { style: "margin-bottom: 8px; color: #666; font-size: 12px;", "{report.occurred_at}" }
+            ol { style: "margin: 0 0 12px; padding-left: 20px;",
+                for message in report.chain.iter() {
+                    li { style: "white-space: pre-wrap; word-break: break-all; margin-bottom: 4px;", "{message}" }
+                }
+            }
+            div { style: "font-size: 12px; color: #555; margin-bottom: 12px;", "記錄檔：{log_path_text}" }
+            div { style: "display: flex; gap: 8px; align-items: center;",
+                button {
+                    onclick: move |_| {
+                        let script = format!(
+                            "navigator.clipboard.writeText({:?}).then(() => dioxus.send(true)).catch(() => dioxus.send(false));",
+                            report_text
+                        );
+                        spawn(async move {
+                            let mut eval = document::eval(&script);
+                            let copied: bool = eval.recv().await.unwrap_or(false);
+                            copy_status.set(if copied { "已複製".to_string() } else { "複製失敗".to_string() });
+                        });
+                    },
+                    "複製錯誤內容"
+                }
+                button {
+                    disabled: log_path.is_none(),
+                    onclick: move |_| {
+                        if let Some(path) = log_path.as_ref() {
+                            if let Err(err) = open_with_system(path) {
+                                copy_status.set(format!("開啟記錄檔失敗：{err}"));
+                            }
+                        }
+                    },
+                    "開啟記錄檔"
+                }
+                span { style: "color: #555;", "{copy_status}" }
+                div { style: "flex: 1;" }
+                button { onclick: move |_| on_close.call(()), "關閉" }
+            }
+        }
+    }
+}
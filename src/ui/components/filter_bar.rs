@@ -0,0 +1,214 @@
+use std::collections::BTreeMap;
+
+use dioxus::prelude::*;
+
+use crate::ui::components::dropdown::{DropdownId, DropdownOption, DropdownSelect};
+use crate::{column_display_name, SearchHistoryEntry, NONE_OPTION_VALUE};
+
+fn column_options(
+    columns: &[String],
+    aliases: &BTreeMap<i64, String>,
+    none_label: &str,
+) -> Vec<DropdownOption> {
+    std::iter::once(DropdownOption {
+        value: NONE_OPTION_VALUE.to_string(),
+        label: none_label.to_string(),
+    })
+    .chain(
+        columns
+            .iter()
+            .enumerate()
+            .map(|(idx, header)| DropdownOption {
+                value: idx.to_string(),
+                label: column_display_name(header, idx, aliases),
+            }),
+    )
+    .collect()
+}
+
+/// What the user did with a remembered global search term.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchHistoryAction {
+    TogglePin(String),
+    Delete(String),
+}
+
+/// Global search, then `children` (the column display controls), then the per-column
+/// search, sort and grouping rows. The search fields are bound to the workspace signals;
+/// `on_search` fires with the error context of whichever search button was pressed, and
+/// `on_global_search` when a global search runs, so the term can join `search_history`.
+#[component]
+pub fn FilterBar(
+    busy: bool,
+    columns: Vec<String>,
+    aliases: BTreeMap<i64, String>,
+    group_by: Option<usize>,
+    mut global_search: Signal<String>,
+    mut column_search_col: Signal<Option<i64>>,
+    mut column_search_text: Signal<String>,
+    mut sort_col: Signal<Option<i64>>,
+    mut sort_desc: Signal<bool>,
+    open_dropdown: Signal<Option<DropdownId>>,
+    dropdown_pos: Signal<Option<(f64, f64)>>,
+    on_search: EventHandler<&'static str>,
+    on_global_search: EventHandler<()>,
+    search_history: Vec<SearchHistoryEntry>,
+    on_search_history: EventHandler<SearchHistoryAction>,
+    on_group: EventHandler<Option<usize>>,
+    children: Element,
+) -> Element {
+    let mut show_history = use_signal(|| false);
+    let mut run_global_search = move || {
+        if !busy {
+            show_history.set(false);
+            on_global_search.call(());
+        }
+    };
+
+    rsx! {
+        div {
+            style: "display: flex; gap: 12px; align-items: center; margin: 12px 0;",
+            div { style: "position: relative; display: inline-flex;",
+                input {
+                    placeholder: "全域搜尋",
+                    value: global_search(),
+                    oninput: move |event| global_search.set(event.value()),
+                    onkeydown: move |event: KeyboardEvent| match event.key() {
+                        Key::Enter => run_global_search(),
+                        Key::Escape => show_history.set(false),
+                        _ => {}
+                    },
+                }
+                button {
+                    title: "搜尋紀錄",
+                    aria_label: "搜尋紀錄",
+                    aria_expanded: "{show_history()}",
+                    disabled: search_history.is_empty(),
+                    onclick: move |_| show_history.set(!show_history()),
+                    "▾"
+                }
+                if show_history() && !search_history.is_empty() {
+                    div {
+                        role: "listbox",
+                        style: "position: absolute; top: 100%; left: 0; z-index: 900; min-width: 100%; background: #fff; border: 1px solid #bbb; box-shadow: 0 2px 6px rgba(0,0,0,0.15);",
+                        for entry in search_history.clone() {
+                            div { style: "display: flex; gap: 4px; align-items: center; padding: 2px 4px;",
+                                button {
+                                    role: "option",
+                                    style: "flex: 1; text-align: left; border: none; background: transparent;",
+                                    onclick: {
+                                        let term = entry.term.clone();
+                                        move |_| {
+                                            global_search.set(term.clone());
+                                            run_global_search();
+                                        }
+                                    },
+                                    "{entry.term}"
+                                }
+                                button {
+                                    title: if entry.pinned { "取消釘選" } else { "釘選" },
+                                    style: if entry.pinned { "color: #4a7bd0;" } else { "color: #999;" },
+                                    onclick: {
+                                        let term = entry.term.clone();
+                                        move |_| on_search_history.call(SearchHistoryAction::TogglePin(term.clone()))
+                                    },
+                                    "📌"
+                                }
+                                button {
+                                    title: "刪除",
+                                    onclick: {
+                                        let term = entry.term.clone();
+                                        move |_| on_search_history.call(SearchHistoryAction::Delete(term.clone()))
+                                    },
+                                    "×"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            button {
+                disabled: busy,
+                onclick: move |_| run_global_search(),
+                "搜尋"
+            }
+        }
+
+        {children}
+
+        if !columns.is_empty() {
+            div { style: "margin-bottom: 12px;",
+                DropdownSelect {
+                    id: DropdownId::Column,
+                    label: "欄位",
+                    options: column_options(&columns, &aliases, "選擇欄位"),
+                    selected: Some(
+                        column_search_col()
+                            .map(|idx| idx.to_string())
+                            .unwrap_or_else(|| NONE_OPTION_VALUE.to_string()),
+                    ),
+                    open_dropdown: open_dropdown,
+                    dropdown_pos: dropdown_pos,
+                    on_select: move |value: String| {
+                        column_search_col.set(value.parse::<i64>().ok());
+                    }
+                }
+                input {
+                    placeholder: "欄位搜尋",
+                    value: column_search_text(),
+                    oninput: move |event| column_search_text.set(event.value()),
+                }
+                button {
+                    disabled: busy,
+                    onclick: move |_| on_search.call("欄位搜尋失敗"),
+                    "欄位搜尋"
+                }
+            }
+            div { style: "margin-bottom: 12px;",
+                DropdownSelect {
+                    id: DropdownId::Sort,
+                    label: "排序",
+                    options: column_options(&columns, &aliases, "選擇排序欄位"),
+                    selected: Some(
+                        sort_col()
+                            .map(|idx| idx.to_string())
+                            .unwrap_or_else(|| NONE_OPTION_VALUE.to_string()),
+                    ),
+                    open_dropdown: open_dropdown,
+                    dropdown_pos: dropdown_pos,
+                    on_select: move |value: String| {
+                        sort_col.set(value.parse::<i64>().ok());
+                    }
+                }
+                button {
+                    disabled: busy,
+                    onclick: move |_| {
+                        sort_desc.set(!sort_desc());
+                        on_search.call("排序失敗");
+                    },
+                    if sort_desc() { "降冪" } else { "升冪" }
+                }
+                button {
+                    disabled: busy,
+                    onclick: move |_| on_search.call("排序失敗"),
+                    "套用排序"
+                }
+            }
+            div { style: "margin-bottom: 12px;",
+                DropdownSelect {
+                    id: DropdownId::GroupBy,
+                    label: "分組",
+                    options: column_options(&columns, &aliases, "不分組"),
+                    selected: Some(
+                        group_by
+                            .map(|idx| idx.to_string())
+                            .unwrap_or_else(|| NONE_OPTION_VALUE.to_string()),
+                    ),
+                    open_dropdown: open_dropdown,
+                    dropdown_pos: dropdown_pos,
+                    on_select: move |value: String| on_group.call(value.parse::<usize>().ok()),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use dioxus::prelude::*;
+
+/// The focused cell of the table, as shown in the formula bar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormulaCell {
+    /// Identifies the cell, so a draft typed into one cell is not shown for another.
+    pub key: String,
+    pub label: String,
+    /// The stored (or staged) value; `=運算式` for a computed column.
+    pub value: String,
+    pub editable: bool,
+}
+
+/// Excel-like bar above the table with the raw value of the focused cell. Typing replaces
+/// the value; Enter or 套用 hands the text to `on_commit` and Escape restores the value.
+#[component]
+pub fn FormulaBar(cell: Option<FormulaCell>, on_commit: EventHandler<String>) -> Element {
+    let mut draft = use_signal(|| None::<(String, String)>);
+    let key = cell.as_ref().map(|cell| cell.key.clone());
+    let pending = draft()
+        .filter(|(draft_key, _)| Some(draft_key) == key.as_ref())
+        .map(|(_, text)| text);
+    let has_pending = pending.is_some();
+    let text = pending
+        .or_else(|| cell.as_ref().map(|cell| cell.value.clone()))
+        .unwrap_or_default();
+    let editable = cell.as_ref().is_some_and(|cell| cell.editable);
+    let label = cell
+        .as_ref()
+        .map(|cell| cell.label.clone())
+        .unwrap_or_default();
+    let commit = {
+        let key = key.clone();
+        move || match draft.take() {
+            Some((draft_key, text)) if Some(&draft_key) == key.as_ref() => on_commit.call(text),
+            _ => {}
+        }
+    };
+
+    rsx! {
+        div { style: "display: flex; gap: 6px; align-items: center; margin-bottom: 6px;",
+            span { style: "min-width: 140px; font-size: 12px; color: #555;", "{label}" }
+            span { style: "font-style: italic; color: #777;", "fx" }
+            input {
+                style: "flex: 1;",
+                aria_label: "儲存格內容",
+                value: text,
+                readonly: !editable,
+                disabled: key.is_none(),
+                oninput: {
+                    let key = key.clone();
+                    move |event: FormEvent| {
+                        if let Some(key) = key.clone() {
+                            draft.set(Some((key, event.value())));
+                        }
+                    }
+                },
+                onkeydown: {
+                    let mut commit = commit.clone();
+                    move |event: KeyboardEvent| match event.key() {
+                        Key::Enter => commit(),
+                        Key::Escape => draft.set(None),
+                        _ => {}
+                    }
+                },
+            }
+            button {
+                disabled: !editable || !has_pending,
+                onclick: {
+                    let mut commit = commit.clone();
+                    move |_| commit()
+                },
+                "套用"
+            }
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use std::path::PathBuf;
+
+use dioxus::prelude::*;
+
+use crate::domain::entities::import_profile::{ColumnMapping, ImportProfile};
+use crate::infra::import::mapped_csv::{csv_headers, PROFILE_ENCODINGS};
+use crate::ui::components::modal::ModalDialog;
+
+/// Maps the columns of a CSV onto the holdings columns and saves the mapping as a named
+/// profile, which later imports of files with the same headers apply automatically. Opened
+/// for a file whose headers fit no dataset type, it explains why and also offers to import
+/// the file unmapped.
+#[component]
+pub fn ImportMappingDialog(
+    file_path: PathBuf,
+    profile: ImportProfile,
+    saved: Vec<ImportProfile>,
+    /// Required columns of 持股, offered as targets before any the profile adds.
+    required_columns: Vec<String>,
+    #[props(default)] unknown_headers: bool,
+    busy: bool,
+    on_import: EventHandler<ImportProfile>,
+    on_import_unmapped: EventHandler<()>,
+    on_delete: EventHandler<String>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let mut draft = use_signal(|| profile.clone());
+    let current = draft();
+    let headers = csv_headers(&file_path, &current.encoding, current.header_row);
+    let mut targets = required_columns.clone();
+    for mapping in &current.columns {
+        if !targets.contains(&mapping.target) {
+            targets.push(mapping.target.clone());
+        }
+    }
+    let source_of = |target: &str| {
+        current
+            .columns
+            .iter()
+            .find(|mapping| mapping.target == target)
+            .map(|mapping| mapping.source.clone())
+            .unwrap_or_default()
+    };
+    let can_import = !busy && !current.name.trim().is_empty() && !current.columns.is_empty();
+
+    rsx! {
+        ModalDialog {
+            label: "匯入對應設定".to_string(),
+            panel_style: "min-width: 460px; max-height: 80vh; overflow-y: auto;",
+            on_close: move |_| on_close.call(()),
+            div { style: "margin-bottom: 8px; font-weight: 600;", "以對應設定匯入 CSV" }
+            div { style: "margin-bottom: 8px; word-break: break-all; color: #555;",
+                "{file_path.display()}"
+            }
+            if unknown_headers {
+                div { style: "margin-bottom: 8px;",
+                    "這個 CSV 的欄位不符合任何資料集類型。下方已依欄位名稱的相似程度建議對應，請確認或調整；不需要的欄位選「（不匯入）」。"
+                }
+            }
+            div { style: "display: grid; grid-template-columns: auto 1fr; gap: 8px; align-items: center;",
+                label { "設定名稱" }
+                input {
+                    value: current.name.clone(),
+                    oninput: move |event| draft.write().name = event.value(),
+                }
+                label { "編碼" }
+                select {
+                    onchange: move |event| draft.write().encoding = event.value(),
+                    for (value, label) in PROFILE_ENCODINGS {
+                        option {
+                            value,
+                            selected: current.encoding.eq_ignore_ascii_case(value),
+                            "{label}"
+                        }
+                    }
+                }
+                label { "標題列" }
+                input {
+                    r#type: "number",
+                    min: "1",
+                    value: (current.header_row + 1).to_string(),
+                    oninput: move |event| {
+                        if let Ok(row) = event.value().trim().parse::<usize>() {
+                            draft.write().header_row = row.saturating_sub(1);
+                        }
+                    },
+                }
+            }
+            match headers {
+                Err(err) => rsx! {
+                    div { style: "margin-top: 8px; color: #b00020;", "無法讀取標題列：{err:#}" }
+                },
+                Ok(headers) => {
+                    let ignored = headers
+                        .iter()
+                        .filter(|header| {
+                            !header.is_empty()
+                                && !current.columns.iter().any(|mapping| mapping.source == **header)
+                        })
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join("、");
+                    rsx! {
+                        div { style: "display: grid; grid-template-columns: auto 1fr; gap: 6px 8px; align-items: center; margin-top: 12px;",
+                            for target in targets.clone() {
+                                label { "{target}" }
+                                select {
+                                    onchange: {
+                                        let target = target.clone();
+                                        let targets = targets.clone();
+                                        move |event: FormEvent| {
+                                            let source = event.value();
+                                            let mut next = draft();
+                                            let mut sources = targets
+                                                .iter()
+                                                .map(|name| {
+                                                    next.columns
+                                                        .iter()
+                                                        .find(|mapping| &mapping.target == name)
+                                                        .map(|mapping| mapping.source.clone())
+                                                        .unwrap_or_default()
+                                                })
+                                                .collect::<Vec<_>>();
+                                            if let Some(idx) = targets.iter().position(|name| *name == target) {
+                                                sources[idx] = source;
+                                            }
+                                            next.columns = targets
+                                                .iter()
+                                                .zip(sources)
+                                                .filter(|(_, source)| !source.is_empty())
+                                                .map(|(target, source)| ColumnMapping {
+                                                    source,
+                                                    target: target.clone(),
+                                                })
+                                                .collect();
+                                            draft.set(next);
+                                        }
+                                    },
+                                    option { value: "", selected: source_of(&target).is_empty(), "（不匯入）" }
+                                    for header in headers.iter().filter(|header| !header.is_empty()) {
+                                        option {
+                                            value: header.clone(),
+                                            selected: source_of(&target) == *header,
+                                            "{header}"
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        if !ignored.is_empty() {
+                            div { style: "margin-top: 8px; font-size: 12px; color: #555;",
+                                "不會匯入的欄位：{ignored}"
+                            }
+                        }
+                    }
+                }
+            }
+            if !saved.is_empty() {
+                div { style: "margin-top: 12px; font-weight: 600;", "已儲存的設定" }
+                for saved_profile in saved.iter().cloned() {
+                    div {
+                        key: "{saved_profile.name}",
+                        style: "display: flex; gap: 8px; align-items: center; margin-top: 4px;",
+                        span { style: "flex: 1;", "{saved_profile.name}" }
+                        button {
+                            onclick: {
+                                let saved_profile = saved_profile.clone();
+                                move |_| draft.set(saved_profile.clone())
+                            },
+                            "套用"
+                        }
+                        button {
+                            disabled: busy,
+                            onclick: move |_| on_delete.call(saved_profile.name.clone()),
+                            "刪除"
+                        }
+                    }
+                }
+            }
+            div { style: "margin-top: 8px; font-size: 12px; color: #555;",
+                "儲存後，之後匯入標題相同的 CSV 會自動套用這組設定。"
+            }
+            div { style: "display: flex; justify-content: flex-end; gap: 8px; margin-top: 12px;",
+                button {
+                    disabled: !can_import,
+                    onclick: move |_| {
+                        let mut profile = draft();
+                        profile.name = profile.name.trim().to_string();
+                        on_import.call(profile);
+                    },
+                    "儲存並匯入"
+                }
+                if unknown_headers {
+                    button {
+                        disabled: busy,
+                        onclick: move |_| on_import_unmapped.call(()),
+                        "照原樣匯入"
+                    }
+                }
+                button { onclick: move |_| on_close.call(()), "取消" }
+            }
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use dioxus::prelude::*;
+
+use crate::ui::components::modal::ModalDialog;
+use bom_core::holdings::DuplicateCode;
+
+/// Shown before importing a workbook whose 持股明細 lists a 代號 more than once: merge the
+/// lots into one row, import them as they are, or stop.
+#[component]
+pub fn DuplicateCodesDialog(
+    file_name: String,
+    duplicates: Vec<DuplicateCode>,
+    on_merge: EventHandler<()>,
+    on_keep_both: EventHandler<()>,
+    on_cancel: EventHandler<()>,
+) -> Element {
+    rsx! {
+        ModalDialog {
+            label: "重複的代號".to_string(),
+            z_index: 1100,
+            panel_style: "min-width: 320px; max-width: 480px;",
+            on_close: move |_| on_cancel.call(()),
+            div { style: "margin-bottom: 8px; font-weight: 600;", "重複的代號" }
+            div { style: "margin-bottom: 8px;",
+                "{file_name} 的持股明細中有代號出現多次："
+            }
+            ul { style: "margin: 0 0 12px; padding-left: 20px; max-height: 240px; overflow-y: auto;",
+                for duplicate in duplicates.iter() {
+                    li { key: "{duplicate.code}",
+                        "{duplicate.code}：{duplicate.names.join(\"、\")}"
+                    }
+                }
+            }
+            div { style: "margin-bottom: 12px; color: #555; font-size: 12px;",
+                "合併會加總數量，買進價以數量加權平均；保留兩筆則照原樣匯入。"
+            }
+            div { style: "display: flex; gap: 8px;",
+                button { onclick: move |_| on_merge.call(()), "合併" }
+                button { onclick: move |_| on_keep_both.call(()), "保留兩筆" }
+                button { onclick: move |_| on_cancel.call(()), "取消" }
+            }
+        }
+    }
+}
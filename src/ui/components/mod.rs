@@ -1,2 +1,33 @@
+pub mod account_form;
+pub mod app_lock;
+pub mod balance_update;
+pub mod batch_import;
+pub mod chart;
+pub mod column_format;
+pub mod command_palette;
+pub mod computed_columns;
+pub mod dataset_manager;
+pub mod dataset_picker;
+pub mod dividend_receipts;
+pub mod dropdown;
+pub mod error_dialog;
+pub mod filter_bar;
+pub mod formula_bar;
+pub mod import_mapping;
+pub mod import_review;
+pub mod modal;
+pub mod owners;
+pub mod quick_filters;
+pub mod remote_backup;
+pub mod report_builder;
+pub mod row_detail;
+pub mod save_prompt;
+pub mod status_bar;
+pub mod summary_dialog;
+pub mod sync_folder;
+pub mod table_image;
+pub mod toolbar;
+pub mod welcome;
+
 #[allow(dead_code)]
 pub fn components_placeholder() {}
@@ -0,0 +1,89 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use dioxus::prelude::*;
+
+static NEXT_MODAL_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Visible outlines for keyboard focus; rendered once at the root of every window.
+pub const FOCUS_STYLES: &str = "button:focus-visible, input:focus-visible, select:focus-visible, textarea:focus-visible, [tabindex]:focus-visible { outline: 2px solid #1a5fd0; outline-offset: 2px; }";
+
+/// Moves focus into the dialog and keeps Tab / Shift+Tab cycling inside it. Focus goes back
+/// to whatever was focused before once the dialog is removed.
+const FOCUS_TRAP_SCRIPT: &str = r#"
+const id = await dioxus.recv();
+const dialog = document.getElementById(id);
+if (dialog) {
+  const previous = document.activeElement;
+  const focusables = () => Array.from(dialog.querySelectorAll(
+    'button, input, select, textarea, a[href], [tabindex]:not([tabindex="-1"])'
+  )).filter((el) => !el.disabled && el.offsetParent !== null);
+  const first = focusables()[0];
+  (dialog.querySelector('[autofocus]') || first || dialog).focus();
+  dialog.addEventListener('keydown', (event) => {
+    if (event.key !== 'Tab') return;
+    const items = focusables();
+    if (items.length === 0) {
+      event.preventDefault();
+      return;
+    }
+    const head = items[0];
+    const tail = items[items.length - 1];
+    if (event.shiftKey && document.activeElement === head) {
+      event.preventDefault();
+      tail.focus();
+    } else if (!event.shiftKey && document.activeElement === tail) {
+      event.preventDefault();
+      head.focus();
+    }
+  });
+  new MutationObserver((_, observer) => {
+    if (!document.body.contains(dialog)) {
+      observer.disconnect();
+      if (previous && document.body.contains(previous)) previous.focus();
+    }
+  }).observe(document.body, { childList: true, subtree: true });
+}
+"#;
+
+/// Overlay plus a `role="dialog"` panel with focus trapping and Escape-to-close.
+#[component]
+pub fn ModalDialog(
+    label: String,
+    #[props(default = 1100)] z_index: i32,
+    #[props(default = "min-width: 280px;".to_string())] panel_style: String,
+    on_close: EventHandler<()>,
+    children: Element,
+) -> Element {
+    let dialog_id = use_hook(|| {
+        format!(
+            "modal-dialog-{}",
+            NEXT_MODAL_ID.fetch_add(1, Ordering::Relaxed)
+        )
+    });
+    let dialog_id_for_trap = dialog_id.clone();
+    use_effect(move || {
+        let eval = document::eval(FOCUS_TRAP_SCRIPT);
+        let _ = eval.send(dialog_id_for_trap.clone());
+    });
+
+    rsx! {
+        div {
+            style: "position: fixed; inset: 0; background: rgba(0,0,0,0.35); display: flex; align-items: center; justify-content: center; z-index: {z_index};",
+            div {
+                id: "{dialog_id}",
+                role: "dialog",
+                aria_modal: "true",
+                aria_label: "{label}",
+                tabindex: "-1",
+                style: "background: #fff; padding: 16px; border: 1px solid #999; {panel_style}",
+                onkeydown: move |event| {
+                    if event.key() == Key::Escape {
+                        event.stop_propagation();
+                        on_close.call(());
+                    }
+                },
+                {children}
+            }
+        }
+    }
+}
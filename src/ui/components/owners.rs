@@ -0,0 +1,42 @@
+use dioxus::prelude::*;
+
+use crate::ui::components::modal::ModalDialog;
+use bom_core::report::parse_owner_list;
+
+/// Edits the owners list, one name per line. Listed owners come first, in this order, in
+/// the per-owner part of the summary report; owners not listed follow.
+#[component]
+pub fn DividendOwnersDialog(
+    owners: Vec<String>,
+    busy: bool,
+    on_save: EventHandler<Vec<String>>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let mut draft = use_signal(|| owners.join("\n"));
+
+    rsx! {
+        ModalDialog {
+            label: "所有權人".to_string(),
+            panel_style: "min-width: 360px;",
+            on_close: move |_| on_close.call(()),
+            div { style: "margin-bottom: 8px; font-weight: 600;", "所有權人" }
+            textarea {
+                style: "width: 100%; min-height: 140px; box-sizing: border-box;",
+                placeholder: "每行一位所有權人",
+                value: draft(),
+                oninput: move |event| draft.set(event.value()),
+            }
+            div { style: "margin-top: 8px; font-size: 12px; color: #555;",
+                "總結報表會依這個順序列出所有權人；留空則依名稱排序。"
+            }
+            div { style: "display: flex; justify-content: flex-end; gap: 8px; margin-top: 12px;",
+                button {
+                    disabled: busy,
+                    onclick: move |_| on_save.call(parse_owner_list(&draft())),
+                    "保存"
+                }
+                button { onclick: move |_| on_close.call(()), "取消" }
+            }
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use dioxus::prelude::*;
+
+/// A key column with its distinct values and how many rows hold each.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuickFilterColumn {
+    pub col_idx: i64,
+    pub label: String,
+    pub values: Vec<(String, i64)>,
+}
+
+/// One row of chips per key column. Clicking a chip toggles its value in that column's
+/// filter; rows must match one of the checked values of every filtered column.
+#[component]
+pub fn QuickFilterChips(
+    columns: Vec<QuickFilterColumn>,
+    selected: BTreeMap<i64, BTreeSet<String>>,
+    busy: bool,
+    on_toggle: EventHandler<(i64, String)>,
+    on_clear: EventHandler<()>,
+) -> Element {
+    let any_selected = selected.values().any(|values| !values.is_empty());
+
+    rsx! {
+        div { style: "display: flex; flex-direction: column; gap: 4px; margin-bottom: 8px;",
+            for column in columns {
+                div { style: "display: flex; flex-wrap: wrap; gap: 4px; align-items: center;",
+                    span { style: "min-width: 64px; font-size: 12px; color: #555;", "{column.label}" }
+                    for (value, count) in column.values.clone() {
+                        {
+                            let active = selected
+                                .get(&column.col_idx)
+                                .is_some_and(|values| values.contains(&value));
+                            let chip_style = if active {
+                                "border: 1px solid #4a7bd0; background: #4a7bd0; color: #fff;"
+                            } else {
+                                "border: 1px solid #bbb; background: #f6f6f6; color: #222;"
+                            };
+                            let col_idx = column.col_idx;
+                            rsx! {
+                                button {
+                                    style: "{chip_style} border-radius: 12px; padding: 1px 10px; font-size: 12px;",
+                                    disabled: busy,
+                                    aria_pressed: "{active}",
+                                    onclick: {
+                                        let value = value.clone();
+                                        move |_| on_toggle.call((col_idx, value.clone()))
+                                    },
+                                    "{value} ({count})"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            if any_selected {
+                div {
+                    button {
+                        style: "font-size: 12px;",
+                        disabled: busy,
+                        onclick: move |_| on_clear.call(()),
+                        "清除快速篩選"
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use dioxus::prelude::*;
+
+use crate::infra::remote_backup::{RemoteBackupConfig, RemoteBackupKind};
+use crate::ui::components::modal::ModalDialog;
+
+/// Edits the WebDAV / S3 upload target and shows how the last upload went. 立即上傳 saves
+/// the form first, then backs up and uploads without keeping a local copy.
+#[component]
+pub fn RemoteBackupSettings(
+    config: RemoteBackupConfig,
+    last_status: Option<String>,
+    busy: bool,
+    on_save: EventHandler<RemoteBackupConfig>,
+    on_upload: EventHandler<RemoteBackupConfig>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let mut draft = use_signal(|| config.clone());
+    let current = draft();
+    let is_s3 = current.kind == RemoteBackupKind::S3;
+    let (user_label, secret_label) = if is_s3 {
+        ("存取金鑰", "私密金鑰")
+    } else {
+        ("帳號", "密碼")
+    };
+
+    rsx! {
+        ModalDialog {
+            label: "遠端備份".to_string(),
+            panel_style: "min-width: 420px;",
+            on_close: move |_| on_close.call(()),
+            div { style: "margin-bottom: 8px; font-weight: 600;", "遠端備份" }
+            div { style: "display: grid; grid-template-columns: auto 1fr; gap: 8px; align-items: center;",
+                label { "類型" }
+                select {
+                    onchange: move |event| {
+                        if let Some(kind) = RemoteBackupKind::parse(&event.value()) {
+                            draft.write().kind = kind;
+                        }
+                    },
+                    option { value: "webdav", selected: !is_s3, "WebDAV" }
+                    option { value: "s3", selected: is_s3, "S3 相容" }
+                }
+                label { "網址" }
+                input {
+                    placeholder: if is_s3 { "https://端點/儲存貯體/前綴" } else { "https://伺服器/資料夾" },
+                    value: current.url.clone(),
+                    oninput: move |event| draft.write().url = event.value(),
+                }
+                label { "{user_label}" }
+                input {
+                    value: current.username.clone(),
+                    oninput: move |event| draft.write().username = event.value(),
+                }
+                label { "{secret_label}" }
+                input {
+                    r#type: "password",
+                    value: current.secret.clone(),
+                    oninput: move |event| draft.write().secret = event.value(),
+                }
+                if is_s3 {
+                    label { "區域" }
+                    input {
+                        placeholder: "us-east-1",
+                        value: current.region.clone(),
+                        oninput: move |event| draft.write().region = event.value(),
+                    }
+                }
+            }
+            div { style: "margin-top: 8px; font-size: 12px; color: #555;",
+                "設定後，每次備份資料庫都會一併上傳；網址留空即停用。帳號與金鑰只透過 https:// 傳送，並以明文保存在本機資料庫中。"
+            }
+            div { style: "margin-top: 8px;",
+                "上次上傳："
+                {last_status.unwrap_or_else(|| "尚未上傳".to_string())}
+            }
+            div { style: "display: flex; justify-content: flex-end; gap: 8px; margin-top: 12px;",
+                button {
+                    disabled: busy || !current.is_configured(),
+                    onclick: move |_| on_upload.call(draft()),
+                    "立即上傳"
+                }
+                button { disabled: busy, onclick: move |_| on_save.call(draft()), "儲存" }
+                button { onclick: move |_| on_close.call(()), "取消" }
+            }
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use dioxus::prelude::*;
+
+use crate::domain::entities::saved_report::SavedReport;
+use crate::report_script::{
+    parse_report_script, Aggregate, Comparison, ReportDefinition, ReportFilter, ReportMetric,
+};
+use crate::ui::components::modal::ModalDialog;
+
+/// Builds a report from metrics, an optional grouping column and filters over the
+/// selected dataset's `columns`. Reports are saved by name in their script form and can
+/// be loaded back here or run from 總結報表 → 已存報表.
+#[component]
+pub fn ReportBuilderDialog(
+    columns: Vec<String>,
+    saved: Vec<SavedReport>,
+    busy: bool,
+    on_save: EventHandler<SavedReport>,
+    on_run: EventHandler<ReportDefinition>,
+    on_delete: EventHandler<String>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let mut name = use_signal(String::new);
+    let mut draft = use_signal(|| ReportDefinition {
+        metrics: vec![ReportMetric {
+            aggregate: Aggregate::Count,
+            column: None,
+            label: Aggregate::Count.default_label(None),
+        }],
+        ..ReportDefinition::default()
+    });
+    let mut message = use_signal(String::new);
+    let definition = draft();
+    let first_column = columns.first().cloned().unwrap_or_default();
+
+    let mut load = move |report: &SavedReport| match parse_report_script(&report.script) {
+        Ok(definition) => {
+            name.set(report.name.clone());
+            draft.set(definition);
+            message.set(String::new());
+        }
+        Err(err) => message.set(format!("無法載入報表「{}」：{err}", report.name)),
+    };
+
+    rsx! {
+        ModalDialog {
+            label: "報表產生器".to_string(),
+            z_index: 1200,
+            panel_style: "min-width: 560px; max-width: 860px; max-height: 85vh; overflow: auto;",
+            on_close: move |_| on_close.call(()),
+            div { style: "margin-bottom: 8px; font-weight: 600;", "報表產生器" }
+            if !saved.is_empty() {
+                div { style: "display: flex; flex-wrap: wrap; gap: 6px; align-items: center; margin-bottom: 8px;",
+                    span { style: "font-size: 12px; color: #555;", "已存報表" }
+                    for report in saved.clone() {
+                        span { style: "display: inline-flex; gap: 2px;",
+                            button {
+                                onclick: {
+                                    let report = report.clone();
+                                    move |_| load(&report)
+                                },
+                                "{report.name}"
+                            }
+                            button {
+                                disabled: busy,
+                                title: "刪除",
+                                onclick: {
+                                    let report_name = report.name.clone();
+                                    move |_| on_delete.call(report_name.clone())
+                                },
+                                "×"
+                            }
+                        }
+                    }
+                }
+            }
+            div { style: "display: grid; grid-template-columns: auto 1fr; gap: 6px 8px; align-items: center;",
+                span { "名稱" }
+                input {
+                    value: name(),
+                    placeholder: "儲存時使用的名稱",
+                    oninput: move |event| name.set(event.value()),
+                }
+                span { "標題" }
+                input {
+                    value: definition.title.clone(),
+                    oninput: move |event| draft.write().title = event.value(),
+                }
+                span { "分組" }
+                select {
+                    onchange: move |event| {
+                        let value = event.value();
+                        draft.write().group_by = (!value.is_empty()).then_some(value);
+                    },
+                    option { value: "", selected: definition.group_by.is_none(), "（不分組）" }
+                    for column in columns.clone() {
+                        option {
+                            value: column.clone(),
+                            selected: definition.group_by.as_deref() == Some(column.as_str()),
+                            "{column}"
+                        }
+                    }
+                }
+            }
+
+            div { style: "margin-top: 12px; font-weight: 600;", "計算項目" }
+            for (idx, metric) in definition.metrics.iter().cloned().enumerate() {
+                div { style: "display: flex; gap: 6px; align-items: center; margin-top: 4px;",
+                    select {
+                        onchange: {
+                            let first_column = first_column.clone();
+                            move |event: FormEvent| {
+                                let Some(aggregate) = Aggregate::parse(&event.value()) else {
+                                    return;
+                                };
+                                let mut definition = draft.write();
+                                let metric = &mut definition.metrics[idx];
+                                let relabel = metric.label
+                                    == metric.aggregate.default_label(metric_column(metric));
+                                metric.aggregate = aggregate;
+                                if aggregate != Aggregate::Count && metric.column.is_none() {
+                                    metric.column = Some(first_column.clone());
+                                }
+                                if relabel {
+                                    metric.label = aggregate.default_label(metric_column(metric));
+                                }
+                            }
+                        },
+                        for aggregate in Aggregate::ALL {
+                            option {
+                                value: aggregate.as_str(),
+                                selected: aggregate == metric.aggregate,
+                                "{aggregate.label()}"
+                            }
+                        }
+                    }
+                    if metric.aggregate != Aggregate::Count {
+                        select {
+                            onchange: move |event: FormEvent| {
+                                let mut definition = draft.write();
+                                let metric = &mut definition.metrics[idx];
+                                let relabel = metric.label
+                                    == metric.aggregate.default_label(metric_column(metric));
+                                metric.column = Some(event.value());
+                                if relabel {
+                                    metric.label =
+                                        metric.aggregate.default_label(metric_column(metric));
+                                }
+                            },
+                            for column in columns.clone() {
+                                option {
+                                    value: column.clone(),
+                                    selected: metric.column.as_deref() == Some(column.as_str()),
+                                    "{column}"
+                                }
+                            }
+                        }
+                    }
+                    input {
+                        style: "flex: 1;",
+                        value: metric.label.clone(),
+                        placeholder: "顯示名稱",
+                        oninput: move |event| draft.write().metrics[idx].label = event.value(),
+                    }
+                    button {
+                        title: "移除",
+                        onclick: move |_| {
+                            draft.write().metrics.remove(idx);
+                        },
+                        "×"
+                    }
+                }
+            }
+            button {
+                style: "margin-top: 4px;",
+                onclick: {
+                    let first_column = first_column.clone();
+                    move |_| {
+                        draft.write().metrics.push(ReportMetric {
+                            aggregate: Aggregate::Sum,
+                            column: Some(first_column.clone()),
+                            label: Aggregate::Sum.default_label(Some(&first_column)),
+                        });
+                    }
+                },
+                "新增計算項目"
+            }
+
+            div { style: "margin-top: 12px; font-weight: 600;", "篩選條件" }
+            for (idx, filter) in definition.filters.iter().cloned().enumerate() {
+                div { style: "display: flex; gap: 6px; align-items: center; margin-top: 4px;",
+                    select {
+                        onchange: move |event: FormEvent| draft.write().filters[idx].column = event.value(),
+                        for column in columns.clone() {
+                            option {
+                                value: column.clone(),
+                                selected: filter.column == column,
+                                "{column}"
+                            }
+                        }
+                    }
+                    select {
+                        onchange: move |event: FormEvent| {
+                            if let Some(comparison) = Comparison::ALL
+                                .into_iter()
+                                .find(|comparison| comparison.as_str() == event.value())
+                            {
+                                draft.write().filters[idx].comparison = comparison;
+                            }
+                        },
+                        for comparison in Comparison::ALL {
+                            option {
+                                value: comparison.as_str(),
+                                selected: comparison == filter.comparison,
+                                "{comparison.label()}"
+                            }
+                        }
+                    }
+                    input {
+                        style: "flex: 1;",
+                        value: filter.value.clone(),
+                        oninput: move |event| draft.write().filters[idx].value = event.value(),
+                    }
+                    button {
+                        title: "移除",
+                        onclick: move |_| {
+                            draft.write().filters.remove(idx);
+                        },
+                        "×"
+                    }
+                }
+            }
+            button {
+                style: "margin-top: 4px;",
+                onclick: {
+                    let first_column = first_column.clone();
+                    move |_| {
+                        draft.write().filters.push(ReportFilter {
+                            column: first_column.clone(),
+                            comparison: Comparison::Eq,
+                            value: String::new(),
+                        });
+                    }
+                },
+                "新增篩選條件"
+            }
+
+            if !message().is_empty() {
+                div { style: "margin-top: 8px; color: #b00020;", "{message}" }
+            }
+            div { style: "display: flex; justify-content: flex-end; gap: 8px; margin-top: 12px;",
+                button {
+                    disabled: busy || name().trim().is_empty(),
+                    onclick: move |_| {
+                        on_save.call(SavedReport {
+                            name: name().trim().to_string(),
+                            script: draft().to_script(),
+                        });
+                    },
+                    "儲存"
+                }
+                button {
+                    disabled: busy || definition.metrics.is_empty(),
+                    onclick: move |_| on_run.call(draft()),
+                    "執行"
+                }
+                button { onclick: move |_| on_close.call(()), "關閉" }
+            }
+        }
+    }
+}
+
+/// The column a metric reads; `count` reads none.
+fn metric_column(metric: &ReportMetric) -> Option<&str> {
+    metric
+        .column
+        .as_deref()
+        .filter(|_| metric.aggregate != Aggregate::Count)
+}
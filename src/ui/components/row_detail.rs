@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+
+use dioxus::prelude::*;
+
+use crate::domain::entities::dataset::{ColumnFormat, ColumnType, DatasetDisplay};
+use crate::domain::entities::owner_share::OwnerShare;
+use crate::format_display_cell_value;
+use bom_core::numeric::format_f64;
+use bom_core::owner_split::format_owner_shares;
+
+/// One column of the row shown in the detail panel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RowDetailField {
+    pub col_idx: usize,
+    pub header: String,
+    pub label: String,
+    pub value: String,
+    pub column_type: ColumnType,
+    pub hidden: bool,
+    pub editable: bool,
+}
+
+/// Vertical view of every column of one row, hidden columns included, and how the row is
+/// split between co-owners.
+#[component]
+pub fn RowDetailPanel(
+    row_number: usize,
+    fields: Vec<RowDetailField>,
+    formats: BTreeMap<i64, ColumnFormat>,
+    display: DatasetDisplay,
+    /// Co-owners of the row; `None` leaves the section out, for datasets without 所有權人.
+    owner_shares: Option<Vec<OwnerShare>>,
+    shares_editable: bool,
+    on_commit: EventHandler<(usize, String)>,
+    on_shares_commit: EventHandler<String>,
+    on_close: EventHandler<()>,
+) -> Element {
+    rsx! {
+        div {
+            role: "dialog",
+            aria_label: "第 {row_number} 列詳細資料",
+            style: "position: fixed; top: 0; right: 0; bottom: 0; width: 380px; background: #fff; border-left: 1px solid #999; box-shadow: -6px 0 18px rgba(0,0,0,0.12); z-index: 1150; display: flex; flex-direction: column;",
+            onkeydown: move |event| {
+                if event.key() == Key::Escape {
+                    on_close.call(());
+                }
+            },
+            div { style: "display: flex; justify-content: space-between; align-items: center; padding: 10px 12px; border-bottom: 1px solid #ddd;",
+                span { style: "font-weight: 600;", "第 {row_number} 列" }
+                button { onclick: move |_| on_close.call(()), "關閉" }
+            }
+            div { style: "flex: 1; overflow-y: auto; padding: 8px 12px;",
+                for field in fields.iter() {
+                    {
+                        let col_idx = field.col_idx;
+                        let formatted = format_display_cell_value(
+                            &field.header,
+                            field.column_type,
+                            &field.value,
+                            formats.get(&(col_idx as i64)),
+                            &display,
+                        );
+                        let label_color = if field.hidden { "#888" } else { "#333" };
+                        rsx! {
+                            div { style: "display: flex; flex-direction: column; gap: 2px; padding: 6px 0; border-bottom: 1px solid #f0f0f0;",
+                                span { style: "font-size: 12px; color: {label_color};",
+                                    "{field.label}"
+                                    if field.hidden { " （隱藏）" }
+                                }
+                                if field.editable {
+                                    input {
+                                        aria_label: "{field.label}",
+                                        value: "{field.value}",
+                                        onchange: move |event: FormEvent| {
+                                            on_commit.call((col_idx, event.value()));
+                                        }
+                                    }
+                                } else {
+                                    span { style: "white-space: pre-wrap; word-break: break-all;", "{formatted}" }
+                                }
+                            }
+                        }
+                    }
+                }
+                if let Some(shares) = owner_shares {
+                    div { style: "display: flex; flex-direction: column; gap: 2px; padding: 6px 0;",
+                        span { style: "font-size: 12px; color: #333;", "所有權分配" }
+                        if shares_editable {
+                            textarea {
+                                aria_label: "所有權分配",
+                                style: "min-height: 60px;",
+                                placeholder: "每行一位：所有權人 百分比，例如 王小明 60%",
+                                value: "{format_owner_shares(&shares)}",
+                                onchange: move |event: FormEvent| on_shares_commit.call(event.value()),
+                            }
+                        } else if shares.is_empty() {
+                            span { style: "color: #888;", "未分配，全數歸所有權人欄位" }
+                        } else {
+                            for share in shares {
+                                span { "{share.owner} {format_f64(share.percent)}%" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use dioxus::prelude::*;
+
+use crate::ui::components::modal::ModalDialog;
+
+/// Asked before leaving a table with staged edits: overwrite the dataset, keep the old
+/// rows under another name, or stay.
+#[component]
+pub fn SavePrompt(
+    on_overwrite: EventHandler<()>,
+    on_save_as: EventHandler<()>,
+    on_cancel: EventHandler<()>,
+) -> Element {
+    rsx! {
+        ModalDialog {
+            label: "未儲存變更".to_string(),
+            z_index: 1100,
+            panel_style: "min-width: 280px;",
+            on_close: move |_| on_cancel.call(()),
+            div { style: "margin-bottom: 8px; font-weight: 600;", "未儲存變更" }
+            div { style: "margin-bottom: 12px;", "你要覆蓋目前資料集，或另存舊內容？" }
+            div { style: "display: flex; gap: 8px;",
+                button { onclick: move |_| on_overwrite.call(()), "覆蓋" }
+                button { onclick: move |_| on_save_as.call(()), "另存" }
+                button { onclick: move |_| on_cancel.call(()), "取消" }
+            }
+        }
+    }
+}
+
+/// Name for the copy of the unedited rows made by 另存.
+#[component]
+pub fn SaveAsPrompt(
+    mut name: Signal<String>,
+    on_confirm: EventHandler<String>,
+    on_cancel: EventHandler<()>,
+) -> Element {
+    rsx! {
+        ModalDialog {
+            label: "另存舊內容".to_string(),
+            z_index: 1200,
+            panel_style: "min-width: 280px;",
+            on_close: move |_| on_cancel.call(()),
+            div { style: "margin-bottom: 8px; font-weight: 600;", "另存舊內容" }
+            div { style: "margin-bottom: 8px;", "請輸入新資料集名稱（預設 MMDD）" }
+            input {
+                value: name(),
+                oninput: move |event| name.set(event.value()),
+            }
+            div { style: "display: flex; gap: 8px; margin-top: 12px;",
+                button { onclick: move |_| on_confirm.call(name()), "確認" }
+                button { onclick: move |_| on_cancel.call(()), "取消" }
+            }
+        }
+    }
+}
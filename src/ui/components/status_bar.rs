@@ -0,0 +1,30 @@
+use dioxus::prelude::*;
+
+/// Footer pinned to the bottom of the workspace: database, dataset, row counts and save state.
+#[component]
+pub fn StatusBar(
+    db_path: String,
+    dataset_name: Option<String>,
+    shown_rows: i64,
+    dataset_rows: Option<i64>,
+    pending_changes: usize,
+    last_saved_at: Option<String>,
+) -> Element {
+    let dataset_name = dataset_name.unwrap_or_else(|| "未選擇".to_string());
+    let last_saved_at = last_saved_at.unwrap_or_else(|| "尚未儲存".to_string());
+    rsx! {
+        div {
+            style: "position: sticky; bottom: 0; display: flex; gap: 16px; align-items: center; margin-top: 8px; padding: 4px 8px; background: #f3f3f3; border-top: 1px solid #ccc; font-size: 12px; color: #444; z-index: 5;",
+            span { title: "{db_path}", "資料庫：{db_path}" }
+            span { "資料集：{dataset_name}" }
+            if let Some(row_count) = dataset_rows {
+                span { "列數：{shown_rows} / {row_count}" }
+            }
+            span {
+                style: if pending_changes > 0 { "color: #b25;" } else { "" },
+                "未儲存變更：{pending_changes}"
+            }
+            span { "上次儲存：{last_saved_at}" }
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use dioxus::prelude::*;
+
+use crate::ui::components::modal::ModalDialog;
+use crate::ui::components::table_image::{export_table_image, table_image};
+use crate::{StoredSummaryReport, SummaryReport, SummarySections};
+use bom_core::report::{summary_report_table, summary_report_text};
+
+/// Totals, per-group breakdown and notes of a built-in or scripted summary report.
+/// `history` holds the dataset's saved summaries, newest first; earlier ones can be
+/// picked to compare. Scripted reports have none. Only the checked `sections` are shown,
+/// copied by 複製報表, drawn by 匯出為圖片 and exported by `on_export`; the app keeps them
+/// per dataset group.
+#[component]
+pub fn SummaryDialog(
+    report: SummaryReport,
+    history: Vec<StoredSummaryReport>,
+    sections: SummarySections,
+    on_sections_change: EventHandler<SummarySections>,
+    on_export: EventHandler<SummaryReport>,
+    on_close: EventHandler<()>,
+) -> Element {
+    let mut viewing = use_signal(|| None::<i64>);
+    let mut copy_status = use_signal(String::new);
+    let shown = viewing()
+        .and_then(|id| history.iter().find(|stored| stored.id == id))
+        .or(history.first());
+    let report = shown.map_or(report, |stored| stored.report.clone());
+
+    rsx! {
+        ModalDialog {
+            label: report.title.clone(),
+            z_index: 1200,
+            panel_style: "min-width: 360px; max-width: 720px; max-height: 80vh; overflow: auto;",
+            on_close: move |_| on_close.call(()),
+            div { style: "margin-bottom: 8px; font-weight: 600;", "{report.title}" }
+            if let Some(stored) = shown {
+                div { style: "display: flex; gap: 8px; align-items: center; margin-bottom: 8px; font-size: 12px; color: #555;",
+                    span { "計算時間" }
+                    select {
+                        onchange: move |event| viewing.set(event.value().parse().ok()),
+                        for (idx, entry) in history.iter().enumerate() {
+                            option {
+                                value: entry.id.to_string(),
+                                selected: entry.id == stored.id,
+                                if idx == 0 {
+                                    "{entry.created_at}（最新）"
+                                } else {
+                                    "{entry.created_at}"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            if report.totals.is_empty() {
+                div { "沒有可計算的摘要欄位" }
+            } else if sections.totals {
+                div { style: "display: grid; grid-template-columns: repeat(auto-fill, minmax(180px, 1fr)); gap: 6px 12px;",
+                    for entry in report.totals.clone() {
+                        div { "{entry.label}: {entry.value}" }
+                    }
+                }
+            }
+            if sections.owner_totals && !report.owner_totals.is_empty() {
+                div { style: "margin-top: 12px; font-weight: 600;",
+                    if report.group_heading.is_empty() {
+                        "依所有權人"
+                    } else {
+                        "{report.group_heading}"
+                    }
+                }
+                for owner in report.owner_totals.clone() {
+                    div { style: "margin-top: 6px; font-weight: 600;", "{owner.owner}" }
+                    div { style: "display: grid; grid-template-columns: repeat(auto-fill, minmax(180px, 1fr)); gap: 6px 12px;",
+                        for entry in owner.entries {
+                            div { "{entry.label}: {entry.value}" }
+                        }
+                    }
+                }
+            }
+            if sections.notes && !report.notes.is_empty() {
+                div { style: "margin-top: 12px; font-weight: 600;", "備註" }
+                for note in report.notes.clone() {
+                    div { "{note}" }
+                }
+            }
+            div { style: "display: flex; flex-wrap: wrap; gap: 12px; margin-top: 12px; font-size: 12px; color: #555;",
+                if !report.totals.is_empty() {
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: sections.totals,
+                            onchange: move |event: FormEvent| {
+                                on_sections_change.call(SummarySections {
+                                    totals: event.checked(),
+                                    ..sections
+                                })
+                            },
+                        }
+                        "合計"
+                    }
+                }
+                if !report.owner_totals.is_empty() {
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: sections.owner_totals,
+                            onchange: move |event: FormEvent| {
+                                on_sections_change.call(SummarySections {
+                                    owner_totals: event.checked(),
+                                    ..sections
+                                })
+                            },
+                        }
+                        if report.group_heading.is_empty() {
+                            "依所有權人"
+                        } else {
+                            "{report.group_heading}"
+                        }
+                    }
+                }
+                if !report.notes.is_empty() {
+                    label {
+                        input {
+                            r#type: "checkbox",
+                            checked: sections.notes,
+                            onchange: move |event: FormEvent| {
+                                on_sections_change.call(SummarySections {
+                                    notes: event.checked(),
+                                    ..sections
+                                })
+                            },
+                        }
+                        "備註"
+                    }
+                }
+            }
+            div { style: "display: flex; justify-content: flex-end; align-items: center; gap: 8px; margin-top: 8px;",
+                span { style: "color: #555;", "{copy_status}" }
+                button {
+                    onclick: {
+                        let report = report.clone();
+                        move |_| {
+                            let script = format!(
+                                "navigator.clipboard.writeText({:?}).then(() => dioxus.send(true)).catch(() => dioxus.send(false));",
+                                summary_report_text(&report, sections)
+                            );
+                            spawn(async move {
+                                let mut eval = document::eval(&script);
+                                let copied: bool = eval.recv().await.unwrap_or(false);
+                                copy_status.set(if copied { "已複製".to_string() } else { "複製失敗".to_string() });
+                            });
+                        }
+                    },
+                    "複製報表"
+                }
+                button {
+                    onclick: {
+                        let report = report.clone();
+                        move |_| {
+                            let (headers, rows) = summary_report_table(&report, sections);
+                            let right_aligned = [false, false, true];
+                            let image = table_image(&report.title, &headers, &rows, &right_aligned);
+                            let file_name = format!("{}.png", report.title);
+                            spawn(async move {
+                                match export_table_image(image, &file_name).await {
+                                    Ok(true) => copy_status.set("已匯出圖片".to_string()),
+                                    Ok(false) => {}
+                                    Err(err) => copy_status.set(format!("匯出圖片失敗：{err}")),
+                                }
+                            });
+                        }
+                    },
+                    "匯出為圖片…"
+                }
+                button {
+                    onclick: {
+                        let report = report.clone();
+                        move |_| on_export.call(report.clone())
+                    },
+                    "匯出 CSV…"
+                }
+                button {
+                    onclick: move |_| on_close.call(()),
+                    "關閉"
+                }
+            }
+        }
+    }
+}
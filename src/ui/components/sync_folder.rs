@@ -0,0 +1,39 @@
+use dioxus::prelude::*;
+
+use crate::ui::components::modal::ModalDialog;
+use crate::ui::state::sync_folder::SyncFolderStatus;
+
+/// Startup warning for a database kept in a cloud sync folder: another machine holds the
+/// writer lock, and/or the sync client left conflicted copies next to the database.
+#[component]
+pub fn SyncFolderNotice(status: SyncFolderStatus, on_close: EventHandler<()>) -> Element {
+    let provider = status.provider.label();
+
+    rsx! {
+        ModalDialog {
+            label: "同步資料夾".to_string(),
+            panel_style: "min-width: 360px; max-width: 560px;",
+            on_close: move |_| on_close.call(()),
+            div { style: "margin-bottom: 8px; font-weight: 600;", "資料庫位於 {provider}" }
+            if let Some(holder) = status.held_by.clone() {
+                div { style: "margin-bottom: 8px;",
+                    "電腦「{holder}」正在使用這個資料庫，已改用唯讀模式，以免兩邊同時寫入造成損毀。"
+                    "確定對方已關閉 BOM 後，可從 檔案 → 唯讀模式 解除。"
+                }
+            }
+            if !status.conflicted_copies.is_empty() {
+                div { style: "margin-bottom: 4px;",
+                    "同步程式留下了以下衝突副本，內容可能與目前資料庫不同，請確認後自行合併或刪除："
+                }
+                ul { style: "margin: 0 0 8px 0; padding-left: 20px; word-break: break-all;",
+                    for path in status.conflicted_copies.iter() {
+                        li { "{path.display()}" }
+                    }
+                }
+            }
+            div { style: "display: flex; justify-content: flex-end; margin-top: 12px;",
+                button { onclick: move |_| on_close.call(()), "知道了" }
+            }
+        }
+    }
+}
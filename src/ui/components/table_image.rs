@@ -0,0 +1,149 @@
+use anyhow::{anyhow, Result};
+use dioxus::prelude::*;
+
+use crate::ui::components::chart::save_png;
+
+const FONT_SIZE: f64 = 13.0;
+/// Width of a narrow character at [`FONT_SIZE`]; CJK and full-width characters take two.
+const CHAR_WIDTH: f64 = 7.5;
+const CELL_PADDING: f64 = 8.0;
+const ROW_HEIGHT: f64 = 24.0;
+const TITLE_HEIGHT: f64 = 32.0;
+const MARGIN: f64 = 12.0;
+
+/// A table drawn as standalone SVG, sized to fit its text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableImage {
+    pub svg: String,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Draws `headers` and `rows` as they are shown, under `title`. Columns flagged in
+/// `right_aligned` are aligned like numbers in the table.
+pub fn table_image(
+    title: &str,
+    headers: &[String],
+    rows: &[Vec<String>],
+    right_aligned: &[bool],
+) -> TableImage {
+    let column_widths: Vec<f64> = headers
+        .iter()
+        .enumerate()
+        .map(|(idx, header)| {
+            let widest = rows
+                .iter()
+                .filter_map(|row| row.get(idx))
+                .chain(std::iter::once(header))
+                .map(|text| text_width(text))
+                .fold(0.0, f64::max);
+            widest + CELL_PADDING * 2.0
+        })
+        .collect();
+    let table_width: f64 = column_widths.iter().sum();
+    let width = (table_width + MARGIN * 2.0).max(text_width(title) + MARGIN * 2.0);
+    let table_top = MARGIN + TITLE_HEIGHT;
+    let height = table_top + ROW_HEIGHT * (rows.len() + 1) as f64 + MARGIN;
+
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="sans-serif" font-size="{FONT_SIZE}">"#
+    );
+    svg.push_str(&format!(
+        r##"<rect width="{width}" height="{height}" fill="#fff"/><text x="{MARGIN}" y="{}" font-size="16" font-weight="600" fill="#222">{}</text>"##,
+        MARGIN + 18.0,
+        escape_xml(title)
+    ));
+    svg.push_str(&format!(
+        r##"<rect x="{MARGIN}" y="{table_top}" width="{table_width}" height="{ROW_HEIGHT}" fill="#f3f4f6"/>"##
+    ));
+    let lines = std::iter::once(headers).chain(rows.iter().map(Vec::as_slice));
+    for (row_idx, cells) in lines.enumerate() {
+        let top = table_top + ROW_HEIGHT * row_idx as f64;
+        let baseline = top + ROW_HEIGHT / 2.0 + FONT_SIZE * 0.35;
+        let weight = if row_idx == 0 {
+            r#" font-weight="600""#
+        } else {
+            ""
+        };
+        let mut left = MARGIN;
+        for (col_idx, column_width) in column_widths.iter().enumerate() {
+            let text = cells.get(col_idx).map(String::as_str).unwrap_or_default();
+            let (x, anchor) = if right_aligned.get(col_idx).copied().unwrap_or(false) {
+                (left + column_width - CELL_PADDING, "end")
+            } else {
+                (left + CELL_PADDING, "start")
+            };
+            if !text.is_empty() {
+                svg.push_str(&format!(
+                    r##"<text x="{x}" y="{baseline}" text-anchor="{anchor}" fill="#222"{weight}>{}</text>"##,
+                    escape_xml(text)
+                ));
+            }
+            left += column_width;
+        }
+        let bottom = top + ROW_HEIGHT;
+        svg.push_str(&format!(
+            r##"<line x1="{MARGIN}" y1="{bottom}" x2="{}" y2="{bottom}" stroke="#ddd"/>"##,
+            MARGIN + table_width
+        ));
+    }
+    svg.push_str("</svg>");
+    TableImage { svg, width, height }
+}
+
+/// Renders `image` to PNG in the webview and asks where to save it. `Ok(false)` when the
+/// save dialog was cancelled.
+pub async fn export_table_image(image: TableImage, file_name: &str) -> Result<bool> {
+    let mut eval = document::eval(&png_script(&image));
+    let data_url: String = eval.recv().await.map_err(|err| anyhow!("{err}"))?;
+    save_png(&data_url, file_name)
+}
+
+fn text_width(text: &str) -> f64 {
+    text.chars()
+        .map(|ch| if is_wide(ch) { 2.0 } else { 1.0 })
+        .sum::<f64>()
+        * CHAR_WIDTH
+}
+
+fn is_wide(ch: char) -> bool {
+    matches!(ch as u32,
+        0x1100..=0x115F
+        | 0x2E80..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x20000..=0x3FFFD)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn png_script(image: &TableImage) -> String {
+    format!(
+        r##"
+const image = new Image();
+image.onload = () => {{
+  const ratio = 2;
+  const canvas = document.createElement("canvas");
+  canvas.width = {width} * ratio;
+  canvas.height = {height} * ratio;
+  const ctx = canvas.getContext("2d");
+  ctx.scale(ratio, ratio);
+  ctx.drawImage(image, 0, 0);
+  dioxus.send(canvas.toDataURL("image/png"));
+}};
+image.onerror = () => dioxus.send("");
+image.src = "data:image/svg+xml;charset=utf-8," + encodeURIComponent({svg:?});
+"##,
+        width = image.width,
+        height = image.height,
+        svg = image.svg,
+    )
+}
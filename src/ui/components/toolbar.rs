@@ -0,0 +1,83 @@
+use dioxus::prelude::*;
+
+use crate::ui::state::app_state::{BusyKind, BusyState};
+
+/// Workspace actions along the top, followed by the status message and selection totals.
+/// `split_enabled` is `None` where no split view is available (secondary windows). Each
+/// button only waits for the kinds of `busy` work it would conflict with.
+#[component]
+pub fn Toolbar(
+    busy: BusyState,
+    read_only: bool,
+    has_dataset: bool,
+    status: String,
+    has_error: bool,
+    selection_summary: Option<String>,
+    split_enabled: Option<bool>,
+    on_manage: EventHandler<()>,
+    on_open_window: EventHandler<()>,
+    on_export: EventHandler<()>,
+    on_backup: EventHandler<()>,
+    on_toggle_split: EventHandler<()>,
+    on_summary: EventHandler<()>,
+    on_show_error: EventHandler<()>,
+) -> Element {
+    let writing = busy.is(BusyKind::Save) || busy.is(BusyKind::Import);
+    let querying = busy.is(BusyKind::Query);
+
+    rsx! {
+        div {
+            style: "display: flex; gap: 8px; align-items: center; margin-bottom: 12px; background: #fff; padding: 8px 0;",
+            button {
+                disabled: writing,
+                onclick: move |_| on_manage.call(()),
+                "資料集管理"
+            }
+            button {
+                disabled: !has_dataset,
+                onclick: move |_| on_open_window.call(()),
+                "新視窗開啟"
+            }
+            button {
+                disabled: querying || !has_dataset,
+                title: "Ctrl+E",
+                onclick: move |_| on_export.call(()),
+                "匯出 CSV"
+            }
+            button {
+                disabled: busy.is(BusyKind::Save),
+                onclick: move |_| on_backup.call(()),
+                "備份"
+            }
+            if let Some(enabled) = split_enabled {
+                button {
+                    onclick: move |_| on_toggle_split.call(()),
+                    if enabled { "關閉分割" } else { "分割檢視" }
+                }
+            }
+            button {
+                disabled: querying,
+                onclick: move |_| on_summary.call(()),
+                "總結報表"
+            }
+            if read_only {
+                span {
+                    style: "padding: 2px 8px; border-radius: 10px; background: #fff3bf; color: #7a5b00; font-size: 12px;",
+                    title: "檔案 → 唯讀模式 可切換",
+                    "唯讀"
+                }
+            }
+            span { " {status}" }
+            if has_error {
+                button {
+                    style: "margin-left: 6px;",
+                    onclick: move |_| on_show_error.call(()),
+                    "詳細資訊"
+                }
+            }
+            if let Some(summary) = selection_summary {
+                span { style: "margin-left: 12px; color: #335;", "{summary}" }
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use dioxus::prelude::*;
+
+/// Shown instead of the workspace while the database has no datasets.
+#[component]
+pub fn WelcomeScreen(
+    busy: bool,
+    status: String,
+    /// Required columns of 持股, as set in 設定.
+    holdings_columns: Vec<String>,
+    on_import: EventHandler<()>,
+    on_import_folder: EventHandler<()>,
+    on_create_blank: EventHandler<()>,
+    on_load_demo: EventHandler<()>,
+) -> Element {
+    let holdings_columns = holdings_columns.join("、");
+    let big_button = "font-size: 16px; padding: 12px 28px; border-radius: 8px; border: 1px solid #4a7bd0; cursor: pointer;";
+
+    rsx! {
+        div { style: "max-width: 720px; margin: 48px auto; padding: 24px; background: #fff; border: 1px solid #ddd; border-radius: 12px; line-height: 1.6;",
+            h2 { style: "margin-top: 0;", "歡迎使用 BOM" }
+            p { "目前還沒有任何資料集。匯入一份活頁簿或 CSV（或一次匯入整個資料夾），或先建立一個空白的持股資料集；想先看看各項功能，可以載入示範資料。" }
+            h3 { "活頁簿格式" }
+            ul {
+                li { "XLSX 需包含「資產總表」（前 3 列為標題）、「持股明細」（前 2 列為標題）與「股息收入明細表」（首列為標題）三個工作表。" }
+                li { "匯入後會產生「資產總表」與「持股股息總表」兩個資料集。" }
+                li { "CSV 以第一列為欄位名稱，其餘每列為一筆資料。" }
+                li { "持股資料需具備欄位：{holdings_columns}。" }
+            }
+            div { style: "display: flex; gap: 16px; margin-top: 24px;",
+                button {
+                    style: "{big_button} background: #4a7bd0; color: #fff;",
+                    disabled: busy,
+                    onclick: move |_| on_import.call(()),
+                    "匯入 CSV / XLSX"
+                }
+                button {
+                    style: "{big_button} background: #fff; color: #4a7bd0;",
+                    disabled: busy,
+                    onclick: move |_| on_import_folder.call(()),
+                    "匯入資料夾"
+                }
+                button {
+                    style: "{big_button} background: #fff; color: #4a7bd0;",
+                    disabled: busy,
+                    onclick: move |_| on_create_blank.call(()),
+                    "建立空白資料集"
+                }
+                button {
+                    style: "{big_button} background: #fff; color: #4a7bd0;",
+                    disabled: busy,
+                    onclick: move |_| on_load_demo.call(()),
+                    "載入示範資料"
+                }
+            }
+            if !status.is_empty() {
+                div { style: "margin-top: 16px; color: #555;", "{status}" }
+            }
+        }
+    }
+}
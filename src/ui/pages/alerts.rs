@@ -0,0 +1,130 @@
+use std::collections::BTreeSet;
+
+use dioxus::prelude::*;
+
+use crate::load_alert_loss_threshold;
+use crate::platform::desktop::blocking::run_blocking;
+use crate::ui::state::app_state::RouteState;
+use crate::ui::state::services::AppServices;
+use bom_core::alerts::{collect_alerts, Alert, AlertKind};
+
+/// Alerts across every stored dataset, grouped by kind. Acknowledged alerts are hidden
+/// until asked for, and come back on their own once the values behind them change.
+#[component]
+pub fn AlertsPage() -> Element {
+    let services = use_context::<AppServices>();
+    let mut route = use_context::<RouteState>();
+    let mut alerts = use_signal(Vec::<Alert>::new);
+    let mut acknowledged = use_signal(BTreeSet::<String>::new);
+    let mut show_acknowledged = use_signal(|| false);
+    let mut message = use_signal(String::new);
+
+    let query_service = services.query_service.clone();
+    let reload = move || {
+        let load = || {
+            let threshold = load_alert_loss_threshold(&query_service)?;
+            let today = chrono::Local::now().date_naive();
+            let found = collect_alerts(&query_service, today, threshold)?;
+            let keys = query_service.load_acknowledged_alerts()?;
+            Ok::<_, anyhow::Error>((found, keys))
+        };
+        match run_blocking(load) {
+            Ok((found, keys)) => {
+                alerts.set(found);
+                acknowledged.set(keys);
+            }
+            Err(err) => message.set(format!("載入警示失敗：{err:#}")),
+        }
+    };
+    use_hook({
+        let mut reload = reload.clone();
+        move || reload()
+    });
+
+    let acknowledge = {
+        let query_service = services.query_service.clone();
+        move |keys: Vec<String>| {
+            let count = keys.len();
+            match run_blocking(|| query_service.acknowledge_alerts(keys.clone())) {
+                Ok(()) => {
+                    acknowledged.write().extend(keys);
+                    message.set(format!("已確認 {count} 則警示"));
+                }
+                Err(err) => message.set(format!("確認警示失敗：{err}")),
+            }
+        }
+    };
+    let acknowledged_keys = acknowledged();
+    let pending: Vec<String> = alerts()
+        .iter()
+        .filter(|alert| !acknowledged_keys.contains(&alert.key))
+        .map(|alert| alert.key.clone())
+        .collect();
+    let shown: Vec<Alert> = alerts()
+        .into_iter()
+        .filter(|alert| show_acknowledged() || !acknowledged_keys.contains(&alert.key))
+        .collect();
+
+    rsx! {
+        div { style: "padding: 16px; overflow: auto; height: 100%; box-sizing: border-box;",
+            div { style: "font-weight: 600; margin-bottom: 8px;", "警示" }
+            div { style: "display: flex; gap: 8px; align-items: center; margin-bottom: 12px;",
+                span { style: "color: #555;", "{pending.len()} 則未確認" }
+                button {
+                    disabled: pending.is_empty(),
+                    onclick: {
+                        let mut acknowledge = acknowledge.clone();
+                        let pending = pending.clone();
+                        move |_| acknowledge(pending.clone())
+                    },
+                    "全部確認"
+                }
+                label { style: "display: flex; gap: 4px; align-items: center;",
+                    input {
+                        r#type: "checkbox",
+                        checked: show_acknowledged(),
+                        onchange: move |event| show_acknowledged.set(event.checked()),
+                    }
+                    "顯示已確認"
+                }
+                button {
+                    onclick: {
+                        let mut reload = reload.clone();
+                        move |_| reload()
+                    },
+                    "重新檢查"
+                }
+                span { style: "color: #555;", "{message}" }
+            }
+            if shown.is_empty() {
+                div { style: "color: #555;", "目前沒有警示。" }
+            }
+            for kind in AlertKind::ALL {
+                if shown.iter().any(|alert| alert.kind == kind) {
+                    div { style: "font-weight: 600; margin: 12px 0 6px;", "{kind.label()}" }
+                    for alert in shown.iter().filter(|alert| alert.kind == kind).cloned() {
+                        div {
+                            key: "{alert.key}",
+                            style: if acknowledged_keys.contains(&alert.key) { "display: flex; gap: 8px; align-items: center; padding: 4px 0; color: #888;" } else { "display: flex; gap: 8px; align-items: center; padding: 4px 0;" },
+                            span { style: "flex: 1 1 auto;", "{alert.message}" }
+                            button {
+                                onclick: move |_| route.open_dataset(alert.dataset_id.0),
+                                "{alert.dataset_name}"
+                            }
+                            if !acknowledged_keys.contains(&alert.key) {
+                                button {
+                                    onclick: {
+                                        let mut acknowledge = acknowledge.clone();
+                                        let key = alert.key.clone();
+                                        move |_| acknowledge(vec![key.clone()])
+                                    },
+                                    "確認"
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use std::path::PathBuf;
+
+use dioxus::prelude::*;
+
+use crate::platform::desktop::blocking::run_blocking;
+use crate::ui::state::app_state::{MenuCommandState, Route, RouteState};
+use crate::ui::state::services::AppServices;
+use crate::{build_dataset_groups, parse_recent_imports, RECENT_IMPORTS_KEY};
+
+/// Dataset groups with their row counts and the recent imports; picking either opens it
+/// in the dataset view.
+#[component]
+pub fn DashboardPage() -> Element {
+    let services = use_context::<AppServices>();
+    let mut route = use_context::<RouteState>();
+    let mut menu_commands = use_context::<MenuCommandState>();
+    let loaded = use_hook(|| {
+        let load = || {
+            let datasets = services.query_service.list_datasets(false)?;
+            let recent = services.query_service.load_setting(RECENT_IMPORTS_KEY)?;
+            Ok::<_, anyhow::Error>((datasets, parse_recent_imports(&recent.unwrap_or_default())))
+        };
+        run_blocking(load).map_err(|err| format!("{err:#}"))
+    });
+    let (datasets, recent_imports) = match loaded {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            return rsx! {
+                div { style: "padding: 16px;", "載入總覽失敗：{err}" }
+            };
+        }
+    };
+    let groups = build_dataset_groups(&datasets);
+    let total_rows: i64 = datasets.iter().map(|dataset| dataset.row_count).sum();
+
+    rsx! {
+        div { style: "padding: 16px; overflow: auto; height: 100%; box-sizing: border-box;",
+            div { style: "font-weight: 600; margin-bottom: 8px;", "總覽" }
+            div { style: "color: #555; margin-bottom: 12px;",
+                "共 {datasets.len()} 個資料集、{total_rows} 列"
+            }
+            div { style: "display: grid; grid-template-columns: repeat(auto-fill, minmax(240px, 1fr)); gap: 12px;",
+                for group in groups {
+                    div { style: "border: 1px solid #ddd; border-radius: 4px; padding: 8px;",
+                        div { style: "font-weight: 600; margin-bottom: 6px;", "{group.label}" }
+                        for dataset in group.datasets {
+                            button {
+                                style: "display: flex; justify-content: space-between; width: 100%; margin-top: 2px;",
+                                onclick: move |_| route.open_dataset(dataset.id.0),
+                                span { "{dataset.name}" }
+                                span { style: "color: #555;", "{dataset.row_count} 列" }
+                            }
+                        }
+                    }
+                }
+            }
+            if !recent_imports.is_empty() {
+                div { style: "font-weight: 600; margin: 16px 0 6px;", "最近匯入" }
+                for path in recent_imports {
+                    div {
+                        button {
+                            onclick: {
+                                let path = path.clone();
+                                move |_| {
+                                    menu_commands.open_file.set(Some(PathBuf::from(&path)));
+                                    route.navigate(Route::Dataset);
+                                }
+                            },
+                            "{path}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
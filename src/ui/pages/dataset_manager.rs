@@ -0,0 +1,251 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use dioxus::prelude::*;
+
+use crate::domain::entities::dataset::{DatasetId, DatasetKind};
+use crate::infra::export::csv::CsvOptions;
+use crate::platform::desktop::blocking::run_blocking;
+use crate::platform::desktop::menu::MenuCommand;
+use crate::ui::components::dataset_manager::DatasetManager;
+use crate::ui::components::import_review::DuplicateCodesDialog;
+use crate::ui::state::app_state::{MenuCommandState, ReadOnlyState, Route, RouteState};
+use crate::ui::state::services::AppServices;
+use crate::usecase::ports::importer::ImportValidation;
+use crate::{
+    build_page_query, dataset_diff_summary, export_dataset_diff, load_trash_retention_days,
+    QueryOptions, DEFAULT_TRASH_RETENTION_DAYS,
+};
+use bom_core::holdings::DuplicateCodePolicy;
+
+/// 資料集管理 as a page. Renames, moves to and from the trash and deletions go straight to
+/// the services; the dataset view reloads its list when it is shown again. Importing hands over to the dataset view.
+/// Re-transforming asks about duplicate 代號 in the stored sheets with the import dialog.
+#[component]
+pub fn DatasetManagerPage() -> Element {
+    let services = use_context::<AppServices>();
+    let mut route = use_context::<RouteState>();
+    let mut menu_commands = use_context::<MenuCommandState>();
+    let read_only = use_context::<ReadOnlyState>();
+    let mut datasets = use_signal(Vec::new);
+    let mut history = use_signal(Vec::new);
+    let mut trash = use_signal(Vec::new);
+    let retention_days = use_hook(|| {
+        run_blocking(|| load_trash_retention_days(&services.query_service))
+            .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS)
+    });
+    let mut busy = use_signal(|| false);
+    let mut message = use_signal(String::new);
+
+    let query_service = services.query_service.clone();
+    let mut reload = move || {
+        match run_blocking(|| query_service.list_datasets(true)) {
+            Ok(available) => {
+                let (deleted, available): (Vec<_>, Vec<_>) = available
+                    .into_iter()
+                    .partition(|dataset| dataset.deleted_at.is_some());
+                trash.set(deleted);
+                datasets.set(available);
+            }
+            Err(err) => message.set(format!("載入資料集失敗：{err}")),
+        }
+        match run_blocking(|| query_service.load_import_history()) {
+            Ok(records) => history.set(records),
+            Err(err) => message.set(format!("載入匯入紀錄失敗：{err}")),
+        }
+    };
+    use_hook({
+        let mut reload = reload.clone();
+        move || reload()
+    });
+
+    let mut review = use_signal(|| None::<(i64, ImportValidation)>);
+    let import_service = services.import_service.clone();
+    let mut retransform = {
+        let mut reload = reload.clone();
+        move |dataset_id: i64, duplicate_codes: DuplicateCodePolicy| {
+            busy.set(true);
+            match run_blocking(|| {
+                import_service.retransform(DatasetId(dataset_id), duplicate_codes)
+            }) {
+                Ok(result) => {
+                    reload();
+                    message.set(format!("已重新轉換（{} 筆）", result.row_count));
+                }
+                Err(err) => message.set(format!("重新轉換失敗：{err}")),
+            }
+            busy.set(false);
+        }
+    };
+
+    rsx! {
+        DatasetManager {
+            datasets: datasets(),
+            initial_dataset_id: None,
+            busy: busy(),
+            read_only: (read_only.enabled)(),
+            page: true,
+            history: history(),
+            trash: trash(),
+            retention_days,
+            on_import: move |_| {
+                route.navigate(Route::Dataset);
+                menu_commands.pending.set(Some(MenuCommand::Import));
+            },
+            on_rename: {
+                let query_service = services.query_service.clone();
+                let mut reload = reload.clone();
+                move |(dataset_id, name): (i64, String)| {
+                    let name = name.trim().to_string();
+                    if name.is_empty() {
+                        message.set("資料集名稱不可空白".to_string());
+                        return;
+                    }
+                    busy.set(true);
+                    match run_blocking(|| query_service.rename_dataset(DatasetId(dataset_id), name.clone())) {
+                        Ok(()) => {
+                            reload();
+                            message.set("已重新命名".to_string());
+                        }
+                        Err(err) => message.set(format!("重新命名失敗：{err}")),
+                    }
+                    busy.set(false);
+                }
+            },
+            on_set_kind: {
+                let query_service = services.query_service.clone();
+                let mut reload = reload.clone();
+                move |(dataset_id, kind): (i64, DatasetKind)| {
+                    busy.set(true);
+                    match run_blocking(|| query_service.set_dataset_kind(DatasetId(dataset_id), kind)) {
+                        Ok(()) => {
+                            reload();
+                            message.set(format!("已設為{}", kind.label()));
+                        }
+                        Err(err) => message.set(format!("設定類型失敗：{err}")),
+                    }
+                    busy.set(false);
+                }
+            },
+            on_export_diff: {
+                let query_service = services.query_service.clone();
+                move |(before, after, path): (i64, i64, PathBuf)| {
+                    busy.set(true);
+                    match run_blocking(|| export_dataset_diff(&query_service, before, after, &path)) {
+                        Ok(diff) => message.set(dataset_diff_summary(&diff)),
+                        Err(err) => message.set(format!("匯出差異失敗：{err}")),
+                    }
+                    busy.set(false);
+                }
+            },
+            on_export_csv: {
+                let export_service = services.export_service.clone();
+                move |(dataset_id, path, options): (i64, PathBuf, CsvOptions)| {
+                    busy.set(true);
+                    let query = build_page_query(dataset_id, 0, &QueryOptions::default());
+                    match run_blocking(|| {
+                        export_service.export_csv(query, &BTreeMap::new(), &options, &path)
+                    }) {
+                        Ok(count) => message.set(format!("已匯出 {count} 筆至 {}", path.display())),
+                        Err(err) => message.set(format!("匯出 CSV 失敗：{err:#}")),
+                    }
+                    busy.set(false);
+                }
+            },
+            on_retransform: {
+                let import_service = services.import_service.clone();
+                let mut retransform = retransform.clone();
+                move |dataset_id: i64| {
+                    match run_blocking(|| import_service.validate_sources(DatasetId(dataset_id))) {
+                        Ok(validation) if !validation.is_clean() => {
+                            review.set(Some((dataset_id, validation)));
+                        }
+                        Ok(_) => retransform(dataset_id, DuplicateCodePolicy::default()),
+                        Err(err) => message.set(format!("重新轉換失敗：{err}")),
+                    }
+                }
+            },
+            on_trash: {
+                let edit_service = services.edit_service.clone();
+                let mut reload = reload.clone();
+                move |dataset_id: i64| {
+                    busy.set(true);
+                    match run_blocking(|| edit_service.soft_delete_dataset(DatasetId(dataset_id))) {
+                        Ok(()) => {
+                            reload();
+                            message.set("已移到垃圾桶".to_string());
+                        }
+                        Err(err) => message.set(format!("移到垃圾桶失敗：{err}")),
+                    }
+                    busy.set(false);
+                }
+            },
+            on_restore: {
+                let edit_service = services.edit_service.clone();
+                let mut reload = reload.clone();
+                move |dataset_id: i64| {
+                    busy.set(true);
+                    match run_blocking(|| edit_service.restore_dataset(DatasetId(dataset_id))) {
+                        Ok(()) => {
+                            reload();
+                            message.set("已還原資料集".to_string());
+                        }
+                        Err(err) => message.set(format!("還原資料集失敗：{err}")),
+                    }
+                    busy.set(false);
+                }
+            },
+            on_keep_forever: {
+                let edit_service = services.edit_service.clone();
+                let mut reload = reload.clone();
+                move |(dataset_id, keep_forever): (i64, bool)| {
+                    if let Err(err) = run_blocking(|| edit_service.set_keep_forever(DatasetId(dataset_id), keep_forever)) {
+                        message.set(format!("設定永久保留失敗：{err}"));
+                    }
+                    reload();
+                }
+            },
+            on_delete: {
+                let edit_service = services.edit_service.clone();
+                move |dataset_id: i64| {
+                    busy.set(true);
+                    match run_blocking(|| edit_service.hard_delete_dataset(DatasetId(dataset_id))) {
+                        Ok(()) => {
+                            reload();
+                            message.set("已永久刪除資料集".to_string());
+                        }
+                        Err(err) => message.set(format!("刪除資料集失敗：{err}")),
+                    }
+                    busy.set(false);
+                }
+            },
+            on_close: move |_| route.navigate(Route::Dataset),
+        }
+        if let Some((dataset_id, validation)) = review() {
+            DuplicateCodesDialog {
+                file_name: datasets
+                    .read()
+                    .iter()
+                    .find(|dataset| dataset.id.0 == dataset_id)
+                    .map(|dataset| dataset.name.clone())
+                    .unwrap_or_default(),
+                duplicates: validation.duplicate_codes,
+                on_merge: {
+                    let mut retransform = retransform.clone();
+                    move |_| {
+                        review.set(None);
+                        retransform(dataset_id, DuplicateCodePolicy::Merge);
+                    }
+                },
+                on_keep_both: move |_| {
+                    review.set(None);
+                    retransform(dataset_id, DuplicateCodePolicy::KeepBoth);
+                },
+                on_cancel: move |_| review.set(None),
+            }
+        }
+        if !message().is_empty() {
+            div { style: "padding: 0 16px; color: #555;", "{message}" }
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use dioxus::prelude::*;
+
+use crate::platform::desktop::blocking::run_blocking;
+use crate::ui::components::chart::sparkline_points;
+use crate::ui::state::app_state::RouteState;
+use crate::ui::state::services::AppServices;
+use bom_core::holding_detail::{
+    collect_holding_detail, holding_codes, save_holding_note, HoldingDetail,
+};
+use bom_core::numeric::{format_cell_value, format_number_with_commas};
+use bom_core::trade_costs::{Oversold, PositionCost};
+
+const PRICE_CHART_WIDTH: f64 = 480.0;
+const PRICE_CHART_HEIGHT: f64 = 120.0;
+
+/// One 代號 across the stored datasets: its positions, dividend history, stored prices,
+/// notes and the rows of other tables listing it. Opened from a 代號 cell of the dataset
+/// view or picked here.
+#[component]
+pub fn HoldingPage() -> Element {
+    let services = use_context::<AppServices>();
+    let mut route = use_context::<RouteState>();
+    let mut codes =
+        use_signal(|| run_blocking(|| holding_codes(&services.query_service)).unwrap_or_default());
+    let mut detail = use_signal(|| None::<HoldingDetail>);
+    let mut note = use_signal(String::new);
+    let mut message = use_signal(String::new);
+
+    let query_service = services.query_service.clone();
+    use_effect(move || {
+        let Some(code) = (route.holding_code)() else {
+            detail.set(None);
+            return;
+        };
+        match run_blocking(|| collect_holding_detail(&query_service, &code)) {
+            Ok(found) => {
+                note.set(found.note.clone());
+                detail.set(Some(found));
+                message.set(String::new());
+            }
+            Err(err) => message.set(format!("載入個股資料失敗：{err:#}")),
+        }
+    });
+
+    let selected = (route.holding_code)().unwrap_or_default();
+
+    rsx! {
+        div { style: "padding: 16px; overflow: auto; height: 100%; box-sizing: border-box;",
+            div { style: "font-weight: 600; margin-bottom: 8px;", "個股" }
+            div { style: "display: flex; gap: 8px; align-items: center; margin-bottom: 12px;",
+                select {
+                    onchange: move |event: FormEvent| {
+                        let code = event.value();
+                        if !code.is_empty() {
+                            route.open_holding(&code);
+                        }
+                    },
+                    option { value: "", selected: selected.is_empty(), "選擇代號…" }
+                    for (code, name) in codes() {
+                        option {
+                            value: "{code}",
+                            selected: code == selected,
+                            "{code} {name}"
+                        }
+                    }
+                }
+                button {
+                    onclick: {
+                        let query_service = services.query_service.clone();
+                        move |_| match run_blocking(|| holding_codes(&query_service)) {
+                            Ok(found) => codes.set(found),
+                            Err(err) => message.set(format!("載入代號失敗：{err:#}")),
+                        }
+                    },
+                    "重新整理"
+                }
+                span { style: "color: #555;", "{message}" }
+            }
+            match detail() {
+                None => rsx! {
+                    div { style: "color: #555;", "從資料集的代號欄位點選，或在上方選擇代號。" }
+                },
+                Some(detail) => rsx! {
+                    div { style: "font-size: 18px; font-weight: 600; margin-bottom: 12px;",
+                        "{detail.code} {detail.name}"
+                    }
+                    div { style: "font-weight: 600; margin: 12px 0 6px;", "持倉摘要" }
+                    if detail.positions.is_empty() {
+                        div { style: "color: #555;", "持股表中沒有這個代號。" }
+                    }
+                    for position in detail.positions.clone() {
+                        div {
+                            key: "{position.dataset_id.0}-{position.row_idx}",
+                            style: "display: flex; flex-wrap: wrap; gap: 4px 16px; align-items: center; padding: 4px 0; border-bottom: 1px solid #eee;",
+                            button {
+                                onclick: move |_| route.open_dataset(position.dataset_id.0),
+                                "{position.dataset_name}"
+                            }
+                            for (header, value) in position.fields.clone() {
+                                span {
+                                    span { style: "color: #666;", "{header} " }
+                                    "{format_cell_value(&header, &value)}"
+                                }
+                            }
+                        }
+                    }
+                    div { style: "font-weight: 600; margin: 16px 0 6px;", "股息紀錄" }
+                    if detail.dividends.is_empty() {
+                        div { style: "color: #555;", "尚無股息紀錄。" }
+                    } else {
+                        table { style: "border-collapse: collapse;",
+                            for period in detail.dividends.clone() {
+                                tr {
+                                    td { style: "padding: 2px 12px 2px 0; color: #666;", "{period.label}" }
+                                    td { style: "padding: 2px 0; text-align: right;",
+                                        "{format_number_with_commas(period.amount, 0)}"
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    div { style: "font-weight: 600; margin: 16px 0 6px;", "價格走勢" }
+                    PriceHistoryChart { detail: detail.clone() }
+                    div { style: "font-weight: 600; margin: 16px 0 6px;", "備註" }
+                    for remark in detail.remarks.clone() {
+                        div { style: "color: #444; margin-bottom: 4px;", "{remark}" }
+                    }
+                    textarea {
+                        style: "width: 100%; max-width: 640px; min-height: 80px; box-sizing: border-box;",
+                        placeholder: "這個代號的筆記",
+                        value: note(),
+                        oninput: move |event| note.set(event.value()),
+                    }
+                    div { style: "margin-top: 6px;",
+                        button {
+                            onclick: {
+                                let query_service = services.query_service.clone();
+                                let code = detail.code.clone();
+                                move |_| match run_blocking(|| save_holding_note(&query_service, &code, &note())) {
+                                    Ok(()) => message.set("已保存備註".to_string()),
+                                    Err(err) => message.set(format!("保存備註失敗：{err}")),
+                                }
+                            },
+                            "保存"
+                        }
+                    }
+                    div { style: "font-weight: 600; margin: 16px 0 6px;", "相關紀錄" }
+                    if detail.related.is_empty() {
+                        div { style: "color: #555;", "其他資料集沒有這個代號。" }
+                    }
+                    for related in detail.related.clone() {
+                        div { key: "{related.dataset_id.0}", style: "margin-bottom: 12px;",
+                            button {
+                                style: "margin-bottom: 4px;",
+                                onclick: move |_| route.open_dataset(related.dataset_id.0),
+                                "{related.dataset_name}"
+                            }
+                            table { style: "border-collapse: collapse;",
+                                tr {
+                                    for header in related.columns.clone() {
+                                        th { style: "padding: 2px 8px; border-bottom: 1px solid #ddd; text-align: left;", "{header}" }
+                                    }
+                                }
+                                for (row_idx, row) in related.rows.clone() {
+                                    tr { key: "{row_idx}",
+                                        for (header, value) in related.columns.iter().zip(row.iter()) {
+                                            td { style: "padding: 2px 8px;", "{format_cell_value(header, value)}" }
+                                        }
+                                    }
+                                }
+                            }
+                            if let Some(trade_cost) = related.trade_cost {
+                                TradeCostSummary { trade_cost }
+                            }
+                        }
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// Stored prices of the holding as a line, with the lowest and highest price and the
+/// dates of the first and last fetch.
+#[component]
+fn PriceHistoryChart(detail: HoldingDetail) -> Element {
+    let Some((first, last)) = detail.prices.first().zip(detail.prices.last()) else {
+        return rsx! {
+            div { style: "color: #555;", "尚無價格紀錄；在持股表修改市價並保存後會記下當時的價格。" }
+        };
+    };
+    let values: Vec<f64> = detail.prices.iter().map(|point| point.price).collect();
+    let low = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let high = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let points = sparkline_points(&values, PRICE_CHART_WIDTH, PRICE_CHART_HEIGHT);
+    rsx! {
+        svg {
+            width: "{PRICE_CHART_WIDTH}",
+            height: "{PRICE_CHART_HEIGHT}",
+            view_box: "0 0 {PRICE_CHART_WIDTH} {PRICE_CHART_HEIGHT}",
+            style: "overflow: visible; border-bottom: 1px solid #ddd;",
+            polyline { points: "{points}", fill: "none", stroke: "#4a7bd0", stroke_width: "2" }
+        }
+        div { style: "display: flex; gap: 16px; color: #555; font-size: 12px; margin-top: 4px;",
+            span { "{first.fetched_at} ～ {last.fetched_at}" }
+            span { "最低 {format_number_with_commas(low, 2)}" }
+            span { "最高 {format_number_with_commas(high, 2)}" }
+            span { "最新 {format_number_with_commas(last.price, 2)}" }
+        }
+    }
+}
+
+/// The position a trade ledger adds up to, or why it could not be worked out.
+#[component]
+fn TradeCostSummary(trade_cost: Result<PositionCost, Oversold>) -> Element {
+    let position = match trade_cost {
+        Ok(position) => position,
+        Err(err) => {
+            return rsx! {
+                div { style: "margin-top: 4px; color: #b00020;", "無法計算持有成本：{err}" }
+            };
+        }
+    };
+    rsx! {
+        div { style: "display: flex; flex-wrap: wrap; gap: 4px 16px; color: #444; margin-top: 4px;",
+            span { "持有 {format_number_with_commas(position.quantity, 0)} 股" }
+            if let Some(average) = position.average_cost() {
+                span { "平均成本 {format_number_with_commas(average, 2)}" }
+            }
+            span { "已實現損益 {format_number_with_commas(position.realized_gain, 0)}" }
+            span { "手續費 {format_number_with_commas(position.fees, 0)}" }
+            span { "交易稅 {format_number_with_commas(position.taxes, 0)}" }
+        }
+    }
+}
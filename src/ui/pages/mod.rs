@@ -1,2 +1,8 @@
-#[allow(dead_code)]
-pub fn pages_placeholder() {}
+pub mod alerts;
+pub mod dashboard;
+pub mod dataset_manager;
+pub mod holding;
+pub mod nav;
+pub mod reports;
+pub mod retirement;
+pub mod settings;
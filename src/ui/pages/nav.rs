@@ -0,0 +1,25 @@
+use dioxus::prelude::*;
+
+use crate::ui::state::app_state::{Route, RouteState};
+
+/// Page tabs along the top of the main window.
+#[component]
+pub fn NavBar() -> Element {
+    let mut route = use_context::<RouteState>();
+    let current = (route.current)();
+
+    rsx! {
+        nav {
+            style: "display: flex; gap: 4px; padding: 4px 8px; border-bottom: 1px solid #ddd;",
+            aria_label: "頁面",
+            for page in Route::ALL {
+                button {
+                    style: if page == current { "font-weight: 600; border-bottom: 2px solid #2563eb;" } else { "" },
+                    aria_current: if page == current { "page" } else { "false" },
+                    onclick: move |_| route.navigate(page),
+                    "{page.label()}"
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use dioxus::prelude::*;
+use rfd::FileDialog;
+
+use crate::infra::export::csv::export_csv;
+use crate::infra::export::xlsx::export_xlsx;
+use crate::platform::desktop::blocking::run_blocking;
+use crate::platform::desktop::menu::MenuCommand;
+use crate::ui::state::app_state::{MenuCommandState, Route, RouteState};
+use crate::ui::state::services::AppServices;
+use bom_core::income_goal::{
+    estimated_monthly_income, income_projection_table, load_income_goal, project_income,
+    save_income_goal, IncomeGoal, PROJECTION_YEARS,
+};
+use bom_core::numeric::{format_f64, parse_numeric_value};
+use bom_core::year_comparison::{
+    compare_summaries, load_summary, summary_sources, year_comparison_table, SourceOption,
+    SummarySource, YearComparison,
+};
+
+/// Entry point to every report. Reports run against the dataset selected in the dataset
+/// view, so each action switches back to it and hands the command over; the year
+/// comparison picks its own two summaries and runs here, and the passive income goal is
+/// followed here across all 持股 tables.
+#[component]
+pub fn ReportsPage() -> Element {
+    let services = use_context::<AppServices>();
+    let mut route = use_context::<RouteState>();
+    let mut menu_commands = use_context::<MenuCommandState>();
+    let saved_reports = use_hook(|| run_blocking(|| services.query_service.load_saved_reports()));
+    let mut run = move |command: MenuCommand| {
+        route.navigate(Route::Dataset);
+        menu_commands.pending.set(Some(command));
+    };
+    let sources =
+        use_hook(|| run_blocking(|| summary_sources(&services.query_service)).unwrap_or_default());
+    let mut before_key = use_signal(String::new);
+    let mut after_key = use_signal(String::new);
+    let mut comparison = use_signal(|| None::<YearComparison>);
+    let mut comparison_message = use_signal(String::new);
+    let compare = {
+        let query_service = services.query_service.clone();
+        let sources = sources.clone();
+        move || {
+            let side = |key: &str| {
+                let source = SummarySource::parse(key)?;
+                sources
+                    .iter()
+                    .find(|option| option.source == source)
+                    .cloned()
+            };
+            let (Some(before), Some(after)) = (side(&before_key()), side(&after_key())) else {
+                comparison_message.set("請選擇要比較的兩份總結".to_string());
+                return;
+            };
+            let load = || {
+                let before_report = load_summary(&query_service, before.source)?;
+                let after_report = load_summary(&query_service, after.source)?;
+                Ok::<_, anyhow::Error>(compare_summaries(
+                    &before.label,
+                    &before_report,
+                    &after.label,
+                    &after_report,
+                ))
+            };
+            match run_blocking(load) {
+                Ok(found) => {
+                    comparison.set(Some(found));
+                    comparison_message.set(String::new());
+                }
+                Err(err) => comparison_message.set(format!("比較失敗：{err:#}")),
+            }
+        }
+    };
+    let comparison_table = comparison().map(|current| year_comparison_table(&current));
+    let mut goal = use_signal(|| {
+        run_blocking(|| load_income_goal(&services.query_service)).unwrap_or_default()
+    });
+    let monthly_income = use_hook(|| {
+        run_blocking(|| estimated_monthly_income(&services.query_service)).unwrap_or_default()
+    });
+    let mut target_text = use_signal(|| {
+        let target = goal().monthly_target;
+        if target > 0.0 {
+            format_f64(target)
+        } else {
+            String::new()
+        }
+    });
+    let mut inflation_text =
+        use_signal(|| format!("{}%", format_f64(goal().inflation_rate * 100.0)));
+    let mut goal_message = use_signal(String::new);
+    let (projection_headers, projection_rows) = income_projection_table(
+        &project_income(monthly_income, &goal(), &PROJECTION_YEARS),
+        &goal(),
+    );
+
+    rsx! {
+        div { style: "padding: 16px; overflow: auto; height: 100%; box-sizing: border-box;",
+            div { style: "font-weight: 600; margin-bottom: 8px;", "總結報表" }
+            div { style: "display: flex; flex-wrap: wrap; gap: 8px;",
+                button { onclick: move |_| run(MenuCommand::SummaryReport), "總結報表" }
+                button { onclick: move |_| run(MenuCommand::ReportBuilder), "報表產生器…" }
+                button { onclick: move |_| run(MenuCommand::RunReportScript), "執行自訂報表…" }
+            }
+            div { style: "font-weight: 600; margin: 16px 0 8px;", "已存報表" }
+            match saved_reports {
+                Ok(reports) if reports.is_empty() => rsx! {
+                    div { style: "color: #555;", "尚未儲存報表，可在報表產生器建立。" }
+                },
+                Ok(reports) => rsx! {
+                    for (idx, report) in reports.into_iter().enumerate() {
+                        div { style: "display: flex; gap: 8px; align-items: center; margin-top: 4px;",
+                            span { style: "min-width: 160px;", "{report.name}" }
+                            button { onclick: move |_| run(MenuCommand::SavedReport(idx)), "執行" }
+                        }
+                    }
+                },
+                Err(err) => rsx! {
+                    div { "讀取已存報表失敗：{err}" }
+                },
+            }
+            div { style: "font-weight: 600; margin: 16px 0 8px;", "股息" }
+            div { style: "display: flex; flex-wrap: wrap; gap: 8px;",
+                button { onclick: move |_| run(MenuCommand::DividendOwners), "所有權人…" }
+                button { onclick: move |_| run(MenuCommand::DividendYearRollover), "年度結轉…" }
+            }
+            div { style: "font-weight: 600; margin: 16px 0 8px;", "年度比較" }
+            div { style: "display: flex; flex-wrap: wrap; gap: 8px; align-items: center;",
+                SourcePicker {
+                    placeholder: "較早…",
+                    sources: sources.clone(),
+                    selected: before_key(),
+                    on_select: move |key| before_key.set(key),
+                }
+                span { "→" }
+                SourcePicker {
+                    placeholder: "較晚…",
+                    sources: sources.clone(),
+                    selected: after_key(),
+                    on_select: move |key| after_key.set(key),
+                }
+                button {
+                    onclick: {
+                        let mut compare = compare.clone();
+                        move |_| compare()
+                    },
+                    "比較"
+                }
+                button {
+                    disabled: comparison().is_none(),
+                    onclick: move |_| {
+                        let Some(current) = comparison() else {
+                            return;
+                        };
+                        let Some(path) = FileDialog::new()
+                            .add_filter("CSV", &["csv"])
+                            .add_filter("Excel 活頁簿", &["xlsx"])
+                            .set_file_name("年度比較.csv")
+                            .save_file()
+                        else {
+                            return;
+                        };
+                        let (headers, rows) = year_comparison_table(&current);
+                        let is_xlsx = path
+                            .extension()
+                            .is_some_and(|ext| ext.eq_ignore_ascii_case("xlsx"));
+                        let result = if is_xlsx {
+                            export_xlsx(&path, "年度比較", &headers, &rows)
+                        } else {
+                            export_csv(&path, &headers, &rows)
+                        };
+                        match result {
+                            Ok(()) => comparison_message.set(format!("已匯出至 {}", path.display())),
+                            Err(err) => comparison_message.set(format!("匯出失敗：{err:#}")),
+                        }
+                    },
+                    "匯出…"
+                }
+                span { style: "color: #555;", "{comparison_message}" }
+            }
+            if sources.is_empty() {
+                div { style: "color: #555; margin-top: 4px;", "尚無持股資料集可比較。" }
+            }
+            if let Some((headers, rows)) = comparison_table {
+                table { style: "border-collapse: collapse; margin-top: 8px;",
+                    tr {
+                        for header in headers {
+                            th { style: "padding: 4px 12px; border-bottom: 1px solid #ddd; text-align: right;", "{header}" }
+                        }
+                    }
+                    for row in rows {
+                        tr {
+                            for value in row {
+                                td { style: "padding: 4px 12px; text-align: right;", "{value}" }
+                            }
+                        }
+                    }
+                }
+            }
+            div { style: "font-weight: 600; margin: 16px 0 8px;", "被動收入目標" }
+            div { style: "display: flex; flex-wrap: wrap; gap: 8px; align-items: center;",
+                label { style: "display: flex; gap: 8px; align-items: center;",
+                    "每月目標（今日幣值）"
+                    input {
+                        style: "width: 100px;",
+                        placeholder: "50000",
+                        value: target_text(),
+                        oninput: move |event| target_text.set(event.value()),
+                    }
+                }
+                label { style: "display: flex; gap: 8px; align-items: center;",
+                    "通膨率"
+                    input {
+                        style: "width: 80px;",
+                        placeholder: "2%",
+                        value: inflation_text(),
+                        oninput: move |event| inflation_text.set(event.value()),
+                    }
+                }
+                button {
+                    onclick: {
+                        let query_service = services.query_service.clone();
+                        move |_| {
+                            let target_input = target_text();
+                            let monthly_target = if target_input.trim().is_empty() {
+                                0.0
+                            } else {
+                                match parse_numeric_value(&target_input).filter(|target| *target >= 0.0) {
+                                    Some(target) => target,
+                                    None => {
+                                        goal_message.set(format!("「{target_input}」不是有效的金額"));
+                                        return;
+                                    }
+                                }
+                            };
+                            let rate_input = inflation_text();
+                            // A bare number is read as a percentage, like the placeholder.
+                            let percent = format!("{}%", rate_input.trim().trim_end_matches('%'));
+                            let Some(inflation_rate) = parse_numeric_value(&percent)
+                                .filter(|rate| *rate > -1.0)
+                            else {
+                                goal_message.set(format!("「{rate_input}」不是有效的百分比"));
+                                return;
+                            };
+                            let updated = IncomeGoal {
+                                monthly_target,
+                                inflation_rate,
+                            };
+                            match run_blocking(|| save_income_goal(&query_service, &updated)) {
+                                Ok(()) => {
+                                    goal.set(updated);
+                                    inflation_text.set(format!("{}%", format_f64(inflation_rate * 100.0)));
+                                    goal_message.set("已保存被動收入目標".to_string());
+                                }
+                                Err(err) => goal_message.set(format!("保存被動收入目標失敗：{err}")),
+                            }
+                        }
+                    },
+                    "保存"
+                }
+                span { style: "color: #555;", "{goal_message}" }
+            }
+            div { style: "color: #666; font-size: 12px; margin-top: 4px;",
+                "以各持股表的估計配息推算每月收入，未來的收入依通膨率折回今日幣值後與目標比較。"
+            }
+            table { style: "border-collapse: collapse; margin-top: 8px;",
+                tr {
+                    for header in projection_headers {
+                        th { style: "padding: 4px 12px; border-bottom: 1px solid #ddd; text-align: right;", "{header}" }
+                    }
+                }
+                for row in projection_rows {
+                    tr {
+                        for value in row {
+                            td { style: "padding: 4px 12px; text-align: right;", "{value}" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A `<select>` of the summaries a comparison can use; `on_select` gets the source key.
+#[component]
+fn SourcePicker(
+    placeholder: String,
+    sources: Vec<SourceOption>,
+    selected: String,
+    on_select: EventHandler<String>,
+) -> Element {
+    rsx! {
+        select {
+            onchange: move |event: FormEvent| on_select.call(event.value()),
+            option { value: "", selected: selected.is_empty(), "{placeholder}" }
+            for choice in sources {
+                option {
+                    value: "{choice.source.key()}",
+                    selected: choice.source.key() == selected,
+                    "{choice.label}"
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use dioxus::prelude::*;
+
+use crate::platform::desktop::blocking::run_blocking;
+use crate::ui::components::chart::value_range;
+use crate::ui::state::services::AppServices;
+use bom_core::income_goal::load_income_goal;
+use bom_core::numeric::{format_f64, format_number_with_commas, parse_numeric_value};
+use bom_core::retirement::{
+    load_retirement_plan, retirement_projection_table, save_retirement_plan, simulate_retirement,
+    starting_allocation, AssetClass, ClassAssumption, RetirementPlan, RetirementProjection,
+    MAX_TRIALS, MAX_YEARS,
+};
+
+const BAND_CHART_WIDTH: f64 = 640.0;
+const BAND_CHART_HEIGHT: f64 = 220.0;
+
+/// Monte Carlo projection of the portfolio's value and income, in today's dollars at the
+/// inflation rate of the 被動收入目標. The assumptions per 性質 are kept between runs.
+#[component]
+pub fn RetirementPage() -> Element {
+    let services = use_context::<AppServices>();
+    let plan = use_hook(|| {
+        run_blocking(|| load_retirement_plan(&services.query_service)).unwrap_or_default()
+    });
+    let mut years_text = use_signal(|| plan.years.to_string());
+    let mut trials_text = use_signal(|| plan.trials.to_string());
+    let mut assumption_texts = use_signal(|| {
+        plan.assumptions.map(|assumption| {
+            [
+                assumption.expected_return,
+                assumption.volatility,
+                assumption.income_yield,
+            ]
+            .map(|ratio| format!("{}%", format_f64(ratio * 100.0)))
+        })
+    });
+    let mut projection = use_signal(|| None::<RetirementProjection>);
+    let mut message = use_signal(String::new);
+
+    let run = {
+        let query_service = services.query_service.clone();
+        move || {
+            let Some(plan) = read_plan(&years_text(), &trials_text(), &assumption_texts()) else {
+                message.set(format!(
+                    "請輸入 1–{MAX_YEARS} 年、1–{MAX_TRIALS} 次與有效的百分比"
+                ));
+                return;
+            };
+            let simulate = || {
+                save_retirement_plan(&query_service, &plan)?;
+                let start = starting_allocation(&query_service)?;
+                let inflation_rate = load_income_goal(&query_service)?.inflation_rate;
+                Ok::<_, anyhow::Error>(simulate_retirement(start, &plan, inflation_rate))
+            };
+            match run_blocking(simulate) {
+                Ok(found) => {
+                    projection.set(Some(found));
+                    message.set(String::new());
+                }
+                Err(err) => message.set(format!("試算失敗：{err:#}")),
+            }
+        }
+    };
+    let table = projection().map(|current| retirement_projection_table(&current));
+
+    rsx! {
+        div { style: "padding: 16px; overflow: auto; height: 100%; box-sizing: border-box;",
+            div { style: "font-weight: 600; margin-bottom: 8px;", "退休試算" }
+            div { style: "display: flex; flex-wrap: wrap; gap: 8px; align-items: center;",
+                label { style: "display: flex; gap: 8px; align-items: center;",
+                    "年數"
+                    input {
+                        style: "width: 60px;",
+                        value: years_text(),
+                        oninput: move |event| years_text.set(event.value()),
+                    }
+                }
+                label { style: "display: flex; gap: 8px; align-items: center;",
+                    "模擬次數"
+                    input {
+                        style: "width: 80px;",
+                        value: trials_text(),
+                        oninput: move |event| trials_text.set(event.value()),
+                    }
+                }
+            }
+            table { style: "border-collapse: collapse; margin-top: 8px;",
+                tr {
+                    th { style: "padding: 4px 12px 4px 0; text-align: left;", "性質" }
+                    th { style: "padding: 4px 12px;", "預期報酬" }
+                    th { style: "padding: 4px 12px;", "波動度" }
+                    th { style: "padding: 4px 12px;", "配息率" }
+                }
+                for (class_idx, class) in AssetClass::ALL.into_iter().enumerate() {
+                    tr {
+                        td { style: "padding: 2px 12px 2px 0;", "{class.label()}" }
+                        for field in 0..3 {
+                            td { style: "padding: 2px 12px;",
+                                input {
+                                    style: "width: 70px;",
+                                    value: assumption_texts()[class_idx][field].clone(),
+                                    oninput: move |event| {
+                                        assumption_texts.write()[class_idx][field] = event.value();
+                                    },
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            div { style: "display: flex; gap: 8px; align-items: center; margin-top: 8px;",
+                button {
+                    onclick: {
+                        let mut run = run.clone();
+                        move |_| run()
+                    },
+                    "試算"
+                }
+                span { style: "color: #555;", "{message}" }
+            }
+            div { style: "color: #666; font-size: 12px; margin-top: 4px;",
+                "起始金額取自持股表的淨值（依性質分股、債）與資產總表的定存；每次模擬各性質的年報酬依預期報酬與波動度隨機抽取，金額以被動收入目標的通膨率折回今日幣值。"
+            }
+            if let Some(current) = projection() {
+                div { style: "display: flex; gap: 16px; margin: 12px 0 6px; color: #444;",
+                    for (class, amount) in AssetClass::ALL.into_iter().zip(current.start) {
+                        span { "{class.label()} {format_number_with_commas(amount, 0)}" }
+                    }
+                }
+                ProjectionBandChart { projection: current.clone() }
+            }
+            if let Some((headers, rows)) = table {
+                table { style: "border-collapse: collapse; margin-top: 12px;",
+                    tr {
+                        for header in headers {
+                            th { style: "padding: 4px 12px; border-bottom: 1px solid #ddd; text-align: right;", "{header}" }
+                        }
+                    }
+                    for row in rows {
+                        tr {
+                            for value in row {
+                                td { style: "padding: 2px 12px; text-align: right;", "{value}" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 淨值 by year: the 10th–90th and 25th–75th percentile bands, with the median drawn
+/// over them.
+#[component]
+fn ProjectionBandChart(projection: RetirementProjection) -> Element {
+    let bands = &projection.bands;
+    let all_values: Vec<f64> = bands.iter().flat_map(|band| band.value).collect();
+    let domain = value_range(&all_values, true);
+    let step = BAND_CHART_WIDTH / bands.len().saturating_sub(1).max(1) as f64;
+    let point = |idx: usize, value: f64| {
+        let y = BAND_CHART_HEIGHT - (value - domain.0) / (domain.1 - domain.0) * BAND_CHART_HEIGHT;
+        format!("{:.1},{y:.1}", idx as f64 * step)
+    };
+    let line = |percentile: usize| {
+        bands
+            .iter()
+            .enumerate()
+            .map(|(idx, band)| point(idx, band.value[percentile]))
+            .collect::<Vec<_>>()
+    };
+    let area = |low: usize, high: usize| {
+        let mut points = line(high);
+        points.extend(line(low).into_iter().rev());
+        points.join(" ")
+    };
+    let outer = area(0, 4);
+    let inner = area(1, 3);
+    let median = line(2).join(" ");
+    let last_year = bands.last().map(|band| band.year).unwrap_or_default();
+    rsx! {
+        svg {
+            width: "{BAND_CHART_WIDTH}",
+            height: "{BAND_CHART_HEIGHT}",
+            view_box: "0 0 {BAND_CHART_WIDTH} {BAND_CHART_HEIGHT}",
+            style: "overflow: visible; border-bottom: 1px solid #ddd;",
+            polygon { points: "{outer}", fill: "#4a7bd0", fill_opacity: "0.15" }
+            polygon { points: "{inner}", fill: "#4a7bd0", fill_opacity: "0.3" }
+            polyline { points: "{median}", fill: "none", stroke: "#4a7bd0", stroke_width: "2" }
+        }
+        div { style: "display: flex; gap: 16px; color: #555; font-size: 12px; margin-top: 4px;",
+            span { "0 ～ {last_year} 年後" }
+            span { "上限 {format_number_with_commas(domain.1, 0)}" }
+            span { "深色為 P25–P75，淺色為 P10–P90，線為中位數" }
+        }
+    }
+}
+
+/// The plan typed into the form; `None` when a field is out of range or not a number. A
+/// bare number is read as a percentage.
+fn read_plan(years: &str, trials: &str, assumptions: &[[String; 3]; 3]) -> Option<RetirementPlan> {
+    let years = years
+        .trim()
+        .parse()
+        .ok()
+        .filter(|y| (1..=MAX_YEARS).contains(y))?;
+    let trials = trials
+        .trim()
+        .parse()
+        .ok()
+        .filter(|t| (1..=MAX_TRIALS).contains(t))?;
+    let percent =
+        |text: &str| parse_numeric_value(&format!("{}%", text.trim().trim_end_matches('%')));
+    let mut plan = RetirementPlan {
+        years,
+        trials,
+        ..RetirementPlan::default()
+    };
+    for (slot, [expected_return, volatility, income_yield]) in
+        plan.assumptions.iter_mut().zip(assumptions)
+    {
+        *slot = ClassAssumption {
+            expected_return: percent(expected_return)?,
+            volatility: percent(volatility)?.abs(),
+            income_yield: percent(income_yield)?,
+        };
+    }
+    Some(plan)
+}
@@ -0,0 +1,380 @@
+use dioxus::prelude::*;
+
+use crate::domain::entities::column_set::ColumnSet;
+use crate::domain::entities::dataset::DatasetKind;
+use crate::platform::desktop::blocking::run_blocking;
+use crate::platform::desktop::menu::MenuCommand;
+use crate::ui::state::app_lock::AppLockState;
+use crate::ui::state::app_state::{ColumnSetState, MenuCommandState, Route, RouteState};
+use crate::ui::state::services::AppServices;
+use crate::{
+    load_alert_loss_threshold, load_snapshot_schedule, load_trash_retention_days,
+    ALERT_LOSS_THRESHOLD_KEY, DIVIDEND_OWNERS_KEY, SNAPSHOT_SCHEDULE_KEY, TRASH_RETENTION_KEY,
+};
+use bom_core::assets::{format_deposit_rates, parse_deposit_rates};
+use bom_core::dividend_sweep::{sweep_enabled, DIVIDEND_SWEEP_KEY};
+use bom_core::holdings::{builtin_column_set, parse_column_names};
+use bom_core::numeric::{format_f64, parse_numeric_value, NumberLocale};
+use bom_core::report::{parse_owner_list, SnapshotSchedule};
+
+/// Application settings that don't belong to one dataset. Dialog-based settings (remote
+/// backup, preference files) still open in the dataset view.
+#[component]
+pub fn SettingsPage() -> Element {
+    let services = use_context::<AppServices>();
+    let mut route = use_context::<RouteState>();
+    let mut menu_commands = use_context::<MenuCommandState>();
+    let mut app_lock = use_context::<AppLockState>();
+    let mut locale = use_signal(|| services.number_locale.get());
+    let mut owners = use_signal(|| {
+        run_blocking(|| services.query_service.load_setting(DIVIDEND_OWNERS_KEY))
+            .ok()
+            .flatten()
+            .map(|value| parse_owner_list(&value).join("\n"))
+            .unwrap_or_default()
+    });
+    let mut snapshot_schedule = use_signal(|| {
+        run_blocking(|| load_snapshot_schedule(&services.query_service)).unwrap_or_default()
+    });
+    let mut dividend_sweep = use_signal(|| {
+        run_blocking(|| services.query_service.load_setting(DIVIDEND_SWEEP_KEY))
+            .map(|value| sweep_enabled(value.as_deref()))
+            .unwrap_or(false)
+    });
+    let mut message = use_signal(String::new);
+    let mut column_sets = use_context::<ColumnSetState>();
+    let mut column_kind = use_signal(|| DatasetKind::Holdings);
+    let mut required_text =
+        use_signal(|| column_sets.get(DatasetKind::Holdings).required.join("\n"));
+    let mut derived_text = use_signal(|| column_sets.get(DatasetKind::Holdings).derived.join("\n"));
+    let mut column_message = use_signal(String::new);
+    let mut deposit_rates = use_signal(|| {
+        run_blocking(|| services.query_service.load_deposit_rates())
+            .map(|rates| format_deposit_rates(&rates))
+            .unwrap_or_default()
+    });
+    let mut deposit_rate_message = use_signal(String::new);
+    let mut loss_threshold = use_signal(|| {
+        run_blocking(|| load_alert_loss_threshold(&services.query_service))
+            .map(|threshold| format!("{}%", format_f64(threshold * 100.0)))
+            .unwrap_or_default()
+    });
+    let mut alert_message = use_signal(String::new);
+    let mut trash_retention = use_signal(|| {
+        run_blocking(|| load_trash_retention_days(&services.query_service))
+            .map(|days| days.to_string())
+            .unwrap_or_default()
+    });
+    let mut trash_message = use_signal(String::new);
+    let mut show_column_set = move |kind: DatasetKind, set: ColumnSet| {
+        column_kind.set(kind);
+        required_text.set(set.required.join("\n"));
+        derived_text.set(set.derived.join("\n"));
+    };
+    let mut run = move |command: MenuCommand| {
+        route.navigate(Route::Dataset);
+        menu_commands.pending.set(Some(command));
+    };
+
+    rsx! {
+        div { style: "padding: 16px; overflow: auto; height: 100%; box-sizing: border-box; max-width: 640px;",
+            div { style: "font-weight: 600; margin-bottom: 8px;", "設定" }
+            label { style: "display: flex; gap: 8px; align-items: center;",
+                "數字寫法"
+                select {
+                    onchange: {
+                        let services = services.clone();
+                        move |event: FormEvent| {
+                            if let Some(next) = NumberLocale::parse(&event.value()) {
+                                services.save_number_locale(next);
+                                locale.set(next);
+                            }
+                        }
+                    },
+                    for option_locale in NumberLocale::ALL {
+                        option {
+                            value: option_locale.as_str(),
+                            selected: option_locale == locale(),
+                            "{option_locale.label()}"
+                        }
+                    }
+                }
+            }
+            label { style: "display: flex; gap: 8px; align-items: center; margin-top: 8px;",
+                "自動記錄總結"
+                select {
+                    onchange: {
+                        let query_service = services.query_service.clone();
+                        move |event: FormEvent| {
+                            let Some(next) = SnapshotSchedule::parse(&event.value()) else {
+                                return;
+                            };
+                            match run_blocking(|| query_service.save_setting(SNAPSHOT_SCHEDULE_KEY, next.as_str())) {
+                                Ok(()) => snapshot_schedule.set(next),
+                                Err(err) => message.set(format!("保存自動記錄設定失敗：{err}")),
+                            }
+                        }
+                    },
+                    for option_schedule in SnapshotSchedule::ALL {
+                        option {
+                            value: option_schedule.as_str(),
+                            selected: option_schedule == snapshot_schedule(),
+                            "{option_schedule.label()}"
+                        }
+                    }
+                }
+            }
+            div { style: "color: #666; font-size: 12px; margin-top: 4px;",
+                "程式開啟時，依頻率將最新的資產總表與持股總結存入總結歷史；未開啟時可用 cron 執行 bom snapshot。"
+            }
+            label { style: "display: flex; gap: 8px; align-items: center; margin-top: 8px;",
+                input {
+                    r#type: "checkbox",
+                    checked: dividend_sweep(),
+                    onchange: {
+                        let query_service = services.query_service.clone();
+                        move |event: FormEvent| {
+                            let enabled = event.checked();
+                            let value = if enabled { "on" } else { "off" };
+                            match run_blocking(|| query_service.save_setting(DIVIDEND_SWEEP_KEY, value)) {
+                                Ok(()) => dividend_sweep.set(enabled),
+                                Err(err) => message.set(format!("保存股息入帳設定失敗：{err}")),
+                            }
+                        }
+                    },
+                }
+                "股息入帳時加到所有權人的活存餘額"
+            }
+            div { style: "color: #666; font-size: 12px; margin-top: 4px;",
+                "預設關閉。開啟後，在持股股息總表的月份欄位填入股息並保存，同一活頁簿資產總表中該所有權人的第一個活存帳戶餘額會增加相同金額；另存的複本不受影響。"
+            }
+            div { style: "font-weight: 600; margin: 16px 0 6px;", "所有權人" }
+            textarea {
+                style: "width: 100%; min-height: 120px; box-sizing: border-box;",
+                placeholder: "每行一位所有權人",
+                value: owners(),
+                oninput: move |event| owners.set(event.value()),
+            }
+            div { style: "display: flex; gap: 8px; align-items: center; margin-top: 6px;",
+                button {
+                    onclick: {
+                        let query_service = services.query_service.clone();
+                        move |_| {
+                            let list = parse_owner_list(&owners());
+                            match run_blocking(|| query_service.save_setting(DIVIDEND_OWNERS_KEY, &list.join("\n"))) {
+                                Ok(()) => message.set("已保存所有權人設定".to_string()),
+                                Err(err) => message.set(format!("保存所有權人設定失敗：{err}")),
+                            }
+                        }
+                    },
+                    "保存"
+                }
+                span { style: "color: #555;", "{message}" }
+            }
+            div { style: "font-weight: 600; margin: 16px 0 6px;", "欄位設定" }
+            label { style: "display: flex; gap: 8px; align-items: center;",
+                "資料集類型"
+                select {
+                    onchange: move |event: FormEvent| {
+                        if let Some(kind) = DatasetKind::parse(&event.value()) {
+                            show_column_set(kind, column_sets.get(kind));
+                            column_message.set(String::new());
+                        }
+                    },
+                    for option_kind in DatasetKind::ALL {
+                        option {
+                            value: option_kind.as_str(),
+                            selected: option_kind == column_kind(),
+                            "{option_kind.label()}"
+                        }
+                    }
+                }
+            }
+            div { style: "display: grid; grid-template-columns: 1fr 1fr; gap: 8px; margin-top: 8px;",
+                div {
+                    div { style: "margin-bottom: 4px;", "必填欄位" }
+                    textarea {
+                        style: "width: 100%; min-height: 160px; box-sizing: border-box;",
+                        placeholder: "每行一個欄位名稱",
+                        value: required_text(),
+                        oninput: move |event| required_text.set(event.value()),
+                    }
+                }
+                div {
+                    div { style: "margin-bottom: 4px;", "衍生欄位（匯入時計算，不可編輯）" }
+                    textarea {
+                        style: "width: 100%; min-height: 160px; box-sizing: border-box;",
+                        placeholder: "每行一個欄位名稱",
+                        value: derived_text(),
+                        oninput: move |event| derived_text.set(event.value()),
+                    }
+                }
+            }
+            div { style: "color: #666; font-size: 12px; margin-top: 4px;",
+                "具備所有持股必填欄位的資料表會視為持股；欄位名稱與活頁簿不同時，可在此改成活頁簿的寫法。"
+            }
+            div { style: "display: flex; gap: 8px; align-items: center; margin-top: 6px;",
+                button {
+                    onclick: {
+                        let query_service = services.query_service.clone();
+                        move |_| {
+                            let kind = column_kind();
+                            let set = ColumnSet {
+                                required: parse_column_names(&required_text()),
+                                derived: parse_column_names(&derived_text()),
+                            };
+                            match run_blocking(|| query_service.save_column_set(kind, set.clone())) {
+                                Ok(()) => {
+                                    column_sets.set(kind, set.clone());
+                                    show_column_set(kind, set);
+                                    column_message.set(format!("已保存{}欄位設定", kind.label()));
+                                }
+                                Err(err) => column_message.set(format!("保存欄位設定失敗：{err}")),
+                            }
+                        }
+                    },
+                    "保存"
+                }
+                button {
+                    onclick: {
+                        let query_service = services.query_service.clone();
+                        move |_| {
+                            let kind = column_kind();
+                            match run_blocking(|| query_service.reset_column_set(kind)) {
+                                Ok(()) => {
+                                    let set = builtin_column_set(kind);
+                                    column_sets.set(kind, set.clone());
+                                    show_column_set(kind, set);
+                                    column_message.set(format!("已還原{}欄位設定", kind.label()));
+                                }
+                                Err(err) => column_message.set(format!("還原欄位設定失敗：{err}")),
+                            }
+                        }
+                    },
+                    "還原預設"
+                }
+                span { style: "color: #555;", "{column_message}" }
+            }
+            div { style: "font-weight: 600; margin: 16px 0 6px;", "定存利率" }
+            textarea {
+                style: "width: 100%; min-height: 120px; box-sizing: border-box;",
+                placeholder: "每行：往來機構 存期 利率，例如 臺灣銀行 1年 1.6%",
+                value: deposit_rates(),
+                oninput: move |event| deposit_rates.set(event.value()),
+            }
+            div { style: "color: #666; font-size: 12px; margin-top: 4px;",
+                "資產總表的定存依往來機構與存期查表計算年化、月化利息；省略存期即適用該機構其他存期，表中沒有的機構改用該列的利率欄位。"
+            }
+            div { style: "display: flex; gap: 8px; align-items: center; margin-top: 6px;",
+                button {
+                    onclick: {
+                        let query_service = services.query_service.clone();
+                        move |_| {
+                            let rates = match parse_deposit_rates(&deposit_rates()) {
+                                Ok(rates) => rates,
+                                Err(err) => {
+                                    deposit_rate_message.set(err.to_string());
+                                    return;
+                                }
+                            };
+                            match run_blocking(|| query_service.save_deposit_rates(rates.clone())) {
+                                Ok(()) => {
+                                    deposit_rates.set(format_deposit_rates(&rates));
+                                    deposit_rate_message.set(format!("已保存 {} 筆定存利率", rates.len()));
+                                }
+                                Err(err) => deposit_rate_message.set(format!("保存定存利率失敗：{err}")),
+                            }
+                        }
+                    },
+                    "保存"
+                }
+                span { style: "color: #555;", "{deposit_rate_message}" }
+            }
+            div { style: "font-weight: 600; margin: 16px 0 6px;", "警示" }
+            div { style: "display: flex; gap: 8px; align-items: center;",
+                label { style: "display: flex; gap: 8px; align-items: center;",
+                    "損益率低於"
+                    input {
+                        style: "width: 80px;",
+                        placeholder: "10%",
+                        value: loss_threshold(),
+                        oninput: move |event| loss_threshold.set(event.value()),
+                    }
+                    "時列入警示"
+                }
+                button {
+                    onclick: {
+                        let query_service = services.query_service.clone();
+                        move |_| {
+                            let text = loss_threshold();
+                            // A bare number is read as a percentage, like the placeholder.
+                            let percent = format!("{}%", text.trim().trim_start_matches('-').trim_end_matches('%'));
+                            let Some(threshold) = parse_numeric_value(&percent)
+                                .filter(|threshold| *threshold > 0.0)
+                            else {
+                                alert_message.set(format!("「{text}」不是正的百分比"));
+                                return;
+                            };
+                            match run_blocking(|| query_service.save_setting(ALERT_LOSS_THRESHOLD_KEY, &format_f64(threshold))) {
+                                Ok(()) => {
+                                    loss_threshold.set(format!("{}%", format_f64(threshold * 100.0)));
+                                    alert_message.set("已保存警示設定".to_string());
+                                }
+                                Err(err) => alert_message.set(format!("保存警示設定失敗：{err}")),
+                            }
+                        }
+                    },
+                    "保存"
+                }
+                span { style: "color: #555;", "{alert_message}" }
+            }
+            div { style: "color: #666; font-size: 12px; margin-top: 4px;",
+                "輸入虧損的百分比，例如 10%；定存在到期前 30 天起列入警示。"
+            }
+            div { style: "font-weight: 600; margin: 16px 0 6px;", "垃圾桶" }
+            div { style: "display: flex; gap: 8px; align-items: center;",
+                label { style: "display: flex; gap: 8px; align-items: center;",
+                    "保留"
+                    input {
+                        style: "width: 80px;",
+                        placeholder: "30",
+                        value: trash_retention(),
+                        oninput: move |event| trash_retention.set(event.value()),
+                    }
+                    "天"
+                }
+                button {
+                    onclick: {
+                        let query_service = services.query_service.clone();
+                        move |_| {
+                            let text = trash_retention();
+                            let Ok(days) = text.trim().parse::<u32>() else {
+                                trash_message.set(format!("「{text}」不是天數"));
+                                return;
+                            };
+                            match run_blocking(|| query_service.save_setting(TRASH_RETENTION_KEY, &days.to_string())) {
+                                Ok(()) => {
+                                    trash_retention.set(days.to_string());
+                                    trash_message.set("已保存垃圾桶設定".to_string());
+                                }
+                                Err(err) => trash_message.set(format!("保存垃圾桶設定失敗：{err}")),
+                            }
+                        }
+                    },
+                    "保存"
+                }
+                span { style: "color: #555;", "{trash_message}" }
+            }
+            div { style: "color: #666; font-size: 12px; margin-top: 4px;",
+                "移到垃圾桶超過這些天的資料集，會在程式開啟時永久刪除；輸入 0 則不自動清除。在資料集管理勾選「永久保留」的資料集不會被清除。"
+            }
+            div { style: "font-weight: 600; margin: 16px 0 6px;", "安全與備份" }
+            div { style: "display: flex; flex-wrap: wrap; gap: 8px;",
+                button { onclick: move |_| app_lock.show_settings.set(true), "啟動密碼…" }
+                button { onclick: move |_| run(MenuCommand::RemoteBackupSettings), "遠端備份…" }
+                button { onclick: move |_| run(MenuCommand::ExportPreferences), "匯出設定…" }
+                button { onclick: move |_| run(MenuCommand::ImportPreferences), "匯入設定…" }
+            }
+        }
+    }
+}
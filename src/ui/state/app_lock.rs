@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+
+use dioxus::prelude::*;
+
+use crate::infra::security::passphrase::{hash_passphrase, verify_passphrase};
+use crate::ui::state::services::AppServices;
+use crate::{parse_lock_idle_minutes, APP_LOCK_HASH_KEY, APP_LOCK_IDLE_KEY};
+
+/// Startup passphrase and idle auto-lock of the main window. Nothing locks until a
+/// passphrase has been set under 檔案 → 啟動密碼.
+#[derive(Clone, Copy)]
+pub struct AppLockState {
+    pub locked: Signal<bool>,
+    pub show_settings: Signal<bool>,
+    pub idle_minutes: Signal<u32>,
+    passphrase_hash: Signal<Option<String>>,
+    // Not a signal: it changes on every click and key press and nothing renders from it.
+    last_activity: CopyValue<Instant>,
+}
+
+impl AppLockState {
+    /// Reads the lock settings; the app starts locked when a passphrase is set.
+    pub fn load(services: &AppServices) -> Self {
+        let load = |key| {
+            services
+                .query_service
+                .load_setting(key)
+                .unwrap_or_else(|err| {
+                    tracing::warn!(error = %err, key, "讀取鎖定設定失敗");
+                    None
+                })
+        };
+        let passphrase_hash = load(APP_LOCK_HASH_KEY).filter(|hash| !hash.is_empty());
+        let idle_minutes = parse_lock_idle_minutes(load(APP_LOCK_IDLE_KEY).as_deref());
+        Self {
+            locked: Signal::new(passphrase_hash.is_some()),
+            show_settings: Signal::new(false),
+            idle_minutes: Signal::new(idle_minutes),
+            passphrase_hash: Signal::new(passphrase_hash),
+            last_activity: CopyValue::new(Instant::now()),
+        }
+    }
+
+    pub fn reload_idle_minutes(&mut self, services: &AppServices) {
+        match services.query_service.load_setting(APP_LOCK_IDLE_KEY) {
+            Ok(value) => self
+                .idle_minutes
+                .set(parse_lock_idle_minutes(value.as_deref())),
+            Err(err) => tracing::warn!(error = %err, "讀取鎖定設定失敗"),
+        }
+    }
+
+    pub fn has_passphrase(&self) -> bool {
+        self.passphrase_hash.read().is_some()
+    }
+
+    pub fn touch(&mut self) {
+        self.last_activity.set(Instant::now());
+    }
+
+    pub fn lock(&mut self) {
+        if self.has_passphrase() {
+            self.locked.set(true);
+        }
+    }
+
+    pub fn lock_if_idle(&mut self) {
+        let idle_minutes = (self.idle_minutes)();
+        if idle_minutes == 0 || (self.locked)() {
+            return;
+        }
+        let limit = Duration::from_secs(u64::from(idle_minutes) * 60);
+        if self.last_activity.cloned().elapsed() >= limit {
+            self.lock();
+        }
+    }
+
+    pub fn unlock(&mut self, passphrase: &str) -> bool {
+        let matches = self
+            .passphrase_hash
+            .read()
+            .as_deref()
+            .is_some_and(|hash| verify_passphrase(passphrase, hash));
+        if matches {
+            self.touch();
+            self.locked.set(false);
+        }
+        matches
+    }
+
+    /// Sets (or, with an empty `new_passphrase`, removes) the passphrase and stores the idle
+    /// timeout. Changing an existing passphrase requires the current one.
+    pub fn save(
+        &mut self,
+        services: &AppServices,
+        current: &str,
+        new_passphrase: &str,
+        idle_minutes: u32,
+    ) -> anyhow::Result<()> {
+        if let Some(hash) = self.passphrase_hash.read().as_deref() {
+            anyhow::ensure!(verify_passphrase(current, hash), "目前密碼不正確");
+        }
+        let hash = if new_passphrase.is_empty() {
+            None
+        } else {
+            Some(hash_passphrase(new_passphrase)?)
+        };
+        let query_service = &services.query_service;
+        query_service.save_setting(APP_LOCK_HASH_KEY, hash.as_deref().unwrap_or(""))?;
+        query_service.save_setting(APP_LOCK_IDLE_KEY, &idle_minutes.to_string())?;
+        self.passphrase_hash.set(hash);
+        self.idle_minutes.set(idle_minutes);
+        self.touch();
+        Ok(())
+    }
+}
@@ -1,44 +1,342 @@
 use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
 
-use dioxus::prelude::{use_signal, Signal};
+use dioxus::prelude::{use_hook, use_signal, CopyValue, ReadableExt, Signal, WritableExt};
 
+use crate::domain::entities::column_set::ColumnSet;
+use crate::domain::entities::dataset::{ColumnFormat, ColumnType, DatasetDisplay, DatasetKind};
 use crate::domain::entities::edit::CellKey;
+use crate::domain::entities::import_profile::ImportProfile;
+use crate::platform::desktop::menu::MenuCommand;
+use crate::ui::state::errors::ErrorReport;
+use crate::ui::state::services::AppServices;
+use crate::usecase::ports::importer::ImportValidation;
 use crate::usecase::ports::repo::DatasetMeta;
-use crate::{default_dataset_name_mmdd, PendingAction};
+use crate::{default_dataset_name_mmdd, PageCache, PendingAction};
+use bom_core::holdings::builtin_column_set;
+use bom_core::PAGE_SIZE;
 
+/// Split-view toggle shared by the panes of the main window. Secondary windows don't
+/// provide it, so they never offer a split.
+#[derive(Clone, Copy)]
+pub struct SplitViewState {
+    pub enabled: Signal<bool>,
+    pub secondary_dataset_id: Signal<Option<i64>>,
+}
+
+impl SplitViewState {
+    /// Opens the split with `current_dataset_id` in the second pane, or closes it.
+    pub fn toggle(&mut self, current_dataset_id: Option<i64>) {
+        let enabled = (self.enabled)();
+        if !enabled {
+            self.secondary_dataset_id.set(current_dataset_id);
+        }
+        self.enabled.set(!enabled);
+    }
+}
+
+/// Read-only viewing mode of the main window. Mirrors `AppServices::read_only`, which is
+/// what actually refuses changes; the signal lets the UI hide its edit actions.
+#[derive(Clone, Copy)]
+pub struct ReadOnlyState {
+    pub enabled: Signal<bool>,
+}
+
+impl ReadOnlyState {
+    pub fn set(&mut self, services: &AppServices, enabled: bool) {
+        services.read_only.set(enabled);
+        self.enabled.set(enabled);
+    }
+}
+
+/// Required and derived columns of each dataset type, as set in 設定. Shared by every
+/// pane; the settings page replaces a set when it is saved.
+#[derive(Clone, Copy)]
+pub struct ColumnSetState {
+    sets: Signal<HashMap<DatasetKind, ColumnSet>>,
+}
+
+impl ColumnSetState {
+    /// Reads every type's set; one that can't be read falls back to the built-in set.
+    pub fn load(services: &AppServices) -> Self {
+        let sets = DatasetKind::ALL
+            .into_iter()
+            .map(|kind| {
+                let set = services
+                    .query_service
+                    .load_column_set(kind)
+                    .unwrap_or_else(|err| {
+                        tracing::warn!(error = %err, kind = kind.as_str(), "讀取欄位設定失敗");
+                        builtin_column_set(kind)
+                    });
+                (kind, set)
+            })
+            .collect();
+        Self {
+            sets: Signal::new(sets),
+        }
+    }
+
+    pub fn get(&self, kind: DatasetKind) -> ColumnSet {
+        self.sets
+            .read()
+            .get(&kind)
+            .cloned()
+            .unwrap_or_else(|| builtin_column_set(kind))
+    }
+
+    pub fn set(&mut self, kind: DatasetKind, set: ColumnSet) {
+        self.sets.write().insert(kind, set);
+    }
+}
+
+/// Native-menu command, or a file handed over by a second launch, waiting to be handled by
+/// the first pane of the main window.
+#[derive(Clone, Copy)]
+pub struct MenuCommandState {
+    pub pending: Signal<Option<MenuCommand>>,
+    pub open_file: Signal<Option<PathBuf>>,
+}
+
+/// Pages of the main window, switched from the navigation bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Route {
+    Dashboard,
+    Dataset,
+    DatasetManager,
+    Reports,
+    Alerts,
+    Holding,
+    Retirement,
+    Settings,
+}
+
+impl Route {
+    pub const ALL: [Route; 8] = [
+        Route::Dashboard,
+        Route::Dataset,
+        Route::DatasetManager,
+        Route::Reports,
+        Route::Alerts,
+        Route::Holding,
+        Route::Retirement,
+        Route::Settings,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Route::Dashboard => "總覽",
+            Route::Dataset => "資料集",
+            Route::DatasetManager => "資料集管理",
+            Route::Reports => "報表",
+            Route::Alerts => "警示",
+            Route::Holding => "個股",
+            Route::Retirement => "退休試算",
+            Route::Settings => "設定",
+        }
+    }
+}
+
+/// Current page of the main window. The dataset view stays mounted while another page is
+/// shown, so its staged edits and selection survive navigation; other pages hand it work
+/// through `open_dataset` or [`MenuCommandState`] and navigate back.
+#[derive(Clone, Copy)]
+pub struct RouteState {
+    pub current: Signal<Route>,
+    /// Dataset the dataset view should switch to the next time it is shown.
+    pub open_dataset: Signal<Option<i64>>,
+    /// 代號 the holding page shows.
+    pub holding_code: Signal<Option<String>>,
+}
+
+impl RouteState {
+    pub fn navigate(&mut self, route: Route) {
+        if (self.current)() != route {
+            self.current.set(route);
+        }
+    }
+
+    pub fn open_dataset(&mut self, dataset_id: i64) {
+        self.open_dataset.set(Some(dataset_id));
+        self.navigate(Route::Dataset);
+    }
+
+    pub fn open_holding(&mut self, code: &str) {
+        self.holding_code.set(Some(code.trim().to_string()));
+        self.navigate(Route::Holding);
+    }
+}
+
+/// Kind of work that can keep controls disabled while it runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BusyKind {
+    /// Loading, searching or summarizing the selected dataset.
+    Query,
+    /// Reading files into new datasets.
+    Import,
+    /// Writing to the database or to backups.
+    Save,
+}
+
+/// Column visibility and holdings flags read along with a page by
+/// [`WorkspaceController::open_dataset`](crate::ui::state::controller::WorkspaceController::open_dataset),
+/// held until the effects that would otherwise query them run.
+#[derive(Debug, Clone, Default)]
+pub struct PrefetchedContext {
+    pub column_visibility: Option<(i64, BTreeMap<i64, bool>)>,
+    pub holdings_flags: Option<BTreeMap<i64, bool>>,
+}
+
+/// Work in progress per [`BusyKind`]. Controls only wait for the kinds they conflict with,
+/// so searching stays available during a backup. Counted, so an operation that nests
+/// another of the same kind stays busy until the outer one ends.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BusyState {
+    running: BTreeMap<BusyKind, usize>,
+}
+
+impl BusyState {
+    pub fn begin(&mut self, kind: BusyKind) {
+        *self.running.entry(kind).or_default() += 1;
+    }
+
+    pub fn end(&mut self, kind: BusyKind) {
+        if let Some(count) = self.running.get_mut(&kind) {
+            *count -= 1;
+            if *count == 0 {
+                self.running.remove(&kind);
+            }
+        }
+    }
+
+    pub fn is(&self, kind: BusyKind) -> bool {
+        self.running.contains_key(&kind)
+    }
+
+    pub fn any(&self) -> bool {
+        !self.running.is_empty()
+    }
+}
+
+/// What is held back until the user decides on duplicate 代號.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportReviewTarget {
+    /// A workbook about to be imported.
+    File(PathBuf),
+    /// A dataset whose stored sheets are about to be transformed again.
+    Retransform { dataset_id: i64, name: String },
+}
+
+impl ImportReviewTarget {
+    /// The file or dataset name shown in the review dialog.
+    pub fn label(&self) -> String {
+        match self {
+            Self::File(path) => path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.display().to_string()),
+            Self::Retransform { name, .. } => name.clone(),
+        }
+    }
+}
+
+/// A CSV waiting in the column mapping dialog, with the profile it starts from and every
+/// saved one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportMapping {
+    pub file_path: PathBuf,
+    pub draft: ImportProfile,
+    pub saved: Vec<ImportProfile>,
+    /// Opened by an import whose headers fit no dataset type, which can then still go
+    /// ahead with the file as it is.
+    pub unknown_headers: bool,
+}
+
+/// A folder being imported file by file, with the outcome of each file done so far.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchImport {
+    pub folder: PathBuf,
+    pub files: Vec<PathBuf>,
+    /// One per file imported so far, in order: what it created or why it failed.
+    pub results: Vec<Result<String, String>>,
+}
+
+impl BatchImport {
+    pub fn is_done(&self) -> bool {
+        self.results.len() >= self.files.len()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results.iter().filter(|result| result.is_err()).count()
+    }
+}
+
+#[derive(Clone, Copy)]
 pub struct AppState {
     pub datasets: Signal<Vec<DatasetMeta>>,
     pub selected_group_key: Signal<Option<String>>,
     pub selected_dataset_id: Signal<Option<i64>>,
     pub columns: Signal<Vec<String>>,
     pub column_visibility: Signal<BTreeMap<i64, bool>>,
-    pub rows: Signal<Vec<Vec<String>>>,
+    pub column_formats: Signal<BTreeMap<i64, ColumnFormat>>,
+    /// Stored column types; columns missing here are typed from the loaded page.
+    pub column_types: Signal<BTreeMap<i64, ColumnType>>,
+    /// Currency and unit the selected dataset's amounts are shown in.
+    pub dataset_display: Signal<DatasetDisplay>,
+    pub column_aliases: Signal<BTreeMap<i64, String>>,
+    pub visibility_presets: Signal<BTreeMap<String, BTreeMap<i64, bool>>>,
+    /// The loaded page, shared with render snapshots instead of copied into them.
+    pub rows: Signal<Arc<Vec<Vec<String>>>>,
     pub holdings_flags: Signal<BTreeMap<i64, bool>>,
     pub page: Signal<i64>,
     pub total_rows: Signal<i64>,
+    /// Rows per page of the loaded result: the dataset's chosen size, or every row after
+    /// 載入全部.
+    pub page_size: Signal<i64>,
+    /// Dataset whose rows are all loaded at once; cleared by choosing a page size.
+    pub load_all: Signal<Option<i64>>,
     pub global_search: Signal<String>,
     pub column_search_col: Signal<Option<i64>>,
     pub column_search_text: Signal<String>,
+    pub value_filters: Signal<BTreeMap<i64, BTreeSet<String>>>,
+    /// Filter expressions typed in the row under the table header.
+    pub column_conditions: Signal<BTreeMap<i64, String>>,
     pub sort_col: Signal<Option<i64>>,
     pub sort_desc: Signal<bool>,
     pub show_deleted: Signal<bool>,
-    pub busy: Signal<bool>,
+    pub busy: Signal<BusyState>,
     pub status: Signal<String>,
+    pub last_error: Signal<Option<ErrorReport>>,
     pub staged_cells: Signal<HashMap<CellKey, String>>,
     pub deleted_rows: Signal<BTreeSet<usize>>,
     pub selected_rows: Signal<BTreeSet<usize>>,
     pub edit_mode: Signal<bool>,
     pub editing_cell: Signal<Option<CellKey>>,
     pub editing_value: Signal<String>,
+    /// Cell last clicked in the table, shown in the formula bar.
+    pub focused_cell: Signal<Option<CellKey>>,
     pub added_rows: Signal<Vec<Vec<String>>>,
     pub show_add_row: Signal<bool>,
     pub new_row_inputs: Signal<HashMap<String, String>>,
+    /// Where the table's right-click menu is open, and the cell it was opened on.
     pub context_menu: Signal<Option<(f64, f64)>>,
-    pub context_row: Signal<Option<usize>>,
+    pub context_cell: Signal<Option<CellKey>>,
     pub pending_action: Signal<Option<PendingAction>>,
     pub show_save_prompt: Signal<bool>,
     pub show_save_as_prompt: Signal<bool>,
     pub save_as_name: Signal<String>,
+    pub last_saved_at: Signal<Option<String>>,
+    pub recent_imports: Signal<Vec<String>>,
+    /// A workbook import or re-transform held back until the user decides on its
+    /// duplicate 代號.
+    pub import_review: Signal<Option<(ImportReviewTarget, ImportValidation)>>,
+    pub import_mapping: Signal<Option<ImportMapping>>,
+    /// The folder import in progress, kept after it finishes until its results are closed.
+    pub batch_import: Signal<Option<BatchImport>>,
+    /// Not a signal: reusing a page must not re-render anything by itself.
+    pub page_cache: CopyValue<PageCache>,
+    pub prefetched: CopyValue<PrefetchedContext>,
 }
 
 impl AppState {
@@ -49,33 +347,51 @@ impl AppState {
             selected_dataset_id: use_signal(|| None::<i64>),
             columns: use_signal(Vec::<String>::new),
             column_visibility: use_signal(BTreeMap::<i64, bool>::new),
-            rows: use_signal(Vec::<Vec<String>>::new),
+            column_formats: use_signal(BTreeMap::<i64, ColumnFormat>::new),
+            column_types: use_signal(BTreeMap::<i64, ColumnType>::new),
+            dataset_display: use_signal(DatasetDisplay::default),
+            column_aliases: use_signal(BTreeMap::<i64, String>::new),
+            visibility_presets: use_signal(BTreeMap::<String, BTreeMap<i64, bool>>::new),
+            rows: use_signal(|| Arc::new(Vec::new())),
             holdings_flags: use_signal(BTreeMap::<i64, bool>::new),
             page: use_signal(|| 0_i64),
             total_rows: use_signal(|| 0_i64),
+            page_size: use_signal(|| PAGE_SIZE),
+            load_all: use_signal(|| None::<i64>),
             global_search: use_signal(String::new),
             column_search_col: use_signal(|| None::<i64>),
             column_search_text: use_signal(String::new),
+            value_filters: use_signal(BTreeMap::<i64, BTreeSet<String>>::new),
+            column_conditions: use_signal(BTreeMap::<i64, String>::new),
             sort_col: use_signal(|| None::<i64>),
             sort_desc: use_signal(|| false),
             show_deleted: use_signal(|| false),
-            busy: use_signal(|| false),
+            busy: use_signal(BusyState::default),
             status: use_signal(|| "就緒".to_string()),
+            last_error: use_signal(|| None::<ErrorReport>),
             staged_cells: use_signal(HashMap::<CellKey, String>::new),
             deleted_rows: use_signal(BTreeSet::<usize>::new),
             selected_rows: use_signal(BTreeSet::<usize>::new),
             edit_mode: use_signal(|| false),
             editing_cell: use_signal(|| None::<CellKey>),
             editing_value: use_signal(String::new),
+            focused_cell: use_signal(|| None::<CellKey>),
             added_rows: use_signal(Vec::<Vec<String>>::new),
             show_add_row: use_signal(|| false),
             new_row_inputs: use_signal(HashMap::<String, String>::new),
             context_menu: use_signal(|| None::<(f64, f64)>),
-            context_row: use_signal(|| None::<usize>),
+            context_cell: use_signal(|| None::<CellKey>),
             pending_action: use_signal(|| None::<PendingAction>),
             show_save_prompt: use_signal(|| false),
             show_save_as_prompt: use_signal(|| false),
             save_as_name: use_signal(default_dataset_name_mmdd),
+            last_saved_at: use_signal(|| None::<String>),
+            recent_imports: use_signal(Vec::<String>::new),
+            import_review: use_signal(|| None::<(ImportReviewTarget, ImportValidation)>),
+            import_mapping: use_signal(|| None::<ImportMapping>),
+            batch_import: use_signal(|| None::<BatchImport>),
+            page_cache: use_hook(|| CopyValue::new(PageCache::default())),
+            prefetched: use_hook(|| CopyValue::new(PrefetchedContext::default())),
         }
     }
 }
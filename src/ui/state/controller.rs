@@ -0,0 +1,1661 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+use chrono::Datelike;
+use dioxus::prelude::*;
+use rfd::{MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
+
+use crate::bundle::{export_bundle, import_bundle};
+use crate::demo::create_demo_datasets;
+use crate::dividend_year::{open_dividend_year, roll_over_dividend_dataset};
+use crate::domain::entities::cell_origin::CellOrigins;
+use crate::domain::entities::computed_column::ComputedColumn;
+use crate::domain::entities::dataset::{DatasetId, DatasetKind};
+use crate::domain::entities::edit::{SavedChanges, StagedEdits};
+use crate::domain::entities::import_profile::ImportProfile;
+use crate::domain::entities::import_record::ImportRecord;
+use crate::domain::entities::owner_share::{OwnerShare, OwnerSplits};
+use crate::domain::entities::price_point::PriceHistory;
+use crate::domain::entities::saved_report::SavedReport;
+use crate::domain::events::DomainEvent;
+use crate::expression::validate_computed_columns;
+use crate::infra::export::csv::CsvOptions;
+use crate::infra::import::mapped_csv::{csv_headers, suggest_column_mappings};
+use crate::infra::remote_backup::RemoteBackupConfig;
+use crate::platform::desktop::blocking::{run_blocking, yield_to_renderer};
+use crate::platform::desktop::close_guard::{
+    close_other_windows, close_window, current_window_id, quit_app,
+};
+use crate::platform::desktop::notify::notify_if_unfocused;
+use crate::preferences::{export_preferences, import_preferences};
+use crate::remote_backup::{
+    load_remote_backup_config, load_remote_backup_status, save_remote_backup_config,
+    upload_and_record,
+};
+use crate::ui::state::app_state::{
+    AppState, BatchImport, BusyKind, ImportMapping, ImportReviewTarget,
+};
+use crate::ui::state::errors::report_error;
+use crate::ui::state::services::AppServices;
+use crate::usecase::ports::repo::{DatasetMeta, NewDatasetMeta, RepoError};
+use crate::usecase::services::import_service::ImportService;
+use crate::{
+    build_dataset_groups, build_page_query, choose_default_dataset_id,
+    choose_next_dataset_after_delete, dataset_diff_summary, export_dataset_diff,
+    load_trash_retention_days, parse_search_history, reload_page_data_cached,
+    search_history_setting, PendingAction, QueryOptions, SearchHistoryEntry, SummarySections,
+    DEFAULT_TRASH_RETENTION_DAYS, DIVIDEND_OWNERS_KEY, SEARCH_HISTORY_KEY_PREFIX,
+    SUMMARY_SECTIONS_KEY_PREFIX,
+};
+use bom_core::holdings::DuplicateCodePolicy;
+use bom_core::owner_split::parse_owner_shares;
+use bom_core::report::parse_owner_list;
+use bom_core::PAGE_SIZE;
+
+/// Workspace actions that touch several signals and services at once: importing,
+/// switching datasets, saving staged edits and resuming whatever the save prompt held
+/// back. It is `Copy`, so event handlers capture it instead of a clone of every service.
+#[derive(Clone, Copy)]
+pub struct WorkspaceController {
+    pub state: AppState,
+    services: CopyValue<AppServices>,
+    place: WorkspacePlace,
+}
+
+/// Where a workspace is shown: its entry in [`AppServices::workspaces`], its split pane
+/// and whether its window is the main one, whose closing ends the app.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkspacePlace {
+    pub id: u64,
+    pub pane: usize,
+    pub main_window: bool,
+}
+
+impl WorkspaceController {
+    pub fn new(state: AppState, services: CopyValue<AppServices>, place: WorkspacePlace) -> Self {
+        Self {
+            state,
+            services,
+            place,
+        }
+    }
+
+    pub fn services(&self) -> AppServices {
+        self.services.read().clone()
+    }
+
+    /// Search and sort settings as currently entered in the filter bar.
+    pub fn query_options(&self) -> QueryOptions {
+        let state = &self.state;
+        QueryOptions {
+            global_search: (state.global_search)(),
+            column_search_col: (state.column_search_col)(),
+            column_search_text: (state.column_search_text)(),
+            value_filters: (state.value_filters)(),
+            column_conditions: (state.column_conditions)(),
+            sort_col: (state.sort_col)(),
+            sort_desc: (state.sort_desc)(),
+        }
+    }
+
+    /// [`Self::query_options`] without subscribing the caller to the filter bar, for work
+    /// that follows page loads rather than every keystroke.
+    pub fn peek_query_options(&self) -> QueryOptions {
+        let state = &self.state;
+        QueryOptions {
+            global_search: state.global_search.peek().clone(),
+            column_search_col: *state.column_search_col.peek(),
+            column_search_text: state.column_search_text.peek().clone(),
+            value_filters: state.value_filters.peek().clone(),
+            column_conditions: state.column_conditions.peek().clone(),
+            sort_col: *state.sort_col.peek(),
+            sort_desc: *state.sort_desc.peek(),
+        }
+    }
+
+    pub fn report(&self, context: &str, err: &anyhow::Error) {
+        report_error(self.state.status, self.state.last_error, context, err);
+    }
+
+    pub fn set_status(&mut self, message: impl Into<String>) {
+        self.state.status.set(message.into());
+    }
+
+    /// Loads `target_page` of `dataset_id` into the table, reusing the last page when the
+    /// query and the dataset are unchanged. Failures are reported under `error_context` and
+    /// leave the table as it was.
+    pub fn load_page(
+        &mut self,
+        dataset_id: Option<i64>,
+        target_page: i64,
+        options: &QueryOptions,
+        error_context: &str,
+    ) -> bool {
+        let query_service = self.services.read().query_service.clone();
+        let page_size = match self.page_size_for(dataset_id) {
+            Ok(page_size) => page_size,
+            Err(err) => {
+                self.report(error_context, &err.into());
+                return false;
+            }
+        };
+        let mut cache = self.state.page_cache.write();
+        let loaded = reload_page_data_cached(
+            &query_service,
+            &mut cache,
+            dataset_id,
+            target_page,
+            page_size,
+            options,
+        );
+        drop(cache);
+        match loaded {
+            Ok(((loaded_columns, loaded_rows, loaded_total, loaded_page), reused)) => {
+                if reused {
+                    tracing::debug!(?dataset_id, target_page, "reused cached page");
+                }
+                self.state.page_size.set(page_size);
+                self.state.columns.set(loaded_columns);
+                self.state.rows.set(Arc::new(loaded_rows));
+                self.state.total_rows.set(loaded_total);
+                self.state.page.set(loaded_page);
+                self.state.focused_cell.set(None);
+                true
+            }
+            Err(err) => {
+                self.report(error_context, &err);
+                false
+            }
+        }
+    }
+
+    /// Rows per page for `dataset_id`: every row once 載入全部 was confirmed for it,
+    /// otherwise the size chosen for it.
+    fn page_size_for(&self, dataset_id: Option<i64>) -> Result<i64, RepoError> {
+        let Some(dataset_id) = dataset_id else {
+            return Ok(PAGE_SIZE);
+        };
+        if *self.state.load_all.peek() == Some(dataset_id) {
+            return Ok(i64::MAX);
+        }
+        let query_service = self.services.read().query_service.clone();
+        let stored = run_blocking(|| query_service.load_page_size(DatasetId(dataset_id)))?;
+        Ok(stored.unwrap_or(PAGE_SIZE))
+    }
+
+    /// Saves `page_size` as the dataset's page size and reloads its first page with it.
+    pub fn set_page_size(&mut self, dataset_id: i64, page_size: i64) {
+        let query_service = self.services.read().query_service.clone();
+        let stored = (page_size != PAGE_SIZE).then_some(page_size);
+        if let Err(err) =
+            run_blocking(|| query_service.save_page_size(DatasetId(dataset_id), stored))
+        {
+            self.report("保存每頁列數失敗", &err.into());
+            return;
+        }
+        self.state.load_all.set(None);
+        let options = self.query_options();
+        self.load_page(Some(dataset_id), 0, &options, "載入資料失敗");
+    }
+
+    /// Loads every row of the dataset unpaged until another dataset or page size is chosen.
+    /// Callers confirm first when the result is large.
+    pub fn load_all_rows(&mut self, dataset_id: i64) {
+        self.state.load_all.set(Some(dataset_id));
+        let options = self.query_options();
+        if self.load_page(Some(dataset_id), 0, &options, "載入全部失敗") {
+            self.set_status(format!("已載入全部 {} 列", (self.state.total_rows)()));
+        }
+    }
+
+    /// Opens the dataset `select` picks from the dataset list. The list, the first page,
+    /// the column visibility and the holdings flags are read in one round trip; the latter
+    /// two are handed to the effects that would otherwise query them. Failures are reported
+    /// under `error_context` and leave the workspace as it was.
+    pub fn open_dataset(
+        &mut self,
+        select: impl Fn(&[DatasetMeta]) -> Option<i64>,
+        options: &QueryOptions,
+        error_context: &str,
+    ) -> bool {
+        let query_service = self.services.read().query_service.clone();
+        let show_deleted = (self.state.show_deleted)();
+        let loaded = run_blocking(|| {
+            query_service.load_dataset_context(show_deleted, |datasets| {
+                select(datasets).map(|dataset_id| build_page_query(dataset_id, 0, options))
+            })
+        });
+        let context = match loaded {
+            Ok(context) => context,
+            Err(err) => {
+                self.report(error_context, &err.into());
+                return false;
+            }
+        };
+        let selected = context.page.as_ref().map(|(dataset_id, _)| dataset_id.0);
+        {
+            let mut prefetched = self.state.prefetched.write();
+            prefetched.column_visibility = selected.map(|id| (id, context.column_visibility));
+            prefetched.holdings_flags = Some(context.holdings_flags);
+        }
+        self.select_in_groups(&context.datasets, selected);
+        self.state.datasets.set(context.datasets);
+        let (columns, rows, total_rows) = context
+            .page
+            .map(|(_, page)| (page.columns, page.rows, page.total_rows))
+            .unwrap_or_default();
+        self.state.columns.set(columns);
+        self.state.rows.set(Arc::new(rows));
+        self.state.total_rows.set(total_rows);
+        self.state.page.set(0);
+        self.state.page_size.set(PAGE_SIZE);
+        self.state.focused_cell.set(None);
+        // The first page was read at the default size before the dataset was known.
+        if self
+            .page_size_for(selected)
+            .is_ok_and(|page_size| page_size != PAGE_SIZE)
+        {
+            self.load_page(selected, 0, options, error_context);
+        }
+        true
+    }
+
+    /// Re-runs the current search on the selected dataset.
+    pub fn apply_filters(&mut self, error_context: &str) {
+        let Some(dataset_id) = (self.state.selected_dataset_id)() else {
+            return;
+        };
+        self.state.busy.write().begin(BusyKind::Query);
+        let options = self.query_options();
+        self.load_page(Some(dataset_id), 0, &options, error_context);
+        self.state.busy.write().end(BusyKind::Query);
+    }
+
+    pub fn list_datasets(&self) -> Result<Vec<DatasetMeta>, RepoError> {
+        let query_service = self.services.read().query_service.clone();
+        let show_deleted = (self.state.show_deleted)();
+        run_blocking(|| query_service.list_datasets(show_deleted))
+    }
+
+    /// Reloads the dataset list after another page renamed or deleted datasets, moving the
+    /// selection to a default dataset when the selected one is gone.
+    pub fn refresh_datasets(&mut self) {
+        let available = match self.list_datasets() {
+            Ok(available) => available,
+            Err(err) => {
+                self.report("載入資料集失敗", &err.into());
+                return;
+            }
+        };
+        let selected = *self.state.selected_dataset_id.peek();
+        if selected.is_some_and(|id| available.iter().any(|dataset| dataset.id.0 == id)) {
+            self.state.datasets.set(available);
+            return;
+        }
+        let next_dataset = build_dataset_groups(&available)
+            .first()
+            .and_then(|group| choose_default_dataset_id(&group.datasets));
+        self.select_in_groups(&available, next_dataset);
+        self.state.datasets.set(available);
+        self.load_page(next_dataset, 0, &QueryOptions::default(), "載入資料集失敗");
+    }
+
+    /// Selects `dataset_id` and the dataset group that contains it.
+    pub fn select_in_groups(&mut self, available: &[DatasetMeta], dataset_id: Option<i64>) {
+        let groups = build_dataset_groups(available);
+        let group_key = dataset_id.and_then(|id| {
+            groups
+                .iter()
+                .find(|group| group.datasets.iter().any(|dataset| dataset.id.0 == id))
+                .map(|group| group.key.clone())
+        });
+        self.state.selected_group_key.set(group_key);
+        self.state.selected_dataset_id.set(dataset_id);
+    }
+
+    /// Clears the column search, sort and page before showing a different dataset.
+    pub fn reset_query(&mut self) {
+        self.state.column_search_col.set(None);
+        self.state.column_search_text.set(String::new());
+        self.state.value_filters.set(BTreeMap::new());
+        self.state.column_conditions.set(BTreeMap::new());
+        self.state.sort_col.set(None);
+        self.state.sort_desc.set(false);
+        self.state.page.set(0);
+    }
+
+    /// Drops every staged cell, added row and deletion mark.
+    pub fn discard_edits(&mut self) {
+        self.state.staged_cells.write().clear();
+        self.state.deleted_rows.write().clear();
+        self.state.selected_rows.write().clear();
+        self.state.added_rows.write().clear();
+        self.state.editing_cell.set(None);
+        self.state.editing_value.set(String::new());
+        self.state.show_add_row.set(false);
+        self.state.new_row_inputs.write().clear();
+    }
+
+    /// Whether the table holds staged cells, added rows or deletion marks.
+    pub fn has_pending_changes(&self) -> bool {
+        !self.state.staged_cells.peek().is_empty()
+            || !self.state.deleted_rows.peek().is_empty()
+            || !self.state.added_rows.peek().is_empty()
+    }
+
+    /// Runs `action` now, or holds it back behind the 覆蓋/另存/取消 prompt while the table
+    /// has unsaved edits. Every path that would drop staged edits goes through here.
+    pub fn guard_pending_changes(&mut self, action: PendingAction) {
+        if self.has_pending_changes() {
+            self.state.pending_action.set(Some(action));
+            self.state.show_save_prompt.set(true);
+            return;
+        }
+        self.run_action(action);
+    }
+
+    /// Handles a request to close this workspace's window; `true` keeps the window open for
+    /// now. A pane with unsaved edits asks about them first and the last pane to settle
+    /// closes the window. The main window takes the other windows with it, which ends the
+    /// app, after asking about their unsaved edits.
+    pub fn request_close(&mut self) -> bool {
+        if self.has_pending_changes() {
+            self.guard_pending_changes(PendingAction::CloseWindow);
+            return true;
+        }
+        let workspaces = self.services.read().workspaces.clone();
+        if !workspaces
+            .unsaved_elsewhere(self.place.id, Some(current_window_id()))
+            .is_empty()
+        {
+            return true;
+        }
+        if !self.place.main_window || self.place.pane != 0 {
+            return false;
+        }
+        if !confirm_discarding(&workspaces.unsaved_elsewhere(self.place.id, None)) {
+            return true;
+        }
+        close_other_windows(workspaces.windows());
+        false
+    }
+
+    /// 結束: asks whether to drop the unsaved edits of other panes and windows, then this
+    /// workspace's own through the save prompt, and closes every window.
+    pub fn request_quit(&mut self) {
+        let workspaces = self.services.read().workspaces.clone();
+        if confirm_discarding(&workspaces.unsaved_elsewhere(self.place.id, None)) {
+            self.guard_pending_changes(PendingAction::Quit);
+        }
+    }
+
+    /// Closes the window once no other pane in it holds unsaved edits; the last pane to
+    /// settle closes it. Closing the main window ends the app.
+    fn finish_close(&mut self) {
+        let workspaces = self.services.read().workspaces.clone();
+        let window = current_window_id();
+        if !workspaces
+            .unsaved_elsewhere(self.place.id, Some(window))
+            .is_empty()
+        {
+            return;
+        }
+        if !self.place.main_window {
+            close_window();
+        } else if confirm_discarding(&workspaces.unsaved_elsewhere(self.place.id, None)) {
+            quit_app(workspaces.windows());
+        }
+    }
+
+    /// Shows another dataset group, starting from its default sheet.
+    pub fn change_dataset(&mut self, next_group: Option<String>, next_dataset: Option<i64>) {
+        self.state.selected_group_key.set(next_group);
+        self.state.selected_dataset_id.set(next_dataset);
+        self.reset_query();
+        self.state.busy.write().begin(BusyKind::Query);
+        let options = self.query_options();
+        if self.open_dataset(|_| next_dataset, &options, "載入資料集失敗") {
+            self.set_status("已切換資料集");
+        }
+        self.state.busy.write().end(BusyKind::Query);
+    }
+
+    /// Shows another sheet of the current dataset group.
+    pub fn switch_sheet(&mut self, dataset_id: Option<i64>) {
+        self.state.selected_dataset_id.set(dataset_id);
+        self.state.page.set(0);
+        self.state.busy.write().begin(BusyKind::Query);
+        if self.open_dataset(|_| dataset_id, &QueryOptions::default(), "切換工作表失敗") {
+            self.set_status("已切換工作表");
+        }
+        self.state.busy.write().end(BusyKind::Query);
+    }
+
+    /// Imports `file_path`, then shows the first dataset it created. A CSV that fits a
+    /// saved import profile is read through it; one whose headers fit no dataset type opens
+    /// the column mapping dialog instead. A workbook listing a 代號 more than once is held
+    /// in [`AppState::import_review`] until the user picks how to import it.
+    pub fn import_file(&mut self, file_path: &Path) {
+        let import_service = self.services.read().import_service.clone();
+        let profile = run_blocking(|| import_service.matching_profile(file_path));
+        if profile.is_none() && self.has_unknown_headers(file_path) {
+            self.open_import_mapping(file_path, true);
+            return;
+        }
+        if profile.is_none() {
+            match run_blocking(|| import_service.validate(file_path)) {
+                Ok(validation) if !validation.is_clean() => {
+                    self.set_status(format!(
+                        "{} 有 {} 個重複的代號，請選擇匯入方式",
+                        file_path.display(),
+                        validation.duplicate_codes.len()
+                    ));
+                    self.state.import_review.set(Some((
+                        ImportReviewTarget::File(file_path.to_path_buf()),
+                        validation,
+                    )));
+                    return;
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    self.report("檢查匯入檔案失敗", &err.into());
+                    return;
+                }
+            }
+        }
+        self.run_import(file_path, profile, DuplicateCodePolicy::default());
+    }
+
+    /// Imports or re-transforms what is held for review, with its duplicate 代號 handled
+    /// as chosen.
+    pub fn import_reviewed(&mut self, duplicate_codes: DuplicateCodePolicy) {
+        let Some((target, _)) = (self.state.import_review)() else {
+            return;
+        };
+        self.state.import_review.set(None);
+        match target {
+            ImportReviewTarget::File(file_path) => {
+                self.run_import(&file_path, None, duplicate_codes)
+            }
+            ImportReviewTarget::Retransform { dataset_id, .. } => {
+                self.run_retransform(dataset_id, duplicate_codes)
+            }
+        }
+    }
+
+    pub fn cancel_import_review(&mut self) {
+        let retransform = matches!(
+            (self.state.import_review)(),
+            Some((ImportReviewTarget::Retransform { .. }, _))
+        );
+        self.state.import_review.set(None);
+        self.set_status(if retransform {
+            "已取消重新轉換"
+        } else {
+            "已取消匯入"
+        });
+    }
+
+    /// Runs the workbook transform again on the sheets stored with `dataset_id`, replacing
+    /// its cells. Stored sheets that list a 代號 more than once wait in
+    /// [`AppState::import_review`] until the user picks how to handle them.
+    pub fn retransform_dataset(&mut self, dataset_id: i64) {
+        let import_service = self.services.read().import_service.clone();
+        match run_blocking(|| import_service.validate_sources(DatasetId(dataset_id))) {
+            Ok(validation) if !validation.is_clean() => {
+                let name = (self.state.datasets)()
+                    .iter()
+                    .find(|dataset| dataset.id.0 == dataset_id)
+                    .map(|dataset| dataset.name.clone())
+                    .unwrap_or_default();
+                self.set_status(format!(
+                    "{name} 有 {} 個重複的代號，請選擇轉換方式",
+                    validation.duplicate_codes.len()
+                ));
+                self.state.import_review.set(Some((
+                    ImportReviewTarget::Retransform { dataset_id, name },
+                    validation,
+                )));
+            }
+            Ok(_) => self.run_retransform(dataset_id, DuplicateCodePolicy::default()),
+            Err(err) => self.report("重新轉換失敗", &err.into()),
+        }
+    }
+
+    fn run_retransform(&mut self, dataset_id: i64, duplicate_codes: DuplicateCodePolicy) {
+        let import_service = self.services.read().import_service.clone();
+        self.state.busy.write().begin(BusyKind::Import);
+        match run_blocking(|| import_service.retransform(DatasetId(dataset_id), duplicate_codes)) {
+            Ok(result) => {
+                if let Ok(available) = self.list_datasets() {
+                    self.state.datasets.set(available);
+                }
+                // Staged edits of the old cells no longer point at the right rows.
+                if *self.state.selected_dataset_id.peek() == Some(dataset_id) {
+                    self.discard_edits();
+                    let options = self.query_options();
+                    self.open_dataset(
+                        |_| Some(dataset_id),
+                        &options,
+                        "重新轉換成功，但載入資料失敗",
+                    );
+                }
+                self.set_status(format!("已重新轉換（{} 筆）", result.row_count));
+            }
+            Err(err) => self.report("重新轉換失敗", &err.into()),
+        }
+        self.state.busy.write().end(BusyKind::Import);
+    }
+
+    /// Imports `file_path` with its columns as they are, skipping the mapping dialog.
+    pub fn import_unmapped(&mut self, file_path: &Path) {
+        self.run_import(file_path, None, DuplicateCodePolicy::default());
+    }
+
+    /// Whether `file_path` is a CSV whose headers contain the required columns of no
+    /// dataset type. Files whose headers can't be read are left for the import to report.
+    fn has_unknown_headers(&self, file_path: &Path) -> bool {
+        let is_csv = file_path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        if !is_csv {
+            return false;
+        }
+        let Ok(headers) = csv_headers(file_path, "utf-8", 0) else {
+            return false;
+        };
+        let query_service = self.services.read().query_service.clone();
+        let known = run_blocking(|| {
+            [DatasetKind::Assets, DatasetKind::Holdings]
+                .into_iter()
+                .map(|kind| query_service.load_column_set(kind))
+                .collect::<Result<Vec<_>, _>>()
+        });
+        match known {
+            Ok(sets) => !sets.iter().any(|set| set.matches(&headers)),
+            Err(err) => {
+                tracing::warn!(error = %err, "failed to load column sets");
+                false
+            }
+        }
+    }
+
+    /// Opens the column mapping dialog for `file_path`, with the holdings columns matched
+    /// to the most similar headers.
+    pub fn open_import_mapping(&mut self, file_path: &Path, unknown_headers: bool) {
+        let Some((draft, saved)) = self.prepare_mapping_import(file_path) else {
+            return;
+        };
+        if unknown_headers {
+            self.set_status(format!(
+                "{} 的欄位不符合任何資料集類型，請設定欄位對應",
+                file_path.display()
+            ));
+        }
+        self.state.import_mapping.set(Some(ImportMapping {
+            file_path: file_path.to_path_buf(),
+            draft,
+            saved,
+            unknown_headers,
+        }));
+    }
+
+    /// Saves `profile` under its name, then imports `file_path` through it.
+    pub fn import_with_profile(&mut self, file_path: &Path, profile: ImportProfile) {
+        if self.save_import_profile(&profile) {
+            self.run_import(file_path, Some(profile), DuplicateCodePolicy::default());
+        }
+    }
+
+    pub fn save_import_profile(&mut self, profile: &ImportProfile) -> bool {
+        let query_service = self.services.read().query_service.clone();
+        match run_blocking(|| query_service.save_import_profile(profile.clone())) {
+            Ok(()) => true,
+            Err(err) => {
+                self.report("保存匯入對應設定失敗", &err.into());
+                false
+            }
+        }
+    }
+
+    /// Starting point of the import mapping dialog for `file_path`: the saved profile that
+    /// fits it, or one mapping each holdings column to the most similar header, plus every
+    /// saved profile.
+    pub fn prepare_mapping_import(
+        &mut self,
+        file_path: &Path,
+    ) -> Option<(ImportProfile, Vec<ImportProfile>)> {
+        let services = self.services();
+        let result = run_blocking(|| {
+            let saved = services.query_service.load_import_profiles()?;
+            if let Some(profile) = services.import_service.matching_profile(file_path) {
+                return Ok((profile, saved));
+            }
+            let headers = csv_headers(file_path, "utf-8", 0).unwrap_or_default();
+            let holdings = services
+                .query_service
+                .load_column_set(DatasetKind::Holdings)?;
+            let columns = suggest_column_mappings(&headers, &holdings.required);
+            let name = file_path
+                .file_stem()
+                .and_then(|name| name.to_str())
+                .unwrap_or_default()
+                .to_string();
+            Ok::<_, anyhow::Error>((
+                ImportProfile {
+                    name,
+                    header_row: 0,
+                    encoding: "utf-8".to_string(),
+                    columns,
+                },
+                saved,
+            ))
+        });
+        match result {
+            Ok(draft) => Some(draft),
+            Err(err) => {
+                self.report("讀取匯入對應設定失敗", &err);
+                None
+            }
+        }
+    }
+
+    pub fn delete_import_profile(&mut self, name: &str) {
+        let query_service = self.services.read().query_service.clone();
+        match run_blocking(|| query_service.delete_import_profile(name.to_string())) {
+            Ok(()) => self.set_status(format!("已刪除匯入對應設定「{name}」")),
+            Err(err) => self.report("刪除匯入對應設定失敗", &err.into()),
+        }
+    }
+
+    fn run_import(
+        &mut self,
+        file_path: &Path,
+        profile: Option<ImportProfile>,
+        duplicate_codes: DuplicateCodePolicy,
+    ) {
+        let import_service = self.services.read().import_service.clone();
+        let profile_name = profile.as_ref().map(|profile| profile.name.clone());
+        self.state.busy.write().begin(BusyKind::Import);
+        self.set_status(format!("正在匯入 {}", file_path.display()));
+        let imported = run_blocking(|| {
+            import_and_summarize(&import_service, file_path, profile, duplicate_codes)
+        });
+        match imported {
+            Ok((selected_id, imported_count, is_workbook)) => match self.list_datasets() {
+                Ok(available) => {
+                    self.select_in_groups(&available, selected_id);
+                    self.state.datasets.set(available);
+                    self.reset_query();
+                    if self.load_page(
+                        selected_id,
+                        0,
+                        &QueryOptions::default(),
+                        "匯入成功，但載入資料失敗",
+                    ) {
+                        self.set_status(match (is_workbook, profile_name) {
+                            (true, _) => format!("已匯入 XLSX，共 {imported_count} 個資料表"),
+                            (false, Some(name)) => {
+                                format!("已依對應設定「{name}」匯入 CSV（{imported_count} 筆）")
+                            }
+                            (false, None) => format!("已匯入 CSV（{imported_count} 筆）"),
+                        });
+                    }
+                }
+                Err(err) => self.report("匯入成功，但刷新資料集失敗", &err.into()),
+            },
+            Err(err) => self.report("匯入失敗", &err),
+        }
+        self.state.busy.write().end(BusyKind::Import);
+        notify_if_unfocused("BOM 匯入", &(self.state.status)());
+    }
+
+    /// Imports every file in `folder` an importer accepts, one after another, recording
+    /// each outcome in [`AppState::batch_import`]. Nothing stops the batch to ask: CSVs that
+    /// fit a saved import profile are read through it and duplicate 代號 are kept as-is.
+    pub fn import_folder(&mut self, folder: &Path) {
+        let import_service = self.services.read().import_service.clone();
+        let files = match run_blocking(|| import_service.importable_files(folder)) {
+            Ok(files) if files.is_empty() => {
+                self.set_status(format!("{} 中沒有可匯入的檔案", folder.display()));
+                return;
+            }
+            Ok(files) => files,
+            Err(err) => {
+                self.report("讀取資料夾失敗", &err.into());
+                return;
+            }
+        };
+        self.state.batch_import.set(Some(BatchImport {
+            folder: folder.to_path_buf(),
+            files: files.clone(),
+            results: Vec::new(),
+        }));
+        self.state.busy.write().begin(BusyKind::Import);
+        let mut controller = *self;
+        spawn(async move {
+            let mut first_id = None;
+            for (idx, file_path) in files.iter().enumerate() {
+                controller.set_status(format!(
+                    "正在匯入 {}（{}/{}）",
+                    file_path.display(),
+                    idx + 1,
+                    files.len()
+                ));
+                yield_to_renderer().await;
+                let imported = run_blocking(|| {
+                    let profile = import_service.matching_profile(file_path);
+                    import_and_summarize(
+                        &import_service,
+                        file_path,
+                        profile,
+                        DuplicateCodePolicy::default(),
+                    )
+                });
+                let result = match imported {
+                    Ok((selected_id, count, is_workbook)) => {
+                        first_id = first_id.or(selected_id);
+                        Ok(if is_workbook {
+                            format!("{count} 個資料表")
+                        } else {
+                            format!("{count} 筆")
+                        })
+                    }
+                    Err(err) => {
+                        tracing::warn!(path = %file_path.display(), error = %err, "batch import failed");
+                        Err(format!("{err:#}"))
+                    }
+                };
+                if let Some(batch) = controller.state.batch_import.write().as_mut() {
+                    batch.results.push(result);
+                }
+            }
+            controller.finish_batch_import(first_id);
+        });
+    }
+
+    fn finish_batch_import(&mut self, first_id: Option<i64>) {
+        let (total, failed) = (self.state.batch_import)()
+            .map(|batch| (batch.files.len(), batch.failed()))
+            .unwrap_or_default();
+        if first_id.is_some() {
+            match self.list_datasets() {
+                Ok(available) => {
+                    self.select_in_groups(&available, first_id);
+                    self.state.datasets.set(available);
+                    self.reset_query();
+                    self.load_page(
+                        first_id,
+                        0,
+                        &QueryOptions::default(),
+                        "匯入成功，但載入資料失敗",
+                    );
+                }
+                Err(err) => self.report("匯入成功，但刷新資料集失敗", &err.into()),
+            }
+        }
+        self.set_status(if failed == 0 {
+            format!("已匯入資料夾中的 {total} 個檔案")
+        } else {
+            format!("已匯入 {}/{total} 個檔案，{failed} 個失敗", total - failed)
+        });
+        self.state.busy.write().end(BusyKind::Import);
+        notify_if_unfocused("BOM 匯入", &(self.state.status)());
+    }
+
+    pub fn export_preferences(&mut self, path: &Path) {
+        let query_service = self.services.read().query_service.clone();
+        let result = run_blocking(|| {
+            let document = export_preferences(&query_service)?;
+            std::fs::write(path, serde_json::to_string_pretty(&document)?)
+                .with_context(|| format!("failed to write {}", path.display()))
+        });
+        match result {
+            Ok(()) => self.set_status(format!("已匯出設定至 {}", path.display())),
+            Err(err) => self.report("匯出設定失敗", &err),
+        }
+    }
+
+    /// Applies a preferences file and tells every pane and the main window to reload.
+    pub fn import_preferences(&mut self, path: &Path) {
+        let services = self.services();
+        let result = run_blocking(|| {
+            let text = std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            let document = serde_json::from_str(&text).context("設定檔不是有效的 JSON")?;
+            import_preferences(&services.query_service, &document)
+        });
+        match result {
+            Ok(summary) => {
+                services.events.publish(DomainEvent::PreferencesImported);
+                self.set_status(format!(
+                    "已匯入設定：{} 項設定、{} 個資料集",
+                    summary.settings, summary.datasets
+                ));
+            }
+            Err(err) => self.report("匯入設定失敗", &err),
+        }
+    }
+
+    /// Writes every row of `dataset_id` matching the current search, filters and sort to
+    /// an XLSX workbook at `path`, with the columns `visibility` shows.
+    pub fn export_xlsx(&mut self, dataset_id: i64, path: &Path, visibility: BTreeMap<i64, bool>) {
+        let export_service = self.services.read().export_service.clone();
+        let query = build_page_query(dataset_id, 0, &self.peek_query_options());
+        self.state.busy.write().begin(BusyKind::Query);
+        match run_blocking(|| export_service.export_xlsx(query, &visibility, path)) {
+            Ok(count) => self.set_status(format!("已匯出 {count} 筆至 {}", path.display())),
+            Err(err) => self.report("匯出 Excel 失敗", &err),
+        }
+        self.state.busy.write().end(BusyKind::Query);
+    }
+
+    /// Writes every row and column of `dataset_id` to a CSV file at `path`, separated and
+    /// encoded as `options` say.
+    pub fn export_dataset_csv(&mut self, dataset_id: i64, path: &Path, options: CsvOptions) {
+        let export_service = self.services.read().export_service.clone();
+        let query = build_page_query(dataset_id, 0, &QueryOptions::default());
+        self.state.busy.write().begin(BusyKind::Query);
+        match run_blocking(|| export_service.export_csv(query, &BTreeMap::new(), &options, path)) {
+            Ok(count) => self.set_status(format!("已匯出 {count} 筆至 {}", path.display())),
+            Err(err) => self.report("匯出 CSV 失敗", &err),
+        }
+        self.state.busy.write().end(BusyKind::Query);
+    }
+
+    /// Writes `dataset_id` to a `.bomdata` bundle at `path`.
+    pub fn export_bundle(&mut self, dataset_id: i64, path: &Path) {
+        let query_service = self.services.read().query_service.clone();
+        match run_blocking(|| export_bundle(&query_service, DatasetId(dataset_id), path)) {
+            Ok(()) => self.set_status(format!("已匯出資料集封裝至 {}", path.display())),
+            Err(err) => self.report("匯出資料集封裝失敗", &err),
+        }
+    }
+
+    /// Adds the dataset in the bundle at `path` and opens it.
+    pub fn import_bundle(&mut self, path: &Path) {
+        let services = self.services();
+        self.state.busy.write().begin(BusyKind::Import);
+        let result =
+            run_blocking(|| import_bundle(&services.query_service, &services.edit_service, path));
+        match result {
+            Ok(summary) => match self.list_datasets() {
+                Ok(available) => {
+                    let dataset_id = Some(summary.dataset_id.0);
+                    self.select_in_groups(&available, dataset_id);
+                    self.state.datasets.set(available);
+                    self.reset_query();
+                    if self.load_page(
+                        dataset_id,
+                        0,
+                        &QueryOptions::default(),
+                        "已匯入資料集封裝，但載入資料失敗",
+                    ) {
+                        self.set_status(format!(
+                            "已匯入資料集封裝：{}（{} 筆）",
+                            summary.name, summary.row_count
+                        ));
+                    }
+                }
+                Err(err) => self.report("已匯入資料集封裝，但刷新資料集失敗", &err.into()),
+            },
+            Err(err) => self.report("匯入資料集封裝失敗", &err),
+        }
+        self.state.busy.write().end(BusyKind::Import);
+    }
+
+    /// Adds the sample 資產總表 / 持股股息總表 datasets and opens the first one.
+    pub fn load_demo_data(&mut self) {
+        let edit_service = self.services.read().edit_service.clone();
+        self.state.busy.write().begin(BusyKind::Import);
+        match run_blocking(|| create_demo_datasets(&edit_service)) {
+            Ok(ids) => match self.list_datasets() {
+                Ok(available) => {
+                    let first_id = ids.first().map(|id| id.0);
+                    self.select_in_groups(&available, first_id);
+                    self.state.datasets.set(available);
+                    self.reset_query();
+                    if self.load_page(
+                        first_id,
+                        0,
+                        &QueryOptions::default(),
+                        "已載入示範資料，但載入資料失敗",
+                    ) {
+                        self.set_status(format!("已載入示範資料，共 {} 個資料表", ids.len()));
+                    }
+                }
+                Err(err) => self.report("已載入示範資料，但刷新資料集失敗", &err.into()),
+            },
+            Err(err) => self.report("載入示範資料失敗", &err.into()),
+        }
+        self.state.busy.write().end(BusyKind::Import);
+    }
+
+    /// Closes the year of `dataset_id`'s month columns after confirmation: the result
+    /// opens as the live dataset and the original is kept, renamed, as the year's archive.
+    pub fn roll_over_dividend_year(&mut self, dataset_id: i64) {
+        let headers = (self.state.columns)();
+        let Some(year) = open_dividend_year(&headers) else {
+            self.set_status("此資料表沒有年度配息欄位，無法年度結轉");
+            return;
+        };
+        let mut description = format!(
+            "將 {year}年 1～12 月的配息存入「{year}年」欄位並累加至去年度累積，月份歸零。\n\
+             原資料表會改名保留。"
+        );
+        if year >= chrono::Local::now().year() {
+            description.push_str(&format!("\n{year}年 尚未結束，確定要結轉嗎？"));
+        }
+        let confirmed = MessageDialog::new()
+            .set_level(MessageLevel::Warning)
+            .set_title("年度結轉")
+            .set_description(description)
+            .set_buttons(MessageButtons::YesNo)
+            .show();
+        if confirmed != MessageDialogResult::Yes {
+            return;
+        }
+
+        let services = self.services.read().clone();
+        self.state.busy.write().begin(BusyKind::Save);
+        match run_blocking(|| {
+            roll_over_dividend_dataset(
+                &services.query_service,
+                &services.edit_service,
+                DatasetId(dataset_id),
+            )
+        }) {
+            Ok((new_id, closed_year)) => match self.list_datasets() {
+                Ok(available) => {
+                    self.select_in_groups(&available, Some(new_id.0));
+                    self.state.datasets.set(available);
+                    self.reset_query();
+                    if self.load_page(
+                        Some(new_id.0),
+                        0,
+                        &QueryOptions::default(),
+                        "已完成年度結轉，但載入資料失敗",
+                    ) {
+                        self.set_status(format!("已結轉 {closed_year}年 配息，原資料表已封存"));
+                    }
+                }
+                Err(err) => self.report("已完成年度結轉，但刷新資料集失敗", &err.into()),
+            },
+            Err(err) => self.report("年度結轉失敗", &err),
+        }
+        self.state.busy.write().end(BusyKind::Save);
+    }
+
+    /// Owners listed first, in this order, in the summary report and the workbook summary.
+    pub fn load_dividend_owners(&self) -> Vec<String> {
+        let query_service = self.services.read().query_service.clone();
+        match run_blocking(|| query_service.load_setting(DIVIDEND_OWNERS_KEY)) {
+            Ok(value) => parse_owner_list(value.as_deref().unwrap_or_default()),
+            Err(err) => {
+                self.report("讀取所有權人設定失敗", &err.into());
+                Vec::new()
+            }
+        }
+    }
+
+    pub fn save_dividend_owners(&mut self, owners: &[String]) -> bool {
+        let query_service = self.services.read().query_service.clone();
+        match run_blocking(|| query_service.save_setting(DIVIDEND_OWNERS_KEY, &owners.join("\n"))) {
+            Ok(()) => {
+                self.set_status("已保存所有權人設定");
+                true
+            }
+            Err(err) => {
+                self.report("保存所有權人設定失敗", &err.into());
+                false
+            }
+        }
+    }
+
+    /// Summary sections last checked for `group_key`; all of them when none were saved.
+    pub fn load_summary_sections(&self, group_key: &str) -> SummarySections {
+        let query_service = self.services.read().query_service.clone();
+        let key = format!("{SUMMARY_SECTIONS_KEY_PREFIX}{group_key}");
+        match run_blocking(|| query_service.load_setting(&key)) {
+            Ok(value) => value
+                .map(|value| SummarySections::parse_setting(&value))
+                .unwrap_or_default(),
+            Err(err) => {
+                self.report("讀取總結報表區段設定失敗", &err.into());
+                SummarySections::default()
+            }
+        }
+    }
+
+    pub fn save_summary_sections(&self, group_key: &str, sections: SummarySections) {
+        let query_service = self.services.read().query_service.clone();
+        let key = format!("{SUMMARY_SECTIONS_KEY_PREFIX}{group_key}");
+        if let Err(err) = run_blocking(|| query_service.save_setting(&key, &sections.to_setting()))
+        {
+            self.report("保存總結報表區段設定失敗", &err.into());
+        }
+    }
+
+    pub fn load_computed_columns(&self, dataset_id: i64) -> Vec<ComputedColumn> {
+        let query_service = self.services.read().query_service.clone();
+        match run_blocking(|| query_service.load_computed_columns(DatasetId(dataset_id))) {
+            Ok(columns) => columns,
+            Err(err) => {
+                self.report("讀取計算欄位失敗", &err.into());
+                Vec::new()
+            }
+        }
+    }
+
+    /// Checks `computed` against the stored columns of `dataset_id`, saves it and reloads
+    /// the table. A search or sort on a computed column that no longer exists is cleared.
+    pub fn save_computed_columns(
+        &mut self,
+        dataset_id: i64,
+        computed: Vec<ComputedColumn>,
+    ) -> bool {
+        let query_service = self.services.read().query_service.clone();
+        let previous = self.load_computed_columns(dataset_id);
+        let columns = (self.state.columns)();
+        let stored = &columns[..columns.len().saturating_sub(previous.len())];
+        let result = validate_computed_columns(stored, &computed).and_then(|()| {
+            run_blocking(|| {
+                query_service
+                    .save_computed_columns(DatasetId(dataset_id), computed.clone())
+                    .map_err(anyhow::Error::from)
+            })
+        });
+        if let Err(err) = result {
+            self.report("保存計算欄位失敗", &err);
+            return false;
+        }
+        let width = (stored.len() + computed.len()) as i64;
+        if (self.state.sort_col)().is_some_and(|idx| idx >= width) {
+            self.state.sort_col.set(None);
+            self.state.sort_desc.set(false);
+        }
+        if (self.state.column_search_col)().is_some_and(|idx| idx >= width) {
+            self.state.column_search_col.set(None);
+            self.state.column_search_text.set(String::new());
+        }
+        self.apply_filters("重新載入資料失敗");
+        self.set_status(format!("已更新計算欄位（{} 個）", computed.len()));
+        true
+    }
+
+    /// Owner shares of the co-owned rows of `dataset_id`.
+    pub fn load_owner_splits(&self, dataset_id: i64) -> OwnerSplits {
+        let query_service = self.services.read().query_service.clone();
+        match run_blocking(|| query_service.load_owner_splits(DatasetId(dataset_id))) {
+            Ok(splits) => splits,
+            Err(err) => {
+                self.report("讀取所有權分配失敗", &err.into());
+                OwnerSplits::new()
+            }
+        }
+    }
+
+    /// Cells the last save of `dataset_id` changed.
+    pub fn load_saved_changes(&self, dataset_id: i64) -> SavedChanges {
+        let query_service = self.services.read().query_service.clone();
+        match run_blocking(|| query_service.load_saved_changes(DatasetId(dataset_id))) {
+            Ok(changes) => changes,
+            Err(err) => {
+                self.report("讀取上次儲存的變更失敗", &err.into());
+                SavedChanges::new()
+            }
+        }
+    }
+
+    /// Where the imported cells of `dataset_id` came from.
+    pub fn load_cell_origins(&self, dataset_id: i64) -> CellOrigins {
+        let query_service = self.services.read().query_service.clone();
+        match run_blocking(|| query_service.load_cell_origins(DatasetId(dataset_id))) {
+            Ok(origins) => origins,
+            Err(err) => {
+                self.report("讀取匯入來源失敗", &err.into());
+                CellOrigins::new()
+            }
+        }
+    }
+
+    /// Stored prices of `codes`.
+    pub fn load_price_history(&self, codes: Vec<String>) -> PriceHistory {
+        let query_service = self.services.read().query_service.clone();
+        match run_blocking(|| query_service.load_price_history(codes)) {
+            Ok(history) => history,
+            Err(err) => {
+                self.report("讀取價格紀錄失敗", &err.into());
+                PriceHistory::new()
+            }
+        }
+    }
+
+    pub fn load_import_history(&self) -> Vec<ImportRecord> {
+        let query_service = self.services.read().query_service.clone();
+        match run_blocking(|| query_service.load_import_history()) {
+            Ok(history) => history,
+            Err(err) => {
+                self.report("讀取匯入紀錄失敗", &err.into());
+                Vec::new()
+            }
+        }
+    }
+
+    /// Parses `text` with [`parse_owner_shares`] and saves it as the shares of `row_idx`.
+    /// Returns the saved shares, or `None` once the failure is reported.
+    pub fn save_owner_shares(
+        &mut self,
+        dataset_id: i64,
+        row_idx: usize,
+        text: &str,
+    ) -> Option<Vec<OwnerShare>> {
+        let query_service = self.services.read().query_service.clone();
+        let result = parse_owner_shares(text).and_then(|shares| {
+            run_blocking(|| {
+                query_service
+                    .save_owner_shares(DatasetId(dataset_id), row_idx, shares.clone())
+                    .map_err(anyhow::Error::from)
+            })
+            .map(|()| shares)
+        });
+        match result {
+            Ok(shares) => {
+                self.set_status(if shares.is_empty() {
+                    format!("已取消第 {} 列的所有權分配", row_idx + 1)
+                } else {
+                    format!("已更新第 {} 列的所有權分配", row_idx + 1)
+                });
+                Some(shares)
+            }
+            Err(err) => {
+                self.report("保存所有權分配失敗", &err);
+                None
+            }
+        }
+    }
+
+    /// Global search terms remembered for `dataset_id`, pinned ones first.
+    pub fn load_search_history(&self, dataset_id: i64) -> Vec<SearchHistoryEntry> {
+        let query_service = self.services.read().query_service.clone();
+        let key = format!("{SEARCH_HISTORY_KEY_PREFIX}{dataset_id}");
+        match run_blocking(|| query_service.load_setting(&key)) {
+            Ok(value) => parse_search_history(&value.unwrap_or_default()),
+            Err(err) => {
+                self.report("讀取搜尋紀錄失敗", &err.into());
+                Vec::new()
+            }
+        }
+    }
+
+    pub fn save_search_history(&self, dataset_id: i64, entries: &[SearchHistoryEntry]) {
+        let query_service = self.services.read().query_service.clone();
+        let key = format!("{SEARCH_HISTORY_KEY_PREFIX}{dataset_id}");
+        let value = search_history_setting(entries);
+        if let Err(err) = run_blocking(|| query_service.save_setting(&key, &value)) {
+            self.report("保存搜尋紀錄失敗", &err.into());
+        }
+    }
+
+    /// Reports saved from the report builder, by name.
+    pub fn load_saved_reports(&self) -> Vec<SavedReport> {
+        let query_service = self.services.read().query_service.clone();
+        match run_blocking(|| query_service.load_saved_reports()) {
+            Ok(reports) => reports,
+            Err(err) => {
+                self.report("讀取已存報表失敗", &err.into());
+                Vec::new()
+            }
+        }
+    }
+
+    /// Saves `report`, replacing any saved report with the same name.
+    pub fn save_saved_report(&mut self, report: SavedReport) -> bool {
+        let query_service = self.services.read().query_service.clone();
+        let name = report.name.clone();
+        match run_blocking(|| query_service.save_saved_report(report)) {
+            Ok(()) => {
+                self.set_status(format!("已儲存報表：{name}"));
+                true
+            }
+            Err(err) => {
+                self.report("儲存報表失敗", &err.into());
+                false
+            }
+        }
+    }
+
+    pub fn delete_saved_report(&mut self, name: &str) -> bool {
+        let query_service = self.services.read().query_service.clone();
+        match run_blocking(|| query_service.delete_saved_report(name.to_string())) {
+            Ok(()) => {
+                self.set_status(format!("已刪除報表：{name}"));
+                true
+            }
+            Err(err) => {
+                self.report("刪除報表失敗", &err.into());
+                false
+            }
+        }
+    }
+
+    /// Remote backup target and the outcome of its last upload, for the settings dialog.
+    pub fn load_remote_backup(&self) -> Option<(RemoteBackupConfig, Option<String>)> {
+        let query_service = self.services.read().query_service.clone();
+        let result = run_blocking(|| {
+            Ok::<_, anyhow::Error>((
+                load_remote_backup_config(&query_service)?,
+                load_remote_backup_status(&query_service)?,
+            ))
+        });
+        match result {
+            Ok(loaded) => Some(loaded),
+            Err(err) => {
+                self.report("讀取遠端備份設定失敗", &err);
+                None
+            }
+        }
+    }
+
+    pub fn save_remote_backup(&mut self, config: &RemoteBackupConfig) -> bool {
+        let query_service = self.services.read().query_service.clone();
+        match run_blocking(|| save_remote_backup_config(&query_service, config)) {
+            Ok(()) => {
+                self.set_status("已保存遠端備份設定");
+                true
+            }
+            Err(err) => {
+                self.report("保存遠端備份設定失敗", &err);
+                false
+            }
+        }
+    }
+
+    /// Uploads a finished local backup when a remote target is configured. Returns the
+    /// uploaded URL; failures are reported and leave the local backup in place.
+    pub fn upload_remote_backup(&mut self, backup_path: &Path) -> Option<String> {
+        let query_service = self.services.read().query_service.clone();
+        let config = match run_blocking(|| load_remote_backup_config(&query_service)) {
+            Ok(config) if config.is_configured() => config,
+            Ok(_) => return None,
+            Err(err) => {
+                self.report("讀取遠端備份設定失敗", &err);
+                return None;
+            }
+        };
+        match run_blocking(|| upload_and_record(&query_service, &config, backup_path)) {
+            Ok(url) => Some(url),
+            Err(err) => {
+                self.report("遠端備份失敗", &err);
+                None
+            }
+        }
+    }
+
+    /// Backs the database up to a temporary file and uploads it, without keeping a local copy.
+    pub fn back_up_to_remote(&mut self) {
+        let query_service = self.services.read().query_service.clone();
+        let backup_path = std::env::temp_dir().join(format!(
+            "bom-backup-{}.sqlite",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        self.state.busy.write().begin(BusyKind::Save);
+        match run_blocking(|| query_service.backup_database(&backup_path)) {
+            Ok(()) => {
+                if let Some(url) = self.upload_remote_backup(&backup_path) {
+                    self.set_status(format!("已上傳備份至 {url}"));
+                }
+            }
+            Err(err) => self.report("備份失敗", &err.into()),
+        }
+        let _ = std::fs::remove_file(&backup_path);
+        self.state.busy.write().end(BusyKind::Save);
+        notify_if_unfocused("BOM 備份", &(self.state.status)());
+    }
+
+    pub fn rename_dataset(&mut self, dataset_id: i64, name: &str) {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            self.set_status("資料集名稱不可空白");
+            return;
+        }
+        let query_service = self.services.read().query_service.clone();
+        self.state.busy.write().begin(BusyKind::Save);
+        match run_blocking(|| query_service.rename_dataset(DatasetId(dataset_id), name.clone())) {
+            Ok(()) => {
+                if let Ok(available) = self.list_datasets() {
+                    self.state.datasets.set(available);
+                }
+                self.set_status("已重新命名");
+            }
+            Err(err) => self.report("重新命名失敗", &err.into()),
+        }
+        self.state.busy.write().end(BusyKind::Save);
+    }
+
+    /// Writes the rows added, removed and changed from `before` to `after` to `path`.
+    pub fn export_dataset_diff(&mut self, before: i64, after: i64, path: &Path) {
+        let query_service = self.services.read().query_service.clone();
+        self.state.busy.write().begin(BusyKind::Query);
+        match run_blocking(|| export_dataset_diff(&query_service, before, after, path)) {
+            Ok(diff) => self.set_status(dataset_diff_summary(&diff)),
+            Err(err) => self.report("匯出差異失敗", &err),
+        }
+        self.state.busy.write().end(BusyKind::Query);
+    }
+
+    /// Stores the type of `dataset_id`; its tab buttons and editable columns follow it.
+    pub fn set_dataset_kind(&mut self, dataset_id: i64, kind: DatasetKind) {
+        let query_service = self.services.read().query_service.clone();
+        self.state.busy.write().begin(BusyKind::Save);
+        match run_blocking(|| query_service.set_dataset_kind(DatasetId(dataset_id), kind)) {
+            Ok(()) => {
+                if let Ok(available) = self.list_datasets() {
+                    self.state.datasets.set(available);
+                }
+                self.set_status(format!("已設為{}", kind.label()));
+            }
+            Err(err) => self.report("設定類型失敗", &err.into()),
+        }
+        self.state.busy.write().end(BusyKind::Save);
+    }
+
+    /// Moves `dataset_id` to the trash and the selection to a neighbouring dataset.
+    pub fn trash_dataset(&mut self, dataset_id: i64) {
+        let next_candidate = choose_next_dataset_after_delete(&(self.state.datasets)(), dataset_id);
+        let edit_service = self.services.read().edit_service.clone();
+        self.state.busy.write().begin(BusyKind::Save);
+        if let Err(err) = run_blocking(|| edit_service.soft_delete_dataset(DatasetId(dataset_id))) {
+            self.report("移到垃圾桶失敗", &err.into());
+        } else {
+            self.select_after_removal(next_candidate);
+            self.set_status("已移到垃圾桶");
+        }
+        self.state.busy.write().end(BusyKind::Save);
+    }
+
+    /// Takes `dataset_id` out of the trash. It is listed again; the selection stays.
+    pub fn restore_dataset(&mut self, dataset_id: i64) {
+        let edit_service = self.services.read().edit_service.clone();
+        self.state.busy.write().begin(BusyKind::Save);
+        match run_blocking(|| edit_service.restore_dataset(DatasetId(dataset_id))) {
+            Ok(()) => {
+                if let Ok(available) = self.list_datasets() {
+                    self.state.datasets.set(available);
+                }
+                self.set_status("已還原資料集");
+            }
+            Err(err) => self.report("還原資料集失敗", &err.into()),
+        }
+        self.state.busy.write().end(BusyKind::Save);
+    }
+
+    pub fn set_keep_forever(&mut self, dataset_id: i64, keep_forever: bool) {
+        let edit_service = self.services.read().edit_service.clone();
+        if let Err(err) =
+            run_blocking(|| edit_service.set_keep_forever(DatasetId(dataset_id), keep_forever))
+        {
+            self.report("設定永久保留失敗", &err.into());
+        }
+    }
+
+    /// Days datasets stay in the trash, or the default when the setting cannot be read.
+    pub fn trash_retention_days(&self) -> u32 {
+        let query_service = self.services.read().query_service.clone();
+        run_blocking(|| load_trash_retention_days(&query_service)).unwrap_or_else(|err| {
+            self.report("讀取垃圾桶設定失敗", &err);
+            DEFAULT_TRASH_RETENTION_DAYS
+        })
+    }
+
+    /// Datasets in the trash, newest first.
+    pub fn load_trash(&self) -> Vec<DatasetMeta> {
+        let query_service = self.services.read().query_service.clone();
+        match run_blocking(|| query_service.list_datasets(true)) {
+            Ok(datasets) => datasets
+                .into_iter()
+                .filter(|dataset| dataset.deleted_at.is_some())
+                .collect(),
+            Err(err) => {
+                self.report("讀取垃圾桶失敗", &err.into());
+                Vec::new()
+            }
+        }
+    }
+
+    /// Permanently deletes `dataset_id`, listed or in the trash. A listed dataset hands the
+    /// selection to a neighbouring one.
+    pub fn delete_dataset(&mut self, dataset_id: i64) {
+        let datasets = (self.state.datasets)();
+        let listed = datasets.iter().any(|dataset| dataset.id.0 == dataset_id);
+        let next_candidate = choose_next_dataset_after_delete(&datasets, dataset_id);
+        let edit_service = self.services.read().edit_service.clone();
+        self.state.busy.write().begin(BusyKind::Save);
+        if let Err(err) = run_blocking(|| edit_service.hard_delete_dataset(DatasetId(dataset_id))) {
+            self.report("刪除資料集失敗", &err.into());
+        } else {
+            if listed {
+                self.select_after_removal(next_candidate);
+            }
+            self.set_status("已永久刪除資料集");
+        }
+        self.state.busy.write().end(BusyKind::Save);
+    }
+
+    /// Reloads the dataset list after the selected dataset left it and opens
+    /// `next_candidate`, or the default dataset of the group when it is gone as well.
+    fn select_after_removal(&mut self, next_candidate: Option<i64>) {
+        if let Ok(available) = self.list_datasets() {
+            let groups = build_dataset_groups(&available);
+            let next_dataset = next_candidate
+                .filter(|id| available.iter().any(|d| d.id.0 == *id))
+                .or_else(|| {
+                    (self.state.selected_group_key)()
+                        .and_then(|key| groups.iter().find(|g| g.key == key))
+                        .or_else(|| groups.first())
+                        .and_then(|g| choose_default_dataset_id(&g.datasets))
+                });
+            self.select_in_groups(&available, next_dataset);
+            self.state.datasets.set(available);
+            self.state.page.set(0);
+            self.load_page(next_dataset, 0, &QueryOptions::default(), "載入資料集失敗");
+        }
+    }
+
+    /// Resumes the action that was waiting on the save prompt.
+    pub fn resume_pending_action(&mut self) {
+        let Some(action) = (self.state.pending_action)() else {
+            return;
+        };
+        self.state.pending_action.set(None);
+        self.run_action(action);
+    }
+
+    fn run_action(&mut self, action: PendingAction) {
+        match action {
+            PendingAction::Import(file_path) => self.import_file(&file_path),
+            PendingAction::ImportFolder(folder) => self.import_folder(&folder),
+            PendingAction::ImportWithProfile(file_path, profile) => {
+                self.import_with_profile(&file_path, profile)
+            }
+            PendingAction::DatasetChange {
+                next_group,
+                next_dataset,
+            } => {
+                self.discard_edits();
+                self.state.edit_mode.set(false);
+                self.state.context_menu.set(None);
+                self.state.context_cell.set(None);
+                self.change_dataset(next_group, next_dataset);
+            }
+            PendingAction::TabSwitch { dataset_id } => {
+                self.discard_edits();
+                self.state.context_menu.set(None);
+                self.state.context_cell.set(None);
+                self.state.edit_mode.set(true);
+                self.switch_sheet(dataset_id);
+            }
+            PendingAction::DividendRollover { dataset_id } => {
+                self.roll_over_dividend_year(dataset_id)
+            }
+            PendingAction::CloseWindow => self.finish_close(),
+            PendingAction::Quit => {
+                let workspaces = self.services.read().workspaces.clone();
+                quit_app(workspaces.windows());
+            }
+        }
+    }
+
+    fn apply_staged_edits(&mut self, dataset_id: i64) -> bool {
+        let edit_service = self.services.read().edit_service.clone();
+        let edits = StagedEdits {
+            staged_cells: (self.state.staged_cells)(),
+            deleted_rows: (self.state.deleted_rows)(),
+            added_rows: (self.state.added_rows)(),
+        };
+        match edit_service.apply_edits(DatasetId(dataset_id), edits) {
+            Ok(()) => true,
+            Err(err) => {
+                self.report("覆蓋失敗", &err.into());
+                false
+            }
+        }
+    }
+
+    /// 覆蓋: writes the staged edits into the current dataset, then carries on with the
+    /// pending action.
+    pub fn overwrite_and_continue(&mut self) {
+        let Some(dataset_id) = (self.state.selected_dataset_id)() else {
+            self.cancel_save_prompt();
+            return;
+        };
+        if !self.apply_staged_edits(dataset_id) {
+            return;
+        }
+        self.discard_edits();
+        let options = self.query_options();
+        self.load_page(Some(dataset_id), 0, &options, "覆蓋後重新載入失敗");
+        self.state.show_save_prompt.set(false);
+        self.resume_pending_action();
+    }
+
+    /// 另存: keeps the unedited rows as a new dataset called `name`, writes the staged
+    /// edits into the current one, then carries on with the pending action.
+    pub fn save_as_and_continue(&mut self, name: &str) {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            self.set_status("資料集名稱不可空白");
+            return;
+        }
+        let Some(dataset_id) = (self.state.selected_dataset_id)() else {
+            self.state.show_save_as_prompt.set(false);
+            self.state.pending_action.set(None);
+            return;
+        };
+        let datasets = (self.state.datasets)();
+        let Some(current) = datasets.iter().find(|d| d.id.0 == dataset_id) else {
+            self.set_status("找不到目前資料集");
+            return;
+        };
+        if current.name == name {
+            self.set_status("資料集名稱必須不同");
+            return;
+        }
+        let edit_service = self.services.read().edit_service.clone();
+        if let Some(existing) = datasets.iter().find(|d| d.name == name) {
+            let overwrite = MessageDialog::new()
+                .set_level(MessageLevel::Warning)
+                .set_title("名稱已存在")
+                .set_description("已有相同名稱，是否覆蓋？")
+                .set_buttons(MessageButtons::YesNo)
+                .show();
+            if overwrite != MessageDialogResult::Yes {
+                return;
+            }
+            if let Err(err) = edit_service.purge_dataset(existing.id) {
+                self.report("覆蓋失敗", &err.into());
+                return;
+            }
+        }
+
+        // Copied as stored, so a search or computed columns on screen do not leak into it.
+        let backup = edit_service.clone_dataset(
+            DatasetId(dataset_id),
+            NewDatasetMeta {
+                name: name.clone(),
+                source_path: current.save_as_source_path(&name),
+            },
+        );
+        if let Err(err) = backup {
+            self.report("另存失敗", &err.into());
+            return;
+        }
+        if !self.apply_staged_edits(dataset_id) {
+            return;
+        }
+        match self.list_datasets() {
+            Ok(available) => self.state.datasets.set(available),
+            Err(err) => self.report("更新資料集清單失敗", &err.into()),
+        }
+        self.discard_edits();
+        self.state.show_save_as_prompt.set(false);
+        self.resume_pending_action();
+    }
+
+    pub fn cancel_save_prompt(&mut self) {
+        self.state.show_save_prompt.set(false);
+        self.state.show_save_as_prompt.set(false);
+        self.state.pending_action.set(None);
+    }
+}
+
+/// Runs the matching importer and returns the dataset to select, the count shown in the
+/// status bar (sheets for workbooks, rows otherwise) and whether the file was a workbook.
+/// A CSV is read through `profile` when one is given.
+pub fn import_and_summarize(
+    import_service: &ImportService,
+    file_path: &Path,
+    profile: Option<ImportProfile>,
+    duplicate_codes: DuplicateCodePolicy,
+) -> anyhow::Result<(Option<i64>, i64, bool)> {
+    let is_workbook = file_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("xlsx"));
+    let items = match profile {
+        Some(profile) => import_service.import_with_profile(file_path, profile)?,
+        None if is_workbook => import_service.import_workbook(file_path, duplicate_codes)?,
+        None => import_service.import(file_path)?,
+    };
+    let first_id = items.first().map(|item| item.dataset_id);
+    if is_workbook {
+        Ok((first_id, items.len() as i64, true))
+    } else {
+        Ok((
+            first_id,
+            items.iter().map(|item| item.row_count).sum(),
+            false,
+        ))
+    }
+}
+
+/// Asks before the app ends with unsaved edits in the datasets named in `unsaved`; `true`
+/// when there are none or the user agrees to drop them.
+fn confirm_discarding(unsaved: &[String]) -> bool {
+    if unsaved.is_empty() {
+        return true;
+    }
+    MessageDialog::new()
+        .set_level(MessageLevel::Warning)
+        .set_title("尚有未儲存的修改")
+        .set_description(format!(
+            "以下資料集在其他窗格或視窗中的修改尚未儲存，結束後將會捨棄：\n{}\n\n仍要結束嗎？",
+            unsaved.join("\n")
+        ))
+        .set_buttons(MessageButtons::YesNo)
+        .show()
+        == MessageDialogResult::Yes
+}
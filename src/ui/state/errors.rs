@@ -0,0 +1,123 @@
+use dioxus::prelude::{Signal, WritableExt};
+
+use crate::usecase::ports::importer::ImportError;
+use crate::usecase::ports::repo::RepoError;
+
+/// An error as shown in the error dialog: what the user was doing plus every cause.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorReport {
+    pub context: String,
+    pub chain: Vec<String>,
+    pub occurred_at: String,
+}
+
+impl ErrorReport {
+    pub fn new(context: &str, chain: Vec<String>) -> Self {
+        Self {
+            context: context.to_string(),
+            chain,
+            occurred_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        }
+    }
+
+    /// First line of the chain, as used in the one-line status.
+    pub fn summary(&self) -> String {
+        match self.chain.first() {
+            Some(message) => format!("{}：{message}", self.context),
+            None => self.context.clone(),
+        }
+    }
+
+    /// Plain-text form used for the clipboard and the log file.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("[{}] {}", self.occurred_at, self.context);
+        for (idx, message) in self.chain.iter().enumerate() {
+            if idx == 0 {
+                text.push_str(&format!("\n  錯誤：{message}"));
+            } else {
+                text.push_str(&format!("\n  原因：{message}"));
+            }
+        }
+        text
+    }
+}
+
+/// Errors that can list their causes for an [`ErrorReport`].
+pub trait ErrorChain {
+    fn chain_messages(&self) -> Vec<String>;
+}
+
+impl ErrorChain for anyhow::Error {
+    fn chain_messages(&self) -> Vec<String> {
+        let mut messages = self
+            .chain()
+            .map(|cause| cause.to_string())
+            .collect::<Vec<_>>();
+        if let Some(message) = self.chain().find_map(user_message) {
+            messages.insert(0, message);
+        }
+        messages
+    }
+}
+
+impl ErrorChain for RepoError {
+    fn chain_messages(&self) -> Vec<String> {
+        let mut messages = vec![self.to_string()];
+        if let Some(message) = user_message(self) {
+            messages.insert(0, message);
+        }
+        messages
+    }
+}
+
+const READ_ONLY_MESSAGE: &str = "唯讀模式下無法變更資料";
+
+/// The one place typed errors become user-facing text. `None` means the technical message
+/// is already the best we can say.
+pub fn user_message(err: &(dyn std::error::Error + 'static)) -> Option<String> {
+    if let Some(err) = err.downcast_ref::<RepoError>() {
+        return match err {
+            RepoError::NotFound(_) => Some("找不到資料，可能已被刪除".to_string()),
+            RepoError::FileNotFound(_) => Some("找不到資料庫檔案".to_string()),
+            RepoError::SchemaMismatch(_) => {
+                Some("資料庫格式不相容，請先備份後再更新程式".to_string())
+            }
+            RepoError::Busy(_) => Some("資料庫正被其他程式使用，請稍後再試".to_string()),
+            RepoError::ReadOnly => Some(READ_ONLY_MESSAGE.to_string()),
+            RepoError::Message(_) => None,
+        };
+    }
+    if let Some(err) = err.downcast_ref::<ImportError>() {
+        return match err {
+            ImportError::Unsupported(path) => Some(format!(
+                "不支援的檔案格式：{}",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            )),
+            ImportError::FileNotFound(path) => Some(format!("找不到檔案：{}", path.display())),
+            ImportError::MissingSheet(sheet) => Some(format!("活頁簿缺少工作表：{sheet}")),
+            ImportError::ReadOnly => Some(READ_ONLY_MESSAGE.to_string()),
+            ImportError::Other(_) => None,
+        };
+    }
+    None
+}
+
+impl ErrorChain for String {
+    fn chain_messages(&self) -> Vec<String> {
+        vec![self.clone()]
+    }
+}
+
+/// Puts the error in the status line, keeps the full report for the error dialog and
+/// writes it to the log.
+pub fn report_error<E: ErrorChain + ?Sized>(
+    mut status: Signal<String>,
+    mut last_error: Signal<Option<ErrorReport>>,
+    context: &str,
+    err: &E,
+) {
+    let report = ErrorReport::new(context, err.chain_messages());
+    status.set(report.summary());
+    tracing::error!(target: "bom::ui", "{}", report.to_text());
+    last_error.set(Some(report));
+}
@@ -1 +1,8 @@
+pub mod app_lock;
 pub mod app_state;
+pub mod controller;
+pub mod errors;
+pub mod open_workspaces;
+pub mod services;
+pub mod session;
+pub mod sync_folder;
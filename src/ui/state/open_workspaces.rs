@@ -0,0 +1,103 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use dioxus::desktop::tao::window::WindowId;
+
+/// Dataset workspaces open across split panes and windows, and which of them hold unsaved
+/// edits, so quitting or closing a window accounts for every pane it takes down and not
+/// only the one that got the command. `W` identifies a window.
+#[derive(Clone)]
+pub struct OpenWorkspaces<W = WindowId> {
+    entries: Arc<Mutex<Vec<OpenWorkspace<W>>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+struct OpenWorkspace<W> {
+    id: u64,
+    window: W,
+    /// Name of the dataset with unsaved edits, if any.
+    unsaved: Option<String>,
+}
+
+impl<W> Default for OpenWorkspaces<W> {
+    fn default() -> Self {
+        Self {
+            entries: Arc::new(Mutex::new(Vec::new())),
+            next_id: Arc::new(AtomicU64::new(0)),
+        }
+    }
+}
+
+impl<W: Copy + PartialEq> OpenWorkspaces<W> {
+    /// Adds a workspace shown in `window`; it is taken off again when the returned entry
+    /// is dropped.
+    pub fn register(&self, window: W) -> WorkspaceEntry<W> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.lock().push(OpenWorkspace {
+            id,
+            window,
+            unsaved: None,
+        });
+        WorkspaceEntry {
+            workspaces: self.clone(),
+            id,
+        }
+    }
+
+    /// Every window showing a workspace, in the order they were opened.
+    pub fn windows(&self) -> Vec<W> {
+        let mut windows = Vec::new();
+        for entry in self.lock().iter() {
+            if !windows.contains(&entry.window) {
+                windows.push(entry.window);
+            }
+        }
+        windows
+    }
+
+    /// Datasets with unsaved edits in workspaces other than `except`, only those shown in
+    /// `window` when one is given.
+    pub fn unsaved_elsewhere(&self, except: u64, window: Option<W>) -> Vec<String> {
+        self.lock()
+            .iter()
+            .filter(|entry| entry.id != except && window.is_none_or(|w| entry.window == w))
+            .filter_map(|entry| entry.unsaved.clone())
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<OpenWorkspace<W>>> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// One workspace's place in [`OpenWorkspaces`].
+pub struct WorkspaceEntry<W: Copy + PartialEq = WindowId> {
+    workspaces: OpenWorkspaces<W>,
+    id: u64,
+}
+
+impl<W: Copy + PartialEq> WorkspaceEntry<W> {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Records whether the workspace holds unsaved edits, and in which dataset.
+    pub fn set_unsaved(&self, dataset: Option<String>) {
+        if let Some(entry) = self
+            .workspaces
+            .lock()
+            .iter_mut()
+            .find(|entry| entry.id == self.id)
+        {
+            entry.unsaved = dataset;
+        }
+    }
+}
+
+impl<W: Copy + PartialEq> Drop for WorkspaceEntry<W> {
+    fn drop(&mut self) {
+        self.workspaces.lock().retain(|entry| entry.id != self.id);
+    }
+}
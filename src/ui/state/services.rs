@@ -0,0 +1,116 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use bom_core::numeric::NumberLocale;
+
+use crate::domain::events::{DomainEvent, EventBus};
+use crate::infra::sqlite::repo::SqliteRepo;
+use crate::ui::state::open_workspaces::OpenWorkspaces;
+use crate::usecase::services::cash_sweep_service::CashSweepService;
+use crate::usecase::services::edit_service::EditService;
+use crate::usecase::services::export_service::ExportService;
+use crate::usecase::services::import_service::ImportService;
+use crate::usecase::services::number_locale::NumberLocaleSetting;
+use crate::usecase::services::query_service::QueryService;
+use crate::usecase::services::read_only::ReadOnlyMode;
+use crate::usecase::services::recalc_service::RecalcService;
+use crate::{parse_recent_imports, push_recent_import, NUMBER_LOCALE_KEY, RECENT_IMPORTS_KEY};
+
+/// Repo and services shared by every window of the app.
+#[allow(dead_code)]
+#[derive(Clone)]
+pub struct AppServices {
+    pub db_path: Arc<PathBuf>,
+    pub repo: Arc<SqliteRepo>,
+    pub query_service: Arc<QueryService>,
+    pub edit_service: Arc<EditService>,
+    pub export_service: Arc<ExportService>,
+    pub import_service: Arc<ImportService>,
+    /// Data-change events published by the edit and import services.
+    pub events: EventBus,
+    /// While enabled, the services refuse edits, deletes, renames and imports.
+    pub read_only: ReadOnlyMode,
+    /// How numbers are written in imported CSV files and typed into cells.
+    pub number_locale: NumberLocaleSetting,
+    /// Workspaces open in every window, with their unsaved edits.
+    pub workspaces: OpenWorkspaces,
+}
+
+impl AppServices {
+    pub fn new(db_path: PathBuf) -> Self {
+        let repo = Arc::new(SqliteRepo {
+            db_path: db_path.clone(),
+        });
+        let events = EventBus::new();
+        let read_only = ReadOnlyMode::default();
+        let number_locale = NumberLocaleSetting::default();
+        let query_service =
+            Arc::new(QueryService::new(repo.clone()).with_read_only(read_only.clone()));
+        record_recent_imports(&events, query_service.clone());
+        Arc::new(RecalcService::new(repo.clone(), events.clone())).subscribe();
+        Arc::new(CashSweepService::new(repo.clone(), events.clone())).subscribe();
+        Self {
+            query_service,
+            edit_service: Arc::new(
+                EditService::with_events(repo.clone(), events.clone())
+                    .with_read_only(read_only.clone()),
+            ),
+            export_service: Arc::new(ExportService::new(repo.clone())),
+            import_service: Arc::new(
+                ImportService::new(db_path.clone())
+                    .with_events(events.clone())
+                    .with_read_only(read_only.clone())
+                    .with_number_locale(number_locale.clone()),
+            ),
+            events,
+            read_only,
+            number_locale,
+            workspaces: OpenWorkspaces::default(),
+            db_path: Arc::new(db_path),
+            repo,
+        }
+    }
+
+    /// Applies the number locale saved in settings; unknown or missing values mean the
+    /// default `1,234.56`.
+    pub fn reload_number_locale(&self) {
+        match self.query_service.load_setting(NUMBER_LOCALE_KEY) {
+            Ok(value) => self.number_locale.set(
+                value
+                    .as_deref()
+                    .and_then(NumberLocale::parse)
+                    .unwrap_or_default(),
+            ),
+            Err(err) => tracing::warn!(error = %err, "讀取數字格式地區失敗"),
+        }
+    }
+
+    pub fn save_number_locale(&self, locale: NumberLocale) {
+        self.number_locale.set(locale);
+        if let Err(err) = self
+            .query_service
+            .save_setting(NUMBER_LOCALE_KEY, locale.as_str())
+        {
+            tracing::warn!(error = %err, "保存數字格式地區失敗");
+        }
+    }
+}
+
+/// Keeps 最近匯入 in settings up to date for every import, whichever window started it.
+fn record_recent_imports(events: &EventBus, query_service: Arc<QueryService>) {
+    events.subscribe(move |event| {
+        let DomainEvent::DatasetImported { source_path, .. } = event else {
+            return;
+        };
+        let result = query_service
+            .load_setting(RECENT_IMPORTS_KEY)
+            .and_then(|value| {
+                let recent = parse_recent_imports(&value.unwrap_or_default());
+                let next = push_recent_import(&recent, &source_path.to_string_lossy());
+                query_service.save_setting(RECENT_IMPORTS_KEY, &next.join("\n"))
+            });
+        if let Err(err) = result {
+            tracing::warn!(error = %err, "保存最近匯入失敗");
+        }
+    });
+}
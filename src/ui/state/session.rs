@@ -0,0 +1,160 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::{json, Map, Value};
+
+use crate::usecase::ports::repo::RepoError;
+use crate::usecase::services::query_service::QueryService;
+use bom_core::QueryOptions;
+
+/// Settings key of the main window's [`UiSession`].
+pub const SESSION_KEY: &str = "ui_session";
+
+/// What the main window was showing, saved whenever it changes and reopened at the next
+/// launch. The dataset group and sheet tab follow from `dataset_id`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct UiSession {
+    pub dataset_id: Option<i64>,
+    pub options: QueryOptions,
+    pub page: i64,
+    /// Scroll offset of the workspace, in CSS pixels.
+    pub scroll_top: f64,
+    pub chart_tab: bool,
+    /// Row shown in the row detail panel.
+    pub detail_row: Option<usize>,
+    /// Dataset in the second pane when the split view is open.
+    pub split_dataset_id: Option<Option<i64>>,
+}
+
+impl UiSession {
+    pub fn to_setting(&self) -> String {
+        let value_filters: Map<String, Value> = self
+            .options
+            .value_filters
+            .iter()
+            .map(|(col, values)| (col.to_string(), json!(values)))
+            .collect();
+        let column_conditions: Map<String, Value> = self
+            .options
+            .column_conditions
+            .iter()
+            .map(|(col, expression)| (col.to_string(), json!(expression)))
+            .collect();
+        json!({
+            "dataset_id": self.dataset_id,
+            "global_search": self.options.global_search,
+            "column_search_col": self.options.column_search_col,
+            "column_search_text": self.options.column_search_text,
+            "value_filters": value_filters,
+            "column_conditions": column_conditions,
+            "sort_col": self.options.sort_col,
+            "sort_desc": self.options.sort_desc,
+            "page": self.page,
+            "scroll_top": self.scroll_top,
+            "chart_tab": self.chart_tab,
+            "detail_row": self.detail_row,
+            "split": self.split_dataset_id.map(|dataset_id| json!({ "dataset_id": dataset_id })),
+        })
+        .to_string()
+    }
+
+    /// Reads a saved session. Missing or malformed fields fall back to their defaults, so
+    /// an older or damaged setting still opens the app.
+    pub fn parse(value: &str) -> Self {
+        let Ok(Value::Object(fields)) = serde_json::from_str::<Value>(value) else {
+            return Self::default();
+        };
+        let text = |key: &str| {
+            fields
+                .get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+        let value_filters: BTreeMap<i64, BTreeSet<String>> = fields
+            .get("value_filters")
+            .and_then(Value::as_object)
+            .map(|filters| {
+                filters
+                    .iter()
+                    .filter_map(|(col, values)| {
+                        let values = values
+                            .as_array()?
+                            .iter()
+                            .filter_map(|value| value.as_str().map(str::to_string))
+                            .collect();
+                        Some((col.parse().ok()?, values))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let column_conditions: BTreeMap<i64, String> = fields
+            .get("column_conditions")
+            .and_then(Value::as_object)
+            .map(|conditions| {
+                conditions
+                    .iter()
+                    .filter_map(|(col, expression)| {
+                        Some((col.parse().ok()?, expression.as_str()?.to_string()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            dataset_id: fields.get("dataset_id").and_then(Value::as_i64),
+            options: QueryOptions {
+                global_search: text("global_search"),
+                column_search_col: fields.get("column_search_col").and_then(Value::as_i64),
+                column_search_text: text("column_search_text"),
+                value_filters,
+                column_conditions,
+                sort_col: fields.get("sort_col").and_then(Value::as_i64),
+                sort_desc: fields
+                    .get("sort_desc")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false),
+            },
+            page: fields
+                .get("page")
+                .and_then(Value::as_i64)
+                .unwrap_or(0)
+                .max(0),
+            scroll_top: fields
+                .get("scroll_top")
+                .and_then(Value::as_f64)
+                .unwrap_or(0.0)
+                .max(0.0),
+            chart_tab: fields
+                .get("chart_tab")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            detail_row: fields
+                .get("detail_row")
+                .and_then(Value::as_u64)
+                .map(|row| row as usize),
+            split_dataset_id: fields
+                .get("split")
+                .and_then(Value::as_object)
+                .map(|split| split.get("dataset_id").and_then(Value::as_i64)),
+        }
+    }
+}
+
+/// The saved session, or the default one when none was saved or it cannot be read.
+pub fn load_session(service: &QueryService) -> UiSession {
+    match service.load_setting(SESSION_KEY) {
+        Ok(value) => value.as_deref().map(UiSession::parse).unwrap_or_default(),
+        Err(err) => {
+            tracing::warn!(error = %err, "載入上次的畫面狀態失敗");
+            UiSession::default()
+        }
+    }
+}
+
+/// Saving is best effort: a failure only costs the next launch its restored view. In
+/// read-only mode the view saved last is kept.
+pub fn save_session(service: &QueryService, session: &UiSession) {
+    match service.save_setting(SESSION_KEY, &session.to_setting()) {
+        Ok(()) | Err(RepoError::ReadOnly) => {}
+        Err(err) => tracing::warn!(error = %err, "保存畫面狀態失敗"),
+    }
+}
@@ -0,0 +1,72 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Weak};
+
+use crate::infra::sqlite::sync_folder::{
+    acquire_writer_lock, detect_sync_folder, find_conflicted_copies, machine_name,
+    use_sync_safe_journal, SyncProvider, WriterLock, WriterLockOutcome, WRITER_LOCK_HEARTBEAT,
+};
+use crate::ui::state::services::AppServices;
+
+/// What the startup check found for a database inside a cloud sync folder.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncFolderStatus {
+    pub provider: SyncProvider,
+    /// Machine holding the writer lock when it is not this one; the app starts read-only.
+    pub held_by: Option<String>,
+    pub conflicted_copies: Vec<PathBuf>,
+}
+
+impl SyncFolderStatus {
+    /// Whether there is anything to warn about at startup.
+    pub fn needs_attention(&self) -> bool {
+        self.held_by.is_some() || !self.conflicted_copies.is_empty()
+    }
+}
+
+/// Switches a synced database to single-writer operation: rollback journal, a writer lock
+/// kept fresh by a heartbeat thread, and read-only mode when another machine holds the
+/// lock. Returns `None` when the database is not in a sync folder. The lock is released
+/// when the returned handle is dropped.
+pub fn enter_sync_folder_mode(
+    services: &AppServices,
+) -> Option<(SyncFolderStatus, Option<Arc<WriterLock>>)> {
+    let db_path = services.db_path.as_path();
+    let provider = detect_sync_folder(db_path)?;
+    if let Err(err) = use_sync_safe_journal(db_path) {
+        tracing::warn!(error = %err, "切換同步資料夾日誌模式失敗");
+    }
+
+    let (held_by, lock) = match acquire_writer_lock(db_path, &machine_name()) {
+        Ok(WriterLockOutcome::Acquired(lock)) => (None, Some(Arc::new(lock))),
+        Ok(WriterLockOutcome::HeldBy(holder)) => {
+            services.read_only.set(true);
+            (Some(holder), None)
+        }
+        Err(err) => {
+            tracing::warn!(error = %err, "建立寫入鎖定檔失敗");
+            (None, None)
+        }
+    };
+    if let Some(lock) = &lock {
+        keep_lock_fresh(Arc::downgrade(lock));
+    }
+
+    let status = SyncFolderStatus {
+        provider,
+        held_by,
+        conflicted_copies: find_conflicted_copies(db_path),
+    };
+    Some((status, lock))
+}
+
+fn keep_lock_fresh(lock: Weak<WriterLock>) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(WRITER_LOCK_HEARTBEAT);
+        let Some(lock) = lock.upgrade() else {
+            break;
+        };
+        if let Err(err) = lock.refresh() {
+            tracing::warn!(error = %err, "更新寫入鎖定檔失敗");
+        }
+    });
+}
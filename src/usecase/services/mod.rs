@@ -1,3 +0,0 @@
-pub mod edit_service;
-pub mod import_service;
-pub mod query_service;